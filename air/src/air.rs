@@ -9,6 +9,12 @@ pub trait BaseAir<F>: Sync {
     /// The number of private columns (a.k.a. registers) in this AIR.
     fn width(&self) -> usize;
 
+    /// The number of consecutive rows, starting with the current one, which constraints can access
+    /// through `AirBuilder::main` and the other trace matrices.
+    fn window_size(&self) -> usize {
+        2
+    }
+
     /// The number of preprocessed columns in this AIR.
     fn preprocessed_width(&self) -> usize {
         0
@@ -22,6 +28,31 @@ pub trait BaseAir<F>: Sync {
     fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
         None
     }
+
+    /// The number of extension field challenges sampled after the main trace is committed, which
+    /// are available to the permutation trace.
+    fn num_permutation_challenges(&self) -> usize {
+        0
+    }
+
+    /// The number of extension field columns in the permutation trace.
+    fn permutation_width(&self) -> usize {
+        0
+    }
+
+    /// Generate the permutation trace, given the main trace and the challenges sampled after it was
+    /// committed. AIRs with a nonzero `permutation_width` must override this.
+    fn permutation_trace<EF>(
+        &self,
+        _main: &RowMajorMatrix<F>,
+        _challenges: &[EF],
+    ) -> Option<RowMajorMatrix<EF>>
+    where
+        F: Field,
+        EF: ExtensionField<F>,
+    {
+        None
+    }
 }
 
 /// An AIR that works with a particular `AirBuilder`.
@@ -62,6 +93,8 @@ pub trait AirBuilder: Sized {
     fn is_transition(&self) -> Self::Expr {
         self.is_transition_window(2)
     }
+    /// A selector which is nonzero on all rows except the last `size - 1`, i.e. on the rows where a
+    /// window of `size` rows fits in the trace. `size` can be at most the AIR's `window_size`.
     fn is_transition_window(&self, size: usize) -> Self::Expr;
 
     /// Returns a sub-builder whose constraints are enforced only when `condition` is nonzero.
@@ -156,11 +189,13 @@ pub trait ExtensionBuilder: AirBuilder {
 pub trait PermutationAirBuilder: ExtensionBuilder {
     type MP: MatrixRowSlices<Self::VarEF>;
 
+    /// A challenge sampled after the main trace was committed. This is kept separate from `VarEF`
+    /// so that symbolic builders can tell challenges, which are constant, apart from trace cells.
+    type RandomVar: Into<Self::ExprEF> + Copy;
+
     fn permutation(&self) -> Self::MP;
 
-    // TODO: The return type should be some kind of variable to support symbolic evaluation,
-    // but maybe separate from `VarEF` since that might be a `PackedField`?
-    fn permutation_randomness(&self) -> &[Self::EF];
+    fn permutation_randomness(&self) -> &[Self::RandomVar];
 }
 
 pub struct FilteredAirBuilder<'a, AB: AirBuilder> {
@@ -211,12 +246,13 @@ impl<AB: ExtensionBuilder> ExtensionBuilder for FilteredAirBuilder<'_, AB> {
 
 impl<AB: PermutationAirBuilder> PermutationAirBuilder for FilteredAirBuilder<'_, AB> {
     type MP = AB::MP;
+    type RandomVar = AB::RandomVar;
 
     fn permutation(&self) -> Self::MP {
         self.inner.permutation()
     }
 
-    fn permutation_randomness(&self) -> &[Self::EF] {
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
         self.inner.permutation_randomness()
     }
 }
@@ -0,0 +1,30 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use p3_field::Field;
+
+use crate::{BaseAir, VirtualPairCol};
+
+/// A message sent or received by an AIR, for use in cross-table lookups.
+///
+/// On each row, the message `fields` is sent (or received) `count` times. A multi-table prover
+/// checks that, over all tables, every message is sent as many times as it is received.
+#[derive(Debug, Clone)]
+pub struct Interaction<F: Field> {
+    pub fields: Vec<VirtualPairCol<F>>,
+    pub count: VirtualPairCol<F>,
+    /// Distinguishes unrelated kinds of interactions, e.g. memory accesses and hash calls, so that
+    /// messages with different indices never balance each other out.
+    pub argument_index: usize,
+}
+
+/// An AIR which interacts with other AIRs by sending and receiving messages.
+pub trait InteractionAir<F: Field>: BaseAir<F> {
+    fn sends(&self) -> Vec<Interaction<F>> {
+        vec![]
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        vec![]
+    }
+}
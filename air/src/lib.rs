@@ -5,9 +5,11 @@
 extern crate alloc;
 
 mod air;
-mod two_row_matrix;
+mod interaction;
 mod virtual_column;
+mod window_matrix;
 
 pub use air::*;
-pub use two_row_matrix::*;
+pub use interaction::*;
 pub use virtual_column::*;
+pub use window_matrix::*;
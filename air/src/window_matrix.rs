@@ -0,0 +1,51 @@
+use alloc::vec::Vec;
+use core::iter::Cloned;
+use core::slice;
+
+use p3_matrix::{Matrix, MatrixRowSlices, MatrixRows};
+
+/// A view of the window of consecutive rows which an AIR's constraints can access at a given row,
+/// starting with that row.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WindowMatrixView<'a, T> {
+    pub rows: &'a [Vec<T>],
+}
+
+impl<'a, T> WindowMatrixView<'a, T> {
+    pub fn new(rows: &'a [Vec<T>]) -> Self {
+        Self { rows }
+    }
+}
+
+impl<T> Matrix<T> for WindowMatrixView<'_, T> {
+    fn width(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.len())
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+impl<T: Clone> MatrixRows<T> for WindowMatrixView<'_, T> {
+    type Row<'a>
+        = Cloned<slice::Iter<'a, T>>
+    where
+        Self: 'a,
+        T: 'a;
+
+    fn row(&self, r: usize) -> Self::Row<'_> {
+        self.row_slice(r).iter().cloned()
+    }
+}
+
+impl<T: Clone> MatrixRowSlices<T> for WindowMatrixView<'_, T> {
+    fn row_slice(&self, r: usize) -> &[T] {
+        assert!(
+            r < self.rows.len(),
+            "row {r} is outside of the window of {} rows",
+            self.rows.len()
+        );
+        &self.rows[r]
+    }
+}
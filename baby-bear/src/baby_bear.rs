@@ -8,6 +8,7 @@ use p3_field::{
 };
 use rand::distributions::{Distribution, Standard};
 use rand::Rng;
+use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize};
 
 /// The Baby Bear prime
@@ -98,6 +99,9 @@ impl Serialize for BabyBear {
 impl<'de> Deserialize<'de> for BabyBear {
     fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
         let val = u32::deserialize(d)?;
+        if val >= P {
+            return Err(D::Error::custom("non-canonical BabyBear element"));
+        }
         Ok(BabyBear::from_canonical_u32(val))
     }
 }
@@ -2,7 +2,7 @@ use alloc::vec;
 use alloc::vec::Vec;
 
 use p3_field::{ExtensionField, Field, PrimeField64};
-use p3_symmetric::CryptographicPermutation;
+use p3_symmetric::{CryptographicPermutation, MerkleCap};
 
 use crate::{CanObserve, CanSample, CanSampleBits, FieldChallenger};
 
@@ -102,6 +102,19 @@ where
     }
 }
 
+impl<F, P, const N: usize, const WIDTH: usize> CanObserve<MerkleCap<F, F, N>>
+    for DuplexChallenger<F, P, WIDTH>
+where
+    F: Copy,
+    P: CryptographicPermutation<[F; WIDTH]>,
+{
+    fn observe(&mut self, cap: MerkleCap<F, F, N>) {
+        for &digest in cap.digests() {
+            self.observe(digest);
+        }
+    }
+}
+
 impl<F, EF, P, const WIDTH: usize> CanSample<EF> for DuplexChallenger<F, P, WIDTH>
 where
     F: Field,
@@ -1,16 +1,29 @@
-use p3_field::{Field, PrimeField64};
+use p3_field::{AbstractField, PrimeField32, PrimeField64};
 use p3_maybe_rayon::prelude::*;
-use p3_symmetric::CryptographicPermutation;
+use p3_symmetric::{CryptographicHasher, CryptographicPermutation};
 use tracing::instrument;
 
-use crate::{CanObserve, CanSampleBits, DuplexChallenger};
+use crate::{
+    CanObserve, CanSampleBits, DuplexChallenger, HashChallenger, SerializingChallenger32,
+    SerializingChallenger64,
+};
 
 pub trait GrindingChallenger:
     CanObserve<Self::Witness> + CanSampleBits<usize> + Sync + Clone
 {
-    type Witness: Field;
+    type Witness: PrimeField64;
 
-    fn grind(&mut self, bits: usize) -> Self::Witness;
+    /// Search for a witness which makes the next `bits` sampled bits zero, and observe it.
+    #[instrument(name = "grind for proof-of-work witness", skip_all)]
+    fn grind(&mut self, bits: usize) -> Self::Witness {
+        let witness = (0..Self::Witness::ORDER_U64)
+            .into_par_iter()
+            .map(Self::Witness::from_canonical_u64)
+            .find_any(|witness| self.clone().check_witness(bits, *witness))
+            .expect("failed to find witness");
+        assert!(self.check_witness(bits, witness));
+        witness
+    }
 
     #[must_use]
     fn check_witness(&mut self, bits: usize, witness: Self::Witness) -> bool {
@@ -25,15 +38,28 @@ where
     P: CryptographicPermutation<[F; WIDTH]>,
 {
     type Witness = F;
+}
 
-    #[instrument(name = "grind for proof-of-work witness", skip_all)]
-    fn grind(&mut self, bits: usize) -> Self::Witness {
-        let witness = (0..F::ORDER_U64)
-            .into_par_iter()
-            .map(|i| F::from_canonical_u64(i))
-            .find_any(|witness| self.clone().check_witness(bits, *witness))
-            .expect("failed to find witness");
-        assert!(self.check_witness(bits, witness));
-        witness
-    }
+impl<F, H, const OUT_LEN: usize> GrindingChallenger for HashChallenger<F, H, OUT_LEN>
+where
+    F: PrimeField64,
+    H: CryptographicHasher<F, [F; OUT_LEN]> + Sync,
+{
+    type Witness = F;
+}
+
+impl<F, H> GrindingChallenger for SerializingChallenger32<F, H>
+where
+    F: PrimeField32,
+    H: CryptographicHasher<u8, [u8; 32]> + Sync,
+{
+    type Witness = F;
+}
+
+impl<F, H> GrindingChallenger for SerializingChallenger64<F, H>
+where
+    F: PrimeField64,
+    H: CryptographicHasher<u8, [u8; 32]> + Sync,
+{
+    type Witness = F;
 }
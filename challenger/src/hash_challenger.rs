@@ -1,10 +1,10 @@
 use alloc::vec;
 use alloc::vec::Vec;
 
-use p3_field::Field;
-use p3_symmetric::CryptographicHasher;
+use p3_field::{ExtensionField, Field, PrimeField64};
+use p3_symmetric::{CryptographicHasher, MerkleCap};
 
-use crate::{CanObserve, CanSample};
+use crate::{CanObserve, CanSample, CanSampleBits, FieldChallenger};
 
 #[derive(Clone)]
 pub struct HashChallenger<F, H, const OUT_LEN: usize>
@@ -67,21 +67,58 @@ where
     }
 }
 
-impl<F, H, const OUT_LEN: usize> CanSample<F> for HashChallenger<F, H, OUT_LEN>
+impl<F, H, const N: usize, const OUT_LEN: usize> CanObserve<MerkleCap<F, F, N>>
+    for HashChallenger<F, H, OUT_LEN>
 where
     F: Field,
     H: CryptographicHasher<F, [F; OUT_LEN]>,
 {
-    fn sample(&mut self) -> F {
-        if self.output_buffer.is_empty() {
-            self.flush();
+    fn observe(&mut self, cap: MerkleCap<F, F, N>) {
+        for &digest in cap.digests() {
+            self.observe(digest);
         }
-        self.output_buffer
-            .pop()
-            .expect("Output buffer should be non-empty")
     }
 }
 
+impl<F, EF, H, const OUT_LEN: usize> CanSample<EF> for HashChallenger<F, H, OUT_LEN>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    H: CryptographicHasher<F, [F; OUT_LEN]>,
+{
+    fn sample(&mut self) -> EF {
+        EF::from_base_fn(|_| {
+            if self.output_buffer.is_empty() {
+                self.flush();
+            }
+            self.output_buffer
+                .pop()
+                .expect("Output buffer should be non-empty")
+        })
+    }
+}
+
+impl<F, H, const OUT_LEN: usize> CanSampleBits<usize> for HashChallenger<F, H, OUT_LEN>
+where
+    F: PrimeField64,
+    H: CryptographicHasher<F, [F; OUT_LEN]>,
+{
+    fn sample_bits(&mut self, bits: usize) -> usize {
+        debug_assert!(bits < (usize::BITS as usize));
+        debug_assert!((1 << bits) < F::ORDER_U64);
+        let rand_f: F = self.sample();
+        let rand_usize = rand_f.as_canonical_u64() as usize;
+        rand_usize & ((1 << bits) - 1)
+    }
+}
+
+impl<F, H, const OUT_LEN: usize> FieldChallenger<F> for HashChallenger<F, H, OUT_LEN>
+where
+    F: PrimeField64,
+    H: CryptographicHasher<F, [F; OUT_LEN]> + Sync,
+{
+}
+
 #[cfg(test)]
 mod tests {
     use p3_field::AbstractField;
@@ -163,7 +200,7 @@ mod tests {
         let new_expected_len = 3;
         let new_expected_sum = 76;
 
-        let new_element = hash_challenger.sample();
+        let new_element: F = hash_challenger.sample();
         assert_eq!(new_element, F::from_canonical_u8(new_expected_len));
         assert_eq!(
             hash_challenger.output_buffer,
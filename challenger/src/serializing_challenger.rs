@@ -0,0 +1,436 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use p3_field::{ExtensionField, PrimeField32, PrimeField64};
+use p3_symmetric::{CryptographicHasher, MerkleCap};
+
+use crate::{CanObserve, CanSample, CanSampleBits, FieldChallenger};
+
+/// A challenger over bytes, which derives challenges by hashing its transcript with a byte hash
+/// function such as `Keccak256Hash` or `Blake3`.
+///
+/// Observed bytes are appended to an input buffer. When a challenge is sampled and no output is
+/// left, the input buffer is hashed; the digest becomes both the output, which is sampled from
+/// front to back, and the start of the next input.
+#[derive(Clone)]
+pub struct ByteChallenger<H>
+where
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    input_buffer: Vec<u8>,
+    /// The unsampled output, in reverse order.
+    output_buffer: Vec<u8>,
+    hasher: H,
+}
+
+impl<H> Default for ByteChallenger<H>
+where
+    H: CryptographicHasher<u8, [u8; 32]> + Default,
+{
+    fn default() -> Self {
+        Self::new(Vec::new(), H::default())
+    }
+}
+
+impl<H> ByteChallenger<H>
+where
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    pub fn new(initial_state: Vec<u8>, hasher: H) -> Self {
+        Self {
+            input_buffer: initial_state,
+            output_buffer: Vec::new(),
+            hasher,
+        }
+    }
+
+    fn flush(&mut self) {
+        let output = self.hasher.hash_iter(self.input_buffer.drain(..));
+
+        self.output_buffer = output.iter().rev().copied().collect();
+
+        // Chaining values.
+        self.input_buffer.extend(output);
+    }
+}
+
+impl<H> CanObserve<u8> for ByteChallenger<H>
+where
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn observe(&mut self, value: u8) {
+        // Any buffered output is now invalid.
+        self.output_buffer.clear();
+
+        self.input_buffer.push(value);
+    }
+}
+
+impl<H, const N: usize> CanObserve<[u8; N]> for ByteChallenger<H>
+where
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn observe(&mut self, values: [u8; N]) {
+        for value in values {
+            self.observe(value);
+        }
+    }
+}
+
+impl<H> CanSample<u8> for ByteChallenger<H>
+where
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn sample(&mut self) -> u8 {
+        if self.output_buffer.is_empty() {
+            self.flush();
+        }
+        self.output_buffer
+            .pop()
+            .expect("Output buffer should be non-empty")
+    }
+}
+
+/// A field challenger over a `ByteChallenger`, for fields whose elements fit in 32 bits.
+///
+/// Field elements are observed as the 4-byte little-endian encodings of their canonical values,
+/// and the digests of Merkle caps as raw bytes, as committed to by a `FieldMerkleTreeMmcs` with
+/// byte digests.
+/// A field element is sampled from the next 4 bytes of output, read as a little-endian `u32` with
+/// the bits above the field's bit length cleared, rejecting and resampling values which aren't
+/// below the field's order.
+#[derive(Clone)]
+pub struct SerializingChallenger32<F, H>
+where
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    inner: ByteChallenger<H>,
+    _phantom: PhantomData<F>,
+}
+
+/// A field challenger over a `ByteChallenger`, for fields whose elements fit in 64 bits.
+///
+/// Like `SerializingChallenger32`, but with 8-byte little-endian encodings and `u64`s.
+#[derive(Clone)]
+pub struct SerializingChallenger64<F, H>
+where
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    inner: ByteChallenger<H>,
+    _phantom: PhantomData<F>,
+}
+
+impl<F, H> Default for SerializingChallenger32<F, H>
+where
+    F: PrimeField32,
+    H: CryptographicHasher<u8, [u8; 32]> + Default,
+{
+    fn default() -> Self {
+        Self::from_hasher(H::default())
+    }
+}
+
+impl<F, H> SerializingChallenger32<F, H>
+where
+    F: PrimeField32,
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    pub fn new(inner: ByteChallenger<H>) -> Self {
+        Self {
+            inner,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// A challenger over the given hash function, with an empty initial transcript.
+    pub fn from_hasher(hasher: H) -> Self {
+        Self::new(ByteChallenger::new(Vec::new(), hasher))
+    }
+
+    fn sample_u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.inner.sample_array())
+    }
+}
+
+impl<F, H> Default for SerializingChallenger64<F, H>
+where
+    F: PrimeField64,
+    H: CryptographicHasher<u8, [u8; 32]> + Default,
+{
+    fn default() -> Self {
+        Self::from_hasher(H::default())
+    }
+}
+
+impl<F, H> SerializingChallenger64<F, H>
+where
+    F: PrimeField64,
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    pub fn new(inner: ByteChallenger<H>) -> Self {
+        Self {
+            inner,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// A challenger over the given hash function, with an empty initial transcript.
+    pub fn from_hasher(hasher: H) -> Self {
+        Self::new(ByteChallenger::new(Vec::new(), hasher))
+    }
+
+    fn sample_u64(&mut self) -> u64 {
+        u64::from_le_bytes(self.inner.sample_array())
+    }
+}
+
+impl<F, H> CanObserve<F> for SerializingChallenger32<F, H>
+where
+    F: PrimeField32,
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn observe(&mut self, value: F) {
+        self.inner.observe(value.as_canonical_u32().to_le_bytes());
+    }
+}
+
+impl<F, H> CanObserve<F> for SerializingChallenger64<F, H>
+where
+    F: PrimeField64,
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn observe(&mut self, value: F) {
+        self.inner.observe(value.as_canonical_u64().to_le_bytes());
+    }
+}
+
+impl<F, H, const N: usize> CanObserve<[F; N]> for SerializingChallenger32<F, H>
+where
+    F: PrimeField32,
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn observe(&mut self, values: [F; N]) {
+        for value in values {
+            self.observe(value);
+        }
+    }
+}
+
+impl<F, H, const N: usize> CanObserve<[F; N]> for SerializingChallenger64<F, H>
+where
+    F: PrimeField64,
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn observe(&mut self, values: [F; N]) {
+        for value in values {
+            self.observe(value);
+        }
+    }
+}
+
+impl<F, H, const N: usize> CanObserve<MerkleCap<F, u8, N>> for SerializingChallenger32<F, H>
+where
+    F: PrimeField32,
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn observe(&mut self, cap: MerkleCap<F, u8, N>) {
+        for &digest in cap.digests() {
+            self.inner.observe(digest);
+        }
+    }
+}
+
+impl<F, H, const N: usize> CanObserve<MerkleCap<F, u8, N>> for SerializingChallenger64<F, H>
+where
+    F: PrimeField64,
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn observe(&mut self, cap: MerkleCap<F, u8, N>) {
+        for &digest in cap.digests() {
+            self.inner.observe(digest);
+        }
+    }
+}
+
+impl<F, EF, H> CanSample<EF> for SerializingChallenger32<F, H>
+where
+    F: PrimeField32,
+    EF: ExtensionField<F>,
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn sample(&mut self) -> EF {
+        // We use a u64 mask, as the field may have 32 bits.
+        let mask = ((1u64 << F::bits()) - 1) as u32;
+        EF::from_base_fn(|_| loop {
+            let value = self.sample_u32() & mask;
+            if value < F::ORDER_U32 {
+                return F::from_canonical_u32(value);
+            }
+        })
+    }
+}
+
+impl<F, EF, H> CanSample<EF> for SerializingChallenger64<F, H>
+where
+    F: PrimeField64,
+    EF: ExtensionField<F>,
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn sample(&mut self) -> EF {
+        let mask = u64::MAX >> (64 - F::bits());
+        EF::from_base_fn(|_| loop {
+            let value = self.sample_u64() & mask;
+            if value < F::ORDER_U64 {
+                return F::from_canonical_u64(value);
+            }
+        })
+    }
+}
+
+impl<F, H> CanSampleBits<usize> for SerializingChallenger32<F, H>
+where
+    F: PrimeField32,
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn sample_bits(&mut self, bits: usize) -> usize {
+        debug_assert!(bits < 32);
+        (self.sample_u32() & ((1 << bits) - 1)) as usize
+    }
+}
+
+impl<F, H> CanSampleBits<usize> for SerializingChallenger64<F, H>
+where
+    F: PrimeField64,
+    H: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn sample_bits(&mut self, bits: usize) -> usize {
+        debug_assert!(bits < (usize::BITS as usize));
+        (self.sample_u64() & ((1 << bits) - 1)) as usize
+    }
+}
+
+impl<F, H> FieldChallenger<F> for SerializingChallenger32<F, H>
+where
+    F: PrimeField32,
+    H: CryptographicHasher<u8, [u8; 32]> + Sync,
+{
+}
+
+impl<F, H> FieldChallenger<F> for SerializingChallenger64<F, H>
+where
+    F: PrimeField64,
+    H: CryptographicHasher<u8, [u8; 32]> + Sync,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+    use p3_goldilocks::Goldilocks;
+    use p3_keccak::Keccak256Hash;
+
+    use super::*;
+    use crate::GrindingChallenger;
+
+    #[test]
+    fn test_byte_challenger_chaining() {
+        let mut challenger = ByteChallenger::new(vec![1, 2], Keccak256Hash);
+        challenger.observe(3u8);
+
+        let digest = Keccak256Hash.hash_iter([1, 2, 3]);
+        let sampled: [u8; 32] = challenger.sample_array();
+        assert_eq!(sampled, digest);
+
+        // The next output hashes the previous digest, with any newly observed bytes.
+        challenger.observe([4u8, 5]);
+        let mut next_input = digest.to_vec();
+        next_input.extend([4, 5]);
+        assert_eq!(challenger.sample(), Keccak256Hash.hash_iter(next_input)[0]);
+    }
+
+    /// The first `N` bytes of the Keccak-256 digest of `input`.
+    fn keccak_prefix<const N: usize>(input: &[u8]) -> [u8; N] {
+        Keccak256Hash.hash_iter(input.iter().copied())[..N]
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_serializing_challenger_32() {
+        type F = BabyBear;
+        let mut challenger = SerializingChallenger32::<F, _>::from_hasher(Keccak256Hash);
+        challenger.observe(F::from_canonical_u32(0x01020304));
+        assert_eq!(challenger.inner.input_buffer, vec![4, 3, 2, 1]);
+
+        // The first sample of this transcript is in range, so it's taken as is.
+        let expected = u32::from_le_bytes(keccak_prefix(&[4, 3, 2, 1])) & ((1 << 31) - 1);
+        assert!(expected < F::ORDER_U32);
+        let sampled: F = challenger.sample();
+        assert_eq!(sampled, F::from_canonical_u32(expected));
+    }
+
+    #[test]
+    fn test_serializing_challenger_64() {
+        type F = Goldilocks;
+        let mut challenger = SerializingChallenger64::<F, _>::from_hasher(Keccak256Hash);
+        challenger.observe(F::from_canonical_u64(0x0102030405060708));
+        assert_eq!(challenger.inner.input_buffer, vec![8, 7, 6, 5, 4, 3, 2, 1]);
+
+        // The first sample of this transcript is in range, so it's taken as is.
+        let expected = u64::from_le_bytes(keccak_prefix(&[8, 7, 6, 5, 4, 3, 2, 1]));
+        assert!(expected < F::ORDER_U64);
+        let sampled: F = challenger.sample();
+        assert_eq!(sampled, F::from_canonical_u64(expected));
+    }
+
+    #[test]
+    fn test_serializing_challenger_32_rejects_out_of_range_samples() {
+        type F = BabyBear;
+        // Search for a transcript whose first 31-bit sample is at least the field's order, so the
+        // challenger must skip it and use the next 4 bytes of output.
+        let (input, digest) = (0u32..)
+            .map(|i| {
+                let input = i.to_le_bytes();
+                (input, Keccak256Hash.hash_iter(input))
+            })
+            .find(|(_, digest)| {
+                u32::from_le_bytes(digest[..4].try_into().unwrap()) & ((1 << 31) - 1)
+                    >= F::ORDER_U32
+            })
+            .unwrap();
+        let next = u32::from_le_bytes(digest[4..8].try_into().unwrap()) & ((1 << 31) - 1);
+        assert!(next < F::ORDER_U32);
+
+        let mut challenger = SerializingChallenger32::<F, _>::from_hasher(Keccak256Hash);
+        challenger.inner.observe(input);
+        let sampled: F = challenger.sample();
+        assert_eq!(sampled, F::from_canonical_u32(next));
+    }
+
+    #[test]
+    fn test_serializing_challenger_observes_byte_caps() {
+        type F = BabyBear;
+        let digests = vec![[1u8; 32], [2u8; 32]];
+        let mut challenger = SerializingChallenger32::<F, _>::from_hasher(Keccak256Hash);
+        challenger.observe(MerkleCap::<F, u8, 32>::new(digests.clone()));
+        assert_eq!(challenger.inner.input_buffer, digests.concat());
+    }
+
+    #[test]
+    fn test_serializing_challenger_grinding() {
+        type F = BabyBear;
+        let mut challenger = SerializingChallenger32::<F, _>::from_hasher(Keccak256Hash);
+        challenger.observe(F::one());
+
+        let mut verifier_challenger = challenger.clone();
+        let witness = challenger.grind(8);
+        assert!(verifier_challenger.check_witness(8, witness));
+
+        // Both transcripts have observed the witness, and agree on later challenges.
+        let sample: F = challenger.sample();
+        assert_eq!(sample, verifier_challenger.sample());
+    }
+}
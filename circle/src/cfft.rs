@@ -0,0 +1,334 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use p3_field::extension::ComplexExtendable;
+use p3_field::{batch_multiplicative_inverse, AbstractField, ExtensionField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::util::reverse_matrix_index_bits;
+use p3_matrix::{Matrix, MatrixRowSlices};
+use p3_util::log2_strict_usize;
+
+use crate::{double_x, CircleDomain, CirclePoint};
+
+/// The evaluations of a batch of polynomials over a circle domain, one per column.
+///
+/// The polynomials are those spanned by the basis of the circle FFT,
+/// `b_j(x, y) = y^{j_0} pi_1(x)^{j_1} ... pi_{log_n - 1}(x)^{j_{log_n - 1}}` for `j < n`, where `j_k`
+/// is the `k`th bit of `j`, `pi_1(x) = x` and `pi_{k + 1}(x) = 2 pi_k(x)^2 - 1`. They are
+/// `p_0(x) + y p_1(x)` with `p_0` and `p_1` of degree less than `n/2`, and are uniquely determined
+/// by their evaluations over any twin coset of size `n`.
+///
+/// The coefficients used by `interpolate` and `evaluate` are with respect to this basis, in the
+/// order of `j`. The basis doesn't depend on the domain, so zero padding the coefficients of a
+/// polynomial gives its coefficients for a larger domain.
+#[derive(Clone, Debug)]
+pub struct CircleEvaluations<F> {
+    domain: CircleDomain<F>,
+    /// The evaluations, with rows in the domain's natural order.
+    values: RowMajorMatrix<F>,
+}
+
+impl<F: ComplexExtendable> CircleEvaluations<F> {
+    /// Evaluations whose rows are in the domain's natural order.
+    pub fn from_natural_order(domain: CircleDomain<F>, values: RowMajorMatrix<F>) -> Self {
+        assert_eq!(values.height(), domain.size());
+        Self { domain, values }
+    }
+
+    /// Evaluations whose rows are in bit-reversed order, which is how the circle FFT pairs them.
+    pub fn from_bit_reversed_order(domain: CircleDomain<F>, mut values: RowMajorMatrix<F>) -> Self {
+        assert_eq!(values.height(), domain.size());
+        reverse_matrix_index_bits(&mut values);
+        Self { domain, values }
+    }
+
+    /// Evaluations over a standard position coset whose rows are in its cyclic order.
+    pub fn from_cyclic_order(domain: CircleDomain<F>, values: RowMajorMatrix<F>) -> Self {
+        assert!(domain.is_standard(), "only standard position cosets are cyclic");
+        assert_eq!(values.height(), domain.size());
+        let width = values.width();
+        let natural = (0..domain.size())
+            .flat_map(|i| values.row_slice(domain.cyclic_index(i)).iter().copied())
+            .collect();
+        Self {
+            domain,
+            values: RowMajorMatrix::new(natural, width),
+        }
+    }
+
+    pub fn domain(&self) -> CircleDomain<F> {
+        self.domain
+    }
+
+    pub fn to_natural_order(self) -> RowMajorMatrix<F> {
+        self.values
+    }
+
+    /// The evaluations in cyclic order, which only standard position cosets have.
+    pub fn to_cyclic_order(self) -> RowMajorMatrix<F> {
+        assert!(
+            self.domain.is_standard(),
+            "only standard position cosets are cyclic"
+        );
+        let width = self.values.width();
+        let mut cyclic = RowMajorMatrix::new(vec![F::zero(); self.values.values.len()], width);
+        for (i, row) in self.values.rows().enumerate() {
+            cyclic
+                .row_mut(self.domain.cyclic_index(i))
+                .copy_from_slice(row);
+        }
+        cyclic
+    }
+
+    pub fn to_bit_reversed_order(self) -> RowMajorMatrix<F> {
+        let mut values = self.values;
+        reverse_matrix_index_bits(&mut values);
+        values
+    }
+
+    /// Compute the coefficients of each polynomial, with the inverse circle FFT.
+    pub fn interpolate(self) -> RowMajorMatrix<F> {
+        let Self { domain, mut values } = self;
+        let width = values.width();
+        for (layer, twiddles) in domain.twiddles().iter().enumerate() {
+            let half_block_size = domain.size() >> (layer + 1);
+            let inverse_twiddles = batch_multiplicative_inverse(twiddles);
+            for block in values.values.chunks_mut(2 * half_block_size * width) {
+                let (lo, hi) = block.split_at_mut(half_block_size * width);
+                for ((lo_row, hi_row), &inverse_twiddle) in lo
+                    .chunks_mut(width)
+                    .zip(hi.chunks_mut(width))
+                    .zip(&inverse_twiddles)
+                {
+                    // `f(t) = f_0 + t f_1` and `f(-t) = f_0 - t f_1`, with each `f_i` doubled.
+                    for (a, b) in lo_row.iter_mut().zip(hi_row.iter_mut()) {
+                        let (sum, diff) = (*a + *b, *a - *b);
+                        *a = sum;
+                        *b = diff * inverse_twiddle;
+                    }
+                }
+            }
+        }
+
+        // Each layer doubled the coefficients, and left them in bit-reversed order.
+        let scale = F::two().exp_u64(domain.log_n() as u64).inverse();
+        values.values.iter_mut().for_each(|v| *v *= scale);
+        reverse_matrix_index_bits(&mut values);
+        values
+    }
+
+    /// Evaluate polynomials with the given coefficients over `domain`, with the circle FFT.
+    ///
+    /// There may be fewer coefficients than the domain's size, in which case the rest are zero.
+    pub fn evaluate(domain: CircleDomain<F>, mut coeffs: RowMajorMatrix<F>) -> Self {
+        assert!(coeffs.height() <= domain.size());
+        let width = coeffs.width();
+        coeffs.values.resize(domain.size() * width, F::zero());
+        reverse_matrix_index_bits(&mut coeffs);
+
+        let mut values = coeffs;
+        for (layer, twiddles) in domain.twiddles().iter().enumerate().rev() {
+            let half_block_size = domain.size() >> (layer + 1);
+            for block in values.values.chunks_mut(2 * half_block_size * width) {
+                let (lo, hi) = block.split_at_mut(half_block_size * width);
+                for ((lo_row, hi_row), &twiddle) in
+                    lo.chunks_mut(width).zip(hi.chunks_mut(width)).zip(twiddles)
+                {
+                    for (a, b) in lo_row.iter_mut().zip(hi_row.iter_mut()) {
+                        let t_b = *b * twiddle;
+                        (*a, *b) = (*a + t_b, *a - t_b);
+                    }
+                }
+            }
+        }
+        Self { domain, values }
+    }
+
+    /// Evaluate the polynomials over a larger domain, i.e. compute their low-degree extension.
+    pub fn extrapolate(self, target_domain: CircleDomain<F>) -> Self {
+        assert!(target_domain.log_n() >= self.domain.log_n());
+        Self::evaluate(target_domain, self.interpolate())
+    }
+
+    /// Evaluate each polynomial at a point, which may be outside the domain and over an extension.
+    pub fn evaluate_at_point<EF: ExtensionField<F>>(&self, point: CirclePoint<EF>) -> Vec<EF> {
+        evaluate_coeffs_at_point(&self.clone().interpolate(), point)
+    }
+}
+
+/// Evaluate polynomials with the given coefficients, one column per polynomial, at a point.
+pub(crate) fn evaluate_coeffs_at_point<F: Field, EF: ExtensionField<F>>(
+    coeffs: &RowMajorMatrix<F>,
+    point: CirclePoint<EF>,
+) -> Vec<EF> {
+    let basis = circle_basis(point, log2_strict_usize(coeffs.height()));
+    let mut result = vec![EF::zero(); coeffs.width()];
+    for (basis_value, row) in basis.into_iter().zip(coeffs.rows()) {
+        for (r, &c) in result.iter_mut().zip(row) {
+            *r += basis_value * c;
+        }
+    }
+    result
+}
+
+/// The values of the first `2^log_n` elements of the circle FFT's basis at `point`.
+pub fn circle_basis<AF: AbstractField + Copy>(point: CirclePoint<AF>, log_n: usize) -> Vec<AF> {
+    let mut basis = Vec::with_capacity(1 << log_n);
+    basis.push(AF::one());
+    let mut factor = point.y;
+    let mut x = point.x;
+    for _ in 0..log_n {
+        let len = basis.len();
+        basis.extend_from_within(..);
+        for b in &mut basis[len..] {
+            *b *= factor;
+        }
+        factor = x;
+        x = double_x(x);
+    }
+    basis
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::extension::Complex;
+    use p3_mersenne_31::Mersenne31;
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+
+    type F = Mersenne31;
+    type EF = Complex<Mersenne31>;
+
+    fn random_matrix(height: usize, width: usize) -> RowMajorMatrix<F> {
+        let mut rng = thread_rng();
+        RowMajorMatrix::new((0..height * width).map(|_| rng.gen()).collect(), width)
+    }
+
+    /// Evaluate polynomials with the given coefficients at a point, directly from the basis.
+    fn evaluate_naive<EF: ExtensionField<F>>(
+        coeffs: &RowMajorMatrix<F>,
+        point: CirclePoint<EF>,
+    ) -> Vec<EF> {
+        let log_n = p3_util::log2_strict_usize(coeffs.height());
+        let basis = circle_basis(point, log_n);
+        (0..coeffs.width())
+            .map(|col| {
+                basis
+                    .iter()
+                    .zip(coeffs.rows())
+                    .map(|(&b, row)| b * row[col])
+                    .sum()
+            })
+            .collect()
+    }
+
+    fn domains(log_n: usize) -> [CircleDomain<F>; 2] {
+        [
+            CircleDomain::standard(log_n),
+            CircleDomain::new(log_n, CirclePoint::generator(log_n + 3)),
+        ]
+    }
+
+    #[test]
+    fn test_evaluate_matches_basis() {
+        for log_n in 1..=6 {
+            for domain in domains(log_n) {
+                let coeffs = random_matrix(1 << log_n, 3);
+                let evals = CircleEvaluations::evaluate(domain, coeffs.clone()).to_natural_order();
+                for (point, row) in domain.points().into_iter().zip(evals.rows()) {
+                    assert_eq!(evaluate_naive(&coeffs, point), row.to_vec());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_interpolate_inverts_evaluate() {
+        for log_n in 1..=8 {
+            for domain in domains(log_n) {
+                let values = random_matrix(1 << log_n, 2);
+                let coeffs =
+                    CircleEvaluations::from_natural_order(domain, values.clone()).interpolate();
+                let evals = CircleEvaluations::evaluate(domain, coeffs);
+                assert_eq!(evals.to_natural_order(), values);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bit_reversed_order() {
+        let domain = CircleDomain::standard(4);
+        let values = random_matrix(16, 2);
+        let evals = CircleEvaluations::from_bit_reversed_order(domain, values.clone());
+        assert_eq!(evals.clone().to_bit_reversed_order(), values);
+
+        // In bit-reversed order, each point is next to its conjugate.
+        let mut points = RowMajorMatrix::new(domain.points(), 1);
+        reverse_matrix_index_bits(&mut points);
+        for pair in points.values.chunks(2) {
+            assert_eq!(pair[1], pair[0].conjugate());
+        }
+    }
+
+    #[test]
+    fn test_cyclic_order() {
+        let domain = CircleDomain::standard(4);
+        let values = random_matrix(16, 2);
+        let evals = CircleEvaluations::from_cyclic_order(domain, values.clone());
+        assert_eq!(evals.clone().to_cyclic_order(), values);
+
+        // The cyclic order steps through the domain by the generator of its subgroup.
+        let natural = evals.to_natural_order();
+        let points = domain.points();
+        let g = CirclePoint::generator(4);
+        for (j, row) in values.rows().enumerate() {
+            let point = domain.shift() * g.exp_u64(j as u64);
+            let i = points.iter().position(|&p| p == point).unwrap();
+            assert_eq!(natural.row_slice(i), row);
+        }
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        let log_n = 5;
+        let coeffs = random_matrix(1 << log_n, 2);
+        for domain in domains(log_n) {
+            let evals = CircleEvaluations::evaluate(domain, coeffs.clone());
+            for added_bits in 0..3 {
+                let target = CircleDomain::standard(log_n + added_bits);
+                let lde = evals.clone().extrapolate(target);
+                assert_eq!(lde.domain(), target);
+                let lde = lde.to_natural_order();
+                for (point, row) in target.points().into_iter().zip(lde.rows()) {
+                    assert_eq!(evaluate_naive(&coeffs, point), row.to_vec());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate_at_extension_point() {
+        let log_n = 6;
+        let coeffs = random_matrix(1 << log_n, 3);
+        let evals = CircleEvaluations::evaluate(CircleDomain::standard(log_n), coeffs.clone());
+        let point = CirclePoint::from_projective_line(thread_rng().gen::<EF>()).unwrap();
+        assert_eq!(
+            evals.evaluate_at_point(point),
+            evaluate_naive(&coeffs, point)
+        );
+    }
+
+    #[test]
+    fn test_low_degree_extension_has_zero_high_coefficients() {
+        let log_n = 4;
+        let evals = CircleEvaluations::from_natural_order(
+            CircleDomain::standard(log_n),
+            random_matrix(16, 1),
+        );
+        let coeffs = evals
+            .extrapolate(CircleDomain::standard(log_n + 2))
+            .interpolate();
+        assert!(coeffs.values[16..].iter().all(|c| c.is_zero()));
+    }
+}
@@ -0,0 +1,239 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use p3_field::extension::ComplexExtendable;
+use p3_field::ExtensionField;
+
+use crate::{double_x, CirclePoint};
+
+/// A twin coset of the circle group, `shift * H ∪ shift^{-1} * H` where `H` is the subgroup of
+/// order `2^(log_n - 1)`, which has `2^log_n` points.
+///
+/// The standard position coset of size `2^log_n` is the twin coset whose shift has order
+/// `2^(log_n + 1)`. It is also a coset of the subgroup of order `2^log_n`, and doubling its points
+/// gives the standard position coset of half its size.
+///
+/// The domain's natural order lists `shift * h^i` for each `i < 2^(log_n - 1)`, where `h`
+/// generates `H`, and then their conjugates in the same order. The circle FFT pairs up each point
+/// with its conjugate, so in bit-reversed order, the pairs are adjacent.
+///
+/// A standard position coset also has a cyclic order, which lists `shift * g^j` for each `j < n`,
+/// where `g = shift^2` generates the subgroup of order `n`. The natural order's first half holds
+/// the even positions of the cyclic order, and its second half the odd ones, in reverse.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CircleDomain<F> {
+    log_n: usize,
+    shift: CirclePoint<F>,
+}
+
+impl<F: ComplexExtendable> CircleDomain<F> {
+    /// The standard position coset of size `2^log_n`.
+    pub fn standard(log_n: usize) -> Self {
+        Self::new(log_n, CirclePoint::generator(log_n + 1))
+    }
+
+    /// The twin coset of size `2^log_n` with the given shift.
+    ///
+    /// The two cosets must be disjoint, i.e. `shift^2` must not be in `H`.
+    pub fn new(log_n: usize, shift: CirclePoint<F>) -> Self {
+        assert!(
+            (1..F::CIRCLE_TWO_ADICITY).contains(&log_n),
+            "log_n must be between 1 and {}",
+            F::CIRCLE_TWO_ADICITY - 1
+        );
+        assert_ne!(
+            shift.exp_power_of_2(log_n),
+            CirclePoint::identity(),
+            "the twin cosets must be disjoint"
+        );
+        Self { log_n, shift }
+    }
+
+    pub fn log_n(&self) -> usize {
+        self.log_n
+    }
+
+    pub fn size(&self) -> usize {
+        1 << self.log_n
+    }
+
+    pub fn shift(&self) -> CirclePoint<F> {
+        self.shift
+    }
+
+    pub fn is_standard(&self) -> bool {
+        *self == Self::standard(self.log_n)
+    }
+
+    /// For a standard position coset, the position in cyclic order of the `i`th point in natural
+    /// order.
+    pub fn cyclic_index(&self, i: usize) -> usize {
+        debug_assert!(self.is_standard());
+        let half_n = self.size() / 2;
+        if i < half_n {
+            2 * i
+        } else {
+            self.size() - 1 - 2 * (i - half_n)
+        }
+    }
+
+    /// The points of a standard position coset, in cyclic order.
+    pub fn cyclic_points(&self) -> Vec<CirclePoint<F>> {
+        assert!(
+            self.is_standard(),
+            "only standard position cosets are cyclic"
+        );
+        let g = CirclePoint::generator(self.log_n);
+        let mut points = Vec::with_capacity(self.size());
+        let mut point = self.shift;
+        for _ in 0..self.size() {
+            points.push(point);
+            point = point * g;
+        }
+        points
+    }
+
+    /// A generator of `H`, the subgroup which both cosets are cosets of.
+    fn subgroup_generator(&self) -> CirclePoint<F> {
+        CirclePoint::generator(self.log_n - 1)
+    }
+
+    /// The domain's `i`th point, in natural order.
+    pub fn nth_point(&self, i: usize) -> CirclePoint<F> {
+        let half_n = self.size() / 2;
+        assert!(i < self.size(), "index out of bounds");
+        let point = self.shift * self.subgroup_generator().exp_u64((i % half_n) as u64);
+        if i < half_n {
+            point
+        } else {
+            point.conjugate()
+        }
+    }
+
+    /// The domain's points, in natural order.
+    pub fn points(&self) -> Vec<CirclePoint<F>> {
+        let g = self.subgroup_generator();
+        let mut points = Vec::with_capacity(self.size());
+        let mut point = self.shift;
+        for _ in 0..self.size() / 2 {
+            points.push(point);
+            point = point * g;
+        }
+        points.extend_from_within(..);
+        for point in &mut points[self.size() / 2..] {
+            *point = point.conjugate();
+        }
+        points
+    }
+
+    /// Evaluate the polynomial of degree `2^(log_n - 1)` in `x` that vanishes exactly on the
+    /// domain, `v(x) = pi^(log_n - 1)(x) - pi^(log_n - 1)(shift.x)` where `pi(x) = 2x^2 - 1`.
+    ///
+    /// Doubling a point `log_n - 1` times maps all of the domain's points to `shift^(n/2)` or its
+    /// conjugate, which share an x-coordinate.
+    pub fn vanishing_poly<EF: ExtensionField<F>>(&self, point: CirclePoint<EF>) -> EF {
+        let doubled_x = (1..self.log_n).fold(point.x, |x, _| double_x(x));
+        let doubled_shift_x = (1..self.log_n).fold(self.shift.x, |x, _| double_x(x));
+        doubled_x - EF::from_base(doubled_shift_x)
+    }
+
+    /// The twiddles of each layer of the circle FFT. The first layer's are the y-coordinates of
+    /// the first half of the domain, and each later layer's are x-coordinates, of half as many
+    /// points as the layer before.
+    pub(crate) fn twiddles(&self) -> Vec<Vec<F>> {
+        let first_half = &self.points()[..self.size() / 2];
+        let mut twiddles = vec![first_half.iter().map(|point| point.y).collect::<Vec<_>>()];
+        let mut xs = first_half.iter().map(|point| point.x).collect::<Vec<_>>();
+        for _ in 1..self.log_n {
+            xs.truncate(xs.len() / 2);
+            let doubled_xs = xs.iter().map(|&x| double_x(x)).collect();
+            twiddles.push(core::mem::replace(&mut xs, doubled_xs));
+        }
+        twiddles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeSet;
+
+    use p3_field::{AbstractField, PrimeField32};
+    use p3_mersenne_31::Mersenne31;
+
+    use super::*;
+
+    type F = Mersenne31;
+
+    fn point_key(point: &CirclePoint<F>) -> (u32, u32) {
+        (point.x.as_canonical_u32(), point.y.as_canonical_u32())
+    }
+
+    #[test]
+    fn test_standard_domain_is_coset() {
+        let log_n = 5;
+        let domain = CircleDomain::<F>::standard(log_n);
+        let g = CirclePoint::generator(log_n);
+        let coset = (0..1u64 << log_n)
+            .map(|i| point_key(&(domain.shift() * g.exp_u64(i))))
+            .collect::<BTreeSet<_>>();
+        let points = domain.points();
+        assert_eq!(points.iter().map(point_key).collect::<BTreeSet<_>>(), coset);
+        for (i, point) in points.iter().enumerate() {
+            assert_eq!(domain.nth_point(i), *point);
+        }
+
+        // Doubling maps it onto the standard position coset of half the size.
+        let half = CircleDomain::<F>::standard(log_n - 1)
+            .points()
+            .iter()
+            .map(point_key)
+            .collect::<BTreeSet<_>>();
+        assert!(points
+            .iter()
+            .all(|p| half.contains(&point_key(&p.double()))));
+    }
+
+    #[test]
+    fn test_cyclic_order() {
+        let log_n = 5;
+        let domain = CircleDomain::<F>::standard(log_n);
+        let g = CirclePoint::generator(log_n);
+        let cyclic_points = domain.cyclic_points();
+        for (i, point) in domain.points().into_iter().enumerate() {
+            let j = domain.cyclic_index(i);
+            assert_eq!(point, domain.shift() * g.exp_u64(j as u64));
+            assert_eq!(point, cyclic_points[j]);
+        }
+    }
+
+    #[test]
+    fn test_vanishing_poly() {
+        let domain = CircleDomain::<F>::standard(4);
+        for point in domain.points() {
+            assert_eq!(domain.vanishing_poly(point), F::zero());
+        }
+        // The standard position coset of twice the size is disjoint from the domain.
+        for point in CircleDomain::<F>::standard(5).points() {
+            assert_ne!(domain.vanishing_poly(point), F::zero());
+        }
+    }
+
+    #[test]
+    fn test_twin_coset_points_are_distinct() {
+        let domain = CircleDomain::<F>::new(4, CirclePoint::generator(7));
+        let points = domain.points();
+        assert_eq!(
+            points.iter().map(point_key).collect::<BTreeSet<_>>().len(),
+            16
+        );
+        for point in points {
+            assert_eq!(domain.vanishing_poly(point), F::zero());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "the twin cosets must be disjoint")]
+    fn test_overlapping_twin_cosets() {
+        CircleDomain::<F>::new(4, CirclePoint::generator(4));
+    }
+}
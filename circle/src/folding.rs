@@ -0,0 +1,127 @@
+//! Folding of codewords in circle FRI.
+//!
+//! A codeword of evaluations over the standard position coset of size `2^log_n`, in bit-reversed
+//! order, is first folded by pairing each point with its conjugate, which leaves the evaluations of
+//! a polynomial in `x`. Its `k`th evaluation is at the x-coordinate of the `2k`th point, so pairs of
+//! opposite x-coordinates are adjacent, and each later fold pairs them up, halving the codeword's
+//! height like FRI folding does.
+
+use alloc::vec::Vec;
+
+use itertools::Itertools;
+use p3_field::extension::ComplexExtendable;
+use p3_field::{batch_multiplicative_inverse, AbstractExtensionField, ExtensionField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_util::{log2_strict_usize, reverse_bits_len, reverse_slice_index_bits};
+
+use crate::{double_x, CircleDomain, CircleEvaluations, CirclePoint};
+
+/// The point whose x-coordinate the `index`th evaluation of a folded codeword of height
+/// `2^log_height` is at, i.e. the first of the pair of conjugates it was folded from.
+pub(crate) fn folded_point<F: ComplexExtendable>(
+    index: usize,
+    log_height: usize,
+) -> CirclePoint<F> {
+    CircleDomain::standard(log_height + 1).nth_point(reverse_bits_len(index, log_height))
+}
+
+/// The `folded_point` of each evaluation of a folded codeword of height `2^log_height`.
+pub(crate) fn folded_points<F: ComplexExtendable>(log_height: usize) -> Vec<CirclePoint<F>> {
+    let mut points = CircleDomain::standard(log_height + 1).points();
+    reverse_slice_index_bits(&mut points);
+    points.into_iter().step_by(2).collect()
+}
+
+/// The x-coordinates which a fold of a folded codeword of height `2^log_height` divides by.
+pub(crate) fn fold_x_twiddles<F: ComplexExtendable>(log_height: usize) -> Vec<F> {
+    folded_points(log_height)
+        .into_iter()
+        .step_by(2)
+        .map(|point| point.x)
+        .collect()
+}
+
+/// Given the evaluations `lo` at `t` and `hi` at `-t` of `f(t) = f_0 + t f_1`, compute
+/// `f_0 + beta f_1`.
+pub(crate) fn fold_pair<F: Field, EF: ExtensionField<F>>(lo: EF, hi: EF, t: F, beta: EF) -> EF {
+    fold_pair_with_inverse(lo, hi, t.inverse(), F::two().inverse(), beta)
+}
+
+fn fold_pair_with_inverse<F: Field, EF: ExtensionField<F>>(
+    lo: EF,
+    hi: EF,
+    inverse_t: F,
+    half: F,
+    beta: EF,
+) -> EF {
+    (lo + hi + beta * (lo - hi) * inverse_t) * half
+}
+
+/// Fold each pair of adjacent evaluations with `fold_pair`, given the `t` of each pair.
+pub(crate) fn fold<F: Field, EF: ExtensionField<F>>(
+    evals: &[EF],
+    twiddles: &[F],
+    beta: EF,
+) -> Vec<EF> {
+    let half = F::two().inverse();
+    evals
+        .chunks_exact(2)
+        .zip_eq(batch_multiplicative_inverse(twiddles))
+        .map(|(pair, inverse_t)| fold_pair_with_inverse(pair[0], pair[1], inverse_t, half, beta))
+        .collect()
+}
+
+/// The coefficients of the polynomial in `x` which a folded codeword is the evaluations of, with
+/// respect to the basis `pi_1(x)^{j_0} pi_2(x)^{j_1} ...` of `CircleEvaluations`' polynomials
+/// without a factor of `y`.
+///
+/// Panics if there are more than `2^log_len` of them.
+pub(crate) fn folded_codeword_coefficients<F: ComplexExtendable, EF: ExtensionField<F>>(
+    codeword: &[EF],
+    log_len: usize,
+) -> Vec<EF> {
+    let log_height = log2_strict_usize(codeword.len());
+    let ext_degree = <EF as AbstractExtensionField<F>>::D;
+
+    // Each evaluation is at both points of a pair of conjugates, which are adjacent in bit-reversed
+    // order, so it takes up two rows. We interpolate each coordinate of the extension separately.
+    let values = codeword
+        .iter()
+        .flat_map(|eval| {
+            let coords = eval.as_base_slice();
+            coords.iter().chain(coords).copied().collect_vec()
+        })
+        .collect_vec();
+    let values = RowMajorMatrix::new(values, ext_degree);
+    let coeffs =
+        CircleEvaluations::from_bit_reversed_order(CircleDomain::standard(log_height + 1), values)
+            .interpolate();
+
+    // The basis elements with a factor of `y` have odd indices.
+    let mut coeffs = coeffs
+        .values
+        .chunks_exact(ext_degree)
+        .step_by(2)
+        .map(EF::from_base_slice)
+        .collect_vec();
+    assert!(
+        coeffs[1 << log_len..].iter().all(EF::is_zero),
+        "The final polynomial's degree is too large"
+    );
+    coeffs.truncate(1 << log_len);
+    coeffs
+}
+
+/// Evaluate a polynomial in `x`, given its coefficients as in `folded_codeword_coefficients`.
+pub(crate) fn evaluate_folded_poly<F: Field, EF: ExtensionField<F>>(coeffs: &[EF], x: F) -> EF {
+    let mut coeffs = coeffs.to_vec();
+    let mut factor = x;
+    while coeffs.len() > 1 {
+        coeffs = coeffs
+            .chunks(2)
+            .map(|pair| pair[0] + pair.get(1).map_or(EF::zero(), |&c| c * factor))
+            .collect();
+        factor = double_x(factor);
+    }
+    coeffs.first().copied().unwrap_or(EF::zero())
+}
@@ -0,0 +1,18 @@
+//! The circle group `x^2 + y^2 = 1` over fields with a complex extension, such as `Mersenne31`,
+//! the circle FFT over its twin cosets, and a polynomial commitment scheme based on circle FRI.
+
+#![no_std]
+
+extern crate alloc;
+
+mod cfft;
+mod domain;
+mod folding;
+mod pcs;
+mod point;
+mod stark;
+
+pub use cfft::*;
+pub use domain::*;
+pub use pcs::*;
+pub use point::*;
@@ -0,0 +1,886 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use itertools::{izip, Itertools};
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::{DirectMmcs, Mmcs, OpenedValues, Pcs, ProofSize, UnivariatePcs};
+use p3_field::extension::ComplexExtendable;
+use p3_field::{batch_multiplicative_inverse, ExtensionField, Field};
+use p3_fri::FriConfig;
+use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
+use p3_matrix::{Dimensions, Matrix, MatrixRows};
+use p3_util::codec::encoded_len;
+use p3_util::{log2_strict_usize, VecExt};
+use serde::{Deserialize, Serialize};
+use tracing::{info_span, instrument};
+
+use crate::cfft::evaluate_coeffs_at_point;
+use crate::folding::{
+    evaluate_folded_poly, fold, fold_pair, fold_x_twiddles, folded_codeword_coefficients,
+    folded_point, folded_points,
+};
+use crate::{CircleDomain, CircleEvaluations, CirclePoint};
+
+/// Reduced openings are stored in arrays of this length, indexed by log height.
+const MAX_LOG_HEIGHT: usize = 32;
+
+/// A polynomial commitment scheme over circle domains, based on circle FRI.
+///
+/// Each committed matrix holds the evaluations of a batch of polynomials, one per column, over the
+/// standard position coset of its height, in cyclic order, as in `CircleEvaluations`. The scheme
+/// commits to their low-degree extensions, and opens them at points of the circle over the
+/// extension field `Challenge`. As a `UnivariatePcs`, it takes each opening point as its
+/// coordinate on the projective line, as in `CirclePoint::from_projective_line`.
+///
+/// Along with the value of a polynomial `p` at a point `z`, the prover sends its value at `z`'s
+/// conjugate, which lies on the same vertical line `x = z.x`. Then `(p - L) / (x - z.x)`, where
+/// `L = a + b y` takes both values, is a polynomial exactly if both values are right. The prover
+/// shows that a random combination of these quotients is low-degree with circle FRI, which folds
+/// the evaluations at each point and its conjugate together, leaving a polynomial in `x`, and then
+/// folds by two like FRI.
+///
+/// The FRI config's folding arity must be two.
+#[derive(Clone)]
+pub struct CirclePcs<Val, Challenge, InputMmcs, FriMmcs> {
+    pub(crate) fri: FriConfig<FriMmcs>,
+    pub(crate) mmcs: InputMmcs,
+    _phantom: PhantomData<(Val, Challenge)>,
+}
+
+impl<Val, Challenge, InputMmcs, FriMmcs> CirclePcs<Val, Challenge, InputMmcs, FriMmcs> {
+    pub fn new(fri: FriConfig<FriMmcs>, mmcs: InputMmcs) -> Self {
+        assert_eq!(fri.log_folding_arity, 1, "circle FRI folds by two");
+        Self {
+            fri,
+            mmcs,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CirclePcsError<InputMmcsErr, FriMmcsErr> {
+    InvalidProofShape,
+    /// An opening point isn't on the circle, is its own conjugate, or shares its x-coordinate with
+    /// a point of a committed LDE.
+    InvalidOpeningPoint,
+    InputMmcsError(InputMmcsErr),
+    CommitPhaseMmcsError(FriMmcsErr),
+    FinalPolyMismatch,
+    InvalidPowWitness,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound = "")]
+pub struct CirclePcsProof<Val, Challenge, InputMmcs, FriMmcs>
+where
+    Val: Field,
+    Challenge: Field,
+    InputMmcs: Mmcs<Val>,
+    FriMmcs: Mmcs<Challenge>,
+{
+    /// The values at the conjugate of each opening point, in the same shape as the opened values.
+    pub(crate) conjugate_values: OpenedValues<Challenge>,
+    pub(crate) commit_phase_commits: Vec<FriMmcs::Commitment>,
+    /// For each commit phase codeword, the value folded with the queried one, for each query.
+    pub(crate) commit_phase_siblings: Vec<Vec<Challenge>>,
+    /// For each commit phase codeword, a proof of its openings at all queries.
+    pub(crate) commit_phase_opening_proofs: Vec<FriMmcs::MultiProof>,
+    /// The coefficients of the final polynomial in `x`.
+    pub(crate) final_poly: Vec<Challenge>,
+    pub(crate) pow_witness: Val,
+    /// For each committed batch, the batch's openings at every query.
+    pub(crate) batch_openings: Vec<CircleBatchOpening<Val, InputMmcs>>,
+}
+
+impl<Val, Challenge, InputMmcs, FriMmcs> CirclePcsProof<Val, Challenge, InputMmcs, FriMmcs>
+where
+    Val: Field,
+    Challenge: Field,
+    InputMmcs: Mmcs<Val>,
+    FriMmcs: Mmcs<Challenge>,
+{
+    pub fn conjugate_values(&self) -> &OpenedValues<Challenge> {
+        &self.conjugate_values
+    }
+
+    pub fn commit_phase_commits(&self) -> &[FriMmcs::Commitment] {
+        &self.commit_phase_commits
+    }
+
+    pub fn commit_phase_siblings(&self) -> &[Vec<Challenge>] {
+        &self.commit_phase_siblings
+    }
+
+    pub fn commit_phase_opening_proofs(&self) -> &[FriMmcs::MultiProof] {
+        &self.commit_phase_opening_proofs
+    }
+
+    pub fn final_poly(&self) -> &[Challenge] {
+        &self.final_poly
+    }
+
+    pub fn batch_openings(&self) -> &[CircleBatchOpening<Val, InputMmcs>] {
+        &self.batch_openings
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound = "")]
+pub struct CircleBatchOpening<Val: Field, InputMmcs: Mmcs<Val>> {
+    pub(crate) opened_values: Vec<Vec<Vec<Val>>>,
+    pub(crate) opening_proof: InputMmcs::MultiProof,
+}
+
+impl<Val: Field, InputMmcs: Mmcs<Val>> CircleBatchOpening<Val, InputMmcs> {
+    /// For each query, the opened row of each matrix in the batch, which holds the evaluations at
+    /// a point and then at its conjugate.
+    pub fn opened_values(&self) -> &[Vec<Vec<Val>>] {
+        &self.opened_values
+    }
+
+    /// The opening proof of the batch's rows at all queries.
+    pub fn opening_proof(&self) -> &InputMmcs::MultiProof {
+        &self.opening_proof
+    }
+}
+
+/// The lines through the values of a matrix's polynomials at an opening point and its conjugate,
+/// combined with the powers of alpha that their quotients are.
+struct ReducedLine<EF> {
+    /// The x-coordinate of the opening point, at which the quotients' denominator vanishes.
+    x: EF,
+    /// The power of alpha by which the quotient of the matrix's first column is scaled.
+    alpha_pow: EF,
+    /// The combined line is `constant + slope * y`.
+    constant: EF,
+    slope: EF,
+}
+
+impl<EF: Field> ReducedLine<EF> {
+    /// `alpha_pows` are the powers of alpha up to the matrix's width.
+    fn new(
+        point: CirclePoint<EF>,
+        alpha_pows: &[EF],
+        alpha_pow: EF,
+        values: &[EF],
+        conjugate_values: &[EF],
+    ) -> Self {
+        // `L(z) = v` and `L(z̄) = v̄` give `a = (v + v̄) / 2` and `b = (v - v̄) / 2 z.y`.
+        let half = EF::two().inverse();
+        let inverse_double_y = point.y.double().inverse();
+        let (constant, slope) = izip!(alpha_pows, values, conjugate_values).fold(
+            (EF::zero(), EF::zero()),
+            |(constant, slope), (&alpha_pow, &value, &conjugate_value)| {
+                (
+                    constant + alpha_pow * (value + conjugate_value) * half,
+                    slope + alpha_pow * (value - conjugate_value) * inverse_double_y,
+                )
+            },
+        );
+        Self {
+            x: point.x,
+            alpha_pow,
+            constant,
+            slope,
+        }
+    }
+
+    /// The combined quotient at a point with y-coordinate `y`, given `sum_i alpha^i p_i` there and
+    /// the inverse of the denominator.
+    fn quotient<F: Field>(&self, row_sum: EF, y: F, inverse_denominator: EF) -> EF
+    where
+        EF: ExtensionField<F>,
+    {
+        self.alpha_pow * inverse_denominator * (row_sum - self.constant - self.slope * y)
+    }
+}
+
+impl<Val, Challenge, InputMmcs, FriMmcs, In> Pcs<Val, In>
+    for CirclePcs<Val, Challenge, InputMmcs, FriMmcs>
+where
+    Val: ComplexExtendable,
+    Challenge: ExtensionField<Val>,
+    InputMmcs: DirectMmcs<Val>,
+    FriMmcs: Mmcs<Challenge>,
+    In: MatrixRows<Val>,
+{
+    type Commitment = InputMmcs::Commitment;
+    type ProverData = InputMmcs::ProverData;
+    type Proof = CirclePcsProof<Val, Challenge, InputMmcs, FriMmcs>;
+    type Error = CirclePcsError<InputMmcs::Error, FriMmcs::Error>;
+
+    fn commit_batches(&self, polynomials: Vec<In>) -> (Self::Commitment, Self::ProverData) {
+        let ldes = polynomials
+            .into_iter()
+            .map(|polynomials| {
+                let evals = polynomials.to_row_major_matrix();
+                let log_n = log2_strict_usize(evals.height());
+                let lde =
+                    CircleEvaluations::from_cyclic_order(CircleDomain::standard(log_n), evals)
+                        .extrapolate(CircleDomain::standard(log_n + self.fri.log_blowup))
+                        .to_bit_reversed_order();
+                // Each point is next to its conjugate, so we put them in one row, for queries to
+                // open both.
+                let width = lde.width();
+                RowMajorMatrix::new(lde.values, 2 * width)
+            })
+            .collect();
+        self.mmcs.commit(ldes)
+    }
+
+    fn num_queries(&self) -> usize {
+        self.fri.num_queries
+    }
+
+    fn shape_params(&self) -> Vec<usize> {
+        vec![
+            self.fri.log_final_poly_len,
+            self.fri.proof_of_work_bits,
+            self.mmcs.cap_height(),
+            self.fri.mmcs.cap_height(),
+        ]
+    }
+
+    fn proof_size(&self, proof: &Self::Proof) -> ProofSize {
+        let mut size = ProofSize {
+            merkle_paths: encoded_len(&proof.commit_phase_opening_proofs),
+            commit_phase_openings: encoded_len(&proof.commit_phase_siblings),
+            opened_values: encoded_len(&proof.conjugate_values),
+            other: 0,
+        };
+        for batch_opening in &proof.batch_openings {
+            size.opened_values += encoded_len(&batch_opening.opened_values);
+            size.merkle_paths += encoded_len(&batch_opening.opening_proof);
+        }
+        size.other = encoded_len(proof) - size.total();
+        size
+    }
+}
+
+impl<Val, Challenge, InputMmcs, FriMmcs, In, Challenger>
+    UnivariatePcs<Val, Challenge, In, Challenger> for CirclePcs<Val, Challenge, InputMmcs, FriMmcs>
+where
+    Val: ComplexExtendable,
+    Challenge: ExtensionField<Val>,
+    InputMmcs: 'static + for<'a> DirectMmcs<Val, Mat<'a> = RowMajorMatrixView<'a, Val>>,
+    FriMmcs: DirectMmcs<Challenge>,
+    In: MatrixRows<Val>,
+    Challenger:
+        FieldChallenger<Val> + GrindingChallenger<Witness = Val> + CanObserve<FriMmcs::Commitment>,
+{
+    /// Panics if a point is `i` or `-i`, which no point of the circle corresponds to.
+    fn open_multi_batches(
+        &self,
+        prover_data_and_points: &[(&Self::ProverData, &[Vec<Challenge>])],
+        challenger: &mut Challenger,
+    ) -> (OpenedValues<Challenge>, Self::Proof) {
+        let points = prover_data_and_points
+            .iter()
+            .map(|(_, points)| {
+                points
+                    .iter()
+                    .map(|points_for_mat| {
+                        points_for_mat
+                            .iter()
+                            .map(|&t| {
+                                CirclePoint::from_projective_line(t)
+                                    .expect("an opening point isn't on the projective line")
+                            })
+                            .collect_vec()
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+        let prover_data_and_points = izip!(prover_data_and_points, &points)
+            .map(|(&(data, _), points)| (data, points.as_slice()))
+            .collect_vec();
+        Self::open_multi_batches(self, &prover_data_and_points, challenger)
+    }
+
+    fn verify_multi_batches(
+        &self,
+        commits_and_points: &[(Self::Commitment, &[Vec<Challenge>])],
+        dims: &[Vec<Dimensions>],
+        values: OpenedValues<Challenge>,
+        proof: &Self::Proof,
+        challenger: &mut Challenger,
+    ) -> Result<(), Self::Error> {
+        let points = commits_and_points
+            .iter()
+            .map(|(_, points)| {
+                points
+                    .iter()
+                    .map(|points_for_mat| {
+                        points_for_mat
+                            .iter()
+                            .map(|&t| {
+                                CirclePoint::from_projective_line(t)
+                                    .ok_or(CirclePcsError::InvalidOpeningPoint)
+                            })
+                            .collect::<Result<Vec<_>, _>>()
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let commits_and_points = izip!(commits_and_points, &points)
+            .map(|((commit, _), points)| (commit.clone(), points.as_slice()))
+            .collect_vec();
+        Self::verify_multi_batches(self, &commits_and_points, dims, values, proof, challenger)
+    }
+}
+
+impl<Val, Challenge, InputMmcs, FriMmcs> CirclePcs<Val, Challenge, InputMmcs, FriMmcs>
+where
+    Val: ComplexExtendable,
+    Challenge: ExtensionField<Val>,
+    InputMmcs: 'static + for<'a> DirectMmcs<Val, Mat<'a> = RowMajorMatrixView<'a, Val>>,
+    FriMmcs: DirectMmcs<Challenge>,
+{
+    /// The coefficients of the polynomials whose LDE is a committed matrix.
+    pub(crate) fn coefficients(&self, lde: &RowMajorMatrixView<'_, Val>) -> RowMajorMatrix<Val> {
+        let log_lde_height = log2_strict_usize(lde.height()) + 1;
+        let lde = RowMajorMatrix::new(lde.values.to_vec(), lde.width() / 2);
+        let mut coeffs =
+            CircleEvaluations::from_bit_reversed_order(CircleDomain::standard(log_lde_height), lde)
+                .interpolate();
+        // The LDE's higher coefficients are zero.
+        coeffs
+            .values
+            .truncate(coeffs.values.len() >> self.fri.log_blowup);
+        coeffs
+    }
+
+    /// Open each batch of committed matrices at points of the circle, which are given for each
+    /// matrix.
+    ///
+    /// Panics if a point is its own conjugate, or shares its x-coordinate with a point of a
+    /// committed LDE.
+    #[allow(clippy::type_complexity)]
+    #[instrument(name = "open_multi_batches", skip_all)]
+    pub fn open_multi_batches<Challenger>(
+        &self,
+        prover_data_and_points: &[(&InputMmcs::ProverData, &[Vec<CirclePoint<Challenge>>])],
+        challenger: &mut Challenger,
+    ) -> (
+        OpenedValues<Challenge>,
+        CirclePcsProof<Val, Challenge, InputMmcs, FriMmcs>,
+    )
+    where
+        Challenger: FieldChallenger<Val>
+            + GrindingChallenger<Witness = Val>
+            + CanObserve<FriMmcs::Commitment>,
+    {
+        let mut opened_values: OpenedValues<Challenge> = vec![];
+        let mut conjugate_values: OpenedValues<Challenge> = vec![];
+        info_span!("compute opened values").in_scope(|| {
+            for (data, points) in prover_data_and_points {
+                let opened_values_for_round = opened_values.pushed_mut(vec![]);
+                let conjugate_values_for_round = conjugate_values.pushed_mut(vec![]);
+                for (mat, points_for_mat) in izip!(self.mmcs.get_matrices(data), *points) {
+                    let coeffs = self.coefficients(&mat);
+                    let (values, conjugates): (Vec<_>, Vec<_>) = points_for_mat
+                        .iter()
+                        .map(|&point| {
+                            (
+                                evaluate_coeffs_at_point(&coeffs, point),
+                                evaluate_coeffs_at_point(&coeffs, point.conjugate()),
+                            )
+                        })
+                        .unzip();
+                    opened_values_for_round.push(values);
+                    conjugate_values_for_round.push(conjugates);
+                }
+            }
+        });
+        for &value in opened_values
+            .iter()
+            .chain(&conjugate_values)
+            .flatten()
+            .flatten()
+            .flatten()
+        {
+            challenger.observe_ext_element(value);
+        }
+
+        // Batch combination challenge
+        let alpha: Challenge = challenger.sample_ext_element();
+
+        // For each log height, the combination of the quotients of all matrices of that height, in
+        // bit-reversed order.
+        let mut reduced_openings: [Option<Vec<Challenge>>; MAX_LOG_HEIGHT] =
+            core::array::from_fn(|_| None);
+        let mut num_reduced = [0; MAX_LOG_HEIGHT];
+        for ((data, points), values_for_round, conjugates_for_round) in
+            izip!(prover_data_and_points, &opened_values, &conjugate_values)
+        {
+            for (mat, points_for_mat, values_for_mat, conjugates_for_mat) in izip!(
+                self.mmcs.get_matrices(data),
+                *points,
+                values_for_round,
+                conjugates_for_round
+            ) {
+                let _guard =
+                    info_span!("reduce matrix quotients", dims = %mat.dimensions()).entered();
+                let width = mat.width() / 2;
+                let log_height = log2_strict_usize(mat.height()) + 1;
+                let reduced_opening = reduced_openings[log_height]
+                    .get_or_insert_with(|| vec![Challenge::zero(); 1 << log_height]);
+                let alpha_pows = alpha.powers().take(width).collect_vec();
+                // Each row holds the evaluations at a pair of conjugates, which share an
+                // x-coordinate and have opposite y-coordinates.
+                let (xs, ys): (Vec<_>, Vec<_>) = folded_points::<Val>(log_height - 1)
+                    .into_iter()
+                    .map(|point| (point.x, point.y))
+                    .unzip();
+
+                for (&point, values, conjugates) in
+                    izip!(points_for_mat, values_for_mat, conjugates_for_mat)
+                {
+                    assert!(!point.y.is_zero(), "an opening point is its own conjugate");
+                    let line = ReducedLine::new(
+                        point,
+                        &alpha_pows,
+                        alpha.exp_u64(num_reduced[log_height] as u64),
+                        values,
+                        conjugates,
+                    );
+                    let denominators = xs
+                        .iter()
+                        .map(|&x| Challenge::from_base(x) - point.x)
+                        .collect_vec();
+                    assert!(
+                        denominators.iter().all(|d| !d.is_zero()),
+                        "an opening point shares its x-coordinate with a point of the LDE"
+                    );
+                    let inverse_denominators = batch_multiplicative_inverse(&denominators);
+
+                    for (reduced_pair, row, &y, &inverse_denominator) in izip!(
+                        reduced_opening.chunks_exact_mut(2),
+                        mat.values.chunks_exact(2 * width),
+                        &ys,
+                        &inverse_denominators
+                    ) {
+                        let (evals, conjugate_evals) = row.split_at(width);
+                        let row_sum = |evals: &[Val]| -> Challenge {
+                            izip!(&alpha_pows, evals).map(|(&a, &e)| a * e).sum()
+                        };
+                        reduced_pair[0] += line.quotient(row_sum(evals), y, inverse_denominator);
+                        reduced_pair[1] +=
+                            line.quotient(row_sum(conjugate_evals), -y, inverse_denominator);
+                    }
+                    num_reduced[log_height] += width;
+                }
+            }
+        }
+
+        // Fold each point's combined quotient with its conjugate's, leaving polynomials in `x`.
+        let lambda: Challenge = challenger.sample_ext_element();
+        let mut fri_inputs: [Option<Vec<Challenge>>; MAX_LOG_HEIGHT] =
+            core::array::from_fn(|_| None);
+        for (log_height, reduced_opening) in reduced_openings.into_iter().enumerate() {
+            if let Some(reduced_opening) = reduced_opening {
+                let ys = folded_points::<Val>(log_height - 1)
+                    .into_iter()
+                    .map(|point| point.y)
+                    .collect_vec();
+                fri_inputs[log_height - 1] = Some(fold(&reduced_opening, &ys, lambda));
+            }
+        }
+
+        let input_log_heights = (0..MAX_LOG_HEIGHT)
+            .filter(|&log_height| fri_inputs[log_height].is_some())
+            .collect_vec();
+        let log_max_height = *input_log_heights.last().expect("nothing to open");
+        let log_heights = self
+            .fri
+            .commit_phase_log_heights(input_log_heights.iter().copied());
+        let log_final_height = self.fri.log_final_height(input_log_heights.iter().copied());
+
+        let mut current = fri_inputs[log_max_height].clone().unwrap();
+        let mut commit_phase_commits = vec![];
+        let mut commit_phase_data = vec![];
+        info_span!("commit phase").in_scope(|| {
+            for &log_height in &log_heights {
+                // Each pair to be folded together is a row.
+                let (commit, data) = self
+                    .fri
+                    .mmcs
+                    .commit_matrix(RowMajorMatrix::new(current.clone(), 2));
+                challenger.observe(commit.clone());
+                commit_phase_commits.push(commit);
+                commit_phase_data.push(data);
+
+                let beta: Challenge = challenger.sample_ext_element();
+                current = fold(&current, &fold_x_twiddles(log_height), beta);
+                if let Some(input) = &fri_inputs[log_height - 1] {
+                    current.iter_mut().zip_eq(input).for_each(|(c, &v)| *c += v);
+                }
+            }
+        });
+
+        let final_poly = folded_codeword_coefficients::<Val, _>(
+            &current,
+            log_final_height - self.fri.log_blowup,
+        );
+        for &coeff in &final_poly {
+            challenger.observe_ext_element(coeff);
+        }
+
+        let pow_witness = challenger.grind(self.fri.proof_of_work_bits);
+
+        let query_indices = (0..self.fri.num_queries)
+            .map(|_| challenger.sample_bits(log_max_height))
+            .collect_vec();
+
+        let (commit_phase_siblings, commit_phase_opening_proofs) = info_span!("query phase")
+            .in_scope(|| {
+                izip!(&log_heights, &commit_phase_data)
+                    .map(|(&log_height, data)| {
+                        let indices = query_indices
+                            .iter()
+                            .map(|&index| index >> (log_max_height - log_height))
+                            .collect_vec();
+                        let rows = indices.iter().map(|&index| index >> 1).collect_vec();
+                        let (opened_rows, opening_proof) =
+                            self.fri.mmcs.open_multi_batch(&rows, data);
+                        let siblings = izip!(indices, opened_rows)
+                            .map(|(index, opened_rows)| opened_rows[0][(index & 1) ^ 1])
+                            .collect_vec();
+                        (siblings, opening_proof)
+                    })
+                    .unzip()
+            });
+
+        let batch_openings = prover_data_and_points
+            .iter()
+            .map(|(data, _)| {
+                let log_batch_height = log2_strict_usize(self.mmcs.get_max_height(data));
+                let indices = query_indices
+                    .iter()
+                    .map(|&index| index >> (log_max_height - log_batch_height))
+                    .collect_vec();
+                let (opened_values, opening_proof) = self.mmcs.open_multi_batch(&indices, data);
+                CircleBatchOpening {
+                    opened_values,
+                    opening_proof,
+                }
+            })
+            .collect();
+
+        (
+            opened_values,
+            CirclePcsProof {
+                conjugate_values,
+                commit_phase_commits,
+                commit_phase_siblings,
+                commit_phase_opening_proofs,
+                final_poly,
+                pow_witness,
+                batch_openings,
+            },
+        )
+    }
+
+    /// Verify openings of each batch of committed matrices at points of the circle, given the
+    /// dimensions of the committed matrices, not of their LDEs.
+    #[allow(clippy::type_complexity)]
+    pub fn verify_multi_batches<Challenger>(
+        &self,
+        commits_and_points: &[(InputMmcs::Commitment, &[Vec<CirclePoint<Challenge>>])],
+        dims: &[Vec<Dimensions>],
+        values: OpenedValues<Challenge>,
+        proof: &CirclePcsProof<Val, Challenge, InputMmcs, FriMmcs>,
+        challenger: &mut Challenger,
+    ) -> Result<(), CirclePcsError<InputMmcs::Error, FriMmcs::Error>>
+    where
+        Challenger: FieldChallenger<Val>
+            + GrindingChallenger<Witness = Val>
+            + CanObserve<FriMmcs::Commitment>,
+    {
+        let log_blowup = self.fri.log_blowup;
+
+        // Each batch must be nonempty, with values at each point and its conjugate for each column
+        // of each matrix, and each matrix's LDE must be a circle domain.
+        let valid_mat =
+            |mat_dims: &Dimensions, num_points: usize, mat_values: &[Vec<Challenge>]| {
+                mat_dims.height.is_power_of_two()
+                    && mat_dims.height > 1
+                    && log2_strict_usize(mat_dims.height) + log_blowup < Val::CIRCLE_TWO_ADICITY
+                    && mat_values.len() == num_points
+                    && mat_values
+                        .iter()
+                        .all(|values| values.len() == mat_dims.width)
+            };
+        let valid_shape = dims.len() == commits_and_points.len()
+            && values.len() == commits_and_points.len()
+            && proof.conjugate_values.len() == commits_and_points.len()
+            && izip!(dims, commits_and_points, &values, &proof.conjugate_values).all(
+                |(batch_dims, (_, batch_points), batch_values, batch_conjugates)| {
+                    !batch_dims.is_empty()
+                        && batch_points.len() == batch_dims.len()
+                        && batch_values.len() == batch_dims.len()
+                        && batch_conjugates.len() == batch_dims.len()
+                        && izip!(batch_dims, *batch_points, batch_values, batch_conjugates).all(
+                            |(d, points, values, conjugates)| {
+                                valid_mat(d, points.len(), values)
+                                    && valid_mat(d, points.len(), conjugates)
+                            },
+                        )
+                },
+            );
+        if !valid_shape {
+            return Err(CirclePcsError::InvalidProofShape);
+        }
+        if commits_and_points
+            .iter()
+            .flat_map(|(_, points)| points.iter().flatten())
+            .any(|point| {
+                point.x.square() + point.y.square() != Challenge::one() || point.y.is_zero()
+            })
+        {
+            return Err(CirclePcsError::InvalidOpeningPoint);
+        }
+
+        for &value in values
+            .iter()
+            .chain(&proof.conjugate_values)
+            .flatten()
+            .flatten()
+            .flatten()
+        {
+            challenger.observe_ext_element(value);
+        }
+
+        // Batch combination challenge
+        let alpha: Challenge = challenger.sample_ext_element();
+
+        // For each matrix, the powers of alpha up to its width, and the lines of each point.
+        let mut num_reduced = [0; MAX_LOG_HEIGHT];
+        let mut reduced_lines = vec![];
+        for (batch_dims, (_, batch_points), batch_values, batch_conjugates) in
+            izip!(dims, commits_and_points, &values, &proof.conjugate_values)
+        {
+            let lines_for_batch = reduced_lines.pushed_mut(vec![]);
+            for (mat_dims, mat_points, mat_values, mat_conjugates) in
+                izip!(batch_dims, *batch_points, batch_values, batch_conjugates)
+            {
+                let log_height = log2_strict_usize(mat_dims.height) + log_blowup;
+                let alpha_pows = alpha.powers().take(mat_dims.width).collect_vec();
+                let lines = izip!(mat_points, mat_values, mat_conjugates)
+                    .map(|(&point, values, conjugates)| {
+                        let alpha_pow = alpha.exp_u64(num_reduced[log_height] as u64);
+                        num_reduced[log_height] += mat_dims.width;
+                        ReducedLine::new(point, &alpha_pows, alpha_pow, values, conjugates)
+                    })
+                    .collect_vec();
+                lines_for_batch.push((alpha_pows, lines));
+            }
+        }
+
+        let lambda: Challenge = challenger.sample_ext_element();
+
+        // The log heights of the folded inputs to FRI, which are half those of the LDEs.
+        let input_log_heights = dims
+            .iter()
+            .flatten()
+            .map(|mat_dims| log2_strict_usize(mat_dims.height) + log_blowup - 1)
+            .sorted()
+            .dedup()
+            .collect_vec();
+        let Some(&log_max_height) = input_log_heights.last() else {
+            return Err(CirclePcsError::InvalidProofShape);
+        };
+        let log_heights = self
+            .fri
+            .commit_phase_log_heights(input_log_heights.iter().copied());
+        let log_final_height = self.fri.log_final_height(input_log_heights.iter().copied());
+        if proof.commit_phase_commits.len() != log_heights.len()
+            || proof.commit_phase_siblings.len() != log_heights.len()
+            || proof.commit_phase_opening_proofs.len() != log_heights.len()
+            || proof.final_poly.len() != 1 << (log_final_height - log_blowup)
+            || proof.batch_openings.len() != commits_and_points.len()
+        {
+            return Err(CirclePcsError::InvalidProofShape);
+        }
+
+        let betas: Vec<Challenge> = proof
+            .commit_phase_commits
+            .iter()
+            .map(|commit| {
+                challenger.observe(commit.clone());
+                challenger.sample_ext_element()
+            })
+            .collect();
+        for &coeff in &proof.final_poly {
+            challenger.observe_ext_element(coeff);
+        }
+
+        if !challenger.check_witness(self.fri.proof_of_work_bits, proof.pow_witness) {
+            return Err(CirclePcsError::InvalidPowWitness);
+        }
+
+        let query_indices = (0..self.fri.num_queries)
+            .map(|_| challenger.sample_bits(log_max_height))
+            .collect_vec();
+        let num_queries = query_indices.len();
+        if proof
+            .commit_phase_siblings
+            .iter()
+            .any(|siblings| siblings.len() != num_queries)
+            || proof
+                .batch_openings
+                .iter()
+                .any(|batch_opening| batch_opening.opened_values.len() != num_queries)
+        {
+            return Err(CirclePcsError::InvalidProofShape);
+        }
+
+        // For each query, and each log height of the folded inputs, the combined quotients at the
+        // queried point of the LDEs and at its conjugate.
+        let mut reduced_openings =
+            vec![[(Challenge::zero(), Challenge::zero()); MAX_LOG_HEIGHT]; num_queries];
+        for (batch_opening, batch_dims, (batch_commit, _), batch_lines) in izip!(
+            &proof.batch_openings,
+            dims,
+            commits_and_points,
+            &reduced_lines
+        ) {
+            // Each row of a committed LDE holds the evaluations at a pair of conjugates.
+            let batch_lde_dims = batch_dims
+                .iter()
+                .map(|mat_dims| Dimensions {
+                    width: 2 * mat_dims.width,
+                    height: (mat_dims.height << log_blowup) >> 1,
+                })
+                .collect_vec();
+            let log_batch_height = batch_lde_dims
+                .iter()
+                .map(|mat_dims| log2_strict_usize(mat_dims.height))
+                .max()
+                .unwrap_or(0);
+            let indices = query_indices
+                .iter()
+                .map(|&index| index >> (log_max_height - log_batch_height))
+                .collect_vec();
+            self.mmcs
+                .verify_multi_batch(
+                    batch_commit,
+                    &batch_lde_dims,
+                    &indices,
+                    &batch_opening.opened_values,
+                    &batch_opening.opening_proof,
+                )
+                .map_err(CirclePcsError::InputMmcsError)?;
+
+            for (reduced_opening, &index, query_opening) in izip!(
+                &mut reduced_openings,
+                &query_indices,
+                &batch_opening.opened_values
+            ) {
+                if query_opening.len() != batch_lde_dims.len() {
+                    return Err(CirclePcsError::InvalidProofShape);
+                }
+                for (row, mat_lde_dims, (alpha_pows, lines)) in
+                    izip!(query_opening, &batch_lde_dims, batch_lines)
+                {
+                    if row.len() != mat_lde_dims.width {
+                        return Err(CirclePcsError::InvalidProofShape);
+                    }
+                    let log_height = log2_strict_usize(mat_lde_dims.height);
+                    let point =
+                        folded_point::<Val>(index >> (log_max_height - log_height), log_height);
+                    let (evals, conjugate_evals) = row.split_at(row.len() / 2);
+                    let row_sum = |evals: &[Val]| -> Challenge {
+                        izip!(alpha_pows, evals).map(|(&a, &e)| a * e).sum()
+                    };
+                    let (row_sum, conjugate_row_sum) = (row_sum(evals), row_sum(conjugate_evals));
+                    for line in lines {
+                        let inverse_denominator = (Challenge::from_base(point.x) - line.x)
+                            .try_inverse()
+                            .ok_or(CirclePcsError::InvalidOpeningPoint)?;
+                        let (reduced, conjugate_reduced) = &mut reduced_opening[log_height];
+                        *reduced += line.quotient(row_sum, point.y, inverse_denominator);
+                        *conjugate_reduced +=
+                            line.quotient(conjugate_row_sum, -point.y, inverse_denominator);
+                    }
+                }
+            }
+        }
+
+        // The input to FRI of each log height at a query: the combined quotients folded together.
+        let folded_input = |reduced_opening: &[(Challenge, Challenge); MAX_LOG_HEIGHT],
+                            index: usize,
+                            log_height: usize| {
+            let (reduced, conjugate_reduced) = reduced_opening[log_height];
+            let point = folded_point::<Val>(index >> (log_max_height - log_height), log_height);
+            fold_pair(reduced, conjugate_reduced, point.y, lambda)
+        };
+
+        let mut folded_evals = izip!(&reduced_openings, &query_indices)
+            .map(|(reduced_opening, &index)| folded_input(reduced_opening, index, log_max_height))
+            .collect_vec();
+        for (&log_height, commit, &beta, siblings, opening_proof) in izip!(
+            &log_heights,
+            &proof.commit_phase_commits,
+            &betas,
+            &proof.commit_phase_siblings,
+            &proof.commit_phase_opening_proofs
+        ) {
+            let indices = query_indices
+                .iter()
+                .map(|&index| index >> (log_max_height - log_height))
+                .collect_vec();
+            let pairs = izip!(&indices, &folded_evals, siblings)
+                .map(|(&index, &eval, &sibling)| {
+                    if index & 1 == 0 {
+                        vec![eval, sibling]
+                    } else {
+                        vec![sibling, eval]
+                    }
+                })
+                .collect_vec();
+            self.fri
+                .mmcs
+                .verify_multi_batch(
+                    commit,
+                    &[Dimensions {
+                        width: 2,
+                        height: 1 << (log_height - 1),
+                    }],
+                    &indices.iter().map(|&index| index >> 1).collect_vec(),
+                    &pairs.iter().map(|pair| vec![pair.clone()]).collect_vec(),
+                    opening_proof,
+                )
+                .map_err(CirclePcsError::CommitPhaseMmcsError)?;
+
+            for (folded_eval, pair, &index, reduced_opening, &query_index) in izip!(
+                &mut folded_evals,
+                &pairs,
+                &indices,
+                &reduced_openings,
+                &query_indices
+            ) {
+                let x = folded_point::<Val>(index & !1, log_height).x;
+                *folded_eval = fold_pair(pair[0], pair[1], x, beta);
+                if input_log_heights.contains(&(log_height - 1)) {
+                    *folded_eval += folded_input(reduced_opening, query_index, log_height - 1);
+                }
+            }
+        }
+
+        for (&folded_eval, &index) in izip!(&folded_evals, &query_indices) {
+            let x = folded_point::<Val>(
+                index >> (log_max_height - log_final_height),
+                log_final_height,
+            )
+            .x;
+            if evaluate_folded_poly(&proof.final_poly, x) != folded_eval {
+                return Err(CirclePcsError::FinalPolyMismatch);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use core::ops::Mul;
+
+use p3_field::extension::{Complex, ComplexExtendable};
+use p3_field::{AbstractField, ExtensionField, Field};
+
+/// A point `(x, y)` of the circle `x^2 + y^2 = 1`.
+///
+/// The points form a group under `(x_1, y_1) * (x_2, y_2) = (x_1 x_2 - y_1 y_2, x_1 y_2 + x_2 y_1)`,
+/// which is multiplication of complex numbers of norm one. The coordinates may lie in an extension
+/// of the field that the circle's domains are defined over, so that it can be sampled outside them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CirclePoint<F> {
+    pub x: F,
+    pub y: F,
+}
+
+impl<F: ComplexExtendable> CirclePoint<F> {
+    /// A generator of the subgroup of order `2^bits`.
+    pub fn generator(bits: usize) -> Self {
+        Self::from_complex(F::circle_two_adic_generator(bits))
+    }
+
+    pub fn from_complex(c: Complex<F>) -> Self {
+        Self {
+            x: c.real(),
+            y: c.imag(),
+        }
+    }
+}
+
+impl<F: Field> CirclePoint<F> {
+    pub fn identity() -> Self {
+        Self {
+            x: F::one(),
+            y: F::zero(),
+        }
+    }
+
+    /// The point of the circle with stereographic coordinate `t`, i.e. the second intersection of
+    /// the circle with the line through `(-1, 0)` of slope `t`.
+    ///
+    /// Every point but `(-1, 0)` has such a coordinate, so this maps a uniformly sampled `t` to a
+    /// nearly uniform point. Returns `None` if `1 + t^2 = 0`.
+    pub fn from_projective_line(t: F) -> Option<Self> {
+        let denominator = (F::one() + t.square()).try_inverse()?;
+        Some(Self {
+            x: (F::one() - t.square()) * denominator,
+            y: t.double() * denominator,
+        })
+    }
+
+    /// The inverse of `from_projective_line`, i.e. the slope `y / (1 + x)` of the line through
+    /// `(-1, 0)` and the point. Returns `None` for `(-1, 0)` itself.
+    pub fn to_projective_line(&self) -> Option<F> {
+        Some(self.y * (F::one() + self.x).try_inverse()?)
+    }
+
+    /// The inverse of the point, which is its reflection in the x-axis.
+    pub fn conjugate(&self) -> Self {
+        Self {
+            x: self.x,
+            y: -self.y,
+        }
+    }
+
+    pub fn double(&self) -> Self {
+        *self * *self
+    }
+
+    pub fn exp_power_of_2(&self, power_log: usize) -> Self {
+        (0..power_log).fold(*self, |point, _| point.double())
+    }
+
+    pub fn exp_u64(&self, power: u64) -> Self {
+        let mut result = Self::identity();
+        let mut base = *self;
+        let mut power = power;
+        while power > 0 {
+            if power & 1 == 1 {
+                result = result * base;
+            }
+            base = base.double();
+            power >>= 1;
+        }
+        result
+    }
+
+    pub fn to_extension<EF: ExtensionField<F>>(&self) -> CirclePoint<EF> {
+        CirclePoint {
+            x: EF::from_base(self.x),
+            y: EF::from_base(self.y),
+        }
+    }
+}
+
+impl<F: Field> Mul for CirclePoint<F> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            x: self.x * rhs.x - self.y * rhs.y,
+            y: self.x * rhs.y + self.y * rhs.x,
+        }
+    }
+}
+
+/// Given the x-coordinate of a point, the x-coordinate of its double, `2x^2 - 1`.
+pub fn double_x<AF: AbstractField>(x: AF) -> AF {
+    x.square().double() - AF::one()
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+    use p3_mersenne_31::Mersenne31;
+
+    use super::*;
+
+    type F = Mersenne31;
+
+    #[test]
+    fn test_generator_order() {
+        for bits in [1, 2, 5, 31] {
+            let g = CirclePoint::<F>::generator(bits);
+            assert_eq!(g.x.square() + g.y.square(), F::one());
+            assert_eq!(g.exp_power_of_2(bits), CirclePoint::identity());
+            assert_ne!(g.exp_power_of_2(bits - 1), CirclePoint::identity());
+        }
+        assert_eq!(
+            CirclePoint::<F>::generator(1),
+            CirclePoint {
+                x: F::neg_one(),
+                y: F::zero()
+            }
+        );
+    }
+
+    #[test]
+    fn test_group_operations() {
+        let g = CirclePoint::<F>::generator(10);
+        assert_eq!(g * g.conjugate(), CirclePoint::identity());
+        assert_eq!(g.exp_u64(5), g * g * g * g * g);
+        assert_eq!(g.double().x, double_x(g.x));
+    }
+
+    #[test]
+    fn test_projective_line() {
+        for t in [F::zero(), F::one(), F::from_canonical_u32(1234)] {
+            let point = CirclePoint::from_projective_line(t).unwrap();
+            assert_eq!(point.x.square() + point.y.square(), F::one());
+        }
+        assert_eq!(
+            CirclePoint::from_projective_line(F::zero()),
+            Some(CirclePoint::identity())
+        );
+        for t in [F::zero(), F::one(), F::from_canonical_u32(1234)] {
+            let point = CirclePoint::from_projective_line(t).unwrap();
+            assert_eq!(point.to_projective_line(), Some(t));
+        }
+        assert_eq!(CirclePoint::<F>::generator(1).to_projective_line(), None);
+    }
+}
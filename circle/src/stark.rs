@@ -0,0 +1,321 @@
+//! The domains of `CirclePcs`, as seen by a univariate STARK.
+//!
+//! The trace domain of height `n` is the standard position coset of that size, in cyclic order, so
+//! a point's next point is its product with the generator `g` of the subgroup of order `n`. Points
+//! outside it are encoded by their coordinates on the projective line.
+//!
+//! The trace's polynomials have degree at most `n / 2`, and a constraint of degree `d` has a
+//! quotient of degree at most `(d - 1) n / 2`. So the quotient domain is the standard position
+//! coset of size `2^(log_quotient_degree + 1) n`, over which the quotient is determined by its
+//! evaluations, and the quotient is committed as is, without being split into chunks.
+//!
+//! Zero-knowledge mode isn't supported.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::{izip, Itertools};
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::{BlindingRng, DirectMmcs, LagrangeSelectors, Pcs, StarkPcs};
+use p3_field::extension::ComplexExtendable;
+use p3_field::{batch_multiplicative_inverse, ExtensionField};
+use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
+use p3_matrix::{Dimensions, Matrix};
+use p3_util::log2_strict_usize;
+
+use crate::{CircleDomain, CircleEvaluations, CirclePcs, CirclePoint};
+
+const ZK_UNSUPPORTED: &str = "CirclePcs doesn't support zero-knowledge proofs";
+
+/// The point of the circle with the given coordinate on the projective line.
+fn circle_point<F: ComplexExtendable, EF: ExtensionField<F>>(t: EF) -> CirclePoint<EF> {
+    CirclePoint::from_projective_line(t).expect("a point isn't on the projective line")
+}
+
+/// The selectors of the trace domain of height `2^log_height` at points outside it.
+///
+/// With `h = P * conj(Q)`, the line `h.y = 0` through `Q` and `-Q` divides the vanishing
+/// polynomial `v` of the trace domain, and `h.x - 1`, the tangent at `Q`, vanishes only at `Q`.
+/// So `v h.y / (1 - h.x) = v (1 + h.x) / h.y` selects `Q`, and products of tangents at the last
+/// rows select all the others.
+fn selectors<F, K>(
+    log_height: usize,
+    points: &[CirclePoint<K>],
+    window_size: usize,
+) -> LagrangeSelectors<Vec<K>>
+where
+    F: ComplexExtendable,
+    K: ExtensionField<F>,
+{
+    let domain = CircleDomain::<F>::standard(log_height);
+    let first = domain.shift();
+    let last = first.conjugate();
+    let g_inv = CirclePoint::<F>::generator(log_height).conjugate();
+    let relative = |point: CirclePoint<K>, q: CirclePoint<F>| point * q.conjugate().to_extension();
+
+    let zerofier = points
+        .iter()
+        .map(|&point| domain.vanishing_poly(point))
+        .collect_vec();
+    let row_selector = |q: CirclePoint<F>| {
+        let relatives = points.iter().map(|&point| relative(point, q)).collect_vec();
+        let denominators = relatives.iter().map(|h| K::one() - h.x).collect_vec();
+        izip!(
+            &zerofier,
+            &relatives,
+            batch_multiplicative_inverse(&denominators)
+        )
+        .map(|(&v, h, inverse_denominator)| v * h.y * inverse_denominator)
+        .collect_vec()
+    };
+
+    let mut is_transition = vec![vec![K::one(); points.len()]];
+    let mut row = first;
+    for _ in 1..window_size {
+        row = row * g_inv;
+        let mut selector = is_transition.last().unwrap().clone();
+        for (s, &point) in selector.iter_mut().zip(points) {
+            *s *= relative(point, row).x - K::one();
+        }
+        is_transition.push(selector);
+    }
+
+    LagrangeSelectors {
+        is_first_row: row_selector(first),
+        is_last_row: row_selector(last),
+        is_transition,
+        inv_zerofier: batch_multiplicative_inverse(&zerofier),
+    }
+}
+
+impl<Val, Challenge, InputMmcs, FriMmcs, Challenger> StarkPcs<Val, Challenge, Challenger>
+    for CirclePcs<Val, Challenge, InputMmcs, FriMmcs>
+where
+    Val: ComplexExtendable,
+    Challenge: ExtensionField<Val>,
+    InputMmcs: 'static + for<'a> DirectMmcs<Val, Mat<'a> = RowMajorMatrixView<'a, Val>>,
+    FriMmcs: DirectMmcs<Challenge>,
+    Challenger:
+        FieldChallenger<Val> + GrindingChallenger<Witness = Val> + CanObserve<FriMmcs::Commitment>,
+{
+    type QuotientDomainLde<'a>
+        = RowMajorMatrix<Val>
+    where
+        Self: 'a;
+
+    fn log_blowup(&self) -> usize {
+        self.fri.log_blowup
+    }
+
+    fn domain_params(&self) -> Vec<Val> {
+        vec![]
+    }
+
+    fn max_log_height(&self) -> usize {
+        Val::CIRCLE_TWO_ADICITY - 1
+    }
+
+    fn next_point(&self, log_height: usize, point: Challenge) -> Challenge {
+        let g = CirclePoint::<Val>::generator(log_height).to_extension();
+        (circle_point(point) * g)
+            .to_projective_line()
+            .expect("the next point isn't on the projective line")
+    }
+
+    fn selectors_at_point(
+        &self,
+        log_height: usize,
+        point: Challenge,
+        window_size: usize,
+    ) -> LagrangeSelectors<Challenge> {
+        let LagrangeSelectors {
+            is_first_row,
+            is_last_row,
+            is_transition,
+            inv_zerofier,
+        } = selectors::<Val, _>(log_height, &[circle_point(point)], window_size);
+        LagrangeSelectors {
+            is_first_row: is_first_row[0],
+            is_last_row: is_last_row[0],
+            is_transition: is_transition.into_iter().map(|s| s[0]).collect(),
+            inv_zerofier: inv_zerofier[0],
+        }
+    }
+
+    fn log_quotient_height(&self, log_height: usize, log_quotient_degree: usize) -> usize {
+        log_height + log_quotient_degree + 1
+    }
+
+    fn selectors_on_quotient_domain(
+        &self,
+        log_height: usize,
+        log_quotient_height: usize,
+        window_size: usize,
+    ) -> LagrangeSelectors<Vec<Val>> {
+        let points = CircleDomain::standard(log_quotient_height).cyclic_points();
+        selectors::<Val, Val>(log_height, &points, window_size)
+    }
+
+    fn quotient_domain_ldes<'a, 'b>(
+        &'a self,
+        prover_data: &'b Self::ProverData,
+        log_quotient_height: usize,
+    ) -> Vec<RowMajorMatrix<Val>>
+    where
+        'a: 'b,
+    {
+        self.mmcs
+            .get_matrices(prover_data)
+            .iter()
+            .map(|lde| {
+                CircleEvaluations::evaluate(
+                    CircleDomain::standard(log_quotient_height),
+                    self.coefficients(lde),
+                )
+                .to_cyclic_order()
+            })
+            .collect()
+    }
+
+    fn evaluate_on_quotient_domain(
+        &self,
+        evals: RowMajorMatrix<Val>,
+        log_quotient_height: usize,
+    ) -> RowMajorMatrix<Val> {
+        let domain = CircleDomain::standard(log2_strict_usize(evals.height()));
+        CircleEvaluations::from_cyclic_order(domain, evals)
+            .extrapolate(CircleDomain::standard(log_quotient_height))
+            .to_cyclic_order()
+    }
+
+    fn evaluate_at_point(&self, evals: RowMajorMatrix<Val>, point: Challenge) -> Vec<Challenge> {
+        let domain = CircleDomain::standard(log2_strict_usize(evals.height()));
+        CircleEvaluations::from_cyclic_order(domain, evals).evaluate_at_point(circle_point(point))
+    }
+
+    fn commit_quotient(
+        &self,
+        quotient_values: Vec<Challenge>,
+        _log_height: usize,
+        _log_quotient_degree: usize,
+        blinding_rng: Option<&BlindingRng>,
+    ) -> (
+        <Self as Pcs<Val, RowMajorMatrix<Val>>>::Commitment,
+        <Self as Pcs<Val, RowMajorMatrix<Val>>>::ProverData,
+    ) {
+        assert!(blinding_rng.is_none(), "{ZK_UNSUPPORTED}");
+        let flattened = quotient_values
+            .iter()
+            .flat_map(|value| value.as_base_slice().iter().copied())
+            .collect();
+        <Self as Pcs<Val, RowMajorMatrix<Val>>>::commit_batch(
+            self,
+            RowMajorMatrix::new(flattened, Challenge::D),
+        )
+    }
+
+    fn quotient_dimensions(
+        &self,
+        log_height: usize,
+        log_quotient_degree: usize,
+        is_zk: bool,
+    ) -> Dimensions {
+        assert!(!is_zk, "{ZK_UNSUPPORTED}");
+        Dimensions {
+            width: Challenge::D,
+            height: 1
+                << StarkPcs::<Val, Challenge, Challenger>::log_quotient_height(
+                    self,
+                    log_height,
+                    log_quotient_degree,
+                ),
+        }
+    }
+
+    fn quotient_point(
+        &self,
+        zeta: Challenge,
+        _log_quotient_degree: usize,
+        _is_zk: bool,
+    ) -> Challenge {
+        zeta
+    }
+
+    fn recompose_quotient(
+        &self,
+        parts: &[Challenge],
+        _zeta: Challenge,
+        _log_height: usize,
+        _log_quotient_degree: usize,
+        _is_zk: bool,
+    ) -> Challenge {
+        parts[0]
+    }
+
+    fn blind_trace(
+        &self,
+        _trace: RowMajorMatrix<Val>,
+        _blinding_rng: &BlindingRng,
+    ) -> RowMajorMatrix<Val> {
+        panic!("{ZK_UNSUPPORTED}")
+    }
+
+    fn extend_trace(&self, _trace: RowMajorMatrix<Val>) -> RowMajorMatrix<Val> {
+        panic!("{ZK_UNSUPPORTED}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::extension::Complex;
+    use p3_field::{AbstractField, Field};
+    use p3_mersenne_31::Mersenne31;
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+
+    type F = Mersenne31;
+    type EF = Complex<Mersenne31>;
+
+    #[test]
+    fn test_selectors() {
+        let (log_n, log_quotient_height, window_size) = (3, 5, 3);
+        let n = 1 << log_n;
+        let quotient_points = CircleDomain::<F>::standard(log_quotient_height).cyclic_points();
+        let on_domain = selectors(log_n, &quotient_points, window_size);
+        let point = circle_point::<F, EF>(thread_rng().gen());
+        let at_point = selectors::<F, EF>(log_n, &[point], window_size);
+
+        let mut columns = vec![
+            (on_domain.is_first_row, at_point.is_first_row),
+            (on_domain.is_last_row, at_point.is_last_row),
+        ];
+        columns.extend(izip!(on_domain.is_transition, at_point.is_transition));
+        let trace_points = CircleDomain::<F>::standard(log_n).cyclic_points();
+        for (i, (values, value_at_point)) in columns.into_iter().enumerate() {
+            // The selectors are polynomials of low enough degree to be determined by their values
+            // over the quotient domain.
+            let evals = CircleEvaluations::from_cyclic_order(
+                CircleDomain::standard(log_quotient_height),
+                RowMajorMatrix::new_col(values),
+            );
+            assert_eq!(evals.evaluate_at_point(point), value_at_point);
+
+            // They vanish on the rows they don't select.
+            let selected_rows = match i {
+                0 => vec![0],
+                1 => vec![n - 1],
+                _ => (0..n + 2 - i).collect(),
+            };
+            for (row, &trace_point) in trace_points.iter().enumerate() {
+                let value = evals.evaluate_at_point(trace_point)[0];
+                assert_eq!(value.is_zero(), !selected_rows.contains(&row));
+            }
+        }
+
+        for (&point, inv_zerofier) in izip!(&quotient_points, on_domain.inv_zerofier) {
+            let zerofier = CircleDomain::<F>::standard(log_n).vanishing_poly(point);
+            assert_eq!(zerofier * inv_zerofier, F::one());
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use p3_challenger::{CanObserve, FieldChallenger, SerializingChallenger32};
+use p3_circle::{CircleDomain, CircleEvaluations, CirclePcs, CirclePcsError, CirclePoint};
+use p3_commit::{ExtensionMmcs, OpenedValues, Pcs};
+use p3_field::extension::Complex;
+use p3_field::AbstractField;
+use p3_fri::FriConfig;
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{Dimensions, Matrix};
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_mersenne_31::Mersenne31;
+use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher32};
+use rand::thread_rng;
+
+type Val = Mersenne31;
+type Challenge = Complex<Mersenne31>;
+
+type ByteHash = Keccak256Hash;
+type FieldHash = SerializingHasher32<ByteHash>;
+type MyCompress = CompressionFunctionFromHasher<u8, ByteHash, 2, 32>;
+type ValMmcs = FieldMerkleTreeMmcs<Val, FieldHash, MyCompress, 32, u8>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = SerializingChallenger32<Val, ByteHash>;
+type MyPcs = CirclePcs<Val, Challenge, ValMmcs, ChallengeMmcs>;
+type Proof = <MyPcs as Pcs<Val, RowMajorMatrix<Val>>>::Proof;
+type Error = <MyPcs as Pcs<Val, RowMajorMatrix<Val>>>::Error;
+
+fn make_pcs(log_blowup: usize, log_final_poly_len: usize) -> MyPcs {
+    let val_mmcs = ValMmcs::new(FieldHash::new(ByteHash {}), MyCompress::new(ByteHash {}));
+    let fri_config = FriConfig {
+        log_blowup,
+        log_folding_arity: 1,
+        log_final_poly_len,
+        num_queries: 10,
+        proof_of_work_bits: 8,
+        mmcs: ChallengeMmcs::new(val_mmcs.clone()),
+    };
+    MyPcs::new(fri_config, val_mmcs)
+}
+
+fn sample_point(challenger: &mut Challenger) -> CirclePoint<Challenge> {
+    CirclePoint::from_projective_line(challenger.sample_ext_element()).unwrap()
+}
+
+/// A committed batch of matrices, with the points at which to open each matrix.
+struct Batch {
+    polynomials: Vec<RowMajorMatrix<Val>>,
+    points: Vec<Vec<CirclePoint<Challenge>>>,
+}
+
+/// Commit to batches of random polynomials with the given log heights, and open each of them at
+/// `num_points` random points.
+///
+/// Returns the PCS, the batches, their commitments, the opened values and the proof.
+#[allow(clippy::type_complexity)]
+fn open(
+    log_heights: &[&[usize]],
+    num_points: usize,
+    log_blowup: usize,
+    log_final_poly_len: usize,
+) -> (
+    MyPcs,
+    Vec<Batch>,
+    Vec<<MyPcs as Pcs<Val, RowMajorMatrix<Val>>>::Commitment>,
+    OpenedValues<Challenge>,
+    Proof,
+) {
+    let mut rng = thread_rng();
+    let pcs = make_pcs(log_blowup, log_final_poly_len);
+    let mut challenger = Challenger::from_hasher(ByteHash {});
+
+    let (commits, data): (Vec<_>, Vec<_>) = log_heights
+        .iter()
+        .map(|batch_log_heights| {
+            let polynomials = batch_log_heights
+                .iter()
+                .map(|&log_height| RowMajorMatrix::rand(&mut rng, 1 << log_height, 3))
+                .collect::<Vec<_>>();
+            let (commit, data) = pcs.commit_batches(polynomials.clone());
+            challenger.observe(commit.clone());
+            (commit, (polynomials, data))
+        })
+        .unzip();
+
+    let batches = data
+        .iter()
+        .map(|(polynomials, _)| Batch {
+            polynomials: polynomials.clone(),
+            points: polynomials
+                .iter()
+                .map(|_| {
+                    (0..num_points)
+                        .map(|_| sample_point(&mut challenger))
+                        .collect()
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    let (opened_values, proof) = pcs.open_multi_batches(
+        &data
+            .iter()
+            .zip(&batches)
+            .map(|((_, data), batch)| (data, batch.points.as_slice()))
+            .collect::<Vec<_>>(),
+        &mut challenger,
+    );
+
+    // The opened values are the polynomials' evaluations at the points.
+    for (batch, values_for_batch) in batches.iter().zip(&opened_values) {
+        for (polynomials, points, values) in
+            itertools::izip!(&batch.polynomials, &batch.points, values_for_batch)
+        {
+            let log_n = p3_util::log2_strict_usize(polynomials.height());
+            let evals = CircleEvaluations::from_cyclic_order(
+                CircleDomain::standard(log_n),
+                polynomials.clone(),
+            );
+            for (&point, values_at_point) in points.iter().zip(values) {
+                assert_eq!(&evals.evaluate_at_point(point), values_at_point);
+            }
+        }
+    }
+
+    (pcs, batches, commits, opened_values, proof)
+}
+
+fn verify(
+    pcs: &MyPcs,
+    batches: &[Batch],
+    commits: &[<MyPcs as Pcs<Val, RowMajorMatrix<Val>>>::Commitment],
+    dims: &[Vec<Dimensions>],
+    opened_values: OpenedValues<Challenge>,
+    proof: &Proof,
+) -> Result<(), Error> {
+    let mut challenger = Challenger::from_hasher(ByteHash {});
+    for commit in commits {
+        challenger.observe(commit.clone());
+    }
+    for batch in batches {
+        for _ in batch.points.iter().flatten() {
+            sample_point(&mut challenger);
+        }
+    }
+    pcs.verify_multi_batches(
+        &commits
+            .iter()
+            .zip(batches)
+            .map(|(commit, batch)| (commit.clone(), batch.points.as_slice()))
+            .collect::<Vec<_>>(),
+        dims,
+        opened_values,
+        proof,
+        &mut challenger,
+    )
+}
+
+fn dims(batches: &[Batch]) -> Vec<Vec<Dimensions>> {
+    batches
+        .iter()
+        .map(|batch| batch.polynomials.iter().map(|p| p.dimensions()).collect())
+        .collect()
+}
+
+fn do_test_circle_pcs(
+    log_heights: &[&[usize]],
+    num_points: usize,
+    log_blowup: usize,
+    log_final_poly_len: usize,
+) {
+    let (pcs, batches, commits, opened_values, proof) =
+        open(log_heights, num_points, log_blowup, log_final_poly_len);
+    verify(
+        &pcs,
+        &batches,
+        &commits,
+        &dims(&batches),
+        opened_values,
+        &proof,
+    )
+    .expect("verification error");
+}
+
+#[test]
+fn test_circle_pcs_single() {
+    do_test_circle_pcs(&[&[3]], 1, 1, 0);
+}
+
+#[test]
+fn test_circle_pcs_smallest() {
+    do_test_circle_pcs(&[&[1]], 1, 1, 0);
+}
+
+#[test]
+fn test_circle_pcs_many_equal() {
+    for log_height in 1..4 {
+        do_test_circle_pcs(&[&[log_height; 4]], 1, 1, 0);
+    }
+}
+
+#[test]
+fn test_circle_pcs_many_different() {
+    do_test_circle_pcs(&[&[3, 4, 5]], 1, 1, 0);
+    do_test_circle_pcs(&[&[6, 2, 4]], 1, 1, 0);
+}
+
+#[test]
+fn test_circle_pcs_many_batches_and_points() {
+    do_test_circle_pcs(&[&[4, 2], &[5], &[3, 3]], 2, 1, 0);
+}
+
+#[test]
+fn test_circle_pcs_blowup_and_final_poly() {
+    for log_final_poly_len in 0..=3 {
+        do_test_circle_pcs(&[&[6]], 1, 2, log_final_poly_len);
+        do_test_circle_pcs(&[&[1, 4, 7]], 1, 1, log_final_poly_len);
+    }
+}
+
+#[test]
+fn test_circle_pcs_rejects_wrong_values() {
+    let (pcs, batches, commits, opened_values, proof) = open(&[&[3, 5]], 2, 1, 0);
+    let dims = dims(&batches);
+
+    let mut wrong_value = opened_values;
+    wrong_value[0][1][1][2] += Challenge::one();
+    assert!(verify(&pcs, &batches, &commits, &dims, wrong_value, &proof).is_err());
+}
+
+#[test]
+fn test_circle_pcs_rejects_wrong_shapes() {
+    let (pcs, batches, commits, opened_values, proof) = open(&[&[3, 5]], 1, 1, 0);
+    let dims = dims(&batches);
+
+    let mut bad_height = dims.clone();
+    bad_height[0][0].height *= 2;
+    assert!(verify(
+        &pcs,
+        &batches,
+        &commits,
+        &bad_height,
+        opened_values.clone(),
+        &proof
+    )
+    .is_err());
+    let mut too_tall = dims.clone();
+    too_tall[0][0].height = 1 << 31;
+    assert!(matches!(
+        verify(
+            &pcs,
+            &batches,
+            &commits,
+            &too_tall,
+            opened_values.clone(),
+            &proof
+        ),
+        Err(CirclePcsError::InvalidProofShape)
+    ));
+    let mut missing_value = opened_values.clone();
+    missing_value[0][0][0].pop();
+    assert!(matches!(
+        verify(&pcs, &batches, &commits, &dims, missing_value, &proof),
+        Err(CirclePcsError::InvalidProofShape)
+    ));
+
+    // A point off the circle is rejected, rather than its line being used.
+    let mut off_circle = batches;
+    off_circle[0].points[0][0].x += Challenge::one();
+    assert!(matches!(
+        verify(&pcs, &off_circle, &commits, &dims, opened_values, &proof),
+        Err(CirclePcsError::InvalidOpeningPoint)
+    ));
+}
@@ -45,6 +45,7 @@ where
     type ProverData = InnerMmcs::ProverData;
     type Commitment = InnerMmcs::Commitment;
     type Proof = InnerMmcs::Proof;
+    type MultiProof = InnerMmcs::MultiProof;
     type Error = InnerMmcs::Error;
     type Mat<'a>
         = ExtensionMatrix<F, EF, InnerMmcs::Mat<'a>>
@@ -57,13 +58,23 @@ where
         prover_data: &Self::ProverData,
     ) -> (Vec<Vec<EF>>, Self::Proof) {
         let (opened_base_values, proof) = self.inner.open_batch(index, prover_data);
-        let opened_ext_values = opened_base_values
-            .into_iter()
-            .map(|row| row.chunks(EF::D).map(EF::from_base_slice).collect())
-            .collect();
+        (to_ext_rows(opened_base_values), proof)
+    }
+
+    fn open_multi_batch(
+        &self,
+        indices: &[usize],
+        prover_data: &Self::ProverData,
+    ) -> (Vec<Vec<Vec<EF>>>, Self::MultiProof) {
+        let (opened_base_values, proof) = self.inner.open_multi_batch(indices, prover_data);
+        let opened_ext_values = opened_base_values.into_iter().map(to_ext_rows).collect();
         (opened_ext_values, proof)
     }
 
+    fn cap_height(&self) -> usize {
+        self.inner.cap_height()
+    }
+
     fn get_matrices<'a>(&'a self, prover_data: &'a Self::ProverData) -> Vec<Self::Mat<'a>> {
         self.inner
             .get_matrices(prover_data)
@@ -83,27 +94,68 @@ where
         opened_values: &[Vec<EF>],
         proof: &Self::Proof,
     ) -> Result<(), Self::Error> {
-        let opened_base_values: Vec<Vec<F>> = opened_values
+        self.inner.verify_batch(
+            commit,
+            &to_base_dimensions::<F, EF>(dimensions),
+            index,
+            &to_base_rows(opened_values),
+            proof,
+        )
+    }
+
+    fn verify_multi_batch(
+        &self,
+        commit: &Self::Commitment,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        opened_values: &[Vec<Vec<EF>>],
+        proof: &Self::MultiProof,
+    ) -> Result<(), Self::Error> {
+        let opened_base_values: Vec<Vec<Vec<F>>> = opened_values
             .iter()
-            .map(|row| {
-                row.iter()
-                    .flat_map(|el| el.as_base_slice())
-                    .copied()
-                    .collect()
-            })
+            .map(|rows| to_base_rows(rows))
             .collect();
-        let base_dimensions = dimensions
-            .iter()
-            .map(|dim| Dimensions {
-                width: dim.width * EF::D,
-                height: dim.height,
-            })
-            .collect::<Vec<_>>();
-        self.inner
-            .verify_batch(commit, &base_dimensions, index, &opened_base_values, proof)
+        self.inner.verify_multi_batch(
+            commit,
+            &to_base_dimensions::<F, EF>(dimensions),
+            indices,
+            &opened_base_values,
+            proof,
+        )
     }
 }
 
+fn to_ext_rows<F: Field, EF: ExtensionField<F>>(base_rows: Vec<Vec<F>>) -> Vec<Vec<EF>> {
+    base_rows
+        .into_iter()
+        .map(|row| row.chunks(EF::D).map(EF::from_base_slice).collect())
+        .collect()
+}
+
+fn to_base_rows<F: Field, EF: ExtensionField<F>>(ext_rows: &[Vec<EF>]) -> Vec<Vec<F>> {
+    ext_rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .flat_map(|el| el.as_base_slice())
+                .copied()
+                .collect()
+        })
+        .collect()
+}
+
+fn to_base_dimensions<F: Field, EF: ExtensionField<F>>(
+    dimensions: &[Dimensions],
+) -> Vec<Dimensions> {
+    dimensions
+        .iter()
+        .map(|dim| Dimensions {
+            width: dim.width * EF::D,
+            height: dim.height,
+        })
+        .collect()
+}
+
 impl<F, EF, InnerMmcs> DirectMmcs<EF> for ExtensionMmcs<F, EF, InnerMmcs>
 where
     F: Field,
@@ -1,22 +1,458 @@
+use alloc::vec;
+use alloc::vec::Vec;
 use core::marker::PhantomData;
 
-use p3_challenger::FieldChallenger;
-use p3_field::{ExtensionField, Field};
-use p3_matrix::MatrixRows;
+use p3_challenger::{CanObserve, FieldChallenger};
+use p3_dft::TwoAdicSubgroupDft;
+use p3_field::{eq_evals, AbstractExtensionField, ExtensionField, Field, TwoAdicField};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{Dimensions, Matrix, MatrixRows};
+use p3_util::codec::encoded_len;
+use serde::{Deserialize, Serialize};
 
-use crate::pcs::UnivariatePcs;
+use crate::pcs::{MultivariatePcs, OpenedValues, Pcs, ProofSize, UnivariatePcs};
 
-#[allow(dead_code)]
-pub struct MultiFromUniPcs<Val, EF, In, U, Challenger>
+/// A multilinear PCS built on a univariate one.
+///
+/// Each column of a committed matrix, which holds a multilinear polynomial's evaluations `f_i` over
+/// the hypercube, is committed as the coefficients of `U(X) = sum_i f_i X^i`, i.e. `U`'s
+/// evaluations over the subgroup `H` of the matrix's height are the DFT of the column.
+///
+/// The value at `z` is `f(z) = sum_i eq(i, z) f_i`, which is the constant term of the Laurent
+/// polynomial `U(X) V(1/X)`, where `V(Y) = sum_j eq(j, z) Y^j = prod_k (1 - z_k + z_k Y^(2^(n-1-k)))`
+/// for `n` variables. Summing `U(x) V(1/x)` over `H` gives `|H|` times the constant term, so the
+/// opening is a sumcheck over the evaluation domain: the prover commits to `R` and `S` with
+/// `U(X) V(1/X) - f(z) = X R(X) + S(1/X) / X`, and the verifier checks this at a random point `ζ`
+/// by opening `U` and `R` at `ζ` and `S` at `1/ζ`. The univariate PCS bounds their degrees by
+/// `|H|`, so no other constant term can make the identity hold.
+///
+/// The columns of a matrix are combined with the powers of a random `alpha` first, so there is one
+/// `R` and `S` for each matrix and point. Their coordinates over `Val` are committed as columns.
+pub struct MultiFromUniPcs<Val, EF, U, Dft, Challenger> {
+    uni: U,
+    dft: Dft,
+    _phantom: PhantomData<(Val, EF, Challenger)>,
+}
+
+impl<Val, EF, U, Dft, Challenger> MultiFromUniPcs<Val, EF, U, Dft, Challenger> {
+    pub fn new(uni: U, dft: Dft) -> Self {
+        Self {
+            uni,
+            dft,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MultiFromUniPcsError<UniErr> {
+    InvalidProofShape,
+    /// The point the univariate polynomials are opened at is zero, so it has no inverse.
+    InvalidOpeningPoint,
+    UniPcsError(UniErr),
+    /// The univariate openings don't agree with an opened value.
+    SumcheckMismatch,
+}
+
+#[derive(Clone)]
+pub struct MultiFromUniProverData<Val, UniProverData> {
+    data: UniProverData,
+    /// The committed matrices, whose columns are the coefficients of the univariate polynomials.
+    polynomials: Vec<RowMajorMatrix<Val>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MultiFromUniProof<EF, Commitment, UniProof> {
+    /// The commitment to `R` and `S` for each matrix and point, as a batch with one matrix each.
+    pub(crate) sumcheck_commit: Commitment,
+    /// The univariate openings: those of each committed batch at `ζ`, then those of the sumcheck
+    /// batch at `ζ` and `1/ζ`.
+    pub(crate) uni_opened_values: OpenedValues<EF>,
+    pub(crate) uni_proof: UniProof,
+}
+
+impl<EF, Commitment, UniProof> MultiFromUniProof<EF, Commitment, UniProof> {
+    pub fn sumcheck_commit(&self) -> &Commitment {
+        &self.sumcheck_commit
+    }
+
+    pub fn uni_opened_values(&self) -> &OpenedValues<EF> {
+        &self.uni_opened_values
+    }
+
+    pub fn uni_proof(&self) -> &UniProof {
+        &self.uni_proof
+    }
+}
+
+impl<Val, EF, In, U, Dft, Challenger> Pcs<Val, In> for MultiFromUniPcs<Val, EF, U, Dft, Challenger>
 where
-    Val: Field,
+    Val: TwoAdicField,
     EF: ExtensionField<Val>,
     In: MatrixRows<Val>,
-    U: UnivariatePcs<Val, EF, In, Challenger>,
-    Challenger: FieldChallenger<Val>,
+    U: Pcs<Val, RowMajorMatrix<Val>>,
+    Dft: TwoAdicSubgroupDft<Val>,
 {
-    _uni: U,
-    _phantom: PhantomData<(Val, EF, In, Challenger)>,
+    type Commitment = U::Commitment;
+    type ProverData = MultiFromUniProverData<Val, U::ProverData>;
+    type Proof = MultiFromUniProof<EF, U::Commitment, U::Proof>;
+    type Error = MultiFromUniPcsError<U::Error>;
+
+    fn commit_batches(&self, polynomials: Vec<In>) -> (Self::Commitment, Self::ProverData) {
+        let polynomials: Vec<_> = polynomials
+            .into_iter()
+            .map(|mat| mat.to_row_major_matrix())
+            .collect();
+        let evaluations = polynomials
+            .iter()
+            .map(|coeffs| self.dft.dft_batch(coeffs.clone()).to_row_major_matrix())
+            .collect();
+        let (commit, data) = self.uni.commit_batches(evaluations);
+        (commit, MultiFromUniProverData { data, polynomials })
+    }
+
+    fn num_queries(&self) -> usize {
+        self.uni.num_queries()
+    }
+
+    fn shape_params(&self) -> Vec<usize> {
+        self.uni.shape_params()
+    }
+
+    fn proof_size(&self, proof: &Self::Proof) -> ProofSize {
+        let uni_size = self.uni.proof_size(&proof.uni_proof);
+        let opened_values = uni_size.opened_values + encoded_len(&proof.uni_opened_values);
+        ProofSize {
+            opened_values,
+            other: encoded_len(proof)
+                - uni_size.merkle_paths
+                - uni_size.commit_phase_openings
+                - opened_values,
+            ..uni_size
+        }
+    }
 }
 
-// TODO: Impl PCS, MultivariatePcs
+impl<Val, EF, In, U, Dft, Challenger> MultivariatePcs<Val, EF, In, Challenger>
+    for MultiFromUniPcs<Val, EF, U, Dft, Challenger>
+where
+    Val: TwoAdicField,
+    EF: ExtensionField<Val>,
+    In: MatrixRows<Val>,
+    U: UnivariatePcs<Val, EF, RowMajorMatrix<Val>, Challenger>,
+    Dft: TwoAdicSubgroupDft<Val>,
+    Challenger: FieldChallenger<Val> + CanObserve<U::Commitment>,
+{
+    /// Panics if no matrix is opened at any point.
+    fn open_multi_batches(
+        &self,
+        prover_data_and_points: &[(&Self::ProverData, &[Vec<Vec<EF>>])],
+        challenger: &mut Challenger,
+    ) -> (OpenedValues<EF>, Self::Proof) {
+        let opened_values: OpenedValues<EF> = prover_data_and_points
+            .iter()
+            .map(|(data, points_for_batch)| {
+                assert_eq!(data.polynomials.len(), points_for_batch.len());
+                data.polynomials
+                    .iter()
+                    .zip(points_for_batch.iter())
+                    .map(|(coeffs, points)| {
+                        points
+                            .iter()
+                            .map(|point| evaluate_multilinear(coeffs, point))
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        for &value in opened_values.iter().flatten().flatten().flatten() {
+            challenger.observe_ext_element(value);
+        }
+
+        let alpha: EF = challenger.sample_ext_element();
+        let sumcheck_polys: Vec<_> = prover_data_and_points
+            .iter()
+            .flat_map(|(data, points_for_batch)| {
+                data.polynomials.iter().zip(points_for_batch.iter())
+            })
+            .flat_map(|(coeffs, points)| {
+                let alpha_pows: Vec<EF> = alpha.powers().take(coeffs.width()).collect();
+                let combined = combine_columns(coeffs, &alpha_pows);
+                points.iter().map(move |point| {
+                    let (r, s) = sumcheck_polys(&combined, point);
+                    self.dft
+                        .dft_batch(to_base_coordinates(&[r, s]))
+                        .to_row_major_matrix()
+                })
+            })
+            .collect();
+        assert!(
+            !sumcheck_polys.is_empty(),
+            "No matrix is opened at any point"
+        );
+        let (sumcheck_commit, sumcheck_data) = self.uni.commit_batches(sumcheck_polys);
+        challenger.observe(sumcheck_commit.clone());
+
+        let zeta: EF = challenger.sample_ext_element();
+        let zeta_inv = zeta.inverse();
+        let zeta_points: Vec<Vec<Vec<EF>>> = prover_data_and_points
+            .iter()
+            .map(|(data, _)| vec![vec![zeta]; data.polynomials.len()])
+            .collect();
+        let sumcheck_points = vec![vec![zeta, zeta_inv]; num_openings(prover_data_and_points)];
+        let mut uni_rounds: Vec<_> = prover_data_and_points
+            .iter()
+            .zip(&zeta_points)
+            .map(|((data, _), points)| (&data.data, points.as_slice()))
+            .collect();
+        uni_rounds.push((&sumcheck_data, sumcheck_points.as_slice()));
+        let (uni_opened_values, uni_proof) = self.uni.open_multi_batches(&uni_rounds, challenger);
+
+        (
+            opened_values,
+            MultiFromUniProof {
+                sumcheck_commit,
+                uni_opened_values,
+                uni_proof,
+            },
+        )
+    }
+
+    fn verify_multi_batches(
+        &self,
+        commits_and_points: &[(Self::Commitment, &[Vec<Vec<EF>>])],
+        dims: &[Vec<Dimensions>],
+        values: OpenedValues<EF>,
+        proof: &Self::Proof,
+        challenger: &mut Challenger,
+    ) -> Result<(), Self::Error> {
+        let ext_degree = <EF as AbstractExtensionField<Val>>::D;
+        let num_batches = commits_and_points.len();
+        if dims.len() != num_batches || values.len() != num_batches {
+            return Err(MultiFromUniPcsError::InvalidProofShape);
+        }
+        for (((_, points_for_batch), dims), values) in
+            commits_and_points.iter().zip(dims).zip(&values)
+        {
+            if points_for_batch.len() != dims.len() || values.len() != dims.len() {
+                return Err(MultiFromUniPcsError::InvalidProofShape);
+            }
+            for ((points, dims), values) in points_for_batch.iter().zip(dims).zip(values) {
+                if !dims.height.is_power_of_two()
+                    || points.iter().any(|point| 1 << point.len() != dims.height)
+                    || values.len() != points.len()
+                    || values.iter().any(|v| v.len() != dims.width)
+                {
+                    return Err(MultiFromUniPcsError::InvalidProofShape);
+                }
+            }
+        }
+        let num_openings = num_openings(commits_and_points);
+        if num_openings == 0 {
+            return Err(MultiFromUniPcsError::InvalidProofShape);
+        }
+
+        for &value in values.iter().flatten().flatten().flatten() {
+            challenger.observe_ext_element(value);
+        }
+        let alpha: EF = challenger.sample_ext_element();
+        challenger.observe(proof.sumcheck_commit.clone());
+        let zeta: EF = challenger.sample_ext_element();
+        let zeta_inv = zeta
+            .try_inverse()
+            .ok_or(MultiFromUniPcsError::InvalidOpeningPoint)?;
+
+        let zeta_points: Vec<Vec<Vec<EF>>> = dims
+            .iter()
+            .map(|dims| vec![vec![zeta]; dims.len()])
+            .collect();
+        let sumcheck_points = vec![vec![zeta, zeta_inv]; num_openings];
+        let mut uni_commits_and_points: Vec<_> = commits_and_points
+            .iter()
+            .zip(&zeta_points)
+            .map(|((commit, _), points)| (commit.clone(), points.as_slice()))
+            .collect();
+        uni_commits_and_points.push((proof.sumcheck_commit.clone(), sumcheck_points.as_slice()));
+        let mut uni_dims = dims.to_vec();
+        uni_dims.push(
+            commits_and_points
+                .iter()
+                .zip(dims)
+                .flat_map(|((_, points_for_batch), dims)| points_for_batch.iter().zip(dims))
+                .flat_map(|(points, dims)| {
+                    points.iter().map(|_| Dimensions {
+                        width: 2 * ext_degree,
+                        height: dims.height,
+                    })
+                })
+                .collect(),
+        );
+        self.uni
+            .verify_multi_batches(
+                &uni_commits_and_points,
+                &uni_dims,
+                proof.uni_opened_values.clone(),
+                &proof.uni_proof,
+                challenger,
+            )
+            .map_err(MultiFromUniPcsError::UniPcsError)?;
+
+        // The univariate openings now hold, so we only need to check their shape before using them.
+        let uni_values = &proof.uni_opened_values;
+        if uni_values.len() != num_batches + 1 || uni_values[num_batches].len() != num_openings {
+            return Err(MultiFromUniPcsError::InvalidProofShape);
+        }
+        let mut sumcheck_values = uni_values[num_batches].iter();
+        for (((_, points_for_batch), values), uni_values) in
+            commits_and_points.iter().zip(&values).zip(uni_values)
+        {
+            if uni_values.len() != points_for_batch.len() {
+                return Err(MultiFromUniPcsError::InvalidProofShape);
+            }
+            for ((points, values), uni_values) in
+                points_for_batch.iter().zip(values).zip(uni_values)
+            {
+                let u_at_zeta = match uni_values.as_slice() {
+                    [u_at_zeta] => u_at_zeta,
+                    _ => return Err(MultiFromUniPcsError::InvalidProofShape),
+                };
+                let alpha_pows: Vec<EF> = alpha.powers().take(u_at_zeta.len()).collect();
+                let combined_u = dot(&alpha_pows, u_at_zeta);
+                for (point, values) in points.iter().zip(values) {
+                    let (r_at_zeta, s_at_zeta_inv) = match sumcheck_values.next().map(Vec::as_slice)
+                    {
+                        Some([at_zeta, at_zeta_inv])
+                            if at_zeta.len() == 2 * ext_degree
+                                && at_zeta_inv.len() == 2 * ext_degree =>
+                        {
+                            (
+                                from_base_coordinates::<Val, EF>(&at_zeta[..ext_degree]),
+                                from_base_coordinates::<Val, EF>(&at_zeta_inv[ext_degree..]),
+                            )
+                        }
+                        _ => return Err(MultiFromUniPcsError::InvalidProofShape),
+                    };
+                    if alpha_pows.len() != values.len() {
+                        return Err(MultiFromUniPcsError::InvalidProofShape);
+                    }
+                    let combined_value = dot(&alpha_pows, values);
+                    let v_at_zeta_inv = evaluate_v(point, zeta_inv);
+                    if combined_u * v_at_zeta_inv - combined_value
+                        != zeta * r_at_zeta + zeta_inv * s_at_zeta_inv
+                    {
+                        return Err(MultiFromUniPcsError::SumcheckMismatch);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The number of pairs of a matrix and a point it is opened at.
+fn num_openings<D, EF>(data_and_points: &[(D, &[Vec<Vec<EF>>])]) -> usize {
+    data_and_points
+        .iter()
+        .map(|(_, points_for_batch)| points_for_batch.iter().map(Vec::len).sum::<usize>())
+        .sum()
+}
+
+/// Given the coefficients of `U` and a point `z`, compute the coefficients of `R` and `S` with
+/// `U(X) V(1/X) = c + X R(X) + S(1/X) / X` for a constant `c`, where
+/// `V(Y) = prod_k (1 - z_k + z_k Y^(2^(n-1-k)))`. Both have as many coefficients as `U`.
+fn sumcheck_polys<EF: Field>(coeffs: &[EF], point: &[EF]) -> (Vec<EF>, Vec<EF>) {
+    let n = coeffs.len();
+    assert_eq!(n, 1 << point.len());
+
+    // The coefficient of `X^m` is at index `m + n - 1`.
+    let mut laurent = vec![EF::zero(); 2 * n - 1];
+    laurent[n - 1..].copy_from_slice(coeffs);
+    for (k, &z) in point.iter().enumerate() {
+        // Multiply by `1 - z + z X^(-shift)`. Each coefficient only depends on those above it, so
+        // we can go upwards in place.
+        let shift = n >> (k + 1);
+        for i in 0..laurent.len() {
+            let above = laurent.get(i + shift).copied().unwrap_or_else(EF::zero);
+            laurent[i] = (EF::one() - z) * laurent[i] + z * above;
+        }
+    }
+
+    let mut r = laurent[n..].to_vec();
+    r.push(EF::zero());
+    let mut s: Vec<_> = laurent[..n - 1].iter().rev().copied().collect();
+    s.push(EF::zero());
+    (r, s)
+}
+
+/// Evaluate `V(y) = prod_k (1 - z_k + z_k y^(2^(n-1-k)))`.
+fn evaluate_v<EF: Field>(point: &[EF], y: EF) -> EF {
+    let mut y_pow = y;
+    let mut result = EF::one();
+    for &z in point.iter().rev() {
+        result *= EF::one() - z + z * y_pow;
+        y_pow = y_pow.square();
+    }
+    result
+}
+
+/// A matrix whose columns are the coordinates over `Val` of each of the given vectors, which must
+/// have the same length.
+fn to_base_coordinates<Val: Field, EF: ExtensionField<Val>>(
+    vectors: &[Vec<EF>],
+) -> RowMajorMatrix<Val> {
+    let height = vectors[0].len();
+    let values = (0..height)
+        .flat_map(|i| {
+            vectors
+                .iter()
+                .flat_map(move |vector| vector[i].as_base_slice().iter().copied())
+        })
+        .collect();
+    RowMajorMatrix::new(values, vectors.len() * EF::D)
+}
+
+/// Given the values of the coordinates of a polynomial over the extension, combine them into its
+/// value.
+fn from_base_coordinates<Val: Field, EF: ExtensionField<Val>>(values: &[EF]) -> EF {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| EF::monomial(i) * value)
+        .sum()
+}
+
+/// Evaluate the multilinear polynomials whose evaluations over the hypercube are the columns of
+/// `mat`.
+fn evaluate_multilinear<Val: Field, EF: ExtensionField<Val>>(
+    mat: &RowMajorMatrix<Val>,
+    point: &[EF],
+) -> Vec<EF> {
+    assert_eq!(1 << point.len(), mat.height());
+    let mut values = vec![EF::zero(); mat.width()];
+    for (eq, row) in eq_evals(point).into_iter().zip(mat.rows()) {
+        for (value, &x) in values.iter_mut().zip(row) {
+            *value += eq * x;
+        }
+    }
+    values
+}
+
+/// Multiply a matrix by a vector of weights, one per column.
+fn combine_columns<Val: Field, EF: ExtensionField<Val>>(
+    mat: &RowMajorMatrix<Val>,
+    weights: &[EF],
+) -> Vec<EF> {
+    mat.rows()
+        .map(|row| {
+            row.iter()
+                .zip(weights)
+                .map(|(&x, &weight)| weight * x)
+                .sum()
+        })
+        .collect()
+}
+
+fn dot<EF: Field>(a: &[EF], b: &[EF]) -> EF {
+    a.iter().zip(b).map(|(&x, &y)| x * y).sum()
+}
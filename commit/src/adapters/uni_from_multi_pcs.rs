@@ -1,22 +1,455 @@
+use alloc::vec;
+use alloc::vec::Vec;
 use core::marker::PhantomData;
 
 use p3_challenger::FieldChallenger;
-use p3_field::{ExtensionField, Field};
-use p3_matrix::MatrixRows;
+use p3_dft::TwoAdicSubgroupDft;
+use p3_field::{ExtensionField, Field, TwoAdicField};
+use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
+use p3_matrix::strided::VerticallyStridedMatrixView;
+use p3_matrix::{Dimensions, Matrix, MatrixRows};
+use p3_util::log2_strict_usize;
 
-use crate::pcs::MultivariatePcs;
+use crate::pcs::{
+    MultivariatePcs, OpenedValues, Pcs, ProofSize, UnivariatePcs, UnivariatePcsWithLde,
+};
+use crate::{two_adic, BlindingRng, LagrangeSelectors, StarkPcs};
 
-#[allow(dead_code)]
-pub struct UniFromMultiPcs<Val, EF, In, M, Challenger>
+/// A univariate PCS built on a multilinear one.
+///
+/// A committed matrix holds evaluations over a two-adic subgroup `H`, or a coset of it. Each of its
+/// columns is interpolated to a polynomial `p(X) = sum_i c_i X^i` of degree less than `n = |H|`,
+/// and its coefficients are committed as the evaluations over the hypercube of a multilinear
+/// polynomial `f`, with `f_i = c_i`.
+///
+/// Writing `y_k = ζ^(2^(m-1-k))` for `m` variables, `p(ζ) = sum_i c_i prod_k y_k^(i_k)`, where `i_k`
+/// is the `k`th most significant bit of `i`. Each factor is `(1 + y_k)` times the weight
+/// `eq(i_k, z_k)` at `z_k = y_k / (1 + y_k)`, so `p(ζ) = f(z) prod_k (1 + y_k)`, which lets the
+/// multilinear PCS open `p`. This fails when some `y_k` is `-1`, i.e. for the points of `H` other
+/// than `1`, which can't be opened.
+///
+/// The prover also computes the LDEs that `UnivariatePcsWithLde` exposes, over the coset of the
+/// subgroup `2^log_blowup` times larger than `H` shifted by the field's generator. They aren't
+/// committed, as the multilinear PCS doesn't need them.
+pub struct UniFromMultiPcs<Val, EF, M, Dft, Challenger> {
+    multi: M,
+    dft: Dft,
+    log_blowup: usize,
+    _phantom: PhantomData<(Val, EF, Challenger)>,
+}
+
+impl<Val, EF, M, Dft, Challenger> UniFromMultiPcs<Val, EF, M, Dft, Challenger> {
+    pub fn new(multi: M, dft: Dft, log_blowup: usize) -> Self {
+        Self {
+            multi,
+            dft,
+            log_blowup,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum UniFromMultiPcsError<MultiErr> {
+    InvalidProofShape,
+    /// The point is in the subgroup a polynomial is interpolated over, and isn't `1`.
+    InvalidOpeningPoint,
+    MultiPcsError(MultiErr),
+}
+
+#[derive(Clone)]
+pub struct UniFromMultiProverData<Val, MultiProverData> {
+    data: MultiProverData,
+    /// The LDE of each committed matrix, in natural order.
+    ldes: Vec<RowMajorMatrix<Val>>,
+}
+
+impl<Val, EF, M, Dft, Challenger> UniFromMultiPcs<Val, EF, M, Dft, Challenger>
+where
+    Val: TwoAdicField,
+    M: Pcs<Val, RowMajorMatrix<Val>>,
+    Dft: TwoAdicSubgroupDft<Val>,
+{
+    fn commit_coset_evaluations<In: MatrixRows<Val>>(
+        &self,
+        polynomials: Vec<In>,
+        coset_shifts: &[Val],
+    ) -> (M::Commitment, UniFromMultiProverData<Val, M::ProverData>) {
+        assert_eq!(polynomials.len(), coset_shifts.len());
+        let (coeffs, ldes) = polynomials
+            .into_iter()
+            .zip(coset_shifts)
+            .map(|(evals, &shift)| {
+                let mut coeffs = self.dft.idft_batch(evals.to_row_major_matrix());
+                // The coefficients of `p(shift X)` are those of `p` scaled by the powers of `shift`.
+                let shift_inv = shift.inverse();
+                for (r, scale) in shift_inv.powers().take(coeffs.height()).enumerate() {
+                    coeffs.scale_row(r, scale);
+                }
+                let mut padded = coeffs.clone();
+                padded.expand_to_height(coeffs.height() << self.log_blowup);
+                let lde = self
+                    .dft
+                    .coset_dft_batch(padded, Val::generator())
+                    .to_row_major_matrix();
+                (coeffs, lde)
+            })
+            .unzip();
+        let (commit, data) = self.multi.commit_batches(coeffs);
+        (commit, UniFromMultiProverData { data, ldes })
+    }
+}
+
+impl<Val, EF, In, M, Dft, Challenger> Pcs<Val, In> for UniFromMultiPcs<Val, EF, M, Dft, Challenger>
+where
+    Val: TwoAdicField,
+    In: MatrixRows<Val>,
+    M: Pcs<Val, RowMajorMatrix<Val>>,
+    Dft: TwoAdicSubgroupDft<Val>,
+{
+    type Commitment = M::Commitment;
+    type ProverData = UniFromMultiProverData<Val, M::ProverData>;
+    type Proof = M::Proof;
+    type Error = UniFromMultiPcsError<M::Error>;
+
+    fn commit_batches(&self, polynomials: Vec<In>) -> (Self::Commitment, Self::ProverData) {
+        let ones = vec![Val::one(); polynomials.len()];
+        self.commit_coset_evaluations(polynomials, &ones)
+    }
+
+    fn num_queries(&self) -> usize {
+        self.multi.num_queries()
+    }
+
+    fn shape_params(&self) -> Vec<usize> {
+        self.multi.shape_params()
+    }
+
+    fn proof_size(&self, proof: &Self::Proof) -> ProofSize {
+        self.multi.proof_size(proof)
+    }
+}
+
+impl<Val, EF, In, M, Dft, Challenger> UnivariatePcs<Val, EF, In, Challenger>
+    for UniFromMultiPcs<Val, EF, M, Dft, Challenger>
+where
+    Val: TwoAdicField,
+    EF: ExtensionField<Val>,
+    In: MatrixRows<Val>,
+    M: MultivariatePcs<Val, EF, RowMajorMatrix<Val>, Challenger>,
+    Dft: TwoAdicSubgroupDft<Val>,
+    Challenger: FieldChallenger<Val>,
+{
+    /// Panics if a point can't be opened; see `UniFromMultiPcs`.
+    #[allow(clippy::type_complexity)]
+    fn open_multi_batches(
+        &self,
+        prover_data_and_points: &[(&Self::ProverData, &[Vec<EF>])],
+        challenger: &mut Challenger,
+    ) -> (OpenedValues<EF>, Self::Proof) {
+        let (multi_points, scales): (Vec<Vec<Vec<Vec<EF>>>>, Vec<Vec<Vec<EF>>>) =
+            prover_data_and_points
+                .iter()
+                .map(|(data, points_for_batch)| {
+                    assert_eq!(data.ldes.len(), points_for_batch.len());
+                    data.ldes
+                        .iter()
+                        .zip(points_for_batch.iter())
+                        .map(|(lde, points)| {
+                            let log_height = log2_strict_usize(lde.height()) - self.log_blowup;
+                            points
+                                .iter()
+                                .map(|&zeta| {
+                                    multilinear_point(zeta, log_height)
+                                        .expect("Cannot open a polynomial inside its subgroup")
+                                })
+                                .unzip()
+                        })
+                        .unzip()
+                })
+                .unzip();
+        let multi_rounds: Vec<_> = prover_data_and_points
+            .iter()
+            .zip(&multi_points)
+            .map(|((data, _), points)| (&data.data, points.as_slice()))
+            .collect();
+        let (mut opened_values, proof) = self.multi.open_multi_batches(&multi_rounds, challenger);
+
+        for (values, &scale) in opened_values
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .zip(scales.iter().flatten().flatten())
+        {
+            for value in values {
+                *value *= scale;
+            }
+        }
+        (opened_values, proof)
+    }
+
+    fn verify_multi_batches(
+        &self,
+        commits_and_points: &[(Self::Commitment, &[Vec<EF>])],
+        dims: &[Vec<Dimensions>],
+        mut values: OpenedValues<EF>,
+        proof: &Self::Proof,
+        challenger: &mut Challenger,
+    ) -> Result<(), Self::Error> {
+        if dims.len() != commits_and_points.len() || values.len() != commits_and_points.len() {
+            return Err(UniFromMultiPcsError::InvalidProofShape);
+        }
+        let mut multi_points = Vec::with_capacity(commits_and_points.len());
+        for (((_, points_for_batch), dims), values) in
+            commits_and_points.iter().zip(dims).zip(&mut values)
+        {
+            if points_for_batch.len() != dims.len() || values.len() != dims.len() {
+                return Err(UniFromMultiPcsError::InvalidProofShape);
+            }
+            let mut multi_points_for_batch = Vec::with_capacity(dims.len());
+            for ((points, dims), values) in points_for_batch.iter().zip(dims).zip(values) {
+                if !dims.height.is_power_of_two() || values.len() != points.len() {
+                    return Err(UniFromMultiPcsError::InvalidProofShape);
+                }
+                let log_height = log2_strict_usize(dims.height);
+                let mut multi_points_for_matrix = Vec::with_capacity(points.len());
+                for (&zeta, values) in points.iter().zip(values) {
+                    let (point, scale) = multilinear_point(zeta, log_height)
+                        .ok_or(UniFromMultiPcsError::InvalidOpeningPoint)?;
+                    let scale_inv = scale.inverse();
+                    for value in values {
+                        *value *= scale_inv;
+                    }
+                    multi_points_for_matrix.push(point);
+                }
+                multi_points_for_batch.push(multi_points_for_matrix);
+            }
+            multi_points.push(multi_points_for_batch);
+        }
+
+        let multi_commits_and_points: Vec<_> = commits_and_points
+            .iter()
+            .zip(&multi_points)
+            .map(|((commit, _), points)| (commit.clone(), points.as_slice()))
+            .collect();
+        self.multi
+            .verify_multi_batches(&multi_commits_and_points, dims, values, proof, challenger)
+            .map_err(UniFromMultiPcsError::MultiPcsError)
+    }
+}
+
+impl<Val, EF, In, M, Dft, Challenger> UnivariatePcsWithLde<Val, EF, In, Challenger>
+    for UniFromMultiPcs<Val, EF, M, Dft, Challenger>
 where
-    Val: Field,
+    Val: TwoAdicField,
     EF: ExtensionField<Val>,
     In: MatrixRows<Val>,
-    M: MultivariatePcs<Val, EF, In, Challenger>,
+    M: MultivariatePcs<Val, EF, RowMajorMatrix<Val>, Challenger>,
+    Dft: TwoAdicSubgroupDft<Val>,
     Challenger: FieldChallenger<Val>,
 {
-    _multi: M,
-    _phantom: PhantomData<(Val, EF, In, Challenger)>,
+    type Lde<'a>
+        = RowMajorMatrixView<'a, Val>
+    where
+        Self: 'a;
+
+    fn coset_shift(&self) -> Val {
+        Val::generator()
+    }
+
+    fn log_blowup(&self) -> usize {
+        self.log_blowup
+    }
+
+    fn get_ldes<'a, 'b>(&'a self, prover_data: &'b Self::ProverData) -> Vec<Self::Lde<'b>>
+    where
+        'a: 'b,
+    {
+        prover_data.ldes.iter().map(|lde| lde.as_view()).collect()
+    }
+
+    fn compute_coset_ldes_batches(
+        &self,
+        polynomials: Vec<In>,
+        coset_shifts: Vec<Val>,
+    ) -> Vec<RowMajorMatrix<Val>> {
+        polynomials
+            .into_iter()
+            .zip(coset_shifts)
+            .map(|(poly, coset_shift)| {
+                let shift = Val::generator() / coset_shift;
+                self.dft
+                    .coset_lde_batch(poly.to_row_major_matrix(), self.log_blowup, shift)
+                    .to_row_major_matrix()
+            })
+            .collect()
+    }
+
+    fn commit_shifted_batches(
+        &self,
+        polynomials: Vec<In>,
+        coset_shifts: &[Val],
+    ) -> (Self::Commitment, Self::ProverData) {
+        self.commit_coset_evaluations(polynomials, coset_shifts)
+    }
 }
 
-// impl<F: Field, M: MultivariatePcs<F>> UnivariatePcs<F> for UniFromMultiPcs<F> {}
+impl<Val, EF, M, Dft, Challenger> StarkPcs<Val, EF, Challenger>
+    for UniFromMultiPcs<Val, EF, M, Dft, Challenger>
+where
+    Val: TwoAdicField,
+    EF: ExtensionField<Val> + TwoAdicField,
+    M: MultivariatePcs<Val, EF, RowMajorMatrix<Val>, Challenger>,
+    Dft: TwoAdicSubgroupDft<Val>,
+    Challenger: FieldChallenger<Val>,
+{
+    type QuotientDomainLde<'a>
+        = VerticallyStridedMatrixView<RowMajorMatrixView<'a, Val>>
+    where
+        Self: 'a;
+
+    fn log_blowup(&self) -> usize {
+        self.log_blowup
+    }
+
+    fn domain_params(&self) -> Vec<Val> {
+        vec![Val::generator()]
+    }
+
+    fn max_log_height(&self) -> usize {
+        Val::TWO_ADICITY
+    }
+
+    fn next_point(&self, log_height: usize, point: EF) -> EF {
+        two_adic::next_point::<Val, _>(log_height, point)
+    }
+
+    fn selectors_at_point(
+        &self,
+        log_height: usize,
+        point: EF,
+        window_size: usize,
+    ) -> LagrangeSelectors<EF> {
+        two_adic::selectors_at_point::<Val, _>(log_height, point, window_size)
+    }
+
+    fn log_quotient_height(&self, log_height: usize, log_quotient_degree: usize) -> usize {
+        log_height + log_quotient_degree
+    }
+
+    fn selectors_on_quotient_domain(
+        &self,
+        log_height: usize,
+        log_quotient_height: usize,
+        window_size: usize,
+    ) -> LagrangeSelectors<Vec<Val>> {
+        two_adic::selectors_on_coset(
+            log_height,
+            log_quotient_height,
+            Val::generator(),
+            window_size,
+        )
+    }
+
+    fn quotient_domain_ldes<'a, 'b>(
+        &'a self,
+        prover_data: &'b Self::ProverData,
+        log_quotient_height: usize,
+    ) -> Vec<Self::QuotientDomainLde<'b>>
+    where
+        'a: 'b,
+    {
+        let ldes = prover_data.ldes.iter().map(|lde| lde.as_view()).collect();
+        two_adic::quotient_domain_ldes(ldes, log_quotient_height)
+    }
+
+    fn evaluate_on_quotient_domain(
+        &self,
+        evals: RowMajorMatrix<Val>,
+        log_quotient_height: usize,
+    ) -> RowMajorMatrix<Val> {
+        two_adic::evaluate_on_coset(evals, log_quotient_height, Val::generator())
+    }
+
+    fn evaluate_at_point(&self, evals: RowMajorMatrix<Val>, point: EF) -> Vec<EF> {
+        two_adic::evaluate_at_point(evals, point)
+    }
+
+    fn commit_quotient(
+        &self,
+        quotient_values: Vec<EF>,
+        log_height: usize,
+        log_quotient_degree: usize,
+        blinding_rng: Option<&BlindingRng>,
+    ) -> (Self::Commitment, Self::ProverData) {
+        two_adic::commit_quotient(
+            self,
+            quotient_values,
+            log_height,
+            log_quotient_degree,
+            blinding_rng,
+        )
+    }
+
+    fn quotient_dimensions(
+        &self,
+        log_height: usize,
+        log_quotient_degree: usize,
+        is_zk: bool,
+    ) -> Dimensions {
+        two_adic::quotient_dimensions::<Val, EF>(log_height, log_quotient_degree, is_zk)
+    }
+
+    fn quotient_point(&self, zeta: EF, log_quotient_degree: usize, is_zk: bool) -> EF {
+        two_adic::quotient_point(zeta, log_quotient_degree, is_zk)
+    }
+
+    fn recompose_quotient(
+        &self,
+        parts: &[EF],
+        zeta: EF,
+        log_height: usize,
+        log_quotient_degree: usize,
+        is_zk: bool,
+    ) -> EF {
+        two_adic::recompose_quotient(
+            parts,
+            zeta,
+            Val::generator(),
+            log_height,
+            log_quotient_degree,
+            is_zk,
+        )
+    }
+
+    fn blind_trace(
+        &self,
+        trace: RowMajorMatrix<Val>,
+        blinding_rng: &BlindingRng,
+    ) -> RowMajorMatrix<Val> {
+        two_adic::blind_trace(trace, blinding_rng)
+    }
+
+    fn extend_trace(&self, trace: RowMajorMatrix<Val>) -> RowMajorMatrix<Val> {
+        two_adic::extend_trace(trace)
+    }
+}
+
+/// The point at which to open the multilinear polynomial whose evaluations are the coefficients of
+/// a univariate polynomial with `2^log_height` coefficients, so that scaling its value by the
+/// returned factor gives the univariate polynomial's value at `zeta`.
+///
+/// Returns `None` if `zeta` is in the subgroup of order `2^log_height` and isn't `1`.
+fn multilinear_point<EF: Field>(zeta: EF, log_height: usize) -> Option<(Vec<EF>, EF)> {
+    let mut point = vec![EF::zero(); log_height];
+    let mut scale = EF::one();
+    let mut y = zeta;
+    // The last variable is the least significant bit, whose factor is `zeta` itself.
+    for z in point.iter_mut().rev() {
+        let one_plus_y = EF::one() + y;
+        *z = y * one_plus_y.try_inverse()?;
+        scale *= one_plus_y;
+        y = y.square();
+    }
+    Some((point, scale))
+}
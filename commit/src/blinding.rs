@@ -0,0 +1,55 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+/// A source of randomness for hiding commitments and zero-knowledge proofs.
+///
+/// Clones share a seed, but each call to `rng` draws from a fresh ChaCha20 stream, so a single
+/// `BlindingRng` can be shared by every component of a config, including across threads.
+#[derive(Clone, Debug)]
+pub struct BlindingRng {
+    seed: [u8; 32],
+    next_stream: Arc<AtomicU64>,
+}
+
+impl BlindingRng {
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut seed = [0; 32];
+        rng.fill_bytes(&mut seed);
+        Self::from_seed(seed)
+    }
+
+    /// Only use a fixed seed for testing; proofs are only hiding if the seed is secret and fresh.
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self {
+            seed,
+            next_stream: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// A random number generator whose stream no other call to `rng` will reuse.
+    pub fn rng(&self) -> ChaCha20Rng {
+        let mut rng = ChaCha20Rng::from_seed(self.seed);
+        rng.set_stream(self.next_stream.fetch_add(1, Ordering::Relaxed));
+        rng
+    }
+
+    /// A matrix of random field elements.
+    pub fn random_matrix<F: Field>(&self, width: usize, height: usize) -> RowMajorMatrix<F> {
+        let mut rng = self.rng();
+        // We reduce random 128-bit integers, which is statistically close to uniform for fields
+        // of up to 64 bits.
+        let two_to_64 = F::two().exp_power_of_2(6);
+        let values = (0..width * height)
+            .map(|_| {
+                F::from_wrapped_u64(rng.next_u64()) * two_to_64
+                    + F::from_wrapped_u64(rng.next_u64())
+            })
+            .collect();
+        RowMajorMatrix::new(values, width)
+    }
+}
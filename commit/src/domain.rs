@@ -0,0 +1,161 @@
+//! The evaluation domains of a univariate PCS, as seen by a STARK.
+
+use alloc::vec::Vec;
+
+use p3_challenger::FieldChallenger;
+use p3_field::{ExtensionField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{Dimensions, MatrixGet};
+
+use crate::{BlindingRng, Pcs, UnivariatePcs};
+
+/// The values of the selectors of a trace domain, and of the inverse of its vanishing polynomial,
+/// at a point or over a domain.
+#[derive(Clone, Debug)]
+pub struct LagrangeSelectors<T> {
+    /// A multiple of the Lagrange polynomial of the first row, which is nonzero there.
+    pub is_first_row: T,
+    /// A multiple of the Lagrange polynomial of the last row, which is nonzero there.
+    pub is_last_row: T,
+    /// For each `size` in `1..=window_size`, a polynomial which vanishes on the last `size - 1`
+    /// rows and nowhere else on the trace domain.
+    pub is_transition: Vec<T>,
+    pub inv_zerofier: T,
+}
+
+/// A univariate PCS together with the geometry of the domains it commits over: everything a
+/// univariate STARK needs to know about its PCS beyond committing and opening.
+///
+/// A committed matrix of height `n = 2^log_height` holds the evaluations of its polynomials over a
+/// trace domain of that height, whose points are in a cyclic order: some map takes each point to
+/// the next one, and the last one back to the first. Points outside the trace domain, such as
+/// opening points, are encoded as single elements of `EF`, and `next_point` applies the same map
+/// to them.
+///
+/// A quotient domain is a larger domain, disjoint from the trace domain, over which the prover
+/// evaluates the constraints. It is in the same kind of order, such that the next point of the row
+/// `2^(log_quotient_height - log_height)` rows after any row is that row's next point.
+///
+/// The trace domain's polynomials are those of degree less than `n`, in the appropriate sense of
+/// degree, and a constraint of degree `d` has a quotient of degree less than `2^log_quotient_degree`
+/// times that, with `2^log_quotient_degree >= d - 1`.
+pub trait StarkPcs<Val, EF, Challenger>:
+    UnivariatePcs<Val, EF, RowMajorMatrix<Val>, Challenger>
+where
+    Val: Field,
+    EF: ExtensionField<Val>,
+    Challenger: FieldChallenger<Val>,
+{
+    /// The evaluations of a committed matrix's polynomials over a quotient domain.
+    type QuotientDomainLde<'a>: MatrixGet<Val> + Sync
+    where
+        Self: 'a;
+
+    fn log_blowup(&self) -> usize;
+
+    /// Parameters of the domains besides their heights, such as the shift of a coset, so that
+    /// configs can be told apart by them.
+    fn domain_params(&self) -> Vec<Val>;
+
+    /// The largest log height of a trace domain.
+    fn max_log_height(&self) -> usize;
+
+    /// The image of `point` under the map which takes each point of the trace domain of height
+    /// `2^log_height` to the next.
+    fn next_point(&self, log_height: usize, point: EF) -> EF;
+
+    /// The selectors of the trace domain of height `2^log_height` at `point`, which must lie
+    /// outside it.
+    fn selectors_at_point(
+        &self,
+        log_height: usize,
+        point: EF,
+        window_size: usize,
+    ) -> LagrangeSelectors<EF>;
+
+    /// The log height of the quotient domain for a trace domain of height `2^log_height` and
+    /// quotients of degree `2^log_quotient_degree` times the trace domain's.
+    fn log_quotient_height(&self, log_height: usize, log_quotient_degree: usize) -> usize;
+
+    /// The selectors of the trace domain of height `2^log_height` over the quotient domain of
+    /// height `2^log_quotient_height`, one value per row.
+    fn selectors_on_quotient_domain(
+        &self,
+        log_height: usize,
+        log_quotient_height: usize,
+        window_size: usize,
+    ) -> LagrangeSelectors<Vec<Val>>;
+
+    /// The evaluations of each committed matrix's polynomials over the quotient domain of height
+    /// `2^log_quotient_height`.
+    fn quotient_domain_ldes<'a, 'b>(
+        &'a self,
+        prover_data: &'b Self::ProverData,
+        log_quotient_height: usize,
+    ) -> Vec<Self::QuotientDomainLde<'b>>
+    where
+        'a: 'b;
+
+    /// Evaluate polynomials, given by their evaluations over a trace domain, over the quotient
+    /// domain of height `2^log_quotient_height`.
+    fn evaluate_on_quotient_domain(
+        &self,
+        evals: RowMajorMatrix<Val>,
+        log_quotient_height: usize,
+    ) -> RowMajorMatrix<Val>;
+
+    /// Evaluate polynomials, given by their evaluations over a trace domain, at `point`.
+    fn evaluate_at_point(&self, evals: RowMajorMatrix<Val>, point: EF) -> Vec<EF>;
+
+    /// Commit to a quotient, given by its evaluations over the quotient domain, in a way which
+    /// `recompose_quotient` can undo. Each extension field polynomial is committed as `D` base
+    /// field polynomials.
+    ///
+    /// With a `blinding_rng`, the committed polynomials must hide the quotient, in the same sense
+    /// as `blind_trace`.
+    #[allow(clippy::type_complexity)]
+    fn commit_quotient(
+        &self,
+        quotient_values: Vec<EF>,
+        log_height: usize,
+        log_quotient_degree: usize,
+        blinding_rng: Option<&BlindingRng>,
+    ) -> (
+        <Self as Pcs<Val, RowMajorMatrix<Val>>>::Commitment,
+        <Self as Pcs<Val, RowMajorMatrix<Val>>>::ProverData,
+    );
+
+    /// The dimensions of the matrix `commit_quotient` commits to.
+    fn quotient_dimensions(
+        &self,
+        log_height: usize,
+        log_quotient_degree: usize,
+        is_zk: bool,
+    ) -> Dimensions;
+
+    /// The point at which to open the committed quotient to learn its value at `zeta`.
+    fn quotient_point(&self, zeta: EF, log_quotient_degree: usize, is_zk: bool) -> EF;
+
+    /// The quotient's value at `zeta`, given the values of the committed extension field
+    /// polynomials at `quotient_point(zeta)`.
+    fn recompose_quotient(
+        &self,
+        parts: &[EF],
+        zeta: EF,
+        log_height: usize,
+        log_quotient_degree: usize,
+        is_zk: bool,
+    ) -> EF;
+
+    /// Blind a trace for zero-knowledge mode, doubling its height, so that its evaluations at any
+    /// fewer than `n` points outside the trace domain are uniformly random.
+    fn blind_trace(
+        &self,
+        trace: RowMajorMatrix<Val>,
+        blinding_rng: &BlindingRng,
+    ) -> RowMajorMatrix<Val>;
+
+    /// Extend a trace which the verifier knows to the same height as a blinded trace, without
+    /// blinding it.
+    fn extend_trace(&self, trace: RowMajorMatrix<Val>) -> RowMajorMatrix<Val>;
+}
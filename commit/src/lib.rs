@@ -5,9 +5,14 @@
 extern crate alloc;
 
 mod adapters;
+mod blinding;
+mod domain;
 mod mmcs;
 mod pcs;
+pub mod two_adic;
 
 pub use adapters::*;
+pub use blinding::*;
+pub use domain::*;
 pub use mmcs::*;
 pub use pcs::*;
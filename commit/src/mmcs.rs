@@ -23,6 +23,8 @@ pub trait Mmcs<T>: Clone {
     type ProverData: Clone;
     type Commitment: Clone + Serialize + DeserializeOwned;
     type Proof: Serialize + DeserializeOwned + Clone;
+    /// A proof for openings at several indices at once, as produced by `open_multi_batch`.
+    type MultiProof: Serialize + DeserializeOwned + Clone;
     type Error: Debug;
     type Mat<'a>: MatrixRows<T> + Sync
     where
@@ -38,6 +40,23 @@ pub trait Mmcs<T>: Clone {
         prover_data: &Self::ProverData,
     ) -> (Vec<Vec<T>>, Self::Proof);
 
+    /// Opens a batch of rows at each of `indices`, with a single proof for all of them.
+    /// returns `(openings, proof)`
+    /// where `openings[k]` is the batch of rows `open_batch(indices[k], ..)` would open. The proof
+    /// may be smaller than the proofs of each index together, e.g. if it shares the data common to
+    /// several of them.
+    fn open_multi_batch(
+        &self,
+        indices: &[usize],
+        prover_data: &Self::ProverData,
+    ) -> (Vec<Vec<Vec<T>>>, Self::MultiProof);
+
+    /// The log of the number of digests in a commitment, for MMCSs which commit to a cap of their
+    /// Merkle trees rather than a single root.
+    fn cap_height(&self) -> usize {
+        0
+    }
+
     /// Get the matrices that were committed to.
     fn get_matrices<'a>(&'a self, prover_data: &'a Self::ProverData) -> Vec<Self::Mat<'a>>;
 
@@ -69,6 +88,18 @@ pub trait Mmcs<T>: Clone {
         opened_values: &[Vec<T>],
         proof: &Self::Proof,
     ) -> Result<(), Self::Error>;
+
+    /// Verify openings produced by `open_multi_batch`.
+    /// `opened_values[k]` is the batch of rows opened at `indices[k]`, and `dimensions` is as in
+    /// `verify_batch`.
+    fn verify_multi_batch(
+        &self,
+        commit: &Self::Commitment,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        opened_values: &[Vec<Vec<T>>],
+        proof: &Self::MultiProof,
+    ) -> Result<(), Self::Error>;
 }
 
 /// An MMCS over explicit inputs which are supplied upfront.
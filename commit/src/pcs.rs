@@ -8,12 +8,16 @@ use p3_challenger::FieldChallenger;
 use p3_field::{ExtensionField, Field};
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::{Dimensions, MatrixGet, MatrixRows};
+use p3_util::codec::encoded_len;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 /// A (not necessarily hiding) polynomial commitment scheme, for committing to (batches of)
 /// polynomials defined over the field `F`.
 ///
+/// Hiding implementations, such as the hiding mode of `TwoAdicFriPcs`, draw their randomness from a
+/// `BlindingRng`.
+///
 /// This high-level trait is agnostic with respect to the structure of a point; see `UnivariatePCS`
 /// and `MultivariatePcs` for more specific subtraits.
 // TODO: Should we have a super-trait for weakly-binding PCSs, like FRI outside unique decoding radius?
@@ -34,6 +38,46 @@ pub trait Pcs<Val: Field, In: MatrixRows<Val>> {
     fn commit_batch(&self, polynomials: In) -> (Self::Commitment, Self::ProverData) {
         self.commit_batches(vec![polynomials])
     }
+
+    /// The number of positions, such as FRI's query indices, at which an opening proof reveals the
+    /// committed data in addition to the values at the opening points.
+    fn num_queries(&self) -> usize;
+
+    /// Parameters besides `num_queries` which determine the shape of opening proofs, such as FRI's
+    /// folding arity or the cap height of an MMCS, so that configs can be told apart by them.
+    fn shape_params(&self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    /// A breakdown of the size of `proof` in the canonical encoding of `p3_util::codec`.
+    /// Implementations which don't break their proofs down count every byte as `other`.
+    fn proof_size(&self, proof: &Self::Proof) -> ProofSize {
+        ProofSize {
+            other: encoded_len(proof),
+            ..ProofSize::default()
+        }
+    }
+}
+
+/// The number of bytes taken up by each kind of data in an encoded proof.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProofSize {
+    /// MMCS opening proofs, such as Merkle authentication paths and their salts.
+    pub merkle_paths: usize,
+    /// Values of FRI commit-phase codewords opened at query points, excluding their Merkle paths.
+    pub commit_phase_openings: usize,
+    /// Claimed evaluations of committed polynomials, whether at out-of-domain points or at query
+    /// points.
+    pub opened_values: usize,
+    /// Everything else, such as commitments, final polynomials, proof-of-work witnesses and length
+    /// prefixes.
+    pub other: usize,
+}
+
+impl ProofSize {
+    pub fn total(&self) -> usize {
+        self.merkle_paths + self.commit_phase_openings + self.opened_values + self.other
+    }
 }
 
 pub type OpenedValues<F> = Vec<OpenedValuesForRound<F>>;
@@ -114,6 +158,12 @@ where
     }
 }
 
+/// A PCS for multilinear polynomials, each column of a committed matrix holding the evaluations of
+/// one over the boolean hypercube, where the first variable is the most significant bit of the row
+/// index.
+///
+/// Points are given for each matrix, like in `UnivariatePcs`, and each point has one coordinate per
+/// variable.
 pub trait MultivariatePcs<Val, EF, In, Challenger>: Pcs<Val, In>
 where
     Val: Field,
@@ -121,16 +171,20 @@ where
     In: MatrixRows<Val>,
     Challenger: FieldChallenger<Val>,
 {
+    #[allow(clippy::type_complexity)]
     fn open_multi_batches(
         &self,
-        prover_data_and_points: &[(&Self::ProverData, &[Vec<EF>])],
+        prover_data_and_points: &[(&Self::ProverData, &[Vec<Vec<EF>>])],
         challenger: &mut Challenger,
     ) -> (OpenedValues<EF>, Self::Proof);
 
+    #[allow(clippy::type_complexity)]
     fn verify_multi_batches(
         &self,
-        commits_and_points: &[(Self::Commitment, &[Vec<EF>])],
+        commits_and_points: &[(Self::Commitment, &[Vec<Vec<EF>>])],
+        dims: &[Vec<Dimensions>],
         values: OpenedValues<EF>,
         proof: &Self::Proof,
+        challenger: &mut Challenger,
     ) -> Result<(), Self::Error>;
 }
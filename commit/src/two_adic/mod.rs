@@ -0,0 +1,222 @@
+//! Helpers for implementing `StarkPcs` for PCSs which commit over two-adic subgroups and their
+//! cosets, such as `TwoAdicFriPcs`.
+//!
+//! The trace domain of height `n` is the subgroup `H` of order `n`, in the order of the powers of
+//! its generator `g`, and a point's next point is its product with `g`. The quotient domain is the
+//! coset `s K` of a larger subgroup `K` by the PCS's coset shift `s`, in the same order.
+//!
+//! The quotient is committed in `2^log_quotient_degree` chunks of degree less than `n`, which are
+//! interleaved by their coefficients, or in zero-knowledge mode, blinded and split over the cosets
+//! of `H` in `s K`.
+
+mod decompose;
+mod selectors;
+mod zerofier_coset;
+mod zk;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub use decompose::*;
+use p3_challenger::FieldChallenger;
+use p3_dft::{Radix2Dit, TwoAdicSubgroupDft};
+use p3_field::{
+    cyclic_subgroup_coset_known_order, AbstractExtensionField, AbstractField, ExtensionField,
+    TwoAdicField,
+};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::strided::VerticallyStridedMatrixView;
+use p3_matrix::{Dimensions, Matrix, MatrixRows};
+use p3_util::{log2_strict_usize, reverse_slice_index_bits};
+pub use selectors::*;
+pub use zerofier_coset::*;
+pub use zk::*;
+
+use crate::{BlindingRng, LagrangeSelectors, Pcs, UnivariatePcsWithLde};
+
+/// The product of `point` with the generator of `H`.
+pub fn next_point<F: TwoAdicField, EF: ExtensionField<F>>(log_height: usize, point: EF) -> EF {
+    point * F::two_adic_generator(log_height)
+}
+
+/// The selectors of `H` at a point outside it.
+pub fn selectors_at_point<F: TwoAdicField, EF: ExtensionField<F>>(
+    log_height: usize,
+    point: EF,
+    window_size: usize,
+) -> LagrangeSelectors<EF> {
+    let g = F::two_adic_generator(log_height);
+    let z_h = point.exp_power_of_2(log_height) - EF::one();
+    LagrangeSelectors {
+        is_first_row: z_h / (point - F::one()),
+        is_last_row: z_h / (point - g.inverse()),
+        is_transition: transition_selectors(point, g, window_size),
+        inv_zerofier: z_h.inverse(),
+    }
+}
+
+/// The selectors of `H` over its coset `shift K`, where `K` has order `2^log_quotient_height`.
+pub fn selectors_on_coset<F: TwoAdicField>(
+    log_height: usize,
+    log_quotient_height: usize,
+    shift: F,
+    window_size: usize,
+) -> LagrangeSelectors<Vec<F>> {
+    let quotient_height = 1 << log_quotient_height;
+    let zerofier = ZerofierOnCoset::new(log_height, log_quotient_height - log_height, shift);
+    let g = F::two_adic_generator(log_height);
+
+    let mut is_transition = vec![Vec::with_capacity(quotient_height); window_size.max(1)];
+    let coset = cyclic_subgroup_coset_known_order(
+        F::two_adic_generator(log_quotient_height),
+        shift,
+        quotient_height,
+    );
+    for x in coset {
+        for (selector, value) in is_transition
+            .iter_mut()
+            .zip(transition_selectors(x, g, window_size))
+        {
+            selector.push(value);
+        }
+    }
+
+    LagrangeSelectors {
+        is_first_row: zerofier.lagrange_basis_unnormalized(0),
+        is_last_row: zerofier.lagrange_basis_unnormalized((1 << log_height) - 1),
+        is_transition,
+        inv_zerofier: (0..quotient_height)
+            .map(|i| zerofier.eval_inverse(i))
+            .collect(),
+    }
+}
+
+/// Restrict LDEs over cosets at least `2^log_quotient_height` high to the coset of that height.
+pub fn quotient_domain_ldes<F, M: MatrixRows<F>>(
+    ldes: Vec<M>,
+    log_quotient_height: usize,
+) -> Vec<VerticallyStridedMatrixView<M>> {
+    ldes.into_iter()
+        .map(|lde| {
+            let log_stride = log2_strict_usize(lde.height())
+                .checked_sub(log_quotient_height)
+                .expect("the PCS's blowup factor is too small for the quotient degree");
+            lde.vertically_strided(1 << log_stride, 0)
+        })
+        .collect()
+}
+
+/// Evaluate polynomials, given by their evaluations over `H`, over its coset `shift K`, where `K`
+/// has order `2^log_quotient_height`.
+pub fn evaluate_on_coset<F: TwoAdicField>(
+    evals: RowMajorMatrix<F>,
+    log_quotient_height: usize,
+    shift: F,
+) -> RowMajorMatrix<F> {
+    let added_bits = log_quotient_height - log2_strict_usize(evals.height());
+    Radix2Dit::default()
+        .coset_lde_batch(evals, added_bits, shift)
+        .to_row_major_matrix()
+}
+
+/// Evaluate polynomials, given by their evaluations over `H`, at `point`.
+pub fn evaluate_at_point<F, EF>(evals: RowMajorMatrix<F>, point: EF) -> Vec<EF>
+where
+    F: TwoAdicField,
+    EF: ExtensionField<F> + TwoAdicField,
+{
+    p3_interpolation::interpolate_subgroup(&evals, point)
+}
+
+/// Commit to the quotient, given by its evaluations over `s K`, in `2^log_quotient_degree` chunks.
+///
+/// Without blinding, the quotient `q` is decomposed as `q(X) = sum_i X^i q_i(X^d)`, and the chunks
+/// `q_i` are committed over the coset of `H` by `s^d`. With blinding, they are blinded by
+/// `blind_quotient_chunks` and committed over `s H_{2n}`.
+#[allow(clippy::type_complexity)]
+pub fn commit_quotient<F, EF, Challenger, P>(
+    pcs: &P,
+    quotient_values: Vec<EF>,
+    log_height: usize,
+    log_quotient_degree: usize,
+    blinding_rng: Option<&BlindingRng>,
+) -> (
+    <P as Pcs<F, RowMajorMatrix<F>>>::Commitment,
+    <P as Pcs<F, RowMajorMatrix<F>>>::ProverData,
+)
+where
+    F: TwoAdicField,
+    EF: ExtensionField<F> + TwoAdicField,
+    Challenger: FieldChallenger<F>,
+    P: UnivariatePcsWithLde<F, EF, RowMajorMatrix<F>, Challenger>,
+{
+    let shift = pcs.coset_shift();
+    let (chunks, chunks_shift) = match blinding_rng {
+        Some(blinding_rng) => (
+            blind_quotient_chunks(
+                &quotient_values,
+                shift,
+                log_height,
+                log_quotient_degree,
+                blinding_rng,
+            ),
+            shift,
+        ),
+        None => (
+            decompose_and_flatten(quotient_values, EF::from_base(shift), log_quotient_degree),
+            shift.exp_power_of_2(log_quotient_degree),
+        ),
+    };
+    pcs.commit_shifted_batch(chunks, chunks_shift)
+}
+
+/// The dimensions of the chunks `commit_quotient` commits to.
+pub fn quotient_dimensions<F, EF>(
+    log_height: usize,
+    log_quotient_degree: usize,
+    is_zk: bool,
+) -> Dimensions
+where
+    F: TwoAdicField,
+    EF: ExtensionField<F>,
+{
+    Dimensions {
+        width: <EF as AbstractExtensionField<F>>::D << log_quotient_degree,
+        height: 1 << (log_height + usize::from(is_zk)),
+    }
+}
+
+/// In zero-knowledge mode, the quotient chunks are polynomials in the same variable as the
+/// quotient; otherwise in its `2^log_quotient_degree`th power.
+pub fn quotient_point<EF: AbstractField>(zeta: EF, log_quotient_degree: usize, is_zk: bool) -> EF {
+    if is_zk {
+        zeta
+    } else {
+        zeta.exp_power_of_2(log_quotient_degree)
+    }
+}
+
+/// Recombine the openings of the chunks `commit_quotient` commits to into the quotient's.
+pub fn recompose_quotient<F, EF>(
+    parts: &[EF],
+    zeta: EF,
+    shift: F,
+    log_height: usize,
+    log_quotient_degree: usize,
+    is_zk: bool,
+) -> EF
+where
+    F: TwoAdicField,
+    EF: ExtensionField<F>,
+{
+    if is_zk {
+        recombine_blinded_quotient(parts, zeta, shift, log_height, log_quotient_degree)
+    } else {
+        let mut parts = parts.to_vec();
+        reverse_slice_index_bits(&mut parts);
+        zeta.powers()
+            .zip(parts)
+            .map(|(weight, part)| part * weight)
+            .sum()
+    }
+}
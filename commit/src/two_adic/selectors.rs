@@ -0,0 +1,29 @@
+use alloc::vec::Vec;
+use core::ops::Sub;
+
+use p3_field::{AbstractField, Field};
+
+/// Evaluate `is_transition_window(size)` at `x` for each `size` in `1..=window_size`, where `g`
+/// generates the trace domain.
+///
+/// The selector for windows of `size` rows is `prod_{j=1}^{size-1} (x - g^{-j})`, which vanishes on
+/// the last `size - 1` rows of the trace.
+pub fn transition_selectors<F, Point>(x: Point, g: F, window_size: usize) -> Vec<Point>
+where
+    F: Field,
+    Point: AbstractField + Sub<F, Output = Point>,
+{
+    let mut selector = Point::one();
+    let mut selectors = Vec::with_capacity(window_size);
+    selectors.push(selector.clone());
+    for g_inv_pow in g
+        .inverse()
+        .powers()
+        .skip(1)
+        .take(window_size.saturating_sub(1))
+    {
+        selector *= x.clone() - g_inv_pow;
+        selectors.push(selector.clone());
+    }
+    selectors
+}
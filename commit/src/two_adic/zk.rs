@@ -0,0 +1,241 @@
+//! Helpers for zero-knowledge mode, in which committed traces and quotient chunks have twice the
+//! trace height.
+
+use alloc::vec::Vec;
+
+use p3_dft::{Radix2Dit, TwoAdicSubgroupDft};
+use p3_field::{AbstractExtensionField, Field, TwoAdicField};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{Matrix, MatrixGet, MatrixRowSlices};
+
+use crate::BlindingRng;
+
+/// Blind a trace by interleaving its rows with random rows.
+///
+/// Over the subgroup of twice the trace height, the even rows lie on the trace subgroup `H`, so the
+/// result encodes `t + Z_H r` for the trace polynomial `t` and a uniformly random `r` of degree
+/// less than `n`. Its evaluations at any fewer than `n` points outside `H` are uniformly random.
+///
+/// A proof reveals the trace at the out-of-domain points of the AIR's window, and also at one point
+/// of its LDE for each FRI query, so it only hides the trace if `window_size + num_queries < n`.
+pub fn blind_trace<F: Field>(
+    trace: RowMajorMatrix<F>,
+    blinding_rng: &BlindingRng,
+) -> RowMajorMatrix<F> {
+    let width = trace.width();
+    let random = blinding_rng.random_matrix::<F>(width, trace.height());
+    let values = trace
+        .values
+        .chunks_exact(width)
+        .zip(random.values.chunks_exact(width))
+        .flat_map(|(row, random_row)| row.iter().chain(random_row))
+        .copied()
+        .collect();
+    RowMajorMatrix::new(values, width)
+}
+
+/// Extend a trace which the verifier knows, such as a preprocessed trace, to the subgroup of twice
+/// its height without blinding it.
+pub fn extend_trace<F: TwoAdicField>(trace: RowMajorMatrix<F>) -> RowMajorMatrix<F> {
+    Radix2Dit::default().lde_batch(trace, 1)
+}
+
+/// Split the quotient, given by its evaluations over the quotient domain `s H'`, into one chunk per
+/// coset of the trace subgroup `H` in `s H'`, blinded so that their openings reveal nothing beyond
+/// the quotient's.
+///
+/// With `g` generating `H'`, the `d` cosets are `D_i = s g^i H`, whose vanishing polynomials are
+/// `X^n - y_i` with `y_i = (s g^i)^n`. If `q_i` interpolates the quotient `q` over `D_i`, then
+/// ```ignore
+/// q(X) = sum_i L_i(X) q_i(X),    L_i(X) = prod_{j != i} (X^n - y_j) / (y_i - y_j).
+/// ```
+/// Each chunk is committed as `q_i + (X^n - y_i) r_i` instead, for random `r_i` of degree less than
+/// `n` such that `sum_i w^i r_i = 0`, where `w = g^n`. As `prod_{j != i} (y_i - y_j)` is
+/// proportional to `w^(-i)`, the random terms cancel in the sum. The blinded chunks have degree
+/// less than `2n`, and are returned as their evaluations over `s H_{2n}`, each flattened into `D`
+/// base field columns.
+///
+/// Like a blinded trace, each chunk is only hidden if a proof reveals it at fewer than `n` points:
+/// the quotient point, plus one for each query.
+pub fn blind_quotient_chunks<Val, Challenge>(
+    quotient_values: &[Challenge],
+    shift: Val,
+    degree_bits: usize,
+    quotient_degree_bits: usize,
+    blinding_rng: &BlindingRng,
+) -> RowMajorMatrix<Val>
+where
+    Val: TwoAdicField,
+    Challenge: AbstractExtensionField<Val>,
+{
+    let dft = Radix2Dit::default();
+    let degree = 1 << degree_bits;
+    let num_chunks = 1 << quotient_degree_bits;
+    assert!(num_chunks > 1, "a single quotient chunk can't be blinded");
+    let ext_degree = Challenge::D;
+    let g = Val::two_adic_generator(degree_bits + quotient_degree_bits);
+    let w = g.exp_power_of_2(degree_bits);
+
+    // The coefficients of the random polynomials, as D base field columns per chunk. The last one
+    // is set to -w sum_{i < d - 1} w^i r_i, so that sum_i w^i r_i = 0.
+    let mut random = blinding_rng.random_matrix::<Val>(ext_degree * num_chunks, degree);
+    for row in random.rows_mut() {
+        let (others, last) = row.split_at_mut(ext_degree * (num_chunks - 1));
+        last.fill(Val::zero());
+        for (r_i, weight) in others.chunks_exact(ext_degree).zip(w.powers().skip(1)) {
+            for (l, &r) in last.iter_mut().zip(r_i) {
+                *l -= weight * r;
+            }
+        }
+    }
+
+    let chunks: Vec<RowMajorMatrix<Val>> = (0..num_chunks)
+        .map(|i| {
+            let chunk_shift = shift * g.exp_u64(i as u64);
+            // The quotient's evaluations over D_i are every d-th of those over s H', starting from
+            // the i-th.
+            let evals = quotient_values
+                .iter()
+                .skip(i)
+                .step_by(num_chunks)
+                .flat_map(|value| value.as_base_slice().to_vec())
+                .collect();
+            let mut coeffs = dft.idft_batch(RowMajorMatrix::new(evals, ext_degree));
+            // The coefficients of `q_i(X)` are those of `q_i(chunk_shift X)` scaled by the powers of
+            // `chunk_shift^-1`.
+            for (r, scale) in chunk_shift.inverse().powers().take(degree).enumerate() {
+                coeffs.scale_row(r, scale);
+            }
+
+            coeffs.expand_to_height(2 * degree);
+            let y_i = chunk_shift.exp_power_of_2(degree_bits);
+            for k in 0..degree {
+                for c in 0..ext_degree {
+                    let r = random.get(k, i * ext_degree + c);
+                    coeffs.row_mut(k)[c] -= y_i * r;
+                    coeffs.row_mut(degree + k)[c] += r;
+                }
+            }
+            dft.coset_dft_batch(coeffs, shift)
+        })
+        .collect();
+
+    let values = (0..2 * degree)
+        .flat_map(|row| chunks.iter().flat_map(move |chunk| chunk.row_slice(row)))
+        .copied()
+        .collect();
+    RowMajorMatrix::new(values, ext_degree * num_chunks)
+}
+
+/// Recombine the openings at `zeta` of the chunks produced by `blind_quotient_chunks` into the
+/// quotient's, i.e. `q(zeta) = sum_i L_i(zeta) q_i(zeta)`.
+pub fn recombine_blinded_quotient<Val, Challenge>(
+    chunks: &[Challenge],
+    zeta: Challenge,
+    shift: Val,
+    degree_bits: usize,
+    quotient_degree_bits: usize,
+) -> Challenge
+where
+    Val: TwoAdicField,
+    Challenge: AbstractExtensionField<Val> + Field,
+{
+    let g = Val::two_adic_generator(degree_bits + quotient_degree_bits);
+    let ys: Vec<Val> = g
+        .shifted_powers(shift)
+        .take(1 << quotient_degree_bits)
+        .map(|chunk_shift| chunk_shift.exp_power_of_2(degree_bits))
+        .collect();
+    let zeta_n = zeta.exp_power_of_2(degree_bits);
+    chunks
+        .iter()
+        .zip(&ys)
+        .enumerate()
+        .map(|(i, (&chunk, &y_i))| {
+            let (numerator, denominator) = ys
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .fold((Challenge::one(), Val::one()), |(num, den), (_, &y_j)| {
+                    (num * (zeta_n - y_j), den * (y_i - y_j))
+                });
+            chunk * numerator * denominator.inverse()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use p3_baby_bear::BabyBear;
+    use p3_field::extension::BinomialExtensionField;
+    use p3_field::AbstractField;
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+
+    type F = BabyBear;
+    type EF = BinomialExtensionField<F, 4>;
+
+    fn unflatten(row: &[F]) -> Vec<EF> {
+        row.chunks_exact(4).map(EF::from_base_slice).collect()
+    }
+
+    // The blinded chunks are random, but recombine to the quotient at any point.
+    #[test]
+    fn test_blinded_quotient_recombines() {
+        let mut rng = thread_rng();
+        let (degree_bits, quotient_degree_bits) = (3, 2);
+        let quotient_size = 1 << (degree_bits + quotient_degree_bits);
+        let shift = F::generator();
+
+        let coeffs: Vec<EF> = (0..quotient_size).map(|_| rng.gen()).collect();
+        let eval = |x: EF| coeffs.iter().rev().fold(EF::zero(), |acc, &c| acc * x + c);
+        let g = F::two_adic_generator(degree_bits + quotient_degree_bits);
+        let quotient_values: Vec<EF> = g
+            .shifted_powers(shift)
+            .take(quotient_size)
+            .map(|x| eval(EF::from_base(x)))
+            .collect();
+
+        let blind = |seed| {
+            let blinding_rng = BlindingRng::from_seed(seed);
+            blind_quotient_chunks(
+                &quotient_values,
+                shift,
+                degree_bits,
+                quotient_degree_bits,
+                &blinding_rng,
+            )
+        };
+        let chunks_1 = blind([1; 32]);
+        let chunks_2 = blind([2; 32]);
+
+        // Open both at a random point, by interpolating over s H_{2n}.
+        let zeta: EF = rng.gen();
+        let open = |chunks: &RowMajorMatrix<F>| -> Vec<EF> {
+            let dft = Radix2Dit::default();
+            let mut coeffs = dft.idft_batch(chunks.clone());
+            for (r, scale) in shift.inverse().powers().take(coeffs.height()).enumerate() {
+                coeffs.scale_row(r, scale);
+            }
+            let mut opened = vec![EF::zero(); chunks.width() / 4];
+            for r in (0..coeffs.height()).rev() {
+                for (o, c) in opened.iter_mut().zip(unflatten(coeffs.row_slice(r))) {
+                    *o = *o * zeta + c;
+                }
+            }
+            opened
+        };
+        let opened_1 = open(&chunks_1);
+        let opened_2 = open(&chunks_2);
+        assert_ne!(opened_1, opened_2);
+
+        for opened in [opened_1, opened_2] {
+            let quotient =
+                recombine_blinded_quotient(&opened, zeta, shift, degree_bits, quotient_degree_bits);
+            assert_eq!(quotient, eval(zeta));
+        }
+    }
+}
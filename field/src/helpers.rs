@@ -99,3 +99,24 @@ pub fn eval_poly<AF: AbstractField>(poly: &[AF], x: AF) -> AF {
     }
     acc
 }
+
+/// Computes `eq(x, point)` for each `x` in the boolean hypercube `{0, 1}^n`, where `n` is the
+/// length of `point` and `eq(x, z) = prod_i (x_i z_i + (1 - x_i)(1 - z_i))`, so that the inner
+/// product of the result with a multilinear polynomial's evaluations over the hypercube is its
+/// value at `point`.
+///
+/// The first variable is the most significant bit of the index of `x`.
+pub fn eq_evals<F: Field>(point: &[F]) -> Vec<F> {
+    let mut evals = Vec::with_capacity(1 << point.len());
+    evals.push(F::one());
+    for &z in point {
+        evals = evals
+            .into_iter()
+            .flat_map(|e| {
+                let hi = e * z;
+                [e - hi, hi]
+            })
+            .collect();
+    }
+    evals
+}
@@ -149,3 +149,63 @@ unsafe impl<F: Field> PackedField for F {
         }
     }
 }
+
+/// A value, or a packed vector of `WIDTH` values, such as the words of a hash digest.
+///
+/// Every `PackedField` is a `PackedValue` of its scalars. Plain integers are implemented as
+/// vectors of width 1, so that data like byte digests can be used where packed values are
+/// expected.
+pub trait PackedValue: 'static + Copy + Send + Sync {
+    type Value: 'static + Copy + Default + Send + Sync;
+
+    const WIDTH: usize;
+
+    /// Similar to `core:array::from_fn`.
+    fn from_fn<F>(f: F) -> Self
+    where
+        F: FnMut(usize) -> Self::Value;
+
+    fn as_slice(&self) -> &[Self::Value];
+}
+
+impl<P: PackedField> PackedValue for P {
+    type Value = P::Scalar;
+
+    const WIDTH: usize = <P as PackedField>::WIDTH;
+
+    fn from_fn<F>(f: F) -> Self
+    where
+        F: FnMut(usize) -> Self::Value,
+    {
+        <P as PackedField>::from_fn(f)
+    }
+
+    fn as_slice(&self) -> &[Self::Value] {
+        <P as PackedField>::as_slice(self)
+    }
+}
+
+macro_rules! impl_packed_value_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl PackedValue for $t {
+                type Value = Self;
+
+                const WIDTH: usize = 1;
+
+                fn from_fn<F>(mut f: F) -> Self
+                where
+                    F: FnMut(usize) -> Self::Value,
+                {
+                    f(0)
+                }
+
+                fn as_slice(&self) -> &[Self::Value] {
+                    slice::from_ref(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_packed_value_for_int!(u8, u32, u64);
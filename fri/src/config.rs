@@ -1,13 +1,86 @@
-#[derive(Clone, Default)]
+use alloc::vec::Vec;
+
+#[derive(Clone)]
 pub struct FriConfig<M> {
     pub log_blowup: usize,
+    /// The log of the number of evaluations folded into one in each commit phase round, so `1`
+    /// for the usual folding by two. Higher arities mean fewer rounds, and hence fewer Merkle
+    /// openings per query, at the cost of larger leaves.
+    pub log_folding_arity: usize,
+    /// The log of the number of coefficients of the final polynomial. Folding stops once codewords
+    /// have `2^(log_blowup + log_final_poly_len)` evaluations, and the prover sends the final
+    /// polynomial's coefficients instead of committing to further codewords.
+    pub log_final_poly_len: usize,
     pub num_queries: usize,
     pub proof_of_work_bits: usize,
     pub mmcs: M,
 }
 
+impl<M: Default> Default for FriConfig<M> {
+    fn default() -> Self {
+        Self {
+            log_blowup: 0,
+            log_folding_arity: 1,
+            log_final_poly_len: 0,
+            num_queries: 0,
+            proof_of_work_bits: 0,
+            mmcs: M::default(),
+        }
+    }
+}
+
 impl<M> FriConfig<M> {
     pub fn blowup(&self) -> usize {
         1 << self.log_blowup
     }
+
+    pub fn folding_arity(&self) -> usize {
+        1 << self.log_folding_arity
+    }
+
+    /// The log height of the codewords once folding stops, given the log heights of the input
+    /// codewords. This is `log_blowup + log_final_poly_len`, unless an input is smaller, in which
+    /// case folding continues down to its height so that it can be added in.
+    pub fn log_final_height(&self, input_log_heights: impl IntoIterator<Item = usize>) -> usize {
+        input_log_heights
+            .into_iter()
+            .fold(self.log_blowup + self.log_final_poly_len, usize::min)
+    }
+
+    /// The log height of the codeword committed to in each commit phase round, given the log
+    /// heights of the input codewords.
+    ///
+    /// Each round folds by the folding arity, except that it folds less rather than skip past the
+    /// height of an input, which must be added in between rounds, or past the final height.
+    pub fn commit_phase_log_heights(
+        &self,
+        input_log_heights: impl IntoIterator<Item = usize>,
+    ) -> Vec<usize> {
+        assert!(
+            self.log_folding_arity > 0,
+            "the folding arity must be at least 2"
+        );
+        let mut input_log_heights: Vec<usize> = input_log_heights.into_iter().collect();
+        input_log_heights.sort_unstable_by(|a, b| b.cmp(a));
+        let log_final_height = self.log_final_height(input_log_heights.iter().copied());
+
+        let mut log_heights = Vec::new();
+        let mut log_height = input_log_heights
+            .first()
+            .copied()
+            .unwrap_or(log_final_height);
+        while log_height > log_final_height {
+            log_heights.push(log_height);
+            let next_input_log_height = input_log_heights
+                .iter()
+                .copied()
+                .find(|&h| h < log_height)
+                .unwrap_or(0);
+            log_height = log_height
+                .saturating_sub(self.log_folding_arity)
+                .max(log_final_height)
+                .max(next_input_log_height);
+        }
+        log_heights
+    }
 }
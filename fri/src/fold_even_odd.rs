@@ -48,11 +48,57 @@ pub fn fold_even_odd<F: TwoAdicField>(poly: Vec<F>, beta: F) -> Vec<F> {
         .collect()
 }
 
+/// Fold a polynomial
+/// ```ignore
+/// p(x) = sum_j x^j p_j(x^arity)
+/// ```
+/// into
+/// ```ignore
+/// sum_j beta^j p_j(x)
+/// ```
+/// where `arity = 2^log_arity`, by folding it in half `log_arity` times with `beta, beta^2, ...`.
+/// Expects input to be bit-reversed evaluations.
+pub fn fold_by_arity<F: TwoAdicField>(mut poly: Vec<F>, mut beta: F, log_arity: usize) -> Vec<F> {
+    for _ in 0..log_arity {
+        poly = fold_even_odd(poly, beta);
+        beta = beta.square();
+    }
+    poly
+}
+
+/// Evaluate the fold of `p`, as in `fold_by_arity`, at `x^arity`, by interpolating `p` from its
+/// evaluations on the coset `x <w>`, where `w` generates the subgroup of order `arity`. Expects
+/// the evaluations in bit-reversed order, i.e. the `i`th is at `x w^reverse_bits(i)`.
+pub fn fold_coset<F: TwoAdicField>(evals: &[F], x: F, mut beta: F) -> F {
+    let log_arity = log2_strict_usize(evals.len());
+    let mut xs = F::two_adic_generator(log_arity)
+        .shifted_powers(x)
+        .take(evals.len())
+        .collect_vec();
+    reverse_slice_index_bits(&mut xs);
+
+    let mut evals = evals.to_vec();
+    while evals.len() > 1 {
+        // In bit-reversed order, each pair of adjacent points has the form (y, -y). Interpolate
+        // the line through each pair of evaluations and evaluate it at beta.
+        evals = evals
+            .chunks_exact(2)
+            .zip(xs.chunks_exact(2))
+            .map(|(e, y)| e[0] + (beta - y[0]) * (e[1] - e[0]) / (y[1] - y[0]))
+            .collect();
+        xs = xs.iter().step_by(2).map(|y| y.square()).collect();
+        beta = beta.square();
+    }
+    evals[0]
+}
+
 #[cfg(test)]
 mod tests {
     use itertools::{izip, Itertools};
     use p3_baby_bear::BabyBear;
     use p3_dft::{Radix2Dit, TwoAdicSubgroupDft};
+    use p3_field::AbstractField;
+    use p3_util::reverse_bits_len;
     use rand::{thread_rng, Rng};
 
     use super::*;
@@ -89,4 +135,28 @@ mod tests {
 
         assert_eq!(expected, folded);
     }
+
+    #[test]
+    fn test_fold_coset() {
+        type F = BabyBear;
+
+        let mut rng = thread_rng();
+
+        let log_n = 8;
+        let log_arity = 3;
+        let coeffs = (0..1 << log_n).map(|_| rng.gen::<F>()).collect::<Vec<_>>();
+        let mut evals = Radix2Dit::default().dft(coeffs);
+        reverse_slice_index_bits(&mut evals);
+
+        let beta = rng.gen::<F>();
+        let folded = fold_by_arity(evals.clone(), beta, log_arity);
+
+        // In bit-reversed order, each chunk of `arity` evaluations is a coset, and the folded
+        // codeword holds the fold's value on the coset's image under `x -> x^arity`.
+        let g = F::two_adic_generator(log_n);
+        for (i, coset_evals) in evals.chunks(1 << log_arity).enumerate() {
+            let x = g.exp_u64(reverse_bits_len(i << log_arity, log_n) as u64);
+            assert_eq!(fold_coset(coset_evals, x, beta), folded[i]);
+        }
+    }
 }
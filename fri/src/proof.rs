@@ -1,4 +1,5 @@
 use alloc::vec::Vec;
+use core::marker::PhantomData;
 
 use p3_commit::Mmcs;
 use p3_field::Field;
@@ -18,12 +19,44 @@ where
 {
     pub(crate) commit_phase_commits: Vec<M::Commitment>,
     pub(crate) query_proofs: Vec<QueryProof<F, M>>,
-    // This could become Vec<FC::Challenge> if this library was generalized to support non-constant
-    // final polynomials.
-    pub(crate) final_poly: F,
+    /// For each commit phase commitment, a proof of its openings at all queried cosets.
+    pub(crate) commit_phase_opening_proofs: Vec<M::MultiProof>,
+    /// The coefficients of the final polynomial, starting with the constant term.
+    pub(crate) final_poly: Vec<F>,
     pub(crate) pow_witness: Witness,
 }
 
+impl<F, M, Witness> FriProof<F, M, Witness>
+where
+    F: Field + Send + Sync,
+    M: Mmcs<F>,
+    M::Commitment: Send + Sync,
+    M::Proof: Send + Sync,
+    Witness: Send + Sync,
+{
+    pub fn commit_phase_commits(&self) -> &[M::Commitment] {
+        &self.commit_phase_commits
+    }
+
+    pub fn query_proofs(&self) -> &[QueryProof<F, M>] {
+        &self.query_proofs
+    }
+
+    /// For each commit phase commitment, a proof of its openings at all queried cosets.
+    pub fn commit_phase_opening_proofs(&self) -> &[M::MultiProof] {
+        &self.commit_phase_opening_proofs
+    }
+
+    /// The coefficients of the final polynomial, starting with the constant term.
+    pub fn final_poly(&self) -> &[F] {
+        &self.final_poly
+    }
+
+    pub fn pow_witness(&self) -> &Witness {
+        &self.pow_witness
+    }
+}
+
 unsafe impl<F: Field + Send + Sync, M: Mmcs<F>, Witness: Send + Sync> Send
     for FriProof<F, M, Witness>
 where
@@ -41,7 +74,7 @@ where
 }
 
 /// For each commit phase commitment, this contains openings of a commit phase codeword at the
-/// queried location, along with an opening proof.
+/// queried location. They're proven for all queries at once, by the commit phase opening proofs.
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(bound = "")]
 pub struct QueryProof<F: Field, M: Mmcs<F>>
@@ -52,26 +85,45 @@ where
     pub(crate) commit_phase_openings: Vec<CommitPhaseProofStep<F, M>>,
 }
 
+impl<F: Field + Send + Sync, M: Mmcs<F>> QueryProof<F, M>
+where
+    M::Proof: Send + Sync,
+{
+    /// The opening of each commit phase codeword at the queried coset, in round order.
+    pub fn commit_phase_openings(&self) -> &[CommitPhaseProofStep<F, M>] {
+        &self.commit_phase_openings
+    }
+}
+
 unsafe impl<F: Field + Send + Sync, M: Mmcs<F>> Send for QueryProof<F, M> where M::Proof: Send + Sync
 {}
 
 unsafe impl<F: Field + Send + Sync, M: Mmcs<F>> Sync for QueryProof<F, M> where M::Proof: Send + Sync
 {}
 
-// The opening of the commit phase codeword at the sibling location.
-// This may change to Vec<FC::Challenge> if the library is generalized to support other FRI
-// folding arities besides 2, meaning that there can be multiple siblings.
+/// The opening of a commit phase codeword at the coset containing the queried location, i.e. the
+/// evaluations which are folded together with the queried one.
 #[derive(Serialize, Deserialize, Clone)]
-// #[serde(bound(serialize = "F: Serialize"))]
 #[serde(bound = "")]
 pub struct CommitPhaseProofStep<F: Field, M: Mmcs<F>>
 where
     F: Send + Sync,
     M::Proof: Send + Sync,
 {
-    pub(crate) sibling_value: F,
+    /// The coset's evaluations, in the committed order, except for the queried one.
+    pub(crate) sibling_values: Vec<F>,
 
-    pub(crate) opening_proof: M::Proof,
+    pub(crate) _phantom: PhantomData<M>,
+}
+
+impl<F: Field + Send + Sync, M: Mmcs<F>> CommitPhaseProofStep<F, M>
+where
+    M::Proof: Send + Sync,
+{
+    /// The coset's evaluations, in the committed order, except for the queried one.
+    pub fn sibling_values(&self) -> &[F] {
+        &self.sibling_values
+    }
 }
 
 unsafe impl<F: Field + Send + Sync, M: Mmcs<F>> Send for CommitPhaseProofStep<F, M> where
@@ -1,15 +1,17 @@
 use alloc::vec;
 use alloc::vec::Vec;
+use core::marker::PhantomData;
 
-use itertools::Itertools;
+use itertools::{izip, Itertools};
 use p3_challenger::{CanObserve, CanSample, GrindingChallenger};
 use p3_commit::{DirectMmcs, Mmcs};
+use p3_dft::{Radix2Dit, TwoAdicSubgroupDft};
 use p3_field::{Field, TwoAdicField};
 use p3_matrix::dense::RowMajorMatrix;
-use p3_maybe_rayon::prelude::*;
+use p3_util::reverse_slice_index_bits;
 use tracing::{info_span, instrument};
 
-use crate::fold_even_odd::fold_even_odd;
+use crate::fold_even_odd::fold_by_arity;
 use crate::{CommitPhaseProofStep, FriConfig, FriProof, QueryProof};
 
 #[instrument(name = "FRI prover", skip_all)]
@@ -37,17 +39,14 @@ where
         .map(|_| challenger.sample_bits(log_max_height))
         .collect();
 
-    let query_proofs = info_span!("query phase").in_scope(|| {
-        query_indices
-            .par_iter()
-            .map(|&index| answer_query(config, &commit_phase_result.data, index))
-            .collect()
-    });
+    let (query_proofs, commit_phase_opening_proofs) = info_span!("query phase")
+        .in_scope(|| answer_queries(config, &commit_phase_result, log_max_height, &query_indices));
 
     (
         FriProof {
             commit_phase_commits: commit_phase_result.commits,
             query_proofs,
+            commit_phase_opening_proofs,
             final_poly: commit_phase_result.final_poly,
             pow_witness,
         },
@@ -55,40 +54,65 @@ where
     )
 }
 
-fn answer_query<F, M>(
+/// Open each commit phase codeword at the cosets containing the queried locations, with a single
+/// opening proof per codeword.
+fn answer_queries<F, M>(
     config: &FriConfig<M>,
-    commit_phase_commits: &[M::ProverData],
-    index: usize,
-) -> QueryProof<F, M>
+    commit_phase_result: &CommitPhaseResult<F, M>,
+    log_max_height: usize,
+    query_indices: &[usize],
+) -> (Vec<QueryProof<F, M>>, Vec<M::MultiProof>)
 where
     F: Field,
     M: Mmcs<F>,
     M::Proof: Send + Sync,
 {
-    let commit_phase_openings = commit_phase_commits
+    let mut query_proofs = query_indices
         .iter()
-        .enumerate()
-        .map(|(i, commit)| {
-            let index_i = index >> i;
-            let index_i_sibling = index_i ^ 1;
-            let index_pair = index_i >> 1;
-
-            let (mut opened_rows, opening_proof) = config.mmcs.open_batch(index_pair, commit);
-            assert_eq!(opened_rows.len(), 1);
-            let opened_row = opened_rows.pop().unwrap();
-            assert_eq!(opened_row.len(), 2, "Committed data should be in pairs");
-            let sibling_value = opened_row[index_i_sibling % 2];
-
-            CommitPhaseProofStep {
-                sibling_value,
-                opening_proof,
+        .map(|_| QueryProof {
+            commit_phase_openings: vec![],
+        })
+        .collect_vec();
+
+    let opening_proofs = commit_phase_result
+        .log_arities()
+        .zip(&commit_phase_result.log_heights)
+        .zip(&commit_phase_result.data)
+        .map(|((log_arity, &log_height), commit)| {
+            let indices_i = query_indices
+                .iter()
+                .map(|&index| index >> (log_max_height - log_height))
+                .collect_vec();
+            let index_cosets = indices_i
+                .iter()
+                .map(|&index_i| index_i >> log_arity)
+                .collect_vec();
+
+            let (opened_rows, opening_proof) = config.mmcs.open_multi_batch(&index_cosets, commit);
+            for (query_proof, index_i, mut opened_rows) in
+                izip!(&mut query_proofs, indices_i, opened_rows)
+            {
+                assert_eq!(opened_rows.len(), 1);
+                let mut sibling_values = opened_rows.pop().unwrap();
+                assert_eq!(
+                    sibling_values.len(),
+                    1 << log_arity,
+                    "Committed data should be in cosets"
+                );
+                sibling_values.remove(index_i & ((1 << log_arity) - 1));
+
+                query_proof
+                    .commit_phase_openings
+                    .push(CommitPhaseProofStep {
+                        sibling_values,
+                        _phantom: PhantomData,
+                    });
             }
+            opening_proof
         })
         .collect();
 
-    QueryProof {
-        commit_phase_openings,
-    }
+    (query_proofs, opening_proofs)
 }
 
 #[instrument(name = "commit phase", skip_all)]
@@ -106,34 +130,47 @@ where
 {
     let mut current = input[log_max_height].as_ref().unwrap().clone();
 
+    let input_log_heights = (0..input.len()).filter(|&log_height| input[log_height].is_some());
+    let log_heights = config.commit_phase_log_heights(input_log_heights.clone());
+    let log_final_height = config.log_final_height(input_log_heights);
     let mut commits = vec![];
     let mut data = vec![];
 
-    for log_folded_height in (config.log_blowup..log_max_height).rev() {
-        let leaves = RowMajorMatrix::new(current.clone(), 2);
+    for (i, &log_height) in log_heights.iter().enumerate() {
+        let log_folded_height = log_heights.get(i + 1).copied().unwrap_or(log_final_height);
+        let log_arity = log_height - log_folded_height;
+
+        // In bit-reversed order, each coset to be folded together is a chunk of `arity` values.
+        let leaves = RowMajorMatrix::new(current.clone(), 1 << log_arity);
         let (commit, prover_data) = config.mmcs.commit_matrix(leaves);
         challenger.observe(commit.clone());
         commits.push(commit);
         data.push(prover_data);
 
         let beta: F = challenger.sample();
-        current = fold_even_odd(current, beta);
+        current = fold_by_arity(current, beta, log_arity);
 
         if let Some(v) = &input[log_folded_height] {
             current.iter_mut().zip_eq(v).for_each(|(c, v)| *c += *v);
         }
     }
 
-    // We should be left with `blowup` evaluations of a constant polynomial.
-    assert_eq!(current.len(), config.blowup());
-    let final_poly = current[0];
-    for x in current {
-        assert_eq!(x, final_poly);
-    }
+    // We should be left with the evaluations of a polynomial with at most
+    // `2^(log_final_height - log_blowup)` coefficients, which are sent as the final polynomial.
+    reverse_slice_index_bits(&mut current);
+    let mut final_poly = Radix2Dit::default().idft(current);
+    let final_poly_len = 1 << (log_final_height - config.log_blowup);
+    assert!(
+        final_poly[final_poly_len..].iter().all(F::is_zero),
+        "The final polynomial's degree is too large"
+    );
+    final_poly.truncate(final_poly_len);
 
     CommitPhaseResult {
         commits,
         data,
+        log_heights,
+        log_final_height,
         final_poly,
     }
 }
@@ -141,5 +178,23 @@ where
 struct CommitPhaseResult<F, M: Mmcs<F>> {
     commits: Vec<M::Commitment>,
     data: Vec<M::ProverData>,
-    final_poly: F,
+    /// The log height of the codeword committed to in each round.
+    log_heights: Vec<usize>,
+    log_final_height: usize,
+    final_poly: Vec<F>,
+}
+
+impl<F, M: Mmcs<F>> CommitPhaseResult<F, M> {
+    /// The log of the folding arity of each round.
+    fn log_arities(&self) -> impl Iterator<Item = usize> + '_ {
+        self.log_heights
+            .iter()
+            .zip(
+                self.log_heights
+                    .iter()
+                    .skip(1)
+                    .chain([&self.log_final_height]),
+            )
+            .map(|(log_height, log_folded_height)| log_height - log_folded_height)
+    }
 }
@@ -5,17 +5,22 @@ use core::marker::PhantomData;
 
 use itertools::{izip, Itertools};
 use p3_challenger::{CanObserve, CanSample, FieldChallenger, GrindingChallenger};
-use p3_commit::{DirectMmcs, Mmcs, OpenedValues, Pcs, UnivariatePcs, UnivariatePcsWithLde};
+use p3_commit::{
+    two_adic, BlindingRng, DirectMmcs, LagrangeSelectors, Mmcs, OpenedValues, Pcs, ProofSize,
+    StarkPcs, UnivariatePcs, UnivariatePcsWithLde,
+};
 use p3_dft::TwoAdicSubgroupDft;
 use p3_field::{
-    batch_multiplicative_inverse, cyclic_subgroup_coset_known_order, AbstractField, ExtensionField,
-    Field, PackedField, TwoAdicField,
+    batch_multiplicative_inverse, cyclic_subgroup_coset_known_order, AbstractExtensionField,
+    AbstractField, ExtensionField, Field, PackedField, TwoAdicField,
 };
 use p3_interpolation::interpolate_coset;
 use p3_matrix::bitrev::{BitReversableMatrix, BitReversedMatrixView};
 use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
+use p3_matrix::strided::VerticallyStridedMatrixView;
 use p3_matrix::{Dimensions, Matrix, MatrixRows};
 use p3_maybe_rayon::prelude::*;
+use p3_util::codec::encoded_len;
 use p3_util::linear_map::LinearMap;
 use p3_util::{log2_strict_usize, reverse_bits_len, reverse_slice_index_bits, VecExt};
 use serde::{Deserialize, Serialize};
@@ -43,11 +48,20 @@ pub trait TwoAdicFriPcsGenericConfig: Default + Send + Sync {
     type FriMmcs: DirectMmcs<Self::Challenge> + Send + Sync + Clone;
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, InputMmcs, FriMmcs>(
     PhantomData<(Val, Challenge, Challenger, Dft, InputMmcs, FriMmcs)>,
 );
 
+// Implemented by hand, since hiding MMCSs have no sensible default.
+impl<Val, Challenge, Challenger, Dft, InputMmcs, FriMmcs> Default
+    for TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, InputMmcs, FriMmcs>
+{
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
 impl<Val, Challenge, Challenger, Dft, InputMmcs, FriMmcs> TwoAdicFriPcsGenericConfig
     for TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, InputMmcs, FriMmcs>
 where
@@ -65,9 +79,8 @@ where
         + for<'a> DirectMmcs<Val, Mat<'a> = RowMajorMatrixView<'a, Val>>
         + Send
         + Sync
-        + Clone
-        + Default,
-    FriMmcs: DirectMmcs<Challenge> + Send + Sync + Clone + Default,
+        + Clone,
+    FriMmcs: DirectMmcs<Challenge> + Send + Sync + Clone,
 {
     type Val = Val;
     type Challenge = Challenge;
@@ -82,6 +95,8 @@ pub struct TwoAdicFriPcs<C: TwoAdicFriPcsGenericConfig> {
     fri: FriConfig<C::FriMmcs>,
     dft: C::Dft,
     mmcs: C::InputMmcs,
+    /// In hiding mode, the randomness for the codeword added to each FRI batch.
+    blinding: Option<BlindingRng>,
 }
 impl<C: TwoAdicFriPcsGenericConfig> TwoAdicFriPcs<C>
 where
@@ -89,12 +104,68 @@ where
     <C::FriMmcs as Mmcs<C::Challenge>>::Proof: Send + Sync,
 {
     pub fn new(fri: FriConfig<C::FriMmcs>, dft: C::Dft, mmcs: C::InputMmcs) -> Self {
-        Self { fri, dft, mmcs }
+        Self {
+            fri,
+            dft,
+            mmcs,
+            blinding: None,
+        }
+    }
+
+    /// A PCS which adds a random codeword to the batch polynomial it proves low-degree with FRI,
+    /// so that FRI reveals nothing about the committed polynomials beyond their opened values.
+    ///
+    /// This only hides the polynomials if `mmcs` and the FRI MMCS are hiding too, e.g.
+    /// `FieldMerkleTreeHidingMmcs`.
+    pub fn new_hiding(
+        fri: FriConfig<C::FriMmcs>,
+        dft: C::Dft,
+        mmcs: C::InputMmcs,
+        blinding: BlindingRng,
+    ) -> Self {
+        Self {
+            fri,
+            dft,
+            mmcs,
+            blinding: Some(blinding),
+        }
+    }
+
+    pub fn fri_config(&self) -> &FriConfig<C::FriMmcs> {
+        &self.fri
+    }
+
+    /// The MMCS the committed polynomials' LDEs are committed with.
+    pub fn input_mmcs(&self) -> &C::InputMmcs {
+        &self.mmcs
+    }
+
+    /// Whether this PCS adds a random codeword to the FRI batch, as made by `new_hiding`.
+    pub fn is_hiding(&self) -> bool {
+        self.blinding.is_some()
+    }
+
+    /// A random codeword for polynomials whose LDEs have height `lde_height`, in bit-reversed
+    /// order like the reduced openings it's added to.
+    fn random_codeword(&self, blinding: &BlindingRng, lde_height: usize) -> Vec<C::Challenge> {
+        let ext_degree = <C::Challenge as AbstractExtensionField<C::Val>>::D;
+        let evals = blinding.random_matrix(ext_degree, lde_height >> self.fri.log_blowup);
+        let lde = self
+            .dft
+            .coset_lde_batch(evals, self.fri.log_blowup, C::Val::generator())
+            .to_row_major_matrix()
+            .bit_reverse_rows()
+            .to_row_major_matrix();
+        lde.values
+            .chunks_exact(ext_degree)
+            .map(C::Challenge::from_base_slice)
+            .collect()
     }
 }
 
 pub enum VerificationError<C: TwoAdicFriPcsGenericConfig> {
     InputMmcsError(<C::InputMmcs as Mmcs<C::Val>>::Error),
+    RandomCodewordMmcsError(<C::FriMmcs as Mmcs<C::Challenge>>::Error),
     FriError(FriError<<C::FriMmcs as Mmcs<C::Challenge>>::Error>),
 }
 
@@ -108,6 +179,9 @@ where
             VerificationError::InputMmcsError(e) => {
                 f.debug_tuple("InputMmcsError").field(e).finish()
             }
+            VerificationError::RandomCodewordMmcsError(e) => {
+                f.debug_tuple("RandomCodewordMmcsError").field(e).finish()
+            }
             VerificationError::FriError(e) => f.debug_tuple("FriError").field(e).finish(),
         }
     }
@@ -122,8 +196,30 @@ where
     <C::InputMmcs as Mmcs<C::Val>>::Proof: Send + Sync,
 {
     pub(crate) fri_proof: FriProof<C::Challenge, C::FriMmcs, C::Val>,
-    /// For each query, for each committed batch, query openings for that batch
-    pub(crate) query_openings: Vec<Vec<BatchOpening<C>>>,
+    /// For each committed batch, the batch's openings at every query.
+    pub(crate) batch_openings: Vec<BatchOpening<C>>,
+    /// In hiding mode, the random codeword which was added to the FRI batch.
+    pub(crate) random_codeword: Option<RandomCodewordOpenings<C>>,
+}
+
+impl<C: TwoAdicFriPcsGenericConfig> TwoAdicFriPcsProof<C>
+where
+    <C::FriMmcs as Mmcs<C::Challenge>>::Commitment: Send + Sync,
+    <C::FriMmcs as Mmcs<C::Challenge>>::Proof: Send + Sync,
+    <C::InputMmcs as Mmcs<C::Val>>::Proof: Send + Sync,
+{
+    pub fn fri_proof(&self) -> &FriProof<C::Challenge, C::FriMmcs, C::Val> {
+        &self.fri_proof
+    }
+
+    /// For each committed batch, the batch's openings at every queried point.
+    pub fn batch_openings(&self) -> &[BatchOpening<C>] {
+        &self.batch_openings
+    }
+
+    pub fn random_codeword(&self) -> Option<&RandomCodewordOpenings<C>> {
+        self.random_codeword.as_ref()
+    }
 }
 
 unsafe impl<C: TwoAdicFriPcsGenericConfig> Send for TwoAdicFriPcsProof<C>
@@ -148,10 +244,46 @@ where
 {
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound = "")]
+pub struct RandomCodewordOpenings<C: TwoAdicFriPcsGenericConfig> {
+    pub(crate) commitment: <C::FriMmcs as Mmcs<C::Challenge>>::Commitment,
+    /// For each query, the codeword's value at the queried point.
+    pub(crate) opened_values: Vec<C::Challenge>,
+    /// The opening proof of the codeword's values at all queries.
+    pub(crate) opening_proof: <C::FriMmcs as Mmcs<C::Challenge>>::MultiProof,
+}
+
+impl<C: TwoAdicFriPcsGenericConfig> RandomCodewordOpenings<C> {
+    pub fn commitment(&self) -> &<C::FriMmcs as Mmcs<C::Challenge>>::Commitment {
+        &self.commitment
+    }
+
+    pub fn opened_values(&self) -> &[C::Challenge] {
+        &self.opened_values
+    }
+
+    pub fn opening_proof(&self) -> &<C::FriMmcs as Mmcs<C::Challenge>>::MultiProof {
+        &self.opening_proof
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct BatchOpening<C: TwoAdicFriPcsGenericConfig> {
-    pub(crate) opened_values: Vec<Vec<C::Val>>,
-    pub(crate) opening_proof: <C::InputMmcs as Mmcs<C::Val>>::Proof,
+    pub(crate) opened_values: Vec<Vec<Vec<C::Val>>>,
+    pub(crate) opening_proof: <C::InputMmcs as Mmcs<C::Val>>::MultiProof,
+}
+
+impl<C: TwoAdicFriPcsGenericConfig> BatchOpening<C> {
+    /// For each query, the opened row of each matrix in the batch.
+    pub fn opened_values(&self) -> &[Vec<Vec<C::Val>>] {
+        &self.opened_values
+    }
+
+    /// The opening proof of the batch's rows at all queries.
+    pub fn opening_proof(&self) -> &<C::InputMmcs as Mmcs<C::Val>>::MultiProof {
+        &self.opening_proof
+    }
 }
 
 unsafe impl<C: TwoAdicFriPcsGenericConfig> Send for BatchOpening<C>
@@ -187,6 +319,43 @@ where
         let ones = vec![C::Val::one(); polynomials.len()];
         self.commit_shifted_batches(polynomials, &ones)
     }
+
+    fn num_queries(&self) -> usize {
+        self.fri.num_queries
+    }
+
+    fn shape_params(&self) -> Vec<usize> {
+        vec![
+            self.fri.log_folding_arity,
+            self.fri.log_final_poly_len,
+            self.fri.proof_of_work_bits,
+            self.mmcs.cap_height(),
+            self.fri.mmcs.cap_height(),
+        ]
+    }
+
+    fn proof_size(&self, proof: &Self::Proof) -> ProofSize {
+        let mut size = ProofSize::default();
+        for step in proof
+            .fri_proof
+            .query_proofs
+            .iter()
+            .flat_map(|query_proof| &query_proof.commit_phase_openings)
+        {
+            size.commit_phase_openings += encoded_len(&step.sibling_values);
+        }
+        size.merkle_paths += encoded_len(&proof.fri_proof.commit_phase_opening_proofs);
+        for batch_opening in &proof.batch_openings {
+            size.opened_values += encoded_len(&batch_opening.opened_values);
+            size.merkle_paths += encoded_len(&batch_opening.opening_proof);
+        }
+        if let Some(random_codeword) = &proof.random_codeword {
+            size.opened_values += encoded_len(&random_codeword.opened_values);
+            size.merkle_paths += encoded_len(&random_codeword.opening_proof);
+        }
+        size.other = encoded_len(proof) - size.total();
+        size
+    }
 }
 
 impl<C: TwoAdicFriPcsGenericConfig + Clone, In: MatrixRows<C::Val> + Sized + Sync + Clone>
@@ -259,6 +428,164 @@ where
     }
 }
 
+impl<C: TwoAdicFriPcsGenericConfig + Clone> StarkPcs<C::Val, C::Challenge, C::Challenger>
+    for TwoAdicFriPcs<C>
+where
+    C::FriMmcs: Send,
+    <C::FriMmcs as Mmcs<C::Challenge>>::Commitment: Send + Sync,
+    <C::FriMmcs as Mmcs<C::Challenge>>::Proof: Send + Sync,
+    <C::FriMmcs as Mmcs<C::Challenge>>::ProverData: Send + Sync,
+    <C::InputMmcs as Mmcs<C::Val>>::Proof: Send + Sync,
+    <C::InputMmcs as Mmcs<C::Val>>::ProverData: Send + Sync + Sized,
+{
+    type QuotientDomainLde<'a>
+        = VerticallyStridedMatrixView<BitReversedMatrixView<RowMajorMatrixView<'a, C::Val>>>
+    where
+        Self: 'a;
+
+    fn log_blowup(&self) -> usize {
+        self.fri.log_blowup
+    }
+
+    fn domain_params(&self) -> Vec<C::Val> {
+        vec![C::Val::generator()]
+    }
+
+    fn max_log_height(&self) -> usize {
+        C::Val::TWO_ADICITY
+    }
+
+    fn next_point(&self, log_height: usize, point: C::Challenge) -> C::Challenge {
+        two_adic::next_point::<C::Val, _>(log_height, point)
+    }
+
+    fn selectors_at_point(
+        &self,
+        log_height: usize,
+        point: C::Challenge,
+        window_size: usize,
+    ) -> LagrangeSelectors<C::Challenge> {
+        two_adic::selectors_at_point::<C::Val, _>(log_height, point, window_size)
+    }
+
+    fn log_quotient_height(&self, log_height: usize, log_quotient_degree: usize) -> usize {
+        log_height + log_quotient_degree
+    }
+
+    fn selectors_on_quotient_domain(
+        &self,
+        log_height: usize,
+        log_quotient_height: usize,
+        window_size: usize,
+    ) -> LagrangeSelectors<Vec<C::Val>> {
+        two_adic::selectors_on_coset(
+            log_height,
+            log_quotient_height,
+            C::Val::generator(),
+            window_size,
+        )
+    }
+
+    fn quotient_domain_ldes<'a, 'b>(
+        &'a self,
+        prover_data: &'b Self::ProverData,
+        log_quotient_height: usize,
+    ) -> Vec<Self::QuotientDomainLde<'b>>
+    where
+        'a: 'b,
+    {
+        let ldes = <Self as UnivariatePcsWithLde<_, _, RowMajorMatrix<C::Val>, _>>::get_ldes(
+            self,
+            prover_data,
+        );
+        two_adic::quotient_domain_ldes(ldes, log_quotient_height)
+    }
+
+    fn evaluate_on_quotient_domain(
+        &self,
+        evals: RowMajorMatrix<C::Val>,
+        log_quotient_height: usize,
+    ) -> RowMajorMatrix<C::Val> {
+        two_adic::evaluate_on_coset(evals, log_quotient_height, C::Val::generator())
+    }
+
+    fn evaluate_at_point(
+        &self,
+        evals: RowMajorMatrix<C::Val>,
+        point: C::Challenge,
+    ) -> Vec<C::Challenge> {
+        two_adic::evaluate_at_point(evals, point)
+    }
+
+    fn commit_quotient(
+        &self,
+        quotient_values: Vec<C::Challenge>,
+        log_height: usize,
+        log_quotient_degree: usize,
+        blinding_rng: Option<&BlindingRng>,
+    ) -> (Self::Commitment, Self::ProverData) {
+        two_adic::commit_quotient(
+            self,
+            quotient_values,
+            log_height,
+            log_quotient_degree,
+            blinding_rng,
+        )
+    }
+
+    fn quotient_dimensions(
+        &self,
+        log_height: usize,
+        log_quotient_degree: usize,
+        is_zk: bool,
+    ) -> Dimensions {
+        two_adic::quotient_dimensions::<C::Val, C::Challenge>(
+            log_height,
+            log_quotient_degree,
+            is_zk,
+        )
+    }
+
+    fn quotient_point(
+        &self,
+        zeta: C::Challenge,
+        log_quotient_degree: usize,
+        is_zk: bool,
+    ) -> C::Challenge {
+        two_adic::quotient_point(zeta, log_quotient_degree, is_zk)
+    }
+
+    fn recompose_quotient(
+        &self,
+        parts: &[C::Challenge],
+        zeta: C::Challenge,
+        log_height: usize,
+        log_quotient_degree: usize,
+        is_zk: bool,
+    ) -> C::Challenge {
+        two_adic::recompose_quotient(
+            parts,
+            zeta,
+            C::Val::generator(),
+            log_height,
+            log_quotient_degree,
+            is_zk,
+        )
+    }
+
+    fn blind_trace(
+        &self,
+        trace: RowMajorMatrix<C::Val>,
+        blinding_rng: &BlindingRng,
+    ) -> RowMajorMatrix<C::Val> {
+        two_adic::blind_trace(trace, blinding_rng)
+    }
+
+    fn extend_trace(&self, trace: RowMajorMatrix<C::Val>) -> RowMajorMatrix<C::Val> {
+        two_adic::extend_trace(trace)
+    }
+}
+
 impl<C: TwoAdicFriPcsGenericConfig + Clone, In: MatrixRows<C::Val> + Sync + Clone>
     UnivariatePcs<C::Val, C::Challenge, In, C::Challenger> for TwoAdicFriPcs<C>
 where
@@ -276,6 +603,22 @@ where
         prover_data_and_points: &[(&Self::ProverData, &[Vec<C::Challenge>])],
         challenger: &mut C::Challenger,
     ) -> (OpenedValues<C::Challenge>, Self::Proof) {
+        let global_max_height = prover_data_and_points
+            .iter()
+            .map(|(data, _)| self.mmcs.get_max_height(data))
+            .max()
+            .unwrap();
+        let log_global_max_height = log2_strict_usize(global_max_height);
+
+        // In hiding mode, we commit to a random codeword before the batch is combined. Adding it to
+        // the batch polynomial masks everything FRI reveals about the committed polynomials.
+        let random_codeword = self.blinding.as_ref().map(|blinding| {
+            let codeword = self.random_codeword(blinding, global_max_height);
+            let (commitment, data) = self.fri.mmcs.commit_vec(codeword.clone());
+            challenger.observe(commitment.clone());
+            (commitment, codeword, data)
+        });
+
         // Batch combination challenge
         let alpha = <C::Challenger as CanSample<C::Challenge>>::sample(challenger);
 
@@ -326,8 +669,6 @@ where
             .collect_vec();
 
         let global_max_width = mats.iter().map(|m| m.width()).max().unwrap();
-        let global_max_height = mats.iter().map(|m| m.height()).max().unwrap();
-        let log_global_max_height = log2_strict_usize(global_max_height);
 
         let alpha_reducer = PowersReducer::<C::Val, C::Challenge>::new(alpha, global_max_width);
 
@@ -416,25 +757,43 @@ where
             }
         }
 
+        if let Some((_, codeword, _)) = &random_codeword {
+            let reduced_opening = reduced_openings[log_global_max_height].as_mut().unwrap();
+            for (reduced_opening, &r) in reduced_opening.iter_mut().zip_eq(codeword) {
+                *reduced_opening += r;
+            }
+        }
+
         let (fri_proof, query_indices) = prover::prove(&self.fri, &reduced_openings, challenger);
 
-        let query_openings = query_indices
-            .into_iter()
-            .map(|index| {
-                prover_data_and_points
+        let random_codeword = random_codeword.map(|(commitment, _, data)| {
+            let (opened_values, opening_proof) =
+                self.fri.mmcs.open_multi_batch(&query_indices, &data);
+            RandomCodewordOpenings {
+                commitment,
+                opened_values: opened_values
+                    .into_iter()
+                    .map(|mut opened_rows| opened_rows.pop().unwrap()[0])
+                    .collect(),
+                opening_proof,
+            }
+        });
+
+        let batch_openings = prover_data_and_points
+            .iter()
+            .map(|(data, _)| {
+                let log_max_height = log2_strict_usize(self.mmcs.get_max_height(data));
+                let bits_reduced = log_global_max_height - log_max_height;
+                let reduced_indices = query_indices
                     .iter()
-                    .map(|(data, _)| {
-                        let log_max_height = log2_strict_usize(self.mmcs.get_max_height(data));
-                        let bits_reduced = log_global_max_height - log_max_height;
-                        let reduced_index = index >> bits_reduced;
-                        let (opened_values, opening_proof) =
-                            self.mmcs.open_batch(reduced_index, data);
-                        BatchOpening {
-                            opened_values,
-                            opening_proof,
-                        }
-                    })
-                    .collect()
+                    .map(|&index| index >> bits_reduced)
+                    .collect_vec();
+                let (opened_values, opening_proof) =
+                    self.mmcs.open_multi_batch(&reduced_indices, data);
+                BatchOpening {
+                    opened_values,
+                    opening_proof,
+                }
             })
             .collect();
 
@@ -442,7 +801,8 @@ where
             all_opened_values,
             TwoAdicFriPcsProof {
                 fri_proof,
-                query_openings,
+                batch_openings,
+                random_codeword,
             },
         )
     }
@@ -455,69 +815,160 @@ where
         proof: &Self::Proof,
         challenger: &mut C::Challenger,
     ) -> Result<(), Self::Error> {
+        if let Some(random_codeword) = &proof.random_codeword {
+            challenger.observe(random_codeword.commitment.clone());
+        }
+
         // Batch combination challenge
         let alpha = <C::Challenger as CanSample<C::Challenge>>::sample(challenger);
 
-        let fri_challenges =
-            verifier::verify_shape_and_sample_challenges(&self.fri, &proof.fri_proof, challenger)
-                .map_err(VerificationError::FriError)?;
+        // Each batch must be nonempty, with an opened value per point and column of each matrix,
+        // and each matrix's LDE must fit in the field's two-adic subgroup.
+        let valid_mat = |mat_dims: &Dimensions,
+                         mat_points: &[C::Challenge],
+                         mat_at_z: &[Vec<C::Challenge>]| {
+            mat_dims.height.is_power_of_two()
+                && log2_strict_usize(mat_dims.height) + self.fri.log_blowup <= C::Val::TWO_ADICITY
+                && mat_points.len() == mat_at_z.len()
+                && mat_at_z
+                    .iter()
+                    .all(|ps_at_z| ps_at_z.len() == mat_dims.width)
+        };
+        let valid_shape = dims.len() == commits_and_points.len()
+            && values.len() == commits_and_points.len()
+            && izip!(dims, commits_and_points, &values).all(
+                |(batch_dims, (_, batch_points), batch_at_z)| {
+                    !batch_dims.is_empty()
+                        && batch_points.len() == batch_dims.len()
+                        && batch_at_z.len() == batch_dims.len()
+                        && izip!(batch_dims, *batch_points, batch_at_z)
+                            .all(|(d, points, at_z)| valid_mat(d, points, at_z))
+                },
+            );
+        if !valid_shape {
+            return Err(VerificationError::FriError(FriError::InvalidProofShape));
+        }
 
-        let log_global_max_height =
-            proof.fri_proof.commit_phase_commits.len() + self.fri.log_blowup;
-        let reduced_openings: Vec<[C::Challenge; 32]> = proof
-            .query_openings
+        // The FRI inputs are the reduced openings of each height, including the random codeword
+        // at the largest height in hiding mode.
+        let input_log_heights = dims
             .iter()
-            .zip(&fri_challenges.query_indices)
-            .map(|(query_opening, &index)| {
-                let mut ro = [C::Challenge::zero(); 32];
-                let mut alpha_pow = [C::Challenge::one(); 32];
-                for (batch_opening, batch_dims, (batch_commit, batch_points), batch_at_z) in
-                    izip!(query_opening, dims, commits_and_points, &values)
+            .flatten()
+            .map(|mat_dims| log2_strict_usize(mat_dims.height) + self.fri.log_blowup)
+            .collect_vec();
+        let Some(&log_global_max_height) = input_log_heights.iter().max() else {
+            return Err(VerificationError::FriError(FriError::InvalidProofShape));
+        };
+
+        let fri_challenges = verifier::verify_shape_and_sample_challenges(
+            &self.fri,
+            &input_log_heights,
+            &proof.fri_proof,
+            challenger,
+        )
+        .map_err(VerificationError::FriError)?;
+        let num_queries = fri_challenges.query_indices.len();
+        if proof.batch_openings.len() != commits_and_points.len()
+            || proof
+                .batch_openings
+                .iter()
+                .any(|batch_opening| batch_opening.opened_values.len() != num_queries)
+        {
+            return Err(VerificationError::FriError(FriError::InvalidProofShape));
+        }
+
+        let mut reduced_openings = vec![[C::Challenge::zero(); 32]; num_queries];
+        let mut alpha_pows = vec![[C::Challenge::one(); 32]; num_queries];
+        for (batch_opening, batch_dims, (batch_commit, batch_points), batch_at_z) in
+            izip!(&proof.batch_openings, dims, commits_and_points, &values)
+        {
+            // The committed matrices are the LDEs of the polynomials.
+            let batch_lde_dims = batch_dims
+                .iter()
+                .map(|dims| Dimensions {
+                    width: dims.width,
+                    height: dims.height << self.fri.log_blowup,
+                })
+                .collect_vec();
+            // Batches are nonempty, as checked above.
+            let batch_max_height = batch_lde_dims
+                .iter()
+                .map(|dims| dims.height)
+                .max()
+                .unwrap_or(1);
+            let log_batch_max_height = log2_strict_usize(batch_max_height);
+            let bits_reduced = log_global_max_height - log_batch_max_height;
+            let reduced_indices = fri_challenges
+                .query_indices
+                .iter()
+                .map(|&index| index >> bits_reduced)
+                .collect_vec();
+
+            self.mmcs
+                .verify_multi_batch(
+                    batch_commit,
+                    &batch_lde_dims,
+                    &reduced_indices,
+                    &batch_opening.opened_values,
+                    &batch_opening.opening_proof,
+                )
+                .map_err(VerificationError::InputMmcsError)?;
+
+            for (ro, alpha_pow, &index, query_opening) in izip!(
+                &mut reduced_openings,
+                &mut alpha_pows,
+                &fri_challenges.query_indices,
+                &batch_opening.opened_values
+            ) {
+                for (mat_opening, mat_dims, mat_points, mat_at_z) in
+                    izip!(query_opening, batch_dims, *batch_points, batch_at_z)
                 {
-                    let batch_max_height = batch_dims
-                        .iter()
-                        .map(|dims| dims.height << self.fri.log_blowup)
-                        .max()
-                        .expect("Empty batch?");
-                    let log_batch_max_height = log2_strict_usize(batch_max_height);
-                    let bits_reduced = log_global_max_height - log_batch_max_height;
-                    let reduced_index = index >> bits_reduced;
-
-                    self.mmcs.verify_batch(
-                        batch_commit,
-                        batch_dims,
-                        reduced_index,
-                        &batch_opening.opened_values,
-                        &batch_opening.opening_proof,
-                    )?;
-                    for (mat_opening, mat_dims, mat_points, mat_at_z) in izip!(
-                        &batch_opening.opened_values,
-                        batch_dims,
-                        *batch_points,
-                        batch_at_z
-                    ) {
-                        let log_height = log2_strict_usize(mat_dims.height) + self.fri.log_blowup;
-
-                        let bits_reduced = log_global_max_height - log_height;
-                        let rev_reduced_index = reverse_bits_len(index >> bits_reduced, log_height);
-
-                        let x = C::Val::generator()
-                            * C::Val::two_adic_generator(log_height)
-                                .exp_u64(rev_reduced_index as u64);
-
-                        for (&z, ps_at_z) in izip!(mat_points, mat_at_z) {
-                            for (&p_at_x, &p_at_z) in izip!(mat_opening, ps_at_z) {
-                                let quotient = (-p_at_z + p_at_x) / (-z + x);
-                                ro[log_height] += alpha_pow[log_height] * quotient;
-                                alpha_pow[log_height] *= alpha;
-                            }
+                    let log_height = log2_strict_usize(mat_dims.height) + self.fri.log_blowup;
+
+                    let bits_reduced = log_global_max_height - log_height;
+                    let rev_reduced_index = reverse_bits_len(index >> bits_reduced, log_height);
+
+                    let x = C::Val::generator()
+                        * C::Val::two_adic_generator(log_height).exp_u64(rev_reduced_index as u64);
+
+                    for (&z, ps_at_z) in izip!(mat_points, mat_at_z) {
+                        for (&p_at_x, &p_at_z) in izip!(mat_opening, ps_at_z) {
+                            let quotient = (-p_at_z + p_at_x) / (-z + x);
+                            ro[log_height] += alpha_pow[log_height] * quotient;
+                            alpha_pow[log_height] *= alpha;
                         }
                     }
                 }
-                Ok(ro)
-            })
-            .collect::<Result<Vec<_>, <C::InputMmcs as Mmcs<C::Val>>::Error>>()
-            .map_err(VerificationError::InputMmcsError)?;
+            }
+        }
+
+        if let Some(random_codeword) = &proof.random_codeword {
+            if random_codeword.opened_values.len() != num_queries {
+                return Err(VerificationError::FriError(FriError::InvalidProofShape));
+            }
+            let dims = [Dimensions {
+                width: 1,
+                height: 1 << log_global_max_height,
+            }];
+            let opened_values = random_codeword
+                .opened_values
+                .iter()
+                .map(|&value| vec![vec![value]])
+                .collect_vec();
+            self.fri
+                .mmcs
+                .verify_multi_batch(
+                    &random_codeword.commitment,
+                    &dims,
+                    &fri_challenges.query_indices,
+                    &opened_values,
+                    &random_codeword.opening_proof,
+                )
+                .map_err(VerificationError::RandomCodewordMmcsError)?;
+            for (ro, &value) in izip!(&mut reduced_openings, &random_codeword.opened_values) {
+                ro[log_global_max_height] += value;
+            }
+        }
 
         verifier::verify_challenges(
             &self.fri,
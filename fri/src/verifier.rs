@@ -1,14 +1,14 @@
 use alloc::vec;
 use alloc::vec::Vec;
 
-use itertools::izip;
+use itertools::{izip, Itertools};
 use p3_challenger::{CanObserve, CanSample, GrindingChallenger};
 use p3_commit::Mmcs;
 use p3_field::{Field, TwoAdicField};
 use p3_matrix::Dimensions;
 use p3_util::reverse_bits_len;
 
-use crate::{FriConfig, FriProof, QueryProof};
+use crate::{fold_coset, FriConfig, FriProof, QueryProof};
 
 #[derive(Debug)]
 pub enum FriError<CommitMmcsErr> {
@@ -18,14 +18,24 @@ pub enum FriError<CommitMmcsErr> {
     InvalidPowWitness,
 }
 
-#[derive(Debug)]
+/// Reduced openings are stored in arrays of this length, indexed by log height.
+const MAX_LOG_HEIGHT: usize = 32;
+
+#[derive(Clone, Debug)]
 pub struct FriChallenges<F> {
     pub query_indices: Vec<usize>,
     betas: Vec<F>,
+    /// The log height of the codeword committed to in each commit phase round.
+    pub commit_phase_log_heights: Vec<usize>,
+    /// The log height of the codewords once folding stops.
+    pub log_final_height: usize,
 }
 
+/// Check the shape of `proof`, given the log heights of the input codewords, and sample the FRI
+/// challenges.
 pub fn verify_shape_and_sample_challenges<F, M, Challenger>(
     config: &FriConfig<M>,
+    input_log_heights: &[usize],
     proof: &FriProof<F, M, Challenger::Witness>,
     challenger: &mut Challenger,
 ) -> Result<FriChallenges<F>, FriError<M::Error>>
@@ -37,6 +47,25 @@ where
     Challenger: GrindingChallenger + CanObserve<M::Commitment> + CanSample<F>,
     Challenger::Witness: Send + Sync,
 {
+    // Reduced openings are indexed by log height, and every input must be at least as large as
+    // the blowup.
+    let log_max_height = match input_log_heights.iter().max() {
+        Some(&log_max_height) if log_max_height < MAX_LOG_HEIGHT => log_max_height,
+        _ => return Err(FriError::InvalidProofShape),
+    };
+    if input_log_heights
+        .iter()
+        .any(|&log_height| log_height < config.log_blowup)
+    {
+        return Err(FriError::InvalidProofShape);
+    }
+    let commit_phase_log_heights =
+        config.commit_phase_log_heights(input_log_heights.iter().copied());
+    let log_final_height = config.log_final_height(input_log_heights.iter().copied());
+    if !valid_proof_shape(config, proof, &commit_phase_log_heights, log_final_height) {
+        return Err(FriError::InvalidProofShape);
+    }
+
     let betas: Vec<F> = proof
         .commit_phase_commits
         .iter()
@@ -46,17 +75,11 @@ where
         })
         .collect();
 
-    if proof.query_proofs.len() != config.num_queries {
-        return Err(FriError::InvalidProofShape);
-    }
-
     // Check PoW.
     if !challenger.check_witness(config.proof_of_work_bits, proof.pow_witness) {
         return Err(FriError::InvalidPowWitness);
     }
 
-    let log_max_height = proof.commit_phase_commits.len() + config.log_blowup;
-
     let query_indices: Vec<usize> = (0..config.num_queries)
         .map(|_| challenger.sample_bits(log_max_height))
         .collect();
@@ -64,6 +87,8 @@ where
     Ok(FriChallenges {
         query_indices,
         betas,
+        commit_phase_log_heights,
+        log_final_height,
     })
 }
 
@@ -80,22 +105,41 @@ where
     M::Proof: Send + Sync,
     Witness: Send + Sync,
 {
-    let log_max_height = proof.commit_phase_commits.len() + config.log_blowup;
-    for (&index, query_proof, ro) in izip!(
-        &challenges.query_indices,
-        &proof.query_proofs,
-        reduced_openings
-    ) {
-        let folded_eval = verify_query(
-            config,
-            &proof.commit_phase_commits,
-            index,
-            query_proof,
-            &challenges.betas,
-            ro,
-            log_max_height,
-        )?;
-        if folded_eval != proof.final_poly {
+    // The challenges may not come from `verify_shape_and_sample_challenges`, so check that they
+    // are consistent with the proof and with each other.
+    let log_heights = &challenges.commit_phase_log_heights;
+    let log_final_height = challenges.log_final_height;
+    let log_max_height = log_heights.first().copied().unwrap_or(log_final_height);
+    let num_queries = proof.query_proofs.len();
+    let valid_shape = log_max_height <= F::TWO_ADICITY
+        && log_max_height < MAX_LOG_HEIGHT
+        && log_final_height >= config.log_blowup
+        && log_heights
+            .iter()
+            .chain([&log_final_height])
+            .tuple_windows()
+            .all(|(log_height, log_folded_height)| log_height > log_folded_height)
+        && challenges.betas.len() == log_heights.len()
+        && challenges.query_indices.len() == num_queries
+        && challenges
+            .query_indices
+            .iter()
+            .all(|&index| index >> log_max_height == 0)
+        && reduced_openings.len() == num_queries
+        && valid_proof_shape(config, proof, log_heights, log_final_height);
+    if !valid_shape {
+        return Err(FriError::InvalidProofShape);
+    }
+
+    let folded_evals_and_points = verify_queries(config, proof, challenges, reduced_openings)?;
+    for (folded_eval, x) in folded_evals_and_points {
+        // Evaluate the final polynomial at the folded point, by Horner's method.
+        let final_eval = proof
+            .final_poly
+            .iter()
+            .rev()
+            .fold(F::zero(), |acc, &coeff| acc * x + coeff);
+        if folded_eval != final_eval {
             return Err(FriError::FinalPolyMismatch);
         }
     }
@@ -103,64 +147,126 @@ where
     Ok(())
 }
 
-fn verify_query<F, M>(
+/// Whether `proof` has the number of rounds, queries and sibling values implied by the commit phase
+/// log heights.
+fn valid_proof_shape<F, M, Witness>(
     config: &FriConfig<M>,
-    commit_phase_commits: &[M::Commitment],
-    mut index: usize,
-    proof: &QueryProof<F, M>,
-    betas: &[F],
-    reduced_openings: &[F; 32],
-    log_max_height: usize,
-) -> Result<F, FriError<M::Error>>
+    proof: &FriProof<F, M, Witness>,
+    commit_phase_log_heights: &[usize],
+    log_final_height: usize,
+) -> bool
 where
-    F: TwoAdicField,
+    F: Field,
     M: Mmcs<F>,
+    M::Commitment: Send + Sync,
     M::Proof: Send + Sync,
+    Witness: Send + Sync,
 {
-    let mut folded_eval = F::zero();
-    let mut x = F::two_adic_generator(log_max_height)
-        .exp_u64(reverse_bits_len(index, log_max_height) as u64);
-
-    for (log_folded_height, commit, step, &beta) in izip!(
-        (0..log_max_height).rev(),
-        commit_phase_commits,
-        &proof.commit_phase_openings,
-        betas,
-    ) {
-        folded_eval += reduced_openings[log_folded_height + 1];
+    let num_rounds = commit_phase_log_heights.len();
+    let log_arities = log_arities(commit_phase_log_heights, log_final_height);
+
+    let valid_query_shape = |query_proof: &QueryProof<F, M>| {
+        query_proof.commit_phase_openings.len() == num_rounds
+            && izip!(&query_proof.commit_phase_openings, &log_arities)
+                .all(|(step, log_arity)| step.sibling_values.len() == (1 << log_arity) - 1)
+    };
+    proof.commit_phase_commits.len() == num_rounds
+        && proof.commit_phase_opening_proofs.len() == num_rounds
+        && proof.final_poly.len() == 1 << (log_final_height - config.log_blowup)
+        && proof.query_proofs.len() == config.num_queries
+        && proof.query_proofs.iter().all(valid_query_shape)
+}
+
+/// The log of the folding arity of each commit phase round.
+fn log_arities(commit_phase_log_heights: &[usize], log_final_height: usize) -> Vec<usize> {
+    izip!(
+        commit_phase_log_heights,
+        commit_phase_log_heights
+            .iter()
+            .skip(1)
+            .chain([&log_final_height])
+    )
+    .map(|(log_height, log_folded_height)| log_height - log_folded_height)
+    .collect()
+}
 
-        let index_sibling = index ^ 1;
-        let index_pair = index >> 1;
+/// Fold the queried evaluations through each commit phase round, checking each round's openings
+/// against its commitment, and return the final folded evaluation of each query along with the
+/// point it's at.
+fn verify_queries<F, M, Witness>(
+    config: &FriConfig<M>,
+    proof: &FriProof<F, M, Witness>,
+    challenges: &FriChallenges<F>,
+    reduced_openings: &[[F; 32]],
+) -> Result<Vec<(F, F)>, FriError<M::Error>>
+where
+    F: TwoAdicField,
+    M: Mmcs<F>,
+    M::Commitment: Send + Sync,
+    M::Proof: Send + Sync,
+    Witness: Send + Sync,
+{
+    let log_heights = &challenges.commit_phase_log_heights;
+    let log_final_height = challenges.log_final_height;
+    let mut indices = challenges.query_indices.clone();
+    let mut folded_evals = vec![F::zero(); indices.len()];
 
-        let mut evals = vec![folded_eval; 2];
-        evals[index_sibling % 2] = step.sibling_value;
+    for (round, (&log_height, log_arity, commit, opening_proof, &beta)) in izip!(
+        log_heights,
+        log_arities(log_heights, log_final_height),
+        &proof.commit_phase_commits,
+        &proof.commit_phase_opening_proofs,
+        &challenges.betas,
+    )
+    .enumerate()
+    {
+        let index_cosets = indices
+            .iter()
+            .map(|&index| index >> log_arity)
+            .collect_vec();
+        let cosets = izip!(
+            &indices,
+            &folded_evals,
+            &proof.query_proofs,
+            reduced_openings
+        )
+        .map(|(&index, &folded_eval, query_proof, ro)| {
+            let mut evals = query_proof.commit_phase_openings[round]
+                .sibling_values
+                .clone();
+            evals.insert(index & ((1 << log_arity) - 1), folded_eval + ro[log_height]);
+            vec![evals]
+        })
+        .collect_vec();
 
         let dims = &[Dimensions {
-            width: 2,
-            height: (1 << log_folded_height),
+            width: 1 << log_arity,
+            height: 1 << (log_height - log_arity),
         }];
         config
             .mmcs
-            .verify_batch(
-                commit,
-                dims,
-                index_pair,
-                &[evals.clone()],
-                &step.opening_proof,
-            )
+            .verify_multi_batch(commit, dims, &index_cosets, &cosets, opening_proof)
             .map_err(FriError::CommitPhaseMmcsError)?;
 
-        let mut xs = [x; 2];
-        xs[index_sibling % 2] *= F::two_adic_generator(1);
-        // interpolate and evaluate at beta
-        folded_eval = evals[0] + (beta - xs[0]) * (evals[1] - evals[0]) / (xs[1] - xs[0]);
+        for (folded_eval, &index_coset, coset) in izip!(&mut folded_evals, &index_cosets, &cosets) {
+            // The first point of the coset, whose other points are its multiples by the roots of
+            // unity of order `arity`.
+            let x = F::two_adic_generator(log_height)
+                .exp_u64(reverse_bits_len(index_coset << log_arity, log_height) as u64);
+            *folded_eval = fold_coset(&coset[0], x, beta);
+        }
 
-        index = index_pair;
-        x = x.square();
+        indices = index_cosets;
     }
 
-    debug_assert!(index < config.blowup(), "index was {index}");
-    debug_assert_eq!(x.exp_power_of_2(config.log_blowup), F::one());
-
-    Ok(folded_eval)
+    izip!(indices, folded_evals, reduced_openings)
+        .map(|(index, folded_eval, ro)| {
+            if index >> log_final_height != 0 {
+                return Err(FriError::InvalidProofShape);
+            }
+            let x = F::two_adic_generator(log_final_height)
+                .exp_u64(reverse_bits_len(index, log_final_height) as u64);
+            Ok((folded_eval + ro[log_final_height], x))
+        })
+        .collect()
 }
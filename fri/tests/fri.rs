@@ -1,17 +1,24 @@
 use itertools::Itertools;
 use p3_baby_bear::BabyBear;
-use p3_challenger::{CanSampleBits, DuplexChallenger, FieldChallenger};
-use p3_commit::ExtensionMmcs;
+use p3_challenger::{
+    CanObserve, CanSample, DuplexChallenger, FieldChallenger, GrindingChallenger, HashChallenger,
+    SerializingChallenger32,
+};
+use p3_commit::{DirectMmcs, ExtensionMmcs};
 use p3_dft::{Radix2Dit, TwoAdicSubgroupDft};
 use p3_field::extension::BinomialExtensionField;
 use p3_field::{AbstractField, Field};
-use p3_fri::{prover, verifier, FriConfig};
+use p3_fri::verifier::{FriChallenges, FriError};
+use p3_fri::{prover, verifier, FriConfig, FriProof};
+use p3_keccak::Keccak256Hash;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::util::reverse_matrix_index_bits;
 use p3_matrix::{Matrix, MatrixRows};
 use p3_merkle_tree::FieldMerkleTreeMmcs;
 use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
-use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_symmetric::{
+    CompressionFunctionFromHasher, PaddingFreeSponge, SerializingHasher32, TruncatedPermutation,
+};
 use p3_util::log2_strict_usize;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
@@ -27,13 +34,26 @@ type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
 type Challenger = DuplexChallenger<Val, Perm, 16>;
 type MyFriConfig = FriConfig<ChallengeMmcs>;
 
-fn get_ldt_for_testing<R: Rng>(rng: &mut R) -> (Perm, MyFriConfig) {
+/// A FRI proof, along with the verifier's challenges and reduced openings.
+type LdtOutput<M, Witness> = (
+    FriProof<Challenge, M, Witness>,
+    FriChallenges<Challenge>,
+    Vec<[Challenge; 32]>,
+);
+
+fn get_ldt_for_testing<R: Rng>(
+    rng: &mut R,
+    log_folding_arity: usize,
+    log_final_poly_len: usize,
+) -> (Perm, MyFriConfig) {
     let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, rng);
     let hash = MyHash::new(perm.clone());
     let compress = MyCompress::new(perm.clone());
     let mmcs = ChallengeMmcs::new(ValMmcs::new(hash, compress));
     let fri_config = FriConfig {
         log_blowup: 1,
+        log_folding_arity,
+        log_final_poly_len,
         num_queries: 10,
         proof_of_work_bits: 8,
         mmcs,
@@ -41,14 +61,44 @@ fn get_ldt_for_testing<R: Rng>(rng: &mut R) -> (Perm, MyFriConfig) {
     (perm, fri_config)
 }
 
-fn do_test_fri_ldt<R: Rng>(rng: &mut R) {
-    let (perm, fc) = get_ldt_for_testing(rng);
+fn do_test_fri_ldt<R: Rng>(
+    rng: &mut R,
+    log_folding_arity: usize,
+    log_final_poly_len: usize,
+    degree_bits: &[usize],
+) {
+    let (perm, fc) = get_ldt_for_testing(rng, log_folding_arity, log_final_poly_len);
+    do_test_fri_ldt_with_challenger(rng, &fc, || Challenger::new(perm.clone()), degree_bits);
+}
+
+/// Prove and verify a low degree test.
+fn do_test_fri_ldt_with_challenger<R, M, C>(
+    rng: &mut R,
+    fc: &FriConfig<M>,
+    new_challenger: impl Fn() -> C,
+    degree_bits: &[usize],
+) -> LdtOutput<M, C::Witness>
+where
+    R: Rng,
+    M: DirectMmcs<Challenge> + Send + Sync,
+    M::Commitment: Send + Sync,
+    M::Proof: Send + Sync,
+    M::ProverData: Send + Sync,
+    C: FieldChallenger<Val>
+        + GrindingChallenger
+        + CanObserve<M::Commitment>
+        + CanSample<Challenge>
+        + Send
+        + Sync,
+    C::Witness: Send + Sync,
+{
     let dft = Radix2Dit::default();
 
     let shift = Val::generator();
 
-    let ldes: Vec<RowMajorMatrix<Val>> = (3..10)
-        .map(|deg_bits| {
+    let ldes: Vec<RowMajorMatrix<Val>> = degree_bits
+        .iter()
+        .map(|&deg_bits| {
             let evals = RowMajorMatrix::<Val>::rand_nonzero(rng, 1 << deg_bits, 16);
             let mut lde = dft.coset_lde_batch(evals, 1, shift);
             reverse_matrix_index_bits(&mut lde);
@@ -58,7 +108,7 @@ fn do_test_fri_ldt<R: Rng>(rng: &mut R) {
 
     let (proof, reduced_openings, p_sample) = {
         // Prover world
-        let mut chal = Challenger::new(perm.clone());
+        let mut chal = new_challenger();
         let alpha: Challenge = chal.sample_ext_element();
 
         let input: [_; 32] = core::array::from_fn(|log_height| {
@@ -82,7 +132,7 @@ fn do_test_fri_ldt<R: Rng>(rng: &mut R) {
             }
         });
 
-        let (proof, idxs) = prover::prove(&fc, &input, &mut chal);
+        let (proof, idxs) = prover::prove(fc, &input, &mut chal);
 
         let log_max_height = input.iter().rposition(Option::is_some).unwrap();
         let reduced_openings: Vec<[Challenge; 32]> = idxs
@@ -107,12 +157,20 @@ fn do_test_fri_ldt<R: Rng>(rng: &mut R) {
         (proof, reduced_openings, chal.sample_bits(8))
     };
 
-    let mut v_challenger = Challenger::new(perm);
+    let mut v_challenger = new_challenger();
     let _alpha: Challenge = v_challenger.sample_ext_element();
-    let fri_challenges =
-        verifier::verify_shape_and_sample_challenges(&fc, &proof, &mut v_challenger)
-            .expect("failed verify shape and sample");
-    verifier::verify_challenges(&fc, &proof, &fri_challenges, &reduced_openings)
+    let input_log_heights = degree_bits
+        .iter()
+        .map(|&deg_bits| deg_bits + 1)
+        .collect_vec();
+    let fri_challenges = verifier::verify_shape_and_sample_challenges(
+        fc,
+        &input_log_heights,
+        &proof,
+        &mut v_challenger,
+    )
+    .expect("failed verify shape and sample");
+    verifier::verify_challenges(fc, &proof, &fri_challenges, &reduced_openings)
         .expect("failed verify challenges");
 
     assert_eq!(
@@ -120,6 +178,7 @@ fn do_test_fri_ldt<R: Rng>(rng: &mut R) {
         v_challenger.sample_bits(8),
         "prover and verifier transcript have same state after FRI"
     );
+    (proof, fri_challenges, reduced_openings)
 }
 
 #[test]
@@ -127,6 +186,128 @@ fn test_fri_ldt() {
     // FRI is kind of flaky depending on indexing luck
     for i in 0..4 {
         let mut rng = ChaCha20Rng::seed_from_u64(i);
-        do_test_fri_ldt(&mut rng);
+        do_test_fri_ldt(&mut rng, 1, 0, &(3..10).collect_vec());
+    }
+}
+
+#[test]
+fn test_fri_ldt_higher_arity() {
+    for log_folding_arity in 2..=4 {
+        let mut rng = ChaCha20Rng::seed_from_u64(log_folding_arity as u64);
+        // Inputs at every height, so that no round can fold by the full arity.
+        do_test_fri_ldt(&mut rng, log_folding_arity, 0, &(3..10).collect_vec());
+        // Sparse inputs, so that rounds fold by the full arity, or less to reach the next input.
+        do_test_fri_ldt(&mut rng, log_folding_arity, 0, &[1, 4, 9]);
+    }
+}
+
+#[test]
+fn test_fri_ldt_final_poly() {
+    for log_final_poly_len in 1..=3 {
+        let mut rng = ChaCha20Rng::seed_from_u64(log_final_poly_len as u64);
+        do_test_fri_ldt(&mut rng, 1, log_final_poly_len, &(3..10).collect_vec());
+        do_test_fri_ldt(&mut rng, 3, log_final_poly_len, &[4, 9]);
+        // An input smaller than the final polynomial's length, which folding must reach.
+        do_test_fri_ldt(&mut rng, 2, log_final_poly_len, &[0, 6]);
+    }
+}
+
+#[test]
+fn test_fri_ldt_hash_challenger() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let (perm, fc) = get_ldt_for_testing(&mut rng, 1, 0);
+    let new_challenger =
+        || HashChallenger::<Val, MyHash, 8>::new(vec![], MyHash::new(perm.clone()));
+    do_test_fri_ldt_with_challenger(&mut rng, &fc, new_challenger, &(3..10).collect_vec());
+}
+
+#[test]
+fn test_fri_ldt_serializing_challenger() {
+    // The serializing challenger observes byte digests, so the commit phase uses Keccak-256 Merkle
+    // trees, as in an EVM-friendly config.
+    type ByteHash = Keccak256Hash;
+    type FieldHash = SerializingHasher32<ByteHash>;
+    type ByteCompress = CompressionFunctionFromHasher<u8, ByteHash, 2, 32>;
+    type ByteMmcs = FieldMerkleTreeMmcs<Val, FieldHash, ByteCompress, 32, u8>;
+    type ByteChallengeMmcs = ExtensionMmcs<Val, Challenge, ByteMmcs>;
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let byte_mmcs = ByteMmcs::new(FieldHash::new(ByteHash {}), ByteCompress::new(ByteHash {}));
+    let fc = FriConfig {
+        log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 10,
+        proof_of_work_bits: 8,
+        mmcs: ByteChallengeMmcs::new(byte_mmcs),
+    };
+    let new_challenger = || SerializingChallenger32::<Val, _>::from_hasher(ByteHash {});
+    do_test_fri_ldt_with_challenger(&mut rng, &fc, new_challenger, &(3..10).collect_vec());
+}
+
+#[test]
+fn test_fri_rejects_inconsistent_challenges() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let (perm, fc) = get_ldt_for_testing(&mut rng, 2, 0);
+    let (proof, challenges, reduced_openings) =
+        do_test_fri_ldt_with_challenger(&mut rng, &fc, || Challenger::new(perm.clone()), &[3, 6]);
+    let verify = |challenges: &FriChallenges<Challenge>, reduced_openings: &[[Challenge; 32]]| {
+        verifier::verify_challenges(&fc, &proof, challenges, reduced_openings)
+    };
+    assert!(verify(&challenges, &reduced_openings).is_ok());
+
+    // Skipping a query must not go unnoticed.
+    let mut fewer_queries = challenges.clone();
+    fewer_queries.query_indices.pop();
+    let fewer_openings = &reduced_openings[1..];
+    assert!(matches!(
+        verify(&fewer_queries, fewer_openings),
+        Err(FriError::InvalidProofShape)
+    ));
+
+    // One fewer round than there are betas.
+    let mut fewer_rounds = challenges.clone();
+    fewer_rounds.commit_phase_log_heights.pop();
+    assert!(matches!(
+        verify(&fewer_rounds, &reduced_openings),
+        Err(FriError::InvalidProofShape)
+    ));
+
+    let mut index_out_of_range = challenges.clone();
+    index_out_of_range.query_indices[0] |= 1 << 20;
+    assert!(matches!(
+        verify(&index_out_of_range, &reduced_openings),
+        Err(FriError::InvalidProofShape)
+    ));
+
+    // Heights which don't fit in the reduced openings, or in the field's two-adic subgroup.
+    for log_max_height in [30, 40] {
+        let mut too_tall = challenges.clone();
+        too_tall.commit_phase_log_heights[0] = log_max_height;
+        assert!(matches!(
+            verify(&too_tall, &reduced_openings),
+            Err(FriError::InvalidProofShape)
+        ));
+    }
+}
+
+#[test]
+fn test_fri_rejects_inputs_smaller_than_blowup() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let (perm, fc) = get_ldt_for_testing(&mut rng, 1, 0);
+    let (proof, _, _) =
+        do_test_fri_ldt_with_challenger(&mut rng, &fc, || Challenger::new(perm.clone()), &[3, 6]);
+
+    for input_log_heights in [vec![], vec![0, 7], vec![32]] {
+        let mut challenger = Challenger::new(perm.clone());
+        assert!(matches!(
+            verifier::verify_shape_and_sample_challenges(
+                &fc,
+                &input_log_heights,
+                &proof,
+                &mut challenger
+            ),
+            Err(FriError::InvalidProofShape)
+        ));
     }
 }
@@ -0,0 +1,262 @@
+use p3_baby_bear::BabyBear;
+use p3_challenger::{CanObserve, FieldChallenger, SerializingChallenger32};
+use p3_commit::{
+    ExtensionMmcs, MultiFromUniPcs, MultiFromUniPcsError, MultivariatePcs, OpenedValues, Pcs,
+};
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractExtensionField, AbstractField};
+use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{Dimensions, Matrix, MatrixTranspose};
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher32};
+use rand::thread_rng;
+
+type Val = BabyBear;
+type Challenge = BinomialExtensionField<Val, 4>;
+
+type ByteHash = Keccak256Hash;
+type FieldHash = SerializingHasher32<ByteHash>;
+type MyCompress = CompressionFunctionFromHasher<u8, ByteHash, 2, 32>;
+type ValMmcs = FieldMerkleTreeMmcs<Val, FieldHash, MyCompress, 32, u8>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Dft = Radix2DitParallel;
+type Challenger = SerializingChallenger32<Val, ByteHash>;
+type UniPcs =
+    TwoAdicFriPcs<TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>>;
+type MyPcs = MultiFromUniPcs<Val, Challenge, UniPcs, Dft, Challenger>;
+type Commitment = <MyPcs as Pcs<Val, RowMajorMatrix<Val>>>::Commitment;
+type Proof = <MyPcs as Pcs<Val, RowMajorMatrix<Val>>>::Proof;
+type Error = <MyPcs as Pcs<Val, RowMajorMatrix<Val>>>::Error;
+
+fn make_pcs() -> MyPcs {
+    let val_mmcs = ValMmcs::new(FieldHash::new(ByteHash {}), MyCompress::new(ByteHash {}));
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 10,
+        proof_of_work_bits: 8,
+        mmcs: ChallengeMmcs::new(val_mmcs.clone()),
+    };
+    MultiFromUniPcs::new(UniPcs::new(fri_config, Dft {}, val_mmcs), Dft {})
+}
+
+fn sample_point(challenger: &mut Challenger, num_variables: usize) -> Vec<Challenge> {
+    (0..num_variables)
+        .map(|_| challenger.sample_ext_element())
+        .collect()
+}
+
+/// Evaluate a multilinear polynomial, given its evaluations over the hypercube, by fixing its
+/// variables one at a time, starting with the first.
+fn evaluate_naive(evals: &[Val], point: &[Challenge]) -> Challenge {
+    let mut evals = evals
+        .iter()
+        .map(|&x| Challenge::from_base(x))
+        .collect::<Vec<_>>();
+    for &z in point {
+        let (lo, hi) = evals.split_at(evals.len() / 2);
+        evals = lo.iter().zip(hi).map(|(&l, &h)| l + z * (h - l)).collect();
+    }
+    evals[0]
+}
+
+/// A committed batch of matrices, with the points at which to open each matrix.
+struct Batch {
+    polynomials: Vec<RowMajorMatrix<Val>>,
+    points: Vec<Vec<Vec<Challenge>>>,
+}
+
+/// Commit to batches of random matrices with the given log heights, and open each of them at
+/// `num_points` random points.
+#[allow(clippy::type_complexity)]
+fn open(
+    pcs: &MyPcs,
+    log_heights: &[&[usize]],
+    num_points: usize,
+) -> (Vec<Batch>, Vec<Commitment>, OpenedValues<Challenge>, Proof) {
+    let mut rng = thread_rng();
+    let mut challenger = Challenger::from_hasher(ByteHash {});
+
+    let (commits, data): (Vec<_>, Vec<_>) = log_heights
+        .iter()
+        .map(|batch_log_heights| {
+            let polynomials = batch_log_heights
+                .iter()
+                .map(|&log_height| RowMajorMatrix::rand(&mut rng, 1 << log_height, 3))
+                .collect::<Vec<_>>();
+            let (commit, data) = pcs.commit_batches(polynomials.clone());
+            challenger.observe(commit.clone());
+            (commit, (polynomials, data))
+        })
+        .unzip();
+
+    let batches = data
+        .iter()
+        .map(|(polynomials, _)| Batch {
+            polynomials: polynomials.clone(),
+            points: polynomials
+                .iter()
+                .map(|mat| {
+                    let num_variables = p3_util::log2_strict_usize(mat.height());
+                    (0..num_points)
+                        .map(|_| sample_point(&mut challenger, num_variables))
+                        .collect()
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    let (opened_values, proof) =
+        <MyPcs as MultivariatePcs<_, _, RowMajorMatrix<Val>, _>>::open_multi_batches(
+            pcs,
+            &data
+                .iter()
+                .zip(&batches)
+                .map(|((_, data), batch)| (data, batch.points.as_slice()))
+                .collect::<Vec<_>>(),
+            &mut challenger,
+        );
+
+    // The opened values are the polynomials' evaluations at the points.
+    for (batch, values_for_batch) in batches.iter().zip(&opened_values) {
+        for (mat, points, values) in
+            itertools::izip!(&batch.polynomials, &batch.points, values_for_batch)
+        {
+            let mat_t = mat.clone().transpose();
+            for (point, values_at_point) in points.iter().zip(values) {
+                let expected = mat_t
+                    .rows()
+                    .map(|column| evaluate_naive(column, point))
+                    .collect::<Vec<_>>();
+                assert_eq!(&expected, values_at_point);
+            }
+        }
+    }
+
+    (batches, commits, opened_values, proof)
+}
+
+fn verify(
+    pcs: &MyPcs,
+    batches: &[Batch],
+    commits: &[Commitment],
+    dims: &[Vec<Dimensions>],
+    opened_values: OpenedValues<Challenge>,
+    proof: &Proof,
+) -> Result<(), Error> {
+    let mut challenger = Challenger::from_hasher(ByteHash {});
+    for commit in commits {
+        challenger.observe(commit.clone());
+    }
+    for batch in batches {
+        for point in batch.points.iter().flatten() {
+            sample_point(&mut challenger, point.len());
+        }
+    }
+    <MyPcs as MultivariatePcs<_, _, RowMajorMatrix<Val>, _>>::verify_multi_batches(
+        pcs,
+        &commits
+            .iter()
+            .zip(batches)
+            .map(|(commit, batch)| (commit.clone(), batch.points.as_slice()))
+            .collect::<Vec<_>>(),
+        dims,
+        opened_values,
+        proof,
+        &mut challenger,
+    )
+}
+
+fn dims(batches: &[Batch]) -> Vec<Vec<Dimensions>> {
+    batches
+        .iter()
+        .map(|batch| batch.polynomials.iter().map(|p| p.dimensions()).collect())
+        .collect()
+}
+
+fn do_test_multi_from_uni(log_heights: &[&[usize]], num_points: usize) {
+    let pcs = make_pcs();
+    let (batches, commits, opened_values, proof) = open(&pcs, log_heights, num_points);
+    verify(
+        &pcs,
+        &batches,
+        &commits,
+        &dims(&batches),
+        opened_values,
+        &proof,
+    )
+    .expect("verification error");
+}
+
+#[test]
+fn test_multi_from_uni_single() {
+    do_test_multi_from_uni(&[&[4]], 1);
+}
+
+#[test]
+fn test_multi_from_uni_smallest() {
+    do_test_multi_from_uni(&[&[1]], 1);
+}
+
+#[test]
+fn test_multi_from_uni_many_batches_and_points() {
+    do_test_multi_from_uni(&[&[3, 6], &[5], &[2, 2]], 3);
+}
+
+#[test]
+fn test_multi_from_uni_rejects_wrong_values() {
+    let pcs = make_pcs();
+    let (batches, commits, opened_values, proof) = open(&pcs, &[&[3, 5]], 2);
+    let dims = dims(&batches);
+
+    // The wrong value changes the transcript, so the univariate openings no longer match either.
+    let mut wrong_value = opened_values;
+    wrong_value[0][1][1][0] += Challenge::one();
+    assert!(verify(&pcs, &batches, &commits, &dims, wrong_value, &proof).is_err());
+}
+
+#[test]
+fn test_multi_from_uni_rejects_wrong_shapes() {
+    let pcs = make_pcs();
+    let (batches, commits, opened_values, proof) = open(&pcs, &[&[3, 5]], 1);
+    let dims = dims(&batches);
+
+    let mut bad_height = dims.clone();
+    bad_height[0][0].height = 16;
+    assert!(matches!(
+        verify(
+            &pcs,
+            &batches,
+            &commits,
+            &bad_height,
+            opened_values.clone(),
+            &proof
+        ),
+        Err(MultiFromUniPcsError::InvalidProofShape)
+    ));
+    let mut missing_value = opened_values;
+    missing_value[0][0][0].pop();
+    assert!(matches!(
+        verify(&pcs, &batches, &commits, &dims, missing_value, &proof),
+        Err(MultiFromUniPcsError::InvalidProofShape)
+    ));
+}
+
+#[test]
+fn test_multi_from_uni_rejects_wrong_points() {
+    let pcs = make_pcs();
+    let (batches, commits, opened_values, proof) = open(&pcs, &[&[3, 5]], 2);
+    let dims = dims(&batches);
+
+    // The points aren't observed, so the univariate openings still hold, but not the sumcheck.
+    let mut wrong_point = batches;
+    wrong_point[0].points[1][0][2] += Challenge::one();
+    assert!(matches!(
+        verify(&pcs, &wrong_point, &commits, &dims, opened_values, &proof),
+        Err(MultiFromUniPcsError::SumcheckMismatch)
+    ));
+}
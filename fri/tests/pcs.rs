@@ -1,18 +1,26 @@
 use p3_baby_bear::BabyBear;
-use p3_challenger::{CanObserve, DuplexChallenger, FieldChallenger};
-use p3_commit::{ExtensionMmcs, Pcs, UnivariatePcs};
+use p3_challenger::{CanObserve, DuplexChallenger, FieldChallenger, SerializingChallenger32};
+use p3_commit::{BlindingRng, ExtensionMmcs, Pcs, UnivariatePcs};
 use p3_dft::Radix2DitParallel;
 use p3_field::extension::BinomialExtensionField;
-use p3_field::Field;
+use p3_field::{Field, TwoAdicField};
 use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_keccak::Keccak256Hash;
 use p3_matrix::dense::RowMajorMatrix;
-use p3_matrix::Matrix;
-use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_matrix::{Dimensions, Matrix};
+use p3_merkle_tree::{FieldMerkleTreeHidingMmcs, FieldMerkleTreeMmcs};
 use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
-use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_symmetric::{
+    CompressionFunctionFromHasher, PaddingFreeSponge, SerializingHasher32, TruncatedPermutation,
+};
 use rand::thread_rng;
 
-fn make_test_fri_pcs(log_degrees: &[usize]) {
+fn make_test_fri_pcs(
+    log_degrees: &[usize],
+    log_folding_arity: usize,
+    log_final_poly_len: usize,
+    cap_height: usize,
+) {
     let mut rng = thread_rng();
     type Val = BabyBear;
     type Challenge = BinomialExtensionField<Val, 4>;
@@ -27,7 +35,7 @@ fn make_test_fri_pcs(log_degrees: &[usize]) {
     let compress = MyCompress::new(perm.clone());
 
     type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
-    let val_mmcs = ValMmcs::new(hash, compress);
+    let val_mmcs = ValMmcs::new_with_cap(hash, compress, cap_height);
 
     type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
     let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
@@ -39,6 +47,8 @@ fn make_test_fri_pcs(log_degrees: &[usize]) {
 
     let fri_config = FriConfig {
         log_blowup: 1,
+        log_folding_arity,
+        log_final_poly_len,
         num_queries: 10,
         proof_of_work_bits: 8,
         mmcs: challenge_mmcs,
@@ -56,7 +66,7 @@ fn make_test_fri_pcs(log_degrees: &[usize]) {
 
     let (commit, data) = pcs.commit_batches(polynomials.clone());
 
-    challenger.observe(commit);
+    challenger.observe(commit.clone());
 
     let zeta = challenger.sample_ext_element::<Challenge>();
 
@@ -69,33 +79,47 @@ fn make_test_fri_pcs(log_degrees: &[usize]) {
     );
 
     // verify the proof.
-    let mut challenger = Challenger::new(perm);
-    challenger.observe(commit);
-    let _ = challenger.sample_ext_element::<Challenge>();
+    let verify = |dims: Vec<Dimensions>, opening| {
+        let mut challenger = Challenger::new(perm.clone());
+        challenger.observe(commit.clone());
+        let _ = challenger.sample_ext_element::<Challenge>();
+        <Pcs as UnivariatePcs<_, _, RowMajorMatrix<Val>, _>>::verify_multi_batches(
+            &pcs,
+            &[(commit.clone(), &points)],
+            &[dims],
+            opening,
+            &proof,
+            &mut challenger,
+        )
+    };
     let dims = polynomials
         .iter()
         .map(|p| p.dimensions())
         .collect::<Vec<_>>();
-    <Pcs as UnivariatePcs<_, _, RowMajorMatrix<Val>, _>>::verify_multi_batches(
-        &pcs,
-        &[(commit, &points)],
-        &[dims],
-        opening,
-        &proof,
-        &mut challenger,
-    )
-    .expect("verification error");
+    verify(dims.clone(), opening.clone()).expect("verification error");
+
+    // Claimed dimensions and openings which don't match are rejected, rather than panicking.
+    let mut bad_height = dims.clone();
+    bad_height[0].height += 1;
+    assert!(verify(bad_height, opening.clone()).is_err());
+    let mut too_tall = dims.clone();
+    too_tall[0].height = 1 << Val::TWO_ADICITY;
+    assert!(verify(too_tall, opening.clone()).is_err());
+    assert!(verify(dims[1..].to_vec(), opening.clone()).is_err());
+    let mut missing_value = opening;
+    missing_value[0][0][0].pop();
+    assert!(verify(dims, missing_value).is_err());
 }
 
 #[test]
 fn test_fri_pcs_single() {
-    make_test_fri_pcs(&[3]);
+    make_test_fri_pcs(&[3], 1, 0, 0);
 }
 
 #[test]
 fn test_fri_pcs_many_equal() {
     for i in 1..4 {
-        make_test_fri_pcs(&[i; 5]);
+        make_test_fri_pcs(&[i; 5], 1, 0, 0);
     }
 }
 
@@ -103,7 +127,7 @@ fn test_fri_pcs_many_equal() {
 fn test_fri_pcs_many_different() {
     for i in 2..4 {
         let degrees = (3..3 + i).collect::<Vec<_>>();
-        make_test_fri_pcs(&degrees);
+        make_test_fri_pcs(&degrees, 1, 0, 0);
     }
 }
 
@@ -111,6 +135,206 @@ fn test_fri_pcs_many_different() {
 fn test_fri_pcs_many_different_rev() {
     for i in 2..4 {
         let degrees = (3..3 + i).rev().collect::<Vec<_>>();
-        make_test_fri_pcs(&degrees);
+        make_test_fri_pcs(&degrees, 1, 0, 0);
+    }
+}
+
+#[test]
+fn test_fri_pcs_higher_arity() {
+    for log_folding_arity in 2..=4 {
+        make_test_fri_pcs(&[8], log_folding_arity, 0, 0);
+        make_test_fri_pcs(&[2, 7, 9], log_folding_arity, 0, 0);
+    }
+}
+
+#[test]
+fn test_fri_pcs_final_poly() {
+    for log_final_poly_len in 1..=3 {
+        make_test_fri_pcs(&[8], 1, log_final_poly_len, 0);
+        make_test_fri_pcs(&[1, 5, 7], 2, log_final_poly_len, 0);
+    }
+}
+
+#[test]
+fn test_fri_pcs_merkle_cap() {
+    for cap_height in 1..=3 {
+        make_test_fri_pcs(&[8], 1, 0, cap_height);
+        // The cap of the input commitment is limited by the smallest matrix.
+        make_test_fri_pcs(&[1, 5, 7], 2, 1, cap_height);
+    }
+}
+
+/// A config in which the Merkle trees and the transcript all use Keccak-256 over bytes, as an EVM
+/// verifier would.
+fn make_test_keccak_fri_pcs(log_degrees: &[usize], cap_height: usize) {
+    let mut rng = thread_rng();
+    type Val = BabyBear;
+    type Challenge = BinomialExtensionField<Val, 4>;
+
+    type ByteHash = Keccak256Hash;
+    type FieldHash = SerializingHasher32<ByteHash>;
+    let field_hash = FieldHash::new(ByteHash {});
+
+    type MyCompress = CompressionFunctionFromHasher<u8, ByteHash, 2, 32>;
+    let compress = MyCompress::new(ByteHash {});
+
+    type ValMmcs = FieldMerkleTreeMmcs<Val, FieldHash, MyCompress, 32, u8>;
+    let val_mmcs = ValMmcs::new_with_cap(field_hash, compress, cap_height);
+
+    type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+
+    type Dft = Radix2DitParallel;
+    let dft = Dft {};
+
+    type Challenger = SerializingChallenger32<Val, ByteHash>;
+
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 10,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+    type Pcs =
+        TwoAdicFriPcs<TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>>;
+    let pcs = Pcs::new(fri_config, dft, val_mmcs);
+
+    let mut challenger = Challenger::from_hasher(ByteHash {});
+
+    let polynomials = log_degrees
+        .iter()
+        .map(|d| RowMajorMatrix::rand(&mut rng, 1 << *d, 10))
+        .collect::<Vec<_>>();
+
+    let (commit, data) = pcs.commit_batches(polynomials.clone());
+
+    challenger.observe(commit.clone());
+
+    let zeta = challenger.sample_ext_element::<Challenge>();
+
+    let points = polynomials.iter().map(|_| vec![zeta]).collect::<Vec<_>>();
+
+    let (opening, proof) = <Pcs as UnivariatePcs<_, _, RowMajorMatrix<Val>, _>>::open_multi_batches(
+        &pcs,
+        &[(&data, &points)],
+        &mut challenger,
+    );
+
+    // verify the proof.
+    let mut challenger = Challenger::from_hasher(ByteHash {});
+    challenger.observe(commit.clone());
+    let _ = challenger.sample_ext_element::<Challenge>();
+    let dims = polynomials
+        .iter()
+        .map(|p| p.dimensions())
+        .collect::<Vec<_>>();
+    <Pcs as UnivariatePcs<_, _, RowMajorMatrix<Val>, _>>::verify_multi_batches(
+        &pcs,
+        &[(commit, &points)],
+        &[dims],
+        opening,
+        &proof,
+        &mut challenger,
+    )
+    .expect("verification error");
+}
+
+#[test]
+fn test_keccak_fri_pcs() {
+    make_test_keccak_fri_pcs(&[3], 0);
+    make_test_keccak_fri_pcs(&[3, 4, 5], 2);
+}
+
+fn make_test_hiding_fri_pcs(log_degrees: &[usize]) {
+    let mut rng = thread_rng();
+    type Val = BabyBear;
+    type Challenge = BinomialExtensionField<Val, 4>;
+
+    type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut rng);
+
+    type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+    let hash = MyHash::new(perm.clone());
+
+    type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+    let compress = MyCompress::new(perm.clone());
+
+    let blinding_rng = BlindingRng::new(&mut rng);
+
+    type ValMmcs = FieldMerkleTreeHidingMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+    let val_mmcs = ValMmcs::new(hash, compress, 4, blinding_rng.clone());
+
+    type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+
+    type Dft = Radix2DitParallel;
+    let dft = Dft {};
+
+    type Challenger = DuplexChallenger<Val, Perm, 16>;
+
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 10,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+    type Pcs =
+        TwoAdicFriPcs<TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>>;
+    let pcs = Pcs::new_hiding(fri_config, dft, val_mmcs, blinding_rng);
+
+    let mut challenger = Challenger::new(perm.clone());
+
+    let polynomials = log_degrees
+        .iter()
+        .map(|d| RowMajorMatrix::rand(&mut rng, 1 << *d, 10))
+        .collect::<Vec<_>>();
+
+    let (commit, data) = pcs.commit_batches(polynomials.clone());
+
+    challenger.observe(commit.clone());
+
+    let zeta = challenger.sample_ext_element::<Challenge>();
+
+    let points = polynomials.iter().map(|_| vec![zeta]).collect::<Vec<_>>();
+
+    let (opening, proof) = <Pcs as UnivariatePcs<_, _, RowMajorMatrix<Val>, _>>::open_multi_batches(
+        &pcs,
+        &[(&data, &points)],
+        &mut challenger,
+    );
+
+    // verify the proof.
+    let mut challenger = Challenger::new(perm);
+    challenger.observe(commit.clone());
+    let _ = challenger.sample_ext_element::<Challenge>();
+    let dims = polynomials
+        .iter()
+        .map(|p| p.dimensions())
+        .collect::<Vec<_>>();
+    <Pcs as UnivariatePcs<_, _, RowMajorMatrix<Val>, _>>::verify_multi_batches(
+        &pcs,
+        &[(commit, &points)],
+        &[dims],
+        opening,
+        &proof,
+        &mut challenger,
+    )
+    .expect("verification error");
+}
+
+#[test]
+fn test_hiding_fri_pcs_single() {
+    make_test_hiding_fri_pcs(&[3]);
+}
+
+#[test]
+fn test_hiding_fri_pcs_many_different() {
+    for i in 2..4 {
+        let degrees = (3..3 + i).collect::<Vec<_>>();
+        make_test_hiding_fri_pcs(&degrees);
     }
 }
@@ -17,10 +17,11 @@ use p3_field::{
 use p3_util::{assume, branch_hint};
 use rand::distributions::{Distribution, Standard};
 use rand::Rng;
-use serde::{Deserialize, Serialize};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
 
 /// The prime field known as Goldilocks, defined as `F_p` where `p = 2^64 - 2^32 + 1`.
-#[derive(Copy, Clone, Default, Serialize, Deserialize)]
+#[derive(Copy, Clone, Default)]
 pub struct Goldilocks {
     /// Not necessarily canonical.
     value: u64,
@@ -35,6 +36,22 @@ impl Goldilocks {
     const NEG_ORDER: u64 = Self::ORDER_U64.wrapping_neg();
 }
 
+impl Serialize for Goldilocks {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.as_canonical_u64())
+    }
+}
+
+impl<'de> Deserialize<'de> for Goldilocks {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let val = u64::deserialize(d)?;
+        if val >= Self::ORDER_U64 {
+            return Err(D::Error::custom("non-canonical Goldilocks element"));
+        }
+        Ok(Self::new(val))
+    }
+}
+
 impl PartialEq for Goldilocks {
     fn eq(&self, other: &Self) -> bool {
         self.as_canonical_u64() == other.as_canonical_u64()
@@ -55,6 +55,8 @@ fn main() -> Result<(), VerificationError> {
 
     let fri_config = FriConfig {
         log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
         num_queries: 100,
         proof_of_work_bits: 16,
         mmcs: challenge_mmcs,
@@ -1,4 +1,4 @@
-use p3_challenger::DuplexChallenger;
+use p3_challenger::SerializingChallenger64;
 use p3_commit::ExtensionMmcs;
 use p3_dft::Radix2DitParallel;
 use p3_field::extension::BinomialExtensionField;
@@ -6,12 +6,10 @@ use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
 use p3_goldilocks::Goldilocks;
 use p3_keccak::Keccak256Hash;
 use p3_keccak_air::{generate_trace_rows, KeccakAir};
-use p3_matrix::dense::RowMajorMatrix;
 use p3_merkle_tree::FieldMerkleTreeMmcs;
-use p3_poseidon2::{DiffusionMatrixGoldilocks, Poseidon2};
 use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher64};
 use p3_uni_stark::{prove, verify, PublicRow, StarkConfig, VerificationError};
-use rand::{random, thread_rng};
+use rand::random;
 use tracing_forest::util::LevelFilter;
 use tracing_forest::ForestLayer;
 use tracing_subscriber::layer::SubscriberExt;
@@ -33,16 +31,17 @@ fn main() -> Result<(), VerificationError> {
     type Val = Goldilocks;
     type Challenge = BinomialExtensionField<Val, 2>;
 
-    type Perm = Poseidon2<Val, DiffusionMatrixGoldilocks, 8, 7>;
-    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixGoldilocks, &mut thread_rng());
+    // Leaves, Merkle tree nodes and the transcript are all hashed with Keccak-256 over bytes, so
+    // that proofs can be verified cheaply on the EVM.
+    type ByteHash = Keccak256Hash;
+    type FieldHash = SerializingHasher64<ByteHash>;
+    let field_hash = FieldHash::new(ByteHash {});
 
-    type MyHash = SerializingHasher64<Keccak256Hash>;
-    let hash = MyHash::new(Keccak256Hash {});
-    type MyCompress = CompressionFunctionFromHasher<Val, MyHash, 2, 4>;
-    let compress = MyCompress::new(hash);
+    type MyCompress = CompressionFunctionFromHasher<u8, ByteHash, 2, 32>;
+    let compress = MyCompress::new(ByteHash {});
 
-    type ValMmcs = FieldMerkleTreeMmcs<Val, MyHash, MyCompress, 4>;
-    let val_mmcs = ValMmcs::new(hash, compress);
+    type ValMmcs = FieldMerkleTreeMmcs<Val, FieldHash, MyCompress, 32, u8>;
+    let val_mmcs = ValMmcs::new(field_hash, compress);
 
     type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
     let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
@@ -50,10 +49,12 @@ fn main() -> Result<(), VerificationError> {
     type Dft = Radix2DitParallel;
     let dft = Dft {};
 
-    type Challenger = DuplexChallenger<Val, Perm, 8>;
+    type Challenger = SerializingChallenger64<Val, ByteHash>;
 
     let fri_config = FriConfig {
         log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
         num_queries: 100,
         proof_of_work_bits: 16,
         mmcs: challenge_mmcs,
@@ -65,7 +66,7 @@ fn main() -> Result<(), VerificationError> {
     type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
     let config = StarkConfig::new(pcs);
 
-    let mut challenger = Challenger::new(perm.clone());
+    let mut challenger = Challenger::from_hasher(ByteHash {});
 
     let inputs = (0..NUM_HASHES).map(|_| random()).collect::<Vec<_>>();
     let trace = generate_trace_rows::<Val>(inputs);
@@ -77,12 +78,12 @@ fn main() -> Result<(), VerificationError> {
         &PublicRow::default(),
     );
 
-    let mut challenger = Challenger::new(perm);
+    let mut challenger = Challenger::from_hasher(ByteHash {});
     verify(
         &config,
         &KeccakAir {},
         &mut challenger,
         &proof,
-        &RowMajorMatrix::new(vec![], 0),
+        &PublicRow::default(),
     )
 }
@@ -146,6 +146,12 @@ pub trait ParIterExt: Iterator {
         Self: Sized,
         U: IntoIterator,
         F: Fn(Self::Item) -> U;
+
+    fn map_init<F, INIT, T, R>(self, init: INIT, map_op: F) -> MapInit<Self, T, F>
+    where
+        Self: Sized,
+        F: Fn(&mut T, Self::Item) -> R,
+        INIT: Fn() -> T;
 }
 
 impl<T: Iterator> ParIterExt for T {
@@ -164,6 +170,43 @@ impl<T: Iterator> ParIterExt for T {
     {
         self.flat_map(map_op)
     }
+
+    fn map_init<F, INIT, T, R>(self, init: INIT, map_op: F) -> MapInit<Self, T, F>
+    where
+        Self: Sized,
+        F: Fn(&mut T, Self::Item) -> R,
+        INIT: Fn() -> T,
+    {
+        MapInit {
+            iter: self,
+            state: init(),
+            map_op,
+        }
+    }
+}
+
+/// The iterator returned by `map_init`. Serially, a single state is shared by every item.
+pub struct MapInit<I, T, F> {
+    iter: I,
+    state: T,
+    map_op: F,
+}
+
+impl<I, T, F, R> Iterator for MapInit<I, T, F>
+where
+    I: Iterator,
+    F: Fn(&mut T, I::Item) -> R,
+{
+    type Item = R;
+
+    fn next(&mut self) -> Option<R> {
+        let item = self.iter.next()?;
+        Some((self.map_op)(&mut self.state, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
 }
 
 pub fn join<A, B, RA, RB>(oper_a: A, oper_b: B) -> (RA, RB)
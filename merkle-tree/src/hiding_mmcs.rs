@@ -0,0 +1,318 @@
+use alloc::vec::Vec;
+
+use p3_commit::{BlindingRng, DirectMmcs, Mmcs};
+use p3_field::PackedField;
+use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
+use p3_matrix::{Dimensions, Matrix};
+use p3_symmetric::{CryptographicHasher, MerkleCap, PseudoCompressionFunction};
+use serde::{Deserialize, Serialize};
+
+use crate::{FieldMerkleTree, FieldMerkleTreeMmcs};
+
+/// A `FieldMerkleTreeMmcs` whose leaves are salted with random field elements, so that neither the
+/// commitment nor the sibling digests in an opening proof reveal anything about unopened rows.
+///
+/// The salts form an extra matrix, committed alongside the tallest matrices of each batch. An
+/// opening proof consists of the salt of the opened row and the usual Merkle path.
+#[derive(Clone)]
+pub struct FieldMerkleTreeHidingMmcs<P, H, C, const DIGEST_ELEMS: usize> {
+    inner: FieldMerkleTreeMmcs<P, H, C, DIGEST_ELEMS>,
+    salt_elems: usize,
+    rng: BlindingRng,
+}
+
+impl<P, H, C, const DIGEST_ELEMS: usize> FieldMerkleTreeHidingMmcs<P, H, C, DIGEST_ELEMS> {
+    pub fn new(hash: H, compress: C, salt_elems: usize, rng: BlindingRng) -> Self {
+        Self::new_with_cap(hash, compress, 0, salt_elems, rng)
+    }
+
+    /// A hiding MMCS which commits to a cap of `2^cap_height` digests of each tree. See
+    /// `FieldMerkleTreeMmcs`.
+    pub fn new_with_cap(
+        hash: H,
+        compress: C,
+        cap_height: usize,
+        salt_elems: usize,
+        rng: BlindingRng,
+    ) -> Self {
+        assert!(
+            salt_elems > 0,
+            "a hiding MMCS needs at least one salt element"
+        );
+        Self {
+            inner: FieldMerkleTreeMmcs::new_with_cap(hash, compress, cap_height),
+            salt_elems,
+            rng,
+        }
+    }
+}
+
+impl<P, H, C, const DIGEST_ELEMS: usize> FieldMerkleTreeHidingMmcs<P, H, C, DIGEST_ELEMS> {
+    /// The dimensions of the committed matrices, including the salts.
+    fn salted_dimensions(&self, dimensions: &[Dimensions]) -> Result<Vec<Dimensions>, ()> {
+        let max_height = dimensions.iter().map(|dims| dims.height).max().ok_or(())?;
+        let mut salted_dimensions = dimensions.to_vec();
+        salted_dimensions.push(Dimensions {
+            width: self.salt_elems,
+            height: max_height,
+        });
+        Ok(salted_dimensions)
+    }
+}
+
+impl<P, H, C, const DIGEST_ELEMS: usize> Mmcs<P::Scalar>
+    for FieldMerkleTreeHidingMmcs<P, H, C, DIGEST_ELEMS>
+where
+    P: PackedField + Send + Sync,
+    H: CryptographicHasher<P::Scalar, [P::Scalar; DIGEST_ELEMS]>,
+    H: CryptographicHasher<P, [P; DIGEST_ELEMS]>,
+    H: Send + Sync,
+    C: PseudoCompressionFunction<[P::Scalar; DIGEST_ELEMS], 2>,
+    C: PseudoCompressionFunction<[P; DIGEST_ELEMS], 2>,
+    C: Send + Sync,
+    [P::Scalar; DIGEST_ELEMS]: Serialize + for<'de> Deserialize<'de>,
+{
+    /// The committed tree, whose last leaf matrix holds the salts.
+    type ProverData = FieldMerkleTree<P::Scalar, DIGEST_ELEMS>;
+    type Commitment = MerkleCap<P::Scalar, P::Scalar, DIGEST_ELEMS>;
+    /// The salt of the opened row, and the Merkle path.
+    type Proof = (Vec<P::Scalar>, Vec<[P::Scalar; DIGEST_ELEMS]>);
+    /// The salt of each opened row, and the Merkle multi-proof.
+    type MultiProof = (Vec<Vec<P::Scalar>>, Vec<[P::Scalar; DIGEST_ELEMS]>);
+    type Error = ();
+    type Mat<'a>
+        = RowMajorMatrixView<'a, P::Scalar>
+    where
+        H: 'a,
+        C: 'a;
+
+    fn open_batch(
+        &self,
+        index: usize,
+        prover_data: &FieldMerkleTree<P::Scalar, DIGEST_ELEMS>,
+    ) -> (Vec<Vec<P::Scalar>>, Self::Proof) {
+        let (mut openings, proof) = self.inner.open_batch(index, prover_data);
+        let salt = openings
+            .pop()
+            .expect("the salts should be the last committed matrix");
+        (openings, (salt, proof))
+    }
+
+    fn open_multi_batch(
+        &self,
+        indices: &[usize],
+        prover_data: &FieldMerkleTree<P::Scalar, DIGEST_ELEMS>,
+    ) -> (Vec<Vec<Vec<P::Scalar>>>, Self::MultiProof) {
+        let (mut openings, proof) = self.inner.open_multi_batch(indices, prover_data);
+        let salts = openings
+            .iter_mut()
+            .map(|opened_rows| {
+                opened_rows
+                    .pop()
+                    .expect("the salts should be the last committed matrix")
+            })
+            .collect();
+        (openings, (salts, proof))
+    }
+
+    fn cap_height(&self) -> usize {
+        self.inner.cap_height()
+    }
+
+    fn get_matrices<'a>(
+        &'a self,
+        prover_data: &'a Self::ProverData,
+    ) -> Vec<RowMajorMatrixView<'a, P::Scalar>> {
+        let mut matrices = self.inner.get_matrices(prover_data);
+        matrices.pop();
+        matrices
+    }
+
+    fn verify_batch(
+        &self,
+        commit: &MerkleCap<P::Scalar, P::Scalar, DIGEST_ELEMS>,
+        dimensions: &[Dimensions],
+        index: usize,
+        opened_values: &[Vec<P::Scalar>],
+        proof: &Self::Proof,
+    ) -> Result<(), Self::Error> {
+        let (salt, path) = proof;
+        if salt.len() != self.salt_elems {
+            return Err(());
+        }
+
+        let mut salted_values = opened_values.to_vec();
+        salted_values.push(salt.clone());
+
+        self.inner.verify_batch(
+            commit,
+            &self.salted_dimensions(dimensions)?,
+            index,
+            &salted_values,
+            path,
+        )
+    }
+
+    fn verify_multi_batch(
+        &self,
+        commit: &MerkleCap<P::Scalar, P::Scalar, DIGEST_ELEMS>,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        opened_values: &[Vec<Vec<P::Scalar>>],
+        proof: &Self::MultiProof,
+    ) -> Result<(), Self::Error> {
+        let (salts, multi_path) = proof;
+        if salts.len() != opened_values.len()
+            || salts.iter().any(|salt| salt.len() != self.salt_elems)
+        {
+            return Err(());
+        }
+
+        let salted_values = opened_values
+            .iter()
+            .zip(salts)
+            .map(|(opened_rows, salt)| {
+                let mut salted_rows = opened_rows.clone();
+                salted_rows.push(salt.clone());
+                salted_rows
+            })
+            .collect::<Vec<_>>();
+
+        self.inner.verify_multi_batch(
+            commit,
+            &self.salted_dimensions(dimensions)?,
+            indices,
+            &salted_values,
+            multi_path,
+        )
+    }
+}
+
+impl<P, H, C, const DIGEST_ELEMS: usize> DirectMmcs<P::Scalar>
+    for FieldMerkleTreeHidingMmcs<P, H, C, DIGEST_ELEMS>
+where
+    P: PackedField + Send + Sync,
+    H: CryptographicHasher<P::Scalar, [P::Scalar; DIGEST_ELEMS]>,
+    H: CryptographicHasher<P, [P; DIGEST_ELEMS]>,
+    H: Send + Sync,
+    C: PseudoCompressionFunction<[P::Scalar; DIGEST_ELEMS], 2>,
+    C: PseudoCompressionFunction<[P; DIGEST_ELEMS], 2>,
+    C: Send + Sync,
+    [P::Scalar; DIGEST_ELEMS]: Serialize + for<'de> Deserialize<'de>,
+{
+    fn commit(
+        &self,
+        mut inputs: Vec<RowMajorMatrix<P::Scalar>>,
+    ) -> (Self::Commitment, Self::ProverData) {
+        let max_height = inputs
+            .iter()
+            .map(|mat| mat.height())
+            .max()
+            .expect("No matrices given?");
+        inputs.push(self.rng.random_matrix(self.salt_elems, max_height));
+        self.inner.commit(inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use p3_baby_bear::BabyBear;
+    use p3_commit::{BlindingRng, DirectMmcs, Mmcs};
+    use p3_field::{AbstractField, Field};
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::{Dimensions, Matrix, MatrixRowSlices};
+    use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+    use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+    use rand::thread_rng;
+
+    use crate::FieldMerkleTreeHidingMmcs;
+
+    type F = BabyBear;
+
+    type Perm = Poseidon2<F, DiffusionMatrixBabybear, 16, 7>;
+    type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+    type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+    type MyMmcs = FieldMerkleTreeHidingMmcs<<F as Field>::Packing, MyHash, MyCompress, 8>;
+
+    fn hiding_mmcs() -> MyMmcs {
+        let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        MyMmcs::new(hash, compress, 4, BlindingRng::new(&mut thread_rng()))
+    }
+
+    #[test]
+    fn commitments_are_salted() {
+        let mmcs = hiding_mmcs();
+        let mat = RowMajorMatrix::<F>::rand(&mut thread_rng(), 8, 3);
+        let (commit_1, _) = mmcs.commit_matrix(mat.clone());
+        let (commit_2, _) = mmcs.commit_matrix(mat);
+        assert_ne!(commit_1, commit_2);
+    }
+
+    #[test]
+    fn open_and_verify_mixed_heights() {
+        let mmcs = hiding_mmcs();
+        let large = RowMajorMatrix::<F>::rand(&mut thread_rng(), 16, 3);
+        let small = RowMajorMatrix::<F>::rand(&mut thread_rng(), 4, 5);
+        let dims = vec![large.dimensions(), small.dimensions()];
+        let (commit, prover_data) = mmcs.commit(vec![large.clone(), small.clone()]);
+
+        // The salts aren't exposed as committed data.
+        assert_eq!(mmcs.get_matrices(&prover_data).len(), 2);
+
+        let index = 13;
+        let (opened_values, proof) = mmcs.open_batch(index, &prover_data);
+        assert_eq!(
+            opened_values,
+            vec![
+                large.row_slice(index).to_vec(),
+                small.row_slice(index >> 2).to_vec(),
+            ]
+        );
+        mmcs.verify_batch(&commit, &dims, index, &opened_values, &proof)
+            .expect("expected verification to succeed");
+
+        let mut bad_salt = proof.clone();
+        bad_salt.0[0] += F::one();
+        assert!(mmcs
+            .verify_batch(&commit, &dims, index, &opened_values, &bad_salt)
+            .is_err());
+
+        let wrong_dims = vec![
+            dims[0],
+            Dimensions {
+                width: 5,
+                height: 2,
+            },
+        ];
+        assert!(mmcs
+            .verify_batch(&commit, &wrong_dims, index, &opened_values, &proof)
+            .is_err());
+    }
+
+    #[test]
+    fn open_and_verify_multi_batch() {
+        let mmcs = hiding_mmcs();
+        let large = RowMajorMatrix::<F>::rand(&mut thread_rng(), 16, 3);
+        let small = RowMajorMatrix::<F>::rand(&mut thread_rng(), 4, 5);
+        let dims = vec![large.dimensions(), small.dimensions()];
+        let (commit, prover_data) = mmcs.commit(vec![large, small]);
+
+        let indices = [2, 3, 11];
+        let (opened_values, proof) = mmcs.open_multi_batch(&indices, &prover_data);
+        assert!(opened_values
+            .iter()
+            .all(|opened_rows| opened_rows.len() == 2));
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &proof)
+            .expect("expected verification to succeed");
+
+        let mut bad_salt = proof.clone();
+        bad_salt.0[1][0] += F::one();
+        assert!(mmcs
+            .verify_multi_batch(&commit, &dims, &indices, &opened_values, &bad_salt)
+            .is_err());
+    }
+}
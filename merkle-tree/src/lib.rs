@@ -2,8 +2,10 @@
 
 extern crate alloc;
 
+mod hiding_mmcs;
 mod merkle_tree;
 mod mmcs;
 
+pub use hiding_mmcs::*;
 pub use merkle_tree::*;
 pub use mmcs::*;
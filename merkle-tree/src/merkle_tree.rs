@@ -4,53 +4,63 @@ use core::array;
 use core::cmp::Reverse;
 
 use itertools::Itertools;
-use p3_field::{AbstractField, Field, PackedField};
+use p3_field::{Field, PackedField, PackedValue};
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::{Matrix, MatrixRowSlices};
 use p3_maybe_rayon::prelude::*;
-use p3_symmetric::{CryptographicHasher, PseudoCompressionFunction};
+use p3_symmetric::{CryptographicHasher, MerkleCap, PseudoCompressionFunction};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
 
 /// A binary Merkle tree for field data. It has leaves of type `F` and digests of type
-/// `[F; DIGEST_ELEMS]`.
+/// `[W; DIGEST_ELEMS]`, where the digest words `W` are field elements by default, but may be other
+/// values, such as the bytes of a Keccak digest.
 ///
 /// This generally shouldn't be used directly. If you're using a Merkle tree as an MMCS,
 /// see `FieldMerkleTreeMmcs`.
 #[derive(Clone, Debug, Deserialize, Eq, Serialize, PartialEq)]
-#[serde(bound(serialize = "[F; DIGEST_ELEMS]: Serialize"))]
-#[serde(bound(deserialize = "[F; DIGEST_ELEMS]: DeserializeOwned"))]
-pub struct FieldMerkleTree<F: Field, const DIGEST_ELEMS: usize> {
+#[serde(bound(serialize = "[W; DIGEST_ELEMS]: Serialize"))]
+#[serde(bound(deserialize = "[W; DIGEST_ELEMS]: DeserializeOwned"))]
+pub struct FieldMerkleTree<F: Field, const DIGEST_ELEMS: usize, W = F> {
     pub(crate) leaves: Vec<RowMajorMatrix<F>>,
-    pub(crate) digest_layers: Vec<Vec<[F; DIGEST_ELEMS]>>,
+    pub(crate) digest_layers: Vec<Vec<[W; DIGEST_ELEMS]>>,
 }
 
-unsafe impl<F: Field + Send + Sync, const DIGEST_ELEMS: usize> Send
-    for FieldMerkleTree<F, DIGEST_ELEMS>
+unsafe impl<F: Field + Send + Sync, const DIGEST_ELEMS: usize, W: Send> Send
+    for FieldMerkleTree<F, DIGEST_ELEMS, W>
 {
 }
-unsafe impl<F: Field + Send + Sync, const DIGEST_ELEMS: usize> Sync
-    for FieldMerkleTree<F, DIGEST_ELEMS>
+unsafe impl<F: Field + Send + Sync, const DIGEST_ELEMS: usize, W: Sync> Sync
+    for FieldMerkleTree<F, DIGEST_ELEMS, W>
 {
 }
 
-impl<F: Field, const DIGEST_ELEMS: usize> FieldMerkleTree<F, DIGEST_ELEMS> {
+impl<F: Field, const DIGEST_ELEMS: usize, W: Copy> FieldMerkleTree<F, DIGEST_ELEMS, W> {
     /// Matrix heights need not be powers of two. However, if the heights of two given matrices
     /// round up to the same power of two, they must be equal.
+    ///
+    /// Leaves are hashed in packs of type `P`, and digests are computed in packs of type `PW`,
+    /// which must have the same width.
     #[instrument(name = "build merkle tree", level = "debug", skip_all,
                  fields(dimensions = alloc::format!("{:?}", leaves.iter().map(|l| l.dimensions()).collect::<Vec<_>>())))]
-    pub fn new<P, H, C>(h: &H, c: &C, leaves: Vec<RowMajorMatrix<F>>) -> Self
+    pub fn new<P, PW, H, C>(h: &H, c: &C, leaves: Vec<RowMajorMatrix<F>>) -> Self
     where
         P: PackedField<Scalar = F>,
-        H: CryptographicHasher<F, [F; DIGEST_ELEMS]>,
-        H: CryptographicHasher<P, [P; DIGEST_ELEMS]>,
+        PW: PackedValue<Value = W>,
+        H: CryptographicHasher<F, [W; DIGEST_ELEMS]>,
+        H: CryptographicHasher<P, [PW; DIGEST_ELEMS]>,
         H: Sync,
-        C: PseudoCompressionFunction<[F; DIGEST_ELEMS], 2>,
-        C: PseudoCompressionFunction<[P; DIGEST_ELEMS], 2>,
+        C: PseudoCompressionFunction<[W; DIGEST_ELEMS], 2>,
+        C: PseudoCompressionFunction<[PW; DIGEST_ELEMS], 2>,
         C: Sync,
     {
         assert!(!leaves.is_empty(), "No matrices given?");
+        assert_eq!(
+            <P as PackedField>::WIDTH,
+            PW::WIDTH,
+            "packing widths of the leaves and digests must match"
+        );
 
         // check height property
         assert!(
@@ -74,7 +84,7 @@ impl<F: Field, const DIGEST_ELEMS: usize> FieldMerkleTree<F, DIGEST_ELEMS> {
             .peeking_take_while(|m| m.height() == max_height)
             .collect_vec();
 
-        let mut digest_layers = vec![first_digest_layer::<P, H, DIGEST_ELEMS>(
+        let mut digest_layers = vec![first_digest_layer::<P, PW, H, DIGEST_ELEMS>(
             h,
             tallest_matrices,
         )];
@@ -90,8 +100,12 @@ impl<F: Field, const DIGEST_ELEMS: usize> FieldMerkleTree<F, DIGEST_ELEMS> {
                 .peeking_take_while(|m| m.height().next_power_of_two() == next_layer_len)
                 .collect_vec();
 
-            let next_digests =
-                compress_and_inject::<P, H, C, DIGEST_ELEMS>(prev_layer, matrices_to_inject, h, c);
+            let next_digests = compress_and_inject::<P, PW, H, C, DIGEST_ELEMS>(
+                prev_layer,
+                matrices_to_inject,
+                h,
+                c,
+            );
             digest_layers.push(next_digests);
         }
 
@@ -102,26 +116,40 @@ impl<F: Field, const DIGEST_ELEMS: usize> FieldMerkleTree<F, DIGEST_ELEMS> {
     }
 
     #[must_use]
-    pub fn root(&self) -> [F; DIGEST_ELEMS] {
+    pub fn root(&self) -> [W; DIGEST_ELEMS] {
         self.digest_layers.last().unwrap()[0]
     }
+
+    /// The `2^cap_height` digests at height `cap_height` below the root.
+    ///
+    /// Panics if `cap_height` exceeds the height of the tree.
+    #[must_use]
+    pub fn cap(&self, cap_height: usize) -> MerkleCap<F, W, DIGEST_ELEMS> {
+        assert!(
+            cap_height < self.digest_layers.len(),
+            "cap height exceeds the tree height"
+        );
+        let layer = &self.digest_layers[self.digest_layers.len() - 1 - cap_height];
+        MerkleCap::new(layer.clone())
+    }
 }
 
-fn first_digest_layer<P, H, const DIGEST_ELEMS: usize>(
+fn first_digest_layer<P, PW, H, const DIGEST_ELEMS: usize>(
     h: &H,
     tallest_matrices: Vec<&RowMajorMatrix<P::Scalar>>,
-) -> Vec<[P::Scalar; DIGEST_ELEMS]>
+) -> Vec<[PW::Value; DIGEST_ELEMS]>
 where
     P: PackedField,
-    H: CryptographicHasher<P::Scalar, [P::Scalar; DIGEST_ELEMS]>,
-    H: CryptographicHasher<P, [P; DIGEST_ELEMS]>,
+    PW: PackedValue,
+    H: CryptographicHasher<P::Scalar, [PW::Value; DIGEST_ELEMS]>,
+    H: CryptographicHasher<P, [PW; DIGEST_ELEMS]>,
     H: Sync,
 {
-    let width = P::WIDTH;
+    let width = PW::WIDTH;
     let max_height = tallest_matrices[0].height();
     let max_height_padded = max_height.next_power_of_two();
 
-    let default_digest = [P::Scalar::zero(); DIGEST_ELEMS];
+    let default_digest = [PW::Value::default(); DIGEST_ELEMS];
     let mut digests = vec![default_digest; max_height_padded];
 
     digests[0..max_height]
@@ -129,7 +157,7 @@ where
         .enumerate()
         .for_each(|(i, digests_chunk)| {
             let first_row = i * width;
-            let packed_digest: [P; DIGEST_ELEMS] = h.hash_iter(
+            let packed_digest: [PW; DIGEST_ELEMS] = h.hash_iter(
                 tallest_matrices
                     .iter()
                     .flat_map(|m| m.packed_row(first_row)),
@@ -151,30 +179,31 @@ where
 
 /// Compress `n` digests from the previous layer into `n/2` digests, while potentially mixing in
 /// some leaf data, if there are input matrices with (padded) height `n/2`.
-fn compress_and_inject<P, H, C, const DIGEST_ELEMS: usize>(
-    prev_layer: &[[P::Scalar; DIGEST_ELEMS]],
+fn compress_and_inject<P, PW, H, C, const DIGEST_ELEMS: usize>(
+    prev_layer: &[[PW::Value; DIGEST_ELEMS]],
     matrices_to_inject: Vec<&RowMajorMatrix<P::Scalar>>,
     h: &H,
     c: &C,
-) -> Vec<[P::Scalar; DIGEST_ELEMS]>
+) -> Vec<[PW::Value; DIGEST_ELEMS]>
 where
     P: PackedField,
-    H: CryptographicHasher<P::Scalar, [P::Scalar; DIGEST_ELEMS]>,
-    H: CryptographicHasher<P, [P; DIGEST_ELEMS]>,
+    PW: PackedValue,
+    H: CryptographicHasher<P::Scalar, [PW::Value; DIGEST_ELEMS]>,
+    H: CryptographicHasher<P, [PW; DIGEST_ELEMS]>,
     H: Sync,
-    C: PseudoCompressionFunction<[P::Scalar; DIGEST_ELEMS], 2>,
-    C: PseudoCompressionFunction<[P; DIGEST_ELEMS], 2>,
+    C: PseudoCompressionFunction<[PW::Value; DIGEST_ELEMS], 2>,
+    C: PseudoCompressionFunction<[PW; DIGEST_ELEMS], 2>,
     C: Sync,
 {
     if matrices_to_inject.is_empty() {
-        return compress::<P, C, DIGEST_ELEMS>(prev_layer, c);
+        return compress::<PW, C, DIGEST_ELEMS>(prev_layer, c);
     }
 
-    let width = P::WIDTH;
+    let width = PW::WIDTH;
     let next_len = matrices_to_inject[0].height();
     let next_len_padded = prev_layer.len() / 2;
 
-    let default_digest = [P::Scalar::zero(); DIGEST_ELEMS];
+    let default_digest = [PW::Value::default(); DIGEST_ELEMS];
     let mut next_digests = vec![default_digest; next_len_padded];
 
     next_digests[0..next_len]
@@ -182,8 +211,8 @@ where
         .enumerate()
         .for_each(|(i, digests_chunk)| {
             let first_row = i * width;
-            let left = array::from_fn(|j| P::from_fn(|k| prev_layer[2 * (first_row + k)][j]));
-            let right = array::from_fn(|j| P::from_fn(|k| prev_layer[2 * (first_row + k) + 1][j]));
+            let left = array::from_fn(|j| PW::from_fn(|k| prev_layer[2 * (first_row + k)][j]));
+            let right = array::from_fn(|j| PW::from_fn(|k| prev_layer[2 * (first_row + k) + 1][j]));
             let mut packed_digest = c.compress([left, right]);
             let tallest_digest = h.hash_iter(
                 matrices_to_inject
@@ -219,21 +248,21 @@ where
 }
 
 /// Compress `n` digests from the previous layer into `n/2` digests.
-fn compress<P, C, const DIGEST_ELEMS: usize>(
-    prev_layer: &[[P::Scalar; DIGEST_ELEMS]],
+fn compress<PW, C, const DIGEST_ELEMS: usize>(
+    prev_layer: &[[PW::Value; DIGEST_ELEMS]],
     c: &C,
-) -> Vec<[P::Scalar; DIGEST_ELEMS]>
+) -> Vec<[PW::Value; DIGEST_ELEMS]>
 where
-    P: PackedField,
-    C: PseudoCompressionFunction<[P::Scalar; DIGEST_ELEMS], 2>,
-    C: PseudoCompressionFunction<[P; DIGEST_ELEMS], 2>,
+    PW: PackedValue,
+    C: PseudoCompressionFunction<[PW::Value; DIGEST_ELEMS], 2>,
+    C: PseudoCompressionFunction<[PW; DIGEST_ELEMS], 2>,
     C: Sync,
 {
     debug_assert!(prev_layer.len().is_power_of_two());
-    let width = P::WIDTH;
+    let width = PW::WIDTH;
     let next_len = prev_layer.len() / 2;
 
-    let default_digest = [P::Scalar::zero(); DIGEST_ELEMS];
+    let default_digest = [PW::Value::default(); DIGEST_ELEMS];
     let mut next_digests = vec![default_digest; next_len];
 
     next_digests[0..next_len]
@@ -241,8 +270,8 @@ where
         .enumerate()
         .for_each(|(i, digests_chunk)| {
             let first_row = i * width;
-            let left = array::from_fn(|j| P::from_fn(|k| prev_layer[2 * (first_row + k)][j]));
-            let right = array::from_fn(|j| P::from_fn(|k| prev_layer[2 * (first_row + k) + 1][j]));
+            let left = array::from_fn(|j| PW::from_fn(|k| prev_layer[2 * (first_row + k)][j]));
+            let right = array::from_fn(|j| PW::from_fn(|k| prev_layer[2 * (first_row + k) + 1][j]));
             let packed_digest = c.compress([left, right]);
             for (dst, src) in digests_chunk.iter_mut().zip(unpack_array(packed_digest)) {
                 *dst = src;
@@ -263,8 +292,8 @@ where
 
 /// Converts a packed array `[P; N]` into its underlying `P::WIDTH` scalar arrays.
 #[inline]
-fn unpack_array<P: PackedField, const N: usize>(
+fn unpack_array<P: PackedValue, const N: usize>(
     packed_digest: [P; N],
-) -> impl Iterator<Item = [P::Scalar; N]> {
+) -> impl Iterator<Item = [P::Value; N]> {
     (0..P::WIDTH).map(move |j| packed_digest.map(|p| p.as_slice()[j]))
 }
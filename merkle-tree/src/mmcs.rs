@@ -1,13 +1,15 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::cmp::Reverse;
 use core::marker::PhantomData;
 
-use itertools::Itertools;
+use itertools::{izip, Itertools};
 use p3_commit::{DirectMmcs, Mmcs};
-use p3_field::PackedField;
+use p3_field::{PackedField, PackedValue};
 use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
 use p3_matrix::{Dimensions, Matrix, MatrixRows};
-use p3_symmetric::{CryptographicHasher, PseudoCompressionFunction};
+use p3_symmetric::{CryptographicHasher, MerkleCap, PseudoCompressionFunction};
 use p3_util::log2_ceil_usize;
 use serde::{Deserialize, Serialize};
 
@@ -19,53 +21,144 @@ use crate::FieldMerkleTree;
 /// - `P`: a leaf value TODO
 /// - `H`: the leaf hasher
 /// - `C`: the digest compression function
+/// - `PW`: a word of a digest, packed like `P`. By default digests are made of field elements, but
+///   with `PW = u8` and a scalar `P`, digests can be bytes, like those of `Keccak256Hash`.
+///
+/// A commitment is a `MerkleCap` of height `cap_height`, so opening proofs stop short of the root.
+/// The cap height is capped at the log of the smallest committed matrix's padded height, since the
+/// rows of that matrix are only mixed into the tree at that height.
 #[derive(Copy, Clone)]
-pub struct FieldMerkleTreeMmcs<P, H, C, const DIGEST_ELEMS: usize> {
+pub struct FieldMerkleTreeMmcs<P, H, C, const DIGEST_ELEMS: usize, PW = P> {
     hash: H,
     compress: C,
-    _phantom: PhantomData<P>,
+    cap_height: usize,
+    _phantom: PhantomData<(P, PW)>,
 }
 
-impl<P, H, C, const DIGEST_ELEMS: usize> Default for FieldMerkleTreeMmcs<P, H, C, DIGEST_ELEMS>
+impl<P, PW, H, C, const DIGEST_ELEMS: usize> Default
+    for FieldMerkleTreeMmcs<P, H, C, DIGEST_ELEMS, PW>
 where
     P: PackedField,
-    H: CryptographicHasher<P, [P; DIGEST_ELEMS]> + Default,
-    C: PseudoCompressionFunction<[P; DIGEST_ELEMS], 2> + Default,
+    PW: PackedValue,
+    H: CryptographicHasher<P, [PW; DIGEST_ELEMS]> + Default,
+    C: PseudoCompressionFunction<[PW; DIGEST_ELEMS], 2> + Default,
 {
     fn default() -> Self {
         Self {
             hash: H::default(),
             compress: C::default(),
+            cap_height: 0,
             _phantom: PhantomData,
         }
     }
 }
 
-impl<P, H, C, const DIGEST_ELEMS: usize> FieldMerkleTreeMmcs<P, H, C, DIGEST_ELEMS> {
+impl<P, PW, H, C, const DIGEST_ELEMS: usize> FieldMerkleTreeMmcs<P, H, C, DIGEST_ELEMS, PW> {
+    /// An MMCS which commits to the root of each tree.
     pub fn new(hash: H, compress: C) -> Self {
+        Self::new_with_cap(hash, compress, 0)
+    }
+
+    /// An MMCS which commits to a cap of `2^cap_height` digests of each tree.
+    pub fn new_with_cap(hash: H, compress: C, cap_height: usize) -> Self {
         Self {
             hash,
             compress,
+            cap_height,
             _phantom: PhantomData,
         }
     }
+
+    /// The height of the cap committed to for matrices of the given heights, and the length of the
+    /// path from a leaf to the cap.
+    fn cap_height_and_path_len(
+        &self,
+        heights: impl IntoIterator<Item = usize>,
+    ) -> Option<(usize, usize)> {
+        let (log_min_height, log_max_height) = heights
+            .into_iter()
+            .map(log2_ceil_usize)
+            .minmax()
+            .into_option()?;
+        let cap_height = self.cap_height.min(log_min_height);
+        Some((cap_height, log_max_height - cap_height))
+    }
+}
+
+impl<P, PW, H, C, const DIGEST_ELEMS: usize> FieldMerkleTreeMmcs<P, H, C, DIGEST_ELEMS, PW>
+where
+    P: PackedField,
+    PW: PackedValue,
+    PW::Value: Eq,
+    H: CryptographicHasher<P::Scalar, [PW::Value; DIGEST_ELEMS]>,
+{
+    /// The row of each matrix opened at `index`.
+    fn open_rows(
+        &self,
+        index: usize,
+        prover_data: &FieldMerkleTree<P::Scalar, DIGEST_ELEMS, PW::Value>,
+    ) -> Vec<Vec<P::Scalar>> {
+        let log_max_height = prover_data
+            .leaves
+            .iter()
+            .map(|matrix| log2_ceil_usize(matrix.height()))
+            .max()
+            .unwrap();
+
+        prover_data
+            .leaves
+            .iter()
+            .map(|matrix| {
+                let log2_height = log2_ceil_usize(matrix.height());
+                let bits_reduced = log_max_height - log2_height;
+                let reduced_index = index >> bits_reduced;
+                matrix.row(reduced_index).collect()
+            })
+            .collect()
+    }
+
+    /// Hash the rows of the given matrices opened at each of `indices`, into the digest of each
+    /// node `index >> layer`. Fails if two openings disagree on a node.
+    fn hash_opened_rows(
+        &self,
+        indices: &[usize],
+        opened_values: &[Vec<Vec<P::Scalar>>],
+        matrices: &[usize],
+        layer: usize,
+    ) -> Result<BTreeMap<usize, [PW::Value; DIGEST_ELEMS]>, ()> {
+        let mut digests = BTreeMap::new();
+        for (&index, opened_rows) in izip!(indices, opened_values) {
+            let digest = self
+                .hash
+                .hash_iter_slices(matrices.iter().map(|&i| opened_rows[i].as_slice()));
+            if *digests.entry(index >> layer).or_insert(digest) != digest {
+                return Err(());
+            }
+        }
+        Ok(digests)
+    }
 }
 
-impl<P, H, C, const DIGEST_ELEMS: usize> Mmcs<P::Scalar>
-    for FieldMerkleTreeMmcs<P, H, C, DIGEST_ELEMS>
+impl<P, PW, H, C, const DIGEST_ELEMS: usize> Mmcs<P::Scalar>
+    for FieldMerkleTreeMmcs<P, H, C, DIGEST_ELEMS, PW>
 where
     P: PackedField + Send + Sync,
-    H: CryptographicHasher<P::Scalar, [P::Scalar; DIGEST_ELEMS]>,
-    H: CryptographicHasher<P, [P; DIGEST_ELEMS]>,
+    PW: PackedValue,
+    PW::Value: Eq,
+    H: CryptographicHasher<P::Scalar, [PW::Value; DIGEST_ELEMS]>,
+    H: CryptographicHasher<P, [PW; DIGEST_ELEMS]>,
     H: Send + Sync,
-    C: PseudoCompressionFunction<[P::Scalar; DIGEST_ELEMS], 2>,
-    C: PseudoCompressionFunction<[P; DIGEST_ELEMS], 2>,
+    C: PseudoCompressionFunction<[PW::Value; DIGEST_ELEMS], 2>,
+    C: PseudoCompressionFunction<[PW; DIGEST_ELEMS], 2>,
     C: Send + Sync,
-    [P::Scalar; DIGEST_ELEMS]: Serialize + for<'de> Deserialize<'de>,
+    [PW::Value; DIGEST_ELEMS]: Serialize + for<'de> Deserialize<'de>,
 {
-    type ProverData = FieldMerkleTree<P::Scalar, DIGEST_ELEMS>;
-    type Commitment = [P::Scalar; DIGEST_ELEMS];
-    type Proof = Vec<[P::Scalar; DIGEST_ELEMS]>;
+    type ProverData = FieldMerkleTree<P::Scalar, DIGEST_ELEMS, PW::Value>;
+    type Commitment = MerkleCap<P::Scalar, PW::Value, DIGEST_ELEMS>;
+    type Proof = Vec<[PW::Value; DIGEST_ELEMS]>;
+    /// The sibling digests which can't be computed from the opened rows, layer by layer from the
+    /// leaves up, and from left to right within a layer.
+    type MultiProof = Vec<[PW::Value; DIGEST_ELEMS]>;
     type Error = ();
     type Mat<'a>
         = RowMajorMatrixView<'a, P::Scalar>
@@ -76,29 +169,59 @@ where
     fn open_batch(
         &self,
         index: usize,
-        prover_data: &FieldMerkleTree<P::Scalar, DIGEST_ELEMS>,
-    ) -> (Vec<Vec<P::Scalar>>, Vec<[P::Scalar; DIGEST_ELEMS]>) {
-        let max_height = self.get_max_height(prover_data);
-        let log_max_height = log2_ceil_usize(max_height);
+        prover_data: &FieldMerkleTree<P::Scalar, DIGEST_ELEMS, PW::Value>,
+    ) -> (Vec<Vec<P::Scalar>>, Vec<[PW::Value; DIGEST_ELEMS]>) {
+        let openings = self.open_rows(index, prover_data);
+
+        let (_, path_len) = self
+            .cap_height_and_path_len(self.get_matrix_heights(prover_data))
+            .unwrap();
+        let proof = (0..path_len)
+            .map(|i| prover_data.digest_layers[i][(index >> i) ^ 1])
+            .collect();
 
-        let openings = prover_data
-            .leaves
-            .iter()
-            .map(|matrix| {
-                let log2_height = log2_ceil_usize(matrix.height());
-                let bits_reduced = log_max_height - log2_height;
-                let reduced_index = index >> bits_reduced;
-                matrix.row(reduced_index).collect()
-            })
-            .collect_vec();
+        (openings, proof)
+    }
 
-        let proof = (0..log_max_height)
-            .map(|i| prover_data.digest_layers[i][(index >> i) ^ 1])
+    fn open_multi_batch(
+        &self,
+        indices: &[usize],
+        prover_data: &FieldMerkleTree<P::Scalar, DIGEST_ELEMS, PW::Value>,
+    ) -> (Vec<Vec<Vec<P::Scalar>>>, Vec<[PW::Value; DIGEST_ELEMS]>) {
+        let openings = indices
+            .iter()
+            .map(|&index| self.open_rows(index, prover_data))
             .collect();
 
+        let (_, path_len) = self
+            .cap_height_and_path_len(self.get_matrix_heights(prover_data))
+            .unwrap();
+
+        // Walk up from the opened leaves, sending a sibling only if it isn't itself on the path of
+        // another opened leaf.
+        let mut nodes = indices.iter().copied().sorted().dedup().collect_vec();
+        let mut proof = vec![];
+        for layer in &prover_data.digest_layers[..path_len] {
+            let mut i = 0;
+            while i < nodes.len() {
+                let node = nodes[i];
+                if node & 1 == 0 && nodes.get(i + 1) == Some(&(node + 1)) {
+                    i += 2;
+                } else {
+                    proof.push(layer[node ^ 1]);
+                    i += 1;
+                }
+            }
+            nodes = nodes.into_iter().map(|node| node >> 1).dedup().collect();
+        }
+
         (openings, proof)
     }
 
+    fn cap_height(&self) -> usize {
+        self.cap_height
+    }
+
     fn get_matrices<'a>(
         &'a self,
         prover_data: &'a Self::ProverData,
@@ -108,12 +231,22 @@ where
 
     fn verify_batch(
         &self,
-        commit: &[P::Scalar; DIGEST_ELEMS],
+        commit: &MerkleCap<P::Scalar, PW::Value, DIGEST_ELEMS>,
         dimensions: &[Dimensions],
         mut index: usize,
         opened_values: &[Vec<P::Scalar>],
-        proof: &Vec<[P::Scalar; DIGEST_ELEMS]>,
+        proof: &Vec<[PW::Value; DIGEST_ELEMS]>,
     ) -> Result<(), Self::Error> {
+        let (cap_height, path_len) = self
+            .cap_height_and_path_len(dimensions.iter().map(|dims| dims.height))
+            .ok_or(())?;
+        if commit.digests().len() != 1 << cap_height
+            || proof.len() != path_len
+            || opened_values.len() != dimensions.len()
+        {
+            return Err(());
+        }
+
         let mut heights_tallest_first = dimensions
             .iter()
             .enumerate()
@@ -161,7 +294,96 @@ where
             }
         }
 
-        if &root == commit {
+        if commit.digests().get(index) == Some(&root) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    fn verify_multi_batch(
+        &self,
+        commit: &MerkleCap<P::Scalar, PW::Value, DIGEST_ELEMS>,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        opened_values: &[Vec<Vec<P::Scalar>>],
+        proof: &Vec<[PW::Value; DIGEST_ELEMS]>,
+    ) -> Result<(), Self::Error> {
+        let (cap_height, path_len) = self
+            .cap_height_and_path_len(dimensions.iter().map(|dims| dims.height))
+            .ok_or(())?;
+        if commit.digests().len() != 1 << cap_height
+            || indices.len() != opened_values.len()
+            || opened_values
+                .iter()
+                .any(|opened_rows| opened_rows.len() != dimensions.len())
+        {
+            return Err(());
+        }
+
+        let mut heights_tallest_first = dimensions
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(_, dims)| Reverse(dims.height))
+            .peekable();
+
+        let mut curr_height_padded = heights_tallest_first
+            .peek()
+            .unwrap()
+            .1
+            .height
+            .next_power_of_two();
+
+        let tallest_matrices = heights_tallest_first
+            .peeking_take_while(|(_, dims)| dims.height.next_power_of_two() == curr_height_padded)
+            .map(|(i, _)| i)
+            .collect_vec();
+        // The digests of the nodes on the paths of the opened leaves, in the current layer.
+        let mut nodes = self.hash_opened_rows(indices, opened_values, &tallest_matrices, 0)?;
+
+        let mut siblings = proof.iter();
+        for layer in 1..=path_len {
+            let mut next_nodes = BTreeMap::new();
+            let mut nodes_iter = nodes.into_iter().peekable();
+            while let Some((node, digest)) = nodes_iter.next() {
+                let (left, right) = if node & 1 == 0 {
+                    let right = match nodes_iter.next_if(|&(next, _)| next == node + 1) {
+                        Some((_, right)) => right,
+                        None => *siblings.next().ok_or(())?,
+                    };
+                    (digest, right)
+                } else {
+                    (*siblings.next().ok_or(())?, digest)
+                };
+                next_nodes.insert(node >> 1, self.compress.compress([left, right]));
+            }
+            curr_height_padded >>= 1;
+
+            let next_height = heights_tallest_first
+                .peek()
+                .map(|(_, dims)| dims.height)
+                .filter(|h| h.next_power_of_two() == curr_height_padded);
+            if let Some(next_height) = next_height {
+                let matrices_to_inject = heights_tallest_first
+                    .peeking_take_while(|(_, dims)| dims.height == next_height)
+                    .map(|(i, _)| i)
+                    .collect_vec();
+                let injected_digests =
+                    self.hash_opened_rows(indices, opened_values, &matrices_to_inject, layer)?;
+                for (node, digest) in next_nodes.iter_mut() {
+                    let injected_digest = *injected_digests.get(node).ok_or(())?;
+                    *digest = self.compress.compress([*digest, injected_digest]);
+                }
+            }
+
+            nodes = next_nodes;
+        }
+
+        if siblings.next().is_none()
+            && nodes
+                .iter()
+                .all(|(&node, digest)| commit.digests().get(node) == Some(digest))
+        {
             Ok(())
         } else {
             Err(())
@@ -169,25 +391,30 @@ where
     }
 }
 
-impl<P, H, C, const DIGEST_ELEMS: usize> DirectMmcs<P::Scalar>
-    for FieldMerkleTreeMmcs<P, H, C, DIGEST_ELEMS>
+impl<P, PW, H, C, const DIGEST_ELEMS: usize> DirectMmcs<P::Scalar>
+    for FieldMerkleTreeMmcs<P, H, C, DIGEST_ELEMS, PW>
 where
     P: PackedField + Send + Sync,
-    H: CryptographicHasher<P::Scalar, [P::Scalar; DIGEST_ELEMS]>,
-    H: CryptographicHasher<P, [P; DIGEST_ELEMS]>,
+    PW: PackedValue,
+    PW::Value: Eq,
+    H: CryptographicHasher<P::Scalar, [PW::Value; DIGEST_ELEMS]>,
+    H: CryptographicHasher<P, [PW; DIGEST_ELEMS]>,
     H: Send + Sync,
-    C: PseudoCompressionFunction<[P::Scalar; DIGEST_ELEMS], 2>,
-    C: PseudoCompressionFunction<[P; DIGEST_ELEMS], 2>,
+    C: PseudoCompressionFunction<[PW::Value; DIGEST_ELEMS], 2>,
+    C: PseudoCompressionFunction<[PW; DIGEST_ELEMS], 2>,
     C: Send + Sync,
-    [P::Scalar; DIGEST_ELEMS]: Serialize + for<'de> Deserialize<'de>,
+    [PW::Value; DIGEST_ELEMS]: Serialize + for<'de> Deserialize<'de>,
 {
     fn commit(
         &self,
         inputs: Vec<RowMajorMatrix<P::Scalar>>,
     ) -> (Self::Commitment, Self::ProverData) {
-        let tree = FieldMerkleTree::new::<P, H, C>(&self.hash, &self.compress, inputs);
-        let root = tree.root();
-        (root, tree)
+        let tree = FieldMerkleTree::new::<P, PW, H, C>(&self.hash, &self.compress, inputs);
+        let (cap_height, _) = self
+            .cap_height_and_path_len(tree.leaves.iter().map(|matrix| matrix.height()))
+            .unwrap();
+        let cap = tree.cap(cap_height);
+        (cap, tree)
     }
 }
 
@@ -198,12 +425,14 @@ mod tests {
     use itertools::Itertools;
     use p3_baby_bear::BabyBear;
     use p3_commit::{DirectMmcs, Mmcs};
-    use p3_field::{AbstractField, Field};
+    use p3_field::{AbstractField, Field, PrimeField32};
+    use p3_keccak::Keccak256Hash;
     use p3_matrix::dense::RowMajorMatrix;
     use p3_matrix::{Dimensions, Matrix};
     use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
     use p3_symmetric::{
-        CryptographicHasher, PaddingFreeSponge, PseudoCompressionFunction, TruncatedPermutation,
+        CompressionFunctionFromHasher, CryptographicHasher, MerkleCap, PaddingFreeSponge,
+        PseudoCompressionFunction, SerializingHasher32, TruncatedPermutation,
     };
     use rand::thread_rng;
 
@@ -246,7 +475,7 @@ mod tests {
                 compress.compress([hash.hash_item(v[6]), hash.hash_item(v[7])]),
             ]),
         ]);
-        assert_eq!(commit, expected_result);
+        assert_eq!(commit, MerkleCap::from(expected_result));
     }
 
     #[test]
@@ -268,7 +497,7 @@ mod tests {
             hash.hash_slice(&[F::zero(), F::one()]),
             hash.hash_slice(&[F::two(), F::one()]),
         ]);
-        assert_eq!(commit, expected_result);
+        assert_eq!(commit, MerkleCap::from(expected_result));
     }
 
     #[test]
@@ -298,7 +527,7 @@ mod tests {
             ]),
             compress.compress([hash.hash_slice(&[F::two(), F::two()]), default_digest]),
         ]);
-        assert_eq!(commit, expected_result);
+        assert_eq!(commit, MerkleCap::from(expected_result));
     }
 
     #[test]
@@ -349,7 +578,7 @@ mod tests {
                 mat_2_leaf_hashes[1],
             ]),
         ]);
-        assert_eq!(commit, expected_result);
+        assert_eq!(commit, MerkleCap::from(expected_result));
 
         let (opened_values, _proof) = mmcs.open_batch(2, &prover_data);
         assert_eq!(
@@ -501,4 +730,194 @@ mod tests {
         mmcs.verify_batch(&commit, &dims, 17, &opened_values, &proof)
             .expect("expected verification to succeed");
     }
+
+    #[test]
+    fn commit_cap_1x8() {
+        let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        let mmcs = MyMmcs::new_with_cap(hash.clone(), compress.clone(), 2);
+
+        let v = (0..8).map(F::from_canonical_u8).collect_vec();
+        let (commit, prover_data) = mmcs.commit_vec(v.clone());
+
+        // The cap is the layer of 4 digests below the root.
+        let expected_cap = (0..4)
+            .map(|i| compress.compress([hash.hash_item(v[2 * i]), hash.hash_item(v[2 * i + 1])]))
+            .collect_vec();
+        assert_eq!(commit, MerkleCap::new(expected_cap));
+
+        let dims = [Dimensions {
+            width: 1,
+            height: 8,
+        }];
+        let (opened_values, proof) = mmcs.open_batch(5, &prover_data);
+        assert_eq!(proof.len(), 1);
+        mmcs.verify_batch(&commit, &dims, 5, &opened_values, &proof)
+            .expect("expected verification to succeed");
+        mmcs.verify_batch(&commit, &dims, 4, &opened_values, &proof)
+            .expect_err("expected verification at the wrong index to fail");
+    }
+
+    #[test]
+    fn cap_height_limited_by_smallest_matrix() {
+        let mut rng = thread_rng();
+        let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut rng);
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        let root_mmcs = MyMmcs::new(hash.clone(), compress.clone());
+        let mmcs = MyMmcs::new_with_cap(hash, compress, 4);
+
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 64, 3),
+            RowMajorMatrix::<F>::rand(&mut rng, 7, 2),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+
+        // The smaller matrix is mixed in 3 layers below the root, so the cap can be no higher.
+        let (root, _) = root_mmcs.commit(mats.clone());
+        let (commit, prover_data) = mmcs.commit(mats);
+        assert_eq!(commit.height(), 3);
+        assert_eq!(prover_data.cap(0), root);
+
+        for index in [0, 37, 55] {
+            let (opened_values, proof) = mmcs.open_batch(index, &prover_data);
+            assert_eq!(proof.len(), 3);
+            mmcs.verify_batch(&commit, &dims, index, &opened_values, &proof)
+                .expect("expected verification to succeed");
+        }
+    }
+
+    #[test]
+    fn verify_wrong_cap_shape_fails() {
+        let mut rng = thread_rng();
+        let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut rng);
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        let mmcs = MyMmcs::new_with_cap(hash.clone(), compress.clone(), 2);
+        let root_mmcs = MyMmcs::new(hash, compress);
+
+        let mat = RowMajorMatrix::<F>::rand(&mut rng, 16, 4);
+        let dims = [mat.dimensions()];
+        let (commit, prover_data) = mmcs.commit_matrix(mat);
+        let (opened_values, proof) = mmcs.open_batch(9, &prover_data);
+
+        // A root and a full path don't verify against an MMCS expecting a cap.
+        let (root_values, root_proof) = root_mmcs.open_batch(9, &prover_data);
+        mmcs.verify_batch(&prover_data.cap(0), &dims, 9, &root_values, &root_proof)
+            .expect_err("expected verification to fail");
+        root_mmcs
+            .verify_batch(&commit, &dims, 9, &opened_values, &proof)
+            .expect_err("expected verification to fail");
+    }
+
+    #[test]
+    fn multi_proof_shares_siblings() {
+        let mut rng = thread_rng();
+        let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut rng);
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 64, 3),
+            RowMajorMatrix::<F>::rand(&mut rng, 30, 2),
+            RowMajorMatrix::<F>::rand(&mut rng, 8, 5),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+        // Neighbouring, repeated and distant indices.
+        let indices = [12, 13, 40, 13, 0, 55];
+
+        for cap_height in [0, 2] {
+            let mmcs = MyMmcs::new_with_cap(hash.clone(), compress.clone(), cap_height);
+            let (commit, prover_data) = mmcs.commit(mats.clone());
+
+            let (opened_values, proof) = mmcs.open_multi_batch(&indices, &prover_data);
+            for (&index, opened_rows) in indices.iter().zip(&opened_values) {
+                let (expected_rows, path) = mmcs.open_batch(index, &prover_data);
+                assert_eq!(opened_rows, &expected_rows);
+                mmcs.verify_batch(&commit, &dims, index, opened_rows, &path)
+                    .expect("expected verification to succeed");
+            }
+            assert!(proof.len() < 5 * (6 - cap_height));
+
+            mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &proof)
+                .expect("expected verification to succeed");
+        }
+    }
+
+    #[test]
+    fn verify_tampered_multi_proof_fails() {
+        let mut rng = thread_rng();
+        let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut rng);
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        let mmcs = MyMmcs::new(hash, compress);
+
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 32, 4),
+            RowMajorMatrix::<F>::rand(&mut rng, 4, 4),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+        let indices = [3, 9, 9, 30];
+        let (commit, prover_data) = mmcs.commit(mats);
+        let (opened_values, proof) = mmcs.open_multi_batch(&indices, &prover_data);
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &proof)
+            .expect("expected verification to succeed");
+
+        // A tampered sibling.
+        let mut bad_proof = proof.clone();
+        bad_proof[2][0] += F::one();
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &bad_proof)
+            .expect_err("expected verification to fail");
+
+        // A missing or an extra sibling.
+        let mut short_proof = proof.clone();
+        short_proof.pop();
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &short_proof)
+            .expect_err("expected verification to fail");
+        let mut long_proof = proof.clone();
+        long_proof.push(proof[0]);
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &long_proof)
+            .expect_err("expected verification to fail");
+
+        // Different rows of the smaller matrix for queries which share a row of it.
+        let mut bad_values = opened_values.clone();
+        bad_values[2][1][0] += F::one();
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &bad_values, &proof)
+            .expect_err("expected verification to fail");
+    }
+
+    #[test]
+    fn keccak_byte_digests() {
+        type ByteHash = Keccak256Hash;
+        type FieldHash = SerializingHasher32<ByteHash>;
+        type ByteCompress = CompressionFunctionFromHasher<u8, ByteHash, 2, 32>;
+        type ByteMmcs = FieldMerkleTreeMmcs<F, FieldHash, ByteCompress, 32, u8>;
+        let mmcs = ByteMmcs::new(FieldHash::new(ByteHash {}), ByteCompress::new(ByteHash {}));
+
+        // The root of a single column is Keccak-256 over the concatenated child digests, with
+        // leaves hashed as their 4-byte little-endian encodings.
+        let v = [F::from_canonical_u32(7), F::from_canonical_u32(1 << 20)];
+        let (commit, _) = mmcs.commit_vec(v.to_vec());
+        let leaves = v.map(|x| ByteHash {}.hash_iter(x.as_canonical_u32().to_le_bytes()));
+        let root = ByteHash {}.hash_iter(leaves.into_iter().flatten());
+        assert_eq!(commit, MerkleCap::from(root));
+
+        let mut rng = thread_rng();
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 16, 3),
+            RowMajorMatrix::<F>::rand(&mut rng, 4, 5),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+        let indices = [2, 3, 11];
+        let (commit, prover_data) = mmcs.commit(mats);
+        let (opened_values, proof) = mmcs.open_multi_batch(&indices, &prover_data);
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &proof)
+            .expect("expected verification to succeed");
+
+        let mut bad_proof = proof.clone();
+        bad_proof[0][31] ^= 1;
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &bad_proof)
+            .expect_err("expected verification to fail");
+    }
 }
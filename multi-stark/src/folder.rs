@@ -1,29 +1,99 @@
-use p3_air::{AirBuilder, TwoRowMatrixView};
-use p3_field::{AbstractExtensionField, ExtensionField, Field, PackedField};
-
-pub struct ConstraintFolder<'a, F, Challenge, PackedChallenge>
-where
-    F: Field,
-{
-    pub(crate) main: TwoRowMatrixView<'a, F::Packing>,
-    pub(crate) is_first_row: F::Packing,
-    pub(crate) is_last_row: F::Packing,
-    pub(crate) is_transition: F::Packing,
-    pub(crate) alpha: Challenge,
-    pub(crate) accumulator: PackedChallenge,
-}
-
-impl<'a, F, Challenge, PackedChallenge> AirBuilder
-    for ConstraintFolder<'a, F, Challenge, PackedChallenge>
-where
-    F: Field,
-    Challenge: ExtensionField<F>,
-    PackedChallenge: PackedField<Scalar = Challenge> + AbstractExtensionField<F::Packing>,
-{
-    type F = F;
-    type Expr = F::Packing;
-    type Var = F::Packing;
-    type M = TwoRowMatrixView<'a, F::Packing>;
+use alloc::vec::Vec;
+
+use p3_air::{AirBuilder, ExtensionBuilder, PermutationAirBuilder, WindowMatrixView};
+use p3_field::AbstractField;
+use p3_uni_stark::{PackedChallenge, PackedVal, StarkGenericConfig};
+
+/// Folds the constraints of one table into a random linear combination, over packed evaluations of
+/// its trace on the quotient domain.
+pub struct ConstraintFolder<'a, SC: StarkGenericConfig> {
+    pub(crate) main: WindowMatrixView<'a, PackedVal<SC>>,
+    pub(crate) permutation: WindowMatrixView<'a, PackedChallenge<SC>>,
+    pub(crate) permutation_challenges: &'a [PackedChallenge<SC>],
+    pub(crate) is_first_row: PackedVal<SC>,
+    pub(crate) is_last_row: PackedVal<SC>,
+    /// The values of `is_transition_window(size)` for each `size` in `1..=window_size`.
+    pub(crate) transition_selectors: Vec<PackedVal<SC>>,
+    pub(crate) alpha: SC::Challenge,
+    pub(crate) accumulator: PackedChallenge<SC>,
+}
+
+/// Folds the constraints of one table into a random linear combination, at the out-of-domain point
+/// `zeta`.
+pub struct VerifierConstraintFolder<'a, SC: StarkGenericConfig> {
+    pub(crate) main: WindowMatrixView<'a, SC::Challenge>,
+    pub(crate) permutation: WindowMatrixView<'a, SC::Challenge>,
+    pub(crate) permutation_challenges: &'a [SC::Challenge],
+    pub(crate) is_first_row: SC::Challenge,
+    pub(crate) is_last_row: SC::Challenge,
+    /// The values of `is_transition_window(size)` for each `size` in `1..=window_size`.
+    pub(crate) transition_selectors: Vec<SC::Challenge>,
+    pub(crate) alpha: SC::Challenge,
+    pub(crate) accumulator: SC::Challenge,
+}
+
+impl<'a, SC: StarkGenericConfig> AirBuilder for ConstraintFolder<'a, SC> {
+    type F = SC::Val;
+    type Expr = PackedVal<SC>;
+    type Var = PackedVal<SC>;
+    type M = WindowMatrixView<'a, PackedVal<SC>>;
+
+    fn main(&self) -> Self::M {
+        self.main
+    }
+
+    fn is_first_row(&self) -> Self::Expr {
+        self.is_first_row
+    }
+
+    fn is_last_row(&self) -> Self::Expr {
+        self.is_last_row
+    }
+
+    fn is_transition_window(&self, size: usize) -> Self::Expr {
+        self.transition_selectors[size - 1]
+    }
+
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        let x: PackedVal<SC> = x.into();
+        self.accumulator *= PackedChallenge::<SC>::from_f(self.alpha);
+        self.accumulator += x;
+    }
+}
+
+impl<SC: StarkGenericConfig> ExtensionBuilder for ConstraintFolder<'_, SC> {
+    type EF = SC::Challenge;
+    type ExprEF = PackedChallenge<SC>;
+    type VarEF = PackedChallenge<SC>;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        let x: PackedChallenge<SC> = x.into();
+        self.accumulator *= PackedChallenge::<SC>::from_f(self.alpha);
+        self.accumulator += x;
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> PermutationAirBuilder for ConstraintFolder<'a, SC> {
+    type MP = WindowMatrixView<'a, PackedChallenge<SC>>;
+    type RandomVar = PackedChallenge<SC>;
+
+    fn permutation(&self) -> Self::MP {
+        self.permutation
+    }
+
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        self.permutation_challenges
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> AirBuilder for VerifierConstraintFolder<'a, SC> {
+    type F = SC::Val;
+    type Expr = SC::Challenge;
+    type Var = SC::Challenge;
+    type M = WindowMatrixView<'a, SC::Challenge>;
 
     fn main(&self) -> Self::M {
         self.main
@@ -38,16 +108,40 @@ where
     }
 
     fn is_transition_window(&self, size: usize) -> Self::Expr {
-        if size == 2 {
-            self.is_transition
-        } else {
-            panic!("multi-stark only supports a window size of 2")
-        }
+        self.transition_selectors[size - 1]
     }
 
     fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
-        let x: F::Packing = x.into();
+        let x: SC::Challenge = x.into();
         self.accumulator *= self.alpha;
         self.accumulator += x;
     }
 }
+
+impl<SC: StarkGenericConfig> ExtensionBuilder for VerifierConstraintFolder<'_, SC> {
+    type EF = SC::Challenge;
+    type ExprEF = SC::Challenge;
+    type VarEF = SC::Challenge;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        let x: SC::Challenge = x.into();
+        self.accumulator *= self.alpha;
+        self.accumulator += x;
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> PermutationAirBuilder for VerifierConstraintFolder<'a, SC> {
+    type MP = WindowMatrixView<'a, SC::Challenge>;
+    type RandomVar = SC::Challenge;
+
+    fn permutation(&self) -> Self::MP {
+        self.permutation
+    }
+
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        self.permutation_challenges
+    }
+}
@@ -0,0 +1,167 @@
+use alloc::vec::Vec;
+
+use p3_air::{ExtensionBuilder, Interaction, InteractionAir, PermutationAirBuilder};
+use p3_field::{
+    batch_multiplicative_inverse, AbstractExtensionField, AbstractField, ExtensionField, Field,
+};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{Matrix, MatrixRowSlices};
+
+/// The number of challenges used to reduce messages to fingerprints: an offset `beta`, and `gamma`,
+/// whose powers combine the fields of a message.
+pub(crate) const NUM_INTERACTION_CHALLENGES: usize = 2;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum InteractionKind {
+    Send,
+    Receive,
+}
+
+/// All of an AIR's sends, followed by all of its receives.
+pub(crate) fn all_interactions<F, A>(air: &A) -> Vec<(Interaction<F>, InteractionKind)>
+where
+    F: Field,
+    A: InteractionAir<F>,
+{
+    let sends = air
+        .sends()
+        .into_iter()
+        .map(|interaction| (interaction, InteractionKind::Send));
+    let receives = air
+        .receives()
+        .into_iter()
+        .map(|interaction| (interaction, InteractionKind::Receive));
+    sends.chain(receives).collect()
+}
+
+/// The fingerprint of a message, `beta + argument_index + sum_i gamma^(i + 1) fields[i]`.
+fn fingerprint<Expr, ExprEF>(
+    fields: impl Iterator<Item = Expr>,
+    argument_index: usize,
+    beta: ExprEF,
+    gamma: ExprEF,
+) -> ExprEF
+where
+    Expr: AbstractField,
+    ExprEF: AbstractExtensionField<Expr>,
+{
+    let mut result = beta + ExprEF::from_canonical_usize(argument_index);
+    let mut gamma_pow = gamma.clone();
+    for field in fields {
+        result += gamma_pow.clone() * field;
+        gamma_pow *= gamma.clone();
+    }
+    result
+}
+
+/// Generate the LogUp trace of a table with at least one interaction.
+///
+/// For each interaction there is a column holding `count / fingerprint` (negated for receives), and
+/// a final column holds the running sum of all other columns up to and including the current row.
+pub(crate) fn generate_permutation_trace<F, EF>(
+    interactions: &[(Interaction<F>, InteractionKind)],
+    main: &RowMajorMatrix<F>,
+    challenges: &[EF],
+) -> RowMajorMatrix<EF>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+{
+    let (beta, gamma) = (challenges[0], challenges[1]);
+    let num_interactions = interactions.len();
+    let height = main.height();
+
+    let fingerprints: Vec<EF> = (0..height)
+        .flat_map(|i| {
+            let row = main.row_slice(i);
+            interactions.iter().map(move |(interaction, _)| {
+                let fields = interaction
+                    .fields
+                    .iter()
+                    .map(|field| field.apply::<F, F>(&[], &[], row));
+                fingerprint(fields, interaction.argument_index, beta, gamma)
+            })
+        })
+        .collect();
+    let fingerprint_inverses = batch_multiplicative_inverse(&fingerprints);
+
+    let mut values = Vec::with_capacity(height * (num_interactions + 1));
+    let mut running_sum = EF::zero();
+    for (i, row_inverses) in fingerprint_inverses.chunks(num_interactions).enumerate() {
+        let row = main.row_slice(i);
+        for ((interaction, kind), &inverse) in interactions.iter().zip(row_inverses) {
+            let count = interaction.count.apply::<F, F>(&[], &[], row);
+            let term = match kind {
+                InteractionKind::Send => inverse * count,
+                InteractionKind::Receive => -(inverse * count),
+            };
+            running_sum += term;
+            values.push(term);
+        }
+        values.push(running_sum);
+    }
+    RowMajorMatrix::new(values, num_interactions + 1)
+}
+
+/// Evaluate the constraints tying a table's LogUp trace to its main trace, ending in the claimed
+/// `cumulative_sum` on the last row.
+pub(crate) fn eval_permutation_constraints<AB>(
+    interactions: &[(Interaction<AB::F>, InteractionKind)],
+    cumulative_sum: AB::EF,
+    builder: &mut AB,
+) where
+    AB: PermutationAirBuilder,
+{
+    let main = builder.main();
+    let main_local = main.row_slice(0);
+
+    let perm = builder.permutation();
+    let perm_local = perm.row_slice(0);
+    let perm_next = perm.row_slice(1);
+
+    let randomness = builder.permutation_randomness();
+    let beta: AB::ExprEF = randomness[0].into();
+    let gamma: AB::ExprEF = randomness[1].into();
+
+    let num_interactions = interactions.len();
+    for (&term, (interaction, kind)) in perm_local.iter().zip(interactions) {
+        let fields = interaction
+            .fields
+            .iter()
+            .map(|field| field.apply::<AB::Expr, AB::Var>(&[], &[], main_local));
+        let fingerprint = fingerprint(
+            fields,
+            interaction.argument_index,
+            beta.clone(),
+            gamma.clone(),
+        );
+        let count = interaction
+            .count
+            .apply::<AB::Expr, AB::Var>(&[], &[], main_local);
+        let count = match kind {
+            InteractionKind::Send => count,
+            InteractionKind::Receive => -count,
+        };
+        let term: AB::ExprEF = term.into();
+        builder.assert_eq_ext(term * fingerprint, count);
+    }
+
+    let sum_terms = |row: &[AB::VarEF]| -> AB::ExprEF {
+        row[..num_interactions]
+            .iter()
+            .map(|&term| term.into())
+            .sum()
+    };
+    let running_sum = perm_local[num_interactions];
+    let running_sum_next = perm_next[num_interactions];
+    builder
+        .when_first_row()
+        .assert_eq_ext(running_sum, sum_terms(perm_local));
+    let running_sum_expected: AB::ExprEF = running_sum.into() + sum_terms(perm_next);
+    builder
+        .when_transition()
+        .assert_eq_ext(running_sum_next, running_sum_expected);
+    builder
+        .when_last_row()
+        .assert_eq_ext(running_sum, AB::ExprEF::from_f(cumulative_sum));
+}
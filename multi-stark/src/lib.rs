@@ -1,13 +1,48 @@
-//! A minimal multivariate STARK framework.
+//! A STARK framework for proving several AIRs at once, each over its own trace, with a single
+//! proof. Each AIR (or table) may have a different trace height.
+//!
+//! This shares its configuration, `p3_uni_stark::StarkGenericConfig`, with `p3-uni-stark`.
 
 #![no_std]
 
 extern crate alloc;
 
-mod config;
 mod folder;
+mod interaction;
+mod proof;
 mod prover;
+mod verifier;
 
-pub use config::*;
 pub use folder::*;
+use p3_commit::UnivariatePcsWithLde;
+use p3_field::{ExtensionField, TwoAdicField};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::StarkGenericConfig;
+pub use proof::*;
 pub use prover::*;
+pub use verifier::*;
+
+/// A `StarkGenericConfig` whose PCS commits over two-adic subgroups and their cosets, which is
+/// what this crate's prover and verifier are written for.
+pub trait TwoAdicStarkConfig:
+    StarkGenericConfig<
+    Val: TwoAdicField,
+    Challenge: ExtensionField<Self::Val> + TwoAdicField,
+    Pcs: UnivariatePcsWithLde<
+        Self::Val,
+        Self::Challenge,
+        RowMajorMatrix<Self::Val>,
+        Self::Challenger,
+    >,
+>
+{
+}
+
+impl<SC> TwoAdicStarkConfig for SC where
+    SC: StarkGenericConfig<
+        Val: TwoAdicField,
+        Challenge: ExtensionField<SC::Val> + TwoAdicField,
+        Pcs: UnivariatePcsWithLde<SC::Val, SC::Challenge, RowMajorMatrix<SC::Val>, SC::Challenger>,
+    >
+{
+}
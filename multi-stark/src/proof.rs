@@ -0,0 +1,45 @@
+use alloc::vec::Vec;
+
+use p3_commit::Pcs;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::StarkGenericConfig;
+use serde::{Deserialize, Serialize};
+
+type Val<SC> = <SC as StarkGenericConfig>::Val;
+type ValMat<SC> = RowMajorMatrix<Val<SC>>;
+type Com<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<Val<SC>, ValMat<SC>>>::Commitment;
+type PcsProof<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<Val<SC>, ValMat<SC>>>::Proof;
+
+/// A proof that a set of traces satisfies a set of AIRs, one trace per AIR.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Proof<SC: StarkGenericConfig> {
+    pub(crate) commitments: Commitments<Com<SC>>,
+    /// The opened values of each table, in the order the AIRs were given.
+    pub(crate) opened_values: Vec<OpenedValues<SC::Challenge>>,
+    pub(crate) opening_proof: PcsProof<SC>,
+    /// The final value of each table's LogUp running sum, which is zero for tables without
+    /// interactions. These must sum to zero.
+    pub(crate) cumulative_sums: Vec<SC::Challenge>,
+    /// The log of each table's trace height, in the order the AIRs were given.
+    pub(crate) degree_bits: Vec<usize>,
+}
+
+/// Commitments to the traces and quotients of all tables, each batched into a single commitment.
+/// The permutation batch only covers tables with interactions, and is absent if there are none.
+#[derive(Serialize, Deserialize)]
+pub struct Commitments<Com> {
+    pub(crate) main: Com,
+    pub(crate) permutation: Option<Com>,
+    pub(crate) quotient_chunks: Com,
+}
+
+/// The claimed openings of one table. Its trace matrices are opened at `zeta * g^i` for each row `i`
+/// of the AIR's window, where `g` generates the table's trace domain. A table without interactions
+/// has no permutation openings.
+#[derive(Serialize, Deserialize)]
+pub struct OpenedValues<Challenge> {
+    pub(crate) trace: Vec<Vec<Challenge>>,
+    pub(crate) permutation: Vec<Vec<Challenge>>,
+    pub(crate) quotient_chunks: Vec<Challenge>,
+}
@@ -1,21 +1,365 @@
-use p3_air::Air;
-use p3_challenger::FieldChallenger;
-use p3_commit::Pcs;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::{izip, Itertools};
+use p3_air::{Air, Interaction, InteractionAir, WindowMatrixView};
+use p3_challenger::{CanObserve, FieldChallenger};
+use p3_commit::two_adic::{decompose_and_flatten, transition_selectors, ZerofierOnCoset};
+use p3_commit::{Pcs, UnivariatePcs, UnivariatePcsWithLde};
+use p3_field::{
+    cyclic_subgroup_coset_known_order, AbstractExtensionField, AbstractField, PackedField,
+    TwoAdicField,
+};
 use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{Matrix, MatrixGet, MatrixRowSlices, MatrixRows};
+use p3_maybe_rayon::prelude::*;
+use p3_uni_stark::{get_log_quotient_degree, PackedChallenge, PackedVal, SymbolicAirBuilder};
+use p3_util::log2_strict_usize;
+use tracing::{info_span, instrument};
+
+use crate::interaction::{
+    all_interactions, eval_permutation_constraints, generate_permutation_trace, InteractionKind,
+    NUM_INTERACTION_CHALLENGES,
+};
+use crate::{Commitments, ConstraintFolder, OpenedValues, Proof, TwoAdicStarkConfig};
+
+/// Prove that each trace in `traces` satisfies the AIR at the same index in `airs`, and that the
+/// messages sent and received by the AIRs balance out.
+///
+/// All traces are committed to in a single batch, as are all LogUp traces and all quotient
+/// polynomials, so the proof contains a single opening argument regardless of the number of tables.
+#[instrument(skip_all)]
+pub fn prove<SC, A>(
+    config: &SC,
+    airs: &[A],
+    challenger: &mut SC::Challenger,
+    traces: Vec<RowMajorMatrix<SC::Val>>,
+) -> Proof<SC>
+where
+    SC: TwoAdicStarkConfig,
+    A: InteractionAir<SC::Val>
+        + Air<SymbolicAirBuilder<SC::Val>>
+        + for<'a> Air<ConstraintFolder<'a, SC>>,
+{
+    assert_eq!(airs.len(), traces.len(), "expected one trace per AIR");
+    assert!(!airs.is_empty(), "expected at least one AIR");
+    assert!(
+        !config.is_zk(),
+        "zero-knowledge mode isn't supported for multiple tables"
+    );
+
+    let pcs = config.pcs();
+
+    let degree_bits = traces
+        .iter()
+        .map(|trace| log2_strict_usize(trace.height()))
+        .collect_vec();
+    let log_quotient_degrees = airs
+        .iter()
+        .map(|air| get_log_quotient_degree::<SC::Val, A>(air, 0, false))
+        .collect_vec();
+    let interactions = airs.iter().map(all_interactions).collect_vec();
+    let has_interactions = interactions.iter().any(|ints| !ints.is_empty());
+
+    // The LogUp traces are built from the main traces after the latter have been committed, so in
+    // that case we hold on to a copy of them.
+    let main_traces = has_interactions.then(|| traces.clone());
+
+    let (main_commit, main_data) =
+        info_span!("commit to trace data").in_scope(|| pcs.commit_batches(traces));
+    challenger.observe(main_commit.clone());
+
+    let mut permutation_challenges: Vec<SC::Challenge> = vec![];
+    let mut permutation_traces: Vec<Option<RowMajorMatrix<SC::Challenge>>> =
+        airs.iter().map(|_| None).collect();
+    let mut cumulative_sums = vec![SC::Challenge::zero(); airs.len()];
+    let mut permutation_commit = None;
+    let mut permutation_data = None;
+    if let Some(main_traces) = main_traces {
+        permutation_challenges = (0..NUM_INTERACTION_CHALLENGES)
+            .map(|_| challenger.sample_ext_element())
+            .collect();
+        info_span!("generate permutation traces").in_scope(|| {
+            for (perm, ints, main) in izip!(&mut permutation_traces, &interactions, &main_traces) {
+                if !ints.is_empty() {
+                    *perm = Some(generate_permutation_trace(
+                        ints,
+                        main,
+                        &permutation_challenges,
+                    ));
+                }
+            }
+        });
+        for (sum, perm) in izip!(&mut cumulative_sums, &permutation_traces) {
+            if let Some(perm) = perm {
+                *sum = *perm.row_slice(perm.height() - 1).last().unwrap();
+            }
+        }
+
+        let flattened = permutation_traces
+            .iter()
+            .flatten()
+            .map(|perm| perm.flatten_to_base())
+            .collect_vec();
+        let (commit, data) =
+            info_span!("commit to permutation traces").in_scope(|| pcs.commit_batches(flattened));
+        challenger.observe(commit.clone());
+        for &sum in &cumulative_sums {
+            challenger.observe_ext_element(sum);
+        }
+        permutation_commit = Some(commit);
+        permutation_data = Some(data);
+    }
+
+    let alpha: SC::Challenge = challenger.sample_ext_element();
+
+    let main_ldes = pcs.get_ldes(&main_data);
+    assert_eq!(main_ldes.len(), airs.len());
+    let mut permutation_ldes = permutation_data
+        .as_ref()
+        .map(|data| pcs.get_ldes(data))
+        .unwrap_or_default()
+        .into_iter();
+
+    let quotients = izip!(
+        airs,
+        main_ldes,
+        &interactions,
+        &cumulative_sums,
+        &degree_bits,
+        &log_quotient_degrees
+    )
+    .map(
+        |(air, main_lde, ints, &cumulative_sum, &log_degree, &log_quotient_degree)| {
+            let log_stride_for_quotient = pcs.log_blowup() - log_quotient_degree;
+            let main_lde_for_quotient =
+                main_lde.vertically_strided(1 << log_stride_for_quotient, 0);
+            let permutation_lde_for_quotient = (!ints.is_empty()).then(|| {
+                permutation_ldes
+                    .next()
+                    .unwrap()
+                    .vertically_strided(1 << log_stride_for_quotient, 0)
+            });
+            let quotient_values = quotient_values(
+                config,
+                air,
+                log_degree,
+                log_quotient_degree,
+                main_lde_for_quotient,
+                permutation_lde_for_quotient,
+                ints,
+                &permutation_challenges,
+                cumulative_sum,
+                alpha,
+            );
+            decompose_and_flatten(
+                quotient_values,
+                SC::Challenge::from_base(pcs.coset_shift()),
+                log_quotient_degree,
+            )
+        },
+    )
+    .collect_vec();
+    let quotient_shifts = log_quotient_degrees
+        .iter()
+        .map(|&log_quotient_degree| pcs.coset_shift().exp_power_of_2(log_quotient_degree))
+        .collect_vec();
+    let (quotient_commit, quotient_data) = info_span!("commit to quotient poly chunks")
+        .in_scope(|| pcs.commit_shifted_batches(quotients, &quotient_shifts));
+    challenger.observe(quotient_commit.clone());
 
-use crate::{ConstraintFolder, StarkGenericConfig};
+    let commitments = Commitments {
+        main: main_commit,
+        permutation: permutation_commit,
+        quotient_chunks: quotient_commit,
+    };
 
-pub fn prove<SC, A, Challenger>(
+    let zeta: SC::Challenge = challenger.sample_ext_element();
+    let main_points = izip!(airs, &degree_bits)
+        .map(|(air, &log_degree)| {
+            SC::Val::two_adic_generator(log_degree)
+                .powers()
+                .take(air.window_size())
+                .map(|g_pow| zeta * g_pow)
+                .collect_vec()
+        })
+        .collect_vec();
+    let quotient_points = log_quotient_degrees
+        .iter()
+        .map(|&log_quotient_degree| vec![zeta.exp_power_of_2(log_quotient_degree)])
+        .collect_vec();
+    let permutation_points = izip!(&main_points, &interactions)
+        .filter(|(_, ints)| !ints.is_empty())
+        .map(|(points, _)| points.clone())
+        .collect_vec();
+    let mut rounds = vec![
+        (&main_data, main_points.as_slice()),
+        (&quotient_data, quotient_points.as_slice()),
+    ];
+    if let Some(data) = &permutation_data {
+        rounds.push((data, permutation_points.as_slice()));
+    }
+    let (opened_values, opening_proof) = pcs.open_multi_batches(&rounds, challenger);
+
+    let mut opened_values = opened_values.into_iter();
+    let main_openings = opened_values.next().unwrap();
+    let quotient_openings = opened_values.next().unwrap();
+    let mut permutation_openings = opened_values.next().unwrap_or_default().into_iter();
+    let opened_values = izip!(main_openings, quotient_openings, &interactions)
+        .map(|(trace, mut quotient, ints)| {
+            let permutation = if ints.is_empty() {
+                vec![]
+            } else {
+                permutation_openings.next().unwrap()
+            };
+            OpenedValues {
+                trace,
+                permutation,
+                quotient_chunks: quotient.pop().unwrap(),
+            }
+        })
+        .collect();
+
+    Proof {
+        commitments,
+        opened_values,
+        opening_proof,
+        cumulative_sums,
+        degree_bits,
+    }
+}
+
+#[instrument(name = "compute quotient polynomial", skip_all)]
+#[allow(clippy::too_many_arguments)]
+fn quotient_values<SC, A, Mat, PermMat>(
     config: &SC,
-    _air: &A,
-    _challenger: &mut Challenger,
-    trace: RowMajorMatrix<SC::Val>,
-) where
-    SC: StarkGenericConfig,
-    A: for<'a> Air<ConstraintFolder<'a, SC::Val, SC::Challenge, SC::PackedChallenge>>,
-    Challenger: FieldChallenger<SC::Val>,
+    air: &A,
+    degree_bits: usize,
+    quotient_degree_bits: usize,
+    main_lde: Mat,
+    permutation_lde: Option<PermMat>,
+    interactions: &[(Interaction<SC::Val>, InteractionKind)],
+    permutation_challenges: &[SC::Challenge],
+    cumulative_sum: SC::Challenge,
+    alpha: SC::Challenge,
+) -> Vec<SC::Challenge>
+where
+    SC: TwoAdicStarkConfig,
+    A: for<'a> Air<ConstraintFolder<'a, SC>>,
+    Mat: MatrixGet<SC::Val> + Sync,
+    PermMat: MatrixGet<SC::Val> + Sync,
 {
-    let (_trace_commit, _trace_data) = config.pcs().commit_batch(trace.as_view());
+    let degree = 1 << degree_bits;
+    let quotient_size_bits = degree_bits + quotient_degree_bits;
+    let quotient_size = 1 << quotient_size_bits;
+    let g_subgroup = SC::Val::two_adic_generator(degree_bits);
+    let g_extended = SC::Val::two_adic_generator(quotient_size_bits);
+    let coset_shift = config.pcs().coset_shift();
+    let next_step = 1 << quotient_degree_bits;
+    let window_size = air.window_size();
+    let ext_degree = <SC::Challenge as AbstractExtensionField<SC::Val>>::D;
+
+    let mut coset: Vec<_> =
+        cyclic_subgroup_coset_known_order(g_extended, coset_shift, quotient_size).collect();
+
+    let zerofier_on_coset = ZerofierOnCoset::new(degree_bits, quotient_degree_bits, coset_shift);
+
+    // Evaluations of L_first(x) = Z_H(x) / (x - 1) on our coset s H.
+    let mut lagrange_first_evals = zerofier_on_coset.lagrange_basis_unnormalized(0);
+    let mut lagrange_last_evals = zerofier_on_coset.lagrange_basis_unnormalized(degree - 1);
+
+    // As in uni-stark, pad these vectors so that we can take `WIDTH`-sized slices of them even when
+    // `quotient_size < WIDTH`. The padding entries are ignored.
+    for _ in quotient_size..PackedVal::<SC>::WIDTH {
+        coset.push(SC::Val::default());
+        lagrange_first_evals.push(SC::Val::default());
+        lagrange_last_evals.push(SC::Val::default());
+    }
+
+    (0..quotient_size)
+        .into_par_iter()
+        .step_by(PackedVal::<SC>::WIDTH)
+        .flat_map_iter(|i_local_start| {
+            let wrap = |i| i % quotient_size;
+            // The LDE rows holding each row of the window, for the first packed lane.
+            let window_rows = (0..window_size)
+                .map(|offset| i_local_start + offset * next_step)
+                .collect_vec();
+            let i_range = i_local_start..i_local_start + PackedVal::<SC>::WIDTH;
+
+            let x = *PackedVal::<SC>::from_slice(&coset[i_range.clone()]);
+            let transition_selectors = transition_selectors(x, g_subgroup, window_size);
+            let is_first_row = *PackedVal::<SC>::from_slice(&lagrange_first_evals[i_range.clone()]);
+            let is_last_row = *PackedVal::<SC>::from_slice(&lagrange_last_evals[i_range]);
+
+            let main: Vec<Vec<_>> = window_rows
+                .iter()
+                .map(|&row_start| {
+                    (0..main_lde.width())
+                        .map(|col| {
+                            PackedVal::<SC>::from_fn(|lane| {
+                                main_lde.get(wrap(row_start + lane), col)
+                            })
+                        })
+                        .collect()
+                })
+                .collect();
+
+            // The LogUp trace was committed as D base field columns per extension column.
+            let permutation: Vec<Vec<_>> = permutation_lde
+                .as_ref()
+                .map(|perm| {
+                    window_rows
+                        .iter()
+                        .map(|&row_start| {
+                            (0..perm.width() / ext_degree)
+                                .map(|col| {
+                                    PackedChallenge::<SC>::from_base_fn(|coeff_idx| {
+                                        PackedVal::<SC>::from_fn(|lane| {
+                                            perm.get(
+                                                wrap(row_start + lane),
+                                                col * ext_degree + coeff_idx,
+                                            )
+                                        })
+                                    })
+                                })
+                                .collect()
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let packed_permutation_challenges: Vec<_> = permutation_challenges
+                .iter()
+                .map(|&c| PackedChallenge::<SC>::from_f(c))
+                .collect();
+
+            let mut folder = ConstraintFolder {
+                main: WindowMatrixView::new(&main),
+                permutation: WindowMatrixView::new(&permutation),
+                permutation_challenges: &packed_permutation_challenges,
+                is_first_row,
+                is_last_row,
+                transition_selectors,
+                alpha,
+                accumulator: PackedChallenge::<SC>::zero(),
+            };
+            air.eval(&mut folder);
+            if !interactions.is_empty() {
+                eval_permutation_constraints(interactions, cumulative_sum, &mut folder);
+            }
+
+            // quotient(x) = constraints(x) / Z_H(x)
+            let zerofier_inv: PackedVal<SC> = zerofier_on_coset.eval_inverse_packed(i_local_start);
+            let quotient = folder.accumulator * zerofier_inv;
 
-    // challenger.observe_ext_element(trace_commit); // TODO
+            // "Transpose" D packed base coefficients into WIDTH scalar extension coefficients.
+            let limit = PackedVal::<SC>::WIDTH.min(quotient_size);
+            (0..limit).map(move |idx_in_packing| {
+                let quotient_value = (0..ext_degree)
+                    .map(|coeff_idx| quotient.as_base_slice()[coeff_idx].as_slice()[idx_in_packing])
+                    .collect_vec();
+                SC::Challenge::from_base_slice(&quotient_value)
+            })
+        })
+        .collect()
 }
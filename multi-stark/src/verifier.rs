@@ -0,0 +1,269 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::{izip, Itertools};
+use p3_air::{Air, BaseAir, InteractionAir, WindowMatrixView};
+use p3_challenger::{CanObserve, FieldChallenger};
+use p3_commit::two_adic::transition_selectors;
+use p3_commit::UnivariatePcs;
+use p3_field::{AbstractExtensionField, AbstractField, Field, TwoAdicField};
+use p3_matrix::Dimensions;
+use p3_uni_stark::{get_log_quotient_degree, SymbolicAirBuilder};
+use p3_util::reverse_slice_index_bits;
+use tracing::instrument;
+
+use crate::interaction::{
+    all_interactions, eval_permutation_constraints, NUM_INTERACTION_CHALLENGES,
+};
+use crate::{Proof, TwoAdicStarkConfig, VerifierConstraintFolder};
+
+/// Verify a proof produced by `prove`, given the same AIRs in the same order.
+///
+/// Besides checking each table's constraints, this checks that the LogUp running sums of all tables
+/// add up to zero, i.e. that every message sent by some table was received by another.
+#[instrument(skip_all)]
+pub fn verify<SC, A>(
+    config: &SC,
+    airs: &[A],
+    challenger: &mut SC::Challenger,
+    proof: &Proof<SC>,
+) -> Result<(), VerificationError>
+where
+    SC: TwoAdicStarkConfig,
+    A: InteractionAir<SC::Val>
+        + Air<SymbolicAirBuilder<SC::Val>>
+        + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+{
+    let Proof {
+        commitments,
+        opened_values,
+        opening_proof,
+        cumulative_sums,
+        degree_bits,
+    } = proof;
+
+    let challenge_ext_degree = <SC::Challenge as AbstractExtensionField<SC::Val>>::D;
+    let log_quotient_degrees = airs
+        .iter()
+        .map(|air| get_log_quotient_degree::<SC::Val, A>(air, 0, false))
+        .collect_vec();
+    let interactions = airs.iter().map(all_interactions).collect_vec();
+    let has_interactions = interactions.iter().any(|ints| !ints.is_empty());
+
+    let valid_shape = !airs.is_empty()
+        && opened_values.len() == airs.len()
+        && cumulative_sums.len() == airs.len()
+        && degree_bits.len() == airs.len()
+        && degree_bits.iter().all(|&bits| bits <= SC::Val::TWO_ADICITY)
+        && commitments.permutation.is_some() == has_interactions
+        && izip!(
+            airs,
+            opened_values,
+            &interactions,
+            cumulative_sums,
+            &log_quotient_degrees
+        )
+        .all(
+            |(air, opened, ints, cumulative_sum, &log_quotient_degree)| {
+                let air_width = <A as BaseAir<SC::Val>>::width(air);
+                let window_size = <A as BaseAir<SC::Val>>::window_size(air);
+                // A table with interactions has a LogUp column per interaction, plus the running
+                // sum, each committed as D base field columns. It is opened over the same window as
+                // the main trace, while tables without interactions have no permutation openings.
+                let permutation_width = (ints.len() + 1) * challenge_ext_degree;
+                let permutation_window_size = if ints.is_empty() { 0 } else { window_size };
+                opened.trace.len() == window_size
+                    && opened.trace.iter().all(|row| row.len() == air_width)
+                    && opened.permutation.len() == permutation_window_size
+                    && opened
+                        .permutation
+                        .iter()
+                        .all(|row| row.len() == permutation_width)
+                    && (!ints.is_empty() || cumulative_sum.is_zero())
+                    && opened.quotient_chunks.len()
+                        == (1 << log_quotient_degree) * challenge_ext_degree
+            },
+        );
+    if !valid_shape {
+        return Err(VerificationError::InvalidProofShape);
+    }
+
+    challenger.observe(commitments.main.clone());
+    let mut permutation_challenges: Vec<SC::Challenge> = vec![];
+    if let Some(permutation_commit) = &commitments.permutation {
+        permutation_challenges = (0..NUM_INTERACTION_CHALLENGES)
+            .map(|_| challenger.sample_ext_element())
+            .collect();
+        challenger.observe(permutation_commit.clone());
+        for &sum in cumulative_sums {
+            challenger.observe_ext_element(sum);
+        }
+    }
+    let alpha: SC::Challenge = challenger.sample_ext_element();
+    challenger.observe(commitments.quotient_chunks.clone());
+    let zeta: SC::Challenge = challenger.sample_ext_element();
+
+    let main_points = izip!(airs, degree_bits)
+        .map(|(air, &log_degree)| {
+            SC::Val::two_adic_generator(log_degree)
+                .powers()
+                .take(<A as BaseAir<SC::Val>>::window_size(air))
+                .map(|g_pow| zeta * g_pow)
+                .collect_vec()
+        })
+        .collect_vec();
+    let quotient_points = log_quotient_degrees
+        .iter()
+        .map(|&log_quotient_degree| vec![zeta.exp_power_of_2(log_quotient_degree)])
+        .collect_vec();
+    let main_dims = izip!(airs, degree_bits)
+        .map(|(air, &log_degree)| Dimensions {
+            width: <A as BaseAir<SC::Val>>::width(air),
+            height: 1 << log_degree,
+        })
+        .collect_vec();
+    let quotient_dims = izip!(&log_quotient_degrees, degree_bits)
+        .map(|(&log_quotient_degree, &log_degree)| Dimensions {
+            width: (1 << log_quotient_degree) * challenge_ext_degree,
+            height: 1 << log_degree,
+        })
+        .collect_vec();
+    let main_values = opened_values
+        .iter()
+        .map(|opened| opened.trace.clone())
+        .collect_vec();
+    let quotient_values = opened_values
+        .iter()
+        .map(|opened| vec![opened.quotient_chunks.clone()])
+        .collect_vec();
+    let mut commits_and_points = vec![
+        (commitments.main.clone(), main_points.as_slice()),
+        (
+            commitments.quotient_chunks.clone(),
+            quotient_points.as_slice(),
+        ),
+    ];
+    let mut dims = vec![main_dims, quotient_dims];
+    let mut values = vec![main_values, quotient_values];
+    let permutation_points = izip!(&main_points, &interactions)
+        .filter(|(_, ints)| !ints.is_empty())
+        .map(|(points, _)| points.clone())
+        .collect_vec();
+    if let Some(permutation_commit) = &commitments.permutation {
+        commits_and_points.push((permutation_commit.clone(), permutation_points.as_slice()));
+        dims.push(
+            izip!(opened_values, degree_bits)
+                .filter(|(opened, _)| !opened.permutation.is_empty())
+                .map(|(opened, &log_degree)| Dimensions {
+                    width: opened.permutation[0].len(),
+                    height: 1 << log_degree,
+                })
+                .collect(),
+        );
+        values.push(
+            opened_values
+                .iter()
+                .filter(|opened| !opened.permutation.is_empty())
+                .map(|opened| opened.permutation.clone())
+                .collect(),
+        );
+    }
+    config
+        .pcs()
+        .verify_multi_batches(
+            &commits_and_points,
+            &dims,
+            values,
+            opening_proof,
+            challenger,
+        )
+        .map_err(|_| VerificationError::InvalidOpeningArgument)?;
+
+    // Undo the flattening of extension field polynomials into D base field polynomials.
+    let unflatten = |values: &[SC::Challenge]| -> Vec<SC::Challenge> {
+        values
+            .chunks(challenge_ext_degree)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &c)| {
+                        <SC::Challenge as AbstractExtensionField<SC::Val>>::monomial(i) * c
+                    })
+                    .sum()
+            })
+            .collect()
+    };
+
+    for (air, opened, ints, &cumulative_sum, &log_degree) in izip!(
+        airs,
+        opened_values,
+        &interactions,
+        cumulative_sums,
+        degree_bits
+    ) {
+        // Derive the opening of the quotient polynomial, which was split into degree n chunks,
+        // then flattened into D base field polynomials. We first undo the flattening.
+        let mut quotient_parts = unflatten(&opened.quotient_chunks);
+        // Then we reconstruct the larger quotient polynomial from its degree-n parts.
+        reverse_slice_index_bits(&mut quotient_parts);
+        let quotient: SC::Challenge = zeta
+            .powers()
+            .zip(quotient_parts)
+            .map(|(weight, part)| part * weight)
+            .sum();
+
+        let g_subgroup = SC::Val::two_adic_generator(log_degree);
+        let z_h = zeta.exp_power_of_2(log_degree) - SC::Challenge::one();
+        let is_first_row = z_h / (zeta - SC::Val::one());
+        let is_last_row = z_h / (zeta - g_subgroup.inverse());
+        let transition_selectors =
+            transition_selectors(zeta, g_subgroup, <A as BaseAir<SC::Val>>::window_size(air));
+
+        let permutation = opened
+            .permutation
+            .iter()
+            .map(|row| unflatten(row))
+            .collect_vec();
+
+        let mut folder = VerifierConstraintFolder {
+            main: WindowMatrixView::new(&opened.trace),
+            permutation: WindowMatrixView::new(&permutation),
+            permutation_challenges: &permutation_challenges,
+            is_first_row,
+            is_last_row,
+            transition_selectors,
+            alpha,
+            accumulator: SC::Challenge::zero(),
+        };
+        air.eval(&mut folder);
+        if !ints.is_empty() {
+            eval_permutation_constraints(ints, cumulative_sum, &mut folder);
+        }
+
+        // Finally, check that
+        //     folded_constraints(zeta) = Z_H(zeta) * quotient(zeta)
+        if folder.accumulator != z_h * quotient {
+            return Err(VerificationError::OodEvaluationMismatch);
+        }
+    }
+
+    if cumulative_sums.iter().copied().sum::<SC::Challenge>() != SC::Challenge::zero() {
+        return Err(VerificationError::NonZeroCumulativeSum);
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum VerificationError {
+    InvalidProofShape,
+    /// An error occurred while verifying the claimed openings.
+    InvalidOpeningArgument,
+    /// Out-of-domain evaluation mismatch, i.e. `constraints(zeta)` did not match
+    /// `quotient(zeta) Z_H(zeta)` for some table.
+    OodEvaluationMismatch,
+    /// The LogUp running sums of all tables did not add up to zero, i.e. the messages sent and
+    /// received by the tables did not balance.
+    NonZeroCumulativeSum,
+}
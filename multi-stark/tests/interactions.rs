@@ -0,0 +1,169 @@
+use p3_air::{Air, AirBuilder, BaseAir, Interaction, InteractionAir, VirtualPairCol};
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractField, Field};
+use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_multi_stark::{prove, verify, VerificationError};
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::StarkConfig;
+use rand::{thread_rng, Rng};
+
+/// A toy machine, where a CPU table delegates multiplications to a multiplication chip.
+///
+/// Each CPU row has columns `a`, `b`, `c` and `is_mul`, and sends `(a, b, c)` to the chip when
+/// `is_mul` is set, without checking the product itself. Each chip row has columns `a`, `b`, `c` and
+/// `count`, checks that `a * b = c`, and receives `(a, b, c)` `count` times.
+enum MachineAir {
+    Cpu,
+    MulChip,
+}
+
+const MUL_BUS: usize = 0;
+
+impl<F> BaseAir<F> for MachineAir {
+    fn width(&self) -> usize {
+        4
+    }
+}
+
+impl<F: Field> InteractionAir<F> for MachineAir {
+    fn sends(&self) -> Vec<Interaction<F>> {
+        match self {
+            MachineAir::Cpu => vec![mul_interaction(3)],
+            MachineAir::MulChip => vec![],
+        }
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        match self {
+            MachineAir::Cpu => vec![],
+            MachineAir::MulChip => vec![mul_interaction(3)],
+        }
+    }
+}
+
+fn mul_interaction<F: Field>(count_col: usize) -> Interaction<F> {
+    Interaction {
+        fields: (0..3).map(VirtualPairCol::single_main).collect(),
+        count: VirtualPairCol::single_main(count_col),
+        argument_index: MUL_BUS,
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for MachineAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        match self {
+            MachineAir::Cpu => builder.assert_bool(local[3]),
+            MachineAir::MulChip => builder.assert_eq(local[0] * local[1], local[2]),
+        }
+    }
+}
+
+/// Generate CPU and chip traces, where each CPU row may request one of the chip's products, picked
+/// at random.
+fn generate_traces<F: Field>(
+    log_cpu_height: usize,
+    log_chip_height: usize,
+) -> (RowMajorMatrix<F>, RowMajorMatrix<F>) {
+    let mut rng = thread_rng();
+    let chip_height = 1 << log_chip_height;
+    let products: Vec<(F, F, F)> = (0..chip_height)
+        .map(|_| {
+            let a = F::from_canonical_u32(rng.gen_range(0..1 << 16));
+            let b = F::from_canonical_u32(rng.gen_range(0..1 << 16));
+            (a, b, a * b)
+        })
+        .collect();
+
+    let mut counts = vec![0; chip_height];
+    let cpu_values = (0..1 << log_cpu_height)
+        .flat_map(|_| {
+            let is_mul = rng.gen_bool(0.75);
+            let i = rng.gen_range(0..chip_height);
+            counts[i] += is_mul as usize;
+            let (a, b, c) = products[i];
+            [a, b, c, F::from_bool(is_mul)]
+        })
+        .collect();
+    let chip_values = products
+        .iter()
+        .zip(counts)
+        .flat_map(|(&(a, b, c), count)| [a, b, c, F::from_canonical_usize(count)])
+        .collect();
+
+    (
+        RowMajorMatrix::new(cpu_values, 4),
+        RowMajorMatrix::new(chip_values, 4),
+    )
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+fn setup() -> (MyConfig, Perm) {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 40,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+    let pcs = Pcs::new(fri_config, Dft {}, val_mmcs);
+    (MyConfig::new(pcs), perm)
+}
+
+fn prove_and_verify(traces: Vec<RowMajorMatrix<Val>>) -> Result<(), VerificationError> {
+    let (config, perm) = setup();
+
+    let airs = [MachineAir::Cpu, MachineAir::MulChip];
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &airs, &mut challenger, traces);
+
+    let mut challenger = Challenger::new(perm);
+    verify(&config, &airs, &mut challenger, &proof)
+}
+
+#[test]
+fn test_balanced_interactions() -> Result<(), VerificationError> {
+    let (cpu_trace, chip_trace) = generate_traces(6, 3);
+    prove_and_verify(vec![cpu_trace, chip_trace])
+}
+
+#[test]
+fn test_unbalanced_interactions() {
+    let (mut cpu_trace, chip_trace) = generate_traces(5, 4);
+    // Request a product which the chip never provides.
+    let row = cpu_trace.row_mut(0);
+    row[2] += Val::one();
+    row[3] = Val::one();
+    let result = prove_and_verify(vec![cpu_trace, chip_trace]);
+    assert!(matches!(
+        result,
+        Err(VerificationError::NonZeroCumulativeSum)
+    ));
+}
@@ -1,72 +1,211 @@
-// use p3_air::{Air, AirBuilder};
-// use p3_multi_stark::{prove, StarkConfig};
-// use p3_challenger::DuplexChallenger;
-// use p3_fri::FRIBasedPcs;
-// use p3_lde::NaiveCosetLde;
-// use p3_matrix::dense::RowMajorMatrix;
-// use p3_matrix::Matrix;
-// use p3_merkle_tree::MerkleTreeMMCS;
-// use p3_poseidon::Poseidon;
-// use p3_symmetric::TruncatedPermutation;
-// use p3_symmetric::{ArrayPermutation, CryptographicPermutation, MDSPermutation};
-// use p3_symmetric::PaddingFreeSponge;
-// use rand::thread_rng;
-// use p3_mersenne_31::Mersenne31;
-// use p3_tensor_pcs::TensorPcs;
-//
-// struct MulAir;
-//
-// impl<AB: AirBuilder> Air<AB> for MulAir {
-//     fn eval(&self, builder: &mut AB) {
-//         let main = builder.main();
-//         let main_local = main.row(0);
-//         let diff = main_local[0] * main_local[1] - main_local[2];
-//         builder.assert_zero(diff);
-//     }
-// }
-//
-// #[test]
-// #[ignore] // TODO: Not ready yet.
-// fn test_prove_goldilocks() {
-//     type Val = Mersenne31;
-//     type Challenge = Mersenne31; // TODO
-//
-//     #[derive(Clone)]
-//     struct MyMds;
-//     impl CryptographicPermutation<[Val; 8]> for MyMds {
-//         fn permute(&self, input: [Val; 8]) -> [Val; 8] {
-//             input // TODO
-//         }
-//     }
-//     impl ArrayPermutation<Val, 8> for MyMds {}
-//     impl MdsPermutation<Val, 8> for MyMds {}
-//
-//     type Mds = MyMds;
-//     let mds = MyMds;
-//
-//     type Perm = Poseidon<Val, Mds, 8, 7>;
-//     let perm = Perm::new(5, 5, vec![], mds);
-//
-//     type H4 = PaddingFreeSponge<Val, Perm, { 4 + 4 }>;
-//     let h4 = H4::new(perm.clone());
-//
-//     type C = TruncatedPermutation<Val, Perm, 2, 4, { 2 * 4 }>;
-//     let c = C::new(perm.clone());
-//
-//     type Mmcs = MerkleTreeMMCS<Val, [Val; 4], H4, C>;
-//     type Pcs = TensorPcs<Val, MyCode>;
-//     type MyConfig = StarkConfig<Val, Challenge, Challenge, Pcs>;
-//
-//     let mut rng = thread_rng();
-//     let trace = RowMajorMatrix::rand(&mut rng, 256, 10);
-//     let pcs = todo!();
-//     let config = StarkConfig::new(pcs);
-//     let mut challenger = DuplexChallenger::new(perm);
-//     prove::<MyConfig, _, _>(&MulAir, config, &mut challenger, trace);
-// }
-//
-// #[test]
-// #[ignore] // TODO: Not ready yet.
-// fn test_prove_mersenne_31() {
-//     todo!()
-// }
+use itertools::Itertools;
+use p3_air::{Air, AirBuilder, BaseAir, InteractionAir};
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractField, Field};
+use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_multi_stark::{prove, verify, VerificationError};
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::StarkConfig;
+use rand::distributions::{Distribution, Standard};
+use rand::{thread_rng, Rng};
+
+/// How many `a * b = c` operations to do per row in the multiplication table.
+const REPETITIONS: usize = 10;
+
+/// The tables of a toy machine: one doing multiplications, one computing Fibonacci numbers, and one
+/// computing Tribonacci numbers in a single column, using a window of four rows.
+enum TestAir {
+    Mul,
+    Fibonacci,
+    Tribonacci,
+}
+
+impl<F> BaseAir<F> for TestAir {
+    fn width(&self) -> usize {
+        match self {
+            TestAir::Mul => REPETITIONS * 3,
+            TestAir::Fibonacci => 2,
+            TestAir::Tribonacci => 1,
+        }
+    }
+
+    fn window_size(&self) -> usize {
+        match self {
+            TestAir::Mul | TestAir::Fibonacci => 2,
+            TestAir::Tribonacci => 4,
+        }
+    }
+}
+
+impl<F: Field> InteractionAir<F> for TestAir {}
+
+impl<AB: AirBuilder> Air<AB> for TestAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        match self {
+            TestAir::Mul => {
+                for (&a, &b, &c) in local.iter().tuples() {
+                    builder.assert_zero(a * b - c);
+                }
+            }
+            TestAir::Fibonacci => {
+                let next = main.row_slice(1);
+                builder.when_first_row().assert_zero(local[0]);
+                builder.when_first_row().assert_one(local[1]);
+                builder.when_transition().assert_eq(next[0], local[1]);
+                builder
+                    .when_transition()
+                    .assert_eq(next[1], local[0] + local[1]);
+            }
+            TestAir::Tribonacci => {
+                let rows: [AB::Var; 4] = core::array::from_fn(|i| main.row_slice(i)[0]);
+                builder.when_first_row().assert_zero(rows[0]);
+                builder.when_first_row().assert_zero(rows[1]);
+                builder.when_first_row().assert_one(rows[2]);
+                builder
+                    .when_transition_window(4)
+                    .assert_eq(rows[3], rows[0] + rows[1] + rows[2]);
+            }
+        }
+    }
+}
+
+fn random_valid_mul_trace<F: Field>(rows: usize) -> RowMajorMatrix<F>
+where
+    Standard: Distribution<F>,
+{
+    let mut rng = thread_rng();
+    let mut values = vec![F::default(); rows * REPETITIONS * 3];
+    for (a, b, c) in values.iter_mut().tuples() {
+        *a = rng.gen();
+        *b = rng.gen();
+        *c = *a * *b;
+    }
+    RowMajorMatrix::new(values, REPETITIONS * 3)
+}
+
+fn fibonacci_trace<F: Field>(rows: usize) -> RowMajorMatrix<F> {
+    let mut values = Vec::with_capacity(rows * 2);
+    let (mut a, mut b) = (F::zero(), F::one());
+    for _ in 0..rows {
+        values.extend([a, b]);
+        (a, b) = (b, a + b);
+    }
+    RowMajorMatrix::new(values, 2)
+}
+
+fn tribonacci_trace<F: Field>(rows: usize) -> RowMajorMatrix<F> {
+    let mut values = vec![F::zero(), F::zero(), F::one()];
+    for i in 3..rows {
+        values.push(values[i - 3] + values[i - 2] + values[i - 1]);
+    }
+    RowMajorMatrix::new_col(values)
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+fn setup() -> (MyConfig, Perm) {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 40,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+    let pcs = Pcs::new(fri_config, Dft {}, val_mmcs);
+    (MyConfig::new(pcs), perm)
+}
+
+fn prove_and_verify(
+    prover_airs: &[TestAir],
+    verifier_airs: &[TestAir],
+    traces: Vec<RowMajorMatrix<Val>>,
+) -> Result<(), VerificationError> {
+    let (config, perm) = setup();
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, prover_airs, &mut challenger, traces);
+
+    let mut challenger = Challenger::new(perm);
+    verify(&config, verifier_airs, &mut challenger, &proof)
+}
+
+#[test]
+fn test_prove_multi_table() -> Result<(), VerificationError> {
+    let airs = [
+        TestAir::Mul,
+        TestAir::Fibonacci,
+        TestAir::Tribonacci,
+        TestAir::Mul,
+    ];
+    let traces = vec![
+        random_valid_mul_trace(1 << 6),
+        fibonacci_trace(1 << 3),
+        tribonacci_trace(1 << 5),
+        random_valid_mul_trace(1 << 4),
+    ];
+    prove_and_verify(&airs, &airs, traces)
+}
+
+#[test]
+fn test_invalid_trace() {
+    let airs = [TestAir::Fibonacci, TestAir::Mul];
+    let mut mul_trace = random_valid_mul_trace(1 << 4);
+    mul_trace.values[5] += Val::one();
+    let traces = vec![fibonacci_trace(1 << 5), mul_trace];
+    let result = prove_and_verify(&airs, &airs, traces);
+    assert!(matches!(
+        result,
+        Err(VerificationError::OodEvaluationMismatch)
+    ));
+}
+
+#[test]
+fn test_invalid_window_trace() {
+    let airs = [TestAir::Tribonacci, TestAir::Fibonacci];
+    let mut tribonacci_trace = tribonacci_trace(1 << 5);
+    tribonacci_trace.values[10] += Val::one();
+    let traces = vec![tribonacci_trace, fibonacci_trace(1 << 3)];
+    let result = prove_and_verify(&airs, &airs, traces);
+    assert!(matches!(
+        result,
+        Err(VerificationError::OodEvaluationMismatch)
+    ));
+}
+
+#[test]
+fn test_wrong_airs() {
+    let traces = vec![random_valid_mul_trace(1 << 4), fibonacci_trace(1 << 4)];
+    let result = prove_and_verify(
+        &[TestAir::Mul, TestAir::Fibonacci],
+        &[TestAir::Fibonacci, TestAir::Mul],
+        traces,
+    );
+    assert!(matches!(result, Err(VerificationError::InvalidProofShape)));
+}
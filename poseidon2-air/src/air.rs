@@ -0,0 +1,167 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::{AbstractField, PrimeField};
+use p3_matrix::MatrixRowSlices;
+use p3_poseidon2::{DiffusionPermutation, Poseidon2, Poseidon2MEMatrix};
+use p3_symmetric::Permutation;
+
+use crate::columns::{FullRound, PartialRound, Poseidon2Cols, SBox};
+
+/// An AIR for the Poseidon2 permutation, using the round constants and linear layers of a given
+/// `Poseidon2` instance. Each row of the trace holds one permutation.
+pub struct Poseidon2Air<F, Diffusion, const WIDTH: usize, const D: u64> {
+    pub(crate) poseidon2: Poseidon2<F, Diffusion, WIDTH, D>,
+}
+
+impl<F, Diffusion, const WIDTH: usize, const D: u64> Poseidon2Air<F, Diffusion, WIDTH, D>
+where
+    F: PrimeField,
+{
+    pub fn new(poseidon2: Poseidon2<F, Diffusion, WIDTH, D>) -> Self {
+        assert!(D > 1, "the S-box must have degree at least 2");
+        assert!(
+            poseidon2.rounds_f() > 0,
+            "expected at least one external round"
+        );
+        assert_eq!(
+            poseidon2.constants().len(),
+            poseidon2.rounds_f() + poseidon2.rounds_p(),
+            "expected one set of round constants per round"
+        );
+        Self { poseidon2 }
+    }
+
+    pub fn num_cols(&self) -> usize {
+        Poseidon2Cols::<F, WIDTH>::num_cols(D, self.poseidon2.rounds_f(), self.poseidon2.rounds_p())
+    }
+
+    /// Read the columns of one permutation from the first `num_cols()` elements of `row`, which may
+    /// be longer, e.g. when a wider AIR embeds this one.
+    pub fn cols<T: Copy>(&self, row: &[T]) -> Poseidon2Cols<T, WIDTH> {
+        Poseidon2Cols::from_slice(
+            &row[..self.num_cols()],
+            D,
+            self.poseidon2.rounds_f(),
+            self.poseidon2.rounds_p(),
+        )
+    }
+
+    /// Constrain `local` to hold one permutation, from its inputs to its outputs. This is what
+    /// `eval` does for each row, for use by AIRs which embed these columns in a wider row.
+    pub fn eval_cols<AB>(&self, builder: &mut AB, local: &Poseidon2Cols<AB::Var, WIDTH>)
+    where
+        AB: AirBuilder<F = F>,
+        Diffusion: DiffusionPermutation<AB::Expr, WIDTH>,
+    {
+        let mut state: [AB::Expr; WIDTH] = local.inputs.map(Into::into);
+        Poseidon2MEMatrix::<WIDTH, D>.permute_mut(&mut state);
+
+        let mut round_constants = self.poseidon2.constants().iter();
+        for round in &local.beginning_full_rounds {
+            eval_full_round::<AB, WIDTH, D>(
+                builder,
+                &mut state,
+                round,
+                round_constants.next().unwrap(),
+            );
+        }
+        for round in &local.partial_rounds {
+            eval_partial_round::<AB, Diffusion, WIDTH, D>(
+                builder,
+                &mut state,
+                round,
+                &round_constants.next().unwrap()[0],
+                self.poseidon2.internal_linear_layer(),
+            );
+        }
+        for round in &local.ending_full_rounds {
+            eval_full_round::<AB, WIDTH, D>(
+                builder,
+                &mut state,
+                round,
+                round_constants.next().unwrap(),
+            );
+        }
+    }
+}
+
+impl<F, Diffusion, const WIDTH: usize, const D: u64> BaseAir<F>
+    for Poseidon2Air<F, Diffusion, WIDTH, D>
+where
+    F: PrimeField,
+    Diffusion: Sync,
+{
+    fn width(&self) -> usize {
+        self.num_cols()
+    }
+}
+
+impl<AB, Diffusion, const WIDTH: usize, const D: u64> Air<AB>
+    for Poseidon2Air<AB::F, Diffusion, WIDTH, D>
+where
+    AB: AirBuilder,
+    AB::F: PrimeField,
+    Diffusion: DiffusionPermutation<AB::Expr, WIDTH> + Sync,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = self.cols(main.row_slice(0));
+        self.eval_cols(builder, &local);
+    }
+}
+
+fn eval_full_round<AB: AirBuilder, const WIDTH: usize, const D: u64>(
+    builder: &mut AB,
+    state: &mut [AB::Expr; WIDTH],
+    round: &FullRound<AB::Var, WIDTH>,
+    round_constants: &[AB::F; WIDTH],
+) {
+    for ((x, sbox), &rc) in state.iter_mut().zip(&round.sbox).zip(round_constants) {
+        *x += rc.into();
+        eval_sbox::<AB, D>(builder, x, sbox);
+    }
+    Poseidon2MEMatrix::<WIDTH, D>.permute_mut(state);
+
+    // Commit to the new state, so that the next round's S-boxes start from degree one inputs.
+    for (x, &post) in state.iter_mut().zip(&round.post) {
+        builder.assert_eq(x.clone(), post);
+        *x = post.into();
+    }
+}
+
+fn eval_partial_round<AB, Diffusion, const WIDTH: usize, const D: u64>(
+    builder: &mut AB,
+    state: &mut [AB::Expr; WIDTH],
+    round: &PartialRound<AB::Var>,
+    round_constant: &AB::F,
+    internal_linear_layer: &Diffusion,
+) where
+    AB: AirBuilder,
+    Diffusion: DiffusionPermutation<AB::Expr, WIDTH>,
+{
+    state[0] += (*round_constant).into();
+    eval_sbox::<AB, D>(builder, &mut state[0], &round.sbox);
+    builder.assert_eq(state[0].clone(), round.post_sbox);
+    state[0] = round.post_sbox.into();
+
+    internal_linear_layer.permute_mut(state);
+}
+
+/// Replace `x` with `x^D`, using the S-box's register for an intermediate power if it has one.
+fn eval_sbox<AB: AirBuilder, const D: u64>(
+    builder: &mut AB,
+    x: &mut AB::Expr,
+    sbox: &SBox<AB::Var>,
+) {
+    *x = match sbox.register {
+        Some(register) => {
+            builder.assert_eq(register, x.exp_u64(D / 2));
+            let register_squared = register.into().square();
+            if D % 2 == 1 {
+                register_squared * x.clone()
+            } else {
+                register_squared
+            }
+        }
+        None => x.exp_u64(D),
+    };
+}
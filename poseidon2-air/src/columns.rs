@@ -0,0 +1,121 @@
+use alloc::vec::Vec;
+
+/// The number of registers each S-box `x -> x^D` uses. For `D > 3`, we store `x^(D / 2)`, so that
+/// the S-box constraints have degree `max(3, D / 2)` rather than `D`.
+pub const fn sbox_registers(sbox_degree: u64) -> usize {
+    if sbox_degree > 3 {
+        1
+    } else {
+        0
+    }
+}
+
+/// The columns of a Poseidon2 trace. Each row holds one full permutation, from its inputs to the
+/// state after the last round.
+pub struct Poseidon2Cols<T, const WIDTH: usize> {
+    pub inputs: [T; WIDTH],
+    pub beginning_full_rounds: Vec<FullRound<T, WIDTH>>,
+    pub partial_rounds: Vec<PartialRound<T>>,
+    pub ending_full_rounds: Vec<FullRound<T, WIDTH>>,
+}
+
+/// The columns of an external round, which applies an S-box to every state element.
+pub struct FullRound<T, const WIDTH: usize> {
+    pub sbox: [SBox<T>; WIDTH],
+    /// The state after the round's linear layer.
+    pub post: [T; WIDTH],
+}
+
+/// The columns of an internal round, which applies an S-box to the first state element only.
+pub struct PartialRound<T> {
+    pub sbox: SBox<T>,
+    /// The first state element after the S-box, before the round's linear layer.
+    pub post_sbox: T,
+}
+
+/// The intermediate power of an S-box input, if the S-box degree calls for one.
+pub struct SBox<T> {
+    pub register: Option<T>,
+}
+
+impl<T, const WIDTH: usize> Poseidon2Cols<T, WIDTH> {
+    pub const fn num_cols(sbox_degree: u64, rounds_f: usize, rounds_p: usize) -> usize {
+        let registers = sbox_registers(sbox_degree);
+        WIDTH + rounds_f * WIDTH * (registers + 1) + rounds_p * (registers + 1)
+    }
+
+    /// The state at the end of the permutation.
+    pub fn outputs(&self) -> &[T; WIDTH] {
+        &self
+            .ending_full_rounds
+            .last()
+            .expect("expected at least one ending full round")
+            .post
+    }
+}
+
+impl<T: Copy, const WIDTH: usize> Poseidon2Cols<T, WIDTH> {
+    pub fn from_slice(row: &[T], sbox_degree: u64, rounds_f: usize, rounds_p: usize) -> Self {
+        debug_assert_eq!(row.len(), Self::num_cols(sbox_degree, rounds_f, rounds_p));
+        let registers = sbox_registers(sbox_degree);
+        let iter = &mut row.iter().copied();
+
+        let rounds_f_beginning = rounds_f / 2;
+        let inputs = core::array::from_fn(|_| iter.next().unwrap());
+        let beginning_full_rounds = (0..rounds_f_beginning)
+            .map(|_| FullRound::read(iter, registers))
+            .collect();
+        let partial_rounds = (0..rounds_p)
+            .map(|_| PartialRound {
+                sbox: SBox::read(iter, registers),
+                post_sbox: iter.next().unwrap(),
+            })
+            .collect();
+        let ending_full_rounds = (rounds_f_beginning..rounds_f)
+            .map(|_| FullRound::read(iter, registers))
+            .collect();
+
+        Self {
+            inputs,
+            beginning_full_rounds,
+            partial_rounds,
+            ending_full_rounds,
+        }
+    }
+
+    pub(crate) fn append_to(&self, values: &mut Vec<T>) {
+        let append_full_round = |values: &mut Vec<T>, round: &FullRound<T, WIDTH>| {
+            values.extend(round.sbox.iter().flat_map(|sbox| sbox.register));
+            values.extend(round.post);
+        };
+
+        values.extend(self.inputs);
+        for round in &self.beginning_full_rounds {
+            append_full_round(values, round);
+        }
+        for round in &self.partial_rounds {
+            values.extend(round.sbox.register);
+            values.push(round.post_sbox);
+        }
+        for round in &self.ending_full_rounds {
+            append_full_round(values, round);
+        }
+    }
+}
+
+impl<T: Copy, const WIDTH: usize> FullRound<T, WIDTH> {
+    fn read(iter: &mut impl Iterator<Item = T>, registers: usize) -> Self {
+        Self {
+            sbox: core::array::from_fn(|_| SBox::read(iter, registers)),
+            post: core::array::from_fn(|_| iter.next().unwrap()),
+        }
+    }
+}
+
+impl<T: Copy> SBox<T> {
+    fn read(iter: &mut impl Iterator<Item = T>, registers: usize) -> Self {
+        Self {
+            register: (registers > 0).then(|| iter.next().unwrap()),
+        }
+    }
+}
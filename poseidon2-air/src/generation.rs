@@ -0,0 +1,87 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use p3_field::PrimeField;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_poseidon2::{DiffusionPermutation, Poseidon2MEMatrix};
+use p3_symmetric::Permutation;
+use tracing::instrument;
+
+use crate::columns::{sbox_registers, FullRound, PartialRound, Poseidon2Cols, SBox};
+use crate::Poseidon2Air;
+
+impl<F, Diffusion, const WIDTH: usize, const D: u64> Poseidon2Air<F, Diffusion, WIDTH, D>
+where
+    F: PrimeField,
+    Diffusion: DiffusionPermutation<F, WIDTH>,
+{
+    /// Generate a trace with one row per permutation of `inputs`. The trace is padded to a power of
+    /// two height with permutations of the zero state.
+    #[instrument(name = "generate Poseidon2 trace", skip_all)]
+    pub fn generate_trace_rows(&self, inputs: Vec<[F; WIDTH]>) -> RowMajorMatrix<F> {
+        let num_rows = inputs.len().next_power_of_two();
+        let num_cols = self.num_cols();
+        let mut values = Vec::with_capacity(num_rows * num_cols);
+
+        let padding = vec![[F::zero(); WIDTH]; num_rows - inputs.len()];
+        for input in inputs.into_iter().chain(padding) {
+            self.generate_trace_row(input).append_to(&mut values);
+        }
+
+        RowMajorMatrix::new(values, num_cols)
+    }
+
+    fn generate_trace_row(&self, inputs: [F; WIDTH]) -> Poseidon2Cols<F, WIDTH> {
+        let rounds_f_beginning = self.poseidon2.rounds_f() / 2;
+        let (beginning_constants, constants) =
+            self.poseidon2.constants().split_at(rounds_f_beginning);
+        let (partial_constants, ending_constants) = constants.split_at(self.poseidon2.rounds_p());
+
+        let mut state = inputs;
+        Poseidon2MEMatrix::<WIDTH, D>.permute_mut(&mut state);
+
+        let beginning_full_rounds = beginning_constants
+            .iter()
+            .map(|round_constants| full_round::<F, WIDTH, D>(&mut state, round_constants))
+            .collect();
+        let partial_rounds = partial_constants
+            .iter()
+            .map(|round_constants| {
+                let sbox = sbox::<F, D>(&mut state[0], round_constants[0]);
+                let post_sbox = state[0];
+                self.poseidon2
+                    .internal_linear_layer()
+                    .permute_mut(&mut state);
+                PartialRound { sbox, post_sbox }
+            })
+            .collect();
+        let ending_full_rounds = ending_constants
+            .iter()
+            .map(|round_constants| full_round::<F, WIDTH, D>(&mut state, round_constants))
+            .collect();
+
+        Poseidon2Cols {
+            inputs,
+            beginning_full_rounds,
+            partial_rounds,
+            ending_full_rounds,
+        }
+    }
+}
+
+fn full_round<F: PrimeField, const WIDTH: usize, const D: u64>(
+    state: &mut [F; WIDTH],
+    round_constants: &[F; WIDTH],
+) -> FullRound<F, WIDTH> {
+    let sbox = core::array::from_fn(|i| sbox::<F, D>(&mut state[i], round_constants[i]));
+    Poseidon2MEMatrix::<WIDTH, D>.permute_mut(state);
+    FullRound { sbox, post: *state }
+}
+
+/// Add the round constant to `x` and apply the S-box, returning the S-box's register values.
+fn sbox<F: PrimeField, const D: u64>(x: &mut F, round_constant: F) -> SBox<F> {
+    *x += round_constant;
+    let register = (sbox_registers(D) > 0).then(|| x.exp_u64(D / 2));
+    *x = x.exp_u64(D);
+    SBox { register }
+}
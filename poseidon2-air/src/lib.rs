@@ -0,0 +1,12 @@
+//! An AIR for the Poseidon2 permutation, as implemented by `p3_poseidon2::Poseidon2`.
+
+#![no_std]
+
+extern crate alloc;
+
+mod air;
+mod columns;
+mod generation;
+
+pub use air::*;
+pub use columns::*;
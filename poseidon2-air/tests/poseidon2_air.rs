@@ -0,0 +1,152 @@
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractField, Field, PrimeField};
+use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_goldilocks::Goldilocks;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{Matrix, MatrixRowSlices};
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_poseidon2::{
+    DiffusionMatrixBabybear, DiffusionMatrixGoldilocks, DiffusionPermutation, Poseidon2,
+};
+use p3_poseidon2_air::{Poseidon2Air, Poseidon2Cols};
+use p3_symmetric::{PaddingFreeSponge, Permutation, TruncatedPermutation};
+use p3_uni_stark::{check_constraints, prove, verify, PublicRow, StarkConfig, VerificationError};
+use rand::distributions::Standard;
+use rand::prelude::Distribution;
+use rand::{random, thread_rng};
+
+type Val = BabyBear;
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+/// Check that the last state of each row is the output of the native permutation.
+fn check_outputs<F, Diffusion, const WIDTH: usize, const D: u64>(
+    perm: Poseidon2<F, Diffusion, WIDTH, D>,
+    num_permutations: usize,
+) where
+    F: PrimeField,
+    Diffusion: DiffusionPermutation<F, WIDTH> + Clone,
+    Standard: Distribution<F>,
+{
+    let inputs: Vec<[F; WIDTH]> = (0..num_permutations)
+        .map(|_| core::array::from_fn(|_| random()))
+        .collect();
+    let air = Poseidon2Air::new(perm.clone());
+    let trace = air.generate_trace_rows(inputs.clone());
+    assert_eq!(trace.height(), num_permutations.next_power_of_two());
+
+    for (i, input) in inputs.into_iter().enumerate() {
+        let row = Poseidon2Cols::<F, WIDTH>::from_slice(
+            trace.row_slice(i),
+            D,
+            perm.rounds_f(),
+            perm.rounds_p(),
+        );
+        assert_eq!(*row.outputs(), perm.permute(input));
+    }
+}
+
+#[test]
+fn test_outputs_baby_bear() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    check_outputs(perm, 5);
+}
+
+#[test]
+fn test_outputs_goldilocks() {
+    let perm = Poseidon2::<Goldilocks, DiffusionMatrixGoldilocks, 8, 7>::new_from_rng(
+        8,
+        22,
+        DiffusionMatrixGoldilocks,
+        &mut thread_rng(),
+    );
+    check_outputs(perm, 5);
+}
+
+#[test]
+fn test_outputs_odd_rounds() {
+    let perm = Perm::new_from_rng(3, 5, DiffusionMatrixBabybear, &mut thread_rng());
+    check_outputs(perm, 3);
+}
+
+fn prove_and_verify(air_perm: &Perm, trace: RowMajorMatrix<Val>) -> Result<(), VerificationError> {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 40,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+    let pcs = Pcs::new(fri_config, Dft {}, val_mmcs);
+    let config = MyConfig::new(pcs);
+    let air = Poseidon2Air::new(air_perm.clone());
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut challenger, trace, &PublicRow::default());
+
+    let mut challenger = Challenger::new(perm);
+    verify(
+        &config,
+        &air,
+        &mut challenger,
+        &proof,
+        &PublicRow::default(),
+    )
+}
+
+#[test]
+fn test_prove_poseidon2() -> Result<(), VerificationError> {
+    let air_perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let inputs = (0..10).map(|_| random()).collect();
+    let trace = Poseidon2Air::new(air_perm.clone()).generate_trace_rows(inputs);
+    prove_and_verify(&air_perm, trace)
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "constraints had nonzero value on row 3")]
+fn test_prove_poseidon2_invalid_trace() {
+    let air_perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let inputs = (0..10).map(|_| random()).collect();
+    let mut trace = Poseidon2Air::new(air_perm.clone()).generate_trace_rows(inputs);
+    let width = trace.width;
+    // Tamper with the permutation's output.
+    trace.values[4 * width - 1] += Val::one();
+    let _ = prove_and_verify(&air_perm, trace);
+}
+
+#[test]
+fn test_check_constraints_poseidon2_invalid_trace() {
+    let air_perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let inputs = (0..10).map(|_| random()).collect();
+    let air = Poseidon2Air::new(air_perm);
+    let mut trace = air.generate_trace_rows(inputs);
+    let width = trace.width;
+    // Tamper with the permutation's output.
+    trace.values[4 * width - 1] += Val::one();
+    let failures =
+        check_constraints::<_, Challenge, _, _>(&air, &trace, &[], &PublicRow::default());
+
+    // Only the tampered row's output is wrong.
+    assert!(!failures.is_empty());
+    assert!(failures.iter().all(|failure| failure.row == 3));
+}
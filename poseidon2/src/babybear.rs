@@ -26,15 +26,15 @@ pub struct DiffusionMatrixBabybear;
 unsafe impl Send for DiffusionMatrixBabybear {}
 unsafe impl Sync for DiffusionMatrixBabybear {}
 
-impl<AF: AbstractField<F = BabyBear>> Permutation<[AF; 16]> for DiffusionMatrixBabybear {
+impl<AF: AbstractField + From<BabyBear>> Permutation<[AF; 16]> for DiffusionMatrixBabybear {
     fn permute_mut(&self, state: &mut [AF; 16]) {
         matmul_internal::<AF, 16>(state, MATRIX_DIAG_16_BABYBEAR);
     }
 }
 
-impl<AF: AbstractField<F = BabyBear>> DiffusionPermutation<AF, 16> for DiffusionMatrixBabybear {}
+impl<AF: AbstractField + From<BabyBear>> DiffusionPermutation<AF, 16> for DiffusionMatrixBabybear {}
 
-impl<AF: AbstractField<F = BabyBear>> Permutation<[AF; 24]> for DiffusionMatrixBabybear {
+impl<AF: AbstractField + From<BabyBear>> Permutation<[AF; 24]> for DiffusionMatrixBabybear {
     fn permute_mut(&self, state: &mut [AF; 24]) {
         matmul_internal::<AF, 24>(state, MATRIX_DIAG_24_BABYBEAR);
     }
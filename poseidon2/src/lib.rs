@@ -17,7 +17,7 @@ use alloc::vec::Vec;
 pub use babybear::DiffusionMatrixBabybear;
 pub use diffusion::DiffusionPermutation;
 pub use goldilocks::DiffusionMatrixGoldilocks;
-use matrix::Poseidon2MEMatrix;
+pub use matrix::Poseidon2MEMatrix;
 use p3_field::{AbstractField, PrimeField};
 use p3_symmetric::{CryptographicPermutation, Permutation};
 use rand::distributions::Standard;
@@ -96,6 +96,26 @@ where
         }
     }
 
+    /// The number of external rounds.
+    pub fn rounds_f(&self) -> usize {
+        self.rounds_f
+    }
+
+    /// The number of internal rounds.
+    pub fn rounds_p(&self) -> usize {
+        self.rounds_p
+    }
+
+    /// The round constants, in the order the rounds are applied.
+    pub fn constants(&self) -> &[[F; WIDTH]] {
+        &self.constants
+    }
+
+    /// The linear layer used in internal rounds.
+    pub fn internal_linear_layer(&self) -> &Diffusion {
+        &self.internal_linear_layer
+    }
+
     #[inline]
     fn add_rc<AF>(&self, state: &mut [AF; WIDTH], rc: &[AF::F; WIDTH])
     where
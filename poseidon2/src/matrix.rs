@@ -1,4 +1,4 @@
-use p3_field::{AbstractField, PrimeField};
+use p3_field::AbstractField;
 use p3_symmetric::Permutation;
 
 extern crate alloc;
@@ -14,11 +14,7 @@ pub struct Poseidon2MEMatrix<const WIDTH: usize, const D: u64>;
 
 // Multiply a 4-element vector x by M_4, in place.
 // This uses the formula from the start of Appendix B, with multiplications unrolled into additions.
-fn apply_m_4<AF>(x: &mut [AF])
-where
-    AF: AbstractField,
-    AF::F: PrimeField,
-{
+fn apply_m_4<AF: AbstractField>(x: &mut [AF]) {
     let t0 = x[0].clone() + x[1].clone();
     let t1 = x[2].clone() + x[3].clone();
     let t2 = x[1].clone() + x[1].clone() + t1.clone();
@@ -33,10 +29,8 @@ where
     x[3] = t4;
 }
 
-impl<AF, const WIDTH: usize, const D: u64> Permutation<[AF; WIDTH]> for Poseidon2MEMatrix<WIDTH, D>
-where
-    AF: AbstractField,
-    AF::F: PrimeField,
+impl<AF: AbstractField, const WIDTH: usize, const D: u64> Permutation<[AF; WIDTH]>
+    for Poseidon2MEMatrix<WIDTH, D>
 {
     fn permute_mut(&self, state: &mut [AF; WIDTH]) {
         // First, we apply M_4 to each consecutive four elements of the state.
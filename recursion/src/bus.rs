@@ -0,0 +1,42 @@
+//! The buses the tables of the recursive verifier communicate over. Each is the `argument_index` of
+//! the messages sent on it.
+
+use alloc::vec::Vec;
+
+use p3_air::{Interaction, VirtualPairCol};
+use p3_field::Field;
+
+/// Permutations of the Poseidon2 width, as `(inputs, outputs)`.
+pub(crate) const POSEIDON2: usize = 0;
+
+/// Elements of the inner transcript, as `(position, value, is_sample)`, where `position` counts the
+/// elements observed or sampled before.
+pub(crate) const TRANSCRIPT: usize = 1;
+
+/// Opened values of the committed matrices, as `(tree, leaf_index, column, value)`.
+pub(crate) const LEAF: usize = 2;
+
+/// Queried indices, along with the point of the largest FRI domain they query, as `(index, x)`.
+pub(crate) const QUERY: usize = 3;
+
+/// The reduced opening of each query, as `(index, x, reduced_opening)`.
+pub(crate) const REDUCED_OPENING: usize = 4;
+
+/// Claimed openings at out-of-domain points, as `(opening, column, value, point)`, where `opening`
+/// numbers the points of the trace's window, followed by the quotient's point.
+pub(crate) const OPENING: usize = 5;
+
+/// The final polynomial of FRI, which is a constant.
+pub(crate) const FINAL_POLY: usize = 6;
+
+pub(crate) fn message<F: Field>(
+    bus: usize,
+    fields: Vec<VirtualPairCol<F>>,
+    count: VirtualPairCol<F>,
+) -> Interaction<F> {
+    Interaction {
+        fields,
+        count,
+        argument_index: bus,
+    }
+}
@@ -0,0 +1,402 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use p3_air::{Air, AirBuilder, BaseAir, Interaction, InteractionAir, VirtualPairCol};
+use p3_field::{AbstractField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_symmetric::Permutation;
+
+use crate::columns::ColumnAllocator;
+use crate::{bus, WIDTH};
+
+/// An AIR for the transcript of a `p3_challenger::DuplexChallenger` of width `WIDTH`.
+///
+/// Each row observes or samples one element, or is padding. Position `i` of the transcript is on
+/// row `i`, and is received on the `TRANSCRIPT` bus as `(i, value, is_sample)` as many times as
+/// the row's multiplicity says, so that other tables can read challenges and check observed values
+/// by position. Each duplexing is looked up on the `POSEIDON2` bus.
+///
+/// The sponge state is kept with pending inputs already written to it, as `duplexing` would write
+/// them, so a duplexing permutes the state as is, except for the input which triggers it.
+pub struct DuplexChallengerAir {
+    cols: ChallengerCols,
+    width: usize,
+}
+
+struct ChallengerCols {
+    is_observe: usize,
+    is_sample: usize,
+    /// `is_observe`, unless the observation fills the input buffer and so duplexes.
+    observe_without_duplexing: usize,
+    /// `is_sample`, unless there are pending inputs or no outputs left, and so the sample duplexes.
+    sample_without_duplexing: usize,
+    position: usize,
+    value: usize,
+    multiplicity: usize,
+    /// A one-hot encoding of the number of pending inputs, before this row.
+    input_len: [usize; WIDTH],
+    /// A one-hot encoding of the number of unused outputs, before this row.
+    output_len: [usize; WIDTH + 1],
+    /// The sponge state before this row, with pending inputs written to it.
+    state: [usize; WIDTH],
+    /// The last element of the permutation's input, if this row duplexes.
+    last_input: usize,
+    /// The permuted state, if this row duplexes.
+    permuted: [usize; WIDTH],
+}
+
+impl DuplexChallengerAir {
+    pub fn new() -> Self {
+        let mut alloc = ColumnAllocator::default();
+        let cols = ChallengerCols {
+            is_observe: alloc.col(),
+            is_sample: alloc.col(),
+            observe_without_duplexing: alloc.col(),
+            sample_without_duplexing: alloc.col(),
+            position: alloc.col(),
+            value: alloc.col(),
+            multiplicity: alloc.col(),
+            input_len: alloc.array(),
+            output_len: alloc.array(),
+            state: alloc.array(),
+            last_input: alloc.col(),
+            permuted: alloc.array(),
+        };
+        Self {
+            cols,
+            width: alloc.width(),
+        }
+    }
+
+    /// Generate the trace of `transcript`, where position `i` is read `multiplicities[i]` times.
+    pub fn generate_trace<F: Field>(
+        &self,
+        transcript: &ChallengerTranscript<F>,
+        multiplicities: &[usize],
+    ) -> RowMajorMatrix<F> {
+        assert_eq!(
+            multiplicities.len(),
+            transcript.rows.len(),
+            "expected a multiplicity per transcript position"
+        );
+        let c = &self.cols;
+        let num_rows = transcript.rows.len().next_power_of_two().max(2);
+        let mut values = vec![F::zero(); num_rows * self.width];
+        let mut final_state = ChallengerState::default();
+
+        for (i, row) in values.chunks_exact_mut(self.width).enumerate() {
+            let (state, op) = match transcript.rows.get(i) {
+                Some(transcript_row) => (&transcript_row.state, Some(transcript_row)),
+                None => (&final_state, None),
+            };
+            row[c.position] = F::from_canonical_usize(i);
+            row[c.input_len[state.input_len]] = F::one();
+            row[c.output_len[state.output_len]] = F::one();
+            for (&col, &value) in c.state.iter().zip(&state.state) {
+                row[col] = value;
+            }
+            if let Some(op) = op {
+                row[c.value] = op.value;
+                row[c.multiplicity] = F::from_canonical_usize(multiplicities[i]);
+                let duplexes = op.permuted.is_some();
+                if op.is_sample {
+                    row[c.is_sample] = F::one();
+                    row[c.sample_without_duplexing] = F::from_bool(!duplexes);
+                } else {
+                    row[c.is_observe] = F::one();
+                    row[c.observe_without_duplexing] = F::from_bool(!duplexes);
+                }
+                if let Some(permuted) = op.permuted {
+                    row[c.last_input] = if op.is_sample {
+                        state.state[WIDTH - 1]
+                    } else {
+                        op.value
+                    };
+                    for (&col, &value) in c.permuted.iter().zip(&permuted) {
+                        row[col] = value;
+                    }
+                }
+            }
+            if i + 1 == transcript.rows.len() {
+                final_state = transcript.state.clone();
+            }
+        }
+        RowMajorMatrix::new(values, self.width)
+    }
+}
+
+impl Default for DuplexChallengerAir {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F> BaseAir<F> for DuplexChallengerAir {
+    fn width(&self) -> usize {
+        self.width
+    }
+}
+
+impl<F: Field> InteractionAir<F> for DuplexChallengerAir {
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let c = &self.cols;
+        let fields = c.state[..WIDTH - 1]
+            .iter()
+            .chain([&c.last_input])
+            .chain(&c.permuted)
+            .map(|&col| VirtualPairCol::single_main(col))
+            .collect();
+        // The number of duplexings, `is_observe + is_sample` minus the rows which don't duplex.
+        let duplexes = VirtualPairCol::new_main(
+            vec![
+                (c.is_observe, F::one()),
+                (c.is_sample, F::one()),
+                (c.observe_without_duplexing, F::neg_one()),
+                (c.sample_without_duplexing, F::neg_one()),
+            ],
+            F::zero(),
+        );
+        vec![bus::message(bus::POSEIDON2, fields, duplexes)]
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let c = &self.cols;
+        let fields = [c.position, c.value, c.is_sample]
+            .map(VirtualPairCol::single_main)
+            .to_vec();
+        vec![bus::message(
+            bus::TRANSCRIPT,
+            fields,
+            VirtualPairCol::single_main(c.multiplicity),
+        )]
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for DuplexChallengerAir {
+    fn eval(&self, builder: &mut AB) {
+        let c = &self.cols;
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+
+        let is_observe = local[c.is_observe];
+        let is_sample = local[c.is_sample];
+        let observe_without_duplexing = local[c.observe_without_duplexing];
+        let sample_without_duplexing = local[c.sample_without_duplexing];
+        let value = local[c.value];
+        let input_len = c.input_len.map(|col| local[col]);
+        let output_len = c.output_len.map(|col| local[col]);
+        let state = c.state.map(|col| local[col]);
+        let permuted = c.permuted.map(|col| local[col]);
+        let last_input = local[c.last_input];
+
+        builder.assert_bool(is_observe);
+        builder.assert_bool(is_sample);
+        builder.assert_bool(is_observe + is_sample);
+        for one_hot in [&input_len[..], &output_len[..]] {
+            for &flag in one_hot {
+                builder.assert_bool(flag);
+            }
+            builder.assert_one(one_hot.iter().map(|&flag| flag.into()).sum::<AB::Expr>());
+        }
+
+        // Observing duplexes once the input buffer is full, and sampling duplexes if there are
+        // pending inputs or no outputs left.
+        builder.assert_eq(
+            observe_without_duplexing,
+            is_observe * (AB::Expr::one() - input_len[WIDTH - 1]),
+        );
+        builder.assert_eq(
+            sample_without_duplexing,
+            is_sample * input_len[0] * (AB::Expr::one() - output_len[0]),
+        );
+        let observe_duplexing = is_observe - observe_without_duplexing;
+        let sample_duplexing = is_sample - sample_without_duplexing;
+        let duplexing = observe_duplexing.clone() + sample_duplexing.clone();
+        let is_padding = AB::Expr::one() - is_observe - is_sample;
+
+        builder
+            .when(is_padding.clone())
+            .assert_zero(local[c.multiplicity]);
+        builder
+            .when(observe_duplexing.clone())
+            .assert_eq(last_input, value);
+        builder
+            .when(sample_duplexing.clone())
+            .assert_eq(last_input, state[WIDTH - 1]);
+        builder
+            .when(sample_duplexing.clone())
+            .assert_eq(value, permuted[WIDTH - 1]);
+        // Without duplexing, a sample takes the last unused output.
+        let last_output = (1..=WIDTH)
+            .map(|len| output_len[len] * state[len - 1])
+            .sum::<AB::Expr>();
+        builder
+            .when(sample_without_duplexing)
+            .assert_eq(value, last_output);
+
+        let mut when_first = builder.when_first_row();
+        when_first.assert_zero(local[c.position]);
+        when_first.assert_one(input_len[0]);
+        when_first.assert_one(output_len[0]);
+        for &s in &state {
+            when_first.assert_zero(s);
+        }
+
+        let mut when_transition = builder.when_transition();
+        when_transition.assert_eq(next[c.position], local[c.position] + AB::Expr::one());
+        for i in 0..WIDTH {
+            when_transition.assert_eq(
+                next[c.state[i]],
+                state[i].into()
+                    + duplexing.clone() * (permuted[i] - state[i])
+                    + observe_without_duplexing * input_len[i] * (value - state[i]),
+            );
+        }
+        let keeps_inputs = AB::Expr::one() - duplexing.clone() - observe_without_duplexing;
+        for j in 0..WIDTH {
+            let mut expected = keeps_inputs.clone() * input_len[j];
+            if j == 0 {
+                expected += duplexing.clone();
+            } else {
+                expected += observe_without_duplexing * input_len[j - 1];
+            }
+            when_transition.assert_eq(next[c.input_len[j]], expected);
+        }
+        for j in 0..=WIDTH {
+            let mut expected = is_padding.clone() * output_len[j];
+            if j == 0 {
+                expected += observe_without_duplexing.into();
+            }
+            if j == WIDTH {
+                expected += observe_duplexing.clone();
+            }
+            if j == WIDTH - 1 {
+                expected += sample_duplexing.clone();
+            }
+            if j < WIDTH {
+                expected += sample_without_duplexing * output_len[j + 1];
+            }
+            when_transition.assert_eq(next[c.output_len[j]], expected);
+        }
+    }
+}
+
+/// The state of a `DuplexChallenger`, as `DuplexChallengerAir` tracks it.
+#[derive(Clone, Debug)]
+struct ChallengerState<F> {
+    /// The sponge state, with pending inputs written to it.
+    state: [F; WIDTH],
+    input_len: usize,
+    output_len: usize,
+}
+
+impl<F: Field> Default for ChallengerState<F> {
+    fn default() -> Self {
+        Self {
+            state: [F::zero(); WIDTH],
+            input_len: 0,
+            output_len: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TranscriptRow<F> {
+    /// The state before this row.
+    state: ChallengerState<F>,
+    is_sample: bool,
+    value: F,
+    /// The permuted state, if this row duplexes.
+    permuted: Option<[F; WIDTH]>,
+}
+
+/// Replays the observations and samples of a `DuplexChallenger`, recording the rows of its
+/// `DuplexChallengerAir` trace and the permutations it does.
+#[derive(Clone, Debug)]
+pub struct ChallengerTranscript<F> {
+    state: ChallengerState<F>,
+    rows: Vec<TranscriptRow<F>>,
+    permutation_inputs: Vec<[F; WIDTH]>,
+}
+
+impl<F: Field> ChallengerTranscript<F> {
+    pub fn new() -> Self {
+        Self {
+            state: ChallengerState::default(),
+            rows: vec![],
+            permutation_inputs: vec![],
+        }
+    }
+
+    /// The number of elements observed or sampled so far, i.e. the position of the next one.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The inputs of the permutations done so far.
+    pub fn permutation_inputs(&self) -> &[[F; WIDTH]] {
+        &self.permutation_inputs
+    }
+
+    fn duplex<P: Permutation<[F; WIDTH]>>(&mut self, perm: &P) -> [F; WIDTH] {
+        self.permutation_inputs.push(self.state.state);
+        let permuted = perm.permute(self.state.state);
+        self.state = ChallengerState {
+            state: permuted,
+            input_len: 0,
+            output_len: WIDTH,
+        };
+        permuted
+    }
+
+    pub fn observe<P: Permutation<[F; WIDTH]>>(&mut self, perm: &P, value: F) {
+        let before = self.state.clone();
+        self.state.state[self.state.input_len] = value;
+        self.state.input_len += 1;
+        self.state.output_len = 0;
+        let permuted = (self.state.input_len == WIDTH).then(|| self.duplex(perm));
+        self.rows.push(TranscriptRow {
+            state: before,
+            is_sample: false,
+            value,
+            permuted,
+        });
+    }
+
+    pub fn observe_slice<P: Permutation<[F; WIDTH]>>(&mut self, perm: &P, values: &[F]) {
+        for &value in values {
+            self.observe(perm, value);
+        }
+    }
+
+    pub fn sample<P: Permutation<[F; WIDTH]>>(&mut self, perm: &P) -> F {
+        let before = self.state.clone();
+        let permuted =
+            (self.state.input_len > 0 || self.state.output_len == 0).then(|| self.duplex(perm));
+        self.state.output_len -= 1;
+        let value = self.state.state[self.state.output_len];
+        self.rows.push(TranscriptRow {
+            state: before,
+            is_sample: true,
+            value,
+            permuted,
+        });
+        value
+    }
+
+    pub fn sample_array<P: Permutation<[F; WIDTH]>, const N: usize>(&mut self, perm: &P) -> [F; N] {
+        core::array::from_fn(|_| self.sample(perm))
+    }
+}
+
+impl<F: Field> Default for ChallengerTranscript<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
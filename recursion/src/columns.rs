@@ -0,0 +1,27 @@
+use alloc::vec::Vec;
+
+/// Assigns consecutive column indices, to lay out the columns of a table.
+#[derive(Default)]
+pub(crate) struct ColumnAllocator {
+    width: usize,
+}
+
+impl ColumnAllocator {
+    pub(crate) fn col(&mut self) -> usize {
+        self.width += 1;
+        self.width - 1
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> [usize; N] {
+        core::array::from_fn(|_| self.col())
+    }
+
+    pub(crate) fn vec(&mut self, len: usize) -> Vec<usize> {
+        (0..len).map(|_| self.col()).collect()
+    }
+
+    /// The number of columns allocated so far.
+    pub(crate) const fn width(&self) -> usize {
+        self.width
+    }
+}
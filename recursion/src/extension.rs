@@ -0,0 +1,47 @@
+use core::ops::Mul;
+
+use p3_field::extension::{BinomialExtensionField, BinomiallyExtendable};
+use p3_field::{AbstractExtensionField, AbstractField};
+
+/// Multiply two elements of `F[X] / (X^D - W)`, given by their coefficients.
+pub(crate) fn binomial_mul<F, Expr, const D: usize>(a: &[Expr; D], b: &[Expr; D]) -> [Expr; D]
+where
+    F: BinomiallyExtendable<D>,
+    Expr: AbstractField + Mul<F, Output = Expr>,
+{
+    let mut result: [Expr; D] = core::array::from_fn(|_| Expr::zero());
+    for i in 0..D {
+        for j in 0..D {
+            let product = a[i].clone() * b[j].clone();
+            if i + j < D {
+                result[i + j] += product;
+            } else {
+                result[i + j - D] += product * F::w();
+            }
+        }
+    }
+    result
+}
+
+/// Multiply an element of `F[X] / (X^D - W)` by the monomial `X^i`, for `i < D`.
+pub(crate) fn mul_by_monomial<F, Expr, const D: usize>(a: &[Expr; D], i: usize) -> [Expr; D]
+where
+    F: BinomiallyExtendable<D>,
+    Expr: AbstractField + Mul<F, Output = Expr>,
+{
+    core::array::from_fn(|j| {
+        if j >= i {
+            a[j - i].clone()
+        } else {
+            a[j + D - i].clone() * F::w()
+        }
+    })
+}
+
+/// The coefficients of an extension field element, as laid out in trace columns.
+pub(crate) fn coeffs<F, const D: usize>(value: BinomialExtensionField<F, D>) -> [F; D]
+where
+    F: BinomiallyExtendable<D>,
+{
+    value.as_base_slice().try_into().unwrap()
+}
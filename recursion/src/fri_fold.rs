@@ -0,0 +1,532 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::Itertools;
+use p3_air::{
+    Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, Interaction, InteractionAir,
+    VirtualPairCol,
+};
+use p3_field::extension::{BinomialExtensionField, BinomiallyExtendable};
+use p3_field::{AbstractExtensionField, AbstractField, Field, TwoAdicField};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_util::reverse_bits_len;
+
+use crate::bus;
+use crate::extension::{binomial_mul, coeffs};
+
+/// An AIR for the folding done while checking FRI queries, as in `p3_fri::verifier::verify_query`,
+/// over a degree `D` binomial extension of the base field.
+///
+/// Each row folds the evaluation of one query once, and the rows of a query are consecutive, in the
+/// order of the commit phase rounds. The public values are the `num_fold_steps` folding challenges
+/// `beta`, followed by the final polynomial, each as `D` base field coefficients.
+///
+/// Only FRI configurations which fold by two in each round and end with a constant polynomial, i.e.
+/// with a `log_folding_arity` of 1 and a `log_final_poly_len` of 0, are supported.
+///
+/// This is a gadget, not a verifier: the queried indices, sibling values and reduced openings are
+/// unconstrained witnesses, which this AIR doesn't tie to the transcript, the commit phase Merkle
+/// trees or the opened values. Likewise, the first queried point of each query is not checked
+/// against the bit reversal of its index. `LinkedFriFoldAir` does the same folding with all of
+/// these tied to the other tables of a `RecursiveVerifier`.
+pub struct FriFoldAir<const D: usize> {
+    /// The number of commit phase rounds, i.e. how many times each query is folded.
+    pub num_fold_steps: usize,
+}
+
+/// The columns of one row of the FRI folding trace.
+pub struct FriFoldRow<T, const D: usize> {
+    /// Whether this row folds a query, as opposed to being padding.
+    pub is_real: T,
+    /// A one-hot encoding of the commit phase round which this row folds.
+    pub step: Vec<T>,
+    /// The least significant bit of `index`, i.e. which of the two folded points was queried.
+    pub index_bit: T,
+    /// The queried index in the codeword being folded.
+    pub index: T,
+    /// The queried point of the codeword's domain.
+    pub x: T,
+    /// The first of the two folded points, which is `x` or `-x` depending on `index_bit`.
+    pub x0: T,
+    /// The evaluation at `x`, after adding in the reduced opening of this height.
+    pub eval: [T; D],
+    /// The reduced opening of the codeword's height, which is added to the evaluation before folding.
+    pub reduced_opening: [T; D],
+    /// The evaluation at `-x`, opened from the commit phase codeword.
+    pub sibling: [T; D],
+    /// The evaluation at `x0`, i.e. either `eval` or `sibling`.
+    pub e0: [T; D],
+    /// The evaluation of the folded codeword at `x^2`.
+    pub folded: [T; D],
+}
+
+impl<T, const D: usize> FriFoldRow<T, D> {
+    pub const fn width(num_fold_steps: usize) -> usize {
+        5 + num_fold_steps + 5 * D
+    }
+}
+
+impl<T: Copy, const D: usize> FriFoldRow<T, D> {
+    pub fn from_slice(row: &[T], num_fold_steps: usize) -> Self {
+        debug_assert_eq!(row.len(), Self::width(num_fold_steps));
+        let mut iter = row.iter().copied();
+        let mut next = || iter.next().unwrap();
+        let is_real = next();
+        let step = (0..num_fold_steps).map(|_| next()).collect();
+        let index_bit = next();
+        let index = next();
+        let x = next();
+        let x0 = next();
+        Self {
+            is_real,
+            step,
+            index_bit,
+            index,
+            x,
+            x0,
+            eval: core::array::from_fn(|_| next()),
+            reduced_opening: core::array::from_fn(|_| next()),
+            sibling: core::array::from_fn(|_| next()),
+            e0: core::array::from_fn(|_| next()),
+            folded: core::array::from_fn(|_| next()),
+        }
+    }
+
+    fn append_to(&self, values: &mut Vec<T>) {
+        values.push(self.is_real);
+        values.extend(&self.step);
+        values.extend([self.index_bit, self.index, self.x, self.x0]);
+        values.extend(self.eval);
+        values.extend(self.reduced_opening);
+        values.extend(self.sibling);
+        values.extend(self.e0);
+        values.extend(self.folded);
+    }
+}
+
+impl<F, const D: usize> BaseAir<F> for FriFoldAir<D> {
+    fn width(&self) -> usize {
+        FriFoldRow::<F, D>::width(self.num_fold_steps)
+    }
+}
+
+impl<AB, const D: usize> Air<AB> for FriFoldAir<D>
+where
+    AB: AirBuilderWithPublicValues,
+    AB::F: BinomiallyExtendable<D>,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = FriFoldRow::<AB::Var, D>::from_slice(main.row_slice(0), self.num_fold_steps);
+        let next = FriFoldRow::<AB::Var, D>::from_slice(main.row_slice(1), self.num_fold_steps);
+        let public_values = builder.public_values();
+        let public_values = public_values.row_slice(0);
+        let (betas, final_poly) = public_values.split_at(self.num_fold_steps * D);
+
+        let beta: [AB::Expr; D] = core::array::from_fn(|i| {
+            local
+                .step
+                .iter()
+                .enumerate()
+                .map(|(step_index, &step)| step * betas[step_index * D + i])
+                .sum()
+        });
+        eval_fold(builder, &local, &next, beta);
+
+        // A fully folded query must match the final polynomial.
+        let last_step = local.step[self.num_fold_steps - 1];
+        for (&folded, &final_poly) in local.folded.iter().zip(final_poly) {
+            builder.when(last_step).assert_eq(folded, final_poly);
+        }
+    }
+}
+
+/// The constraints of folding one row, with the folding challenge `beta` of the row's round, which
+/// `FriFoldAir` and `LinkedFriFoldAir` share.
+fn eval_fold<AB, const D: usize>(
+    builder: &mut AB,
+    local: &FriFoldRow<AB::Var, D>,
+    next: &FriFoldRow<AB::Var, D>,
+    beta: [AB::Expr; D],
+) where
+    AB: AirBuilder,
+    AB::F: BinomiallyExtendable<D>,
+{
+    let first_step = local.step[0];
+    let last_step = *local.step.last().unwrap();
+
+    builder.assert_bool(local.is_real);
+    for &step in &local.step {
+        builder.assert_bool(step);
+    }
+    builder.assert_eq(
+        local.step.iter().map(|&step| step.into()).sum::<AB::Expr>(),
+        local.is_real,
+    );
+    builder.when_first_row().assert_one(first_step);
+    builder
+        .when_transition()
+        .when(next.is_real)
+        .assert_one(local.is_real);
+    // The trace can't end in the middle of a query.
+    builder.when_last_row().assert_eq(local.is_real, last_step);
+
+    // Select the two evaluations to interpolate, ordered by the parity of the queried index.
+    builder.assert_bool(local.index_bit);
+    builder.assert_eq(
+        local.x0,
+        local.x * (AB::Expr::one() - local.index_bit.into() * AB::F::two()),
+    );
+    let e0 = local.e0.map(Into::into);
+    let e1: [AB::Expr; D] =
+        core::array::from_fn(|i| local.eval[i] + local.sibling[i] - local.e0[i]);
+    for i in 0..D {
+        builder.assert_eq(
+            local.e0[i],
+            local.eval[i] + local.index_bit * (local.sibling[i] - local.eval[i]),
+        );
+    }
+
+    // Interpolate the line through (x0, e0) and (-x0, e1), and evaluate it at beta:
+    //     folded = e0 + (beta - x0) (e1 - e0) / (-2 x0)
+    let mut beta_minus_x0 = beta;
+    beta_minus_x0[0] -= local.x0.into();
+    let e_diff: [AB::Expr; D] = core::array::from_fn(|i| e1[i].clone() - e0[i].clone());
+    let slope_numerator = binomial_mul::<AB::F, _, D>(&beta_minus_x0, &e_diff);
+    let minus_two_x0 = local.x0 * -AB::F::two();
+    for i in 0..D {
+        builder.assert_eq(
+            local.folded[i] * minus_two_x0.clone(),
+            e0[i].clone() * minus_two_x0.clone() + slope_numerator[i].clone(),
+        );
+    }
+
+    // A query starts from its reduced opening of the largest height.
+    for i in 0..D {
+        builder
+            .when(first_step)
+            .assert_eq(local.eval[i], local.reduced_opening[i]);
+    }
+
+    // Within a query, move on to the next commit phase round.
+    for (&step, &next_step) in local.step.iter().zip(&next.step[1..]) {
+        builder.when(step).assert_one(next_step);
+    }
+    builder
+        .when(last_step)
+        .assert_eq(next.step[0], next.is_real);
+    let mut when_folding_again = builder.when(local.is_real - last_step);
+    when_folding_again.assert_eq(next.x, local.x * local.x);
+    when_folding_again.assert_eq(local.index, next.index * AB::F::two() + local.index_bit);
+    for i in 0..D {
+        when_folding_again.assert_eq(next.eval[i], local.folded[i] + next.reduced_opening[i]);
+    }
+}
+
+/// One FRI query, with the values `FriFoldAir` takes as witnesses to fold it.
+#[derive(Clone, Debug)]
+pub struct FriFoldQuery<EF> {
+    /// The queried index in the largest codeword.
+    pub index: usize,
+    /// The reduced opening of each input codeword at the queried location, indexed by log height,
+    /// as passed to `p3_fri::verifier::verify_challenges`.
+    pub reduced_openings: [EF; 32],
+    /// For each commit phase round, the evaluation which is folded together with the queried one.
+    pub siblings: Vec<EF>,
+}
+
+/// Generate the trace of `FriFoldAir`, folding each query with the given challenges `betas`, one
+/// per commit phase round, as `p3_fri::verifier` does.
+pub fn generate_fri_fold_trace<F, const D: usize>(
+    log_blowup: usize,
+    betas: &[BinomialExtensionField<F, D>],
+    queries: &[FriFoldQuery<BinomialExtensionField<F, D>>],
+) -> RowMajorMatrix<F>
+where
+    F: TwoAdicField + BinomiallyExtendable<D>,
+{
+    let num_fold_steps = betas.len();
+    let width = FriFoldRow::<F, D>::width(num_fold_steps);
+    let num_rows = queries.len() * num_fold_steps;
+    let mut values = Vec::with_capacity(num_rows.next_power_of_two() * width);
+    for query in queries {
+        for row in fold_query(log_blowup, betas, query) {
+            row.append_to(&mut values);
+        }
+    }
+    values.resize(num_rows.next_power_of_two() * width, F::zero());
+
+    RowMajorMatrix::new(values, width)
+}
+
+/// The rows folding one query, one per commit phase round.
+pub(crate) fn fold_query<F, const D: usize>(
+    log_blowup: usize,
+    betas: &[BinomialExtensionField<F, D>],
+    query: &FriFoldQuery<BinomialExtensionField<F, D>>,
+) -> Vec<FriFoldRow<F, D>>
+where
+    F: TwoAdicField + BinomiallyExtendable<D>,
+{
+    let num_fold_steps = betas.len();
+    assert!(
+        num_fold_steps > 0,
+        "expected at least one commit phase round"
+    );
+    let log_max_height = num_fold_steps + log_blowup;
+    assert!(log_max_height <= F::TWO_ADICITY);
+    assert_eq!(
+        query.siblings.len(),
+        num_fold_steps,
+        "expected a sibling per commit phase round"
+    );
+
+    let mut index = query.index;
+    let mut x = F::two_adic_generator(log_max_height)
+        .exp_u64(reverse_bits_len(index, log_max_height) as u64);
+    let mut folded = BinomialExtensionField::<F, D>::zero();
+    let mut rows = Vec::with_capacity(num_fold_steps);
+    for (step_index, (&sibling, &beta)) in query.siblings.iter().zip(betas).enumerate() {
+        let reduced_opening = query.reduced_openings[log_max_height - step_index];
+        let eval = folded + reduced_opening;
+        let index_bit = index & 1;
+        let (x0, e0, e1) = if index_bit == 0 {
+            (x, eval, sibling)
+        } else {
+            (-x, sibling, eval)
+        };
+        folded = e0 + (beta - x0) * (e1 - e0) * (-x0.double()).inverse();
+
+        let mut step_one_hot = vec![F::zero(); num_fold_steps];
+        step_one_hot[step_index] = F::one();
+        rows.push(FriFoldRow {
+            is_real: F::one(),
+            step: step_one_hot,
+            index_bit: F::from_canonical_usize(index_bit),
+            index: F::from_canonical_usize(index),
+            x,
+            x0,
+            eval: coeffs(eval),
+            reduced_opening: coeffs(reduced_opening),
+            sibling: coeffs(sibling),
+            e0: coeffs(e0),
+            folded: coeffs(folded),
+        });
+
+        index >>= 1;
+        x = x.square();
+    }
+    rows
+}
+
+/// The public values of `FriFoldAir`: the folding challenges, followed by the constant which the
+/// queries must fold to, i.e. the final polynomial.
+pub fn fri_fold_public_values<F, const D: usize>(
+    betas: &[BinomialExtensionField<F, D>],
+    final_poly: BinomialExtensionField<F, D>,
+) -> Vec<F>
+where
+    F: Field + BinomiallyExtendable<D>,
+{
+    betas
+        .iter()
+        .chain([&final_poly])
+        .flat_map(|value| value.as_base_slice().to_vec())
+        .collect()
+}
+
+/// `FriFoldAir`, with its witnesses tied to the other tables of a `RecursiveVerifier` instead of
+/// being taken as given.
+///
+/// Each row reads its round's folding challenge `beta` from the transcript, and sends the opened
+/// pair of evaluations as a leaf of its round's commit phase tree, whose leaf index is the folded
+/// index. The first row of a query receives the queried index and point from the `QUERY` bus, and
+/// sends them on with the query's reduced opening, to be matched against the `ReducedOpeningAir`.
+/// Only the first round has a reduced opening, as every matrix opened by a single STARK has the
+/// largest height. The last row of a query sends its folded value as the final polynomial.
+pub struct LinkedFriFoldAir<const D: usize> {
+    num_fold_steps: usize,
+    /// The position in the transcript of each round's folding challenge.
+    beta_positions: Vec<usize>,
+    /// The tree of the first commit phase round; the following rounds' trees come after it.
+    first_tree: usize,
+}
+
+impl<const D: usize> LinkedFriFoldAir<D> {
+    pub fn new(beta_positions: Vec<usize>, first_tree: usize) -> Self {
+        assert!(
+            !beta_positions.is_empty(),
+            "expected at least one commit phase round"
+        );
+        Self {
+            num_fold_steps: beta_positions.len(),
+            beta_positions,
+            first_tree,
+        }
+    }
+
+    fn beta_col(&self, i: usize) -> usize {
+        FriFoldRow::<(), D>::width(self.num_fold_steps) + i
+    }
+
+    fn row_cols(&self) -> FriFoldRow<usize, D> {
+        let cols = (0..FriFoldRow::<(), D>::width(self.num_fold_steps)).collect_vec();
+        FriFoldRow::from_slice(&cols, self.num_fold_steps)
+    }
+
+    /// Generate the trace, folding each query with the challenges `betas`. The reduced openings of
+    /// each query must be zero below the largest height.
+    pub fn generate_trace<F>(
+        &self,
+        log_blowup: usize,
+        betas: &[BinomialExtensionField<F, D>],
+        queries: &[FriFoldQuery<BinomialExtensionField<F, D>>],
+    ) -> RowMajorMatrix<F>
+    where
+        F: TwoAdicField + BinomiallyExtendable<D>,
+    {
+        assert_eq!(betas.len(), self.num_fold_steps);
+        let width = <Self as BaseAir<F>>::width(self);
+        let num_rows = queries.len() * self.num_fold_steps;
+        let mut values = Vec::with_capacity(num_rows.next_power_of_two() * width);
+        for query in queries {
+            let log_max_height = self.num_fold_steps + log_blowup;
+            assert!(
+                query.reduced_openings[..log_max_height]
+                    .iter()
+                    .all(|ro| ro.is_zero()),
+                "expected a reduced opening of the largest height only"
+            );
+            for (row, &beta) in fold_query(log_blowup, betas, query).iter().zip(betas) {
+                row.append_to(&mut values);
+                values.extend(coeffs(beta));
+            }
+        }
+        values.resize(num_rows.next_power_of_two() * width, F::zero());
+
+        RowMajorMatrix::new(values, width)
+    }
+}
+
+impl<F, const D: usize> BaseAir<F> for LinkedFriFoldAir<D> {
+    fn width(&self) -> usize {
+        FriFoldRow::<F, D>::width(self.num_fold_steps) + D
+    }
+}
+
+impl<F: Field, const D: usize> InteractionAir<F> for LinkedFriFoldAir<D> {
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let c = self.row_cols();
+        let first_step = c.step[0];
+        let last_step = c.step[self.num_fold_steps - 1];
+        let is_real = VirtualPairCol::single_main(c.is_real);
+
+        let mut sends = Vec::new();
+        for i in 0..D {
+            let position = c
+                .step
+                .iter()
+                .zip(&self.beta_positions)
+                .map(|(&step, &position)| (step, F::from_canonical_usize(position + i)))
+                .collect();
+            sends.push(bus::message(
+                bus::TRANSCRIPT,
+                vec![
+                    VirtualPairCol::new_main(position, F::zero()),
+                    VirtualPairCol::single_main(self.beta_col(i)),
+                    VirtualPairCol::one(),
+                ],
+                is_real.clone(),
+            ));
+        }
+
+        sends.push(bus::message(
+            bus::REDUCED_OPENING,
+            [c.index, c.x]
+                .into_iter()
+                .chain(c.reduced_opening)
+                .map(VirtualPairCol::single_main)
+                .collect(),
+            VirtualPairCol::single_main(first_step),
+        ));
+
+        // The opened leaf of the round's tree is `(e0, e1)`, at the folded index.
+        let tree = VirtualPairCol::new_main(
+            c.step
+                .iter()
+                .enumerate()
+                .map(|(k, &step)| (step, F::from_canonical_usize(self.first_tree + k)))
+                .collect(),
+            F::zero(),
+        );
+        let half = F::two().inverse();
+        let leaf_index =
+            VirtualPairCol::new_main(vec![(c.index, half), (c.index_bit, -half)], F::zero());
+        for i in 0..D {
+            let e1 = VirtualPairCol::new_main(
+                vec![
+                    (c.eval[i], F::one()),
+                    (c.sibling[i], F::one()),
+                    (c.e0[i], F::neg_one()),
+                ],
+                F::zero(),
+            );
+            for (column, value) in [(i, VirtualPairCol::single_main(c.e0[i])), (D + i, e1)] {
+                sends.push(bus::message(
+                    bus::LEAF,
+                    vec![
+                        tree.clone(),
+                        leaf_index.clone(),
+                        VirtualPairCol::constant(F::from_canonical_usize(column)),
+                        value,
+                    ],
+                    is_real.clone(),
+                ));
+            }
+        }
+
+        sends.push(bus::message(
+            bus::FINAL_POLY,
+            c.folded.map(VirtualPairCol::single_main).to_vec(),
+            VirtualPairCol::single_main(last_step),
+        ));
+        sends
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let c = self.row_cols();
+        vec![bus::message(
+            bus::QUERY,
+            vec![
+                VirtualPairCol::single_main(c.index),
+                VirtualPairCol::single_main(c.x),
+            ],
+            VirtualPairCol::single_main(c.step[0]),
+        )]
+    }
+}
+
+impl<AB, const D: usize> Air<AB> for LinkedFriFoldAir<D>
+where
+    AB: AirBuilder,
+    AB::F: BinomiallyExtendable<D>,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+        let row_width = FriFoldRow::<(), D>::width(self.num_fold_steps);
+        let beta: [AB::Expr; D] = core::array::from_fn(|i| local[self.beta_col(i)].into());
+        let local = FriFoldRow::<AB::Var, D>::from_slice(&local[..row_width], self.num_fold_steps);
+        let next = FriFoldRow::<AB::Var, D>::from_slice(&next[..row_width], self.num_fold_steps);
+        eval_fold(builder, &local, &next, beta);
+
+        // Only the first round has a reduced opening to add in.
+        for &reduced_opening in &local.reduced_opening {
+            builder
+                .when(local.is_real - local.step[0])
+                .assert_zero(reduced_opening);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! A recursive verifier of `p3_uni_stark` proofs, as tables to be proven with `p3_multi_stark`, and
+//! AIR gadgets for pieces of STARK verification.
+//!
+//! `RecursiveVerifier` arithmetizes everything `p3_uni_stark::verify` does for a supported
+//! configuration: the Poseidon2 permutations of the challenger and of Merkle path checks, the
+//! proof of work and query indices, the reduction of opened values into FRI inputs, the folding of
+//! FRI queries, and the out-of-domain constraint check. Its tables communicate over the buses in
+//! `bus`, so each value one table reads from another is checked by the LogUp argument of
+//! `p3_multi_stark`.
+//!
+//! `FriFoldAir` is the folding of FRI queries on its own, as a gadget whose witnesses are taken as
+//! given.
+
+#![no_std]
+
+extern crate alloc;
+
+mod bus;
+mod challenger;
+mod columns;
+mod extension;
+mod fri_fold;
+mod merkle;
+mod ood;
+mod poseidon2;
+mod query;
+mod reduced_opening;
+mod verifier;
+
+pub use challenger::*;
+pub use fri_fold::*;
+pub use merkle::*;
+pub use ood::*;
+pub use poseidon2::*;
+pub use query::*;
+pub use reduced_opening::*;
+pub use verifier::*;
+
+/// The width of the Poseidon2 permutation used by the challenger and Merkle trees.
+pub(crate) const WIDTH: usize = 16;
+
+/// The number of field elements in a digest.
+pub(crate) const DIGEST_ELEMS: usize = 8;
@@ -0,0 +1,530 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::Itertools;
+use p3_air::{Air, AirBuilder, BaseAir, Interaction, InteractionAir, VirtualPairCol};
+use p3_field::{AbstractField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_symmetric::Permutation;
+
+use crate::columns::ColumnAllocator;
+use crate::{bus, DIGEST_ELEMS, WIDTH};
+
+/// The shape of a Merkle tree of one matrix, committed with a cap of height zero.
+#[derive(Copy, Clone, Debug)]
+pub struct TreeShape {
+    /// The log of the matrix's height, which is the length of a Merkle path.
+    pub log_height: usize,
+    pub leaf_width: usize,
+    /// The position of the root's first element in the transcript.
+    pub root_position: usize,
+}
+
+impl TreeShape {
+    /// The number of permutations it takes to hash a leaf.
+    fn num_chunks(&self) -> usize {
+        self.leaf_width.div_ceil(DIGEST_ELEMS)
+    }
+
+    /// The number of elements in a leaf's last chunk.
+    fn last_chunk_len(&self) -> usize {
+        self.leaf_width - DIGEST_ELEMS * (self.num_chunks() - 1)
+    }
+}
+
+/// An AIR for opening paths of Merkle trees, as in `p3_merkle_tree::FieldMerkleTreeMmcs` with a
+/// `PaddingFreeSponge` of rate `DIGEST_ELEMS` for leaves and a `TruncatedPermutation` for
+/// compressions, both over a permutation of width `WIDTH = 2 * DIGEST_ELEMS`.
+///
+/// Each path is a run of rows, each doing one permutation: first the absorption of each chunk of
+/// the leaf, then a compression per layer, ending at the root. The leaf's elements are received on
+/// the `LEAF` bus as `(tree, leaf_index, column, value)`, the root is checked against the
+/// transcript at the tree's root position, and each permutation is looked up on the `POSEIDON2`
+/// bus.
+pub struct MerklePathAir {
+    trees: Vec<TreeShape>,
+    cols: MerkleCols,
+    width: usize,
+}
+
+struct MerkleCols {
+    is_absorb: usize,
+    is_compress: usize,
+    /// Whether this row absorbs the first chunk of a leaf, starting a path.
+    is_first: usize,
+    is_last_chunk: usize,
+    /// Whether this row computes the root, ending a path.
+    is_root: usize,
+    /// A one-hot encoding of the tree this path is in.
+    tree: Vec<usize>,
+    /// The leaf index on absorbing rows, and the index of the node being hashed on compressing
+    /// rows.
+    index: usize,
+    /// The low bit of `index` on compressing rows, i.e. whether the node is a right child.
+    bit: usize,
+    /// On compressing rows, the number of compressions left on the path, including this one.
+    depth: usize,
+    /// On absorbing rows, the column of the leaf's matrix the chunk starts at.
+    column: usize,
+    state_in: [usize; WIDTH],
+    state_out: [usize; WIDTH],
+    /// On absorbing rows, whether each of the first `DIGEST_ELEMS` inputs is an element of the leaf.
+    lane_valid: [usize; DIGEST_ELEMS],
+    /// How many times each element of the leaf is received.
+    multiplicity: [usize; DIGEST_ELEMS],
+}
+
+/// A path to open with `MerklePathAir`.
+#[derive(Clone, Debug)]
+pub struct MerklePath<F> {
+    pub tree: usize,
+    pub leaf_index: usize,
+    pub leaf: Vec<F>,
+    /// The sibling of each node on the path, from the leaf's up.
+    pub siblings: Vec<[F; DIGEST_ELEMS]>,
+    /// How many times each of the leaf's elements is received.
+    pub leaf_multiplicity: usize,
+}
+
+impl MerklePathAir {
+    pub fn new(trees: Vec<TreeShape>) -> Self {
+        assert!(!trees.is_empty());
+        for tree in &trees {
+            assert!(tree.log_height > 0, "expected trees of at least two leaves");
+            assert!(tree.leaf_width > 0, "expected nonempty leaves");
+        }
+
+        let mut alloc = ColumnAllocator::default();
+        let cols = MerkleCols {
+            is_absorb: alloc.col(),
+            is_compress: alloc.col(),
+            is_first: alloc.col(),
+            is_last_chunk: alloc.col(),
+            is_root: alloc.col(),
+            tree: alloc.vec(trees.len()),
+            index: alloc.col(),
+            bit: alloc.col(),
+            depth: alloc.col(),
+            column: alloc.col(),
+            state_in: alloc.array(),
+            state_out: alloc.array(),
+            lane_valid: alloc.array(),
+            multiplicity: alloc.array(),
+        };
+        Self {
+            trees,
+            cols,
+            width: alloc.width(),
+        }
+    }
+
+    /// Generate the trace opening `paths`, and return it along with the inputs of the permutations
+    /// it does.
+    pub fn generate_trace<F, P>(
+        &self,
+        paths: &[MerklePath<F>],
+        perm: &P,
+    ) -> (RowMajorMatrix<F>, Vec<[F; WIDTH]>)
+    where
+        F: Field,
+        P: Permutation<[F; WIDTH]>,
+    {
+        let c = &self.cols;
+        let mut rows: Vec<Vec<F>> = vec![];
+        for path in paths {
+            let shape = &self.trees[path.tree];
+            assert_eq!(path.leaf.len(), shape.leaf_width);
+            assert_eq!(path.siblings.len(), shape.log_height);
+            assert!(path.leaf_index >> shape.log_height == 0);
+
+            let new_row = || {
+                let mut row = vec![F::zero(); self.width];
+                row[c.tree[path.tree]] = F::one();
+                row
+            };
+            let mut state = [F::zero(); WIDTH];
+            let num_chunks = shape.num_chunks();
+            for (chunk_index, chunk) in path.leaf.chunks(DIGEST_ELEMS).enumerate() {
+                let mut row = new_row();
+                row[c.is_absorb] = F::one();
+                row[c.is_first] = F::from_bool(chunk_index == 0);
+                row[c.is_last_chunk] = F::from_bool(chunk_index + 1 == num_chunks);
+                row[c.index] = F::from_canonical_usize(path.leaf_index);
+                row[c.column] = F::from_canonical_usize(chunk_index * DIGEST_ELEMS);
+                state[..chunk.len()].copy_from_slice(chunk);
+                for lane in 0..chunk.len() {
+                    row[c.lane_valid[lane]] = F::one();
+                    row[c.multiplicity[lane]] = F::from_canonical_usize(path.leaf_multiplicity);
+                }
+                self.permute_row(&mut row, &mut state, perm);
+                rows.push(row);
+            }
+
+            let mut index = path.leaf_index;
+            for (layer, sibling) in path.siblings.iter().enumerate() {
+                let mut row = new_row();
+                row[c.is_compress] = F::one();
+                row[c.is_root] = F::from_bool(layer + 1 == shape.log_height);
+                row[c.index] = F::from_canonical_usize(index);
+                row[c.bit] = F::from_canonical_usize(index & 1);
+                row[c.depth] = F::from_canonical_usize(shape.log_height - layer);
+                let node: [F; DIGEST_ELEMS] = state[..DIGEST_ELEMS].try_into().unwrap();
+                let (left, right) = if index & 1 == 0 {
+                    (node, *sibling)
+                } else {
+                    (*sibling, node)
+                };
+                state[..DIGEST_ELEMS].copy_from_slice(&left);
+                state[DIGEST_ELEMS..].copy_from_slice(&right);
+                self.permute_row(&mut row, &mut state, perm);
+                rows.push(row);
+                index >>= 1;
+            }
+        }
+
+        let permutation_inputs = rows
+            .iter()
+            .map(|row| c.state_in.map(|col| row[col]))
+            .collect();
+        let num_rows = rows.len().next_power_of_two().max(2);
+        let mut values = rows.concat();
+        values.resize(num_rows * self.width, F::zero());
+        (RowMajorMatrix::new(values, self.width), permutation_inputs)
+    }
+
+    fn permute_row<F: Field, P: Permutation<[F; WIDTH]>>(
+        &self,
+        row: &mut [F],
+        state: &mut [F; WIDTH],
+        perm: &P,
+    ) {
+        for (&col, &value) in self.cols.state_in.iter().zip(state.iter()) {
+            row[col] = value;
+        }
+        perm.permute_mut(state);
+        for (&col, &value) in self.cols.state_out.iter().zip(state.iter()) {
+            row[col] = value;
+        }
+    }
+}
+
+impl<F> BaseAir<F> for MerklePathAir {
+    fn width(&self) -> usize {
+        self.width
+    }
+}
+
+impl<F: Field> InteractionAir<F> for MerklePathAir {
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let c = &self.cols;
+        let permutation = bus::message(
+            bus::POSEIDON2,
+            c.state_in
+                .iter()
+                .chain(&c.state_out)
+                .map(|&col| VirtualPairCol::single_main(col))
+                .collect(),
+            VirtualPairCol::sum_main(vec![c.is_absorb, c.is_compress]),
+        );
+        let roots = (0..DIGEST_ELEMS).map(|j| {
+            let position = VirtualPairCol::new_main(
+                c.tree
+                    .iter()
+                    .zip(&self.trees)
+                    .map(|(&col, shape)| (col, F::from_canonical_usize(shape.root_position + j)))
+                    .collect(),
+                F::zero(),
+            );
+            bus::message(
+                bus::TRANSCRIPT,
+                vec![
+                    position,
+                    VirtualPairCol::single_main(c.state_out[j]),
+                    VirtualPairCol::constant(F::zero()),
+                ],
+                VirtualPairCol::single_main(c.is_root),
+            )
+        });
+        [permutation].into_iter().chain(roots).collect()
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let c = &self.cols;
+        (0..DIGEST_ELEMS)
+            .map(|j| {
+                bus::message(
+                    bus::LEAF,
+                    vec![
+                        self.tree_id(),
+                        VirtualPairCol::single_main(c.index),
+                        VirtualPairCol::new_main(
+                            vec![(c.column, F::one())],
+                            F::from_canonical_usize(j),
+                        ),
+                        VirtualPairCol::single_main(c.state_in[j]),
+                    ],
+                    VirtualPairCol::single_main(c.multiplicity[j]),
+                )
+            })
+            .collect()
+    }
+}
+
+impl MerklePathAir {
+    /// The index of the path's tree, on real rows.
+    fn tree_id<F: Field>(&self) -> VirtualPairCol<F> {
+        VirtualPairCol::new_main(
+            self.cols
+                .tree
+                .iter()
+                .enumerate()
+                .map(|(t, &col)| (col, F::from_canonical_usize(t)))
+                .collect(),
+            F::zero(),
+        )
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for MerklePathAir {
+    fn eval(&self, builder: &mut AB) {
+        let c = &self.cols;
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+
+        let is_absorb = local[c.is_absorb];
+        let is_compress = local[c.is_compress];
+        let is_first = local[c.is_first];
+        let is_last_chunk = local[c.is_last_chunk];
+        let is_root = local[c.is_root];
+        let tree = c.tree.iter().map(|&col| local[col]).collect_vec();
+        let bit = local[c.bit];
+        let state_in = c.state_in.map(|col| local[col]);
+        let state_out = c.state_out.map(|col| local[col]);
+        let lane_valid = c.lane_valid.map(|col| local[col]);
+        let is_real = is_absorb + is_compress;
+
+        for flag in [
+            is_absorb,
+            is_compress,
+            is_first,
+            is_last_chunk,
+            is_root,
+            bit,
+        ] {
+            builder.assert_bool(flag);
+        }
+        for &t in &tree {
+            builder.assert_bool(t);
+        }
+        builder.assert_bool(is_real.clone());
+        builder.when(is_first).assert_one(is_absorb);
+        builder.when(is_last_chunk).assert_one(is_absorb);
+        builder.when(is_root).assert_one(is_compress);
+        builder.assert_eq(
+            tree.iter().map(|&t| t.into()).sum::<AB::Expr>(),
+            is_real.clone(),
+        );
+
+        // Every lane of a chunk but the last is part of the leaf, and the last chunk's first lanes
+        // are, up to the leaf's width.
+        for (j, (&valid, &multiplicity)) in lane_valid
+            .iter()
+            .zip(&c.multiplicity.map(|col| local[col]))
+            .enumerate()
+        {
+            let valid_in_last_chunk = tree
+                .iter()
+                .zip(&self.trees)
+                .filter(|(_, shape)| j < shape.last_chunk_len())
+                .map(|(&t, _)| t.into())
+                .sum::<AB::Expr>();
+            builder.assert_eq(
+                valid,
+                is_absorb - is_last_chunk + is_last_chunk * valid_in_last_chunk,
+            );
+            builder
+                .when(AB::Expr::one() - valid)
+                .assert_zero(multiplicity);
+        }
+
+        // A leaf's first chunk starts at the first column, from the zero state, and its last chunk
+        // is at the end of the leaf.
+        let mut when_first = builder.when(is_first);
+        when_first.assert_zero(local[c.column]);
+        for j in 0..WIDTH {
+            let is_input = if j < DIGEST_ELEMS {
+                lane_valid[j].into()
+            } else {
+                AB::Expr::zero()
+            };
+            when_first
+                .when(AB::Expr::one() - is_input)
+                .assert_zero(state_in[j]);
+        }
+        let last_chunk_column = tree
+            .iter()
+            .zip(&self.trees)
+            .map(|(&t, shape)| {
+                t * AB::F::from_canonical_usize(DIGEST_ELEMS * (shape.num_chunks() - 1))
+            })
+            .sum::<AB::Expr>();
+        builder
+            .when(is_last_chunk)
+            .assert_eq(local[c.column], last_chunk_column);
+
+        // The root is the last compression of a path, of a node with index zero or one.
+        builder.when(is_root).assert_one(local[c.depth]);
+        builder.when(is_root).assert_eq(local[c.index], bit);
+
+        builder.when_first_row().assert_zero(is_compress);
+        builder.when_first_row().assert_eq(is_absorb, is_first);
+        builder.when_last_row().assert_zero(is_absorb);
+        builder.when_last_row().assert_eq(is_compress, is_root);
+
+        let mut when_transition = builder.when_transition();
+        // Absorption continues until the last chunk, then compression until the root. A new path
+        // may start after a root or after padding.
+        let continues_absorbing = next[c.is_absorb] - next[c.is_first];
+        when_transition.assert_eq(continues_absorbing.clone(), is_absorb - is_last_chunk);
+        when_transition.assert_eq(next[c.is_compress], is_last_chunk + is_compress - is_root);
+        let continues_path = next[c.is_absorb] + next[c.is_compress] - next[c.is_first];
+        for (&t, &next_col) in tree.iter().zip(&c.tree) {
+            when_transition
+                .when(continues_path.clone())
+                .assert_eq(next[next_col], t);
+        }
+
+        // The next chunk of a leaf carries over the state, except for the lanes it overwrites.
+        let mut when_absorbing = when_transition.when(continues_absorbing);
+        when_absorbing.assert_eq(
+            next[c.column],
+            local[c.column] + AB::F::from_canonical_usize(DIGEST_ELEMS),
+        );
+        when_absorbing.assert_eq(next[c.index], local[c.index]);
+        for j in 0..WIDTH {
+            let carried = if j < DIGEST_ELEMS {
+                AB::Expr::one() - next[c.lane_valid[j]]
+            } else {
+                AB::Expr::one()
+            };
+            when_absorbing
+                .when(carried)
+                .assert_eq(next[c.state_in[j]], state_out[j]);
+        }
+
+        // The first compression is of the leaf's node, at the bottom of the tree.
+        let tree_height = tree
+            .iter()
+            .zip(&self.trees)
+            .map(|(&t, shape)| t * AB::F::from_canonical_usize(shape.log_height))
+            .sum::<AB::Expr>();
+        let mut when_leaf_hashed = when_transition.when(is_last_chunk);
+        when_leaf_hashed.assert_eq(next[c.depth], tree_height);
+        when_leaf_hashed.assert_eq(next[c.index], local[c.index]);
+
+        // Each compression but the root's moves up to the parent node.
+        let mut when_moving_up = when_transition.when(is_compress - is_root);
+        when_moving_up.assert_eq(local[c.index], next[c.index] * AB::F::two() + bit.into());
+        when_moving_up.assert_eq(next[c.depth], local[c.depth] - AB::Expr::one());
+
+        // A compression puts the digest of the previous row on the side given by its bit.
+        let next_bit = next[c.bit];
+        for j in 0..DIGEST_ELEMS {
+            let left = next[c.state_in[j]];
+            let right = next[c.state_in[DIGEST_ELEMS + j]];
+            when_transition.when(next[c.is_compress]).assert_zero(
+                (AB::Expr::one() - next_bit) * (left - state_out[j])
+                    + next_bit * (right - state_out[j]),
+            );
+        }
+    }
+}
+
+/// Reconstruct the sibling of each node on the paths of a multi-opening of a tree of height
+/// `2^log_height`, given the digests of the opened leaves and the siblings of the multi-opening
+/// proof, in the order `FieldMerkleTreeMmcs::verify_multi_batch` consumes them.
+///
+/// Returns, for each layer from the leaves up, the digests of the nodes of that layer which are on
+/// an opened path or siblings of one.
+pub(crate) fn multi_opening_layers<F, P>(
+    leaves: BTreeMap<usize, [F; DIGEST_ELEMS]>,
+    log_height: usize,
+    proof: &[[F; DIGEST_ELEMS]],
+    perm: &P,
+) -> Vec<BTreeMap<usize, [F; DIGEST_ELEMS]>>
+where
+    F: Field,
+    P: Permutation<[F; WIDTH]>,
+{
+    let mut siblings = proof.iter();
+    let mut nodes = leaves;
+    let mut layers = vec![];
+    for _ in 0..log_height {
+        let mut layer = nodes.clone();
+        let mut next_nodes = BTreeMap::new();
+        let mut nodes_iter = nodes.into_iter().peekable();
+        while let Some((node, digest)) = nodes_iter.next() {
+            let (left, right) = if node & 1 == 0 {
+                let right = match nodes_iter.next_if(|&(next, _)| next == node + 1) {
+                    Some((_, right)) => right,
+                    None => {
+                        let right = *siblings.next().expect("multi-opening proof too short");
+                        layer.insert(node + 1, right);
+                        right
+                    }
+                };
+                (digest, right)
+            } else {
+                let left = *siblings.next().expect("multi-opening proof too short");
+                layer.insert(node - 1, left);
+                (left, digest)
+            };
+            next_nodes.insert(node >> 1, compress(perm, left, right));
+        }
+        layers.push(layer);
+        nodes = next_nodes;
+    }
+    assert!(siblings.next().is_none(), "multi-opening proof too long");
+    layers
+}
+
+/// The path of `leaf_index` through the layers returned by `multi_opening_layers`.
+pub(crate) fn path_siblings<F: Copy>(
+    layers: &[BTreeMap<usize, [F; DIGEST_ELEMS]>],
+    leaf_index: usize,
+) -> Vec<[F; DIGEST_ELEMS]> {
+    layers
+        .iter()
+        .enumerate()
+        .map(|(layer, nodes)| nodes[&((leaf_index >> layer) ^ 1)])
+        .collect()
+}
+
+/// Hash a leaf with a `PaddingFreeSponge` of rate `DIGEST_ELEMS`.
+pub(crate) fn hash_leaf<F, P>(perm: &P, leaf: &[F]) -> [F; DIGEST_ELEMS]
+where
+    F: Field,
+    P: Permutation<[F; WIDTH]>,
+{
+    let mut state = [F::zero(); WIDTH];
+    for chunk in leaf.chunks(DIGEST_ELEMS) {
+        state[..chunk.len()].copy_from_slice(chunk);
+        perm.permute_mut(&mut state);
+    }
+    state[..DIGEST_ELEMS].try_into().unwrap()
+}
+
+fn compress<F, P>(perm: &P, left: [F; DIGEST_ELEMS], right: [F; DIGEST_ELEMS]) -> [F; DIGEST_ELEMS]
+where
+    F: Field,
+    P: Permutation<[F; WIDTH]>,
+{
+    let mut state = [F::zero(); WIDTH];
+    state[..DIGEST_ELEMS].copy_from_slice(&left);
+    state[DIGEST_ELEMS..].copy_from_slice(&right);
+    perm.permute_mut(&mut state);
+    state[..DIGEST_ELEMS].try_into().unwrap()
+}
@@ -0,0 +1,584 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::Itertools;
+use p3_air::{Air, AirBuilder, BaseAir, Interaction, InteractionAir, VirtualPairCol};
+use p3_field::extension::{BinomialExtensionField, BinomiallyExtendable};
+use p3_field::{AbstractExtensionField, AbstractField, Field, TwoAdicField};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_uni_stark::{ConstraintProgram, Entry, Instruction, Register};
+use p3_util::reverse_bits_len;
+
+use crate::bus;
+use crate::columns::ColumnAllocator;
+use crate::extension::{binomial_mul, coeffs, mul_by_monomial};
+
+/// An AIR for the out-of-domain check of `p3_uni_stark::verify`: that the AIR's constraints,
+/// combined with `alpha` and evaluated on the trace's openings at `zeta`, equal the quotient's
+/// opening times the vanishing polynomial of the trace domain.
+///
+/// The constraints are given as a `ConstraintProgram` over the main trace, whose base field
+/// registers are evaluated at `zeta` in the extension field. Sums and constant multiples of
+/// registers are linear combinations of columns, and each product of two such registers gets its
+/// own columns, so that every constraint has degree at most two.
+///
+/// The first row is the only real one; the others repeat it. It reads `alpha` and `zeta` from the
+/// transcript, and receives each claimed opening on the `OPENING` bus and the final polynomial of
+/// FRI on the `FINAL_POLY` bus, once per query, matching what the other tables send.
+pub struct OodCheckAir<F, const D: usize> {
+    program: ConstraintProgram<F>,
+    window_size: usize,
+    trace_width: usize,
+    degree_bits: usize,
+    log_quotient_degree: usize,
+    alpha_position: usize,
+    zeta_position: usize,
+    num_queries: usize,
+    cols: OodCols<D>,
+    width: usize,
+}
+
+struct OodCols<const D: usize> {
+    is_real: usize,
+    alpha: [usize; D],
+    zeta: [usize; D],
+    /// `zeta^(2^(j + 1))`, for `j` up to the larger of `degree_bits` and `log_quotient_degree`.
+    zeta_squares: Vec<[usize; D]>,
+    /// `zeta^k` for `k` from 2 up to the number of quotient chunks.
+    zeta_powers: Vec<[usize; D]>,
+    /// The trace's openings, for each point of the window.
+    trace: Vec<Vec<[usize; D]>>,
+    /// The openings of the quotient chunks' base field columns.
+    quotient_chunks: Vec<[usize; D]>,
+    is_first_row: [usize; D],
+    is_last_row: [usize; D],
+    /// The transition selectors of windows of size 2 and up.
+    is_transition: Vec<[usize; D]>,
+    /// The value of each instruction multiplying two non-constant registers, by instruction index.
+    products: BTreeMap<usize, [usize; D]>,
+    /// The combination of the constraints so far, with powers of `alpha`.
+    accumulators: Vec<[usize; D]>,
+    quotient: [usize; D],
+    final_poly: [usize; D],
+}
+
+/// A register of the program, evaluated at `zeta`: a constant, or a linear combination of columns.
+#[derive(Clone)]
+enum Value<F, Expr, const D: usize> {
+    Constant(F),
+    Linear([Expr; D]),
+}
+
+impl<F, const D: usize> OodCheckAir<F, D>
+where
+    F: TwoAdicField + BinomiallyExtendable<D>,
+{
+    /// Create the table checking `program`, which must only read the main trace, with a window of
+    /// `window_size` rows of `trace_width` columns. The quotient has `2^log_quotient_degree`
+    /// chunks, each committed as `D` base field columns, and is opened at
+    /// `zeta^(2^log_quotient_degree)`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        program: ConstraintProgram<F>,
+        window_size: usize,
+        trace_width: usize,
+        degree_bits: usize,
+        log_quotient_degree: usize,
+        alpha_position: usize,
+        zeta_position: usize,
+        num_queries: usize,
+    ) -> Self {
+        assert!(degree_bits > 0, "expected a trace of at least two rows");
+        assert!(window_size > 0);
+        assert!(
+            !program.constraints().is_empty(),
+            "expected at least one constraint"
+        );
+        assert!(
+            program.num_ext_registers() == 0,
+            "only constraints over the base field are supported"
+        );
+
+        let mut alloc = ColumnAllocator::default();
+        let is_real = alloc.col();
+        let alpha = alloc.array();
+        let zeta = alloc.array();
+        let zeta_squares = (0..degree_bits.max(log_quotient_degree))
+            .map(|_| alloc.array())
+            .collect();
+        let zeta_powers = (2..1 << log_quotient_degree)
+            .map(|_| alloc.array())
+            .collect();
+        let trace = (0..window_size)
+            .map(|_| (0..trace_width).map(|_| alloc.array()).collect())
+            .collect();
+        let quotient_chunks = (0..D << log_quotient_degree)
+            .map(|_| alloc.array())
+            .collect();
+        let is_first_row = alloc.array();
+        let is_last_row = alloc.array();
+        let is_transition = (2..=window_size).map(|_| alloc.array()).collect();
+
+        // Find the products of non-constant registers, folding those of constants.
+        let mut constants: Vec<Option<F>> = Vec::new();
+        let mut products = BTreeMap::new();
+        for (i, instruction) in program.instructions().iter().enumerate() {
+            let constant = |r: &Register| match *r {
+                Register::Base(r) => constants[r],
+                Register::Ext(_) => unreachable!(),
+            };
+            let value = match *instruction {
+                Instruction::Variable { entry, index } => {
+                    assert!(
+                        matches!(entry, Entry::Main { offset } if offset < window_size)
+                            && index < trace_width,
+                        "only constraints over the main trace's window are supported"
+                    );
+                    None
+                }
+                Instruction::IsFirstRow | Instruction::IsLastRow => None,
+                Instruction::IsTransitionWindow(size) => {
+                    assert!(size > 0 && size <= window_size);
+                    (size == 1).then(F::one)
+                }
+                Instruction::Constant(c) => Some(program.constants()[c]),
+                Instruction::Add(x, y) => constant(&x).zip(constant(&y)).map(|(x, y)| x + y),
+                Instruction::Sub(x, y) => constant(&x).zip(constant(&y)).map(|(x, y)| x - y),
+                Instruction::Neg(x) => constant(&x).map(|x| -x),
+                Instruction::Mul(x, y) => {
+                    let (x, y) = (constant(&x), constant(&y));
+                    if x.is_none() && y.is_none() {
+                        products.insert(i, alloc.array());
+                    }
+                    x.zip(y).map(|(x, y)| x * y)
+                }
+            };
+            constants.push(value);
+        }
+
+        let accumulators = program
+            .constraints()
+            .iter()
+            .map(|_| alloc.array())
+            .collect();
+        let cols = OodCols {
+            is_real,
+            alpha,
+            zeta,
+            zeta_squares,
+            zeta_powers,
+            trace,
+            quotient_chunks,
+            is_first_row,
+            is_last_row,
+            is_transition,
+            products,
+            accumulators,
+            quotient: alloc.array(),
+            final_poly: alloc.array(),
+        };
+        Self {
+            program,
+            window_size,
+            trace_width,
+            degree_bits,
+            log_quotient_degree,
+            alpha_position,
+            zeta_position,
+            num_queries,
+            cols,
+            width: alloc.width(),
+        }
+    }
+
+    /// Generate the trace for the challenges `alpha` and `zeta`, the openings of the trace's window
+    /// and the quotient chunks' columns at them, and the final polynomial of FRI.
+    pub fn generate_trace(
+        &self,
+        alpha: BinomialExtensionField<F, D>,
+        zeta: BinomialExtensionField<F, D>,
+        trace: &[Vec<BinomialExtensionField<F, D>>],
+        quotient_chunks: &[BinomialExtensionField<F, D>],
+        final_poly: BinomialExtensionField<F, D>,
+    ) -> RowMajorMatrix<F> {
+        type EF<F, const D: usize> = BinomialExtensionField<F, D>;
+        let c = &self.cols;
+        assert_eq!(trace.len(), self.window_size);
+        assert!(trace.iter().all(|row| row.len() == self.trace_width));
+        assert_eq!(quotient_chunks.len(), c.quotient_chunks.len());
+
+        let mut row = vec![F::zero(); self.width];
+        let mut set = |cols: &[usize; D], value: EF<F, D>| {
+            for (&col, coeff) in cols.iter().zip(coeffs(value)) {
+                row[col] = coeff;
+            }
+        };
+
+        set(&c.alpha, alpha);
+        set(&c.zeta, zeta);
+        let mut square = zeta;
+        for cols in &c.zeta_squares {
+            square = square.square();
+            set(cols, square);
+        }
+        for (cols, power) in c.zeta_powers.iter().zip(zeta.powers().skip(2)) {
+            set(cols, power);
+        }
+        for (cols, values) in c.trace.iter().zip(trace) {
+            for (cols, &value) in cols.iter().zip(values) {
+                set(cols, value);
+            }
+        }
+        for (cols, &value) in c.quotient_chunks.iter().zip(quotient_chunks) {
+            set(cols, value);
+        }
+
+        let g_inv = F::two_adic_generator(self.degree_bits).inverse();
+        let z_h = zeta.exp_power_of_2(self.degree_bits) - EF::<F, D>::one();
+        let is_first_row = z_h / (zeta - EF::<F, D>::one());
+        let is_last_row = z_h / (zeta - g_inv);
+        set(&c.is_first_row, is_first_row);
+        set(&c.is_last_row, is_last_row);
+        let mut is_transition = vec![EF::<F, D>::one()];
+        for (cols, g_inv_pow) in c.is_transition.iter().zip(g_inv.powers().skip(1)) {
+            let selector = *is_transition.last().unwrap() * (zeta - g_inv_pow);
+            set(cols, selector);
+            is_transition.push(selector);
+        }
+
+        let mut registers: Vec<EF<F, D>> = Vec::with_capacity(self.program.instructions().len());
+        for (i, instruction) in self.program.instructions().iter().enumerate() {
+            let reg = |r: &Register| match *r {
+                Register::Base(r) => registers[r],
+                Register::Ext(_) => unreachable!(),
+            };
+            let value = match *instruction {
+                Instruction::Variable { entry, index } => match entry {
+                    Entry::Main { offset } => trace[offset][index],
+                    _ => unreachable!(),
+                },
+                Instruction::IsFirstRow => is_first_row,
+                Instruction::IsLastRow => is_last_row,
+                Instruction::IsTransitionWindow(size) => is_transition[size - 1],
+                Instruction::Constant(c) => EF::<F, D>::from_base(self.program.constants()[c]),
+                Instruction::Add(x, y) => reg(&x) + reg(&y),
+                Instruction::Sub(x, y) => reg(&x) - reg(&y),
+                Instruction::Neg(x) => -reg(&x),
+                Instruction::Mul(x, y) => reg(&x) * reg(&y),
+            };
+            if let Some(cols) = c.products.get(&i) {
+                set(cols, value);
+            }
+            registers.push(value);
+        }
+
+        let mut accumulator = EF::<F, D>::zero();
+        for (cols, constraint) in c.accumulators.iter().zip(self.program.constraints()) {
+            let Register::Base(constraint) = *constraint else {
+                unreachable!()
+            };
+            accumulator = accumulator * alpha + registers[constraint];
+            set(cols, accumulator);
+        }
+
+        let mut parts = quotient_chunks
+            .chunks_exact(D)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &value)| <EF<F, D> as AbstractExtensionField<F>>::monomial(i) * value)
+                    .sum::<EF<F, D>>()
+            })
+            .collect_vec();
+        p3_util::reverse_slice_index_bits(&mut parts);
+        let quotient = zeta
+            .powers()
+            .zip(parts)
+            .map(|(weight, part)| part * weight)
+            .sum();
+        set(&c.quotient, quotient);
+        set(&c.final_poly, final_poly);
+
+        // The first row is the real one, and the second repeats it.
+        let mut values = row.clone();
+        row[c.is_real] = F::one();
+        values.splice(0..0, row);
+        RowMajorMatrix::new(values, self.width)
+    }
+
+    /// The point at which the quotient chunks are opened.
+    fn quotient_point<T: Clone>(&self, zeta: T, squares: &[T]) -> T {
+        if self.log_quotient_degree == 0 {
+            zeta
+        } else {
+            squares[self.log_quotient_degree - 1].clone()
+        }
+    }
+}
+
+impl<F: Sync, const D: usize> BaseAir<F> for OodCheckAir<F, D> {
+    fn width(&self) -> usize {
+        self.width
+    }
+}
+
+impl<F, const D: usize> InteractionAir<F> for OodCheckAir<F, D>
+where
+    F: TwoAdicField + BinomiallyExtendable<D>,
+{
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let c = &self.cols;
+        [
+            (self.alpha_position, &c.alpha),
+            (self.zeta_position, &c.zeta),
+        ]
+        .into_iter()
+        .flat_map(|(position, cols)| {
+            (0..D).map(move |i| {
+                bus::message(
+                    bus::TRANSCRIPT,
+                    vec![
+                        VirtualPairCol::constant(F::from_canonical_usize(position + i)),
+                        VirtualPairCol::single_main(cols[i]),
+                        VirtualPairCol::one(),
+                    ],
+                    VirtualPairCol::single_main(c.is_real),
+                )
+            })
+        })
+        .collect()
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let c = &self.cols;
+        let count = VirtualPairCol::new_main(
+            vec![(c.is_real, F::from_canonical_usize(self.num_queries))],
+            F::zero(),
+        );
+        let columns = |cols: [usize; D]| cols.map(VirtualPairCol::single_main);
+        let scaled = |cols: [usize; D], factor: F| {
+            cols.map(|col| VirtualPairCol::new_main(vec![(col, factor)], F::zero()))
+        };
+        let opening = |opening: usize, column: usize, value: &[usize; D], point: Vec<_>| {
+            bus::message(
+                bus::OPENING,
+                [opening, column]
+                    .map(|i| VirtualPairCol::constant(F::from_canonical_usize(i)))
+                    .into_iter()
+                    .chain(columns(*value))
+                    .chain(point)
+                    .collect(),
+                count.clone(),
+            )
+        };
+
+        let g = F::two_adic_generator(self.degree_bits);
+        let mut receives = Vec::new();
+        for ((point, values), g_pow) in c.trace.iter().enumerate().zip(g.powers()) {
+            for (column, value) in values.iter().enumerate() {
+                let point_cols = scaled(c.zeta, g_pow).to_vec();
+                receives.push(opening(point, column, value, point_cols));
+            }
+        }
+        let quotient_point = self.quotient_point(c.zeta, &c.zeta_squares);
+        for (column, value) in c.quotient_chunks.iter().enumerate() {
+            let point_cols = columns(quotient_point).to_vec();
+            receives.push(opening(self.window_size, column, value, point_cols));
+        }
+        receives.push(bus::message(
+            bus::FINAL_POLY,
+            columns(c.final_poly).to_vec(),
+            count,
+        ));
+        receives
+    }
+}
+
+impl<AB, const D: usize> Air<AB> for OodCheckAir<AB::F, D>
+where
+    AB: AirBuilder,
+    AB::F: TwoAdicField + BinomiallyExtendable<D>,
+{
+    fn eval(&self, builder: &mut AB) {
+        let c = &self.cols;
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+        let ext = |cols: &[usize; D]| -> [AB::Expr; D] { cols.map(|col| local[col].into()) };
+        let assert_ext_eq = |builder: &mut AB, x: [AB::Expr; D], y: [AB::Expr; D]| {
+            for (x, y) in x.into_iter().zip(y) {
+                builder.assert_eq(x, y);
+            }
+        };
+        let minus_constant = |mut x: [AB::Expr; D], constant: AB::F| {
+            x[0] -= constant.into();
+            x
+        };
+
+        builder.when_first_row().assert_one(local[c.is_real]);
+        builder.when_transition().assert_zero(next[c.is_real]);
+
+        let alpha = ext(&c.alpha);
+        let zeta = ext(&c.zeta);
+        let mut square = zeta.clone();
+        for cols in &c.zeta_squares {
+            assert_ext_eq(
+                builder,
+                ext(cols),
+                binomial_mul::<AB::F, _, D>(&square, &square),
+            );
+            square = ext(cols);
+        }
+        let mut zeta_powers = vec![Self::one(), zeta.clone()];
+        for cols in &c.zeta_powers {
+            let power = binomial_mul::<AB::F, _, D>(zeta_powers.last().unwrap(), &zeta);
+            assert_ext_eq(builder, ext(cols), power);
+            zeta_powers.push(ext(cols));
+        }
+
+        // Z_H(zeta) = zeta^(2^degree_bits) - 1, from which the Lagrange selectors are derived.
+        let z_h = minus_constant(ext(&c.zeta_squares[self.degree_bits - 1]), AB::F::one());
+        let g_inv = AB::F::two_adic_generator(self.degree_bits).inverse();
+        let is_first_row = ext(&c.is_first_row);
+        let is_last_row = ext(&c.is_last_row);
+        for (selector, root) in [(&is_first_row, AB::F::one()), (&is_last_row, g_inv)] {
+            let product =
+                binomial_mul::<AB::F, _, D>(selector, &minus_constant(zeta.clone(), root));
+            assert_ext_eq(builder, product, z_h.clone());
+        }
+        let mut is_transition = vec![Self::one()];
+        for (cols, g_inv_pow) in c.is_transition.iter().zip(g_inv.powers().skip(1)) {
+            let selector = binomial_mul::<AB::F, _, D>(
+                is_transition.last().unwrap(),
+                &minus_constant(zeta.clone(), g_inv_pow),
+            );
+            assert_ext_eq(builder, ext(cols), selector);
+            is_transition.push(ext(cols));
+        }
+
+        // Evaluate the program's registers.
+        let mut registers: Vec<Value<AB::F, AB::Expr, D>> = Vec::new();
+        for (i, instruction) in self.program.instructions().iter().enumerate() {
+            let reg = |r: &Register| match *r {
+                Register::Base(r) => registers[r].clone(),
+                Register::Ext(_) => unreachable!(),
+            };
+            let value = match *instruction {
+                Instruction::Variable { entry, index } => match entry {
+                    Entry::Main { offset } => Value::Linear(ext(&c.trace[offset][index])),
+                    _ => unreachable!(),
+                },
+                Instruction::IsFirstRow => Value::Linear(is_first_row.clone()),
+                Instruction::IsLastRow => Value::Linear(is_last_row.clone()),
+                Instruction::IsTransitionWindow(1) => Value::Constant(AB::F::one()),
+                Instruction::IsTransitionWindow(size) => {
+                    Value::Linear(is_transition[size - 1].clone())
+                }
+                Instruction::Constant(c) => Value::Constant(self.program.constants()[c]),
+                Instruction::Add(x, y) => Value::add(reg(&x), reg(&y)),
+                Instruction::Sub(x, y) => Value::add(reg(&x), Value::neg(reg(&y))),
+                Instruction::Neg(x) => Value::neg(reg(&x)),
+                Instruction::Mul(x, y) => match (reg(&x), reg(&y)) {
+                    (Value::Constant(x), Value::Constant(y)) => Value::Constant(x * y),
+                    (Value::Constant(k), Value::Linear(v))
+                    | (Value::Linear(v), Value::Constant(k)) => Value::Linear(v.map(|v| v * k)),
+                    (Value::Linear(x), Value::Linear(y)) => {
+                        let cols = &c.products[&i];
+                        assert_ext_eq(builder, ext(cols), binomial_mul::<AB::F, _, D>(&x, &y));
+                        Value::Linear(ext(cols))
+                    }
+                },
+            };
+            registers.push(value);
+        }
+
+        // Combine the constraints with powers of alpha, as the verifier's folder does.
+        let mut accumulator = Self::zero();
+        for (cols, constraint) in c.accumulators.iter().zip(self.program.constraints()) {
+            let Register::Base(constraint) = *constraint else {
+                unreachable!()
+            };
+            let constraint = match registers[constraint].clone() {
+                Value::Constant(k) => {
+                    let mut value = Self::zero();
+                    value[0] = k.into();
+                    value
+                }
+                Value::Linear(v) => v,
+            };
+            let mut expected = binomial_mul::<AB::F, _, D>(&accumulator, &alpha);
+            for (expected, constraint) in expected.iter_mut().zip(constraint) {
+                *expected += constraint;
+            }
+            assert_ext_eq(builder, ext(cols), expected);
+            accumulator = ext(cols);
+        }
+
+        // Recompose the quotient from its chunks, each flattened into D base field columns.
+        let num_chunks = 1 << self.log_quotient_degree;
+        let mut quotient = Self::zero();
+        for (k, power) in zeta_powers.iter().enumerate().take(num_chunks) {
+            let chunk = reverse_bits_len(k, self.log_quotient_degree);
+            let mut part = Self::zero();
+            for (i, cols) in c.quotient_chunks[chunk * D..(chunk + 1) * D]
+                .iter()
+                .enumerate()
+            {
+                let term = mul_by_monomial::<AB::F, _, D>(&ext(cols), i);
+                for (part, term) in part.iter_mut().zip(term) {
+                    *part += term;
+                }
+            }
+            for (quotient, term) in quotient
+                .iter_mut()
+                .zip(binomial_mul::<AB::F, _, D>(power, &part))
+            {
+                *quotient += term;
+            }
+        }
+        assert_ext_eq(builder, ext(&c.quotient), quotient);
+
+        // constraints(zeta) = quotient(zeta) Z_H(zeta)
+        let expected = binomial_mul::<AB::F, _, D>(&ext(&c.quotient), &z_h);
+        assert_ext_eq(builder, accumulator, expected);
+    }
+}
+
+impl<F: Field, const D: usize> OodCheckAir<F, D> {
+    fn zero<Expr: AbstractField>() -> [Expr; D] {
+        core::array::from_fn(|_| Expr::zero())
+    }
+
+    fn one<Expr: AbstractField>() -> [Expr; D] {
+        core::array::from_fn(|i| if i == 0 { Expr::one() } else { Expr::zero() })
+    }
+}
+
+impl<F: Field, Expr: AbstractField + From<F>, const D: usize> Value<F, Expr, D> {
+    fn add(x: Self, y: Self) -> Self {
+        match (x, y) {
+            (Self::Constant(x), Self::Constant(y)) => Self::Constant(x + y),
+            (Self::Constant(k), Self::Linear(mut v)) | (Self::Linear(mut v), Self::Constant(k)) => {
+                v[0] += Expr::from(k);
+                Self::Linear(v)
+            }
+            (Self::Linear(x), Self::Linear(y)) => {
+                let mut x = x;
+                for (x, y) in x.iter_mut().zip(y) {
+                    *x += y;
+                }
+                Self::Linear(x)
+            }
+        }
+    }
+
+    fn neg(x: Self) -> Self {
+        match x {
+            Self::Constant(k) => Self::Constant(-k),
+            Self::Linear(v) => Self::Linear(v.map(|v| -v)),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::Itertools;
+use p3_air::{Air, AirBuilder, BaseAir, Interaction, InteractionAir, VirtualPairCol};
+use p3_field::PrimeField;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_poseidon2::{DiffusionPermutation, Poseidon2};
+use p3_poseidon2_air::Poseidon2Air;
+
+use crate::bus;
+use crate::WIDTH;
+
+/// A table of Poseidon2 permutations, which the other tables look up: each row proves one
+/// permutation with `Poseidon2Air`'s columns, and receives its `(inputs, outputs)` on the
+/// `POSEIDON2` bus as many times as its multiplicity column says.
+pub struct Poseidon2LookupAir<F, Diffusion, const SBOX_DEGREE: u64> {
+    air: Poseidon2Air<F, Diffusion, WIDTH, SBOX_DEGREE>,
+}
+
+impl<F, Diffusion, const SBOX_DEGREE: u64> Poseidon2LookupAir<F, Diffusion, SBOX_DEGREE>
+where
+    F: PrimeField,
+{
+    pub fn new(poseidon2: Poseidon2<F, Diffusion, WIDTH, SBOX_DEGREE>) -> Self {
+        Self {
+            air: Poseidon2Air::new(poseidon2),
+        }
+    }
+
+    fn multiplicity_col(&self) -> usize {
+        self.air.num_cols()
+    }
+
+    /// Generate a trace with one row per permutation of `inputs`, each looked up once.
+    pub fn generate_trace(&self, inputs: Vec<[F; WIDTH]>) -> RowMajorMatrix<F>
+    where
+        Diffusion: DiffusionPermutation<F, WIDTH>,
+    {
+        let num_calls = inputs.len();
+        let permutations = self.air.generate_trace_rows(inputs);
+        let values = permutations
+            .rows()
+            .enumerate()
+            .flat_map(|(i, row)| {
+                row.iter()
+                    .copied()
+                    .chain([F::from_bool(i < num_calls)])
+                    .collect_vec()
+            })
+            .collect();
+        RowMajorMatrix::new(values, self.multiplicity_col() + 1)
+    }
+}
+
+impl<F, Diffusion, const SBOX_DEGREE: u64> BaseAir<F>
+    for Poseidon2LookupAir<F, Diffusion, SBOX_DEGREE>
+where
+    F: PrimeField,
+    Diffusion: Sync,
+{
+    fn width(&self) -> usize {
+        self.multiplicity_col() + 1
+    }
+}
+
+impl<F, Diffusion, const SBOX_DEGREE: u64> InteractionAir<F>
+    for Poseidon2LookupAir<F, Diffusion, SBOX_DEGREE>
+where
+    F: PrimeField,
+    Diffusion: Sync,
+{
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let cols = self.air.cols(&(0..self.air.num_cols()).collect_vec());
+        let fields = cols
+            .inputs
+            .iter()
+            .chain(cols.outputs())
+            .map(|&col| VirtualPairCol::single_main(col))
+            .collect();
+        vec![bus::message(
+            bus::POSEIDON2,
+            fields,
+            VirtualPairCol::single_main(self.multiplicity_col()),
+        )]
+    }
+}
+
+impl<AB, Diffusion, const SBOX_DEGREE: u64> Air<AB>
+    for Poseidon2LookupAir<AB::F, Diffusion, SBOX_DEGREE>
+where
+    AB: AirBuilder,
+    AB::F: PrimeField,
+    Diffusion: DiffusionPermutation<AB::Expr, WIDTH> + Sync,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = self.air.cols(main.row_slice(0));
+        self.air.eval_cols(builder, &local);
+    }
+}
@@ -0,0 +1,289 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use p3_air::{Air, AirBuilder, BaseAir, Interaction, InteractionAir, VirtualPairCol};
+use p3_field::{AbstractField, PrimeField64, TwoAdicField};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_util::reverse_bits_len;
+
+use crate::bus;
+use crate::columns::ColumnAllocator;
+
+/// An AIR for FRI's proof of work check and query indices, as sampled by
+/// `p3_fri::verifier::verify_shape_and_sample_challenges`.
+///
+/// The first row reads the proof of work witness and the sample after it from the transcript, and
+/// checks that the sample's low `proof_of_work_bits` bits are zero. Each following row reads the
+/// next sample, and sends its low `log_max_height` bits on the `QUERY` bus as the queried index,
+/// along with the point `g^rev(index)` of the largest FRI domain, where `g` generates the domain
+/// and `rev` reverses the index's bits.
+///
+/// Samples are decomposed into bits, which only determines them uniquely if their canonical form
+/// is checked. This is done for fields whose order minus one is a run of ones followed by zeros in
+/// binary, like BabyBear's `2^31 - 2^27`: a sample is canonical unless its top bits are all ones
+/// and some lower bit is set.
+pub struct QueryIndexAir<F> {
+    cols: QueryCols,
+    width: usize,
+    log_max_height: usize,
+    proof_of_work_bits: usize,
+    /// The position of the proof of work witness in the transcript.
+    witness_position: usize,
+    num_queries: usize,
+    /// The number of low bits of the field's order minus one which are zero.
+    low_zero_bits: usize,
+    /// `g^(2^(log_max_height - 1 - j))`, which contributes to the queried point if bit `j` of the
+    /// index is set.
+    point_factors: Vec<F>,
+}
+
+struct QueryCols {
+    is_real: usize,
+    is_query: usize,
+    is_last: usize,
+    position: usize,
+    witness: usize,
+    value: usize,
+    bits: Vec<usize>,
+    /// Running products of the high bits, which are all one exactly when the last is.
+    high_bits_products: Vec<usize>,
+    index: usize,
+    /// Running products of the point factors of the index's bits, ending with the queried point.
+    point_products: Vec<usize>,
+}
+
+impl<F: PrimeField64 + TwoAdicField> QueryIndexAir<F> {
+    pub fn new(
+        log_max_height: usize,
+        proof_of_work_bits: usize,
+        witness_position: usize,
+        num_queries: usize,
+    ) -> Self {
+        let order_minus_one = F::ORDER_U64 - 1;
+        let low_zero_bits = order_minus_one.trailing_zeros() as usize;
+        let num_bits = F::bits();
+        let num_high_bits = num_bits - low_zero_bits;
+        assert_eq!(
+            order_minus_one,
+            ((1 << num_high_bits) - 1) << low_zero_bits,
+            "expected the field's order minus one to be a run of ones followed by zeros"
+        );
+        assert!(num_high_bits >= 2, "expected at least two high bits");
+        assert!(log_max_height >= 1 && log_max_height <= low_zero_bits.min(F::TWO_ADICITY));
+        assert!(proof_of_work_bits <= low_zero_bits);
+        assert!(num_queries > 0, "expected at least one query");
+
+        let mut alloc = ColumnAllocator::default();
+        let cols = QueryCols {
+            is_real: alloc.col(),
+            is_query: alloc.col(),
+            is_last: alloc.col(),
+            position: alloc.col(),
+            witness: alloc.col(),
+            value: alloc.col(),
+            bits: alloc.vec(num_bits),
+            high_bits_products: alloc.vec(num_high_bits - 1),
+            index: alloc.col(),
+            point_products: alloc.vec(log_max_height),
+        };
+        let generator = F::two_adic_generator(log_max_height);
+        let point_factors = (0..log_max_height)
+            .map(|j| generator.exp_power_of_2(log_max_height - 1 - j))
+            .collect();
+        Self {
+            cols,
+            width: alloc.width(),
+            log_max_height,
+            proof_of_work_bits,
+            witness_position,
+            num_queries,
+            low_zero_bits,
+            point_factors,
+        }
+    }
+
+    /// Generate the trace for the proof of work `witness` and the `samples` which follow it: the
+    /// proof of work sample, then one per query.
+    pub fn generate_trace(&self, witness: F, samples: &[F]) -> RowMajorMatrix<F> {
+        assert_eq!(samples.len(), self.num_queries + 1);
+        let c = &self.cols;
+        let num_rows = samples.len().next_power_of_two();
+        let mut values = vec![F::zero(); num_rows * self.width];
+
+        for (i, row) in values.chunks_exact_mut(self.width).enumerate() {
+            let sample = samples.get(i).map_or(0, |s| s.as_canonical_u64());
+            let is_real = i < samples.len();
+            row[c.is_real] = F::from_bool(is_real);
+            row[c.is_query] = F::from_bool(is_real && i > 0);
+            row[c.is_last] = F::from_bool(i + 1 == samples.len());
+            if is_real {
+                row[c.position] = F::from_canonical_usize(self.witness_position + 1 + i);
+                row[c.value] = samples[i];
+            }
+            if i == 0 {
+                row[c.witness] = witness;
+            }
+            for (j, &col) in c.bits.iter().enumerate() {
+                row[col] = F::from_bool(sample >> j & 1 == 1);
+            }
+            let high_bits = &c.bits[self.low_zero_bits..];
+            let mut product = row[high_bits[0]];
+            for (&col, &bit_col) in c.high_bits_products.iter().zip(&high_bits[1..]) {
+                product *= row[bit_col];
+                row[col] = product;
+            }
+            let index = sample as usize & ((1 << self.log_max_height) - 1);
+            row[c.index] = F::from_canonical_usize(index);
+            let mut point = F::one();
+            for ((&col, &factor), j) in c.point_products.iter().zip(&self.point_factors).zip(0..) {
+                if index >> j & 1 == 1 {
+                    point *= factor;
+                }
+                row[col] = point;
+            }
+            debug_assert!(
+                !is_real
+                    || point
+                        == F::two_adic_generator(self.log_max_height).exp_u64(reverse_bits_len(
+                            index,
+                            self.log_max_height
+                        )
+                            as u64)
+            );
+        }
+        RowMajorMatrix::new(values, self.width)
+    }
+}
+
+impl<F: Sync> BaseAir<F> for QueryIndexAir<F> {
+    fn width(&self) -> usize {
+        self.width
+    }
+}
+
+impl<F: PrimeField64 + Sync> InteractionAir<F> for QueryIndexAir<F> {
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let c = &self.cols;
+        let is_witness_row = VirtualPairCol::new_main(
+            vec![(c.is_real, F::one()), (c.is_query, F::neg_one())],
+            F::zero(),
+        );
+        vec![
+            bus::message(
+                bus::TRANSCRIPT,
+                vec![
+                    VirtualPairCol::new_main(vec![(c.position, F::one())], F::neg_one()),
+                    VirtualPairCol::single_main(c.witness),
+                    VirtualPairCol::constant(F::zero()),
+                ],
+                is_witness_row,
+            ),
+            bus::message(
+                bus::TRANSCRIPT,
+                vec![
+                    VirtualPairCol::single_main(c.position),
+                    VirtualPairCol::single_main(c.value),
+                    VirtualPairCol::one(),
+                ],
+                VirtualPairCol::single_main(c.is_real),
+            ),
+            bus::message(
+                bus::QUERY,
+                vec![
+                    VirtualPairCol::single_main(c.index),
+                    VirtualPairCol::single_main(*c.point_products.last().unwrap()),
+                ],
+                VirtualPairCol::single_main(c.is_query),
+            ),
+        ]
+    }
+}
+
+impl<AB> Air<AB> for QueryIndexAir<AB::F>
+where
+    AB: AirBuilder,
+    AB::F: PrimeField64,
+{
+    fn eval(&self, builder: &mut AB) {
+        let c = &self.cols;
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+
+        let is_real = local[c.is_real];
+        let is_query = local[c.is_query];
+        let is_last = local[c.is_last];
+        let bits: Vec<_> = c.bits.iter().map(|&col| local[col]).collect();
+
+        builder.assert_bool(is_real);
+        builder.assert_bool(is_query);
+        builder.assert_bool(is_last);
+        builder.when(is_query).assert_one(is_real);
+        builder.when(is_last).assert_one(is_real);
+        for &bit in &bits {
+            builder.assert_bool(bit);
+        }
+
+        // The sample is the canonical form of its bits.
+        let mut weight = AB::F::one();
+        let mut value = AB::Expr::zero();
+        for &bit in &bits {
+            value += bit * weight;
+            weight = weight.double();
+        }
+        builder.assert_eq(local[c.value], value);
+        let (low_bits, high_bits) = bits.split_at(self.low_zero_bits);
+        let mut all_high_bits = high_bits[0].into();
+        for (&col, &bit) in c.high_bits_products.iter().zip(&high_bits[1..]) {
+            builder.assert_eq(local[col], all_high_bits * bit);
+            all_high_bits = local[col].into();
+        }
+        let any_low_bits: AB::Expr = low_bits.iter().map(|&bit| bit.into()).sum();
+        builder.assert_zero(all_high_bits * any_low_bits);
+
+        // The proof of work sample must have its low bits unset.
+        let is_witness_row = is_real - is_query;
+        for &bit in &bits[..self.proof_of_work_bits] {
+            builder.when(is_witness_row.clone()).assert_zero(bit);
+        }
+
+        // The index is the low bits of the sample, and the queried point is the product of the
+        // factors of its set bits.
+        let mut weight = AB::F::one();
+        let mut index = AB::Expr::zero();
+        for &bit in &bits[..self.log_max_height] {
+            index += bit * weight;
+            weight = weight.double();
+        }
+        builder.assert_eq(local[c.index], index);
+        let mut point = AB::Expr::one();
+        for ((&col, &factor), &bit) in c.point_products.iter().zip(&self.point_factors).zip(&bits) {
+            builder.assert_eq(
+                local[col],
+                point * (AB::Expr::one() + bit * (factor - AB::F::one())),
+            );
+            point = local[col].into();
+        }
+
+        // The proof of work row comes first, followed by the queries at consecutive positions.
+        let mut when_first = builder.when_first_row();
+        when_first.assert_one(is_real);
+        when_first.assert_zero(is_query);
+        when_first.assert_eq(
+            local[c.position],
+            AB::F::from_canonical_usize(self.witness_position + 1),
+        );
+        builder.when(is_last).assert_eq(
+            local[c.position],
+            AB::F::from_canonical_usize(self.witness_position + 1 + self.num_queries),
+        );
+        let mut when_transition = builder.when_transition();
+        when_transition.assert_eq(next[c.is_real], is_real - is_last);
+        when_transition.assert_eq(next[c.is_query], next[c.is_real]);
+        when_transition
+            .when(next[c.is_real])
+            .assert_eq(next[c.position], local[c.position] + AB::Expr::one());
+        builder.when_last_row().assert_eq(is_real, is_last);
+    }
+}
@@ -0,0 +1,356 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::Itertools;
+use p3_air::{Air, AirBuilder, BaseAir, Interaction, InteractionAir, VirtualPairCol};
+use p3_field::extension::{BinomialExtensionField, BinomiallyExtendable};
+use p3_field::{AbstractExtensionField, AbstractField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+
+use crate::bus;
+use crate::columns::ColumnAllocator;
+use crate::extension::{binomial_mul, coeffs};
+
+/// One out-of-domain opening of a committed matrix, reduced by `ReducedOpeningAir`.
+#[derive(Copy, Clone, Debug)]
+pub struct OpeningShape {
+    /// The Merkle tree the matrix is committed in.
+    pub tree: usize,
+    pub width: usize,
+}
+
+/// An AIR for the reduction of the openings of a query into the input of FRI, as done by
+/// `TwoAdicFriPcs::verify_multi_batches` for matrices whose LDEs all have the same height.
+///
+/// For the queried point `x` of the LDE domain, shifted by `shift`, each row adds one term
+///     alpha^i (p(x) - p(z)) / (x - z)
+/// for a column `p` of the matrix of an opening, at the opening's point `z`, going through the
+/// columns of each opening in turn. The values `p(x)` are sent on the `LEAF` bus to be checked
+/// against the matrix's commitment, and `(p(z), z)` on the `OPENING` bus to be checked against the
+/// out-of-domain openings. Each query reads `alpha` from the transcript, and the result of its last
+/// row is received on the `REDUCED_OPENING` bus.
+pub struct ReducedOpeningAir<F, const D: usize> {
+    openings: Vec<OpeningShape>,
+    shift: F,
+    /// The position of `alpha` in the transcript.
+    alpha_position: usize,
+    cols: ReducedOpeningCols<D>,
+    width: usize,
+}
+
+struct ReducedOpeningCols<const D: usize> {
+    is_real: usize,
+    is_first_term: usize,
+    /// Whether this is the last column of its opening.
+    is_last_column: usize,
+    is_last_term: usize,
+    /// A one-hot encoding of the opening this row's column is in.
+    opening: Vec<usize>,
+    column: usize,
+    /// The queried index.
+    index: usize,
+    /// The queried point, before the shift.
+    x: usize,
+    alpha: [usize; D],
+    alpha_pow: [usize; D],
+    /// The sum of the previous terms of the query.
+    acc: [usize; D],
+    /// The sum including this row's term.
+    acc_out: [usize; D],
+    p_x: usize,
+    p_z: [usize; D],
+    z: [usize; D],
+    term: [usize; D],
+}
+
+/// The values of a query which `ReducedOpeningAir` reduces.
+#[derive(Clone, Debug)]
+pub struct ReducedOpeningQuery<F, EF> {
+    pub index: usize,
+    /// The queried point, before the shift.
+    pub x: F,
+    /// For each opening, the opened row of its matrix at the queried index.
+    pub rows: Vec<Vec<F>>,
+    /// For each opening, the claimed evaluations of its matrix's columns at its point.
+    pub values: Vec<Vec<EF>>,
+    /// For each opening, its point.
+    pub points: Vec<EF>,
+}
+
+impl<F, const D: usize> ReducedOpeningAir<F, D>
+where
+    F: Field + BinomiallyExtendable<D>,
+{
+    pub fn new(openings: Vec<OpeningShape>, shift: F, alpha_position: usize) -> Self {
+        assert!(!openings.is_empty());
+        assert!(openings.iter().all(|opening| opening.width > 0));
+        let mut alloc = ColumnAllocator::default();
+        let cols = ReducedOpeningCols {
+            is_real: alloc.col(),
+            is_first_term: alloc.col(),
+            is_last_column: alloc.col(),
+            is_last_term: alloc.col(),
+            opening: alloc.vec(openings.len()),
+            column: alloc.col(),
+            index: alloc.col(),
+            x: alloc.col(),
+            alpha: alloc.array(),
+            alpha_pow: alloc.array(),
+            acc: alloc.array(),
+            acc_out: alloc.array(),
+            p_x: alloc.col(),
+            p_z: alloc.array(),
+            z: alloc.array(),
+            term: alloc.array(),
+        };
+        Self {
+            openings,
+            shift,
+            alpha_position,
+            cols,
+            width: alloc.width(),
+        }
+    }
+
+    /// Generate the trace reducing `queries` with `alpha`, and return it along with each query's
+    /// reduced opening.
+    pub fn generate_trace(
+        &self,
+        alpha: BinomialExtensionField<F, D>,
+        queries: &[ReducedOpeningQuery<F, BinomialExtensionField<F, D>>],
+    ) -> (RowMajorMatrix<F>, Vec<BinomialExtensionField<F, D>>) {
+        let c = &self.cols;
+        let num_terms: usize = self.openings.iter().map(|opening| opening.width).sum();
+        let num_rows = (queries.len() * num_terms).next_power_of_two().max(2);
+        let mut values = vec![F::zero(); num_rows * self.width];
+        let mut rows = values.chunks_exact_mut(self.width);
+        let mut reduced_openings = Vec::with_capacity(queries.len());
+
+        for query in queries {
+            let shifted_x = BinomialExtensionField::<F, D>::from_base(self.shift * query.x);
+            let mut acc = BinomialExtensionField::<F, D>::zero();
+            let mut alpha_pow = BinomialExtensionField::<F, D>::one();
+            let terms = self.openings.iter().enumerate().flat_map(|(o, opening)| {
+                (0..opening.width).map(move |column| (o, opening, column))
+            });
+            for (term_index, (o, opening, column)) in terms.enumerate() {
+                let row = rows.next().unwrap();
+                let p_x = query.rows[o][column];
+                let p_z = query.values[o][column];
+                let z = query.points[o];
+                let term = (p_z - p_x) * (z - shifted_x).inverse();
+                let acc_out = acc + alpha_pow * term;
+
+                row[c.is_real] = F::one();
+                row[c.is_first_term] = F::from_bool(term_index == 0);
+                row[c.is_last_column] = F::from_bool(column + 1 == opening.width);
+                row[c.is_last_term] = F::from_bool(term_index + 1 == num_terms);
+                row[c.opening[o]] = F::one();
+                row[c.column] = F::from_canonical_usize(column);
+                row[c.index] = F::from_canonical_usize(query.index);
+                row[c.x] = query.x;
+                row[c.p_x] = p_x;
+                for (cols, value) in [
+                    (&c.alpha, alpha),
+                    (&c.alpha_pow, alpha_pow),
+                    (&c.acc, acc),
+                    (&c.acc_out, acc_out),
+                    (&c.p_z, p_z),
+                    (&c.z, z),
+                    (&c.term, term),
+                ] {
+                    for (&col, coeff) in cols.iter().zip(coeffs(value)) {
+                        row[col] = coeff;
+                    }
+                }
+
+                acc = acc_out;
+                alpha_pow *= alpha;
+            }
+            reduced_openings.push(acc);
+        }
+        (RowMajorMatrix::new(values, self.width), reduced_openings)
+    }
+}
+
+impl<F: Sync, const D: usize> BaseAir<F> for ReducedOpeningAir<F, D> {
+    fn width(&self) -> usize {
+        self.width
+    }
+}
+
+impl<F: Field, const D: usize> InteractionAir<F> for ReducedOpeningAir<F, D> {
+    fn sends(&self) -> Vec<Interaction<F>> {
+        let c = &self.cols;
+        let alpha = (0..D).map(|i| {
+            bus::message(
+                bus::TRANSCRIPT,
+                vec![
+                    VirtualPairCol::constant(F::from_canonical_usize(self.alpha_position + i)),
+                    VirtualPairCol::single_main(c.alpha[i]),
+                    VirtualPairCol::one(),
+                ],
+                VirtualPairCol::single_main(c.is_first_term),
+            )
+        });
+        let weighted_opening = |weight: &dyn Fn(usize, &OpeningShape) -> usize| {
+            VirtualPairCol::new_main(
+                c.opening
+                    .iter()
+                    .zip(&self.openings)
+                    .enumerate()
+                    .map(|(o, (&col, opening))| (col, F::from_canonical_usize(weight(o, opening))))
+                    .collect(),
+                F::zero(),
+            )
+        };
+        let leaf = bus::message(
+            bus::LEAF,
+            vec![
+                weighted_opening(&|_, opening| opening.tree),
+                VirtualPairCol::single_main(c.index),
+                VirtualPairCol::single_main(c.column),
+                VirtualPairCol::single_main(c.p_x),
+            ],
+            VirtualPairCol::single_main(c.is_real),
+        );
+        let opening = bus::message(
+            bus::OPENING,
+            [
+                weighted_opening(&|o, _| o),
+                VirtualPairCol::single_main(c.column),
+            ]
+            .into_iter()
+            .chain(
+                c.p_z
+                    .iter()
+                    .chain(&c.z)
+                    .map(|&col| VirtualPairCol::single_main(col)),
+            )
+            .collect(),
+            VirtualPairCol::single_main(c.is_real),
+        );
+        alpha.chain([leaf, opening]).collect()
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        let c = &self.cols;
+        let fields = [c.index, c.x]
+            .iter()
+            .chain(&c.acc_out)
+            .map(|&col| VirtualPairCol::single_main(col))
+            .collect();
+        vec![bus::message(
+            bus::REDUCED_OPENING,
+            fields,
+            VirtualPairCol::single_main(c.is_last_term),
+        )]
+    }
+}
+
+impl<AB, const D: usize> Air<AB> for ReducedOpeningAir<AB::F, D>
+where
+    AB: AirBuilder,
+    AB::F: BinomiallyExtendable<D>,
+{
+    fn eval(&self, builder: &mut AB) {
+        let c = &self.cols;
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+
+        let is_real = local[c.is_real];
+        let is_first_term = local[c.is_first_term];
+        let is_last_column = local[c.is_last_column];
+        let is_last_term = local[c.is_last_term];
+        let opening = c.opening.iter().map(|&col| local[col]).collect_vec();
+        let ext = |cols: &[usize; D]| -> [AB::Expr; D] { cols.map(|col| local[col].into()) };
+        let alpha = ext(&c.alpha);
+        let alpha_pow = ext(&c.alpha_pow);
+        let term = ext(&c.term);
+
+        for flag in [is_real, is_first_term, is_last_column, is_last_term] {
+            builder.assert_bool(flag);
+        }
+        for &flag in &opening {
+            builder.assert_bool(flag);
+        }
+        builder.when(is_first_term).assert_one(is_real);
+        builder.when(is_last_column).assert_one(is_real);
+        builder.assert_eq(
+            opening.iter().map(|&flag| flag.into()).sum::<AB::Expr>(),
+            is_real,
+        );
+
+        // term (shift x - z) = p(x) - p(z)
+        let mut denominator: [AB::Expr; D] = c.z.map(|col| -local[col].into());
+        denominator[0] += local[c.x] * self.shift;
+        let mut numerator: [AB::Expr; D] = c.p_z.map(|col| -local[col].into());
+        numerator[0] += local[c.p_x].into();
+        let product = binomial_mul::<AB::F, _, D>(&term, &denominator);
+        for (product, numerator) in product.into_iter().zip(numerator) {
+            builder.assert_eq(product, numerator);
+        }
+
+        // acc_out = acc + alpha^i term
+        let weighted_term = binomial_mul::<AB::F, _, D>(&alpha_pow, &term);
+        for i in 0..D {
+            builder.assert_eq(
+                local[c.acc_out[i]],
+                local[c.acc[i]] + weighted_term[i].clone(),
+            );
+        }
+
+        // A query starts from the first column of the first opening, with alpha^0.
+        let mut when_first_term = builder.when(is_first_term);
+        when_first_term.assert_zero(local[c.column]);
+        when_first_term.assert_one(opening[0]);
+        for i in 0..D {
+            when_first_term.assert_zero(local[c.acc[i]]);
+            if i == 0 {
+                when_first_term.assert_one(alpha_pow[i].clone());
+            } else {
+                when_first_term.assert_zero(alpha_pow[i].clone());
+            }
+        }
+        let last_column = opening
+            .iter()
+            .zip(&self.openings)
+            .map(|(&flag, shape)| flag * AB::F::from_canonical_usize(shape.width - 1))
+            .sum::<AB::Expr>();
+        builder
+            .when(is_last_column)
+            .assert_eq(local[c.column], last_column);
+        builder.assert_eq(is_last_term, is_last_column * *opening.last().unwrap());
+
+        builder.when_first_row().assert_eq(is_real, is_first_term);
+        builder.when_last_row().assert_eq(is_real, is_last_term);
+
+        // Within a query, move on to the next column, or the next opening's first column.
+        let mut when_transition = builder.when_transition();
+        let continues = next[c.is_real] - next[c.is_first_term];
+        when_transition.assert_eq(continues.clone(), is_real - is_last_term);
+        let mut when_continuing = when_transition.when(continues);
+        when_continuing.assert_eq(next[c.index], local[c.index]);
+        when_continuing.assert_eq(next[c.x], local[c.x]);
+        let alpha_pow_next = binomial_mul::<AB::F, _, D>(&alpha_pow, &alpha);
+        for i in 0..D {
+            when_continuing.assert_eq(next[c.alpha[i]], local[c.alpha[i]]);
+            when_continuing.assert_eq(next[c.acc[i]], local[c.acc_out[i]]);
+            when_continuing.assert_eq(next[c.alpha_pow[i]], alpha_pow_next[i].clone());
+        }
+        let stays_in_opening = AB::Expr::one() - is_last_column;
+        when_continuing.assert_eq(
+            next[c.column],
+            stays_in_opening.clone() * (local[c.column] + AB::Expr::one()),
+        );
+        for o in 0..opening.len() {
+            let mut expected = stays_in_opening.clone() * opening[o];
+            if o > 0 {
+                expected += is_last_column * opening[o - 1];
+            }
+            when_continuing.assert_eq(next[c.opening[o]], expected);
+        }
+    }
+}
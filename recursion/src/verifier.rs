@@ -0,0 +1,601 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::{izip, Itertools};
+use p3_air::{Air, AirBuilder, BaseAir, Interaction, InteractionAir};
+use p3_commit::Mmcs;
+use p3_field::extension::{BinomialExtensionField, BinomiallyExtendable};
+use p3_field::{AbstractExtensionField, AbstractField, PrimeField64, TwoAdicField};
+use p3_fri::{TwoAdicFriPcs, TwoAdicFriPcsGenericConfig};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_poseidon2::{DiffusionPermutation, Poseidon2};
+use p3_symmetric::MerkleCap;
+use p3_uni_stark::{
+    get_checked_constraint_program, get_log_quotient_degree, Proof, StarkGenericConfig,
+    SymbolicAirBuilder, VerifierConstraintFolder,
+};
+use p3_util::reverse_bits_len;
+
+use crate::challenger::{ChallengerTranscript, DuplexChallengerAir};
+use crate::fri_fold::{fold_query, FriFoldQuery, LinkedFriFoldAir};
+use crate::merkle::{
+    hash_leaf, multi_opening_layers, path_siblings, MerklePath, MerklePathAir, TreeShape,
+};
+use crate::ood::OodCheckAir;
+use crate::poseidon2::Poseidon2LookupAir;
+use crate::query::QueryIndexAir;
+use crate::reduced_opening::{OpeningShape, ReducedOpeningAir, ReducedOpeningQuery};
+use crate::{DIGEST_ELEMS, WIDTH};
+
+type Digest<F> = [F; DIGEST_ELEMS];
+
+/// The leaves opened in a tree, by index, with the tree's log height, its multi-opening proof, and
+/// the number of times each leaf is read.
+type TreeOpening<'a, F> = (Vec<(usize, Vec<F>)>, usize, &'a [Digest<F>], usize);
+
+/// One of the tables of a `RecursiveVerifier`. They're a single type so that they can be proven
+/// together with `p3_multi_stark`.
+pub enum RecursiveVerifierAir<F, Diffusion, const D: usize, const SBOX_DEGREE: u64> {
+    Poseidon2(Poseidon2LookupAir<F, Diffusion, SBOX_DEGREE>),
+    Challenger(DuplexChallengerAir),
+    QueryIndex(QueryIndexAir<F>),
+    MerklePath(MerklePathAir),
+    ReducedOpening(ReducedOpeningAir<F, D>),
+    FriFold(LinkedFriFoldAir<D>),
+    OodCheck(OodCheckAir<F, D>),
+}
+
+/// The positions of the inner proof's commitments and challenges in its transcript.
+struct TranscriptLayout {
+    trace_root: usize,
+    alpha: usize,
+    quotient_root: usize,
+    zeta: usize,
+    pcs_alpha: usize,
+    commit_phase_roots: Vec<usize>,
+    betas: Vec<usize>,
+    pow_witness: usize,
+    len: usize,
+}
+
+impl TranscriptLayout {
+    fn new(extension_degree: usize, num_rounds: usize, num_queries: usize) -> Self {
+        let mut len = 0;
+        let mut next = |n: usize| {
+            len += n;
+            len - n
+        };
+        let trace_root = next(DIGEST_ELEMS);
+        let alpha = next(extension_degree);
+        let quotient_root = next(DIGEST_ELEMS);
+        let zeta = next(extension_degree);
+        let pcs_alpha = next(extension_degree);
+        let (commit_phase_roots, betas) = (0..num_rounds)
+            .map(|_| (next(DIGEST_ELEMS), next(extension_degree)))
+            .unzip();
+        // The proof of work witness and its sample, followed by the queries.
+        let pow_witness = next(2);
+        next(num_queries);
+        Self {
+            trace_root,
+            alpha,
+            quotient_root,
+            zeta,
+            pcs_alpha,
+            commit_phase_roots,
+            betas,
+            pow_witness,
+            len,
+        }
+    }
+}
+
+/// A verifier of `p3_uni_stark` proofs as a set of tables linked by buses, to be proven with
+/// `p3_multi_stark`: a proof of the tables is a proof that the inner proof verifies.
+///
+/// The inner proof must use a `TwoAdicFriPcs` with Merkle trees and a `DuplexChallenger` over
+/// `poseidon2`, as in `Poseidon2LookupAir`, `MerklePathAir` and `DuplexChallengerAir`, committing
+/// to Merkle roots and folding by two down to a constant. Zero-knowledge and hiding are not
+/// supported, nor are AIRs with preprocessed or permutation columns, or public values. All traces
+/// have the height `2^degree_bits` given when creating the verifier.
+///
+/// The tables are:
+/// - `Poseidon2LookupAir`, proving each permutation the others look up.
+/// - `DuplexChallengerAir`, replaying the transcript, from which the others read the commitments
+///   and challenges.
+/// - `QueryIndexAir`, checking the proof of work and decomposing the queried indices.
+/// - `MerklePathAir`, opening the committed trace and quotient, and the commit phase codewords.
+/// - `ReducedOpeningAir`, combining the opened values of each query into the input of FRI.
+/// - `LinkedFriFoldAir`, folding each query down to the final polynomial.
+/// - `OodCheckAir`, checking the AIR's constraints against the quotient at the out-of-domain point.
+pub struct RecursiveVerifier<F, Diffusion, const D: usize, const SBOX_DEGREE: u64> {
+    poseidon2: Poseidon2<F, Diffusion, WIDTH, SBOX_DEGREE>,
+    airs: Vec<RecursiveVerifierAir<F, Diffusion, D, SBOX_DEGREE>>,
+    layout: TranscriptLayout,
+    degree_bits: usize,
+    log_blowup: usize,
+    log_quotient_degree: usize,
+    window_size: usize,
+    trace_width: usize,
+    num_queries: usize,
+}
+
+impl<F, Diffusion, const D: usize, const SBOX_DEGREE: u64>
+    RecursiveVerifier<F, Diffusion, D, SBOX_DEGREE>
+where
+    F: PrimeField64 + TwoAdicField + BinomiallyExtendable<D>,
+    Diffusion: DiffusionPermutation<F, WIDTH> + Clone,
+{
+    /// Create a verifier of proofs of `air` under `config`, for traces of height `2^degree_bits`.
+    /// `poseidon2` must be the permutation of `config`'s challenger and Merkle trees.
+    pub fn new<SC, C, A>(
+        config: &SC,
+        air: &A,
+        degree_bits: usize,
+        poseidon2: Poseidon2<F, Diffusion, WIDTH, SBOX_DEGREE>,
+    ) -> Self
+    where
+        SC: StarkGenericConfig<
+            Val = F,
+            Challenge = BinomialExtensionField<F, D>,
+            Pcs = TwoAdicFriPcs<C>,
+        >,
+        C: TwoAdicFriPcsGenericConfig<Val = F, Challenge = BinomialExtensionField<F, D>>,
+        <C::FriMmcs as Mmcs<C::Challenge>>::Commitment: Send + Sync,
+        <C::FriMmcs as Mmcs<C::Challenge>>::Proof: Send + Sync,
+        A: BaseAir<F> + Air<SymbolicAirBuilder<F>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    {
+        let pcs = config.pcs();
+        let fri = pcs.fri_config();
+        assert!(
+            !config.is_zk() && !pcs.is_hiding(),
+            "zero-knowledge proofs aren't supported"
+        );
+        assert!(
+            fri.log_folding_arity == 1 && fri.log_final_poly_len == 0,
+            "only folding by two down to a constant is supported"
+        );
+        assert!(
+            air.preprocessed_width() == 0
+                && air.permutation_width() == 0
+                && air.public_width() == 0,
+            "AIRs with preprocessed or permutation columns, or public values, aren't supported"
+        );
+        assert!(degree_bits > 0, "expected a trace of at least two rows");
+
+        let program = get_checked_constraint_program::<SC, A>(air, 0)
+            .expect("the constraint program should match the AIR");
+        let log_quotient_degree = get_log_quotient_degree::<F, A>(air, 0, false);
+        let log_blowup = fri.log_blowup;
+        assert!(
+            log_blowup >= log_quotient_degree.max(1),
+            "expected the blowup to cover the quotient's degree"
+        );
+        let log_max_height = degree_bits + log_blowup;
+        let num_rounds = degree_bits;
+        let num_queries = fri.num_queries;
+        let window_size = air.window_size();
+        let trace_width = air.width();
+        let quotient_width = D << log_quotient_degree;
+        let layout = TranscriptLayout::new(D, num_rounds, num_queries);
+
+        let mut trees = vec![
+            TreeShape {
+                log_height: log_max_height,
+                leaf_width: trace_width,
+                root_position: layout.trace_root,
+            },
+            TreeShape {
+                log_height: log_max_height,
+                leaf_width: quotient_width,
+                root_position: layout.quotient_root,
+            },
+        ];
+        trees.extend(layout.commit_phase_roots.iter().enumerate().map(
+            |(round, &root_position)| TreeShape {
+                log_height: log_max_height - round - 1,
+                leaf_width: 2 * D,
+                root_position,
+            },
+        ));
+        let openings = (0..window_size)
+            .map(|_| OpeningShape {
+                tree: 0,
+                width: trace_width,
+            })
+            .chain([OpeningShape {
+                tree: 1,
+                width: quotient_width,
+            }])
+            .collect();
+
+        let airs = vec![
+            RecursiveVerifierAir::Poseidon2(Poseidon2LookupAir::new(poseidon2.clone())),
+            RecursiveVerifierAir::Challenger(DuplexChallengerAir::new()),
+            RecursiveVerifierAir::QueryIndex(QueryIndexAir::new(
+                log_max_height,
+                fri.proof_of_work_bits,
+                layout.pow_witness,
+                num_queries,
+            )),
+            RecursiveVerifierAir::MerklePath(MerklePathAir::new(trees)),
+            RecursiveVerifierAir::ReducedOpening(ReducedOpeningAir::new(
+                openings,
+                F::generator(),
+                layout.pcs_alpha,
+            )),
+            RecursiveVerifierAir::FriFold(LinkedFriFoldAir::new(layout.betas.clone(), 2)),
+            RecursiveVerifierAir::OodCheck(OodCheckAir::new(
+                program,
+                window_size,
+                trace_width,
+                degree_bits,
+                log_quotient_degree,
+                layout.alpha,
+                layout.zeta,
+                num_queries,
+            )),
+        ];
+
+        Self {
+            poseidon2,
+            airs,
+            layout,
+            degree_bits,
+            log_blowup,
+            log_quotient_degree,
+            window_size,
+            trace_width,
+            num_queries,
+        }
+    }
+
+    /// The tables, in the order of the traces returned by `generate_traces`.
+    pub fn airs(&self) -> &[RecursiveVerifierAir<F, Diffusion, D, SBOX_DEGREE>] {
+        &self.airs
+    }
+
+    /// Generate the trace of each table for verifying `proof`. The traces only satisfy the tables
+    /// if the proof verifies.
+    ///
+    /// Panics if the proof doesn't have the shape the verifier expects.
+    pub fn generate_traces<SC, C>(&self, proof: &Proof<SC>) -> Vec<RowMajorMatrix<F>>
+    where
+        SC: StarkGenericConfig<
+            Val = F,
+            Challenge = BinomialExtensionField<F, D>,
+            Pcs = TwoAdicFriPcs<C>,
+        >,
+        C: TwoAdicFriPcsGenericConfig<Val = F, Challenge = BinomialExtensionField<F, D>> + Clone,
+        C::InputMmcs:
+            Mmcs<F, Commitment = MerkleCap<F, F, DIGEST_ELEMS>, MultiProof = Vec<Digest<F>>>,
+        C::FriMmcs: Mmcs<
+                BinomialExtensionField<F, D>,
+                Commitment = MerkleCap<F, F, DIGEST_ELEMS>,
+                MultiProof = Vec<Digest<F>>,
+            > + Send,
+        <C::FriMmcs as Mmcs<C::Challenge>>::Proof: Send + Sync,
+        <C::FriMmcs as Mmcs<C::Challenge>>::ProverData: Send + Sync,
+        <C::InputMmcs as Mmcs<C::Val>>::Proof: Send + Sync,
+        <C::InputMmcs as Mmcs<C::Val>>::ProverData: Send + Sync + Sized,
+    {
+        type EF<F, const D: usize> = BinomialExtensionField<F, D>;
+        let perm = &self.poseidon2;
+        let layout = &self.layout;
+        let num_rounds = layout.betas.len();
+        let log_max_height = self.degree_bits + self.log_blowup;
+
+        let commitments = proof.commitments();
+        let opened_values = proof.opened_values();
+        let opening_proof = proof.opening_proof();
+        let fri_proof = opening_proof.fri_proof();
+        let root = |cap: &MerkleCap<F, F, DIGEST_ELEMS>| -> Digest<F> {
+            assert_eq!(cap.digests().len(), 1, "expected a Merkle root");
+            cap.digests()[0]
+        };
+        let trace = opened_values.trace();
+        let quotient_chunks = opened_values.quotient_chunks();
+        assert!(
+            proof.degree_bits() == self.degree_bits
+                && commitments.permutation().is_none()
+                && opened_values.preprocessed().is_empty()
+                && opened_values.permutation().is_empty()
+                && trace.len() == self.window_size
+                && trace.iter().all(|row| row.len() == self.trace_width)
+                && quotient_chunks.len() == D << self.log_quotient_degree
+                && opening_proof.batch_openings().len() == 2
+                && opening_proof.random_codeword().is_none()
+                && fri_proof.commit_phase_commits().len() == num_rounds
+                && fri_proof.query_proofs().len() == self.num_queries
+                && fri_proof.final_poly().len() == 1,
+            "the proof doesn't have the expected shape"
+        );
+
+        // Replay the transcript.
+        let ext = |coeffs: [F; D]| EF::<F, D>::from_base_slice(&coeffs);
+        let mut transcript = ChallengerTranscript::new();
+        transcript.observe_slice(perm, &root(commitments.trace()));
+        let alpha = ext(transcript.sample_array(perm));
+        transcript.observe_slice(perm, &root(commitments.quotient_chunks()));
+        let zeta = ext(transcript.sample_array(perm));
+        let pcs_alpha = ext(transcript.sample_array(perm));
+        let mut commit_phase_roots = Vec::with_capacity(num_rounds);
+        let mut betas = Vec::with_capacity(num_rounds);
+        for commit in fri_proof.commit_phase_commits() {
+            let commit_root = root(commit);
+            transcript.observe_slice(perm, &commit_root);
+            commit_phase_roots.push(commit_root);
+            betas.push(ext(transcript.sample_array(perm)));
+        }
+        let pow_witness = *fri_proof.pow_witness();
+        transcript.observe(perm, pow_witness);
+        let samples = (0..=self.num_queries)
+            .map(|_| transcript.sample(perm))
+            .collect_vec();
+        debug_assert_eq!(transcript.len(), layout.len);
+        let indices = samples[1..]
+            .iter()
+            .map(|sample| sample.as_canonical_u64() as usize & ((1 << log_max_height) - 1))
+            .collect_vec();
+
+        // How many times the other tables read each position of the transcript.
+        let mut multiplicities = vec![0; layout.len];
+        let mut read = |position: usize, len: usize, count: usize| {
+            for multiplicity in &mut multiplicities[position..position + len] {
+                *multiplicity = count;
+            }
+        };
+        let q = self.num_queries;
+        read(layout.trace_root, DIGEST_ELEMS, q);
+        read(layout.alpha, D, 1);
+        read(layout.quotient_root, DIGEST_ELEMS, q);
+        read(layout.zeta, D, 1);
+        read(layout.pcs_alpha, D, q);
+        for (&root_position, &beta_position) in izip!(&layout.commit_phase_roots, &layout.betas) {
+            read(root_position, DIGEST_ELEMS, q);
+            read(beta_position, D, q);
+        }
+        read(layout.pow_witness, 2 + q, 1);
+
+        // Reduce the openings of each query.
+        let g = F::two_adic_generator(log_max_height);
+        let [trace_batch, quotient_batch] = opening_proof.batch_openings() else {
+            unreachable!()
+        };
+        let mut points = itertools::iterate(zeta, |&point| {
+            point * F::two_adic_generator(self.degree_bits)
+        })
+        .take(self.window_size)
+        .collect_vec();
+        points.push(zeta.exp_power_of_2(self.log_quotient_degree));
+        let opened_rows = |batch: &[Vec<Vec<F>>]| -> Vec<Vec<F>> {
+            assert_eq!(batch.len(), self.num_queries);
+            batch
+                .iter()
+                .map(|matrices| {
+                    assert_eq!(matrices.len(), 1, "expected one matrix per batch");
+                    matrices[0].clone()
+                })
+                .collect()
+        };
+        let trace_rows = opened_rows(trace_batch.opened_values());
+        let quotient_rows = opened_rows(quotient_batch.opened_values());
+        let ro_queries = izip!(&indices, &trace_rows, &quotient_rows)
+            .map(|(&index, trace_row, quotient_row)| {
+                let mut rows = vec![trace_row.clone(); self.window_size];
+                rows.push(quotient_row.clone());
+                let mut values = trace.to_vec();
+                values.push(quotient_chunks.to_vec());
+                ReducedOpeningQuery {
+                    index,
+                    x: g.exp_u64(reverse_bits_len(index, log_max_height) as u64),
+                    rows,
+                    values,
+                    points: points.clone(),
+                }
+            })
+            .collect_vec();
+        let RecursiveVerifierAir::ReducedOpening(ro_air) = &self.airs[4] else {
+            unreachable!()
+        };
+        let (ro_trace, reduced_openings) = ro_air.generate_trace(pcs_alpha, &ro_queries);
+
+        // Fold each query.
+        let fold_queries = izip!(&indices, &reduced_openings, fri_proof.query_proofs())
+            .map(|(&index, &reduced_opening, query_proof)| {
+                let mut reduced_openings = [EF::<F, D>::zero(); 32];
+                reduced_openings[log_max_height] = reduced_opening;
+                let siblings = query_proof
+                    .commit_phase_openings()
+                    .iter()
+                    .map(|step| {
+                        assert_eq!(step.sibling_values().len(), 1);
+                        step.sibling_values()[0]
+                    })
+                    .collect();
+                FriFoldQuery {
+                    index,
+                    reduced_openings,
+                    siblings,
+                }
+            })
+            .collect_vec();
+        let RecursiveVerifierAir::FriFold(fold_air) = &self.airs[5] else {
+            unreachable!()
+        };
+        let fold_trace = fold_air.generate_trace(self.log_blowup, &betas, &fold_queries);
+
+        // Open the leaves of each tree, with the siblings of the multi-opening proofs.
+        let mut tree_leaves: Vec<TreeOpening<'_, F>> = vec![
+            (
+                indices.iter().copied().zip(trace_rows).collect(),
+                log_max_height,
+                trace_batch.opening_proof(),
+                self.window_size,
+            ),
+            (
+                indices.iter().copied().zip(quotient_rows).collect(),
+                log_max_height,
+                quotient_batch.opening_proof(),
+                1,
+            ),
+        ];
+        let mut commit_phase_leaves = vec![vec![]; num_rounds];
+        for query in &fold_queries {
+            for (round, row) in fold_query(self.log_blowup, &betas, query)
+                .into_iter()
+                .enumerate()
+            {
+                let e1 = izip!(row.eval, row.sibling, row.e0)
+                    .map(|(eval, sibling, e0)| eval + sibling - e0);
+                let leaf = row.e0.into_iter().chain(e1).collect();
+                commit_phase_leaves[round].push((query.index >> (round + 1), leaf));
+            }
+        }
+        for (round, (leaves, opening_proof)) in
+            izip!(commit_phase_leaves, fri_proof.commit_phase_opening_proofs()).enumerate()
+        {
+            tree_leaves.push((leaves, log_max_height - round - 1, opening_proof, 1));
+        }
+        let paths = tree_leaves
+            .into_iter()
+            .enumerate()
+            .flat_map(
+                |(tree, (leaves, log_height, opening_proof, leaf_multiplicity))| {
+                    let digests: BTreeMap<usize, Digest<F>> = leaves
+                        .iter()
+                        .map(|(leaf_index, leaf)| (*leaf_index, hash_leaf(perm, leaf)))
+                        .collect();
+                    let layers = multi_opening_layers(digests, log_height, opening_proof, perm);
+                    leaves
+                        .into_iter()
+                        .map(|(leaf_index, leaf)| MerklePath {
+                            tree,
+                            leaf_index,
+                            leaf,
+                            siblings: path_siblings(&layers, leaf_index),
+                            leaf_multiplicity,
+                        })
+                        .collect_vec()
+                },
+            )
+            .collect_vec();
+        let RecursiveVerifierAir::MerklePath(merkle_air) = &self.airs[3] else {
+            unreachable!()
+        };
+        let (merkle_trace, merkle_permutations) = merkle_air.generate_trace(&paths, perm);
+
+        let RecursiveVerifierAir::QueryIndex(query_air) = &self.airs[2] else {
+            unreachable!()
+        };
+        let query_trace = query_air.generate_trace(pow_witness, &samples);
+
+        let RecursiveVerifierAir::OodCheck(ood_air) = &self.airs[6] else {
+            unreachable!()
+        };
+        let ood_trace = ood_air.generate_trace(
+            alpha,
+            zeta,
+            trace,
+            quotient_chunks,
+            fri_proof.final_poly()[0],
+        );
+
+        let RecursiveVerifierAir::Challenger(challenger_air) = &self.airs[1] else {
+            unreachable!()
+        };
+        let challenger_trace = challenger_air.generate_trace(&transcript, &multiplicities);
+
+        let RecursiveVerifierAir::Poseidon2(poseidon2_air) = &self.airs[0] else {
+            unreachable!()
+        };
+        let permutations = transcript
+            .permutation_inputs()
+            .iter()
+            .copied()
+            .chain(merkle_permutations)
+            .collect();
+        let poseidon2_trace = poseidon2_air.generate_trace(permutations);
+
+        vec![
+            poseidon2_trace,
+            challenger_trace,
+            query_trace,
+            merkle_trace,
+            ro_trace,
+            fold_trace,
+            ood_trace,
+        ]
+    }
+}
+
+impl<F, Diffusion, const D: usize, const SBOX_DEGREE: u64> BaseAir<F>
+    for RecursiveVerifierAir<F, Diffusion, D, SBOX_DEGREE>
+where
+    F: PrimeField64,
+    Diffusion: Sync,
+{
+    fn width(&self) -> usize {
+        match self {
+            Self::Poseidon2(air) => air.width(),
+            Self::Challenger(air) => BaseAir::<F>::width(air),
+            Self::QueryIndex(air) => air.width(),
+            Self::MerklePath(air) => BaseAir::<F>::width(air),
+            Self::ReducedOpening(air) => air.width(),
+            Self::FriFold(air) => BaseAir::<F>::width(air),
+            Self::OodCheck(air) => air.width(),
+        }
+    }
+}
+
+impl<F, Diffusion, const D: usize, const SBOX_DEGREE: u64> InteractionAir<F>
+    for RecursiveVerifierAir<F, Diffusion, D, SBOX_DEGREE>
+where
+    F: PrimeField64 + TwoAdicField + BinomiallyExtendable<D>,
+    Diffusion: Sync,
+{
+    fn sends(&self) -> Vec<Interaction<F>> {
+        match self {
+            Self::Poseidon2(air) => air.sends(),
+            Self::Challenger(air) => air.sends(),
+            Self::QueryIndex(air) => air.sends(),
+            Self::MerklePath(air) => air.sends(),
+            Self::ReducedOpening(air) => air.sends(),
+            Self::FriFold(air) => air.sends(),
+            Self::OodCheck(air) => air.sends(),
+        }
+    }
+
+    fn receives(&self) -> Vec<Interaction<F>> {
+        match self {
+            Self::Poseidon2(air) => air.receives(),
+            Self::Challenger(air) => air.receives(),
+            Self::QueryIndex(air) => air.receives(),
+            Self::MerklePath(air) => air.receives(),
+            Self::ReducedOpening(air) => air.receives(),
+            Self::FriFold(air) => air.receives(),
+            Self::OodCheck(air) => air.receives(),
+        }
+    }
+}
+
+impl<AB, Diffusion, const D: usize, const SBOX_DEGREE: u64> Air<AB>
+    for RecursiveVerifierAir<AB::F, Diffusion, D, SBOX_DEGREE>
+where
+    AB: AirBuilder,
+    AB::F: PrimeField64 + TwoAdicField + BinomiallyExtendable<D>,
+    Diffusion: DiffusionPermutation<AB::Expr, WIDTH> + Sync,
+{
+    fn eval(&self, builder: &mut AB) {
+        match self {
+            Self::Poseidon2(air) => air.eval(builder),
+            Self::Challenger(air) => air.eval(builder),
+            Self::QueryIndex(air) => air.eval(builder),
+            Self::MerklePath(air) => air.eval(builder),
+            Self::ReducedOpening(air) => air.eval(builder),
+            Self::FriFold(air) => air.eval(builder),
+            Self::OodCheck(air) => air.eval(builder),
+        }
+    }
+}
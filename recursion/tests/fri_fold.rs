@@ -0,0 +1,151 @@
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::{Radix2Dit, Radix2DitParallel, TwoAdicSubgroupDft};
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractExtensionField, AbstractField, Field};
+use p3_fri::{fold_even_odd, FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_recursion::{fri_fold_public_values, generate_fri_fold_trace, FriFoldAir, FriFoldQuery};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{prove, verify, PublicRow, StarkConfig, VerificationError};
+use p3_util::reverse_slice_index_bits;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+type Val = BabyBear;
+type Challenge = BinomialExtensionField<Val, 4>;
+
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+const LOG_BLOWUP: usize = 1;
+const NUM_QUERIES: usize = 4;
+
+fn val_mmcs(perm: &Perm) -> ValMmcs {
+    ValMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm.clone()))
+}
+
+/// The bit-reversed evaluations over the subgroup of order `2^(log_degree + LOG_BLOWUP)` of a
+/// random polynomial of degree less than `2^log_degree`.
+fn random_codeword(rng: &mut ChaCha20Rng, log_degree: usize) -> Vec<Challenge> {
+    let mut coeffs = RowMajorMatrix::<Val>::rand(rng, 1 << log_degree, 4);
+    coeffs.expand_to_height(1 << (log_degree + LOG_BLOWUP));
+    let evals = Radix2Dit::default().dft_batch(coeffs);
+    let mut codeword: Vec<Challenge> = evals.rows().map(Challenge::from_base_slice).collect();
+    reverse_slice_index_bits(&mut codeword);
+    codeword
+}
+
+/// Fold random low-degree input codewords of several heights as the FRI commit phase does, and
+/// return the folding challenges, the final constant, and some queries with their witnesses.
+fn fri_fold_for_testing(
+    rng: &mut ChaCha20Rng,
+) -> (Vec<Challenge>, Challenge, Vec<FriFoldQuery<Challenge>>) {
+    let log_degrees = [3, 4, 5];
+    let mut inputs: [Option<Vec<Challenge>>; 32] = Default::default();
+    for log_degree in log_degrees {
+        inputs[log_degree + LOG_BLOWUP] = Some(random_codeword(rng, log_degree));
+    }
+    let log_max_height = log_degrees.iter().max().unwrap() + LOG_BLOWUP;
+    let betas: Vec<Challenge> = (LOG_BLOWUP..log_max_height).map(|_| rng.gen()).collect();
+
+    // The codeword of each commit phase round, including the inputs of its height.
+    let mut codewords = vec![inputs[log_max_height].clone().unwrap()];
+    for (round, &beta) in betas.iter().enumerate() {
+        let mut folded = fold_even_odd(codewords[round].clone(), beta);
+        if let Some(input) = &inputs[log_max_height - round - 1] {
+            for (f, &i) in folded.iter_mut().zip(input) {
+                *f += i;
+            }
+        }
+        codewords.push(folded);
+    }
+    let final_codeword = codewords.pop().unwrap();
+    let final_poly = final_codeword[0];
+    assert!(
+        final_codeword.iter().all(|&e| e == final_poly),
+        "folding should reach a constant codeword"
+    );
+
+    let queries = (0..NUM_QUERIES)
+        .map(|_| {
+            let index = rng.gen_range(0..1 << log_max_height);
+            FriFoldQuery {
+                index,
+                reduced_openings: core::array::from_fn(|log_height| {
+                    inputs[log_height]
+                        .as_ref()
+                        .map_or(Challenge::zero(), |input| {
+                            input[index >> (log_max_height - log_height)]
+                        })
+                }),
+                siblings: codewords
+                    .iter()
+                    .enumerate()
+                    .map(|(round, codeword)| codeword[(index >> round) ^ 1])
+                    .collect(),
+            }
+        })
+        .collect();
+
+    (betas, final_poly, queries)
+}
+
+fn prove_fri_folding(tamper_public_values: bool) -> Result<(), VerificationError> {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut rng);
+    let (betas, final_poly, queries) = fri_fold_for_testing(&mut rng);
+    let air = FriFoldAir::<4> {
+        num_fold_steps: betas.len(),
+    };
+    let trace = generate_fri_fold_trace(LOG_BLOWUP, &betas, &queries);
+    let mut public_values = fri_fold_public_values(&betas, final_poly);
+    if tamper_public_values {
+        *public_values.last_mut().unwrap() += Val::one();
+    }
+    let public_values = PublicRow(public_values);
+
+    let fri_config = FriConfig {
+        log_blowup: 2,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 40,
+        proof_of_work_bits: 8,
+        mmcs: ChallengeMmcs::new(val_mmcs(&perm)),
+    };
+    let pcs = Pcs::new(fri_config, Dft {}, val_mmcs(&perm));
+    let config = MyConfig::new(pcs);
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut challenger, trace, &public_values);
+
+    let mut challenger = Challenger::new(perm);
+    verify(&config, &air, &mut challenger, &proof, &public_values)
+}
+
+#[test]
+fn test_fri_fold_air() -> Result<(), VerificationError> {
+    prove_fri_folding(false)
+}
+
+/// In debug builds the prover checks the constraints and panics; otherwise the proof must be
+/// rejected.
+#[test]
+#[cfg_attr(
+    debug_assertions,
+    should_panic(expected = "constraints had nonzero value")
+)]
+fn test_fri_fold_air_wrong_final_poly() {
+    assert!(prove_fri_folding(true).is_err());
+}
@@ -0,0 +1,138 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractField, Field};
+use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_multi_stark::VerificationError;
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_recursion::RecursiveVerifier;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{PublicRow, StarkConfig};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+type Val = BabyBear;
+type Challenge = BinomialExtensionField<Val, 4>;
+
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+const LOG_HEIGHT: usize = 3;
+
+/// The AIR whose proofs are verified: a Fibonacci sequence `a, b` starting from `0`, with a third
+/// column holding the cube of `a`, so that the quotient has two chunks.
+struct FibonacciCubeAir;
+
+impl<F> BaseAir<F> for FibonacciCubeAir {
+    fn width(&self) -> usize {
+        3
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for FibonacciCubeAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+        let (a, b, cube) = (local[0], local[1], local[2]);
+
+        builder.when_first_row().assert_zero(a);
+        builder.assert_eq(cube, a * a * a);
+        let mut when_transition = builder.when_transition();
+        when_transition.assert_eq(next[0], b);
+        when_transition.assert_eq(next[1], a + b);
+    }
+}
+
+fn fibonacci_cube_trace(b: u32) -> RowMajorMatrix<Val> {
+    let (mut a, mut b) = (Val::zero(), Val::from_canonical_u32(b));
+    let mut values = Vec::new();
+    for _ in 0..1 << LOG_HEIGHT {
+        values.extend([a, b, a.cube()]);
+        (a, b) = (b, a + b);
+    }
+    RowMajorMatrix::new(values, 3)
+}
+
+fn config(perm: &Perm, log_blowup: usize, num_queries: usize) -> MyConfig {
+    let val_mmcs = ValMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm.clone()));
+    let fri_config = FriConfig {
+        log_blowup,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries,
+        proof_of_work_bits: 4,
+        mmcs: ChallengeMmcs::new(val_mmcs.clone()),
+    };
+    MyConfig::new(Pcs::new(fri_config, Dft {}, val_mmcs))
+}
+
+/// Prove the verification of two inner proofs, starting from `b = 1` and `b = 2`, using the tables
+/// of the first and, if `mix_proofs` is set, the out-of-domain check of the second.
+fn prove_recursive_verification(mix_proofs: bool) -> Result<(), VerificationError> {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut rng);
+    let inner_config = config(&perm, 1, 2);
+    let air = FibonacciCubeAir;
+    let proofs = [1, 2].map(|b| {
+        let mut challenger = Challenger::new(perm.clone());
+        let proof = p3_uni_stark::prove(
+            &inner_config,
+            &air,
+            &mut challenger,
+            fibonacci_cube_trace(b),
+            &PublicRow(vec![]),
+        );
+        let mut challenger = Challenger::new(perm.clone());
+        p3_uni_stark::verify(
+            &inner_config,
+            &air,
+            &mut challenger,
+            &proof,
+            &PublicRow(vec![]),
+        )
+        .expect("the inner proof should verify");
+        proof
+    });
+
+    let verifier = RecursiveVerifier::new(&inner_config, &air, LOG_HEIGHT, perm.clone());
+    let mut traces = verifier.generate_traces(&proofs[0]);
+    if mix_proofs {
+        let other = verifier.generate_traces(&proofs[1]);
+        *traces.last_mut().unwrap() = other.last().unwrap().clone();
+    }
+
+    let outer_config = config(&perm, 2, 40);
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = p3_multi_stark::prove(&outer_config, verifier.airs(), &mut challenger, traces);
+    let mut challenger = Challenger::new(perm);
+    p3_multi_stark::verify(&outer_config, verifier.airs(), &mut challenger, &proof)
+}
+
+#[test]
+fn test_recursive_verifier() -> Result<(), VerificationError> {
+    prove_recursive_verification(false)
+}
+
+#[test]
+fn test_recursive_verifier_mixed_proofs() {
+    let result = prove_recursive_verification(true);
+    assert!(matches!(
+        result,
+        Err(VerificationError::NonZeroCumulativeSum)
+    ));
+}
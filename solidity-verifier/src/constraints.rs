@@ -0,0 +1,194 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use p3_air::Air;
+use p3_field::{AbstractExtensionField, ExtensionField, Field, PrimeField64};
+use p3_uni_stark::{
+    get_constraint_program, ConstraintProgram, Entry, Instruction, Register, SymbolicAirBuilder,
+};
+
+/// Generate a Solidity library named `library_name`, which evaluates the constraints of `air` at
+/// the out-of-domain point and folds them with powers of `alpha`, like `VerifierConstraintFolder`.
+///
+/// The library works over the binomial extension `EF` of the prime field `F`. An extension element
+/// is a `uint256[D]` of canonical coefficients in the basis `1, X, ..., X^{D-1}`, where `X^D = W`.
+/// The opened values are passed in an `Openings` struct, whose matrices are indexed by the row
+/// offset within the window, then by the column, as in `OpenedValues`. The permutation columns are
+/// extension elements, as after the verifier unflattens them, and the public values are those
+/// interpolated at the out-of-domain point.
+///
+/// The constraints are compiled with `get_constraint_program`, so each common subexpression is
+/// computed once. Like the symbolic builder, this assumes that extension field constraints only
+/// involve base field constants.
+pub fn generate_constraint_library<F, EF, A>(
+    air: &A,
+    public_width: usize,
+    library_name: &str,
+) -> String
+where
+    F: PrimeField64,
+    EF: ExtensionField<F>,
+    A: Air<SymbolicAirBuilder<F>>,
+{
+    let program = get_constraint_program::<F, A>(air, public_width);
+
+    // Every value is an extension field element in Solidity, so each instruction gets a slot of `t`
+    // in turn, whichever kind of register it writes to.
+    let mut slots = BTreeMap::new();
+    let mut values = Vec::with_capacity(program.instructions().len());
+    for (instruction, register) in program
+        .instructions()
+        .iter()
+        .zip(output_registers(&program))
+    {
+        slots.insert(register, values.len());
+        values.push(lower(instruction, program.constants(), &slots));
+    }
+
+    let mut out = LIBRARY_HEADER
+        .replace("$NAME", library_name)
+        .replace("$P", &F::ORDER_U64.to_string())
+        .replace("$W", &binomial_w::<F, EF>().as_canonical_u64().to_string())
+        .replace("$D", &<EF as AbstractExtensionField<F>>::D.to_string())
+        .replace("$NUM_VALUES", &values.len().to_string());
+
+    for (i, value) in values.iter().enumerate() {
+        writeln!(out, "        t[{i}] = {value};").unwrap();
+    }
+    for constraint in program.constraints() {
+        let slot = slots[constraint];
+        writeln!(out, "        acc = add(mul(acc, alpha), t[{slot}]);").unwrap();
+    }
+    out.push_str("    }\n}\n");
+    out
+}
+
+/// The library up to the body of `evalFolded`, which assigns each slot of `t` in turn, then folds the
+/// constraints into `acc`.
+const LIBRARY_HEADER: &str = r#"// SPDX-License-Identifier: MIT OR Apache-2.0
+// Generated by p3-solidity-verifier.
+pragma solidity ^0.8.20;
+
+/// Evaluation of an AIR's constraints at the out-of-domain point.
+///
+/// Extension field elements are arrays of `D` coefficients modulo `P`, in the basis
+/// `1, X, ..., X^(D-1)`, where `X^D = W`.
+library $NAME {
+    uint256 internal constant P = $P;
+    uint256 internal constant W = $W;
+    uint256 internal constant D = $D;
+
+    /// The opened values, indexed by the row offset within the window and then by the column.
+    struct Openings {
+        uint256[$D][][] preprocessed;
+        uint256[$D][][] main;
+        uint256[$D][][] permutation;
+        uint256[$D][] permutationChallenges;
+        uint256[$D][][] publicValues;
+        uint256[$D] isFirstRow;
+        uint256[$D] isLastRow;
+        /// The selector for windows of `i + 1` rows, at index `i`.
+        uint256[$D][] transitionSelectors;
+    }
+
+    function fromBase(uint256 a) internal pure returns (uint256[$D] memory c) {
+        c[0] = a;
+    }
+
+    function add(uint256[$D] memory a, uint256[$D] memory b) internal pure returns (uint256[$D] memory c) {
+        for (uint256 i = 0; i < D; i++) {
+            c[i] = addmod(a[i], b[i], P);
+        }
+    }
+
+    function sub(uint256[$D] memory a, uint256[$D] memory b) internal pure returns (uint256[$D] memory c) {
+        for (uint256 i = 0; i < D; i++) {
+            c[i] = addmod(a[i], P - b[i], P);
+        }
+    }
+
+    function neg(uint256[$D] memory a) internal pure returns (uint256[$D] memory c) {
+        for (uint256 i = 0; i < D; i++) {
+            c[i] = (P - a[i]) % P;
+        }
+    }
+
+    function mul(uint256[$D] memory a, uint256[$D] memory b) internal pure returns (uint256[$D] memory c) {
+        for (uint256 i = 0; i < D; i++) {
+            for (uint256 j = 0; j < D; j++) {
+                uint256 term = mulmod(a[i], b[j], P);
+                if (i + j < D) {
+                    c[i + j] = addmod(c[i + j], term, P);
+                } else {
+                    c[i + j - D] = addmod(c[i + j - D], mulmod(term, W, P), P);
+                }
+            }
+        }
+    }
+
+    /// The constraints folded with powers of `alpha`, as in `VerifierConstraintFolder`.
+    function evalFolded(Openings memory o, uint256[$D] memory alpha) internal pure returns (uint256[$D] memory acc) {
+        uint256[$D][] memory t = new uint256[$D][]($NUM_VALUES);
+"#;
+
+/// The register each instruction of `program` writes to, in order. Base and extension registers
+/// are numbered separately, in the order of the instructions writing to them.
+pub(crate) fn output_registers<F: Field>(program: &ConstraintProgram<F>) -> Vec<Register> {
+    let (mut num_base, mut num_ext) = (0, 0);
+    program
+        .instructions()
+        .iter()
+        .map(|instruction| {
+            if instruction.is_ext() {
+                num_ext += 1;
+                Register::Ext(num_ext - 1)
+            } else {
+                num_base += 1;
+                Register::Base(num_base - 1)
+            }
+        })
+        .collect()
+}
+
+/// The constant `W` such that `X^D = W` in the extension field `EF`.
+pub(crate) fn binomial_w<F: Field, EF: ExtensionField<F>>() -> F {
+    let d = <EF as AbstractExtensionField<F>>::D;
+    if d == 1 {
+        return F::zero();
+    }
+    let x_pow_d = EF::monomial(1).exp_u64(d as u64);
+    assert!(
+        x_pow_d.is_in_basefield(),
+        "the extension field must be a binomial extension"
+    );
+    x_pow_d.as_base_slice()[0]
+}
+
+/// The Solidity expression for the value of `instruction`, given the slot holding each register.
+fn lower<F: PrimeField64>(
+    instruction: &Instruction,
+    constants: &[F],
+    slots: &BTreeMap<Register, usize>,
+) -> String {
+    let t = |r: &Register| format!("t[{}]", slots[r]);
+    match instruction {
+        Instruction::Variable { entry, index } => match entry {
+            Entry::Preprocessed { offset } => format!("o.preprocessed[{offset}][{index}]"),
+            Entry::Main { offset } => format!("o.main[{offset}][{index}]"),
+            Entry::Permutation { offset } => format!("o.permutation[{offset}][{index}]"),
+            Entry::Public { offset } => format!("o.publicValues[{offset}][{index}]"),
+            Entry::Challenge => format!("o.permutationChallenges[{index}]"),
+        },
+        Instruction::IsFirstRow => "o.isFirstRow".into(),
+        Instruction::IsLastRow => "o.isLastRow".into(),
+        Instruction::IsTransitionWindow(size) => format!("o.transitionSelectors[{}]", size - 1),
+        Instruction::Constant(i) => format!("fromBase({})", constants[*i].as_canonical_u64()),
+        Instruction::Add(x, y) => format!("add({}, {})", t(x), t(y)),
+        Instruction::Sub(x, y) => format!("sub({}, {})", t(x), t(y)),
+        Instruction::Neg(x) => format!("neg({})", t(x)),
+        Instruction::Mul(x, y) => format!("mul({}, {})", t(x), t(y)),
+    }
+}
@@ -0,0 +1,1268 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::marker::PhantomData;
+
+use p3_air::{Air, BaseAir};
+use p3_challenger::SerializingChallenger32;
+use p3_commit::{ExtensionMmcs, Mmcs};
+use p3_field::{AbstractExtensionField, ExtensionField, Field, PrimeField32, TwoAdicField};
+use p3_fri::{TwoAdicFriPcs, TwoAdicFriPcsGenericConfig};
+use p3_keccak::Keccak256Hash;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_symmetric::{CompressionFunctionFromHasher, CryptographicHasher, SerializingHasher32};
+use p3_uni_stark::{
+    get_checked_constraint_program, get_log_quotient_degree, ConstraintProgram, Entry, Instruction,
+    Proof, StarkGenericConfig, SymbolicAirBuilder, VerifierConstraintFolder,
+};
+use p3_util::reverse_bits_len;
+
+use crate::constraints::{binomial_w, output_registers};
+
+/// The Merkle trees of a config the contract can verify: rows are hashed with Keccak-256 as the
+/// little-endian bytes of their elements, and pairs of digests are compressed with Keccak-256.
+pub type KeccakMmcs<F> = FieldMerkleTreeMmcs<
+    F,
+    SerializingHasher32<Keccak256Hash>,
+    CompressionFunctionFromHasher<u8, Keccak256Hash, 2, 32>,
+    32,
+    u8,
+>;
+
+/// The challenger of a config the contract can verify.
+pub type KeccakChallenger<F> = SerializingChallenger32<F, Keccak256Hash>;
+
+/// The Solidity signature of the contract's entry point.
+const VERIFY_SIGNATURE: &[u8] = b"verify(uint256[],bytes)";
+
+/// Solidity reserves the memory below this address.
+const FIRST_FREE_ADDRESS: usize = 0x80;
+
+/// A generator of Solidity contracts verifying `p3_uni_stark` proofs of one AIR, for traces of one
+/// height, along with the encoding of proofs as the contract's calldata.
+///
+/// Proofs must use a `TwoAdicFriPcs` whose Merkle trees are `KeccakMmcs`, with a
+/// `KeccakChallenger`, so that every hash is a `keccak256` on the EVM, and must fold by two in each
+/// FRI round. Zero-knowledge and hiding are not supported, nor are AIRs with preprocessed or
+/// permutation columns.
+///
+/// The contract replays the transcript, checks the proof of work, opens the trace, quotient and
+/// commit phase trees at each query, reduces the openings, folds them down to the final
+/// polynomial, and finally checks that the AIR's folded constraints equal `quotient * Z_H` at the
+/// out-of-domain point. Field arithmetic is in inline assembly, as is everything else.
+pub struct SolidityVerifier<F: Field, EF> {
+    program: ConstraintProgram<F>,
+    degree_bits: usize,
+    log_blowup: usize,
+    log_quotient_degree: usize,
+    window_size: usize,
+    trace_width: usize,
+    public_width: usize,
+    num_queries: usize,
+    proof_of_work_bits: usize,
+    log_final_poly_len: usize,
+    input_cap_height: usize,
+    fri_cap_height: usize,
+    _phantom: PhantomData<EF>,
+}
+
+/// The offsets, in 32-byte words, of the fixed-size sections of an encoded proof. They're followed
+/// by the multi-opening proofs of the trace tree, the quotient tree and each commit phase tree, each
+/// as a count of digests followed by the digests.
+struct ProofLayout {
+    trace_cap: usize,
+    quotient_cap: usize,
+    /// The opened values of the trace at each point of the window, then of the quotient chunks.
+    opened_values: usize,
+    commit_phase_caps: Vec<usize>,
+    final_poly: usize,
+    pow_witness: usize,
+    trace_rows: usize,
+    quotient_rows: usize,
+    /// The sibling of each query in each commit phase round, round by round.
+    siblings: usize,
+    len: usize,
+}
+
+/// Allocates the contract's variables at fixed memory addresses.
+struct Memory {
+    next: usize,
+}
+
+impl Memory {
+    fn alloc(&mut self, words: usize) -> usize {
+        self.next += 32 * words;
+        self.next - 32 * words
+    }
+}
+
+impl<F, EF> SolidityVerifier<F, EF>
+where
+    F: PrimeField32 + TwoAdicField,
+    EF: ExtensionField<F>,
+{
+    /// Create a verifier of proofs of `air` under `config`, for traces of height `2^degree_bits`
+    /// and `public_width` public values.
+    pub fn new<SC, C, A>(config: &SC, air: &A, degree_bits: usize, public_width: usize) -> Self
+    where
+        SC: StarkGenericConfig<Val = F, Challenge = EF, Pcs = TwoAdicFriPcs<C>>,
+        C: TwoAdicFriPcsGenericConfig<
+            Val = F,
+            Challenge = EF,
+            Challenger = KeccakChallenger<F>,
+            InputMmcs = KeccakMmcs<F>,
+            FriMmcs = ExtensionMmcs<F, EF, KeccakMmcs<F>>,
+        >,
+        A: BaseAir<F> + Air<SymbolicAirBuilder<F>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    {
+        let pcs = config.pcs();
+        let fri = pcs.fri_config();
+        assert!(
+            !config.is_zk() && !pcs.is_hiding(),
+            "zero-knowledge proofs aren't supported"
+        );
+        assert_eq!(fri.log_folding_arity, 1, "only folding by two is supported");
+        assert!(
+            air.preprocessed_width() == 0
+                && air.permutation_width() == 0
+                && air.num_permutation_challenges() == 0,
+            "AIRs with preprocessed or permutation columns aren't supported"
+        );
+        let d = <EF as AbstractExtensionField<F>>::D;
+        assert_eq!(
+            (F::ORDER_U32 - 1) % d as u32,
+            0,
+            "the extension degree must divide the order of the field's multiplicative group"
+        );
+        // Check that the extension is binomial.
+        binomial_w::<F, EF>();
+
+        let program = get_checked_constraint_program::<SC, A>(air, public_width)
+            .expect("the constraint program should match the AIR");
+        let log_quotient_degree = get_log_quotient_degree::<F, A>(air, public_width, false);
+        assert!(
+            fri.log_blowup >= log_quotient_degree.max(1),
+            "expected the blowup to cover the quotient's degree"
+        );
+        assert!(
+            degree_bits > 0 && degree_bits + fri.log_blowup <= F::TWO_ADICITY.min(31),
+            "unsupported trace height"
+        );
+
+        Self {
+            program,
+            degree_bits,
+            log_blowup: fri.log_blowup,
+            log_quotient_degree,
+            window_size: air.window_size(),
+            trace_width: air.width(),
+            public_width,
+            num_queries: fri.num_queries,
+            proof_of_work_bits: fri.proof_of_work_bits,
+            log_final_poly_len: fri.log_final_poly_len,
+            input_cap_height: pcs.input_mmcs().cap_height(),
+            fri_cap_height: fri.mmcs.cap_height(),
+            _phantom: PhantomData,
+        }
+    }
+
+    fn extension_degree(&self) -> usize {
+        <EF as AbstractExtensionField<F>>::D
+    }
+
+    /// The log height of the committed LDEs.
+    fn log_max_height(&self) -> usize {
+        self.degree_bits + self.log_blowup
+    }
+
+    fn num_rounds(&self) -> usize {
+        let log_final_height = self
+            .log_max_height()
+            .min(self.log_blowup + self.log_final_poly_len);
+        self.log_max_height() - log_final_height
+    }
+
+    fn final_poly_len(&self) -> usize {
+        1 << (self.log_max_height() - self.num_rounds() - self.log_blowup)
+    }
+
+    fn quotient_width(&self) -> usize {
+        self.extension_degree() << self.log_quotient_degree
+    }
+
+    /// The log of the number of digests in the cap of the trace and quotient trees.
+    fn input_cap_height(&self) -> usize {
+        self.input_cap_height.min(self.log_max_height())
+    }
+
+    /// The log of the number of digests in the cap of the tree of commit phase round `round`.
+    fn round_cap_height(&self, round: usize) -> usize {
+        self.fri_cap_height.min(self.log_max_height() - round - 1)
+    }
+
+    fn layout(&self) -> ProofLayout {
+        let d = self.extension_degree();
+        let q = self.num_queries;
+        let mut len = 0;
+        let mut next = |n: usize| {
+            len += n;
+            len - n
+        };
+        let trace_cap = next(1 << self.input_cap_height());
+        let quotient_cap = next(1 << self.input_cap_height());
+        let opened_values = next((self.window_size * self.trace_width + self.quotient_width()) * d);
+        let commit_phase_caps = (0..self.num_rounds())
+            .map(|round| next(1 << self.round_cap_height(round)))
+            .collect();
+        let final_poly = next(self.final_poly_len() * d);
+        let pow_witness = next(1);
+        let trace_rows = next(q * self.trace_width);
+        let quotient_rows = next(q * self.quotient_width());
+        let siblings = next(self.num_rounds() * q * d);
+        ProofLayout {
+            trace_cap,
+            quotient_cap,
+            opened_values,
+            commit_phase_caps,
+            final_poly,
+            pow_witness,
+            trace_rows,
+            quotient_rows,
+            siblings,
+            len,
+        }
+    }
+
+    /// Generate the source of a Solidity contract named `contract_name`, whose
+    /// `verify(uint256[] publicValues, bytes proof)` returns whether `proof`, as encoded by
+    /// `encode_proof`, is a valid proof with the given public values.
+    pub fn contract(&self, contract_name: &str) -> String {
+        let d = self.extension_degree();
+        let ext_bytes = 32 * d;
+        let p = F::ORDER_U32 as u64;
+        let q = self.num_queries;
+        let num_rounds = self.num_rounds();
+        let log_max_height = self.log_max_height();
+        let layout = self.layout();
+        let num_opened = self.window_size * self.trace_width + self.quotient_width();
+        let leaf_width = self.trace_width.max(self.quotient_width()).max(2 * d);
+        let canonical = |x: F| x.as_canonical_u32().to_string();
+
+        let mut memory = Memory {
+            next: FIRST_FREE_ADDRESS,
+        };
+        let mut ext = |n: usize| memory.alloc(n * d);
+        let m_mul = ext(1);
+        let m_inv_prod = ext(1);
+        let m_inv_frob = ext(1);
+        let m_inv_norm = ext(1);
+        let m_opened = ext(num_opened);
+        let m_public = ext(self.public_width);
+        let m_final_poly = ext(self.final_poly_len());
+        let m_alpha = ext(1);
+        let m_zeta = ext(1);
+        let m_pcs_alpha = ext(1);
+        let m_betas = ext(num_rounds);
+        let m_zh = ext(1);
+        let m_tmp = ext(1);
+        let m_is_first = ext(1);
+        let m_is_last = ext(1);
+        let m_transitions = ext(self.window_size.max(1));
+        let m_acc = ext(1);
+        let m_quotient = ext(1);
+        let m_part = ext(1);
+        let m_zeta_pow = ext(1);
+        let m_points = ext(self.window_size + 1);
+        let m_opened_combined = ext(self.window_size + 1);
+        let m_alpha_offsets = ext(self.window_size + 1);
+        let m_alpha_width = ext(1);
+        let m_row_trace = ext(1);
+        let m_row_quotient = ext(1);
+        let m_num = ext(1);
+        let m_den = ext(1);
+        let m_term = ext(1);
+        let m_diff = ext(1);
+        let m_folded = ext(q);
+        let m_sibling = ext(1);
+        let m_t1 = ext(1);
+        let m_t2 = ext(1);
+        let m_eval = ext(1);
+        let m_x = memory.alloc(q);
+        let m_x_inv = memory.alloc(q);
+        let m_indices = memory.alloc(q);
+        let m_order = memory.alloc(q);
+        // Each leaf element is written as a word at a 4-byte stride, overrunning by 28 bytes.
+        let m_leaf = memory.alloc(leaf_width.div_ceil(8) + 1);
+        let m_nodes = memory.alloc(2 * q);
+
+        let constraints = self.constraint_code(
+            &mut memory,
+            m_opened,
+            m_public,
+            [m_is_first, m_is_last, m_transitions],
+            m_acc,
+            m_alpha,
+        );
+        let m_ts_len = memory.alloc(1);
+        let m_ts_pos = memory.alloc(1);
+        let m_ts_out = memory.alloc(1);
+        // The transcript's input grows past the end of the allocated memory.
+        let m_ts_buf = memory.alloc(0);
+
+        // The constants of the Frobenius automorphisms: X^(p^k) = z^k X, for a D-th root of
+        // unity z.
+        let z = binomial_w::<F, EF>().exp_u64((p - 1) / d as u64);
+        let mut frobenius = String::new();
+        for k in 1..d {
+            for i in 0..d {
+                let c = z.exp_u64((i * k) as u64);
+                let a = format!("mload(add(a, {}))", 32 * i);
+                let value = if c.is_one() {
+                    a
+                } else {
+                    format!("mulmod({a}, {}, {p})", canonical(c))
+                };
+                writeln!(
+                    frobenius,
+                    "                mstore({}, {value})",
+                    m_inv_frob + 32 * i
+                )
+                .unwrap();
+            }
+            writeln!(
+                frobenius,
+                "                extMul({m_inv_prod}, {m_inv_prod}, {m_inv_frob})"
+            )
+            .unwrap();
+        }
+
+        let mut observe_commits = String::new();
+        for round in 0..num_rounds {
+            writeln!(
+                observe_commits,
+                "            observeCap(add(p, {}), {})\n            sampleExt({})",
+                32 * layout.commit_phase_caps[round],
+                1 << self.round_cap_height(round),
+                m_betas + round * ext_bytes
+            )
+            .unwrap();
+        }
+
+        let g = F::two_adic_generator(self.degree_bits);
+        let mut transitions = String::new();
+        let mut g_inv_pow = F::one();
+        for size in 1..self.window_size {
+            g_inv_pow *= g.inverse();
+            let selector = m_transitions + size * ext_bytes;
+            writeln!(
+                transitions,
+                "            extCopy({selector}, {m_zeta})\n            \
+                 mstore({selector}, addmod(mload({selector}), {}, {p}))\n            \
+                 extMul({selector}, {selector}, {})",
+                canonical(-g_inv_pow),
+                selector - ext_bytes
+            )
+            .unwrap();
+        }
+
+        // Recompose the quotient from the parts, each of which is flattened into D chunks.
+        let mut quotient = String::new();
+        let num_parts = 1 << self.log_quotient_degree;
+        writeln!(quotient, "            extSetBase({m_zeta_pow}, 1)").unwrap();
+        for k in 0..num_parts {
+            let part = reverse_bits_len(k, self.log_quotient_degree);
+            writeln!(quotient, "            extSetBase({m_part}, 0)").unwrap();
+            for i in (0..d).rev() {
+                let chunk =
+                    m_opened + (self.window_size * self.trace_width + part * d + i) * ext_bytes;
+                if i + 1 < d {
+                    writeln!(quotient, "            extMulX({m_part}, {m_part})").unwrap();
+                }
+                writeln!(quotient, "            extAdd({m_part}, {m_part}, {chunk})").unwrap();
+            }
+            writeln!(
+                quotient,
+                "            extMul({m_part}, {m_part}, {m_zeta_pow})\n            \
+                 extAdd({m_quotient}, {m_quotient}, {m_part})\n            \
+                 extMul({m_zeta_pow}, {m_zeta_pow}, {m_zeta})"
+            )
+            .unwrap();
+        }
+
+        // x_0 is the product of g_L^(2^(L - 1 - i)) over the set bits i > 0 of the index.
+        let g_max = F::two_adic_generator(log_max_height);
+        let mut x0_bits = String::new();
+        for bit in 1..log_max_height {
+            let c = g_max.exp_power_of_2(log_max_height - 1 - bit);
+            writeln!(
+                x0_bits,
+                "                if and(shr({bit}, index), 1) {{\n                    \
+                 x := mulmod(x, {}, {p})\n                    \
+                 xInv := mulmod(xInv, {}, {p})\n                }}",
+                canonical(c),
+                canonical(c.inverse())
+            )
+            .unwrap();
+        }
+
+        let mut fold_rounds = String::new();
+        for round in 0..num_rounds {
+            let cap_height = self.round_cap_height(round);
+            writeln!(
+                fold_rounds,
+                "            n := foldRound({round}, {}, add(p, {}))",
+                m_betas + round * ext_bytes,
+                32 * (layout.siblings + round * q * d),
+            )
+            .unwrap();
+            writeln!(
+                fold_rounds,
+                "            cursor := verifyMultiProof(n, {}, add(p, {}), {}, cursor, end)",
+                log_max_height - round - 1 - cap_height,
+                32 * layout.commit_phase_caps[round],
+                1 << cap_height,
+            )
+            .unwrap();
+        }
+
+        let two = F::two();
+        let vars = [
+            ("$NAME", contract_name.to_string()),
+            ("$P_MINUS_2", (p - 2).to_string()),
+            ("$P_MINUS_1", (p - 1).to_string()),
+            ("$P", p.to_string()),
+            ("$W", canonical(binomial_w::<F, EF>())),
+            ("$D", d.to_string()),
+            ("$EXT_BYTES", ext_bytes.to_string()),
+            ("$EXT_TOP", (32 * (d - 1)).to_string()),
+            ("$MASK", ((1u64 << F::bits()) - 1).to_string()),
+            (
+                "$POW_MASK",
+                ((1u64 << self.proof_of_work_bits) - 1).to_string(),
+            ),
+            ("$INDEX_MASK", ((1u64 << log_max_height) - 1).to_string()),
+            ("$NEG_HALF", canonical(-two.inverse())),
+            ("$NEG_G_INV", canonical(-g.inverse())),
+            ("$G", canonical(g)),
+            ("$SHIFT", canonical(F::generator())),
+            ("$DEGREE_BITS", self.degree_bits.to_string()),
+            ("$LOG_QUOTIENT_DEGREE", self.log_quotient_degree.to_string()),
+            ("$WINDOW", self.window_size.to_string()),
+            ("$NUM_POINTS", (self.window_size + 1).to_string()),
+            ("$TRACE_WIDTH", self.trace_width.to_string()),
+            ("$QUOTIENT_WIDTH", self.quotient_width().to_string()),
+            ("$TRACE_ROW_BYTES", (32 * self.trace_width).to_string()),
+            (
+                "$QUOTIENT_ROW_BYTES",
+                (32 * self.quotient_width()).to_string(),
+            ),
+            ("$NUM_PUBLIC", self.public_width.to_string()),
+            ("$NUM_OPENED", (num_opened * d).to_string()),
+            ("$NUM_QUERIES", q.to_string()),
+            ("$NUM_ROUNDS", num_rounds.to_string()),
+            ("$FINAL_POLY_LEN", self.final_poly_len().to_string()),
+            ("$FINAL_POLY_WORDS", (self.final_poly_len() * d).to_string()),
+            ("$FIXED_LEN", (32 * layout.len).to_string()),
+            ("$INPUT_CAP_LEN", (1 << self.input_cap_height()).to_string()),
+            (
+                "$INPUT_PATH_LEN",
+                (log_max_height - self.input_cap_height()).to_string(),
+            ),
+            ("$OFF_TRACE_CAP", (32 * layout.trace_cap).to_string()),
+            ("$OFF_QUOTIENT_CAP", (32 * layout.quotient_cap).to_string()),
+            ("$OFF_OPENED", (32 * layout.opened_values).to_string()),
+            ("$OFF_FINAL_POLY", (32 * layout.final_poly).to_string()),
+            ("$OFF_POW_WITNESS", (32 * layout.pow_witness).to_string()),
+            ("$OFF_TRACE_ROWS", (32 * layout.trace_rows).to_string()),
+            (
+                "$OFF_QUOTIENT_ROWS",
+                (32 * layout.quotient_rows).to_string(),
+            ),
+            ("$M_MUL", m_mul.to_string()),
+            ("$M_INV_PROD", m_inv_prod.to_string()),
+            ("$M_INV_NORM", m_inv_norm.to_string()),
+            ("$M_OPENED", m_opened.to_string()),
+            ("$M_PUBLIC", m_public.to_string()),
+            ("$M_FINAL_POLY", m_final_poly.to_string()),
+            ("$M_ALPHA", m_alpha.to_string()),
+            ("$M_ZETA", m_zeta.to_string()),
+            ("$M_PCS_ALPHA", m_pcs_alpha.to_string()),
+            ("$M_ZH", m_zh.to_string()),
+            ("$M_TMP", m_tmp.to_string()),
+            ("$M_IS_FIRST", m_is_first.to_string()),
+            ("$M_IS_LAST", m_is_last.to_string()),
+            ("$M_TRANSITIONS", m_transitions.to_string()),
+            ("$M_ACC", m_acc.to_string()),
+            ("$M_QUOTIENT", m_quotient.to_string()),
+            ("$M_POINTS", m_points.to_string()),
+            ("$M_OPENED_COMBINED", m_opened_combined.to_string()),
+            ("$M_ALPHA_OFFSETS", m_alpha_offsets.to_string()),
+            ("$M_ALPHA_WIDTH", m_alpha_width.to_string()),
+            ("$M_ROW_TRACE", m_row_trace.to_string()),
+            ("$M_ROW_QUOTIENT", m_row_quotient.to_string()),
+            ("$M_NUM", m_num.to_string()),
+            ("$M_DEN", m_den.to_string()),
+            ("$M_TERM", m_term.to_string()),
+            ("$M_DIFF", m_diff.to_string()),
+            ("$M_FOLDED", m_folded.to_string()),
+            ("$M_SIBLING", m_sibling.to_string()),
+            ("$M_T1", m_t1.to_string()),
+            ("$M_T2", m_t2.to_string()),
+            ("$M_EVAL", m_eval.to_string()),
+            ("$M_X_INV", m_x_inv.to_string()),
+            ("$M_X", m_x.to_string()),
+            ("$M_INDICES", m_indices.to_string()),
+            ("$M_ORDER", m_order.to_string()),
+            ("$M_LEAF", m_leaf.to_string()),
+            ("$M_NODES", m_nodes.to_string()),
+            ("$M_TS_LEN", m_ts_len.to_string()),
+            ("$M_TS_POS", m_ts_pos.to_string()),
+            ("$M_TS_OUT", m_ts_out.to_string()),
+            ("$M_TS_BUF", m_ts_buf.to_string()),
+        ];
+        let blocks = [
+            ("%FROBENIUS\n", frobenius),
+            ("%OBSERVE_COMMITS\n", observe_commits),
+            ("%TRANSITIONS\n", transitions),
+            ("%CONSTRAINTS\n", constraints),
+            ("%QUOTIENT\n", quotient),
+            ("%X0_BITS\n", x0_bits),
+            ("%FOLD_ROUNDS\n", fold_rounds),
+        ];
+
+        // Substitute longer names first, so that no name is replaced within another.
+        let mut out = CONTRACT.to_string();
+        let mut vars = vars.to_vec();
+        vars.sort_by_key(|(name, _)| core::cmp::Reverse(name.len()));
+        for (name, value) in vars {
+            out = out.replace(name, &value);
+        }
+        for (name, code) in blocks {
+            out = out.replace(name, &code);
+        }
+        debug_assert!(!out.contains('$') && !out.contains('%'));
+        out
+    }
+
+    /// The code folding the constraints into `acc` with powers of `alpha`, given the addresses of
+    /// the opened values, the public values and the selectors `[is_first_row, is_last_row,
+    /// is_transition]`. Each computed value is allocated an extension element in `memory`.
+    fn constraint_code(
+        &self,
+        memory: &mut Memory,
+        opened: usize,
+        public: usize,
+        [is_first_row, is_last_row, is_transition]: [usize; 3],
+        acc: usize,
+        alpha: usize,
+    ) -> String {
+        let d = self.extension_degree();
+        let ext_bytes = 32 * d;
+        let mut code = String::new();
+        let mut addresses = BTreeMap::new();
+        for (instruction, register) in self
+            .program
+            .instructions()
+            .iter()
+            .zip(output_registers(&self.program))
+        {
+            let address = match instruction {
+                Instruction::Variable { entry, index } => match entry {
+                    Entry::Main { offset } => {
+                        opened + (offset * self.trace_width + index) * ext_bytes
+                    }
+                    // The public values are the same at every point of the window.
+                    Entry::Public { .. } => public + index * ext_bytes,
+                    _ => unreachable!("only main and public columns are supported"),
+                },
+                Instruction::IsFirstRow => is_first_row,
+                Instruction::IsLastRow => is_last_row,
+                Instruction::IsTransitionWindow(size) => is_transition + (size - 1) * ext_bytes,
+                _ => {
+                    let address = memory.alloc(d);
+                    let a = |r| addresses[r];
+                    let line = match instruction {
+                        Instruction::Constant(i) => format!(
+                            "extSetBase({address}, {})",
+                            self.program.constants()[*i].as_canonical_u32()
+                        ),
+                        Instruction::Add(x, y) => format!("extAdd({address}, {}, {})", a(x), a(y)),
+                        Instruction::Sub(x, y) => format!("extSub({address}, {}, {})", a(x), a(y)),
+                        Instruction::Neg(x) => format!("extNeg({address}, {})", a(x)),
+                        Instruction::Mul(x, y) => format!("extMul({address}, {}, {})", a(x), a(y)),
+                        _ => unreachable!(),
+                    };
+                    writeln!(code, "            {line}").unwrap();
+                    address
+                }
+            };
+            addresses.insert(register, address);
+        }
+        for constraint in self.program.constraints() {
+            writeln!(
+                code,
+                "            extMul({acc}, {acc}, {alpha})\n            extAdd({acc}, {acc}, {})",
+                addresses[constraint]
+            )
+            .unwrap();
+        }
+        code
+    }
+
+    /// Encode `proof` as the `proof` argument of the contract's `verify`: a sequence of 32-byte
+    /// words, each a field element or a digest, laid out as in `ProofLayout`.
+    ///
+    /// Panics if the proof doesn't have the shape the verifier expects.
+    pub fn encode_proof<SC, C>(&self, proof: &Proof<SC>) -> Vec<u8>
+    where
+        SC: StarkGenericConfig<Val = F, Challenge = EF, Pcs = TwoAdicFriPcs<C>>,
+        C: TwoAdicFriPcsGenericConfig<
+                Val = F,
+                Challenge = EF,
+                Challenger = KeccakChallenger<F>,
+                InputMmcs = KeccakMmcs<F>,
+                FriMmcs = ExtensionMmcs<F, EF, KeccakMmcs<F>>,
+            > + Clone,
+    {
+        let num_rounds = self.num_rounds();
+        let commitments = proof.commitments();
+        let opened_values = proof.opened_values();
+        let opening_proof = proof.opening_proof();
+        let fri_proof = opening_proof.fri_proof();
+        let trace = opened_values.trace();
+        let quotient_chunks = opened_values.quotient_chunks();
+        let batch_openings = opening_proof.batch_openings();
+        assert!(
+            proof.degree_bits() == self.degree_bits
+                && commitments.permutation().is_none()
+                && opened_values.preprocessed().is_empty()
+                && opened_values.permutation().is_empty()
+                && trace.len() == self.window_size
+                && trace.iter().all(|row| row.len() == self.trace_width)
+                && quotient_chunks.len() == self.quotient_width()
+                && batch_openings.len() == 2
+                && opening_proof.random_codeword().is_none()
+                && fri_proof.commit_phase_commits().len() == num_rounds
+                && fri_proof.query_proofs().len() == self.num_queries
+                && fri_proof.final_poly().len() == self.final_poly_len(),
+            "the proof doesn't have the expected shape"
+        );
+
+        let mut out = Vec::new();
+        let push_ext = |out: &mut Vec<u8>, value: &EF| {
+            for coeff in value.as_base_slice() {
+                push_word(out, coeff.as_canonical_u32());
+            }
+        };
+        for cap in [commitments.trace(), commitments.quotient_chunks()] {
+            assert_eq!(cap.digests().len(), 1 << self.input_cap_height());
+            out.extend(cap.digests().iter().flatten());
+        }
+        for value in trace.iter().flatten().chain(quotient_chunks) {
+            push_ext(&mut out, value);
+        }
+        for (round, cap) in fri_proof.commit_phase_commits().iter().enumerate() {
+            assert_eq!(cap.digests().len(), 1 << self.round_cap_height(round));
+            out.extend(cap.digests().iter().flatten());
+        }
+        for coeff in fri_proof.final_poly() {
+            push_ext(&mut out, coeff);
+        }
+        push_word(&mut out, fri_proof.pow_witness().as_canonical_u32());
+        for (batch, width) in batch_openings
+            .iter()
+            .zip([self.trace_width, self.quotient_width()])
+        {
+            assert_eq!(batch.opened_values().len(), self.num_queries);
+            for matrices in batch.opened_values() {
+                assert!(matrices.len() == 1 && matrices[0].len() == width);
+                for value in &matrices[0] {
+                    push_word(&mut out, value.as_canonical_u32());
+                }
+            }
+        }
+        for round in 0..num_rounds {
+            for query_proof in fri_proof.query_proofs() {
+                let step = &query_proof.commit_phase_openings()[round];
+                assert_eq!(step.sibling_values().len(), 1);
+                push_ext(&mut out, &step.sibling_values()[0]);
+            }
+        }
+        debug_assert_eq!(out.len(), 32 * self.layout().len);
+
+        let multi_proofs = batch_openings
+            .iter()
+            .map(|batch| batch.opening_proof())
+            .chain(fri_proof.commit_phase_opening_proofs());
+        for siblings in multi_proofs {
+            push_word(&mut out, siblings.len() as u32);
+            out.extend(siblings.iter().flatten());
+        }
+        out
+    }
+
+    /// Encode a call to the contract's `verify` with `public_values` and `proof`.
+    pub fn encode_calldata<SC, C>(&self, public_values: &[F], proof: &Proof<SC>) -> Vec<u8>
+    where
+        SC: StarkGenericConfig<Val = F, Challenge = EF, Pcs = TwoAdicFriPcs<C>>,
+        C: TwoAdicFriPcsGenericConfig<
+                Val = F,
+                Challenge = EF,
+                Challenger = KeccakChallenger<F>,
+                InputMmcs = KeccakMmcs<F>,
+                FriMmcs = ExtensionMmcs<F, EF, KeccakMmcs<F>>,
+            > + Clone,
+    {
+        let mut proof = self.encode_proof(proof);
+        let proof_len = proof.len();
+        proof.resize(proof_len.next_multiple_of(32), 0);
+
+        let mut out = Keccak256Hash.hash_iter(VERIFY_SIGNATURE.iter().copied())[..4].to_vec();
+        // The offsets of the dynamic arguments, after the two offset words.
+        push_word(&mut out, 0x40);
+        push_word(&mut out, (0x60 + 32 * public_values.len()) as u32);
+        push_word(&mut out, public_values.len() as u32);
+        for value in public_values {
+            push_word(&mut out, value.as_canonical_u32());
+        }
+        push_word(&mut out, proof_len as u32);
+        out.extend(proof);
+        out
+    }
+}
+
+/// Append `value` to `out` as a big-endian 32-byte word.
+fn push_word(out: &mut Vec<u8>, value: u32) {
+    out.extend([0; 28]);
+    out.extend(value.to_be_bytes());
+}
+
+/// The contract, with `$NAME` placeholders for constants and `%NAME` lines for generated code.
+///
+/// Extension field elements are `D` consecutive words in memory, and the `ext*` functions take and
+/// write to their addresses. Field elements read from calldata must be canonical.
+const CONTRACT: &str = r#"// SPDX-License-Identifier: MIT OR Apache-2.0
+// Generated by p3-solidity-verifier.
+pragma solidity ^0.8.20;
+
+/// A verifier of `p3_uni_stark` proofs for one AIR and trace height.
+///
+/// Field elements are integers modulo `$P`, and extension field elements have `$D` coefficients in
+/// the basis `1, X, ..., X^($D-1)`, where `X^$D = $W`.
+contract $NAME {
+    /// Whether `proof` is a valid proof for the public values `publicValues`.
+    function verify(uint256[] calldata publicValues, bytes calldata proof) external pure returns (bool valid) {
+        assembly {
+            // Return false.
+            function fail() {
+                mstore(0, 0)
+                return(0, 0x20)
+            }
+
+            // The field element at `ptr` in calldata, which must be canonical.
+            function felt(ptr) -> v {
+                v := calldataload(ptr)
+                if iszero(lt(v, $P)) { fail() }
+            }
+
+            function inv(a) -> r {
+                if iszero(a) { fail() }
+                r := 1
+                for { let e := $P_MINUS_2 } e { e := shr(1, e) } {
+                    if and(e, 1) { r := mulmod(r, a, $P) }
+                    a := mulmod(a, a, $P)
+                }
+            }
+
+            function bswap32(v) -> r {
+                r := or(
+                    or(shl(24, and(v, 0xff)), shl(16, and(shr(8, v), 0xff))),
+                    or(shl(8, and(shr(16, v), 0xff)), shr(24, v))
+                )
+            }
+
+            function extSetBase(r, a) {
+                mstore(r, a)
+                for { let i := 1 } lt(i, $D) { i := add(i, 1) } { mstore(add(r, shl(5, i)), 0) }
+            }
+
+            function extCopy(r, a) {
+                for { let i := 0 } lt(i, $D) { i := add(i, 1) } {
+                    mstore(add(r, shl(5, i)), mload(add(a, shl(5, i))))
+                }
+            }
+
+            function extEq(a, b) -> e {
+                e := 1
+                for { let i := 0 } lt(i, $D) { i := add(i, 1) } {
+                    if iszero(eq(mload(add(a, shl(5, i))), mload(add(b, shl(5, i))))) { e := 0 }
+                }
+            }
+
+            function extAdd(r, a, b) {
+                for { let i := 0 } lt(i, $D) { i := add(i, 1) } {
+                    let o := shl(5, i)
+                    mstore(add(r, o), addmod(mload(add(a, o)), mload(add(b, o)), $P))
+                }
+            }
+
+            function extSub(r, a, b) {
+                for { let i := 0 } lt(i, $D) { i := add(i, 1) } {
+                    let o := shl(5, i)
+                    mstore(add(r, o), addmod(mload(add(a, o)), sub($P, mload(add(b, o))), $P))
+                }
+            }
+
+            function extNeg(r, a) {
+                for { let i := 0 } lt(i, $D) { i := add(i, 1) } {
+                    let o := shl(5, i)
+                    mstore(add(r, o), mod(sub($P, mload(add(a, o))), $P))
+                }
+            }
+
+            function extMulBase(r, a, s) {
+                for { let i := 0 } lt(i, $D) { i := add(i, 1) } {
+                    let o := shl(5, i)
+                    mstore(add(r, o), mulmod(mload(add(a, o)), s, $P))
+                }
+            }
+
+            // The product is accumulated in scratch memory, so `r` may be `a` or `b`.
+            function extMul(r, a, b) {
+                for { let i := 0 } lt(i, $D) { i := add(i, 1) } { mstore(add($M_MUL, shl(5, i)), 0) }
+                for { let i := 0 } lt(i, $D) { i := add(i, 1) } {
+                    let ai := mload(add(a, shl(5, i)))
+                    for { let j := 0 } lt(j, $D) { j := add(j, 1) } {
+                        let t := mulmod(ai, mload(add(b, shl(5, j))), $P)
+                        let k := add(i, j)
+                        if iszero(lt(k, $D)) {
+                            k := sub(k, $D)
+                            t := mulmod(t, $W, $P)
+                        }
+                        let slot := add($M_MUL, shl(5, k))
+                        mstore(slot, addmod(mload(slot), t, $P))
+                    }
+                }
+                extCopy(r, $M_MUL)
+            }
+
+            // Multiply by X, shifting the coefficients up.
+            function extMulX(r, a) {
+                let top := mulmod(mload(add(a, $EXT_TOP)), $W, $P)
+                for { let i := sub($D, 1) } i { i := sub(i, 1) } {
+                    mstore(add(r, shl(5, i)), mload(add(a, shl(5, sub(i, 1)))))
+                }
+                mstore(r, top)
+            }
+
+            // The inverse of `a` is the product of its conjugates over its norm, which is in the
+            // base field.
+            function extInv(r, a) {
+                extSetBase($M_INV_PROD, 1)
+%FROBENIUS
+                extMul($M_INV_NORM, a, $M_INV_PROD)
+                extMulBase(r, $M_INV_PROD, inv(mload($M_INV_NORM)))
+            }
+
+            // The transcript of `SerializingChallenger32` over Keccak-256: field elements are
+            // observed as 4 little-endian bytes and digests as their 32 bytes. Sampling hashes the
+            // input once the output is used up, and the digest becomes the next input.
+            function observeU32(v) {
+                let len := mload($M_TS_LEN)
+                mstore(add($M_TS_BUF, len), shl(224, bswap32(v)))
+                mstore($M_TS_LEN, add(len, 4))
+                mstore($M_TS_POS, 32)
+            }
+
+            function observeDigest(digest) {
+                let len := mload($M_TS_LEN)
+                mstore(add($M_TS_BUF, len), digest)
+                mstore($M_TS_LEN, add(len, 32))
+                mstore($M_TS_POS, 32)
+            }
+
+            function observeCap(ptr, n) {
+                for { let i := 0 } lt(i, n) { i := add(i, 1) } {
+                    observeDigest(calldataload(add(ptr, shl(5, i))))
+                }
+            }
+
+            function sampleByte() -> b {
+                let pos := mload($M_TS_POS)
+                if eq(pos, 32) {
+                    let digest := keccak256($M_TS_BUF, mload($M_TS_LEN))
+                    mstore($M_TS_BUF, digest)
+                    mstore($M_TS_LEN, 32)
+                    mstore($M_TS_OUT, digest)
+                    pos := 0
+                }
+                b := byte(pos, mload($M_TS_OUT))
+                mstore($M_TS_POS, add(pos, 1))
+            }
+
+            function sampleU32() -> v {
+                for { let i := 0 } lt(i, 4) { i := add(i, 1) } {
+                    let b := sampleByte()
+                    v := or(v, shl(shl(3, i), b))
+                }
+            }
+
+            function sampleFelt() -> v {
+                v := and(sampleU32(), $MASK)
+                for { } iszero(lt(v, $P)) { } { v := and(sampleU32(), $MASK) }
+            }
+
+            function sampleExt(r) {
+                for { let i := 0 } lt(i, $D) { i := add(i, 1) } {
+                    let v := sampleFelt()
+                    mstore(add(r, shl(5, i)), v)
+                }
+            }
+
+            // The digest of a row of `n` field elements at `ptr` in calldata.
+            function hashRow(ptr, n) -> digest {
+                for { let i := 0 } lt(i, n) { i := add(i, 1) } {
+                    mstore(add($M_LEAF, shl(2, i)), shl(224, bswap32(felt(add(ptr, shl(5, i))))))
+                }
+                digest := keccak256($M_LEAF, shl(2, n))
+            }
+
+            // The digest of a row of the extension elements `a` and `b` in memory.
+            function hashExtPair(a, b) -> digest {
+                // Each store clears the bytes after the value, so the values are stored in order.
+                for { let i := 0 } lt(i, $D) { i := add(i, 1) } {
+                    mstore(add($M_LEAF, shl(2, i)), shl(224, bswap32(mload(add(a, shl(5, i))))))
+                }
+                for { let i := 0 } lt(i, $D) { i := add(i, 1) } {
+                    mstore(add($M_LEAF, shl(2, add(i, $D))), shl(224, bswap32(mload(add(b, shl(5, i))))))
+                }
+                digest := keccak256($M_LEAF, shl(3, $D))
+            }
+
+            function compress(left, right) -> digest {
+                mstore(0, left)
+                mstore(0x20, right)
+                digest := keccak256(0, 0x40)
+            }
+
+            // Append the leaf `node` to the `n` opened leaves, which are in order, unless it is the
+            // last of them, in which case the digests must agree.
+            function pushNode(n, node, digest) -> m {
+                m := n
+                if n {
+                    let last := add($M_NODES, shl(6, sub(n, 1)))
+                    if eq(mload(last), node) {
+                        if iszero(eq(mload(add(last, 0x20)), digest)) { fail() }
+                        leave
+                    }
+                }
+                let slot := add($M_NODES, shl(6, n))
+                mstore(slot, node)
+                mstore(add(slot, 0x20), digest)
+                m := add(n, 1)
+            }
+
+            // Check the `n` opened leaves against the cap of `capLen` digests at `cap`, with the
+            // multi-opening proof at `cursor`, and return the position after the proof. Each layer
+            // pairs adjacent nodes, taking the siblings of the others from the proof in order.
+            function verifyMultiProof(n, pathLen, cap, capLen, cursor, end) -> next {
+                let siblings := add(cursor, 0x20)
+                if gt(siblings, end) { fail() }
+                let numSiblings := calldataload(cursor)
+                if gt(numSiblings, shr(5, sub(end, siblings))) { fail() }
+                next := add(siblings, shl(5, numSiblings))
+                let s := 0
+                for { let layer := 0 } lt(layer, pathLen) { layer := add(layer, 1) } {
+                    let m := 0
+                    for { let i := 0 } lt(i, n) { } {
+                        let slot := add($M_NODES, shl(6, i))
+                        let node := mload(slot)
+                        let left := mload(add(slot, 0x20))
+                        let right := 0
+                        let paired := 0
+                        i := add(i, 1)
+                        if iszero(and(node, 1)) {
+                            if lt(i, n) {
+                                if eq(mload(add(slot, 0x40)), add(node, 1)) {
+                                    right := mload(add(slot, 0x60))
+                                    paired := 1
+                                    i := add(i, 1)
+                                }
+                            }
+                        }
+                        if iszero(paired) {
+                            if iszero(lt(s, numSiblings)) { fail() }
+                            let sibling := calldataload(add(siblings, shl(5, s)))
+                            s := add(s, 1)
+                            right := sibling
+                            if and(node, 1) {
+                                right := left
+                                left := sibling
+                            }
+                        }
+                        let out := add($M_NODES, shl(6, m))
+                        mstore(out, shr(1, node))
+                        mstore(add(out, 0x20), compress(left, right))
+                        m := add(m, 1)
+                    }
+                    n := m
+                }
+                if iszero(eq(s, numSiblings)) { fail() }
+                for { let i := 0 } lt(i, n) { i := add(i, 1) } {
+                    let slot := add($M_NODES, shl(6, i))
+                    let node := mload(slot)
+                    if iszero(lt(node, capLen)) { fail() }
+                    if iszero(eq(calldataload(add(cap, shl(5, node))), mload(add(slot, 0x20)))) { fail() }
+                }
+            }
+
+            // Sort the queries by index, as the leaves of a multi-opening proof are in order.
+            function sortQueries() {
+                for { let i := 0 } lt(i, $NUM_QUERIES) { i := add(i, 1) } {
+                    mstore(add($M_ORDER, shl(5, i)), i)
+                }
+                for { let i := 1 } lt(i, $NUM_QUERIES) { i := add(i, 1) } {
+                    let q := mload(add($M_ORDER, shl(5, i)))
+                    let index := mload(add($M_INDICES, shl(5, q)))
+                    let j := i
+                    for { } j { } {
+                        let prev := mload(add($M_ORDER, shl(5, sub(j, 1))))
+                        if iszero(gt(mload(add($M_INDICES, shl(5, prev))), index)) { break }
+                        mstore(add($M_ORDER, shl(5, j)), prev)
+                        j := sub(j, 1)
+                    }
+                    mstore(add($M_ORDER, shl(5, j)), q)
+                }
+            }
+
+            // The combination with powers of the batch challenge of `n` extension elements at
+            // `values` in memory.
+            function combineOpened(r, values, n) {
+                extSetBase(r, 0)
+                for { let c := n } c { } {
+                    c := sub(c, 1)
+                    extMul(r, r, $M_PCS_ALPHA)
+                    extAdd(r, r, add(values, mul(c, $EXT_BYTES)))
+                }
+            }
+
+            // The combination with powers of the batch challenge of a row of `n` field elements at
+            // `row` in calldata.
+            function combineRow(r, row, n) {
+                extSetBase(r, 0)
+                for { let c := n } c { } {
+                    c := sub(c, 1)
+                    extMul(r, r, $M_PCS_ALPHA)
+                    mstore(r, addmod(mload(r), felt(add(row, shl(5, c))), $P))
+                }
+            }
+
+            // Fold the queries with `beta` in commit phase round `round`, and return the number of
+            // leaves of the round's tree they open. Each query's evaluations are at x and -x, with x
+            // tracked as the queries are folded.
+            function foldRound(round, beta, siblings) -> n {
+                for { let i := 0 } lt(i, $NUM_QUERIES) { i := add(i, 1) } {
+                    let q := mload(add($M_ORDER, shl(5, i)))
+                    let index := shr(round, mload(add($M_INDICES, shl(5, q))))
+                    let folded := add($M_FOLDED, mul(q, $EXT_BYTES))
+                    {
+                        let sibling := add(siblings, mul(q, $EXT_BYTES))
+                        for { let c := 0 } lt(c, $D) { c := add(c, 1) } {
+                            mstore(add($M_SIBLING, shl(5, c)), felt(add(sibling, shl(5, c))))
+                        }
+                    }
+                    let e0 := folded
+                    let e1 := $M_SIBLING
+                    if and(index, 1) {
+                        e0 := $M_SIBLING
+                        e1 := folded
+                    }
+                    n := pushNode(n, shr(1, index), hashExtPair(e0, e1))
+
+                    // Evaluate the line through (x, e0) and (-x, e1) at beta.
+                    let x := mload(add($M_X, shl(5, q)))
+                    let xInv := mload(add($M_X_INV, shl(5, q)))
+                    extSub($M_T1, e1, e0)
+                    extCopy($M_T2, beta)
+                    mstore($M_T2, addmod(mload($M_T2), sub($P, x), $P))
+                    extMul($M_T1, $M_T1, $M_T2)
+                    extMulBase($M_T1, $M_T1, mulmod(xInv, $NEG_HALF, $P))
+                    extAdd(folded, e0, $M_T1)
+
+                    // The next x is x^2, negated if the next bit of the index is set.
+                    x := mulmod(x, x, $P)
+                    xInv := mulmod(xInv, xInv, $P)
+                    if and(shr(1, index), 1) {
+                        x := sub($P, x)
+                        xInv := sub($P, xInv)
+                    }
+                    mstore(add($M_X, shl(5, q)), x)
+                    mstore(add($M_X_INV, shl(5, q)), xInv)
+                }
+            }
+
+            let p := proof.offset
+            let end := add(p, proof.length)
+            if lt(proof.length, $FIXED_LEN) { fail() }
+            if iszero(eq(publicValues.length, $NUM_PUBLIC)) { fail() }
+            for { let i := 0 } lt(i, $NUM_PUBLIC) { i := add(i, 1) } {
+                extSetBase(add($M_PUBLIC, mul(i, $EXT_BYTES)), felt(add(publicValues.offset, shl(5, i))))
+            }
+            for { let i := 0 } lt(i, $NUM_OPENED) { i := add(i, 1) } {
+                mstore(add($M_OPENED, shl(5, i)), felt(add(add(p, $OFF_OPENED), shl(5, i))))
+            }
+            for { let i := 0 } lt(i, $FINAL_POLY_WORDS) { i := add(i, 1) } {
+                mstore(add($M_FINAL_POLY, shl(5, i)), felt(add(add(p, $OFF_FINAL_POLY), shl(5, i))))
+            }
+
+            // Replay the transcript, and check the proof of work.
+            mstore($M_TS_LEN, 0)
+            mstore($M_TS_POS, 32)
+            observeCap(add(p, $OFF_TRACE_CAP), $INPUT_CAP_LEN)
+            for { let i := 0 } lt(i, $NUM_PUBLIC) { i := add(i, 1) } {
+                observeU32(mload(add($M_PUBLIC, mul(i, $EXT_BYTES))))
+            }
+            sampleExt($M_ALPHA)
+            observeCap(add(p, $OFF_QUOTIENT_CAP), $INPUT_CAP_LEN)
+            sampleExt($M_ZETA)
+            sampleExt($M_PCS_ALPHA)
+%OBSERVE_COMMITS
+            observeU32(felt(add(p, $OFF_POW_WITNESS)))
+            if and(sampleU32(), $POW_MASK) { fail() }
+            for { let q := 0 } lt(q, $NUM_QUERIES) { q := add(q, 1) } {
+                let index := and(sampleU32(), $INDEX_MASK)
+                mstore(add($M_INDICES, shl(5, q)), index)
+            }
+
+            // The selectors and the vanishing polynomial Z_H at zeta.
+            extCopy($M_ZH, $M_ZETA)
+            for { let i := 0 } lt(i, $DEGREE_BITS) { i := add(i, 1) } { extMul($M_ZH, $M_ZH, $M_ZH) }
+            mstore($M_ZH, addmod(mload($M_ZH), $P_MINUS_1, $P))
+            extCopy($M_TMP, $M_ZETA)
+            mstore($M_TMP, addmod(mload($M_TMP), $P_MINUS_1, $P))
+            extInv($M_TMP, $M_TMP)
+            extMul($M_IS_FIRST, $M_ZH, $M_TMP)
+            extCopy($M_TMP, $M_ZETA)
+            mstore($M_TMP, addmod(mload($M_TMP), $NEG_G_INV, $P))
+            extInv($M_TMP, $M_TMP)
+            extMul($M_IS_LAST, $M_ZH, $M_TMP)
+            extSetBase($M_TRANSITIONS, 1)
+%TRANSITIONS
+
+            // Fold the constraints with powers of alpha, recompose the quotient from its chunks,
+            // and check that the folded constraints are quotient * Z_H.
+            extSetBase($M_ACC, 0)
+%CONSTRAINTS
+            extSetBase($M_QUOTIENT, 0)
+%QUOTIENT
+            extMul($M_TMP, $M_QUOTIENT, $M_ZH)
+            if iszero(extEq($M_TMP, $M_ACC)) { fail() }
+
+            // The trace is opened at zeta times the first powers of the trace domain's generator,
+            // and the quotient at zeta^(2^$LOG_QUOTIENT_DEGREE). The terms of each point start at an
+            // offset in the powers of the batch challenge.
+            extCopy($M_POINTS, $M_ZETA)
+            for { let j := 1 } lt(j, $WINDOW) { j := add(j, 1) } {
+                let point := add($M_POINTS, mul(j, $EXT_BYTES))
+                extMulBase(point, sub(point, $EXT_BYTES), $G)
+            }
+            let quotientPoint := add($M_POINTS, mul($WINDOW, $EXT_BYTES))
+            extCopy(quotientPoint, $M_ZETA)
+            for { let i := 0 } lt(i, $LOG_QUOTIENT_DEGREE) { i := add(i, 1) } {
+                extMul(quotientPoint, quotientPoint, quotientPoint)
+            }
+            extSetBase($M_ALPHA_WIDTH, 1)
+            for { let i := 0 } lt(i, $TRACE_WIDTH) { i := add(i, 1) } {
+                extMul($M_ALPHA_WIDTH, $M_ALPHA_WIDTH, $M_PCS_ALPHA)
+            }
+            extSetBase($M_ALPHA_OFFSETS, 1)
+            for { let j := 0 } lt(j, $WINDOW) { j := add(j, 1) } {
+                let offset := add($M_ALPHA_OFFSETS, mul(j, $EXT_BYTES))
+                extMul(add(offset, $EXT_BYTES), offset, $M_ALPHA_WIDTH)
+                combineOpened(
+                    add($M_OPENED_COMBINED, mul(j, $EXT_BYTES)),
+                    add($M_OPENED, mul(mul(j, $TRACE_WIDTH), $EXT_BYTES)),
+                    $TRACE_WIDTH
+                )
+            }
+            combineOpened(
+                add($M_OPENED_COMBINED, mul($WINDOW, $EXT_BYTES)),
+                add($M_OPENED, mul(mul($WINDOW, $TRACE_WIDTH), $EXT_BYTES)),
+                $QUOTIENT_WIDTH
+            )
+
+            // Reduce the openings of each query: the sum over points z and columns of
+            // alpha^k (p(x) - p(z)) / (x - z), accumulated as a single fraction.
+            for { let q := 0 } lt(q, $NUM_QUERIES) { q := add(q, 1) } {
+                let index := mload(add($M_INDICES, shl(5, q)))
+                let x := 1
+                let xInv := 1
+%X0_BITS
+                mstore(add($M_X, shl(5, q)), x)
+                mstore(add($M_X_INV, shl(5, q)), xInv)
+                let y := x
+                if and(index, 1) { y := sub($P, x) }
+                y := mulmod(y, $SHIFT, $P)
+
+                combineRow($M_ROW_TRACE, add(add(p, $OFF_TRACE_ROWS), mul(q, $TRACE_ROW_BYTES)), $TRACE_WIDTH)
+                combineRow($M_ROW_QUOTIENT, add(add(p, $OFF_QUOTIENT_ROWS), mul(q, $QUOTIENT_ROW_BYTES)), $QUOTIENT_WIDTH)
+                extSetBase($M_NUM, 0)
+                extSetBase($M_DEN, 1)
+                for { let j := 0 } lt(j, $NUM_POINTS) { j := add(j, 1) } {
+                    let row := $M_ROW_TRACE
+                    if eq(j, $WINDOW) { row := $M_ROW_QUOTIENT }
+                    let o := mul(j, $EXT_BYTES)
+                    extSub($M_TERM, row, add($M_OPENED_COMBINED, o))
+                    extMul($M_TERM, $M_TERM, add($M_ALPHA_OFFSETS, o))
+                    extNeg($M_DIFF, add($M_POINTS, o))
+                    mstore($M_DIFF, addmod(mload($M_DIFF), y, $P))
+                    extMul($M_NUM, $M_NUM, $M_DIFF)
+                    extMul($M_TERM, $M_TERM, $M_DEN)
+                    extAdd($M_NUM, $M_NUM, $M_TERM)
+                    extMul($M_DEN, $M_DEN, $M_DIFF)
+                }
+                extInv($M_DEN, $M_DEN)
+                extMul(add($M_FOLDED, mul(q, $EXT_BYTES)), $M_NUM, $M_DEN)
+            }
+
+            // Open the trace and quotient trees at the queries.
+            sortQueries()
+            let cursor := add(p, $FIXED_LEN)
+            let n := 0
+            for { let i := 0 } lt(i, $NUM_QUERIES) { i := add(i, 1) } {
+                let q := mload(add($M_ORDER, shl(5, i)))
+                let row := add(add(p, $OFF_TRACE_ROWS), mul(q, $TRACE_ROW_BYTES))
+                n := pushNode(n, mload(add($M_INDICES, shl(5, q))), hashRow(row, $TRACE_WIDTH))
+            }
+            cursor := verifyMultiProof(n, $INPUT_PATH_LEN, add(p, $OFF_TRACE_CAP), $INPUT_CAP_LEN, cursor, end)
+            n := 0
+            for { let i := 0 } lt(i, $NUM_QUERIES) { i := add(i, 1) } {
+                let q := mload(add($M_ORDER, shl(5, i)))
+                let row := add(add(p, $OFF_QUOTIENT_ROWS), mul(q, $QUOTIENT_ROW_BYTES))
+                n := pushNode(n, mload(add($M_INDICES, shl(5, q))), hashRow(row, $QUOTIENT_WIDTH))
+            }
+            cursor := verifyMultiProof(n, $INPUT_PATH_LEN, add(p, $OFF_QUOTIENT_CAP), $INPUT_CAP_LEN, cursor, end)
+
+            // Fold the queries down to the final polynomial, and check their evaluations.
+%FOLD_ROUNDS
+            for { let q := 0 } lt(q, $NUM_QUERIES) { q := add(q, 1) } {
+                let x := mload(add($M_X, shl(5, q)))
+                if and(shr($NUM_ROUNDS, mload(add($M_INDICES, shl(5, q)))), 1) { x := sub($P, x) }
+                extSetBase($M_EVAL, 0)
+                for { let c := $FINAL_POLY_LEN } c { } {
+                    c := sub(c, 1)
+                    extMulBase($M_EVAL, $M_EVAL, x)
+                    extAdd($M_EVAL, $M_EVAL, add($M_FINAL_POLY, mul(c, $EXT_BYTES)))
+                }
+                if iszero(extEq($M_EVAL, add($M_FOLDED, mul(q, $EXT_BYTES)))) { fail() }
+            }
+            if iszero(eq(cursor, end)) { fail() }
+
+            mstore(0, 1)
+            return(0, 0x20)
+        }
+    }
+}
+"#;
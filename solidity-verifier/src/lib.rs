@@ -0,0 +1,25 @@
+//! Generation of Solidity code for verifying `p3_uni_stark` proofs on the EVM.
+//!
+//! `SolidityVerifier` generates a contract verifying the proofs of one AIR, under a config whose
+//! transcript and Merkle trees use Keccak-256, and encodes proofs as its calldata. The contract
+//! replays the transcript of `SerializingChallenger32`, checks the Merkle multi-proofs of
+//! `KeccakMmcs` and the FRI queries, and checks the AIR's folded constraints against the quotient
+//! at the out-of-domain point.
+//!
+//! `generate_constraint_library` generates a library evaluating just the AIR's constraints, in
+//! high-level Solidity.
+//!
+//! The contract's tests run it in an EVM after compiling its assembly block with a compiler for the
+//! subset of Yul it uses, which also checks solc's scoping and stack depth rules. The Solidity
+//! around the assembly block, including the ABI decoding of the arguments, is only tested by
+//! `test_solc_verifier`, which needs solc and is ignored unless run with `--ignored`, as CI does.
+
+#![no_std]
+
+extern crate alloc;
+
+mod constraints;
+mod contract;
+
+pub use constraints::*;
+pub use contract::*;
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, PairBuilder};
+use p3_baby_bear::BabyBear;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractExtensionField, AbstractField};
+use p3_matrix::MatrixRowSlices;
+use p3_solidity_verifier::generate_constraint_library;
+use p3_uni_stark::{get_symbolic_constraints, Entry, SymbolicExpression};
+use rand::distributions::{Distribution, Standard};
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng};
+
+type F = BabyBear;
+type EF = BinomialExtensionField<F, 4>;
+
+/// An AIR using each kind of input: preprocessed, main and public values, and every selector.
+struct TestAir;
+
+impl<T> BaseAir<T> for TestAir {
+    fn width(&self) -> usize {
+        3
+    }
+
+    fn window_size(&self) -> usize {
+        3
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        1
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues + PairBuilder> Air<AB> for TestAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let preprocessed = builder.preprocessed();
+        let public_values = builder.public_values();
+        let (local, next, last) = (main.row_slice(0), main.row_slice(1), main.row_slice(2));
+        let selector = preprocessed.row_slice(0)[0];
+        let pis = public_values.row_slice(0);
+
+        let sum = local[0] + local[1];
+        builder.when_first_row().assert_eq(local[0], pis[0]);
+        builder
+            .when_transition()
+            .assert_eq(next[2], sum.clone() * sum.clone());
+        builder
+            .when_transition_window(3)
+            .assert_zero(selector * (last[0] - sum * AB::Expr::from_canonical_u32(7)));
+        builder
+            .when_last_row()
+            .assert_eq(AB::Expr::zero() - local[2], pis[1]);
+        builder.assert_bool(local[1]);
+    }
+}
+
+/// Random values for each input of the generated library, keyed by its name.
+struct Inputs(HashMap<String, EF>);
+
+impl Inputs {
+    fn get(&mut self, rng: &mut ThreadRng, name: String) -> EF {
+        *self.0.entry(name).or_insert_with(|| Standard.sample(rng))
+    }
+}
+
+fn variable_name(entry: Entry, index: usize) -> String {
+    match entry {
+        Entry::Preprocessed { offset } => format!("o.preprocessed[{offset}][{index}]"),
+        Entry::Main { offset } => format!("o.main[{offset}][{index}]"),
+        Entry::Permutation { offset } => format!("o.permutation[{offset}][{index}]"),
+        Entry::Public { offset } => format!("o.publicValues[{offset}][{index}]"),
+        Entry::Challenge => format!("o.permutationChallenges[{index}]"),
+    }
+}
+
+/// Evaluate a constraint directly, as `VerifierConstraintFolder` would.
+fn eval(expr: &SymbolicExpression<F>, inputs: &mut Inputs, rng: &mut ThreadRng) -> EF {
+    match expr {
+        SymbolicExpression::Variable(v) => inputs.get(rng, variable_name(v.entry, v.index)),
+        SymbolicExpression::IsFirstRow => inputs.get(rng, "o.isFirstRow".into()),
+        SymbolicExpression::IsLastRow => inputs.get(rng, "o.isLastRow".into()),
+        SymbolicExpression::IsTransitionWindow(size) => {
+            inputs.get(rng, format!("o.transitionSelectors[{}]", size - 1))
+        }
+        SymbolicExpression::Constant(c) => EF::from_base(*c),
+        SymbolicExpression::Add { x, y, .. } => eval(x, inputs, rng) + eval(y, inputs, rng),
+        SymbolicExpression::Sub { x, y, .. } => eval(x, inputs, rng) - eval(y, inputs, rng),
+        SymbolicExpression::Neg { x, .. } => -eval(x, inputs, rng),
+        SymbolicExpression::Mul { x, y, .. } => eval(x, inputs, rng) * eval(y, inputs, rng),
+    }
+}
+
+/// Run the body of the generated `evalFolded`, which is a straight-line program of calls to the
+/// library's arithmetic functions.
+fn run_eval_folded(library: &str, inputs: &mut Inputs, rng: &mut ThreadRng, alpha: EF) -> EF {
+    let slot = |operand: &str| -> usize {
+        operand
+            .strip_prefix("t[")
+            .and_then(|s| s.strip_suffix(']'))
+            .expect("expected a slot")
+            .parse()
+            .unwrap()
+    };
+
+    let mut t: Vec<EF> = vec![];
+    let mut acc = EF::zero();
+    let body = library
+        .split_once("evalFolded(")
+        .unwrap()
+        .1
+        .lines()
+        .skip(2)
+        .map(str::trim)
+        .take_while(|line| *line != "}");
+    for line in body {
+        if let Some(constraint) = line
+            .strip_prefix("acc = add(mul(acc, alpha), ")
+            .and_then(|s| s.strip_suffix(");"))
+        {
+            acc = acc * alpha + t[slot(constraint)];
+            continue;
+        }
+
+        let (lhs, rhs) = line.strip_suffix(';').unwrap().split_once(" = ").unwrap();
+        assert_eq!(slot(lhs), t.len(), "slots must be assigned in order");
+        let value = match rhs.split_once('(') {
+            Some((op, args)) => {
+                let args = args
+                    .strip_suffix(')')
+                    .unwrap()
+                    .split(", ")
+                    .collect::<Vec<_>>();
+                match op {
+                    "fromBase" => EF::from_canonical_u32(args[0].parse().unwrap()),
+                    "add" => t[slot(args[0])] + t[slot(args[1])],
+                    "sub" => t[slot(args[0])] - t[slot(args[1])],
+                    "mul" => t[slot(args[0])] * t[slot(args[1])],
+                    "neg" => -t[slot(args[0])],
+                    _ => panic!("unexpected function {op}"),
+                }
+            }
+            None => inputs.get(rng, rhs.into()),
+        };
+        t.push(value);
+    }
+    acc
+}
+
+#[test]
+fn test_generated_constraints_match_folder() {
+    let library = generate_constraint_library::<F, EF, _>(&TestAir, 2, "TestAirConstraints");
+    assert!(library.contains("library TestAirConstraints {"));
+    assert!(library.contains("uint256 internal constant P = 2013265921;"));
+    assert!(library.contains("uint256 internal constant W = 11;"));
+    assert!(library.contains("uint256 internal constant D = 4;"));
+
+    let mut rng = thread_rng();
+    let mut inputs = Inputs(HashMap::new());
+    let alpha: EF = rng.sample(Standard);
+
+    let expected = get_symbolic_constraints::<F, _>(&TestAir, 2)
+        .iter()
+        .fold(EF::zero(), |acc, constraint| {
+            acc * alpha + eval(constraint, &mut inputs, &mut rng)
+        });
+    assert_eq!(
+        run_eval_folded(&library, &mut inputs, &mut rng, alpha),
+        expected
+    );
+}
+
+#[test]
+fn test_shared_nodes_computed_once() {
+    let library = generate_constraint_library::<F, EF, _>(&TestAir, 2, "TestAirConstraints");
+    // `local[0] + local[1]` is built separately for three constraints, but computed once.
+    assert_eq!(library.matches("add(t[").count(), 1);
+    let sum_line = library
+        .lines()
+        .find(|line| line.contains("add(t["))
+        .unwrap();
+    let sum = sum_line.trim().split_once(" = ").unwrap().0;
+    assert_eq!(library.matches(&format!("mul({sum}, {sum})")).count(), 1);
+    assert_eq!(library.matches("= fromBase(7);").count(), 1);
+    assert_eq!(library.matches("= o.main[0][0];").count(), 1);
+}
+
+#[test]
+fn test_base_field_library() {
+    let library = generate_constraint_library::<F, F, _>(&TestAir, 2, "BaseConstraints");
+    assert!(library.contains("uint256 internal constant W = 0;"));
+    assert!(library.contains("uint256 internal constant D = 1;"));
+    assert!(library.contains("uint256[1][] memory t"));
+}
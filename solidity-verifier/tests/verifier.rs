@@ -0,0 +1,312 @@
+mod yul;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_baby_bear::BabyBear;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractField, PrimeField32};
+use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_solidity_verifier::{KeccakChallenger, KeccakMmcs, SolidityVerifier};
+use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher32};
+use p3_uni_stark::{prove, verify, Proof, PublicRow, StarkConfig};
+use revm::db::{CacheDB, EmptyDB};
+use revm::primitives::{AccountInfo, Address, Bytecode, ExecutionResult, Output, TxKind, U256};
+use revm::Evm;
+
+type Val = BabyBear;
+type Challenge = BinomialExtensionField<Val, 4>;
+
+type FieldHash = SerializingHasher32<Keccak256Hash>;
+type MyCompress = CompressionFunctionFromHasher<u8, Keccak256Hash, 2, 32>;
+type ValMmcs = KeccakMmcs<Val>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = KeccakChallenger<Val>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+const CONTRACT_NAME: &str = "FibonacciCubeVerifier";
+const LOG_HEIGHT: usize = 4;
+const NUM_PUBLIC_VALUES: usize = 3;
+const GAS_LIMIT: u64 = 1 << 36;
+
+/// A Fibonacci sequence `a, b`, whose first values and last `b` are public, with a third column
+/// holding the cube of `a`, so that the quotient has two chunks.
+struct FibonacciCubeAir;
+
+impl<F> BaseAir<F> for FibonacciCubeAir {
+    fn width(&self) -> usize {
+        3
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for FibonacciCubeAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let public_values = builder.public_values();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let pis = public_values.row_slice(0);
+        let (a, b, cube) = (local[0], local[1], local[2]);
+
+        let mut when_first_row = builder.when_first_row();
+        when_first_row.assert_eq(a, pis[0]);
+        when_first_row.assert_eq(b, pis[1]);
+        builder.assert_eq(cube, a * a * a);
+        let mut when_transition = builder.when_transition();
+        when_transition.assert_eq(next[0], b);
+        when_transition.assert_eq(next[1], a + b);
+        builder.when_last_row().assert_eq(b, pis[2]);
+    }
+}
+
+/// The trace starting from `a, b`, and its public values.
+fn fibonacci_cube_trace(a: u32, b: u32) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let (a0, b0) = (Val::from_canonical_u32(a), Val::from_canonical_u32(b));
+    let (mut a, mut b) = (a0, b0);
+    let mut values = Vec::new();
+    for i in 0..1 << LOG_HEIGHT {
+        values.extend([a, b, a.cube()]);
+        if i + 1 < 1 << LOG_HEIGHT {
+            (a, b) = (b, a + b);
+        }
+    }
+    (RowMajorMatrix::new(values, 3), vec![a0, b0, b])
+}
+
+fn config() -> MyConfig {
+    let val_mmcs = ValMmcs::new_with_cap(
+        FieldHash::new(Keccak256Hash),
+        MyCompress::new(Keccak256Hash),
+        1,
+    );
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 1,
+        num_queries: 20,
+        proof_of_work_bits: 4,
+        mmcs: ChallengeMmcs::new(val_mmcs.clone()),
+    };
+    MyConfig::new(Pcs::new(fri_config, Dft {}, val_mmcs))
+}
+
+/// A proof of the trace starting from `1, 2`, checked by `p3_uni_stark::verify`, with its public
+/// values.
+fn valid_proof(config: &MyConfig) -> (Proof<MyConfig>, Vec<Val>) {
+    let (trace, public_values) = fibonacci_cube_trace(1, 2);
+    let public_row = PublicRow(public_values.clone());
+    let mut challenger = Challenger::from_hasher(Keccak256Hash);
+    let proof = prove(
+        config,
+        &FibonacciCubeAir,
+        &mut challenger,
+        trace,
+        &public_row,
+    );
+    let mut challenger = Challenger::from_hasher(Keccak256Hash);
+    verify(
+        config,
+        &FibonacciCubeAir,
+        &mut challenger,
+        &proof,
+        &public_row,
+    )
+    .expect("the proof should verify");
+    (proof, public_values)
+}
+
+/// The generated contract, its verifier, and the calldata of a valid proof.
+struct Setup {
+    contract: String,
+    verifier: SolidityVerifier<Val, Challenge>,
+    calldata: Vec<u8>,
+}
+
+fn setup() -> Setup {
+    let config = config();
+    let verifier = SolidityVerifier::new(&config, &FibonacciCubeAir, LOG_HEIGHT, NUM_PUBLIC_VALUES);
+    let contract = verifier.contract(CONTRACT_NAME);
+    let (proof, public_values) = valid_proof(&config);
+    let calldata = verifier.encode_calldata(&public_values, &proof);
+    Setup {
+        contract,
+        verifier,
+        calldata,
+    }
+}
+
+/// The runtime code of `contract`, compiled by solc, which is `$SOLC` or else `solc` on the path.
+fn solc_runtime_code(contract: &str) -> Vec<u8> {
+    let solc = std::env::var("SOLC").unwrap_or_else(|_| "solc".to_string());
+    let mut child = Command::new(&solc)
+        .args(["--bin-runtime", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|err| panic!("couldn't run {solc}: {err}"));
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(contract.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "solc failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let hex = stdout
+        .lines()
+        .skip_while(|line| !line.starts_with("Binary of the runtime part"))
+        .nth(1)
+        .expect("solc didn't output the runtime code")
+        .trim();
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// Call the contract with code `code`, returning the boolean it returns.
+fn call(code: &[u8], calldata: &[u8]) -> bool {
+    let address = Address::with_last_byte(0x42);
+    let bytecode = Bytecode::new_raw(code.to_vec().into());
+    let mut db = CacheDB::new(EmptyDB::default());
+    db.insert_account_info(
+        address,
+        AccountInfo {
+            code_hash: bytecode.hash_slow(),
+            code: Some(bytecode),
+            ..Default::default()
+        },
+    );
+    let mut evm = Evm::builder()
+        .with_db(db)
+        .modify_block_env(|block| block.gas_limit = U256::from(GAS_LIMIT))
+        .modify_tx_env(|tx| {
+            tx.transact_to = TxKind::Call(address);
+            tx.data = calldata.to_vec().into();
+            tx.gas_limit = GAS_LIMIT;
+        })
+        .build();
+    match evm
+        .transact()
+        .expect("the transaction should be valid")
+        .result
+    {
+        ExecutionResult::Success {
+            output: Output::Call(output),
+            ..
+        } => {
+            assert_eq!(output.len(), 32);
+            assert!(output[..31].iter().all(|&byte| byte == 0) && output[31] <= 1);
+            output[31] == 1
+        }
+        result => panic!("the call should return a boolean, but got {result:?}"),
+    }
+}
+
+/// The offset of the encoded proof within the calldata: after the selector, the offsets of the
+/// arguments, the public values and their count, and the proof's length.
+const PROOF_OFFSET: usize = 4 + 32 * (NUM_PUBLIC_VALUES + 4);
+
+fn check_accepts_valid_proof(code: &[u8], setup: &Setup) {
+    assert!(call(code, &setup.calldata));
+}
+
+fn check_rejects_wrong_public_value(code: &[u8], setup: &Setup) {
+    let (proof, mut public_values) = valid_proof(&config());
+    public_values[2] += Val::one();
+    let calldata = setup.verifier.encode_calldata(&public_values, &proof);
+    assert!(!call(code, &calldata));
+}
+
+fn check_rejects_non_canonical_public_value(code: &[u8], setup: &Setup) {
+    let mut calldata = setup.calldata.clone();
+    // The last public value, plus the field's order.
+    let word = PROOF_OFFSET - 64;
+    let value = u32::from_be_bytes(calldata[word + 28..word + 32].try_into().unwrap());
+    let non_canonical = value as u64 + Val::ORDER_U32 as u64;
+    calldata[word + 24..word + 32].copy_from_slice(&non_canonical.to_be_bytes());
+    assert!(!call(code, &calldata));
+}
+
+fn check_rejects_tampered_proof(code: &[u8], setup: &Setup) {
+    let calldata = &setup.calldata;
+    let start = PROOF_OFFSET;
+    let num_words = (calldata.len() - start) / 32;
+    // Tamper with a spread of words: each of them is a digest, a field element or a count, and
+    // the proof must be rejected whichever it is.
+    for word in (0..num_words).step_by(num_words / 24) {
+        let mut tampered = calldata.clone();
+        tampered[start + 32 * word + 31] ^= 1;
+        assert!(
+            !call(code, &tampered),
+            "tampering with word {word} went undetected"
+        );
+    }
+}
+
+fn check_rejects_truncated_proof(code: &[u8], setup: &Setup) {
+    let start = PROOF_OFFSET;
+    let mut truncated = setup.calldata.clone();
+    truncated.truncate(truncated.len() - 32);
+    // Update the length of the proof.
+    let len = (truncated.len() - start) as u32;
+    truncated[start - 4..start].copy_from_slice(&len.to_be_bytes());
+    assert!(!call(code, &truncated));
+}
+
+#[test]
+fn test_verifier_accepts_valid_proof() {
+    let setup = setup();
+    check_accepts_valid_proof(&yul::compile_contract(&setup.contract), &setup);
+}
+
+#[test]
+fn test_verifier_rejects_wrong_public_value() {
+    let setup = setup();
+    check_rejects_wrong_public_value(&yul::compile_contract(&setup.contract), &setup);
+}
+
+#[test]
+fn test_verifier_rejects_non_canonical_public_value() {
+    let setup = setup();
+    check_rejects_non_canonical_public_value(&yul::compile_contract(&setup.contract), &setup);
+}
+
+#[test]
+fn test_verifier_rejects_tampered_proof() {
+    let setup = setup();
+    check_rejects_tampered_proof(&yul::compile_contract(&setup.contract), &setup);
+}
+
+#[test]
+fn test_verifier_rejects_truncated_proof() {
+    let setup = setup();
+    check_rejects_truncated_proof(&yul::compile_contract(&setup.contract), &setup);
+}
+
+/// The same checks, against the contract as compiled by solc, which CI installs.
+#[test]
+#[ignore = "requires solc"]
+fn test_solc_verifier() {
+    let setup = setup();
+    let code = solc_runtime_code(&setup.contract);
+    check_accepts_valid_proof(&code, &setup);
+    check_rejects_wrong_public_value(&code, &setup);
+    check_rejects_non_canonical_public_value(&code, &setup);
+    check_rejects_tampered_proof(&code, &setup);
+    check_rejects_truncated_proof(&code, &setup);
+}
@@ -0,0 +1,793 @@
+//! A compiler to EVM bytecode of the subset of Yul the generated contracts use, so that the
+//! contracts can be tested without solc.
+//!
+//! Every variable lives in its own memory slot, which is only sound without recursion, and the
+//! memory the code addresses is moved past the slots. Functions are called by pushing a return
+//! label and the arguments, and jumping to the function.
+//!
+//! As the code wouldn't otherwise be checked against solc's rules, the compiler also rejects what
+//! solc would reject in inline assembly: declarations shadowing variables, functions or the
+//! Solidity function's names, and variables more than 16 slots deep in the stack layout of solc's
+//! legacy code generator.
+
+use std::collections::HashMap;
+
+use revm::primitives::U256;
+
+/// Compile the `verify` function of `contract` to the code of a contract, which decodes its
+/// arguments and runs the function's assembly block.
+pub fn compile_contract(contract: &str) -> Vec<u8> {
+    let start = contract.find("assembly {").expect("no assembly block") + "assembly ".len();
+    let mut depth = 0;
+    let end = start
+        + contract[start..]
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            })
+            .expect("unbalanced braces")
+            .0;
+    let source = format!("{{\n{PRELUDE}\n{{{}}}\n}}", &contract[start + 1..end]);
+    let name = contract
+        .split("contract ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .expect("no contract name");
+    compile(&source, &["publicValues", "proof", "verify", name])
+}
+
+/// The decoding of `verify(uint256[] publicValues, bytes proof)`'s calldata arguments, declared in
+/// the order solc lays them out on the stack, followed by the return variable.
+const PRELUDE: &str = "
+    let publicValues.offset := add(add(4, calldataload(4)), 32)
+    let publicValues.length := calldataload(add(4, calldataload(4)))
+    let proof.offset := add(add(4, calldataload(36)), 32)
+    let proof.length := calldataload(add(4, calldataload(36)))
+    let valid := 0
+";
+
+/// Compile `source`, a Yul block, in a context where the names `reserved` are already declared.
+pub fn compile(source: &str, reserved: &[&str]) -> Vec<u8> {
+    let tokens = lex(source);
+    let mut parser = Parser { tokens, pos: 0 };
+    let program = parser.block();
+    assert_eq!(parser.pos, parser.tokens.len(), "trailing tokens");
+    StackChecker::new(&program, reserved).block(&program);
+
+    let mut codegen = Codegen::default();
+    codegen.base_label = codegen.new_label();
+    codegen.collect_functions(&program);
+    codegen.block(&program);
+    codegen.code.push(STOP);
+    let functions: Vec<_> = codegen.functions.values().cloned().collect();
+    for function in functions {
+        codegen.function(&function);
+    }
+    codegen.finish()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(U256),
+    Punct(&'static str),
+}
+
+fn lex(source: &str) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
+            let literal: String = chars[start..i].iter().collect();
+            let value = match literal.strip_prefix("0x") {
+                Some(hex) => U256::from_str_radix(hex, 16),
+                None => U256::from_str_radix(&literal, 10),
+            };
+            tokens.push(Token::Number(value.expect("invalid number")));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || "_.".contains(chars[i])) {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let punct = ["{", "}", "(", ")", ",", ":=", "->"]
+                .into_iter()
+                .find(|p| two.starts_with(p))
+                .unwrap_or_else(|| panic!("unexpected character {c:?}"));
+            tokens.push(Token::Punct(punct));
+            i += punct.len();
+        }
+    }
+    tokens
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Number(U256),
+    Var(String),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Clone, Debug)]
+enum Stmt {
+    Block(Vec<Stmt>),
+    Function(Function),
+    Let(String, Option<Expr>),
+    Assign(String, Expr),
+    If(Expr, Vec<Stmt>),
+    For {
+        init: Vec<Stmt>,
+        cond: Expr,
+        post: Vec<Stmt>,
+        body: Vec<Stmt>,
+    },
+    Break,
+    Continue,
+    Leave,
+    Expr(Expr),
+}
+
+#[derive(Clone, Debug)]
+struct Function {
+    name: String,
+    params: Vec<String>,
+    ret: Option<String>,
+    body: Vec<Stmt>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Token {
+        self.pos += 1;
+        self.tokens[self.pos - 1].clone()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expect(&mut self, punct: &str) {
+        let token = self.next();
+        assert_eq!(token, Token::Punct(punct_str(punct)), "expected {punct}");
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if self.peek() == Some(&Token::Punct(punct_str(punct))) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ident(&mut self) -> String {
+        match self.next() {
+            Token::Ident(name) => name,
+            token => panic!("expected an identifier, found {token:?}"),
+        }
+    }
+
+    fn block(&mut self) -> Vec<Stmt> {
+        self.expect("{");
+        let mut stmts = Vec::new();
+        while !self.eat("}") {
+            stmts.push(self.stmt());
+        }
+        stmts
+    }
+
+    fn stmt(&mut self) -> Stmt {
+        if self.peek() == Some(&Token::Punct("{")) {
+            return Stmt::Block(self.block());
+        }
+        let Some(Token::Ident(word)) = self.peek().cloned() else {
+            panic!("expected a statement, found {:?}", self.peek());
+        };
+        match word.as_str() {
+            "function" => {
+                self.pos += 1;
+                let name = self.ident();
+                self.expect("(");
+                let mut params = Vec::new();
+                while !self.eat(")") {
+                    params.push(self.ident());
+                    self.eat(",");
+                }
+                let ret = self.eat("->").then(|| self.ident());
+                let body = self.block();
+                Stmt::Function(Function {
+                    name,
+                    params,
+                    ret,
+                    body,
+                })
+            }
+            "let" => {
+                self.pos += 1;
+                let name = self.ident();
+                let value = self.eat(":=").then(|| self.expr());
+                Stmt::Let(name, value)
+            }
+            "if" => {
+                self.pos += 1;
+                let cond = self.expr();
+                Stmt::If(cond, self.block())
+            }
+            "for" => {
+                self.pos += 1;
+                let init = self.block();
+                let cond = self.expr();
+                let post = self.block();
+                let body = self.block();
+                Stmt::For {
+                    init,
+                    cond,
+                    post,
+                    body,
+                }
+            }
+            "break" | "continue" | "leave" => {
+                self.pos += 1;
+                match word.as_str() {
+                    "break" => Stmt::Break,
+                    "continue" => Stmt::Continue,
+                    _ => Stmt::Leave,
+                }
+            }
+            _ => {
+                if self.tokens.get(self.pos + 1) == Some(&Token::Punct(":=")) {
+                    let name = self.ident();
+                    self.pos += 1;
+                    Stmt::Assign(name, self.expr())
+                } else {
+                    Stmt::Expr(self.expr())
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self) -> Expr {
+        match self.next() {
+            Token::Number(value) => Expr::Number(value),
+            Token::Ident(name) => {
+                if self.eat("(") {
+                    let mut args = Vec::new();
+                    while !self.eat(")") {
+                        args.push(self.expr());
+                        self.eat(",");
+                    }
+                    Expr::Call(name, args)
+                } else {
+                    Expr::Var(name)
+                }
+            }
+            token => panic!("expected an expression, found {token:?}"),
+        }
+    }
+}
+
+fn punct_str(punct: &str) -> &'static str {
+    ["{", "}", "(", ")", ",", ":=", "->"]
+        .into_iter()
+        .find(|&p| p == punct)
+        .unwrap()
+}
+
+/// The deepest stack slot `DUP16` and `SWAP16` can reach.
+const MAX_STACK_DEPTH: usize = 16;
+
+/// Checks the rules solc enforces on inline assembly that the code generator here doesn't need.
+///
+/// The stack is modelled as solc's legacy code generator lays it out: a function's return label,
+/// its parameters from last to first and its return variable, then the variables in scope in order
+/// of declaration, and finally the temporary values of the expression being evaluated.
+struct StackChecker {
+    functions: HashMap<String, Function>,
+    reserved: Vec<String>,
+    /// The variables on the stack, with `None` for a return label.
+    stack: Vec<Option<String>>,
+    /// The number of variables on the stack when each of the enclosing blocks was entered.
+    scopes: Vec<usize>,
+    temporaries: usize,
+    /// The function being checked, if any.
+    function: Option<String>,
+}
+
+impl StackChecker {
+    fn new(program: &[Stmt], reserved: &[&str]) -> Self {
+        let mut functions = HashMap::new();
+        collect_functions(program, &mut functions);
+        Self {
+            functions,
+            reserved: reserved.iter().map(|name| name.to_string()).collect(),
+            stack: Vec::new(),
+            scopes: Vec::new(),
+            temporaries: 0,
+            function: None,
+        }
+    }
+
+    fn context(&self) -> String {
+        match &self.function {
+            Some(name) => format!("in function {name}"),
+            None => "in the main block".to_string(),
+        }
+    }
+
+    fn declare(&mut self, name: &str) {
+        assert!(
+            !self.functions.contains_key(name)
+                && !self.reserved.iter().any(|reserved| reserved == name)
+                && !self.stack.iter().any(|var| var.as_deref() == Some(name)),
+            "{name} is declared again {}",
+            self.context()
+        );
+        self.stack.push(Some(name.to_string()));
+    }
+
+    /// The depth of `name` below the top of the stack, counting the top as depth 1.
+    fn depth(&self, name: &str) -> usize {
+        let position = self
+            .stack
+            .iter()
+            .rposition(|var| var.as_deref() == Some(name))
+            .unwrap_or_else(|| panic!("undefined variable {name} {}", self.context()));
+        self.stack.len() + self.temporaries - position
+    }
+
+    fn check_depth(&self, name: &str, depth: usize) {
+        assert!(
+            depth <= MAX_STACK_DEPTH,
+            "{name} is {depth} slots deep in the stack {}",
+            self.context()
+        );
+    }
+
+    fn function(&mut self, function: &Function) {
+        let stack = std::mem::replace(&mut self.stack, vec![None]);
+        let outer = self.function.replace(function.name.clone());
+        for param in function.params.iter().rev() {
+            self.declare(param);
+        }
+        if let Some(ret) = &function.ret {
+            self.declare(ret);
+        }
+        self.block(&function.body);
+        self.stack = stack;
+        self.function = outer;
+    }
+
+    fn block(&mut self, stmts: &[Stmt]) {
+        self.scopes.push(self.stack.len());
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+        self.stack.truncate(self.scopes.pop().unwrap());
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(body) | Stmt::If(_, body) => {
+                if let Stmt::If(cond, _) = stmt {
+                    self.expr(cond);
+                }
+                self.block(body)
+            }
+            Stmt::Function(function) => self.function(function),
+            Stmt::Let(name, value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+                self.declare(name);
+            }
+            Stmt::Assign(name, value) => {
+                self.expr(value);
+                // The value is on top of the stack, and is swapped into the variable's slot.
+                self.temporaries += 1;
+                let depth = self.depth(name) - 1;
+                self.temporaries -= 1;
+                self.check_depth(name, depth);
+            }
+            Stmt::For {
+                init,
+                cond,
+                post,
+                body,
+            } => {
+                self.scopes.push(self.stack.len());
+                for stmt in init {
+                    self.stmt(stmt);
+                }
+                self.expr(cond);
+                self.block(body);
+                self.block(post);
+                self.stack.truncate(self.scopes.pop().unwrap());
+            }
+            Stmt::Break | Stmt::Continue | Stmt::Leave => {}
+            Stmt::Expr(expr) => self.expr(expr),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Number(_) => {}
+            Expr::Var(name) => self.check_depth(name, self.depth(name)),
+            Expr::Call(name, args) => {
+                let temporaries = self.temporaries;
+                // A function's return label is pushed before its arguments.
+                if builtin(name).is_none() {
+                    self.temporaries += 1;
+                }
+                for arg in args.iter().rev() {
+                    self.expr(arg);
+                    self.temporaries += 1;
+                }
+                self.temporaries = temporaries;
+            }
+        }
+    }
+}
+
+fn collect_functions(stmts: &[Stmt], functions: &mut HashMap<String, Function>) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Function(function) => {
+                assert!(
+                    functions
+                        .insert(function.name.clone(), function.clone())
+                        .is_none(),
+                    "function {} is declared again",
+                    function.name
+                );
+                collect_functions(&function.body, functions);
+            }
+            Stmt::Block(body) | Stmt::If(_, body) => collect_functions(body, functions),
+            Stmt::For { init, body, .. } => {
+                collect_functions(init, functions);
+                collect_functions(body, functions);
+            }
+            _ => {}
+        }
+    }
+}
+
+const STOP: u8 = 0x00;
+const ADD: u8 = 0x01;
+const ISZERO: u8 = 0x15;
+const POP: u8 = 0x50;
+const MLOAD: u8 = 0x51;
+const MSTORE: u8 = 0x52;
+const JUMP: u8 = 0x56;
+const JUMPI: u8 = 0x57;
+const JUMPDEST: u8 = 0x5b;
+const PUSH1: u8 = 0x60;
+const PUSH3: u8 = 0x62;
+const SWAP1: u8 = 0x90;
+
+/// The opcode of a builtin, its number of arguments, whether it returns a value, and whether its
+/// first argument is a memory address.
+fn builtin(name: &str) -> Option<(u8, usize, bool, bool)> {
+    Some(match name {
+        "add" => (0x01, 2, true, false),
+        "mul" => (0x02, 2, true, false),
+        "sub" => (0x03, 2, true, false),
+        "div" => (0x04, 2, true, false),
+        "mod" => (0x06, 2, true, false),
+        "addmod" => (0x08, 3, true, false),
+        "mulmod" => (0x09, 3, true, false),
+        "exp" => (0x0a, 2, true, false),
+        "lt" => (0x10, 2, true, false),
+        "gt" => (0x11, 2, true, false),
+        "eq" => (0x14, 2, true, false),
+        "iszero" => (0x15, 1, true, false),
+        "and" => (0x16, 2, true, false),
+        "or" => (0x17, 2, true, false),
+        "xor" => (0x18, 2, true, false),
+        "not" => (0x19, 1, true, false),
+        "byte" => (0x1a, 2, true, false),
+        "shl" => (0x1b, 2, true, false),
+        "shr" => (0x1c, 2, true, false),
+        "keccak256" => (0x20, 2, true, true),
+        "calldataload" => (0x35, 1, true, false),
+        "calldatasize" => (0x36, 0, true, false),
+        "calldatacopy" => (0x37, 3, false, true),
+        "pop" => (0x50, 1, false, false),
+        "mload" => (0x51, 1, true, true),
+        "mstore" => (0x52, 2, false, true),
+        "mstore8" => (0x53, 2, false, true),
+        "return" => (0xf3, 2, false, true),
+        "revert" => (0xfd, 2, false, true),
+        _ => return None,
+    })
+}
+
+#[derive(Clone)]
+struct FunctionInfo {
+    function: Function,
+    label: usize,
+    exit: usize,
+    params: Vec<usize>,
+    ret: Option<usize>,
+}
+
+#[derive(Default)]
+struct Codegen {
+    code: Vec<u8>,
+    labels: Vec<Option<usize>>,
+    /// The positions of label operands to patch.
+    fixups: Vec<(usize, usize)>,
+    functions: HashMap<String, FunctionInfo>,
+    scopes: Vec<HashMap<String, usize>>,
+    next_slot: usize,
+    /// The continue and break labels of the enclosing loops.
+    loops: Vec<(usize, usize)>,
+    exit: Option<usize>,
+    /// A label resolved to the start of the memory the code addresses.
+    base_label: usize,
+}
+
+impl Codegen {
+    fn new_label(&mut self) -> usize {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn place(&mut self, label: usize) {
+        self.labels[label] = Some(self.code.len());
+        self.code.push(JUMPDEST);
+    }
+
+    fn push_label(&mut self, label: usize) {
+        self.code.push(PUSH3);
+        self.fixups.push((self.code.len(), label));
+        self.code.extend([0; 3]);
+    }
+
+    fn push(&mut self, value: U256) {
+        let bytes = value.to_be_bytes::<32>();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count().min(31);
+        self.code.push(PUSH1 + (31 - skip) as u8);
+        self.code.extend(&bytes[skip..]);
+    }
+
+    fn push_usize(&mut self, value: usize) {
+        self.push(U256::from(value));
+    }
+
+    fn alloc(&mut self) -> usize {
+        self.next_slot += 1;
+        32 * (self.next_slot - 1)
+    }
+
+    fn lookup(&self, name: &str) -> usize {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .copied()
+            .unwrap_or_else(|| panic!("undefined variable {name}"))
+    }
+
+    fn collect_functions(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Function(function) => {
+                    let info = FunctionInfo {
+                        function: function.clone(),
+                        label: self.new_label(),
+                        exit: self.new_label(),
+                        params: function.params.iter().map(|_| self.alloc()).collect(),
+                        ret: function.ret.as_ref().map(|_| self.alloc()),
+                    };
+                    self.functions.insert(function.name.clone(), info);
+                    self.collect_functions(&function.body);
+                }
+                Stmt::Block(body) | Stmt::If(_, body) => self.collect_functions(body),
+                Stmt::For { init, body, .. } => {
+                    self.collect_functions(init);
+                    self.collect_functions(body);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn function(&mut self, info: &FunctionInfo) {
+        let function = &info.function;
+        self.place(info.label);
+        // The first argument is on top of the stack, above the others and the return label.
+        for &slot in &info.params {
+            self.push_usize(slot);
+            self.code.push(MSTORE);
+        }
+        let mut scope: HashMap<String, usize> = function
+            .params
+            .iter()
+            .cloned()
+            .zip(info.params.clone())
+            .collect();
+        if let (Some(name), Some(slot)) = (&function.ret, info.ret) {
+            self.push_usize(0);
+            self.push_usize(slot);
+            self.code.push(MSTORE);
+            scope.insert(name.clone(), slot);
+        }
+        let scopes = std::mem::replace(&mut self.scopes, vec![scope]);
+        self.exit = Some(info.exit);
+        self.block(&function.body);
+        self.scopes = scopes;
+        self.place(info.exit);
+        if let Some(slot) = info.ret {
+            self.push_usize(slot);
+            self.code.push(MLOAD);
+            self.code.push(SWAP1);
+        }
+        self.code.push(JUMP);
+    }
+
+    fn block(&mut self, stmts: &[Stmt]) {
+        self.scopes.push(HashMap::new());
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(body) => self.block(body),
+            Stmt::Function(_) => {}
+            Stmt::Let(name, value) => {
+                match value {
+                    Some(value) => {
+                        self.expr(value);
+                    }
+                    None => self.push_usize(0),
+                }
+                let slot = self.alloc();
+                self.push_usize(slot);
+                self.code.push(MSTORE);
+                self.scopes.last_mut().unwrap().insert(name.clone(), slot);
+            }
+            Stmt::Assign(name, value) => {
+                self.expr(value);
+                let slot = self.lookup(name);
+                self.push_usize(slot);
+                self.code.push(MSTORE);
+            }
+            Stmt::If(cond, body) => {
+                let end = self.new_label();
+                self.expr(cond);
+                self.code.push(ISZERO);
+                self.push_label(end);
+                self.code.push(JUMPI);
+                self.block(body);
+                self.place(end);
+            }
+            Stmt::For {
+                init,
+                cond,
+                post,
+                body,
+            } => {
+                let (start, next, end) = (self.new_label(), self.new_label(), self.new_label());
+                self.scopes.push(HashMap::new());
+                for stmt in init {
+                    self.stmt(stmt);
+                }
+                self.place(start);
+                self.expr(cond);
+                self.code.push(ISZERO);
+                self.push_label(end);
+                self.code.push(JUMPI);
+                self.loops.push((next, end));
+                self.block(body);
+                self.loops.pop();
+                self.place(next);
+                self.block(post);
+                self.push_label(start);
+                self.code.push(JUMP);
+                self.place(end);
+                self.scopes.pop();
+            }
+            Stmt::Break | Stmt::Continue => {
+                let &(next, end) = self.loops.last().expect("not in a loop");
+                self.push_label(if matches!(stmt, Stmt::Break) {
+                    end
+                } else {
+                    next
+                });
+                self.code.push(JUMP);
+            }
+            Stmt::Leave => {
+                let exit = self.exit.expect("not in a function");
+                self.push_label(exit);
+                self.code.push(JUMP);
+            }
+            Stmt::Expr(expr) => {
+                if self.expr(expr) {
+                    self.code.push(POP);
+                }
+            }
+        }
+    }
+
+    /// Compile `expr`, returning whether it leaves a value on the stack.
+    fn expr(&mut self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Number(value) => self.push(*value),
+            Expr::Var(name) => {
+                let slot = self.lookup(name);
+                self.push_usize(slot);
+                self.code.push(MLOAD);
+            }
+            Expr::Call(name, args) => {
+                if let Some((opcode, num_args, returns, addresses_memory)) = builtin(name) {
+                    assert_eq!(args.len(), num_args, "wrong number of arguments to {name}");
+                    for arg in args.iter().rev() {
+                        self.expr(arg);
+                    }
+                    if addresses_memory {
+                        self.push_label(self.base_label);
+                        self.code.push(ADD);
+                    }
+                    self.code.push(opcode);
+                    return returns;
+                }
+                let info = self
+                    .functions
+                    .get(name)
+                    .unwrap_or_else(|| panic!("undefined function {name}"))
+                    .clone();
+                assert_eq!(
+                    args.len(),
+                    info.params.len(),
+                    "wrong number of arguments to {name}"
+                );
+                let ret = self.new_label();
+                self.push_label(ret);
+                for arg in args.iter().rev() {
+                    self.expr(arg);
+                }
+                self.push_label(info.label);
+                self.code.push(JUMP);
+                self.place(ret);
+                return info.ret.is_some();
+            }
+        }
+        true
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.labels[self.base_label] = Some(32 * self.next_slot);
+        for (pos, label) in self.fixups {
+            let target = self.labels[label].expect("unplaced label");
+            assert!(target < 1 << 24);
+            self.code[pos..pos + 3].copy_from_slice(&target.to_be_bytes()[5..]);
+        }
+        self.code
+    }
+}
@@ -6,12 +6,14 @@ extern crate alloc;
 
 mod compression;
 mod hasher;
+mod merkle_cap;
 mod permutation;
 mod serializing_hasher;
 mod sponge;
 
 pub use compression::*;
 pub use hasher::*;
+pub use merkle_cap::*;
 pub use permutation::*;
 pub use serializing_hasher::*;
 pub use sponge::*;
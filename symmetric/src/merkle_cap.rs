@@ -0,0 +1,52 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// The `2^cap_height` digests at height `cap_height` below the root of a Merkle tree, from left to
+/// right. A cap of height 0 is just the root.
+///
+/// Committing to a cap rather than the root makes the commitment larger, but shortens every
+/// opening proof by `cap_height` digests.
+///
+/// Digests are arrays of `DIGEST_ELEMS` words of type `W`. `F` is the field of the committed data,
+/// which is usually also the word type, but which is recorded even when digests are bytes, so that
+/// a cap can't be mistaken for a field element.
+#[derive(Clone, Debug, Deserialize, Eq, Serialize, PartialEq)]
+#[serde(transparent)]
+#[serde(bound(serialize = "[W; DIGEST_ELEMS]: Serialize"))]
+#[serde(bound(deserialize = "[W; DIGEST_ELEMS]: DeserializeOwned"))]
+pub struct MerkleCap<F, W, const DIGEST_ELEMS: usize> {
+    digests: Vec<[W; DIGEST_ELEMS]>,
+    #[serde(skip)]
+    _phantom: PhantomData<F>,
+}
+
+impl<F, W, const DIGEST_ELEMS: usize> MerkleCap<F, W, DIGEST_ELEMS> {
+    pub fn new(digests: Vec<[W; DIGEST_ELEMS]>) -> Self {
+        assert!(
+            digests.len().is_power_of_two(),
+            "a Merkle cap must hold a power of two digests"
+        );
+        Self {
+            digests,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn digests(&self) -> &[[W; DIGEST_ELEMS]] {
+        &self.digests
+    }
+
+    /// The log of the number of digests in the cap.
+    pub fn height(&self) -> usize {
+        self.digests.len().trailing_zeros() as usize
+    }
+}
+
+impl<F, W, const DIGEST_ELEMS: usize> From<[W; DIGEST_ELEMS]> for MerkleCap<F, W, DIGEST_ELEMS> {
+    fn from(root: [W; DIGEST_ELEMS]) -> Self {
+        Self::new(alloc::vec![root])
+    }
+}
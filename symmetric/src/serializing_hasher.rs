@@ -3,14 +3,14 @@ use p3_field::{PrimeField32, PrimeField64};
 use crate::CryptographicHasher;
 
 /// Maps input field elements to their 4-byte little-endian encodings, and maps output of the form
-/// `[u8; 32]` to `[F; 8]`.
+/// `[u8; 32]` to `[F; 8]`, or leaves it as bytes.
 #[derive(Copy, Clone, Default)]
 pub struct SerializingHasher32<Inner> {
     inner: Inner,
 }
 
 /// Maps input field elements to their 8-byte little-endian encodings, and maps output of the form
-/// `[u8; 32]` to `[F; 4]`.
+/// `[u8; 32]` to `[F; 4]`, or leaves it as bytes.
 #[derive(Copy, Clone, Default)]
 pub struct SerializingHasher64<Inner> {
     inner: Inner,
@@ -71,3 +71,37 @@ where
         })
     }
 }
+
+impl<F, Inner> CryptographicHasher<F, [u8; 32]> for SerializingHasher32<Inner>
+where
+    F: PrimeField32,
+    Inner: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn hash_iter<I>(&self, input: I) -> [u8; 32]
+    where
+        I: IntoIterator<Item = F>,
+    {
+        self.inner.hash_iter(
+            input
+                .into_iter()
+                .flat_map(|x| x.as_canonical_u32().to_le_bytes()),
+        )
+    }
+}
+
+impl<F, Inner> CryptographicHasher<F, [u8; 32]> for SerializingHasher64<Inner>
+where
+    F: PrimeField64,
+    Inner: CryptographicHasher<u8, [u8; 32]>,
+{
+    fn hash_iter<I>(&self, input: I) -> [u8; 32]
+    where
+        I: IntoIterator<Item = F>,
+    {
+        self.inner.hash_iter(
+            input
+                .into_iter()
+                .flat_map(|x| x.as_canonical_u64().to_le_bytes()),
+        )
+    }
+}
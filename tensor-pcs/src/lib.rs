@@ -9,3 +9,4 @@ mod tensor_pcs;
 mod wrapped_matrix;
 
 pub use tensor_pcs::*;
+pub use wrapped_matrix::*;
@@ -1,10 +1,17 @@
 use p3_util::log2_strict_usize;
 
-pub(crate) fn optimal_wraps(width: usize, height: usize) -> usize {
+/// The number of bits of wrapping, i.e. the log of the number of wraps, which minimizes the
+/// estimated cost of committing to a matrix with the given dimensions, among those which leave a
+/// supported message length.
+pub(crate) fn optimal_wrap_bits(
+    width: usize,
+    height: usize,
+    is_supported: impl Fn(usize) -> bool,
+) -> Option<usize> {
     let height_bits = log2_strict_usize(height);
-    (0..height_bits)
+    (0..=height_bits)
+        .filter(|&wrap_bits| is_supported(height >> wrap_bits))
         .min_by_key(|&wrap_bits| estimate_cost(width << wrap_bits, height >> wrap_bits))
-        .unwrap()
 }
 
 fn estimate_cost(width: usize, height: usize) -> usize {
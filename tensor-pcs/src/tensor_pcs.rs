@@ -1,16 +1,35 @@
+use alloc::vec;
 use alloc::vec::Vec;
 use core::marker::PhantomData;
 
+use itertools::{izip, Itertools};
+use p3_challenger::FieldChallenger;
 use p3_code::LinearCodeFamily;
-use p3_commit::{DirectMmcs, Pcs};
-use p3_field::Field;
+use p3_commit::{DirectMmcs, Mmcs, MultivariatePcs, OpenedValues, Pcs, ProofSize};
+use p3_field::{eq_evals, AbstractExtensionField, ExtensionField, Field};
 use p3_matrix::dense::RowMajorMatrix;
-use p3_matrix::MatrixRows;
+use p3_matrix::{Dimensions, Matrix, MatrixRows};
+use p3_util::codec::encoded_len;
+use p3_util::{log2_ceil_usize, log2_strict_usize};
+use serde::{Deserialize, Serialize};
 
-use crate::reshape::optimal_wraps;
+use crate::reshape::optimal_wrap_bits;
 use crate::wrapped_matrix::WrappedMatrix;
 
-pub struct TensorPcs<F, C, M>
+/// A PCS for multilinear polynomials based on a tensor code, in the style of Ligero and Brakedown.
+///
+/// Each committed matrix is wrapped into a matrix of `2^k` times its width, whose columns are then
+/// encoded with a code from `codes`. To open a polynomial at a point `z`, whose first `k`
+/// coordinates pick out a block of the wrapped matrix and whose other coordinates pick out a row of
+/// the block, the prover sends the combination of the wrapped matrix's columns with `eq` of the
+/// first `k` coordinates. The verifier checks that its combination with `eq` of the rest gives the
+/// opened values, and that its encoding agrees with the same combination of the committed codewords
+/// at a few random rows. The prover also sends a random combination of all the wrapped columns,
+/// which is checked the same way, to show that the committed codewords are close to the code.
+///
+/// Matrices must have power-of-two heights, and the codeword matrices are padded with zero rows up
+/// to a power-of-two height.
+pub struct TensorPcs<F, EF, C, M>
 where
     F: Field,
     C: LinearCodeFamily<F, WrappedMatrix<F, RowMajorMatrix<F>>>,
@@ -18,49 +37,581 @@ where
 {
     codes: C,
     mmcs: M,
+    num_queries: usize,
     _phantom_f: PhantomData<F>,
-    _phantom_m: PhantomData<M>,
+    _phantom_ef: PhantomData<EF>,
 }
 
-impl<F, C, M> TensorPcs<F, C, M>
+impl<F, EF, C, M> TensorPcs<F, EF, C, M>
 where
     F: Field,
-    C: LinearCodeFamily<F, WrappedMatrix<F, RowMajorMatrix<F>>, Out = RowMajorMatrix<F>>,
+    C: LinearCodeFamily<F, WrappedMatrix<F, RowMajorMatrix<F>>>,
     M: DirectMmcs<F>,
 {
-    pub fn new(codes: C, mmcs: M) -> Self {
+    pub fn new(codes: C, mmcs: M, num_queries: usize) -> Self {
         Self {
             codes,
             mmcs,
+            num_queries,
             _phantom_f: PhantomData,
-            _phantom_m: PhantomData,
+            _phantom_ef: PhantomData,
+        }
+    }
+
+    /// How a matrix with the given dimensions is wrapped and encoded, or `None` if its height isn't
+    /// a power of two or no code supports any wrapping of it.
+    fn layout(&self, dims: Dimensions) -> Option<Layout> {
+        if !dims.height.is_power_of_two() {
+            return None;
+        }
+        let wrap_bits = optimal_wrap_bits(dims.width, dims.height, |message_len| {
+            self.codes.next_message_len(message_len) == Some(message_len)
+        })?;
+        let message_len = dims.height >> wrap_bits;
+        Some(Layout {
+            width: dims.width,
+            log_height: log2_strict_usize(dims.height),
+            wrap_bits,
+            message_len,
+            codeword_len: self.codes.codeword_len(message_len)?,
+        })
+    }
+}
+
+/// The shape of a committed matrix, once wrapped and encoded.
+#[derive(Copy, Clone, Debug)]
+struct Layout {
+    width: usize,
+    log_height: usize,
+    wrap_bits: usize,
+    message_len: usize,
+    codeword_len: usize,
+}
+
+impl Layout {
+    fn wrapped_width(&self) -> usize {
+        self.width << self.wrap_bits
+    }
+
+    fn log_padded_height(&self) -> usize {
+        log2_ceil_usize(self.codeword_len)
+    }
+
+    fn codeword_dims(&self) -> Dimensions {
+        Dimensions {
+            width: self.wrapped_width(),
+            height: 1 << self.log_padded_height(),
         }
     }
+
+    /// The weights of the wrapped matrix's columns whose combination, restricted to the rows picked
+    /// out by the rest of `point`, gives the combination of the polynomials' values at `point` with
+    /// the powers of alpha.
+    fn eval_weights<EF: Field>(&self, point: &[EF], alpha_pows: &[EF]) -> Vec<EF> {
+        eq_evals(&point[..self.wrap_bits])
+            .into_iter()
+            .flat_map(|eq| alpha_pows.iter().map(move |&alpha_pow| eq * alpha_pow))
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub enum TensorPcsError<MmcsErr> {
+    InvalidProofShape,
+    MmcsError(MmcsErr),
+    /// The message sent for an opening doesn't agree with the opened values.
+    EvaluationMismatch,
+    /// A queried row of the committed codewords doesn't agree with the encoding of a message.
+    CodewordMismatch,
+}
+
+#[derive(Clone)]
+pub struct TensorPcsProverData<F, M: Mmcs<F>> {
+    data: M::ProverData,
+    /// The committed matrices, before encoding.
+    polynomials: Vec<RowMajorMatrix<F>>,
 }
 
-impl<F, In, C, M> Pcs<F, In> for TensorPcs<F, C, M>
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound = "")]
+pub struct TensorPcsProof<F: Field, EF: Field, M: Mmcs<F>> {
+    /// For each batch, matrix and point, the combination of the wrapped matrix's columns which the
+    /// point's first coordinates and alpha pick out.
+    pub(crate) eval_messages: Vec<Vec<Vec<Vec<EF>>>>,
+    /// For each batch and matrix, a random combination of the wrapped matrix's columns.
+    pub(crate) proximity_messages: Vec<Vec<Vec<EF>>>,
+    /// For each batch, the batch's openings at every query.
+    pub(crate) batch_openings: Vec<TensorBatchOpening<F, M>>,
+}
+
+impl<F: Field, EF: Field, M: Mmcs<F>> TensorPcsProof<F, EF, M> {
+    pub fn eval_messages(&self) -> &[Vec<Vec<Vec<EF>>>] {
+        &self.eval_messages
+    }
+
+    pub fn proximity_messages(&self) -> &[Vec<Vec<EF>>] {
+        &self.proximity_messages
+    }
+
+    pub fn batch_openings(&self) -> &[TensorBatchOpening<F, M>] {
+        &self.batch_openings
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound = "")]
+pub struct TensorBatchOpening<F: Field, M: Mmcs<F>> {
+    pub(crate) opened_values: Vec<Vec<Vec<F>>>,
+    pub(crate) opening_proof: M::MultiProof,
+}
+
+impl<F: Field, M: Mmcs<F>> TensorBatchOpening<F, M> {
+    /// For each query, the opened row of each matrix's codewords in the batch.
+    pub fn opened_values(&self) -> &[Vec<Vec<F>>] {
+        &self.opened_values
+    }
+
+    /// The opening proof of the batch's rows at all queries.
+    pub fn opening_proof(&self) -> &M::MultiProof {
+        &self.opening_proof
+    }
+}
+
+impl<F, EF, In, C, M> Pcs<F, In> for TensorPcs<F, EF, C, M>
 where
     F: Field,
+    EF: ExtensionField<F>,
     In: MatrixRows<F>,
-    C: LinearCodeFamily<F, WrappedMatrix<F, RowMajorMatrix<F>>, Out = RowMajorMatrix<F>>,
+    C: LinearCodeFamily<F, WrappedMatrix<F, RowMajorMatrix<F>>>,
     M: DirectMmcs<F>,
 {
     type Commitment = M::Commitment;
-    type ProverData = M::ProverData;
-    type Proof = Vec<M::Proof>;
-    type Error = ();
+    type ProverData = TensorPcsProverData<F, M>;
+    type Proof = TensorPcsProof<F, EF, M>;
+    type Error = TensorPcsError<M::Error>;
 
     fn commit_batches(&self, polynomials: Vec<In>) -> (Self::Commitment, Self::ProverData) {
-        let encoded_polynomials = polynomials
+        let polynomials = polynomials
             .into_iter()
+            .map(|mat| mat.to_row_major_matrix())
+            .collect_vec();
+        let encoded_polynomials = polynomials
+            .iter()
             .map(|mat| {
-                let wraps = optimal_wraps(mat.width(), mat.height());
-                let wrapped = WrappedMatrix::new(mat.to_row_major_matrix(), wraps);
-                self.codes.encode_batch(wrapped)
+                let layout = self.layout(mat.dimensions()).unwrap_or_else(|| {
+                    panic!(
+                        "No code supports a wrapping of a matrix with dimensions {:?}",
+                        mat.dimensions()
+                    )
+                });
+                let wrapped = WrappedMatrix::new(mat.clone(), 1 << layout.wrap_bits);
+                let mut encoded = self.codes.encode_batch(wrapped).to_row_major_matrix();
+                assert_eq!(encoded.height(), layout.codeword_len);
+                let codeword_dims = layout.codeword_dims();
+                encoded
+                    .values
+                    .resize(codeword_dims.width * codeword_dims.height, F::zero());
+                encoded
             })
             .collect();
-        self.mmcs.commit(encoded_polynomials)
+        let (commit, data) = self.mmcs.commit(encoded_polynomials);
+        (commit, TensorPcsProverData { data, polynomials })
+    }
+
+    fn num_queries(&self) -> usize {
+        self.num_queries
+    }
+
+    fn shape_params(&self) -> Vec<usize> {
+        vec![self.mmcs.cap_height()]
+    }
+
+    fn proof_size(&self, proof: &Self::Proof) -> ProofSize {
+        let mut size = ProofSize::default();
+        for batch_opening in &proof.batch_openings {
+            size.opened_values += encoded_len(&batch_opening.opened_values);
+            size.merkle_paths += encoded_len(&batch_opening.opening_proof);
+        }
+        size.other = encoded_len(proof) - size.total();
+        size
     }
 }
 
-// TODO: Impl MultivariatePcs
+impl<F, EF, In, C, M, Challenger> MultivariatePcs<F, EF, In, Challenger> for TensorPcs<F, EF, C, M>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    In: MatrixRows<F>,
+    C: LinearCodeFamily<F, WrappedMatrix<F, RowMajorMatrix<F>>>,
+    M: DirectMmcs<F>,
+    Challenger: FieldChallenger<F>,
+{
+    fn open_multi_batches(
+        &self,
+        prover_data_and_points: &[(&Self::ProverData, &[Vec<Vec<EF>>])],
+        challenger: &mut Challenger,
+    ) -> (OpenedValues<EF>, Self::Proof) {
+        let layouts = prover_data_and_points
+            .iter()
+            .map(|(data, _)| {
+                data.polynomials
+                    .iter()
+                    .map(|mat| self.layout(mat.dimensions()).unwrap())
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        let opened_values: OpenedValues<EF> = prover_data_and_points
+            .iter()
+            .map(|(data, points_for_batch)| {
+                assert_eq!(data.polynomials.len(), points_for_batch.len());
+                data.polynomials
+                    .iter()
+                    .zip(points_for_batch.iter())
+                    .map(|(mat, points)| {
+                        points
+                            .iter()
+                            .map(|point| evaluate_multilinear(mat, point))
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        for &value in opened_values.iter().flatten().flatten().flatten() {
+            challenger.observe_ext_element(value);
+        }
+
+        let alpha: EF = challenger.sample_ext_element();
+        let eval_messages = izip!(prover_data_and_points, &layouts)
+            .map(|((data, points_for_batch), layouts)| {
+                izip!(&data.polynomials, *points_for_batch, layouts)
+                    .map(|(mat, points, layout)| {
+                        let alpha_pows = alpha.powers().take(layout.width).collect_vec();
+                        let wrapped = WrappedMatrix::new(mat.as_view(), 1 << layout.wrap_bits);
+                        points
+                            .iter()
+                            .map(|point| {
+                                assert_eq!(point.len(), layout.log_height);
+                                combine_columns(&wrapped, &layout.eval_weights(point, &alpha_pows))
+                            })
+                            .collect_vec()
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+        for &value in eval_messages.iter().flatten().flatten().flatten() {
+            challenger.observe_ext_element(value);
+        }
+
+        let gamma: EF = challenger.sample_ext_element();
+        let proximity_messages = izip!(prover_data_and_points, &layouts)
+            .map(|((data, _), layouts)| {
+                izip!(&data.polynomials, layouts)
+                    .map(|(mat, layout)| {
+                        let gamma_pows = gamma.powers().take(layout.wrapped_width()).collect_vec();
+                        let wrapped = WrappedMatrix::new(mat.as_view(), 1 << layout.wrap_bits);
+                        combine_columns(&wrapped, &gamma_pows)
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+        for &value in proximity_messages.iter().flatten().flatten() {
+            challenger.observe_ext_element(value);
+        }
+
+        let log_max_height = max_log_padded_height(layouts.iter().flatten());
+        let query_indices = (0..self.num_queries)
+            .map(|_| challenger.sample_bits(log_max_height))
+            .collect_vec();
+        let batch_openings = izip!(prover_data_and_points, &layouts)
+            .map(|((data, _), layouts)| {
+                let bits_reduced = log_max_height - max_log_padded_height(layouts);
+                let indices = query_indices
+                    .iter()
+                    .map(|&index| index >> bits_reduced)
+                    .collect_vec();
+                let (opened_values, opening_proof) =
+                    self.mmcs.open_multi_batch(&indices, &data.data);
+                TensorBatchOpening {
+                    opened_values,
+                    opening_proof,
+                }
+            })
+            .collect();
+
+        (
+            opened_values,
+            TensorPcsProof {
+                eval_messages,
+                proximity_messages,
+                batch_openings,
+            },
+        )
+    }
+
+    fn verify_multi_batches(
+        &self,
+        commits_and_points: &[(Self::Commitment, &[Vec<Vec<EF>>])],
+        dims: &[Vec<Dimensions>],
+        values: OpenedValues<EF>,
+        proof: &Self::Proof,
+        challenger: &mut Challenger,
+    ) -> Result<(), Self::Error> {
+        let TensorPcsProof {
+            eval_messages,
+            proximity_messages,
+            batch_openings,
+        } = proof;
+
+        let num_batches = commits_and_points.len();
+        if dims.len() != num_batches
+            || values.len() != num_batches
+            || eval_messages.len() != num_batches
+            || proximity_messages.len() != num_batches
+            || batch_openings.len() != num_batches
+        {
+            return Err(TensorPcsError::InvalidProofShape);
+        }
+        let layouts = dims
+            .iter()
+            .map(|dims_for_batch| {
+                dims_for_batch
+                    .iter()
+                    .map(|&dims| self.layout(dims))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(TensorPcsError::InvalidProofShape)?;
+        for (layouts, (_, points_for_batch), values, eval_messages, proximity_messages) in izip!(
+            &layouts,
+            commits_and_points,
+            &values,
+            eval_messages,
+            proximity_messages
+        ) {
+            let num_matrices = layouts.len();
+            if points_for_batch.len() != num_matrices
+                || values.len() != num_matrices
+                || eval_messages.len() != num_matrices
+                || proximity_messages.len() != num_matrices
+            {
+                return Err(TensorPcsError::InvalidProofShape);
+            }
+            for (layout, points, values, eval_messages, proximity_message) in izip!(
+                layouts,
+                *points_for_batch,
+                values,
+                eval_messages,
+                proximity_messages
+            ) {
+                if points.iter().any(|point| point.len() != layout.log_height)
+                    || values.len() != points.len()
+                    || values.iter().any(|v| v.len() != layout.width)
+                    || eval_messages.len() != points.len()
+                    || eval_messages
+                        .iter()
+                        .any(|message| message.len() != layout.message_len)
+                    || proximity_message.len() != layout.message_len
+                {
+                    return Err(TensorPcsError::InvalidProofShape);
+                }
+            }
+        }
+
+        for &value in values.iter().flatten().flatten().flatten() {
+            challenger.observe_ext_element(value);
+        }
+        let alpha: EF = challenger.sample_ext_element();
+
+        // Each message's combination with `eq` of the rest of its point must be the combination of
+        // the opened values with the powers of alpha.
+        for (layouts, (_, points_for_batch), values, eval_messages) in
+            izip!(&layouts, commits_and_points, &values, eval_messages)
+        {
+            for (layout, points, values, eval_messages) in
+                izip!(layouts, *points_for_batch, values, eval_messages)
+            {
+                let alpha_pows = alpha.powers().take(layout.width).collect_vec();
+                for (point, values, message) in izip!(points, values, eval_messages) {
+                    let eq = eq_evals(&point[layout.wrap_bits..]);
+                    let combined_message = dot::<EF, EF>(&eq, message);
+                    let combined_values = dot::<EF, EF>(&alpha_pows, values);
+                    if combined_message != combined_values {
+                        return Err(TensorPcsError::EvaluationMismatch);
+                    }
+                }
+            }
+        }
+        for &value in eval_messages.iter().flatten().flatten().flatten() {
+            challenger.observe_ext_element(value);
+        }
+
+        let gamma: EF = challenger.sample_ext_element();
+        for &value in proximity_messages.iter().flatten().flatten() {
+            challenger.observe_ext_element(value);
+        }
+
+        let log_max_height = max_log_padded_height(layouts.iter().flatten());
+        let query_indices = (0..self.num_queries)
+            .map(|_| challenger.sample_bits(log_max_height))
+            .collect_vec();
+
+        for (
+            layouts,
+            (commit, points_for_batch),
+            eval_messages,
+            proximity_messages,
+            batch_opening,
+        ) in izip!(
+            &layouts,
+            commits_and_points,
+            eval_messages,
+            proximity_messages,
+            batch_openings
+        ) {
+            let batch_log_max_height = max_log_padded_height(layouts);
+            let indices = query_indices
+                .iter()
+                .map(|&index| index >> (log_max_height - batch_log_max_height))
+                .collect_vec();
+            let opened_rows = &batch_opening.opened_values;
+            if opened_rows.len() != self.num_queries
+                || opened_rows.iter().any(|rows| {
+                    rows.len() != layouts.len()
+                        || izip!(rows, layouts)
+                            .any(|(row, layout)| row.len() != layout.wrapped_width())
+                })
+            {
+                return Err(TensorPcsError::InvalidProofShape);
+            }
+            self.mmcs
+                .verify_multi_batch(
+                    commit,
+                    &layouts.iter().map(Layout::codeword_dims).collect_vec(),
+                    &indices,
+                    opened_rows,
+                    &batch_opening.opening_proof,
+                )
+                .map_err(TensorPcsError::MmcsError)?;
+
+            for (mat_index, (layout, points, eval_messages, proximity_message)) in izip!(
+                layouts,
+                *points_for_batch,
+                eval_messages,
+                proximity_messages
+            )
+            .enumerate()
+            {
+                let alpha_pows = alpha.powers().take(layout.width).collect_vec();
+                let gamma_pows = gamma.powers().take(layout.wrapped_width()).collect_vec();
+                let weights = points
+                    .iter()
+                    .map(|point| layout.eval_weights(point, &alpha_pows))
+                    .chain([gamma_pows])
+                    .collect_vec();
+                let messages = eval_messages
+                    .iter()
+                    .chain([proximity_message])
+                    .collect_vec();
+                let encoded = self.encode_messages(&messages);
+
+                for (&index, rows) in izip!(&indices, opened_rows) {
+                    let row = &rows[mat_index];
+                    let row_index = index >> (batch_log_max_height - layout.log_padded_height());
+                    if row_index >= layout.codeword_len {
+                        // Rows past the codewords are padding.
+                        if row.iter().any(|x| !x.is_zero()) {
+                            return Err(TensorPcsError::CodewordMismatch);
+                        }
+                        continue;
+                    }
+                    for (weights, &expected) in izip!(&weights, &encoded[row_index]) {
+                        if dot(weights, row) != expected {
+                            return Err(TensorPcsError::CodewordMismatch);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<F, EF, C, M> TensorPcs<F, EF, C, M>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    C: LinearCodeFamily<F, WrappedMatrix<F, RowMajorMatrix<F>>>,
+    M: DirectMmcs<F>,
+{
+    /// Encode messages over the extension field, which must all have the same length, by encoding
+    /// each of their coordinates over `F`. Returns each row of the codewords.
+    fn encode_messages(&self, messages: &[&Vec<EF>]) -> Vec<Vec<EF>> {
+        let ext_degree = <EF as AbstractExtensionField<F>>::D;
+        let message_len = messages[0].len();
+        let values = (0..message_len)
+            .flat_map(|r| {
+                messages
+                    .iter()
+                    .flat_map(move |message| message[r].as_base_slice().iter().copied())
+            })
+            .collect();
+        let mat = RowMajorMatrix::new(values, messages.len() * ext_degree);
+        self.codes
+            .encode_batch(WrappedMatrix::new(mat, 1))
+            .to_row_major_matrix()
+            .rows()
+            .map(|row| row.chunks(ext_degree).map(EF::from_base_slice).collect())
+            .collect()
+    }
+}
+
+/// The largest log of the padded height of any codewords.
+fn max_log_padded_height<'a>(layouts: impl IntoIterator<Item = &'a Layout>) -> usize {
+    layouts
+        .into_iter()
+        .map(Layout::log_padded_height)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Evaluate the multilinear polynomials whose evaluations over the hypercube are the columns of
+/// `mat`.
+fn evaluate_multilinear<F: Field, EF: ExtensionField<F>>(
+    mat: &RowMajorMatrix<F>,
+    point: &[EF],
+) -> Vec<EF> {
+    assert_eq!(1 << point.len(), mat.height());
+    let mut values = vec![EF::zero(); mat.width()];
+    for (eq, row) in eq_evals(point).into_iter().zip(mat.rows()) {
+        for (value, &x) in values.iter_mut().zip(row) {
+            *value += eq * x;
+        }
+    }
+    values
+}
+
+/// Multiply a matrix by a vector of weights, one per column.
+fn combine_columns<F: Field, EF: ExtensionField<F>>(
+    mat: &impl MatrixRows<F>,
+    weights: &[EF],
+) -> Vec<EF> {
+    (0..mat.height())
+        .map(|r| {
+            mat.row(r)
+                .into_iter()
+                .zip(weights)
+                .map(|(x, &weight)| weight * x)
+                .sum()
+        })
+        .collect()
+}
+
+fn dot<F: Field, EF: ExtensionField<F>>(weights: &[EF], values: &[F]) -> EF {
+    weights
+        .iter()
+        .zip(values)
+        .map(|(&weight, &value)| weight * value)
+        .sum()
+}
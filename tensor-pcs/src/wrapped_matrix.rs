@@ -2,6 +2,9 @@ use core::marker::PhantomData;
 
 use p3_matrix::{Matrix, MatrixRows};
 
+/// A view of a matrix with its rows split into `wraps` consecutive blocks, which are placed side by
+/// side. Row `r` of the wrapped matrix is rows `r`, `r + h`, `r + 2h`, ... of the inner matrix
+/// joined together, where `h` is the wrapped matrix's height.
 pub struct WrappedMatrix<T, M> {
     inner: M,
     wraps: usize,
@@ -13,6 +16,7 @@ where
     M: Matrix<T>,
 {
     pub fn new(inner: M, wraps: usize) -> Self {
+        assert!(wraps > 0);
         assert_eq!(inner.height() % wraps, 0);
         Self {
             inner,
@@ -31,7 +35,7 @@ where
     }
 
     fn height(&self) -> usize {
-        self.inner.width() / self.wraps
+        self.inner.height() / self.wraps
     }
 }
 
@@ -79,7 +83,7 @@ where
                 self.current_iter = self
                     .wrapped_matrix
                     .inner
-                    .row(self.next_wrap * self.wrapped_matrix.wraps + self.row)
+                    .row(self.next_wrap * self.wrapped_matrix.height() + self.row)
                     .into_iter();
                 self.next_wrap += 1;
                 self.current_iter.next().unwrap()
@@ -0,0 +1,299 @@
+use p3_brakedown::fast_registry;
+use p3_challenger::{CanObserve, FieldChallenger, SerializingChallenger32};
+use p3_code::{IdentityCode, LinearCodeFamily, SLCodeRegistry, SystematicLinearCode};
+use p3_commit::{MultivariatePcs, OpenedValues, Pcs};
+use p3_field::extension::Complex;
+use p3_field::{AbstractExtensionField, AbstractField};
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{Dimensions, Matrix, MatrixTranspose};
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_mersenne_31::Mersenne31;
+use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher32};
+use p3_tensor_pcs::{TensorPcs, TensorPcsError, WrappedMatrix};
+use rand::thread_rng;
+
+type Val = Mersenne31;
+type Challenge = Complex<Mersenne31>;
+
+type ByteHash = Keccak256Hash;
+type FieldHash = SerializingHasher32<ByteHash>;
+type MyCompress = CompressionFunctionFromHasher<u8, ByteHash, 2, 32>;
+type ValMmcs = FieldMerkleTreeMmcs<Val, FieldHash, MyCompress, 32, u8>;
+type Challenger = SerializingChallenger32<Val, ByteHash>;
+type Message = WrappedMatrix<Val, RowMajorMatrix<Val>>;
+type MyPcs<C> = TensorPcs<Val, Challenge, C, ValMmcs>;
+type Commitment = <ValMmcs as p3_commit::Mmcs<Val>>::Commitment;
+
+/// A registry of identity codes, which supports every power-of-two message length up to `2^10`.
+fn identity_registry() -> SLCodeRegistry<Val, Message, Message> {
+    SLCodeRegistry::new(
+        (0..=10)
+            .map(|log_len| {
+                Box::new(IdentityCode { len: 1 << log_len })
+                    as Box<dyn SystematicLinearCode<Val, Message, Out = Message>>
+            })
+            .collect(),
+    )
+}
+
+fn make_pcs<C: LinearCodeFamily<Val, Message>>(codes: C) -> MyPcs<C> {
+    let mmcs = ValMmcs::new(FieldHash::new(ByteHash {}), MyCompress::new(ByteHash {}));
+    TensorPcs::new(codes, mmcs, 10)
+}
+
+fn sample_point(challenger: &mut Challenger, num_variables: usize) -> Vec<Challenge> {
+    (0..num_variables)
+        .map(|_| challenger.sample_ext_element())
+        .collect()
+}
+
+/// Evaluate a multilinear polynomial, given its evaluations over the hypercube, by fixing its
+/// variables one at a time, starting with the first.
+fn evaluate_naive(evals: &[Val], point: &[Challenge]) -> Challenge {
+    let mut evals = evals
+        .iter()
+        .map(|&x| Challenge::from_base(x))
+        .collect::<Vec<_>>();
+    for &z in point {
+        let (lo, hi) = evals.split_at(evals.len() / 2);
+        evals = lo.iter().zip(hi).map(|(&l, &h)| l + z * (h - l)).collect();
+    }
+    evals[0]
+}
+
+/// A committed batch of matrices, with the points at which to open each matrix.
+struct Batch {
+    polynomials: Vec<RowMajorMatrix<Val>>,
+    points: Vec<Vec<Vec<Challenge>>>,
+}
+
+impl Batch {
+    fn dims(&self) -> Vec<Dimensions> {
+        self.polynomials.iter().map(|p| p.dimensions()).collect()
+    }
+}
+
+/// Commit to batches of random matrices with the given log heights and width, and open each of them
+/// at `num_points` random points.
+#[allow(clippy::type_complexity)]
+fn open<C: LinearCodeFamily<Val, Message>>(
+    pcs: &MyPcs<C>,
+    log_heights: &[&[usize]],
+    width: usize,
+    num_points: usize,
+) -> (
+    Vec<Batch>,
+    Vec<Commitment>,
+    OpenedValues<Challenge>,
+    <MyPcs<C> as Pcs<Val, RowMajorMatrix<Val>>>::Proof,
+) {
+    let mut rng = thread_rng();
+    let mut challenger = Challenger::from_hasher(ByteHash {});
+
+    let (commits, data): (Vec<_>, Vec<_>) = log_heights
+        .iter()
+        .map(|batch_log_heights| {
+            let polynomials = batch_log_heights
+                .iter()
+                .map(|&log_height| RowMajorMatrix::rand(&mut rng, 1 << log_height, width))
+                .collect::<Vec<_>>();
+            let (commit, data) = pcs.commit_batches(polynomials.clone());
+            challenger.observe(commit.clone());
+            (commit, (polynomials, data))
+        })
+        .unzip();
+
+    let batches = data
+        .iter()
+        .map(|(polynomials, _)| Batch {
+            polynomials: polynomials.clone(),
+            points: polynomials
+                .iter()
+                .map(|mat| {
+                    let num_variables = p3_util::log2_strict_usize(mat.height());
+                    (0..num_points)
+                        .map(|_| sample_point(&mut challenger, num_variables))
+                        .collect()
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    let (opened_values, proof) =
+        <MyPcs<C> as MultivariatePcs<_, _, RowMajorMatrix<Val>, _>>::open_multi_batches(
+            pcs,
+            &data
+                .iter()
+                .zip(&batches)
+                .map(|((_, data), batch)| (data, batch.points.as_slice()))
+                .collect::<Vec<_>>(),
+            &mut challenger,
+        );
+
+    // The opened values are the polynomials' evaluations at the points.
+    for (batch, values_for_batch) in batches.iter().zip(&opened_values) {
+        for (mat, points, values) in
+            itertools::izip!(&batch.polynomials, &batch.points, values_for_batch)
+        {
+            let mat_t = mat.clone().transpose();
+            for (point, values_at_point) in points.iter().zip(values) {
+                let expected = mat_t
+                    .rows()
+                    .map(|column| evaluate_naive(column, point))
+                    .collect::<Vec<_>>();
+                assert_eq!(&expected, values_at_point);
+            }
+        }
+    }
+
+    (batches, commits, opened_values, proof)
+}
+
+fn verify<C: LinearCodeFamily<Val, Message>>(
+    pcs: &MyPcs<C>,
+    batches: &[Batch],
+    commits: &[Commitment],
+    dims: &[Vec<Dimensions>],
+    opened_values: OpenedValues<Challenge>,
+    proof: &<MyPcs<C> as Pcs<Val, RowMajorMatrix<Val>>>::Proof,
+) -> Result<(), TensorPcsError<<ValMmcs as p3_commit::Mmcs<Val>>::Error>> {
+    let mut challenger = Challenger::from_hasher(ByteHash {});
+    for commit in commits {
+        challenger.observe(commit.clone());
+    }
+    for batch in batches {
+        for point in batch.points.iter().flatten() {
+            sample_point(&mut challenger, point.len());
+        }
+    }
+    <MyPcs<C> as MultivariatePcs<_, _, RowMajorMatrix<Val>, _>>::verify_multi_batches(
+        pcs,
+        &commits
+            .iter()
+            .zip(batches)
+            .map(|(commit, batch)| (commit.clone(), batch.points.as_slice()))
+            .collect::<Vec<_>>(),
+        dims,
+        opened_values,
+        proof,
+        &mut challenger,
+    )
+}
+
+fn dims(batches: &[Batch]) -> Vec<Vec<Dimensions>> {
+    batches.iter().map(Batch::dims).collect()
+}
+
+fn do_test_tensor_pcs<C: LinearCodeFamily<Val, Message>>(
+    codes: C,
+    log_heights: &[&[usize]],
+    width: usize,
+    num_points: usize,
+) {
+    let pcs = make_pcs(codes);
+    let (batches, commits, opened_values, proof) = open(&pcs, log_heights, width, num_points);
+    verify(
+        &pcs,
+        &batches,
+        &commits,
+        &dims(&batches),
+        opened_values,
+        &proof,
+    )
+    .expect("verification error");
+}
+
+#[test]
+fn test_tensor_pcs_single() {
+    do_test_tensor_pcs(identity_registry(), &[&[4]], 3, 1);
+}
+
+#[test]
+fn test_tensor_pcs_smallest() {
+    do_test_tensor_pcs(identity_registry(), &[&[0]], 1, 1);
+}
+
+#[test]
+fn test_tensor_pcs_many_batches_and_points() {
+    do_test_tensor_pcs(identity_registry(), &[&[3, 6], &[5], &[2, 2]], 2, 3);
+}
+
+#[test]
+fn test_tensor_pcs_brakedown() {
+    // The fast registry has codes for messages of length `2^14` and `2^16`, so the taller matrix
+    // is wrapped.
+    do_test_tensor_pcs(fast_registry(), &[&[14, 15]], 2, 1);
+}
+
+#[test]
+fn test_tensor_pcs_rejects_wrong_values() {
+    let pcs = make_pcs(identity_registry());
+    let (batches, commits, opened_values, proof) = open(&pcs, &[&[3, 5]], 2, 2);
+    let dims = dims(&batches);
+
+    let mut wrong_value = opened_values;
+    wrong_value[0][1][1][0] += Challenge::one();
+    assert!(matches!(
+        verify(&pcs, &batches, &commits, &dims, wrong_value, &proof),
+        Err(TensorPcsError::EvaluationMismatch)
+    ));
+}
+
+#[test]
+fn test_tensor_pcs_rejects_wrong_messages() {
+    let pcs = make_pcs(identity_registry());
+    let (batches, commits, opened_values, proof) = open(&pcs, &[&[4]], 2, 1);
+    let dims = dims(&batches);
+
+    // The proof's messages are only reachable through serialization.
+    let mut proof_value = serde_json::to_value(&proof).unwrap();
+    for x in proof_value["proximity_messages"][0][0]
+        .as_array_mut()
+        .unwrap()
+    {
+        let value: Challenge = serde_json::from_value(x.clone()).unwrap();
+        *x = serde_json::to_value(value + Challenge::one()).unwrap();
+    }
+    let wrong_proof = serde_json::from_value(proof_value).unwrap();
+    // Whichever rows the verifier queries, their combination doesn't match the encoded message.
+    assert!(verify(&pcs, &batches, &commits, &dims, opened_values, &wrong_proof).is_err());
+}
+
+#[test]
+fn test_tensor_pcs_rejects_wrong_shapes() {
+    let pcs = make_pcs(identity_registry());
+    let (batches, commits, opened_values, proof) = open(&pcs, &[&[3, 5]], 2, 1);
+    let dims = dims(&batches);
+
+    let mut bad_height = dims.clone();
+    bad_height[0][0].height = 12;
+    assert!(matches!(
+        verify(
+            &pcs,
+            &batches,
+            &commits,
+            &bad_height,
+            opened_values.clone(),
+            &proof
+        ),
+        Err(TensorPcsError::InvalidProofShape)
+    ));
+    let mut too_tall = dims.clone();
+    too_tall[0][0].height = 1 << 20;
+    assert!(verify(
+        &pcs,
+        &batches,
+        &commits,
+        &too_tall,
+        opened_values.clone(),
+        &proof
+    )
+    .is_err());
+    let mut missing_value = opened_values;
+    missing_value[0][0][0].pop();
+    assert!(matches!(
+        verify(&pcs, &batches, &commits, &dims, missing_value, &proof),
+        Err(TensorPcsError::InvalidProofShape)
+    ));
+}
@@ -0,0 +1,89 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use p3_air::{Air, BaseAir, WindowMatrixView};
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractField, Field, PackedField};
+use p3_fri::{TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_keccak_air::KeccakAir;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    get_constraint_program, ConstraintRegisters, PackedChallenge, PackedConstraintProgram,
+    PackedVal, ProverConstraintFolder, StarkConfig,
+};
+use rand::random;
+
+type Val = BabyBear;
+type Challenge = BinomialExtensionField<Val, 4>;
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type Pcs =
+    TwoAdicFriPcs<TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+/// Compare evaluating the constraints of `KeccakAir` on a packed batch of rows, as the prover's
+/// quotient loop does, with `Air::eval` and by interpreting its compiled `ConstraintProgram`, either
+/// generically or as a `PackedConstraintProgram`.
+fn bench_constraint_program(c: &mut Criterion) {
+    let air = KeccakAir {};
+    let program = get_constraint_program::<Val, _>(&air, 0);
+    let packed_program = PackedConstraintProgram::new(&program);
+    let alpha: Challenge = random();
+    let alpha_powers = packed_program.alpha_powers(alpha);
+    let packed = || PackedVal::<MyConfig>::from_fn(|_| random());
+    let main: Vec<Vec<_>> = (0..BaseAir::<Val>::window_size(&air))
+        .map(|_| (0..BaseAir::<Val>::width(&air)).map(|_| packed()).collect())
+        .collect();
+    let no_rows: Vec<Vec<PackedVal<MyConfig>>> = vec![];
+    let no_permutation: Vec<Vec<PackedChallenge<MyConfig>>> = vec![];
+    let folder = || ProverConstraintFolder::<MyConfig> {
+        preprocessed: WindowMatrixView::new(&no_rows),
+        main: WindowMatrixView::new(&main),
+        permutation: WindowMatrixView::new(&no_permutation),
+        permutation_challenges: &[],
+        public_values: WindowMatrixView::new(&no_rows),
+        is_first_row: packed(),
+        is_last_row: packed(),
+        transition_selectors: vec![packed(), packed()],
+        alpha,
+        accumulator: PackedChallenge::<MyConfig>::zero(),
+    };
+
+    let mut group = c.benchmark_group("eval keccak constraints");
+    group.bench_function("air", |b| {
+        b.iter(|| {
+            let mut folder = folder();
+            air.eval(&mut folder);
+            folder.accumulator
+        })
+    });
+    let mut registers = ConstraintRegisters::default();
+    group.bench_function("program", |b| {
+        b.iter(|| {
+            let mut folder = folder();
+            program.eval_with_registers(&mut folder, &mut registers);
+            folder.accumulator
+        })
+    });
+    let mut registers = ConstraintRegisters::default();
+    group.bench_function("packed program", |b| {
+        b.iter(|| {
+            let mut folder = folder();
+            packed_program.eval(&mut folder, &alpha_powers, &mut registers);
+            folder.accumulator
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_constraint_program);
+criterion_main!(benches);
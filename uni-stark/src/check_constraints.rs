@@ -1,67 +1,270 @@
-use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, TwoRowMatrixView};
-use p3_field::Field;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use core::ops::ControlFlow;
+
+use p3_air::{
+    Air, AirBuilder, AirBuilderWithPublicValues, ExtensionBuilder, PairBuilder,
+    PermutationAirBuilder, WindowMatrixView,
+};
+use p3_field::{ExtensionField, Field};
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::{Matrix, MatrixRowSlices};
 use tracing::instrument;
 
+use crate::constraint_report::collect_variables;
+use crate::symbolic_builder::{get_symbolic_constraints, SymbolicAirBuilder};
+use crate::symbolic_variable::Entry;
+
+/// Check that `main` satisfies the constraints of `air`, returning every failure found.
+///
+/// This evaluates the constraints directly on each row, so it's much cheaper than proving, and
+/// unlike the check the prover makes in debug builds, it doesn't stop at the first failure. The
+/// preprocessed and permutation traces are generated from `air`, with the given permutation
+/// challenges, which are normally sampled by the prover; any random challenges will do here.
 #[instrument(name = "check constraints", skip_all)]
-pub(crate) fn check_constraints<F, A, P>(air: &A, main: &RowMajorMatrix<F>, public_values: &P)
+pub fn check_constraints<F, EF, A, P>(
+    air: &A,
+    main: &RowMajorMatrix<F>,
+    permutation_challenges: &[EF],
+    public_values: &P,
+) -> Vec<ConstraintFailure<EF>>
 where
     F: Field,
-    A: for<'a> Air<DebugConstraintBuilder<'a, F>>,
+    EF: ExtensionField<F>,
+    A: Air<SymbolicAirBuilder<F>> + for<'a> Air<DebugConstraintBuilder<'a, F, EF>>,
     P: MatrixRowSlices<F>,
 {
-    let height = main.height();
+    let permutation = air.permutation_trace(main, permutation_challenges);
+    find_constraint_failures(
+        air,
+        air.preprocessed_trace().as_ref(),
+        main,
+        permutation.as_ref(),
+        permutation_challenges,
+        public_values,
+    )
+}
 
-    (0..height).for_each(|i| {
-        let i_next = (i + 1) % height;
+pub(crate) fn find_constraint_failures<F, EF, A, P>(
+    air: &A,
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    main: &RowMajorMatrix<F>,
+    permutation: Option<&RowMajorMatrix<EF>>,
+    permutation_challenges: &[EF],
+    public_values: &P,
+) -> Vec<ConstraintFailure<EF>>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    A: Air<SymbolicAirBuilder<F>> + for<'a> Air<DebugConstraintBuilder<'a, F, EF>>,
+    P: MatrixRowSlices<F>,
+{
+    let mut failures = vec![];
+    for_each_constraint_failure(
+        air,
+        preprocessed,
+        main,
+        permutation,
+        permutation_challenges,
+        public_values,
+        |failure| {
+            failures.push(failure);
+            ControlFlow::Continue(())
+        },
+    );
+    failures
+}
 
-        let main_local = main.row_slice(i);
-        let main_next = main.row_slice(i_next);
-        let main = TwoRowMatrixView {
-            local: main_local,
-            next: main_next,
-        };
+/// Like `find_constraint_failures`, but stops at the first failure, without evaluating the rows
+/// after it.
+#[cfg(debug_assertions)]
+pub(crate) fn find_first_constraint_failure<F, EF, A, P>(
+    air: &A,
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    main: &RowMajorMatrix<F>,
+    permutation: Option<&RowMajorMatrix<EF>>,
+    permutation_challenges: &[EF],
+    public_values: &P,
+) -> Option<ConstraintFailure<EF>>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    A: Air<SymbolicAirBuilder<F>> + for<'a> Air<DebugConstraintBuilder<'a, F, EF>>,
+    P: MatrixRowSlices<F>,
+{
+    let mut first = None;
+    for_each_constraint_failure(
+        air,
+        preprocessed,
+        main,
+        permutation,
+        permutation_challenges,
+        public_values,
+        |failure| {
+            first = Some(failure);
+            ControlFlow::Break(())
+        },
+    );
+    first
+}
 
-        let public_local = public_values.row_slice(i);
-        let public_next = public_values.row_slice(i_next);
-        let public_values = TwoRowMatrixView {
-            local: public_local,
-            next: public_next,
-        };
+/// Evaluate the constraints row by row, passing each failure to `f` in order, until it breaks.
+fn for_each_constraint_failure<F, EF, A, P>(
+    air: &A,
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    main: &RowMajorMatrix<F>,
+    permutation: Option<&RowMajorMatrix<EF>>,
+    permutation_challenges: &[EF],
+    public_values: &P,
+    mut f: impl FnMut(ConstraintFailure<EF>) -> ControlFlow<()>,
+) where
+    F: Field,
+    EF: ExtensionField<F>,
+    A: Air<SymbolicAirBuilder<F>> + for<'a> Air<DebugConstraintBuilder<'a, F, EF>>,
+    P: MatrixRowSlices<F>,
+{
+    let height = main.height();
+    let window_size = air.window_size();
+
+    // The variables each constraint reads, to report their values if it fails.
+    let constraint_variables = get_symbolic_constraints::<F, A>(air, public_values.width())
+        .iter()
+        .map(collect_variables)
+        .collect::<Vec<_>>();
+
+    for i in 0..height {
+        let preprocessed = preprocessed.map_or(vec![], |prep| window(prep, i, window_size));
+        let main_window = window(main, i, window_size);
+        let permutation = permutation.map_or(vec![], |perm| window(perm, i, window_size));
+        let public_window = window(public_values, i, window_size);
 
         let mut builder = DebugConstraintBuilder {
-            row_index: i,
-            main,
-            public_values,
+            preprocessed: WindowMatrixView::new(&preprocessed),
+            main: WindowMatrixView::new(&main_window),
+            permutation: WindowMatrixView::new(&permutation),
+            permutation_challenges,
+            public_values: WindowMatrixView::new(&public_window),
             is_first_row: F::from_bool(i == 0),
             is_last_row: F::from_bool(i == height - 1),
-            is_transition: F::from_bool(i != height - 1),
+            transition_selectors: (1..=window_size)
+                .map(|size| F::from_bool(i + size <= height))
+                .collect(),
+            num_constraints: 0,
+            failed_constraints: vec![],
         };
-
         air.eval(&mut builder);
-    });
+
+        for constraint in builder.failed_constraints {
+            let values = constraint_variables[constraint]
+                .iter()
+                .map(|&(entry, index)| {
+                    let value = match entry {
+                        Entry::Preprocessed { offset } => {
+                            EF::from_base(preprocessed[offset][index])
+                        }
+                        Entry::Main { offset } => EF::from_base(main_window[offset][index]),
+                        Entry::Permutation { offset } => permutation[offset][index],
+                        Entry::Public { offset } => EF::from_base(public_window[offset][index]),
+                        Entry::Challenge => permutation_challenges[index],
+                    };
+                    VariableValue {
+                        entry,
+                        index,
+                        value,
+                    }
+                })
+                .collect();
+            let failure = ConstraintFailure {
+                row: i,
+                constraint,
+                values,
+            };
+            if f(failure).is_break() {
+                return;
+            }
+        }
+    }
+}
+
+/// A constraint which doesn't hold on some row of the trace.
+#[derive(Clone, Debug)]
+pub struct ConstraintFailure<EF> {
+    /// The first row of the window on which the constraint failed.
+    pub row: usize,
+    /// The constraint's position among those the AIR asserts, as in `get_symbolic_constraints`.
+    pub constraint: usize,
+    /// The values of the variables the constraint reads, in that window.
+    pub values: Vec<VariableValue<EF>>,
+}
+
+/// The value of a variable, i.e. of a column at some offset within the window, or of a permutation
+/// challenge. Base field values are embedded in the extension field.
+#[derive(Clone, Debug)]
+pub struct VariableValue<EF> {
+    pub entry: Entry,
+    pub index: usize,
+    pub value: EF,
+}
+
+impl<EF: Display> Display for ConstraintFailure<EF> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {}, constraint {}", self.row, self.constraint)?;
+        for (i, v) in self.values.iter().enumerate() {
+            f.write_str(if i == 0 { ": " } else { ", " })?;
+            match v.entry {
+                Entry::Preprocessed { offset } => write!(f, "preprocessed[{offset}]")?,
+                Entry::Main { offset } => write!(f, "main[{offset}]")?,
+                Entry::Permutation { offset } => write!(f, "permutation[{offset}]")?,
+                Entry::Public { offset } => write!(f, "public_values[{offset}]")?,
+                Entry::Challenge => f.write_str("permutation_challenges")?,
+            }
+            write!(f, "[{}] = {}", v.index, v.value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Copy the window of rows starting at row `i`, wrapping around to the first rows if needed.
+fn window<T: Clone>(mat: &impl MatrixRowSlices<T>, i: usize, window_size: usize) -> Vec<Vec<T>> {
+    (0..window_size)
+        .map(|offset| mat.row_slice((i + offset) % mat.height()).to_vec())
+        .collect()
 }
 
-/// An `AirBuilder` which asserts that each constraint is zero, allowing any failed constraints to
-/// be detected early.
-pub struct DebugConstraintBuilder<'a, F: Field> {
-    row_index: usize,
-    main: TwoRowMatrixView<'a, F>,
-    public_values: TwoRowMatrixView<'a, F>,
+/// An `AirBuilder` which evaluates the constraints on a single window of the trace, recording which
+/// of them are nonzero.
+pub struct DebugConstraintBuilder<'a, F: Field, EF: ExtensionField<F>> {
+    preprocessed: WindowMatrixView<'a, F>,
+    main: WindowMatrixView<'a, F>,
+    permutation: WindowMatrixView<'a, EF>,
+    permutation_challenges: &'a [EF],
+    public_values: WindowMatrixView<'a, F>,
     is_first_row: F,
     is_last_row: F,
-    is_transition: F,
+    transition_selectors: Vec<F>,
+    num_constraints: usize,
+    failed_constraints: Vec<usize>,
 }
 
-impl<'a, F> AirBuilder for DebugConstraintBuilder<'a, F>
+impl<F: Field, EF: ExtensionField<F>> DebugConstraintBuilder<'_, F, EF> {
+    fn record(&mut self, is_zero: bool) {
+        if !is_zero {
+            self.failed_constraints.push(self.num_constraints);
+        }
+        self.num_constraints += 1;
+    }
+}
+
+impl<'a, F, EF> AirBuilder for DebugConstraintBuilder<'a, F, EF>
 where
     F: Field,
+    EF: ExtensionField<F>,
 {
     type F = F;
     type Expr = F;
     type Var = F;
-    type M = TwoRowMatrixView<'a, F>;
+    type M = WindowMatrixView<'a, F>;
 
     fn is_first_row(&self) -> Self::Expr {
         self.is_first_row
@@ -72,11 +275,7 @@ where
     }
 
     fn is_transition_window(&self, size: usize) -> Self::Expr {
-        if size == 2 {
-            self.is_transition
-        } else {
-            panic!("only supports a window size of 2")
-        }
+        self.transition_selectors[size - 1]
     }
 
     fn main(&self) -> Self::M {
@@ -84,27 +283,48 @@ where
     }
 
     fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
-        assert_eq!(
-            x.into(),
-            F::zero(),
-            "constraints had nonzero value on row {}",
-            self.row_index
-        );
-    }
-
-    fn assert_eq<I1: Into<Self::Expr>, I2: Into<Self::Expr>>(&mut self, x: I1, y: I2) {
-        let x = x.into();
-        let y = y.into();
-        assert_eq!(
-            x, y,
-            "values didn't match on row {}: {} != {}",
-            self.row_index, x, y
-        );
+        self.record(x.into().is_zero());
     }
 }
 
-impl<F: Field> AirBuilderWithPublicValues for DebugConstraintBuilder<'_, F> {
+impl<F: Field, EF: ExtensionField<F>> AirBuilderWithPublicValues
+    for DebugConstraintBuilder<'_, F, EF>
+{
     fn public_values(&self) -> Self::M {
         self.public_values
     }
 }
+
+impl<F: Field, EF: ExtensionField<F>> PairBuilder for DebugConstraintBuilder<'_, F, EF> {
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed
+    }
+}
+
+impl<F: Field, EF: ExtensionField<F>> ExtensionBuilder for DebugConstraintBuilder<'_, F, EF> {
+    type EF = EF;
+    type ExprEF = EF;
+    type VarEF = EF;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        self.record(x.into().is_zero());
+    }
+}
+
+impl<'a, F: Field, EF: ExtensionField<F>> PermutationAirBuilder
+    for DebugConstraintBuilder<'a, F, EF>
+{
+    type MP = WindowMatrixView<'a, EF>;
+    type RandomVar = EF;
+
+    fn permutation(&self) -> Self::MP {
+        self.permutation
+    }
+
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        self.permutation_challenges
+    }
+}
@@ -1,8 +1,8 @@
 use core::marker::PhantomData;
 
 use p3_challenger::{CanObserve, FieldChallenger};
-use p3_commit::{Pcs, UnivariatePcsWithLde};
-use p3_field::{ExtensionField, Field, TwoAdicField};
+use p3_commit::{BlindingRng, Pcs, StarkPcs};
+use p3_field::{ExtensionField, Field};
 use p3_matrix::dense::RowMajorMatrix;
 
 pub type PackedVal<SC> = <<SC as StarkGenericConfig>::Val as Field>::Packing;
@@ -12,28 +12,34 @@ pub type PackedChallenge<SC> = <<SC as StarkGenericConfig>::Challenge as Extensi
 
 pub trait StarkGenericConfig {
     /// The field over which trace data is encoded.
-    type Val: TwoAdicField;
+    type Val: Field;
 
     /// The field from which most random challenges are drawn.
-    type Challenge: ExtensionField<Self::Val> + TwoAdicField;
+    type Challenge: ExtensionField<Self::Val>;
 
-    /// The PCS used to commit to trace polynomials.
-    type Pcs: UnivariatePcsWithLde<
-        Self::Val,
-        Self::Challenge,
-        RowMajorMatrix<Self::Val>,
-        Self::Challenger,
-    >;
+    /// The PCS used to commit to trace polynomials, which also determines the trace and quotient
+    /// domains.
+    type Pcs: StarkPcs<Self::Val, Self::Challenge, Self::Challenger>;
 
     /// The challenger (Fiat-Shamir) implementation used.
     type Challenger: FieldChallenger<Self::Val>
         + CanObserve<<Self::Pcs as Pcs<Self::Val, RowMajorMatrix<Self::Val>>>::Commitment>;
 
     fn pcs(&self) -> &Self::Pcs;
+
+    /// The randomness used to blind traces if proofs should be zero-knowledge, or `None` otherwise.
+    fn blinding_rng(&self) -> Option<&BlindingRng> {
+        None
+    }
+
+    fn is_zk(&self) -> bool {
+        self.blinding_rng().is_some()
+    }
 }
 
 pub struct StarkConfig<Val, Challenge, Pcs, Challenger> {
     pcs: Pcs,
+    blinding_rng: Option<BlindingRng>,
     _phantom: PhantomData<(Val, Challenge, Challenger)>,
 }
 
@@ -41,6 +47,27 @@ impl<Val, Challenge, Pcs, Challenger> StarkConfig<Val, Challenge, Pcs, Challenge
     pub fn new(pcs: Pcs) -> Self {
         Self {
             pcs,
+            blinding_rng: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// A config for zero-knowledge proofs. Each committed trace is blinded by interleaving its rows
+    /// with random ones, doubling its height, and the quotient degree grows accordingly.
+    ///
+    /// Proofs only hide the witness if `pcs` is hiding too, e.g. `TwoAdicFriPcs::new_hiding` with
+    /// hiding MMCSs, and the PCS must support blinding traces; `CirclePcs` doesn't. A trace of
+    /// height `n` is blinded with a random polynomial of degree less than `n`, so a proof must
+    /// reveal it at fewer than `n` points outside the trace domain: the out-of-domain points of the
+    /// AIR's window, plus one point of the LDE for each query. The prover asserts that
+    /// `window_size + pcs.num_queries() < n`.
+    /// Prover and verifier must both use a zero-knowledge config. A hiding MMCS salts the commitment
+    /// to any preprocessed trace, so the verifier can't recompute it; use `setup_keys` to produce a
+    /// verifying key for `verify_with_key` instead.
+    pub fn new_zk(pcs: Pcs, blinding_rng: BlindingRng) -> Self {
+        Self {
+            pcs,
+            blinding_rng: Some(blinding_rng),
             _phantom: PhantomData,
         }
     }
@@ -49,9 +76,9 @@ impl<Val, Challenge, Pcs, Challenger> StarkConfig<Val, Challenge, Pcs, Challenge
 impl<Val, Challenge, Pcs, Challenger> StarkGenericConfig
     for StarkConfig<Val, Challenge, Pcs, Challenger>
 where
-    Val: TwoAdicField,
-    Challenge: ExtensionField<Val> + TwoAdicField,
-    Pcs: UnivariatePcsWithLde<Val, Challenge, RowMajorMatrix<Val>, Challenger>,
+    Val: Field,
+    Challenge: ExtensionField<Val>,
+    Pcs: StarkPcs<Val, Challenge, Challenger>,
     Challenger: FieldChallenger<Val>
         + CanObserve<<Pcs as p3_commit::Pcs<Val, RowMajorMatrix<Val>>>::Commitment>,
 {
@@ -63,4 +90,8 @@ where
     fn pcs(&self) -> &Self::Pcs {
         &self.pcs
     }
+
+    fn blinding_rng(&self) -> Option<&BlindingRng> {
+        self.blinding_rng.as_ref()
+    }
 }
@@ -0,0 +1,988 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::iter;
+
+use itertools::Itertools;
+use p3_air::{
+    Air, AirBuilderWithPublicValues, PairBuilder, PermutationAirBuilder, WindowMatrixView,
+};
+use p3_field::{AbstractExtensionField, AbstractField, ExtensionField, Field, PrimeField64};
+use p3_matrix::MatrixRowSlices;
+use tracing::instrument;
+
+use crate::symbolic_builder::{get_symbolic_constraints, SymbolicAirBuilder};
+use crate::symbolic_expression::SymbolicExpression;
+use crate::symbolic_variable::Entry;
+use crate::{
+    PackedChallenge, PackedVal, ProverConstraintFolder, StarkGenericConfig,
+    VerifierConstraintFolder,
+};
+
+/// Compile the constraints of `air` into a `ConstraintProgram`.
+#[instrument(name = "compile constraints", skip_all, level = "debug")]
+pub fn get_constraint_program<F, A>(air: &A, public_width: usize) -> ConstraintProgram<F>
+where
+    F: Field,
+    A: Air<SymbolicAirBuilder<F>>,
+{
+    ConstraintProgram::new(&get_symbolic_constraints(air, public_width))
+}
+
+/// Like `get_constraint_program`, but checking with `check_constraint_program` that the program
+/// matches the AIR over `SC`'s challenge field.
+pub fn get_checked_constraint_program<SC, A>(
+    air: &A,
+    public_width: usize,
+) -> Result<ConstraintProgram<SC::Val>, ConstraintProgramMismatch>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<SC::Val>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+{
+    let program = get_constraint_program(air, public_width);
+    check_constraint_program::<SC, A>(air, &program, public_width)?;
+    Ok(program)
+}
+
+/// The error returned when a `ConstraintProgram` doesn't evaluate the same constraints as the AIR
+/// it was compiled from.
+#[derive(Debug)]
+pub struct ConstraintProgramMismatch;
+
+/// Check that `program` evaluates the same constraints as `air` over the challenge field, as the
+/// verifier evaluates them, by comparing their combinations at a pseudorandom point.
+///
+/// Programs are compiled with `SymbolicAirBuilder`, whose extension field is the base field, so
+/// this catches AIRs whose constraints differ over the actual extension field, e.g. because they
+/// use extension field constants.
+pub fn check_constraint_program<SC, A>(
+    air: &A,
+    program: &ConstraintProgram<SC::Val>,
+    public_width: usize,
+) -> Result<(), ConstraintProgramMismatch>
+where
+    SC: StarkGenericConfig,
+    A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+{
+    // The point only needs to avoid honest mistakes, not adversarial AIRs, so a fixed
+    // pseudorandom sequence (SplitMix64) is enough.
+    let mut state = 0x5eed_u64;
+    let mut random = || -> SC::Challenge {
+        SC::Challenge::from_base_fn(|_| {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            SC::Val::from_wrapped_u64(z ^ (z >> 31))
+        })
+    };
+    let mut window = |width: usize| -> Vec<Vec<SC::Challenge>> {
+        (0..air.window_size())
+            .map(|_| (0..width).map(|_| random()).collect())
+            .collect()
+    };
+    let preprocessed = window(air.preprocessed_width());
+    let main = window(air.width());
+    let permutation = window(air.permutation_width());
+    let public_values = window(public_width);
+    let permutation_challenges: Vec<_> = (0..air.num_permutation_challenges())
+        .map(|_| random())
+        .collect();
+    let transition_selectors: Vec<_> = (0..air.window_size()).map(|_| random()).collect();
+    let (is_first_row, is_last_row, alpha) = (random(), random(), random());
+
+    let folder = || VerifierConstraintFolder::<SC> {
+        preprocessed: WindowMatrixView::new(&preprocessed),
+        main: WindowMatrixView::new(&main),
+        permutation: WindowMatrixView::new(&permutation),
+        permutation_challenges: &permutation_challenges,
+        public_values: WindowMatrixView::new(&public_values),
+        is_first_row,
+        is_last_row,
+        transition_selectors: transition_selectors.clone(),
+        alpha,
+        accumulator: SC::Challenge::zero(),
+    };
+    let mut air_folder = folder();
+    air.eval(&mut air_folder);
+    let mut program_folder = folder();
+    program.eval(&mut program_folder);
+
+    if air_folder.accumulator == program_folder.accumulator {
+        Ok(())
+    } else {
+        Err(ConstraintProgramMismatch)
+    }
+}
+
+/// A register of a `ConstraintProgram`. Values involving permutation columns or challenges live in
+/// extension field registers; all others live in base field registers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Register {
+    Base(usize),
+    Ext(usize),
+}
+
+/// An instruction of a `ConstraintProgram`.
+///
+/// Each instruction writes its result to the next unused register: an extension field register if
+/// it reads a permutation column or challenge, or if any operand is an extension field register,
+/// and a base field register otherwise.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Instruction {
+    Variable {
+        entry: Entry,
+        index: usize,
+    },
+    IsFirstRow,
+    IsLastRow,
+    IsTransitionWindow(usize),
+    /// The constant at the given index of `ConstraintProgram::constants`.
+    Constant(usize),
+    Add(Register, Register),
+    Sub(Register, Register),
+    Neg(Register),
+    Mul(Register, Register),
+}
+
+impl Instruction {
+    /// Whether this instruction's result is written to an extension field register.
+    pub fn is_ext(&self) -> bool {
+        let is_ext = |r: &Register| matches!(r, Register::Ext(_));
+        match self {
+            Self::Variable { entry, .. } => {
+                matches!(entry, Entry::Permutation { .. } | Entry::Challenge)
+            }
+            Self::IsFirstRow | Self::IsLastRow | Self::IsTransitionWindow(_) => false,
+            Self::Constant(_) => false,
+            Self::Add(x, y) | Self::Sub(x, y) | Self::Mul(x, y) => is_ext(x) || is_ext(y),
+            Self::Neg(x) => is_ext(x),
+        }
+    }
+}
+
+/// A flat, straight-line program evaluating an AIR's constraints, compiled from their symbolic
+/// form.
+///
+/// Equal subexpressions are computed once, even if the AIR built them separately. It can be run by
+/// an interpreter with `eval`, or exported as Rust source with `to_rust`. The prover interprets it
+/// in its quotient loop, as a `PackedConstraintProgram`, if it's given one with
+/// `ProvingKey::with_constraint_program`.
+///
+/// Programs are compiled with `SymbolicAirBuilder`, whose extension field is the base field, so
+/// compile them with `get_checked_constraint_program` unless the AIR is known not to use extension
+/// field constants.
+#[derive(Clone, Debug)]
+pub struct ConstraintProgram<F> {
+    instructions: Vec<Instruction>,
+    constants: Vec<F>,
+    constraints: Vec<Register>,
+    num_base_registers: usize,
+    num_ext_registers: usize,
+}
+
+impl<F: Field> ConstraintProgram<F> {
+    pub fn new(constraints: &[SymbolicExpression<F>]) -> Self {
+        let mut compiler = Compiler {
+            program: Self {
+                instructions: Vec::new(),
+                constants: Vec::new(),
+                constraints: Vec::new(),
+                num_base_registers: 0,
+                num_ext_registers: 0,
+            },
+            registers: BTreeMap::new(),
+            nodes: BTreeMap::new(),
+        };
+        for constraint in constraints {
+            let register = compiler.compile(constraint);
+            compiler.program.constraints.push(register);
+        }
+        compiler.program
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    pub fn constants(&self) -> &[F] {
+        &self.constants
+    }
+
+    /// The register holding each constraint, in the order the AIR asserted them.
+    pub fn constraints(&self) -> &[Register] {
+        &self.constraints
+    }
+
+    pub fn num_base_registers(&self) -> usize {
+        self.num_base_registers
+    }
+
+    pub fn num_ext_registers(&self) -> usize {
+        self.num_ext_registers
+    }
+
+    /// Run the program on `builder`, asserting each constraint in turn, as `Air::eval` would.
+    pub fn eval<AB>(&self, builder: &mut AB)
+    where
+        AB: AirBuilderWithPublicValues<F = F> + PairBuilder + PermutationAirBuilder,
+    {
+        self.eval_with_registers(builder, &mut ConstraintRegisters::default());
+    }
+
+    /// Like `eval`, but storing the registers in `registers`, so that evaluating the program on
+    /// many rows can reuse the same buffers.
+    pub fn eval_with_registers<AB>(
+        &self,
+        builder: &mut AB,
+        registers: &mut ConstraintRegisters<AB::Expr, AB::ExprEF>,
+    ) where
+        AB: AirBuilderWithPublicValues<F = F> + PairBuilder + PermutationAirBuilder,
+    {
+        let ConstraintRegisters { base, ext } = registers;
+        base.clear();
+        ext.clear();
+        base.reserve(self.num_base_registers);
+        ext.reserve(self.num_ext_registers);
+        {
+            let preprocessed = builder.preprocessed();
+            let main = builder.main();
+            let permutation = builder.permutation();
+            let public_values = builder.public_values();
+            let challenges = builder.permutation_randomness();
+
+            for instruction in &self.instructions {
+                match *instruction {
+                    Instruction::Variable { entry, index } => match entry {
+                        Entry::Preprocessed { offset } => {
+                            base.push(preprocessed.row_slice(offset)[index].into());
+                        }
+                        Entry::Main { offset } => base.push(main.row_slice(offset)[index].into()),
+                        Entry::Public { offset } => {
+                            base.push(public_values.row_slice(offset)[index].into());
+                        }
+                        Entry::Permutation { offset } => {
+                            ext.push(permutation.row_slice(offset)[index].into());
+                        }
+                        Entry::Challenge => ext.push(challenges[index].into()),
+                    },
+                    Instruction::IsFirstRow => base.push(builder.is_first_row()),
+                    Instruction::IsLastRow => base.push(builder.is_last_row()),
+                    Instruction::IsTransitionWindow(size) => {
+                        base.push(builder.is_transition_window(size));
+                    }
+                    Instruction::Constant(i) => base.push(AB::Expr::from(self.constants[i])),
+                    Instruction::Add(x, y) => match (x, y) {
+                        (Register::Base(x), Register::Base(y)) => {
+                            base.push(base[x].clone() + base[y].clone());
+                        }
+                        (Register::Base(b), Register::Ext(e))
+                        | (Register::Ext(e), Register::Base(b)) => {
+                            ext.push(ext[e].clone() + base[b].clone());
+                        }
+                        (Register::Ext(x), Register::Ext(y)) => {
+                            ext.push(ext[x].clone() + ext[y].clone());
+                        }
+                    },
+                    Instruction::Sub(x, y) => match (x, y) {
+                        (Register::Base(x), Register::Base(y)) => {
+                            base.push(base[x].clone() - base[y].clone());
+                        }
+                        (Register::Base(x), Register::Ext(y)) => {
+                            ext.push(AB::ExprEF::from(base[x].clone()) - ext[y].clone());
+                        }
+                        (Register::Ext(x), Register::Base(y)) => {
+                            ext.push(ext[x].clone() - base[y].clone());
+                        }
+                        (Register::Ext(x), Register::Ext(y)) => {
+                            ext.push(ext[x].clone() - ext[y].clone());
+                        }
+                    },
+                    Instruction::Neg(x) => match x {
+                        Register::Base(x) => base.push(-base[x].clone()),
+                        Register::Ext(x) => ext.push(-ext[x].clone()),
+                    },
+                    Instruction::Mul(x, y) => match (x, y) {
+                        (Register::Base(x), Register::Base(y)) => {
+                            base.push(base[x].clone() * base[y].clone());
+                        }
+                        (Register::Base(b), Register::Ext(e))
+                        | (Register::Ext(e), Register::Base(b)) => {
+                            ext.push(ext[e].clone() * base[b].clone());
+                        }
+                        (Register::Ext(x), Register::Ext(y)) => {
+                            ext.push(ext[x].clone() * ext[y].clone());
+                        }
+                    },
+                }
+            }
+        }
+
+        for &constraint in &self.constraints {
+            match constraint {
+                Register::Base(i) => builder.assert_zero(base[i].clone()),
+                Register::Ext(i) => builder.assert_zero_ext(ext[i].clone()),
+            }
+        }
+    }
+}
+
+/// Buffers for the base and extension field registers of a `ConstraintProgram`, which keep their
+/// capacity between evaluations.
+#[derive(Clone, Debug)]
+pub struct ConstraintRegisters<E, EF> {
+    base: Vec<E>,
+    ext: Vec<EF>,
+}
+
+impl<E, EF> Default for ConstraintRegisters<E, EF> {
+    fn default() -> Self {
+        Self {
+            base: Vec::new(),
+            ext: Vec::new(),
+        }
+    }
+}
+
+/// A `ConstraintProgram` laid out for the prover's quotient loop, which evaluates it on packed
+/// rows with `eval`.
+///
+/// Base field instructions never read extension field registers, so they all run before the
+/// extension field ones. They're scheduled in runs of the same kind, so that the interpreter
+/// dispatches once per run rather than once per instruction, and reuse a register once the value
+/// it holds is dead, so that the registers of a wide AIR stay in cache. Each constraint is added to
+/// the accumulator, weighted by its power of alpha, as soon as it's computed.
+#[derive(Clone, Debug)]
+pub struct PackedConstraintProgram<F> {
+    /// The `Variable` and selector instructions writing base field registers, which write to the
+    /// first registers, in order.
+    base_inputs: Vec<Instruction>,
+    /// The constants, which are written to the registers following the inputs.
+    constants: Vec<F>,
+    /// The kind and length of each run of base field instructions.
+    base_runs: Vec<(BaseOpKind, usize)>,
+    /// The destination and operand registers of each base field instruction of each kind, in the
+    /// order they run. Registers are numbered with `u16`s, as the instructions of a wide AIR don't
+    /// fit in cache, so their size bounds the interpreter's speed.
+    adds: Vec<[u16; 3]>,
+    subs: Vec<[u16; 3]>,
+    negs: Vec<[u16; 2]>,
+    muls: Vec<[u16; 3]>,
+    /// The register holding each base field constraint, in the order they're added to the
+    /// accumulator.
+    asserts: Vec<u16>,
+    num_base_registers: usize,
+    /// The index in the AIR's order of each base field constraint in `asserts`.
+    base_constraints: Vec<usize>,
+    /// The `Variable` instructions writing extension field registers.
+    ext_inputs: Vec<Instruction>,
+    /// The arithmetic instructions writing the following extension field registers, with their
+    /// operands renumbered.
+    ext_ops: Vec<Instruction>,
+    /// The register holding each extension field constraint, with its index in the AIR's order.
+    ext_constraints: Vec<(usize, usize)>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum BaseOpKind {
+    Add,
+    Sub,
+    Neg,
+    Mul,
+    AssertZero,
+}
+
+/// A base field instruction, as `PackedConstraintProgram::new` schedules it. It reads `x` and `y`
+/// (`Neg` has `y == x`) and writes `dst`, except for `AssertZero`, whose `dst` is the index of the
+/// constraint.
+#[derive(Copy, Clone, Debug)]
+struct BaseOp {
+    kind: BaseOpKind,
+    dst: usize,
+    x: usize,
+    y: usize,
+}
+
+impl BaseOp {
+    fn reads(&self) -> impl Iterator<Item = usize> {
+        iter::once(self.x).chain((self.y != self.x).then_some(self.y))
+    }
+
+    fn writes(&self) -> Option<usize> {
+        (self.kind != BaseOpKind::AssertZero).then_some(self.dst)
+    }
+}
+
+impl<F: Field> PackedConstraintProgram<F> {
+    pub fn new(program: &ConstraintProgram<F>) -> Self {
+        let is_input = |instruction: &Instruction| {
+            matches!(
+                instruction,
+                Instruction::Variable { .. }
+                    | Instruction::IsFirstRow
+                    | Instruction::IsLastRow
+                    | Instruction::IsTransitionWindow(_)
+            )
+        };
+        let (ext_inputs, base_inputs): (Vec<_>, Vec<_>) = program
+            .instructions
+            .iter()
+            .copied()
+            .filter(is_input)
+            .partition(Instruction::is_ext);
+
+        // Number the base field values with the inputs first, then the constants, then the
+        // results of arithmetic instructions, and the extension field ones with the inputs first.
+        let first_base_op = base_inputs.len() + program.constants.len();
+        let mut base_values = Vec::with_capacity(program.num_base_registers);
+        let mut ext_registers = Vec::with_capacity(program.num_ext_registers);
+        let mut ops = Vec::new();
+        let mut ext_ops = Vec::new();
+        let (mut num_base_inputs, mut num_ext_inputs) = (0, 0);
+        for instruction in &program.instructions {
+            if instruction.is_ext() {
+                let register = if is_input(instruction) {
+                    num_ext_inputs += 1;
+                    num_ext_inputs - 1
+                } else {
+                    // Base field operands are renumbered again once registers are allocated.
+                    let renumber = |r: Register| match r {
+                        Register::Base(i) => Register::Base(base_values[i]),
+                        Register::Ext(i) => Register::Ext(ext_registers[i]),
+                    };
+                    ext_ops.push(match *instruction {
+                        Instruction::Add(x, y) => Instruction::Add(renumber(x), renumber(y)),
+                        Instruction::Sub(x, y) => Instruction::Sub(renumber(x), renumber(y)),
+                        Instruction::Neg(x) => Instruction::Neg(renumber(x)),
+                        Instruction::Mul(x, y) => Instruction::Mul(renumber(x), renumber(y)),
+                        _ => unreachable!("only arithmetic instructions read registers"),
+                    });
+                    ext_inputs.len() + ext_ops.len() - 1
+                };
+                ext_registers.push(register);
+            } else {
+                let value = |r: Register| match r {
+                    Register::Base(i) => base_values[i],
+                    Register::Ext(_) => unreachable!("base field values only depend on base ones"),
+                };
+                let (kind, x, y) = match *instruction {
+                    Instruction::Constant(i) => {
+                        base_values.push(base_inputs.len() + i);
+                        continue;
+                    }
+                    Instruction::Add(x, y) => (BaseOpKind::Add, value(x), value(y)),
+                    Instruction::Sub(x, y) => (BaseOpKind::Sub, value(x), value(y)),
+                    Instruction::Neg(x) => (BaseOpKind::Neg, value(x), value(x)),
+                    Instruction::Mul(x, y) => (BaseOpKind::Mul, value(x), value(y)),
+                    _ => {
+                        num_base_inputs += 1;
+                        base_values.push(num_base_inputs - 1);
+                        continue;
+                    }
+                };
+                let dst = first_base_op + ops.len();
+                ops.push(BaseOp { kind, dst, x, y });
+                base_values.push(dst);
+            }
+        }
+        let num_base_values = first_base_op + ops.len();
+
+        let mut ext_constraints = Vec::new();
+        for (k, &constraint) in program.constraints.iter().enumerate() {
+            match constraint {
+                Register::Base(i) => ops.push(BaseOp {
+                    kind: BaseOpKind::AssertZero,
+                    dst: k,
+                    x: base_values[i],
+                    y: base_values[i],
+                }),
+                Register::Ext(i) => ext_constraints.push((ext_registers[i], k)),
+            }
+        }
+
+        let ops = schedule(&ops, first_base_op, num_base_values);
+
+        // The last base field instruction reading each value; the extension field instructions
+        // run after all of them.
+        let mut last_use = vec![None; num_base_values];
+        for (position, op) in ops.iter().enumerate() {
+            for value in op.reads() {
+                last_use[value] = Some(position);
+            }
+        }
+        for op in &ext_ops {
+            if let Instruction::Add(x, y) | Instruction::Sub(x, y) | Instruction::Mul(x, y) = op {
+                for r in [x, y] {
+                    if let Register::Base(value) = r {
+                        last_use[*value] = Some(usize::MAX);
+                    }
+                }
+            }
+        }
+
+        // Allocate registers, reusing those holding dead values. The inputs and constants are
+        // written to the first registers before any instruction runs.
+        let mut registers: Vec<usize> = (0..first_base_op).collect();
+        registers.resize(num_base_values, 0);
+        let mut free: Vec<usize> = (0..first_base_op)
+            .filter(|&value| last_use[value].is_none())
+            .collect();
+        let mut num_base_registers = first_base_op;
+        let register = |r: usize| {
+            u16::try_from(r).expect("a PackedConstraintProgram has at most 2^16 base registers")
+        };
+        let mut packed = Self {
+            base_inputs,
+            constants: program.constants.clone(),
+            base_runs: Vec::new(),
+            adds: Vec::new(),
+            subs: Vec::new(),
+            negs: Vec::new(),
+            muls: Vec::new(),
+            asserts: Vec::new(),
+            num_base_registers: 0,
+            base_constraints: Vec::new(),
+            ext_inputs,
+            ext_ops: Vec::new(),
+            ext_constraints,
+        };
+        for (position, op) in ops.iter().enumerate() {
+            let (x, y) = (register(registers[op.x]), register(registers[op.y]));
+            for value in op.reads() {
+                if last_use[value] == Some(position) {
+                    free.push(registers[value]);
+                }
+            }
+            let dst = op.writes().map(|value| {
+                let r = free.pop().unwrap_or_else(|| {
+                    num_base_registers += 1;
+                    num_base_registers - 1
+                });
+                registers[value] = r;
+                if last_use[value].is_none() {
+                    free.push(r);
+                }
+                register(r)
+            });
+
+            match packed.base_runs.last_mut() {
+                Some((kind, len)) if *kind == op.kind => *len += 1,
+                _ => packed.base_runs.push((op.kind, 1)),
+            }
+            match op.kind {
+                BaseOpKind::Add => packed.adds.push([dst.unwrap(), x, y]),
+                BaseOpKind::Sub => packed.subs.push([dst.unwrap(), x, y]),
+                BaseOpKind::Neg => packed.negs.push([dst.unwrap(), x]),
+                BaseOpKind::Mul => packed.muls.push([dst.unwrap(), x, y]),
+                BaseOpKind::AssertZero => {
+                    packed.asserts.push(x);
+                    packed.base_constraints.push(op.dst);
+                }
+            }
+        }
+        packed.num_base_registers = num_base_registers;
+
+        for op in &mut ext_ops {
+            if let Instruction::Add(x, y) | Instruction::Sub(x, y) | Instruction::Mul(x, y) = op {
+                for r in [x, y] {
+                    if let Register::Base(value) = r {
+                        *value = registers[*value];
+                    }
+                }
+            }
+        }
+        packed.ext_ops = ext_ops;
+        packed
+    }
+
+    /// The weights `eval` combines the constraints with for `alpha`. They only depend on `alpha`,
+    /// so they can be computed once per proof.
+    pub fn alpha_powers<EF>(&self, alpha: EF) -> Vec<EF::ExtensionPacking>
+    where
+        EF: ExtensionField<F>,
+    {
+        // `Air::eval` multiplies the accumulator by alpha before adding each constraint, so the
+        // k-th of n constraints ends up weighted by alpha^(n - 1 - k), and the accumulator it
+        // started with by alpha^n.
+        let n = self.base_constraints.len() + self.ext_constraints.len();
+        let powers = alpha.powers().take(n + 1).collect_vec();
+        let weight = |k: usize| EF::ExtensionPacking::from_f(powers[n - 1 - k]);
+        iter::once(EF::ExtensionPacking::from_f(powers[n]))
+            .chain(self.base_constraints.iter().map(|&k| weight(k)))
+            .chain(self.ext_constraints.iter().map(|&(_, k)| weight(k)))
+            .collect()
+    }
+
+    /// Evaluate the constraints on the folder's packed rows, and combine them into its
+    /// accumulator, as `Air::eval` would. `alpha_powers` must be `alpha_powers(folder.alpha)`.
+    pub fn eval<SC>(
+        &self,
+        folder: &mut ProverConstraintFolder<'_, SC>,
+        alpha_powers: &[PackedChallenge<SC>],
+        registers: &mut ConstraintRegisters<PackedVal<SC>, PackedChallenge<SC>>,
+    ) where
+        SC: StarkGenericConfig<Val = F>,
+    {
+        let ConstraintRegisters { base, ext } = registers;
+        base.resize(self.num_base_registers, PackedVal::<SC>::zero());
+        for (register, instruction) in base.iter_mut().zip(&self.base_inputs) {
+            *register = match *instruction {
+                Instruction::Variable { entry, index } => match entry {
+                    Entry::Preprocessed { offset } => folder.preprocessed.rows[offset][index],
+                    Entry::Main { offset } => folder.main.rows[offset][index],
+                    Entry::Public { offset } => folder.public_values.rows[offset][index],
+                    Entry::Permutation { .. } | Entry::Challenge => {
+                        unreachable!("permutation variables are extension field inputs")
+                    }
+                },
+                Instruction::IsFirstRow => folder.is_first_row,
+                Instruction::IsLastRow => folder.is_last_row,
+                Instruction::IsTransitionWindow(size) => folder.transition_selectors[size - 1],
+                _ => unreachable!("inputs are variables or selectors"),
+            };
+        }
+        for (register, &c) in base[self.base_inputs.len()..]
+            .iter_mut()
+            .zip(&self.constants)
+        {
+            *register = c.into();
+        }
+
+        let mut accumulator = folder.accumulator * alpha_powers[0];
+        let (mut adds, mut subs, mut negs, mut muls) =
+            (&*self.adds, &*self.subs, &*self.negs, &*self.muls);
+        let (mut asserts, mut weights) = (&*self.asserts, &alpha_powers[1..]);
+        for &(kind, len) in &self.base_runs {
+            match kind {
+                BaseOpKind::Add => {
+                    let (run, rest) = adds.split_at(len);
+                    for &[dst, x, y] in run {
+                        base[dst as usize] = base[x as usize] + base[y as usize];
+                    }
+                    adds = rest;
+                }
+                BaseOpKind::Sub => {
+                    let (run, rest) = subs.split_at(len);
+                    for &[dst, x, y] in run {
+                        base[dst as usize] = base[x as usize] - base[y as usize];
+                    }
+                    subs = rest;
+                }
+                BaseOpKind::Neg => {
+                    let (run, rest) = negs.split_at(len);
+                    for &[dst, x] in run {
+                        base[dst as usize] = -base[x as usize];
+                    }
+                    negs = rest;
+                }
+                BaseOpKind::Mul => {
+                    let (run, rest) = muls.split_at(len);
+                    for &[dst, x, y] in run {
+                        base[dst as usize] = base[x as usize] * base[y as usize];
+                    }
+                    muls = rest;
+                }
+                BaseOpKind::AssertZero => {
+                    let (run, rest) = asserts.split_at(len);
+                    let (run_weights, rest_weights) = weights.split_at(len);
+                    for (&x, &weight) in run.iter().zip(run_weights) {
+                        accumulator += weight * base[x as usize];
+                    }
+                    (asserts, weights) = (rest, rest_weights);
+                }
+            }
+        }
+
+        ext.clear();
+        ext.reserve(self.ext_inputs.len() + self.ext_ops.len());
+        ext.extend(
+            self.ext_inputs
+                .iter()
+                .map(|instruction| match *instruction {
+                    Instruction::Variable {
+                        entry: Entry::Permutation { offset },
+                        index,
+                    } => folder.permutation.rows[offset][index],
+                    Instruction::Variable {
+                        entry: Entry::Challenge,
+                        index,
+                    } => folder.permutation_challenges[index],
+                    _ => unreachable!("extension field inputs are permutation variables"),
+                }),
+        );
+        for op in &self.ext_ops {
+            let value = match *op {
+                Instruction::Add(x, y) => match (x, y) {
+                    (Register::Base(b), Register::Ext(e))
+                    | (Register::Ext(e), Register::Base(b)) => ext[e] + base[b],
+                    (Register::Ext(x), Register::Ext(y)) => ext[x] + ext[y],
+                    (Register::Base(_), Register::Base(_)) => unreachable!(),
+                },
+                Instruction::Sub(x, y) => match (x, y) {
+                    (Register::Base(x), Register::Ext(y)) => {
+                        PackedChallenge::<SC>::from(base[x]) - ext[y]
+                    }
+                    (Register::Ext(x), Register::Base(y)) => ext[x] - base[y],
+                    (Register::Ext(x), Register::Ext(y)) => ext[x] - ext[y],
+                    (Register::Base(_), Register::Base(_)) => unreachable!(),
+                },
+                Instruction::Neg(x) => match x {
+                    Register::Ext(x) => -ext[x],
+                    Register::Base(_) => unreachable!(),
+                },
+                Instruction::Mul(x, y) => match (x, y) {
+                    (Register::Base(b), Register::Ext(e))
+                    | (Register::Ext(e), Register::Base(b)) => ext[e] * base[b],
+                    (Register::Ext(x), Register::Ext(y)) => ext[x] * ext[y],
+                    (Register::Base(_), Register::Base(_)) => unreachable!(),
+                },
+                _ => unreachable!("only arithmetic instructions read registers"),
+            };
+            ext.push(value);
+        }
+
+        let ext_weights = &alpha_powers[1 + self.base_constraints.len()..];
+        for (&(register, _), &weight) in self.ext_constraints.iter().zip(ext_weights) {
+            accumulator += weight * ext[register];
+        }
+        folder.accumulator = accumulator;
+    }
+}
+
+/// Order `ops`, which compute the values from `first_op_value` to `num_values`, so that each runs
+/// after those computing its operands, and those of the same kind run together where possible.
+fn schedule(ops: &[BaseOp], first_op_value: usize, num_values: usize) -> Vec<BaseOp> {
+    // The instructions reading each value, and the number of each instruction's operands which
+    // aren't computed yet.
+    let mut readers = vec![Vec::new(); num_values];
+    let mut pending = vec![0; ops.len()];
+    let mut ready: [Vec<usize>; 5] = Default::default();
+    for (i, op) in ops.iter().enumerate() {
+        for value in op.reads().filter(|&value| value >= first_op_value) {
+            readers[value].push(i);
+            pending[i] += 1;
+        }
+        if pending[i] == 0 {
+            ready[op.kind as usize].push(i);
+        }
+    }
+
+    // Keep running instructions of one kind while any are ready, then switch to the kind with the
+    // most ready instructions.
+    let mut scheduled = Vec::with_capacity(ops.len());
+    let mut kind = 0;
+    while scheduled.len() < ops.len() {
+        if ready[kind].is_empty() {
+            kind = (0..ready.len()).max_by_key(|&k| ready[k].len()).unwrap();
+        }
+        let op = ops[ready[kind].pop().unwrap()];
+        scheduled.push(op);
+        if let Some(value) = op.writes() {
+            for &reader in &readers[value] {
+                pending[reader] -= 1;
+                if pending[reader] == 0 {
+                    ready[ops[reader].kind as usize].push(reader);
+                }
+            }
+        }
+    }
+    scheduled
+}
+
+impl<F: PrimeField64> ConstraintProgram<F> {
+    /// Export the program as the source of a Rust function named `fn_name`, which asserts the
+    /// constraints on any builder, like `eval`, but without interpreting instructions.
+    ///
+    /// The function has the signature
+    /// ```ignore
+    /// pub fn fn_name<AB>(builder: &mut AB)
+    /// where
+    ///     AB: AirBuilderWithPublicValues + PairBuilder + PermutationAirBuilder;
+    /// ```
+    /// and needs those three traits to be in scope. It imports any others it uses itself.
+    pub fn to_rust(&self, fn_name: &str) -> String {
+        let reg = |r: Register| match r {
+            Register::Base(i) => format!("b{i}"),
+            Register::Ext(i) => format!("e{i}"),
+        };
+        let uses = |f: fn(&Entry) -> bool| {
+            self.instructions.iter().any(
+                |instruction| matches!(instruction, Instruction::Variable { entry, .. } if f(entry)),
+            )
+        };
+
+        let mut out = String::new();
+        writeln!(out, "pub fn {fn_name}<AB>(builder: &mut AB)").unwrap();
+        out.push_str("where\n");
+        out.push_str("    AB: AirBuilderWithPublicValues + PairBuilder + PermutationAirBuilder,\n");
+        out.push_str("{\n");
+        let imports = [
+            (!self.constants.is_empty()).then_some("p3_field::AbstractField"),
+            uses(|e| !matches!(e, Entry::Challenge)).then_some("p3_matrix::MatrixRowSlices"),
+        ];
+        for import in imports.iter().flatten() {
+            writeln!(out, "    use {import};").unwrap();
+        }
+        if imports.iter().any(Option::is_some) {
+            out.push('\n');
+        }
+        if uses(|e| matches!(e, Entry::Preprocessed { .. })) {
+            out.push_str("    let preprocessed = builder.preprocessed();\n");
+        }
+        if uses(|e| matches!(e, Entry::Main { .. })) {
+            out.push_str("    let main = builder.main();\n");
+        }
+        if uses(|e| matches!(e, Entry::Permutation { .. })) {
+            out.push_str("    let permutation = builder.permutation();\n");
+        }
+        if uses(|e| matches!(e, Entry::Public { .. })) {
+            out.push_str("    let public_values = builder.public_values();\n");
+        }
+        if uses(|e| matches!(e, Entry::Challenge)) {
+            out.push_str("    let challenges = builder.permutation_randomness().to_vec();\n");
+        }
+
+        let (mut num_base, mut num_ext) = (0, 0);
+        for instruction in &self.instructions {
+            let (dst, ty) = if instruction.is_ext() {
+                num_ext += 1;
+                (format!("e{}", num_ext - 1), "AB::ExprEF")
+            } else {
+                num_base += 1;
+                (format!("b{}", num_base - 1), "AB::Expr")
+            };
+            let value = match *instruction {
+                Instruction::Variable { entry, index } => match entry {
+                    Entry::Preprocessed { offset } => {
+                        format!("preprocessed.row_slice({offset})[{index}].into()")
+                    }
+                    Entry::Main { offset } => format!("main.row_slice({offset})[{index}].into()"),
+                    Entry::Permutation { offset } => {
+                        format!("permutation.row_slice({offset})[{index}].into()")
+                    }
+                    Entry::Public { offset } => {
+                        format!("public_values.row_slice({offset})[{index}].into()")
+                    }
+                    Entry::Challenge => format!("challenges[{index}].into()"),
+                },
+                Instruction::IsFirstRow => "builder.is_first_row()".into(),
+                Instruction::IsLastRow => "builder.is_last_row()".into(),
+                Instruction::IsTransitionWindow(size) => {
+                    format!("builder.is_transition_window({size})")
+                }
+                Instruction::Constant(i) => format!(
+                    "AB::Expr::from_canonical_u64({})",
+                    self.constants[i].as_canonical_u64()
+                ),
+                Instruction::Add(x, y) | Instruction::Mul(x, y) => {
+                    // Extension field values only support operations with base field values on
+                    // the right.
+                    let (x, y) = match (x, y) {
+                        (Register::Base(_), Register::Ext(_)) => (y, x),
+                        _ => (x, y),
+                    };
+                    let op = if matches!(instruction, Instruction::Add(..)) {
+                        '+'
+                    } else {
+                        '*'
+                    };
+                    format!("{}.clone() {op} {}.clone()", reg(x), reg(y))
+                }
+                Instruction::Sub(x, y) => match (x, y) {
+                    (Register::Base(_), Register::Ext(_)) => {
+                        format!("AB::ExprEF::from({}.clone()) - {}.clone()", reg(x), reg(y))
+                    }
+                    _ => format!("{}.clone() - {}.clone()", reg(x), reg(y)),
+                },
+                Instruction::Neg(x) => format!("-{}.clone()", reg(x)),
+            };
+            writeln!(out, "    let {dst}: {ty} = {value};").unwrap();
+        }
+
+        for &constraint in &self.constraints {
+            let assert = match constraint {
+                Register::Base(_) => "assert_zero",
+                Register::Ext(_) => "assert_zero_ext",
+            };
+            writeln!(out, "    builder.{assert}({}.clone());", reg(constraint)).unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+struct Compiler<F> {
+    program: ConstraintProgram<F>,
+    /// The register holding the result of each instruction, so that it's only computed once.
+    registers: BTreeMap<Instruction, Register>,
+    /// The register holding each shared node, by its address, so that it's only compiled once.
+    nodes: BTreeMap<usize, Register>,
+}
+
+impl<F: Field> Compiler<F> {
+    fn push(&mut self, instruction: Instruction) -> Register {
+        if let Some(&register) = self.registers.get(&instruction) {
+            return register;
+        }
+        let program = &mut self.program;
+        program.instructions.push(instruction);
+        let register = if instruction.is_ext() {
+            program.num_ext_registers += 1;
+            Register::Ext(program.num_ext_registers - 1)
+        } else {
+            program.num_base_registers += 1;
+            Register::Base(program.num_base_registers - 1)
+        };
+        self.registers.insert(instruction, register);
+        register
+    }
+
+    fn compile_shared(&mut self, expr: &Rc<SymbolicExpression<F>>) -> Register {
+        let address = Rc::as_ptr(expr) as usize;
+        if let Some(&register) = self.nodes.get(&address) {
+            return register;
+        }
+        let register = self.compile(expr);
+        self.nodes.insert(address, register);
+        register
+    }
+
+    fn compile(&mut self, expr: &SymbolicExpression<F>) -> Register {
+        // Operands of commutative operations are sorted, so that `x + y` and `y + x` are shared.
+        let sorted = |x: Register, y: Register| (x.min(y), x.max(y));
+        let instruction = match expr {
+            SymbolicExpression::Variable(v) => Instruction::Variable {
+                entry: v.entry,
+                index: v.index,
+            },
+            SymbolicExpression::IsFirstRow => Instruction::IsFirstRow,
+            SymbolicExpression::IsLastRow => Instruction::IsLastRow,
+            SymbolicExpression::IsTransitionWindow(size) => Instruction::IsTransitionWindow(*size),
+            SymbolicExpression::Constant(c) => {
+                let constants = &mut self.program.constants;
+                let i = constants.iter().position(|x| x == c).unwrap_or_else(|| {
+                    constants.push(*c);
+                    constants.len() - 1
+                });
+                Instruction::Constant(i)
+            }
+            SymbolicExpression::Add { x, y, .. } => {
+                let (x, y) = sorted(self.compile_shared(x), self.compile_shared(y));
+                Instruction::Add(x, y)
+            }
+            SymbolicExpression::Sub { x, y, .. } => {
+                Instruction::Sub(self.compile_shared(x), self.compile_shared(y))
+            }
+            SymbolicExpression::Neg { x, .. } => Instruction::Neg(self.compile_shared(x)),
+            SymbolicExpression::Mul { x, y, .. } => {
+                let (x, y) = sorted(self.compile_shared(x), self.compile_shared(y));
+                Instruction::Mul(x, y)
+            }
+        };
+        self.push(instruction)
+    }
+}
@@ -0,0 +1,179 @@
+use alloc::collections::BTreeSet;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::fmt;
+
+use p3_air::Air;
+use p3_field::Field;
+
+use crate::symbolic_builder::{
+    get_symbolic_constraints, log_quotient_degree_for, SymbolicAirBuilder,
+};
+use crate::symbolic_expression::SymbolicExpression;
+use crate::symbolic_variable::Entry;
+
+/// Describe each constraint of `air`, and flag those which make proving more expensive than needed.
+///
+/// This is a diagnostic for AIR authors; the prover only needs `get_log_quotient_degree`.
+pub fn get_constraint_report<F, A>(air: &A, public_width: usize, is_zk: bool) -> ConstraintReport
+where
+    F: Field,
+    A: Air<SymbolicAirBuilder<F>>,
+{
+    let constraints = get_symbolic_constraints::<F, A>(air, public_width)
+        .iter()
+        .enumerate()
+        .map(|(index, constraint)| {
+            let columns = collect_variables(constraint)
+                .into_iter()
+                .filter_map(|(entry, index)| match entry {
+                    Entry::Preprocessed { .. } => Some(Column::Preprocessed(index)),
+                    Entry::Main { .. } => Some(Column::Main(index)),
+                    Entry::Permutation { .. } => Some(Column::Permutation(index)),
+                    Entry::Public { .. } => Some(Column::Public(index)),
+                    Entry::Challenge => None,
+                })
+                .collect();
+            ConstraintInfo {
+                index,
+                degree: constraint.degree_multiple(),
+                columns,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let max_degree = constraints.iter().map(|c| c.degree).max().unwrap_or(0);
+    let log_quotient_degree = log_quotient_degree_for(max_degree, is_zk);
+    // The constraints whose degree is too large for a quotient of half the size, if there is one.
+    let costly_constraints = if log_quotient_degree > log_quotient_degree_for(0, is_zk) {
+        constraints
+            .iter()
+            .filter(|c| log_quotient_degree_for(c.degree, is_zk) == log_quotient_degree)
+            .map(|c| c.index)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let used = constraints
+        .iter()
+        .flat_map(|c| c.columns.iter().copied())
+        .collect::<BTreeSet<_>>();
+    let all_columns = (0..air.preprocessed_width())
+        .map(Column::Preprocessed)
+        .chain((0..air.width()).map(Column::Main))
+        .chain((0..air.permutation_width()).map(Column::Permutation))
+        .chain((0..public_width).map(Column::Public));
+    let unused_columns = all_columns.filter(|c| !used.contains(c)).collect();
+
+    ConstraintReport {
+        constraints,
+        max_degree,
+        log_quotient_degree,
+        costly_constraints,
+        unused_columns,
+    }
+}
+
+/// The variables that `constraint` reads, by entry and index.
+pub(crate) fn collect_variables<F: Field>(
+    constraint: &SymbolicExpression<F>,
+) -> BTreeSet<(Entry, usize)> {
+    fn collect<F: Field>(
+        expr: &SymbolicExpression<F>,
+        variables: &mut BTreeSet<(Entry, usize)>,
+        visited: &mut BTreeSet<usize>,
+    ) {
+        // Shared nodes are only visited once, as an expression may be exponentially larger as a
+        // tree.
+        let mut visit = |x: &Rc<SymbolicExpression<F>>, variables: &mut BTreeSet<_>| {
+            if visited.insert(Rc::as_ptr(x) as usize) {
+                collect(x, variables, visited);
+            }
+        };
+        match expr {
+            SymbolicExpression::Variable(v) => {
+                variables.insert((v.entry, v.index));
+            }
+            SymbolicExpression::IsFirstRow
+            | SymbolicExpression::IsLastRow
+            | SymbolicExpression::IsTransitionWindow(_)
+            | SymbolicExpression::Constant(_) => {}
+            SymbolicExpression::Add { x, y, .. }
+            | SymbolicExpression::Sub { x, y, .. }
+            | SymbolicExpression::Mul { x, y, .. } => {
+                visit(x, variables);
+                visit(y, variables);
+            }
+            SymbolicExpression::Neg { x, .. } => visit(x, variables),
+        }
+    }
+
+    let mut variables = BTreeSet::new();
+    collect(constraint, &mut variables, &mut BTreeSet::new());
+    variables
+}
+
+/// A column of one of the traces, or of the public values.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Column {
+    Preprocessed(usize),
+    Main(usize),
+    Permutation(usize),
+    Public(usize),
+}
+
+/// A constraint's degree, and the columns it reads at any row offset.
+#[derive(Clone, Debug)]
+pub struct ConstraintInfo {
+    /// The constraint's position among those the AIR asserts, as in `get_symbolic_constraints`.
+    pub index: usize,
+    /// The constraint's degree, as a multiple of the trace length.
+    pub degree: usize,
+    pub columns: BTreeSet<Column>,
+}
+
+/// The output of `get_constraint_report`.
+#[derive(Clone, Debug)]
+pub struct ConstraintReport {
+    pub constraints: Vec<ConstraintInfo>,
+    pub max_degree: usize,
+    /// The `log_quotient_degree` that the prover will use for the AIR.
+    pub log_quotient_degree: usize,
+    /// The indices of the constraints that force `log_quotient_degree` to its value. If all of
+    /// them had a lower degree, the quotient would be half the size. Empty if the quotient is
+    /// already as small as possible.
+    pub costly_constraints: Vec<usize>,
+    /// The columns which no constraint reads.
+    pub unused_columns: Vec<Column>,
+}
+
+impl fmt::Display for ConstraintReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} constraints, max degree {}, log_quotient_degree {}",
+            self.constraints.len(),
+            self.max_degree,
+            self.log_quotient_degree
+        )?;
+        for c in &self.constraints {
+            write!(f, "  constraint {}: degree {}, columns", c.index, c.degree)?;
+            for column in &c.columns {
+                write!(f, " {column:?}")?;
+            }
+            if self.costly_constraints.contains(&c.index) {
+                write!(
+                    f,
+                    " (forces log_quotient_degree {})",
+                    self.log_quotient_degree
+                )?;
+            }
+            writeln!(f)?;
+        }
+        if !self.unused_columns.is_empty() {
+            writeln!(f, "unused columns: {:?}", self.unused_columns)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+//! A stable binary encoding of `Proof`s.
+//!
+//! An encoded proof starts with a fixed header:
+//!
+//! | bytes | contents                                           |
+//! |-------|----------------------------------------------------|
+//! | 4     | the magic bytes `P3US`                             |
+//! | 2     | the format version, as a little-endian `u16`       |
+//! | 8     | the config fingerprint, as a little-endian `u64`   |
+//! | 4     | `degree_bits`, as a little-endian `u32`            |
+//!
+//! followed by the commitments, the opened values and the PCS opening proof, in that order, each as
+//! a section prefixed by its length in bytes as a little-endian `u32`. Sections are written in a
+//! compact serde format with fixed-width little-endian integers, `u32` length prefixes and no field
+//! names, and field elements are written in canonical form.
+//!
+//! Decoding is strict: it rejects unknown versions, proofs for a different config, non-canonical
+//! field elements, malformed or truncated sections and trailing bytes, so each proof has exactly one
+//! encoding.
+
+use alloc::vec::Vec;
+
+use p3_commit::{Pcs, StarkPcs};
+use p3_field::{AbstractExtensionField, AbstractField};
+use p3_util::codec::{from_bytes, to_bytes, CodecError};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Proof, StarkGenericConfig};
+
+/// The magic bytes at the start of every encoded proof.
+pub const PROOF_MAGIC: [u8; 4] = *b"P3US";
+
+/// The version of the proof format written by `encode_proof`.
+///
+/// Version 2 changed the PCS opening proof: Merkle commitments are caps rather than roots and are
+/// opened with multi-proofs, and FRI may fold by higher arities and end with a non-constant final
+/// polynomial.
+pub const PROOF_FORMAT_VERSION: u16 = 2;
+
+const HEADER_LEN: usize = 4 + 2 + 8 + 4;
+
+/// The bytes of an encoded proof outside its sections: the header, and each section's length.
+pub(crate) const ENCODING_FRAMING_LEN: usize = HEADER_LEN + 3 * 4;
+
+#[derive(Debug)]
+pub enum ProofDecodingError {
+    /// The input doesn't start with `PROOF_MAGIC`, or is too short to hold a header.
+    InvalidMagic,
+    /// The proof was written in a format version this crate can't read.
+    UnsupportedVersion(u16),
+    /// The proof was produced under a config with a different fingerprint.
+    ConfigMismatch { expected: u64, found: u64 },
+    /// The trace is too large for the config's PCS.
+    InvalidDegreeBits(u32),
+    /// A section is truncated or malformed, or holds a non-canonical value.
+    InvalidSection {
+        section: &'static str,
+        error: CodecError,
+    },
+    /// The input has bytes left over after the last section.
+    TrailingBytes,
+}
+
+/// A fingerprint of the parts of `config` which affect the shape and meaning of a proof: the base
+/// and extension fields, the PCS's blowup, domain parameters such as a coset shift, maximum trace
+/// height, number of queries and other shape parameters, such as FRI's folding arity and final
+/// polynomial length and the MMCSs' cap heights, and whether proofs are zero-knowledge.
+///
+/// The fingerprint guards against decoding a proof under the wrong config by mistake. It doesn't
+/// cover everything a verifier depends on, such as the hash functions.
+pub fn config_fingerprint<SC: StarkGenericConfig>(config: &SC) -> u64 {
+    let pcs = config.pcs();
+    let mut hasher = Fnv1a::default();
+    // The canonical encoding of -1 identifies the field's order.
+    hasher.write(&encode_section(&-SC::Val::one()));
+    hasher.write(&encode_section(&SC::Val::generator()));
+    hasher.write_u64(pcs.max_log_height() as u64);
+    hasher.write_u64(<SC::Challenge as AbstractExtensionField<SC::Val>>::D as u64);
+    hasher.write(&encode_section(&SC::Challenge::generator()));
+    hasher.write_u64(pcs.log_blowup() as u64);
+    hasher.write(&encode_section(&pcs.domain_params()));
+    hasher.write_u64(pcs.num_queries() as u64);
+    let shape_params = pcs.shape_params();
+    hasher.write_u64(shape_params.len() as u64);
+    for param in shape_params {
+        hasher.write_u64(param as u64);
+    }
+    hasher.write_u64(config.is_zk() as u64);
+    hasher.finish()
+}
+
+/// Encode `proof` in the current format. See the module documentation for the layout.
+pub fn encode_proof<SC: StarkGenericConfig>(config: &SC, proof: &Proof<SC>) -> Vec<u8> {
+    let degree_bits = u32::try_from(proof.degree_bits).expect("degree_bits too large");
+
+    let mut out = Vec::new();
+    out.extend(PROOF_MAGIC);
+    out.extend(PROOF_FORMAT_VERSION.to_le_bytes());
+    out.extend(config_fingerprint(config).to_le_bytes());
+    out.extend(degree_bits.to_le_bytes());
+    for section in [
+        encode_section(&proof.commitments),
+        encode_section(&proof.opened_values),
+        encode_section(&proof.opening_proof),
+    ] {
+        let len = u32::try_from(section.len()).expect("proof section too large");
+        out.extend(len.to_le_bytes());
+        out.extend(section);
+    }
+    out
+}
+
+/// Decode a proof written by `encode_proof` under a config with the same fingerprint as `config`.
+pub fn decode_proof<SC: StarkGenericConfig>(
+    config: &SC,
+    bytes: &[u8],
+) -> Result<Proof<SC>, ProofDecodingError> {
+    if bytes.len() < HEADER_LEN || bytes[..4] != PROOF_MAGIC {
+        return Err(ProofDecodingError::InvalidMagic);
+    }
+    let (header, mut rest) = bytes.split_at(HEADER_LEN);
+
+    let version = u16::from_le_bytes(header[4..6].try_into().unwrap());
+    if version != PROOF_FORMAT_VERSION {
+        return Err(ProofDecodingError::UnsupportedVersion(version));
+    }
+
+    let expected = config_fingerprint(config);
+    let found = u64::from_le_bytes(header[6..14].try_into().unwrap());
+    if found != expected {
+        return Err(ProofDecodingError::ConfigMismatch { expected, found });
+    }
+
+    let degree_bits = u32::from_le_bytes(header[14..18].try_into().unwrap());
+    if degree_bits as usize > config.pcs().max_log_height() {
+        return Err(ProofDecodingError::InvalidDegreeBits(degree_bits));
+    }
+
+    let commitments = decode_section(&mut rest, "commitments")?;
+    let opened_values = decode_section(&mut rest, "opened_values")?;
+    let opening_proof = decode_section(&mut rest, "opening_proof")?;
+    if !rest.is_empty() {
+        return Err(ProofDecodingError::TrailingBytes);
+    }
+
+    Ok(Proof {
+        commitments,
+        opened_values,
+        opening_proof,
+        degree_bits: degree_bits as usize,
+    })
+}
+
+fn encode_section<T: Serialize>(value: &T) -> Vec<u8> {
+    to_bytes(value).expect("proof values are always encodable")
+}
+
+/// Decode the length-prefixed section at the start of `input`, and advance `input` past it.
+fn decode_section<T: DeserializeOwned>(
+    input: &mut &[u8],
+    section: &'static str,
+) -> Result<T, ProofDecodingError> {
+    let invalid = |error| ProofDecodingError::InvalidSection { section, error };
+
+    if input.len() < 4 {
+        return Err(invalid(CodecError::UnexpectedEnd));
+    }
+    let (len, rest) = input.split_at(4);
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    if rest.len() < len {
+        return Err(invalid(CodecError::UnexpectedEnd));
+    }
+    let (body, rest) = rest.split_at(len);
+    *input = rest;
+    from_bytes(body).map_err(invalid)
+}
+
+/// The 64-bit FNV-1a hash. It only needs to be stable, not collision resistant.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        // Prefix the length, so that consecutive writes can't run into each other.
+        for &byte in (bytes.len() as u64).to_le_bytes().iter().chain(bytes) {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
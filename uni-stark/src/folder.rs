@@ -1,24 +1,37 @@
-use p3_air::{AirBuilder, AirBuilderWithPublicValues, TwoRowMatrixView};
+use alloc::vec::Vec;
+
+use p3_air::{
+    AirBuilder, AirBuilderWithPublicValues, ExtensionBuilder, PairBuilder, PermutationAirBuilder,
+    WindowMatrixView,
+};
 use p3_field::AbstractField;
 
 use crate::{PackedChallenge, PackedVal, StarkGenericConfig};
 
 pub struct ProverConstraintFolder<'a, SC: StarkGenericConfig> {
-    pub main: TwoRowMatrixView<'a, PackedVal<SC>>,
-    pub public_values: TwoRowMatrixView<'a, PackedVal<SC>>,
+    pub preprocessed: WindowMatrixView<'a, PackedVal<SC>>,
+    pub main: WindowMatrixView<'a, PackedVal<SC>>,
+    pub permutation: WindowMatrixView<'a, PackedChallenge<SC>>,
+    pub permutation_challenges: &'a [PackedChallenge<SC>],
+    pub public_values: WindowMatrixView<'a, PackedVal<SC>>,
     pub is_first_row: PackedVal<SC>,
     pub is_last_row: PackedVal<SC>,
-    pub is_transition: PackedVal<SC>,
+    /// The values of `is_transition_window(size)` for each `size` in `1..=window_size`.
+    pub transition_selectors: Vec<PackedVal<SC>>,
     pub alpha: SC::Challenge,
     pub accumulator: PackedChallenge<SC>,
 }
 
 pub struct VerifierConstraintFolder<'a, SC: StarkGenericConfig> {
-    pub main: TwoRowMatrixView<'a, SC::Challenge>,
-    pub public_values: TwoRowMatrixView<'a, SC::Challenge>,
+    pub preprocessed: WindowMatrixView<'a, SC::Challenge>,
+    pub main: WindowMatrixView<'a, SC::Challenge>,
+    pub permutation: WindowMatrixView<'a, SC::Challenge>,
+    pub permutation_challenges: &'a [SC::Challenge],
+    pub public_values: WindowMatrixView<'a, SC::Challenge>,
     pub is_first_row: SC::Challenge,
     pub is_last_row: SC::Challenge,
-    pub is_transition: SC::Challenge,
+    /// The values of `is_transition_window(size)` for each `size` in `1..=window_size`.
+    pub transition_selectors: Vec<SC::Challenge>,
     pub alpha: SC::Challenge,
     pub accumulator: SC::Challenge,
 }
@@ -27,7 +40,7 @@ impl<'a, SC: StarkGenericConfig> AirBuilder for ProverConstraintFolder<'a, SC> {
     type F = SC::Val;
     type Expr = PackedVal<SC>;
     type Var = PackedVal<SC>;
-    type M = TwoRowMatrixView<'a, PackedVal<SC>>;
+    type M = WindowMatrixView<'a, PackedVal<SC>>;
 
     fn main(&self) -> Self::M {
         self.main
@@ -42,11 +55,7 @@ impl<'a, SC: StarkGenericConfig> AirBuilder for ProverConstraintFolder<'a, SC> {
     }
 
     fn is_transition_window(&self, size: usize) -> Self::Expr {
-        if size == 2 {
-            self.is_transition
-        } else {
-            panic!("uni-stark only supports a window size of 2")
-        }
+        self.transition_selectors[size - 1]
     }
 
     fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
@@ -62,11 +71,45 @@ impl<SC: StarkGenericConfig> AirBuilderWithPublicValues for ProverConstraintFold
     }
 }
 
+impl<SC: StarkGenericConfig> PairBuilder for ProverConstraintFolder<'_, SC> {
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed
+    }
+}
+
+impl<SC: StarkGenericConfig> ExtensionBuilder for ProverConstraintFolder<'_, SC> {
+    type EF = SC::Challenge;
+    type ExprEF = PackedChallenge<SC>;
+    type VarEF = PackedChallenge<SC>;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        let x: PackedChallenge<SC> = x.into();
+        self.accumulator *= PackedChallenge::<SC>::from_f(self.alpha);
+        self.accumulator += x;
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> PermutationAirBuilder for ProverConstraintFolder<'a, SC> {
+    type MP = WindowMatrixView<'a, PackedChallenge<SC>>;
+    type RandomVar = PackedChallenge<SC>;
+
+    fn permutation(&self) -> Self::MP {
+        self.permutation
+    }
+
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        self.permutation_challenges
+    }
+}
+
 impl<'a, SC: StarkGenericConfig> AirBuilder for VerifierConstraintFolder<'a, SC> {
     type F = SC::Val;
     type Expr = SC::Challenge;
     type Var = SC::Challenge;
-    type M = TwoRowMatrixView<'a, SC::Challenge>;
+    type M = WindowMatrixView<'a, SC::Challenge>;
 
     fn main(&self) -> Self::M {
         self.main
@@ -81,11 +124,7 @@ impl<'a, SC: StarkGenericConfig> AirBuilder for VerifierConstraintFolder<'a, SC>
     }
 
     fn is_transition_window(&self, size: usize) -> Self::Expr {
-        if size == 2 {
-            self.is_transition
-        } else {
-            panic!("uni-stark only supports a window size of 2")
-        }
+        self.transition_selectors[size - 1]
     }
 
     fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
@@ -99,3 +138,37 @@ impl<SC: StarkGenericConfig> AirBuilderWithPublicValues for VerifierConstraintFo
         self.public_values
     }
 }
+
+impl<SC: StarkGenericConfig> PairBuilder for VerifierConstraintFolder<'_, SC> {
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed
+    }
+}
+
+impl<SC: StarkGenericConfig> ExtensionBuilder for VerifierConstraintFolder<'_, SC> {
+    type EF = SC::Challenge;
+    type ExprEF = SC::Challenge;
+    type VarEF = SC::Challenge;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        let x: SC::Challenge = x.into();
+        self.accumulator *= self.alpha;
+        self.accumulator += x;
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> PermutationAirBuilder for VerifierConstraintFolder<'a, SC> {
+    type MP = WindowMatrixView<'a, SC::Challenge>;
+    type RandomVar = SC::Challenge;
+
+    fn permutation(&self) -> Self::MP {
+        self.permutation
+    }
+
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        self.permutation_challenges
+    }
+}
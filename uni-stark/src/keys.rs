@@ -0,0 +1,118 @@
+use p3_air::BaseAir;
+use p3_commit::{Pcs, StarkPcs};
+use p3_matrix::Matrix;
+use p3_util::log2_strict_usize;
+use serde::{Deserialize, Serialize};
+use tracing::{info_span, instrument};
+
+use crate::proof::{Com, PcsProverData};
+use crate::{ConstraintProgram, PackedConstraintProgram, StarkGenericConfig};
+
+/// Prover-side data for an AIR, computed once by `setup_keys` and reused across proofs.
+pub struct ProvingKey<SC: StarkGenericConfig> {
+    pub(crate) preprocessed: Option<PreprocessedProverData<SC>>,
+    /// If set, the prover interprets this program in its quotient loop, instead of evaluating the
+    /// AIR.
+    pub(crate) constraint_program: Option<PackedConstraintProgram<SC::Val>>,
+}
+
+pub(crate) struct PreprocessedProverData<SC: StarkGenericConfig> {
+    pub(crate) width: usize,
+    pub(crate) degree_bits: usize,
+    pub(crate) commitment: Com<SC>,
+    pub(crate) data: PcsProverData<SC>,
+}
+
+/// Verifier-side data for an AIR, i.e. the commitment to its preprocessed trace, if any.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct VerifyingKey<SC: StarkGenericConfig> {
+    pub(crate) preprocessed: Option<PreprocessedVerifierData<Com<SC>>>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct PreprocessedVerifierData<Com> {
+    pub(crate) width: usize,
+    pub(crate) degree_bits: usize,
+    pub(crate) commitment: Com,
+}
+
+impl<SC: StarkGenericConfig> ProvingKey<SC> {
+    /// Have the prover evaluate the constraints in its quotient loop by interpreting `program`,
+    /// rather than with `Air::eval`. This is faster for wide AIRs, such as Keccak's (see the
+    /// `constraint_program` bench).
+    ///
+    /// The program should be compiled from the same AIR, for the width of the public values the
+    /// proofs will use, by `get_checked_constraint_program`, or by `get_constraint_program` if the
+    /// AIR doesn't use extension field constants.
+    pub fn with_constraint_program(mut self, program: &ConstraintProgram<SC::Val>) -> Self {
+        self.constraint_program = Some(PackedConstraintProgram::new(program));
+        self
+    }
+
+    /// The key the verifier needs to check proofs produced with this proving key.
+    pub fn verifying_key(&self) -> VerifyingKey<SC> {
+        VerifyingKey {
+            preprocessed: self
+                .preprocessed
+                .as_ref()
+                .map(|prep| PreprocessedVerifierData {
+                    width: prep.width,
+                    degree_bits: prep.degree_bits,
+                    commitment: prep.commitment.clone(),
+                }),
+        }
+    }
+}
+
+impl<SC: StarkGenericConfig> Clone for VerifyingKey<SC> {
+    fn clone(&self) -> Self {
+        Self {
+            preprocessed: self.preprocessed.clone(),
+        }
+    }
+}
+
+/// Commit to the AIR's preprocessed trace, if it has one, producing a proving key and the
+/// matching verifying key.
+///
+/// The preprocessed trace only depends on the AIR, so this only needs to be done once; the keys can
+/// then be passed to `prove_with_key` and `verify_with_key` for any number of proofs. The height of
+/// the preprocessed trace fixes the height of every main trace proven with these keys.
+#[instrument(skip_all)]
+pub fn setup_keys<SC, A>(config: &SC, air: &A) -> (ProvingKey<SC>, VerifyingKey<SC>)
+where
+    SC: StarkGenericConfig,
+    A: BaseAir<SC::Val>,
+{
+    let preprocessed = air.preprocessed_trace().map(|trace| {
+        assert_eq!(
+            trace.width(),
+            air.preprocessed_width(),
+            "preprocessed trace width should match BaseAir::preprocessed_width"
+        );
+        let width = trace.width();
+        let degree_bits = log2_strict_usize(trace.height());
+        // The preprocessed trace is known to the verifier, so it is extended rather than blinded.
+        let trace = if config.is_zk() {
+            config.pcs().extend_trace(trace)
+        } else {
+            trace
+        };
+        let (commitment, data) = info_span!("commit to preprocessed trace")
+            .in_scope(|| config.pcs().commit_batch(trace));
+        PreprocessedProverData {
+            width,
+            degree_bits,
+            commitment,
+            data,
+        }
+    });
+
+    let proving_key = ProvingKey {
+        preprocessed,
+        constraint_program: None,
+    };
+    let verifying_key = proving_key.verifying_key();
+    (proving_key, verifying_key)
+}
@@ -4,9 +4,13 @@
 
 extern crate alloc;
 
+mod check_constraints;
 mod config;
-mod decompose;
+mod constraint_program;
+mod constraint_report;
+mod encoding;
 mod folder;
+mod keys;
 mod proof;
 mod prover;
 mod public;
@@ -14,16 +18,20 @@ mod symbolic_builder;
 mod symbolic_expression;
 mod symbolic_variable;
 mod verifier;
-mod zerofier_coset;
-
-#[cfg(debug_assertions)]
-mod check_constraints;
+mod zk;
 
+pub use check_constraints::*;
 pub use config::*;
-pub use decompose::*;
+pub use constraint_program::*;
+pub use constraint_report::*;
+pub use encoding::*;
 pub use folder::*;
+pub use keys::*;
+pub use p3_util::codec::CodecError;
 pub use proof::*;
 pub use prover::*;
 pub use public::*;
+pub use symbolic_builder::*;
+pub use symbolic_expression::*;
+pub use symbolic_variable::*;
 pub use verifier::*;
-pub use zerofier_coset::*;
@@ -1,15 +1,18 @@
 use alloc::vec::Vec;
 
-use p3_commit::Pcs;
+use p3_commit::{Pcs, ProofSize};
 use p3_matrix::dense::RowMajorMatrix;
+use p3_util::codec::encoded_len;
 use serde::{Deserialize, Serialize};
 
-use crate::StarkGenericConfig;
+use crate::{StarkGenericConfig, ENCODING_FRAMING_LEN};
 
 type Val<SC> = <SC as StarkGenericConfig>::Val;
 type ValMat<SC> = RowMajorMatrix<Val<SC>>;
-type Com<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<Val<SC>, ValMat<SC>>>::Commitment;
-type PcsProof<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<Val<SC>, ValMat<SC>>>::Proof;
+pub type Com<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<Val<SC>, ValMat<SC>>>::Commitment;
+pub(crate) type PcsProverData<SC> =
+    <<SC as StarkGenericConfig>::Pcs as Pcs<Val<SC>, ValMat<SC>>>::ProverData;
+pub type PcsProof<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<Val<SC>, ValMat<SC>>>::Proof;
 
 #[derive(Serialize, Deserialize)]
 #[serde(bound = "")]
@@ -20,15 +23,84 @@ pub struct Proof<SC: StarkGenericConfig> {
     pub(crate) degree_bits: usize,
 }
 
+impl<SC: StarkGenericConfig> Proof<SC> {
+    pub fn commitments(&self) -> &Commitments<Com<SC>> {
+        &self.commitments
+    }
+
+    pub fn opened_values(&self) -> &OpenedValues<SC::Challenge> {
+        &self.opened_values
+    }
+
+    pub fn opening_proof(&self) -> &PcsProof<SC> {
+        &self.opening_proof
+    }
+
+    /// The log of the trace's height, before any zero-knowledge blinding.
+    pub fn degree_bits(&self) -> usize {
+        self.degree_bits
+    }
+
+    /// A breakdown of the size of this proof as written by `encode_proof`. The total is the length
+    /// of the encoding.
+    pub fn size(&self, config: &SC) -> ProofSize {
+        let mut size = config.pcs().proof_size(&self.opening_proof);
+        size.opened_values += encoded_len(&self.opened_values);
+        size.other += encoded_len(&self.commitments) + ENCODING_FRAMING_LEN;
+        size
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Commitments<Com> {
     pub(crate) trace: Com,
+    pub(crate) permutation: Option<Com>,
     pub(crate) quotient_chunks: Com,
 }
 
+impl<Com> Commitments<Com> {
+    pub fn trace(&self) -> &Com {
+        &self.trace
+    }
+
+    /// The commitment to the permutation trace, if the AIR has one.
+    pub fn permutation(&self) -> Option<&Com> {
+        self.permutation.as_ref()
+    }
+
+    pub fn quotient_chunks(&self) -> &Com {
+        &self.quotient_chunks
+    }
+}
+
+/// The claimed openings of each committed matrix. A trace matrix is opened at `zeta * g^i` for each
+/// row `i` of the AIR's window, while matrices which weren't committed have no openings.
 #[derive(Serialize, Deserialize)]
 pub struct OpenedValues<Challenge> {
-    pub(crate) trace_local: Vec<Challenge>,
-    pub(crate) trace_next: Vec<Challenge>,
+    pub(crate) preprocessed: Vec<Vec<Challenge>>,
+    pub(crate) trace: Vec<Vec<Challenge>>,
+    pub(crate) permutation: Vec<Vec<Challenge>>,
     pub(crate) quotient_chunks: Vec<Challenge>,
 }
+
+impl<Challenge> OpenedValues<Challenge> {
+    /// For each row of the AIR's window, the preprocessed trace's opening.
+    pub fn preprocessed(&self) -> &[Vec<Challenge>] {
+        &self.preprocessed
+    }
+
+    /// For each row of the AIR's window, the main trace's opening.
+    pub fn trace(&self) -> &[Vec<Challenge>] {
+        &self.trace
+    }
+
+    /// For each row of the AIR's window, the permutation trace's opening.
+    pub fn permutation(&self) -> &[Vec<Challenge>] {
+        &self.permutation
+    }
+
+    /// The quotient chunks' openings at `zeta`, flattened over base field coordinates.
+    pub fn quotient_chunks(&self) -> &[Challenge] {
+        &self.quotient_chunks
+    }
+}
@@ -2,30 +2,33 @@ use alloc::vec;
 use alloc::vec::Vec;
 
 use itertools::Itertools;
-use p3_air::{Air, TwoRowMatrixView};
+use p3_air::{Air, WindowMatrixView};
 use p3_challenger::{CanObserve, FieldChallenger};
-use p3_commit::{Pcs, UnivariatePcs, UnivariatePcsWithLde};
-use p3_field::{
-    cyclic_subgroup_coset_known_order, AbstractExtensionField, AbstractField, Field, PackedField,
-    TwoAdicField,
-};
+use p3_commit::{LagrangeSelectors, Pcs, StarkPcs, UnivariatePcs};
+use p3_field::{AbstractExtensionField, AbstractField, PackedField};
 use p3_matrix::dense::RowMajorMatrix;
-use p3_matrix::{Matrix, MatrixGet, MatrixRows};
+use p3_matrix::{Matrix, MatrixGet};
 use p3_maybe_rayon::prelude::*;
 use p3_util::log2_strict_usize;
 use tracing::{info_span, instrument};
 
 use crate::symbolic_builder::{get_log_quotient_degree, SymbolicAirBuilder};
+use crate::zk::check_hiding_bound;
 use crate::{
-    decompose_and_flatten, Commitments, OpenedValues, PackedChallenge, PackedVal, Proof,
-    ProverConstraintFolder, PublicValues, StarkGenericConfig, ZerofierOnCoset,
+    setup_keys, Commitments, ConstraintRegisters, OpenedValues, PackedChallenge,
+    PackedConstraintProgram, PackedVal, Proof, ProverConstraintFolder, ProvingKey, PublicValues,
+    StarkGenericConfig,
 };
 
+/// Prove that `trace` satisfies `air`.
+///
+/// Any preprocessed trace is committed on every call; use `setup_keys` and `prove_with_key` to
+/// commit to it once instead.
 #[allow(clippy::multiple_bound_locations)]
 #[instrument(skip_all)]
 pub fn prove<
     SC,
-    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, SC::Val>>,
+    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, SC::Val, SC::Challenge>>,
     #[cfg(not(debug_assertions))] A,
     P,
 >(
@@ -40,83 +43,207 @@ where
     A: Air<SymbolicAirBuilder<SC::Val>> + for<'a> Air<ProverConstraintFolder<'a, SC>>,
     P: PublicValues<SC::Val, SC::Challenge> + Sync,
 {
-    #[cfg(debug_assertions)]
-    crate::check_constraints::check_constraints(air, &trace, public_values);
+    let (proving_key, _) = setup_keys(config, air);
+    prove_with_key(config, &proving_key, air, challenger, trace, public_values)
+}
 
+#[allow(clippy::multiple_bound_locations)]
+#[instrument(skip_all)]
+pub fn prove_with_key<
+    SC,
+    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, SC::Val, SC::Challenge>>,
+    #[cfg(not(debug_assertions))] A,
+    P,
+>(
+    config: &SC,
+    proving_key: &ProvingKey<SC>,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    trace: RowMajorMatrix<SC::Val>,
+    public_values: &P,
+) -> Proof<SC>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<SC::Val>> + for<'a> Air<ProverConstraintFolder<'a, SC>>,
+    P: PublicValues<SC::Val, SC::Challenge> + Sync,
+{
     let degree = trace.height();
     let log_degree = log2_strict_usize(degree);
 
-    let log_quotient_degree = get_log_quotient_degree::<SC::Val, A>(air, public_values.width());
-
-    let g_subgroup = SC::Val::two_adic_generator(log_degree);
+    let is_zk = config.is_zk();
+    if is_zk {
+        check_hiding_bound(degree, air.window_size(), config.pcs().num_queries());
+    }
+    let log_quotient_degree =
+        get_log_quotient_degree::<SC::Val, A>(air, public_values.width(), is_zk);
 
     let pcs = config.pcs();
+    let preprocessed = proving_key.preprocessed.as_ref();
+    if let Some(prep) = preprocessed {
+        assert_eq!(
+            prep.degree_bits, log_degree,
+            "trace height should match the preprocessed trace height"
+        );
+        challenger.observe(prep.commitment.clone());
+    }
+
+    // Without a permutation phase, the constraints only involve the main trace, so in debug builds
+    // we can check them before the trace is committed.
+    #[cfg(debug_assertions)]
+    if air.permutation_width() == 0 {
+        if let Some(failure) = crate::check_constraints::find_first_constraint_failure(
+            air,
+            air.preprocessed_trace().as_ref(),
+            &trace,
+            None,
+            &[],
+            public_values,
+        ) {
+            panic!("constraints had nonzero value on {failure}");
+        }
+    }
+
+    // The permutation trace is built from the main trace after the latter has been committed, so in
+    // that case we hold on to a copy of it.
+    let main_trace = (air.permutation_width() > 0).then(|| trace.clone());
+
+    // In zero-knowledge mode, each committed trace is blinded with random rows first.
+    let blind = |trace| match config.blinding_rng() {
+        Some(blinding_rng) => pcs.blind_trace(trace, blinding_rng),
+        None => trace,
+    };
+
     let (trace_commit, trace_data) =
-        info_span!("commit to trace data").in_scope(|| pcs.commit_batch(trace));
+        info_span!("commit to trace data").in_scope(|| pcs.commit_batch(blind(trace)));
 
     challenger.observe(trace_commit.clone());
     for i in 0..public_values.height() {
         challenger.observe_slice(public_values.row_slice(i));
     }
-    let alpha: SC::Challenge = challenger.sample_ext_element();
 
-    let mut trace_ldes = pcs.get_ldes(&trace_data);
-    assert_eq!(trace_ldes.len(), 1);
-    let trace_lde = trace_ldes.pop().unwrap();
+    let permutation_challenges: Vec<SC::Challenge> = (0..air.num_permutation_challenges())
+        .map(|_| challenger.sample_ext_element())
+        .collect();
+    let permutation_trace = (air.permutation_width() > 0).then(|| {
+        let perm = info_span!("generate permutation trace").in_scope(|| {
+            air.permutation_trace(main_trace.as_ref().unwrap(), &permutation_challenges)
+                .expect("an AIR with permutation columns should generate a permutation trace")
+        });
+        assert_eq!(
+            perm.width(),
+            air.permutation_width(),
+            "permutation trace width should match BaseAir::permutation_width"
+        );
+        assert_eq!(
+            perm.height(),
+            degree,
+            "permutation trace height should match the main trace height"
+        );
+        perm
+    });
 
-    let public_trace_lde = public_values.get_ldes(config);
+    #[cfg(debug_assertions)]
+    if let Some(main_trace) = &main_trace {
+        if let Some(failure) = crate::check_constraints::find_first_constraint_failure(
+            air,
+            air.preprocessed_trace().as_ref(),
+            main_trace,
+            permutation_trace.as_ref(),
+            &permutation_challenges,
+            public_values,
+        ) {
+            panic!("constraints had nonzero value on {failure}");
+        }
+    }
+
+    let (permutation_commit, permutation_data) = permutation_trace
+        .map(|perm| {
+            let (permutation_commit, permutation_data) = info_span!("commit to permutation trace")
+                .in_scope(|| pcs.commit_batch(blind(perm.flatten_to_base())));
+            challenger.observe(permutation_commit.clone());
+            (permutation_commit, permutation_data)
+        })
+        .unzip();
 
-    let log_stride_for_quotient = pcs.log_blowup() - log_quotient_degree;
-    let trace_lde_for_quotient = trace_lde.vertically_strided(1 << log_stride_for_quotient, 0);
+    let alpha: SC::Challenge = challenger.sample_ext_element();
 
-    let public_trace_lde_for_quotient =
-        public_trace_lde.vertically_strided(1 << log_stride_for_quotient, 0);
+    let log_quotient_height = pcs.log_quotient_height(log_degree, log_quotient_degree);
+    let single_lde = |data| {
+        let mut ldes = pcs.quotient_domain_ldes(data, log_quotient_height);
+        assert_eq!(ldes.len(), 1);
+        ldes.pop().unwrap()
+    };
+    let trace_lde_for_quotient = single_lde(&trace_data);
+    let preprocessed_lde_for_quotient = preprocessed.map(|prep| single_lde(&prep.data));
+    let permutation_lde_for_quotient = permutation_data.as_ref().map(single_lde);
+    let public_trace_lde_for_quotient = public_values.get_ldes(config, log_quotient_height);
 
     let quotient_values = quotient_values(
         config,
         air,
+        proving_key.constraint_program.as_ref(),
         &public_trace_lde_for_quotient,
         log_degree,
-        log_quotient_degree,
+        log_quotient_height,
+        preprocessed_lde_for_quotient.as_ref(),
         trace_lde_for_quotient,
+        permutation_lde_for_quotient.as_ref(),
+        &permutation_challenges,
         alpha,
     );
-    let quotient_chunks_flattened = decompose_and_flatten(
-        quotient_values,
-        SC::Challenge::from_base(pcs.coset_shift()),
-        log_quotient_degree,
-    );
     let (quotient_commit, quotient_data) =
         info_span!("commit to quotient poly chunks").in_scope(|| {
-            pcs.commit_shifted_batch(
-                quotient_chunks_flattened,
-                pcs.coset_shift().exp_power_of_2(log_quotient_degree),
+            pcs.commit_quotient(
+                quotient_values,
+                log_degree,
+                log_quotient_degree,
+                config.blinding_rng(),
             )
         });
     challenger.observe(quotient_commit.clone());
 
     let commitments = Commitments {
         trace: trace_commit,
+        permutation: permutation_commit,
         quotient_chunks: quotient_commit,
     };
 
     let zeta: SC::Challenge = challenger.sample_ext_element();
-    let (opened_values, opening_proof) = pcs.open_multi_batches(
-        &[
-            (&trace_data, &[vec![zeta, zeta * g_subgroup]]),
-            (
-                &quotient_data,
-                &[vec![zeta.exp_power_of_2(log_quotient_degree)]],
-            ),
-        ],
-        challenger,
-    );
-    let trace_local = opened_values[0][0][0].clone();
-    let trace_next = opened_values[0][0][1].clone();
+    let window_points = [
+        itertools::iterate(zeta, |&point| pcs.next_point(log_degree, point))
+            .take(air.window_size())
+            .collect_vec(),
+    ];
+    let quotient_points = [vec![pcs.quotient_point(zeta, log_quotient_degree, is_zk)]];
+    let mut rounds = vec![
+        (&trace_data, window_points.as_slice()),
+        (&quotient_data, quotient_points.as_slice()),
+    ];
+    if let Some(prep) = preprocessed {
+        rounds.push((&prep.data, window_points.as_slice()));
+    }
+    if let Some(data) = &permutation_data {
+        rounds.push((data, window_points.as_slice()));
+    }
+    let (opened_values, opening_proof) = pcs.open_multi_batches(&rounds, challenger);
+    let trace = opened_values[0][0].clone();
     let quotient_chunks = opened_values[1][0][0].clone();
+    // The optional rounds follow the trace and quotient rounds, in the order they were pushed.
+    let mut round = 2;
+    let mut open_window = |present: bool| {
+        if present {
+            round += 1;
+            opened_values[round - 1][0].clone()
+        } else {
+            vec![]
+        }
+    };
+    let preprocessed = open_window(preprocessed.is_some());
+    let permutation = open_window(permutation_data.is_some());
     let opened_values = OpenedValues {
-        trace_local,
-        trace_next,
+        preprocessed,
+        trace,
+        permutation,
         quotient_chunks,
     };
     Proof {
@@ -128,126 +255,176 @@ where
 }
 
 #[instrument(name = "compute quotient polynomial", skip_all)]
-fn quotient_values<SC, A, Mat, PubMat>(
+#[allow(clippy::too_many_arguments)]
+fn quotient_values<SC, A, PrepMat, Mat, PermMat, PubMat>(
     config: &SC,
     air: &A,
+    constraint_program: Option<&PackedConstraintProgram<SC::Val>>,
     public_trace_lde: &PubMat,
     degree_bits: usize,
-    quotient_degree_bits: usize,
+    quotient_size_bits: usize,
+    preprocessed_lde: Option<&PrepMat>,
     trace_lde: Mat,
+    permutation_lde: Option<&PermMat>,
+    permutation_challenges: &[SC::Challenge],
     alpha: SC::Challenge,
 ) -> Vec<SC::Challenge>
 where
     SC: StarkGenericConfig,
     A: for<'a> Air<ProverConstraintFolder<'a, SC>>,
+    PrepMat: MatrixGet<SC::Val> + Sync,
     PubMat: MatrixGet<SC::Val> + Sync,
     Mat: MatrixGet<SC::Val> + Sync,
+    PermMat: MatrixGet<SC::Val> + Sync,
 {
-    let degree = 1 << degree_bits;
-    let quotient_size_bits = degree_bits + quotient_degree_bits;
     let quotient_size = 1 << quotient_size_bits;
-    let g_subgroup = SC::Val::two_adic_generator(degree_bits);
-    let g_extended = SC::Val::two_adic_generator(quotient_size_bits);
-    let subgroup_last = g_subgroup.inverse();
-    let coset_shift = config.pcs().coset_shift();
-    let next_step = 1 << quotient_degree_bits;
-
-    let mut coset: Vec<_> =
-        cyclic_subgroup_coset_known_order(g_extended, coset_shift, quotient_size).collect();
+    // The next row of each row of the quotient domain is this many rows after it.
+    let next_step = 1 << (quotient_size_bits - degree_bits);
+    let window_size = air.window_size();
+    let ext_degree = <SC::Challenge as AbstractExtensionField<SC::Val>>::D;
 
-    let zerofier_on_coset = ZerofierOnCoset::new(degree_bits, quotient_degree_bits, coset_shift);
-
-    // Evaluations of L_first(x) = Z_H(x) / (x - 1) on our coset s H.
-    let mut lagrange_first_evals = zerofier_on_coset.lagrange_basis_unnormalized(0);
-    let mut lagrange_last_evals = zerofier_on_coset.lagrange_basis_unnormalized(degree - 1);
+    let LagrangeSelectors {
+        mut is_first_row,
+        mut is_last_row,
+        mut is_transition,
+        mut inv_zerofier,
+    } = config
+        .pcs()
+        .selectors_on_quotient_domain(degree_bits, quotient_size_bits, window_size);
 
     // We have a few vectors of length `quotient_size`, and we're going to take slices therein of
     // length `WIDTH`. In the edge case where `quotient_size < WIDTH`, we need to pad those vectors
     // in order for the slices to exist. The entries beyond quotient_size will be ignored, so we can
     // just use default values.
-    for _ in quotient_size..PackedVal::<SC>::WIDTH {
-        coset.push(SC::Val::default());
-        lagrange_first_evals.push(SC::Val::default());
-        lagrange_last_evals.push(SC::Val::default());
+    for selector in [&mut is_first_row, &mut is_last_row, &mut inv_zerofier]
+        .into_iter()
+        .chain(&mut is_transition)
+    {
+        selector.resize(
+            quotient_size.max(PackedVal::<SC>::WIDTH),
+            SC::Val::default(),
+        );
     }
 
     (0..quotient_size)
         .into_par_iter()
         .step_by(PackedVal::<SC>::WIDTH)
-        .flat_map_iter(|i_local_start| {
-            let wrap = |i| i % quotient_size;
-            let i_next_start = wrap(i_local_start + next_step);
-            let i_range = i_local_start..i_local_start + PackedVal::<SC>::WIDTH;
+        .map_init(
+            || {
+                // Packed values aren't necessarily `Sync`, so each thread computes its own.
+                let alpha_powers = constraint_program
+                    .map(|program| program.alpha_powers(alpha))
+                    .unwrap_or_default();
+                (ConstraintRegisters::default(), alpha_powers)
+            },
+            |(registers, alpha_powers), i_local_start| {
+                let wrap = |i| i % quotient_size;
+                // The LDE rows holding each row of the window, for the first packed lane.
+                let window_rows = (0..window_size)
+                    .map(|offset| i_local_start + offset * next_step)
+                    .collect_vec();
+                let i_range = i_local_start..i_local_start + PackedVal::<SC>::WIDTH;
+                let packed =
+                    |values: &[SC::Val]| *PackedVal::<SC>::from_slice(&values[i_range.clone()]);
 
-            let x = *PackedVal::<SC>::from_slice(&coset[i_range.clone()]);
-            let is_transition = x - subgroup_last;
-            let is_first_row = *PackedVal::<SC>::from_slice(&lagrange_first_evals[i_range.clone()]);
-            let is_last_row = *PackedVal::<SC>::from_slice(&lagrange_last_evals[i_range]);
+                let transition_selectors = is_transition.iter().map(|sel| packed(sel)).collect();
+                let is_first_row = packed(&is_first_row);
+                let is_last_row = packed(&is_last_row);
 
-            let local: Vec<_> = (0..trace_lde.width())
-                .map(|col| {
-                    PackedVal::<SC>::from_fn(|offset| {
-                        let row = wrap(i_local_start + offset);
-                        trace_lde.get(row, col)
-                    })
-                })
-                .collect();
-            let next: Vec<_> = (0..trace_lde.width())
-                .map(|col| {
-                    PackedVal::<SC>::from_fn(|offset| {
-                        let row = wrap(i_next_start + offset);
-                        trace_lde.get(row, col)
+                let preprocessed = preprocessed_lde
+                    .map(|prep| packed_window::<SC, _>(prep, &window_rows, quotient_size))
+                    .unwrap_or_default();
+                let main = packed_window::<SC, _>(&trace_lde, &window_rows, quotient_size);
+                let public_values =
+                    packed_window::<SC, _>(public_trace_lde, &window_rows, quotient_size);
+
+                // The permutation trace was committed as D base field columns per extension column.
+                let permutation: Vec<Vec<_>> = permutation_lde
+                    .map(|perm| {
+                        window_rows
+                            .iter()
+                            .map(|&row_start| {
+                                (0..perm.width() / ext_degree)
+                                    .map(|col| {
+                                        PackedChallenge::<SC>::from_base_fn(|coeff_idx| {
+                                            PackedVal::<SC>::from_fn(|lane| {
+                                                perm.get(
+                                                    wrap(row_start + lane),
+                                                    col * ext_degree + coeff_idx,
+                                                )
+                                            })
+                                        })
+                                    })
+                                    .collect()
+                            })
+                            .collect()
                     })
-                })
-                .collect();
+                    .unwrap_or_default();
+                let packed_permutation_challenges: Vec<_> = permutation_challenges
+                    .iter()
+                    .map(|&c| PackedChallenge::<SC>::from_f(c))
+                    .collect();
 
-            let public_local: Vec<_> = (0..public_trace_lde.width())
-                .map(|col| {
-                    PackedVal::<SC>::from_fn(|offset| {
-                        let row = wrap(i_local_start + offset);
-                        public_trace_lde.get(row, col)
+                let accumulator = PackedChallenge::<SC>::zero();
+                let mut folder = ProverConstraintFolder {
+                    preprocessed: WindowMatrixView::new(&preprocessed),
+                    main: WindowMatrixView::new(&main),
+                    permutation: WindowMatrixView::new(&permutation),
+                    permutation_challenges: &packed_permutation_challenges,
+                    public_values: WindowMatrixView::new(&public_values),
+                    is_first_row,
+                    is_last_row,
+                    transition_selectors,
+                    alpha,
+                    accumulator,
+                };
+                match constraint_program {
+                    Some(program) => program.eval(&mut folder, alpha_powers, registers),
+                    None => air.eval(&mut folder),
+                }
+
+                // quotient(x) = constraints(x) / Z_H(x)
+                let quotient = folder.accumulator * packed(&inv_zerofier);
+
+                // "Transpose" D packed base coefficients into WIDTH scalar extension coefficients.
+                let limit = PackedVal::<SC>::WIDTH.min(quotient_size);
+                (0..limit)
+                    .map(|idx_in_packing| {
+                        let quotient_value = (0..ext_degree)
+                            .map(|coeff_idx| {
+                                quotient.as_base_slice()[coeff_idx].as_slice()[idx_in_packing]
+                            })
+                            .collect_vec();
+                        SC::Challenge::from_base_slice(&quotient_value)
                     })
-                })
-                .collect();
-            let public_next: Vec<_> = (0..public_trace_lde.width())
+                    .collect_vec()
+            },
+        )
+        .flat_map_iter(|quotient_values| quotient_values)
+        .collect()
+}
+
+/// Read the rows of a window from `mat`, where each packed value holds consecutive rows, starting
+/// with the given rows of `window_rows`.
+fn packed_window<SC, Mat>(
+    mat: &Mat,
+    window_rows: &[usize],
+    quotient_size: usize,
+) -> Vec<Vec<PackedVal<SC>>>
+where
+    SC: StarkGenericConfig,
+    Mat: MatrixGet<SC::Val>,
+{
+    window_rows
+        .iter()
+        .map(|&row_start| {
+            (0..mat.width())
                 .map(|col| {
-                    PackedVal::<SC>::from_fn(|offset| {
-                        let row = wrap(i_next_start + offset);
-                        public_trace_lde.get(row, col)
+                    PackedVal::<SC>::from_fn(|lane| {
+                        mat.get((row_start + lane) % quotient_size, col)
                     })
                 })
-                .collect();
-
-            let accumulator = PackedChallenge::<SC>::zero();
-            let mut folder = ProverConstraintFolder {
-                main: TwoRowMatrixView {
-                    local: &local,
-                    next: &next,
-                },
-                public_values: TwoRowMatrixView {
-                    local: &public_local,
-                    next: &public_next,
-                },
-                is_first_row,
-                is_last_row,
-                is_transition,
-                alpha,
-                accumulator,
-            };
-            air.eval(&mut folder);
-
-            // quotient(x) = constraints(x) / Z_H(x)
-            let zerofier_inv: PackedVal<SC> = zerofier_on_coset.eval_inverse_packed(i_local_start);
-            let quotient = folder.accumulator * zerofier_inv;
-
-            // "Transpose" D packed base coefficients into WIDTH scalar extension coefficients.
-            let limit = PackedVal::<SC>::WIDTH.min(quotient_size);
-            (0..limit).map(move |idx_in_packing| {
-                let quotient_value = (0..<SC::Challenge as AbstractExtensionField<SC::Val>>::D)
-                    .map(|coeff_idx| quotient.as_base_slice()[coeff_idx].as_slice()[idx_in_packing])
-                    .collect_vec();
-                SC::Challenge::from_base_slice(&quotient_value)
-            })
+                .collect()
         })
         .collect()
 }
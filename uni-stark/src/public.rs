@@ -2,56 +2,60 @@ use alloc::slice;
 use alloc::vec::Vec;
 use core::iter::Cloned;
 
-use p3_commit::UnivariatePcsWithLde;
-use p3_field::{ExtensionField, TwoAdicField};
+use p3_commit::StarkPcs;
+use p3_field::{ExtensionField, Field};
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::{Matrix, MatrixGet, MatrixRowSlices, MatrixRows};
-use p3_util::log2_strict_usize;
 
 use crate::StarkGenericConfig;
 
 pub trait PublicValues<F, E>: MatrixRowSlices<F> + MatrixGet<F> + Sized
 where
-    F: TwoAdicField,
-    E: ExtensionField<F> + TwoAdicField,
+    F: Field,
+    E: ExtensionField<F>,
 {
-    fn interpolate(&self, zeta: E, offset: usize) -> Vec<E>
+    /// Evaluate the public values' polynomials at `point`, which lies outside the trace domain.
+    fn interpolate<SC>(&self, config: &SC, point: E) -> Vec<E>
     where
-        Self: core::marker::Sized,
-    {
-        let height = self.height();
-        let log_height = log2_strict_usize(height);
-        let g = F::two_adic_generator(log_height);
-        let shift = g.powers().nth(offset).unwrap();
-
-        p3_interpolation::interpolate_coset::<F, E, _>(self, shift, zeta)
-    }
+        SC: StarkGenericConfig<Val = F, Challenge = E>;
 
-    fn get_ldes<SC>(&self, config: &SC) -> Self
+    /// Evaluate the public values' polynomials over the quotient domain of height
+    /// `2^log_quotient_height`.
+    fn get_ldes<SC>(&self, config: &SC, log_quotient_height: usize) -> Self
     where
         SC: StarkGenericConfig<Val = F, Challenge = E>;
 }
 
 impl<F, E, T> PublicValues<F, E> for T
 where
-    F: TwoAdicField,
-    E: ExtensionField<F> + TwoAdicField,
+    F: Field,
+    E: ExtensionField<F>,
     T: From<RowMajorMatrix<F>> + MatrixRowSlices<F> + MatrixGet<F> + Sized + Clone,
 {
-    fn get_ldes<SC>(&self, config: &SC) -> Self
+    fn interpolate<SC>(&self, config: &SC, point: E) -> Vec<E>
     where
         SC: StarkGenericConfig<Val = F, Challenge = E>,
     {
-        let pcs = config.pcs();
-        let mat = self.clone().to_row_major_matrix();
-        pcs.compute_lde_batch(mat).into()
+        config
+            .pcs()
+            .evaluate_at_point(self.clone().to_row_major_matrix(), point)
+    }
+
+    fn get_ldes<SC>(&self, config: &SC, log_quotient_height: usize) -> Self
+    where
+        SC: StarkGenericConfig<Val = F, Challenge = E>,
+    {
+        config
+            .pcs()
+            .evaluate_on_quotient_domain(self.clone().to_row_major_matrix(), log_quotient_height)
+            .into()
     }
 }
 
 // impl<F, E> PublicValues<F, E> for RowMajorMatrix<F>
 // where
-//     F: TwoAdicField,
-//     E: ExtensionField<F> + TwoAdicField,
+//     F: Field,
+//     E: ExtensionField<F>,
 // {
 // }
 
@@ -95,16 +99,19 @@ impl<T: Clone> MatrixGet<T> for PublicRow<T> {
 
 impl<F, E> PublicValues<F, E> for PublicRow<F>
 where
-    F: TwoAdicField,
-    E: ExtensionField<F> + TwoAdicField,
+    F: Field,
+    E: ExtensionField<F>,
 {
-    fn interpolate(&self, _zeta: E, _offset: usize) -> Vec<E> {
+    fn interpolate<SC>(&self, _config: &SC, _point: E) -> Vec<E>
+    where
+        SC: StarkGenericConfig<Val = F, Challenge = E>,
+    {
         self.0.iter().map(|v| E::from_base(*v)).collect()
     }
 
-    fn get_ldes<SC>(&self, _config: &SC) -> Self
+    fn get_ldes<SC>(&self, _config: &SC, _log_quotient_height: usize) -> Self
     where
-        SC: StarkGenericConfig<Val = F>,
+        SC: StarkGenericConfig<Val = F, Challenge = E>,
     {
         self.clone()
     }
@@ -1,29 +1,43 @@
 use alloc::vec;
 use alloc::vec::Vec;
-use core::marker::PhantomData;
 
-use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues};
+use p3_air::{
+    Air, AirBuilder, AirBuilderWithPublicValues, ExtensionBuilder, PairBuilder,
+    PermutationAirBuilder,
+};
 use p3_field::Field;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_util::log2_ceil_usize;
 use tracing::instrument;
 
 use crate::symbolic_expression::SymbolicExpression;
-use crate::symbolic_variable::SymbolicVariable;
+use crate::symbolic_variable::{Entry, SymbolicVariable};
 
 #[instrument(name = "infer log of constraint degree", skip_all)]
-pub fn get_log_quotient_degree<F, A>(air: &A, public_width: usize) -> usize
+pub fn get_log_quotient_degree<F, A>(air: &A, public_width: usize, is_zk: bool) -> usize
 where
     F: Field,
     A: Air<SymbolicAirBuilder<F>>,
 {
+    log_quotient_degree_for(get_max_constraint_degree(air, public_width), is_zk)
+}
+
+/// The log of the quotient's degree, as a multiple of the trace length, for constraints of at most
+/// `constraint_degree`.
+pub(crate) fn log_quotient_degree_for(constraint_degree: usize, is_zk: bool) -> usize {
     // We pad to at least degree 2, since a quotient argument doesn't make sense with smaller degrees.
-    let constraint_degree = get_max_constraint_degree(air, public_width).max(2);
+    let constraint_degree = constraint_degree.max(2);
 
     // The quotient's actual degree is approximately (max_constraint_degree - 1) n,
-    // where subtracting 1 comes from division by the zerofier.
+    // where subtracting 1 comes from division by the zerofier. In zero-knowledge mode, the blinded
+    // trace polynomials have degree 2n, so it's approximately (2 max_constraint_degree - 1) n.
     // But we pad it to a power of two so that we can efficiently decompose the quotient.
-    log2_ceil_usize(constraint_degree - 1)
+    let quotient_degree = if is_zk {
+        2 * constraint_degree - 1
+    } else {
+        constraint_degree - 1
+    };
+    log2_ceil_usize(quotient_degree)
 }
 
 #[instrument(name = "infer constraint degree", skip_all, level = "debug")]
@@ -45,44 +59,75 @@ where
     F: Field,
     A: Air<SymbolicAirBuilder<F>>,
 {
-    let mut builder = SymbolicAirBuilder::new(air.width(), public_width);
+    let mut builder = SymbolicAirBuilder::new(
+        air.window_size(),
+        air.preprocessed_width(),
+        air.width(),
+        air.permutation_width(),
+        air.num_permutation_challenges(),
+        public_width,
+    );
     air.eval(&mut builder);
     builder.constraints()
 }
 
 /// An `AirBuilder` for evaluating constraints symbolically, and recording them for later use.
 pub struct SymbolicAirBuilder<F: Field> {
+    window_size: usize,
+    preprocessed: RowMajorMatrix<SymbolicVariable<F>>,
     main: RowMajorMatrix<SymbolicVariable<F>>,
+    permutation: RowMajorMatrix<SymbolicVariable<F>>,
+    permutation_challenges: Vec<SymbolicVariable<F>>,
     public_values: RowMajorMatrix<SymbolicVariable<F>>,
     constraints: Vec<SymbolicExpression<F>>,
 }
 
 impl<F: Field> SymbolicAirBuilder<F> {
-    pub(crate) fn new(width: usize, public_width: usize) -> Self {
-        let values = [false, true]
-            .into_iter()
-            .flat_map(|is_next| {
-                (0..width).map(move |column| SymbolicVariable {
-                    is_next,
-                    column,
-                    _phantom: PhantomData,
-                })
+    pub(crate) fn new(
+        window_size: usize,
+        preprocessed_width: usize,
+        width: usize,
+        permutation_width: usize,
+        num_permutation_challenges: usize,
+        public_width: usize,
+    ) -> Self {
+        let prep_values = (0..window_size)
+            .flat_map(|offset| {
+                (0..preprocessed_width)
+                    .map(move |index| SymbolicVariable::new(Entry::Preprocessed { offset }, index))
+            })
+            .collect();
+
+        let main_values = (0..window_size)
+            .flat_map(|offset| {
+                (0..width).map(move |index| SymbolicVariable::new(Entry::Main { offset }, index))
+            })
+            .collect();
+
+        let perm_values = (0..window_size)
+            .flat_map(|offset| {
+                (0..permutation_width)
+                    .map(move |index| SymbolicVariable::new(Entry::Permutation { offset }, index))
             })
             .collect();
 
-        let public_values = [false, true]
-            .into_iter()
-            .flat_map(|is_next| {
-                (0..public_width).map(move |column| SymbolicVariable {
-                    is_next,
-                    column,
-                    _phantom: PhantomData,
-                })
+        let permutation_challenges = (0..num_permutation_challenges)
+            .map(|index| SymbolicVariable::new(Entry::Challenge, index))
+            .collect();
+
+        let public_values = (0..window_size)
+            .flat_map(|offset| {
+                (0..public_width)
+                    .map(move |index| SymbolicVariable::new(Entry::Public { offset }, index))
             })
             .collect();
 
         Self {
-            main: RowMajorMatrix::new(values, width),
+            window_size,
+            preprocessed: RowMajorMatrix::new(prep_values, preprocessed_width.max(1)),
+            main: RowMajorMatrix::new(main_values, width),
+            permutation: RowMajorMatrix::new(perm_values, permutation_width.max(1)),
+            permutation_challenges,
             // TODO replace zeros once we have SymbolicExpression::PublicValue
             public_values: RowMajorMatrix::new(public_values, public_width.max(1)),
             constraints: vec![],
@@ -113,11 +158,12 @@ impl<F: Field> AirBuilder for SymbolicAirBuilder<F> {
     }
 
     fn is_transition_window(&self, size: usize) -> Self::Expr {
-        if size == 2 {
-            SymbolicExpression::IsTransition
-        } else {
-            panic!("uni-stark only supports a window size of 2")
-        }
+        assert!(
+            size <= self.window_size,
+            "transition window of {size} rows exceeds the AIR's window size of {}",
+            self.window_size
+        );
+        SymbolicExpression::IsTransitionWindow(size)
     }
 
     fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
@@ -130,3 +176,37 @@ impl<F: Field> AirBuilderWithPublicValues for SymbolicAirBuilder<F> {
         self.public_values.clone()
     }
 }
+
+impl<F: Field> PairBuilder for SymbolicAirBuilder<F> {
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed.clone()
+    }
+}
+
+// Extension field constraints are recorded like base field ones; their symbolic form only matters
+// for degree inference, which doesn't depend on the field.
+impl<F: Field> ExtensionBuilder for SymbolicAirBuilder<F> {
+    type EF = F;
+    type ExprEF = SymbolicExpression<F>;
+    type VarEF = SymbolicVariable<F>;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        self.constraints.push(x.into());
+    }
+}
+
+impl<F: Field> PermutationAirBuilder for SymbolicAirBuilder<F> {
+    type MP = RowMajorMatrix<Self::VarEF>;
+    type RandomVar = SymbolicVariable<F>;
+
+    fn permutation(&self) -> Self::MP {
+        self.permutation.clone()
+    }
+
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        &self.permutation_challenges
+    }
+}
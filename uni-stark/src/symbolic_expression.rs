@@ -13,7 +13,8 @@ pub enum SymbolicExpression<F: Field> {
     Variable(SymbolicVariable<F>),
     IsFirstRow,
     IsLastRow,
-    IsTransition,
+    /// The selector for rows where a window of the given number of rows fits in the trace.
+    IsTransitionWindow(usize),
     Constant(F),
     Add {
         x: Rc<Self>,
@@ -40,10 +41,13 @@ impl<F: Field> SymbolicExpression<F> {
     /// Returns the multiple of `n` (the trace length) in this expression's degree.
     pub(crate) fn degree_multiple(&self) -> usize {
         match self {
-            SymbolicExpression::Variable(_) => 1,
+            SymbolicExpression::Variable(v) => v.degree_multiple(),
             SymbolicExpression::IsFirstRow => 1,
             SymbolicExpression::IsLastRow => 1,
-            SymbolicExpression::IsTransition => 0,
+            // The selector has degree `size - 1 < n`, so counting it as a whole multiple of `n` is
+            // a safe upper bound. For `size <= 2` this isn't needed, as dividing by the zerofier
+            // leaves room for one extra degree.
+            SymbolicExpression::IsTransitionWindow(size) => usize::from(*size > 2),
             SymbolicExpression::Constant(_) => 0,
             SymbolicExpression::Add {
                 degree_multiple, ..
@@ -5,14 +5,44 @@ use p3_field::Field;
 
 use crate::symbolic_expression::SymbolicExpression;
 
+/// The trace (or public input matrix) that a `SymbolicVariable` refers to, along with the row
+/// offset within the evaluation window. `Challenge` refers to a permutation challenge, which is
+/// constant over the whole trace.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Entry {
+    Preprocessed { offset: usize },
+    Main { offset: usize },
+    Permutation { offset: usize },
+    Public { offset: usize },
+    Challenge,
+}
+
 /// A variable within the evaluation window, i.e. a column in either the local or next row.
 #[derive(Copy, Clone, Debug)]
 pub struct SymbolicVariable<F: Field> {
-    pub is_next: bool,
-    pub column: usize,
+    pub entry: Entry,
+    pub index: usize,
     pub(crate) _phantom: PhantomData<F>,
 }
 
+impl<F: Field> SymbolicVariable<F> {
+    pub(crate) const fn new(entry: Entry, index: usize) -> Self {
+        Self {
+            entry,
+            index,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the multiple of `n` (the trace length) in this variable's degree.
+    pub(crate) const fn degree_multiple(&self) -> usize {
+        match self.entry {
+            Entry::Challenge => 0,
+            _ => 1,
+        }
+    }
+}
+
 impl<F: Field> From<SymbolicVariable<F>> for SymbolicExpression<F> {
     fn from(value: SymbolicVariable<F>) -> Self {
         SymbolicExpression::Variable(value)
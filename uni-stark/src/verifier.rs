@@ -1,17 +1,23 @@
 use alloc::vec;
 use alloc::vec::Vec;
 
-use p3_air::{Air, BaseAir, TwoRowMatrixView};
+use itertools::Itertools;
+use p3_air::{Air, BaseAir, WindowMatrixView};
 use p3_challenger::{CanObserve, FieldChallenger};
-use p3_commit::UnivariatePcs;
-use p3_field::{AbstractExtensionField, AbstractField, Field, TwoAdicField};
+use p3_commit::{StarkPcs, UnivariatePcs};
+use p3_field::{AbstractExtensionField, AbstractField};
 use p3_matrix::Dimensions;
-use p3_util::reverse_slice_index_bits;
 use tracing::instrument;
 
 use crate::symbolic_builder::{get_log_quotient_degree, SymbolicAirBuilder};
-use crate::{Proof, PublicValues, StarkGenericConfig, VerifierConstraintFolder};
+use crate::{
+    setup_keys, Proof, PublicValues, StarkGenericConfig, VerifierConstraintFolder, VerifyingKey,
+};
 
+/// Verify a proof produced by `prove`.
+///
+/// Any preprocessed trace is recomputed and committed on every call; use `setup_keys` and
+/// `verify_with_key` to do so once instead.
 #[instrument(skip_all)]
 pub fn verify<SC, A, P>(
     config: &SC,
@@ -25,8 +31,35 @@ where
     A: Air<SymbolicAirBuilder<SC::Val>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
     P: PublicValues<SC::Val, SC::Challenge>,
 {
-    let log_quotient_degree = get_log_quotient_degree::<SC::Val, A>(air, public_values.width());
-    let quotient_degree = 1 << log_quotient_degree;
+    let (_, verifying_key) = setup_keys(config, air);
+    verify_with_key(
+        config,
+        &verifying_key,
+        air,
+        challenger,
+        proof,
+        public_values,
+    )
+}
+
+#[instrument(skip_all)]
+pub fn verify_with_key<SC, A, P>(
+    config: &SC,
+    verifying_key: &VerifyingKey<SC>,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    proof: &Proof<SC>,
+    public_values: &P,
+) -> Result<(), VerificationError>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<SC::Val>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    P: PublicValues<SC::Val, SC::Challenge>,
+{
+    let pcs = config.pcs();
+    let is_zk = config.is_zk();
+    let log_quotient_degree =
+        get_log_quotient_degree::<SC::Val, A>(air, public_values.width(), is_zk);
 
     let Proof {
         commitments,
@@ -35,99 +68,160 @@ where
         degree_bits,
     } = proof;
 
+    let preprocessed = verifying_key.preprocessed.as_ref();
+    let preprocessed_width = preprocessed.map_or(0, |prep| prep.width);
     let air_width = <A as BaseAir<SC::Val>>::width(air);
-    let quotient_chunks = quotient_degree * <SC::Challenge as AbstractExtensionField<SC::Val>>::D;
-    let valid_shape = opened_values.trace_local.len() == air_width
-        && opened_values.trace_next.len() == air_width
-        && opened_values.quotient_chunks.len() == quotient_chunks;
+    let challenge_ext_degree = <SC::Challenge as AbstractExtensionField<SC::Val>>::D;
+    // The permutation trace is committed as D base field columns per extension column.
+    let permutation_width = <A as BaseAir<SC::Val>>::permutation_width(air);
+    let permutation_base_width = permutation_width * challenge_ext_degree;
+    let window_size = <A as BaseAir<SC::Val>>::window_size(air);
+    // A committed matrix is opened at each row of the window, while others have no openings.
+    let valid_window = |window: &[Vec<SC::Challenge>], committed: bool, width: usize| {
+        let height = if committed { window_size } else { 0 };
+        window.len() == height && window.iter().all(|row| row.len() == width)
+    };
+    // The verifying key must have been set up for this AIR.
+    let valid_shape = preprocessed_width == <A as BaseAir<SC::Val>>::preprocessed_width(air)
+        && valid_window(
+            &opened_values.preprocessed,
+            preprocessed.is_some(),
+            preprocessed_width,
+        )
+        && valid_window(&opened_values.trace, true, air_width)
+        && valid_window(
+            &opened_values.permutation,
+            permutation_width > 0,
+            permutation_base_width,
+        )
+        && commitments.permutation.is_some() == (permutation_width > 0)
+        && preprocessed.is_none_or(|prep| prep.degree_bits == *degree_bits)
+        && *degree_bits <= pcs.max_log_height();
     if !valid_shape {
         return Err(VerificationError::InvalidProofShape);
     }
+    let quotient_dims = pcs.quotient_dimensions(*degree_bits, log_quotient_degree, is_zk);
+    if opened_values.quotient_chunks.len() != quotient_dims.width {
+        return Err(VerificationError::InvalidProofShape);
+    }
 
-    let g_subgroup = SC::Val::two_adic_generator(*degree_bits);
+    // In zero-knowledge mode, committed traces are twice the trace height.
+    let committed_trace_height = 1 << (degree_bits + usize::from(is_zk));
 
+    if let Some(prep) = preprocessed {
+        challenger.observe(prep.commitment.clone());
+    }
     challenger.observe(commitments.trace.clone());
     for i in 0..public_values.height() {
         challenger.observe_slice(public_values.row_slice(i));
     }
+    let permutation_challenges: Vec<SC::Challenge> = (0..air.num_permutation_challenges())
+        .map(|_| challenger.sample_ext_element())
+        .collect();
+    if let Some(permutation_commit) = &commitments.permutation {
+        challenger.observe(permutation_commit.clone());
+    }
     let alpha: SC::Challenge = challenger.sample_ext_element();
     challenger.observe(commitments.quotient_chunks.clone());
     let zeta: SC::Challenge = challenger.sample_ext_element();
 
-    let local_and_next = [vec![zeta, zeta * g_subgroup]];
-    let commits_and_points = &[
-        (commitments.trace.clone(), local_and_next.as_slice()),
+    let window_points = [
+        itertools::iterate(zeta, |&point| pcs.next_point(*degree_bits, point))
+            .take(window_size)
+            .collect_vec(),
+    ];
+    let quotient_points = [vec![pcs.quotient_point(zeta, log_quotient_degree, is_zk)]];
+    let mut commits_and_points = vec![
+        (commitments.trace.clone(), window_points.as_slice()),
         (
             commitments.quotient_chunks.clone(),
-            &[vec![zeta.exp_power_of_2(log_quotient_degree)]],
+            quotient_points.as_slice(),
         ),
     ];
-    let values = vec![
-        vec![vec![
-            opened_values.trace_local.clone(),
-            opened_values.trace_next.clone(),
-        ]],
+    let mut values = vec![
+        vec![opened_values.trace.clone()],
         vec![vec![opened_values.quotient_chunks.clone()]],
     ];
-    let dims = &[
+    let mut dims = vec![
         vec![Dimensions {
             width: air_width,
-            height: 1 << degree_bits,
-        }],
-        vec![Dimensions {
-            width: quotient_chunks,
-            height: 1 << degree_bits,
+            height: committed_trace_height,
         }],
+        vec![quotient_dims],
     ];
-    config
-        .pcs()
-        .verify_multi_batches(commits_and_points, dims, values, opening_proof, challenger)
-        .map_err(|_| VerificationError::InvalidOpeningArgument)?;
+    if let Some(prep) = preprocessed {
+        commits_and_points.push((prep.commitment.clone(), window_points.as_slice()));
+        values.push(vec![opened_values.preprocessed.clone()]);
+        dims.push(vec![Dimensions {
+            width: prep.width,
+            height: committed_trace_height,
+        }]);
+    }
+    if let Some(permutation_commit) = &commitments.permutation {
+        commits_and_points.push((permutation_commit.clone(), window_points.as_slice()));
+        values.push(vec![opened_values.permutation.clone()]);
+        dims.push(vec![Dimensions {
+            width: permutation_base_width,
+            height: committed_trace_height,
+        }]);
+    }
+    pcs.verify_multi_batches(
+        &commits_and_points,
+        &dims,
+        values,
+        opening_proof,
+        challenger,
+    )
+    .map_err(|_| VerificationError::InvalidOpeningArgument)?;
 
-    // Derive the opening of the quotient polynomial, which was split into degree n chunks, then
-    // flattened into D base field polynomials. We first undo the flattening.
-    let challenge_ext_degree = <SC::Challenge as AbstractExtensionField<SC::Val>>::D;
-    let mut quotient_parts: Vec<SC::Challenge> = opened_values
-        .quotient_chunks
-        .chunks(challenge_ext_degree)
-        .map(|chunk| {
-            chunk
-                .iter()
-                .enumerate()
-                .map(|(i, &c)| <SC::Challenge as AbstractExtensionField<SC::Val>>::monomial(i) * c)
-                .sum()
-        })
-        .collect();
-    // Then we reconstruct the larger quotient polynomial from its degree-n parts.
-    reverse_slice_index_bits(&mut quotient_parts);
-    let quotient: SC::Challenge = zeta
-        .powers()
-        .zip(quotient_parts)
-        .map(|(weight, part)| part * weight)
-        .sum();
-
-    let z_h = zeta.exp_power_of_2(*degree_bits) - SC::Challenge::one();
-    let is_first_row = z_h / (zeta - SC::Val::one());
-    let is_last_row = z_h / (zeta - g_subgroup.inverse());
-    let is_transition = zeta - g_subgroup.inverse();
-
-    let (public_local, public_next) = (
-        public_values.interpolate(zeta, 0),
-        public_values.interpolate(zeta, 1),
+    // Undo the flattening of extension field polynomials into D base field polynomials.
+    let unflatten = |values: &[SC::Challenge]| -> Vec<SC::Challenge> {
+        values
+            .chunks(challenge_ext_degree)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &c)| {
+                        <SC::Challenge as AbstractExtensionField<SC::Val>>::monomial(i) * c
+                    })
+                    .sum()
+            })
+            .collect()
+    };
+
+    // Derive the opening of the quotient polynomial, which was committed as D base field
+    // polynomials per extension field polynomial. We first undo the flattening, then let the PCS
+    // reconstruct the quotient from its parts.
+    let quotient_parts = unflatten(&opened_values.quotient_chunks);
+    let quotient = pcs.recompose_quotient(
+        &quotient_parts,
+        zeta,
+        *degree_bits,
+        log_quotient_degree,
+        is_zk,
     );
 
+    let selectors = pcs.selectors_at_point(*degree_bits, zeta, window_size);
+    let public_values = window_points[0]
+        .iter()
+        .map(|&point| public_values.interpolate(config, point))
+        .collect_vec();
+    let permutation = opened_values
+        .permutation
+        .iter()
+        .map(|row| unflatten(row))
+        .collect_vec();
+
     let mut folder = VerifierConstraintFolder {
-        main: TwoRowMatrixView {
-            local: &opened_values.trace_local,
-            next: &opened_values.trace_next,
-        },
-        public_values: TwoRowMatrixView {
-            local: &public_local,
-            next: &public_next,
-        },
-        is_first_row,
-        is_last_row,
-        is_transition,
+        preprocessed: WindowMatrixView::new(&opened_values.preprocessed),
+        main: WindowMatrixView::new(&opened_values.trace),
+        permutation: WindowMatrixView::new(&permutation),
+        permutation_challenges: &permutation_challenges,
+        public_values: WindowMatrixView::new(&public_values),
+        is_first_row: selectors.is_first_row,
+        is_last_row: selectors.is_last_row,
+        transition_selectors: selectors.is_transition,
         alpha,
         accumulator: SC::Challenge::zero(),
     };
@@ -135,8 +229,8 @@ where
     let folded_constraints = folder.accumulator;
 
     // Finally, check that
-    //     folded_constraints(zeta) = Z_H(zeta) * quotient(zeta)
-    if folded_constraints != z_h * quotient {
+    //     folded_constraints(zeta) / Z_H(zeta) = quotient(zeta)
+    if folded_constraints * selectors.inv_zerofier != quotient {
         return Err(VerificationError::OodEvaluationMismatch);
     }
 
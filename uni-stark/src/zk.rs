@@ -0,0 +1,13 @@
+//! Helpers for zero-knowledge mode, in which committed traces are blinded by the PCS's
+//! `StarkPcs::blind_trace`.
+
+/// Assert that proofs for traces of height `degree` reveal each blinded polynomial at fewer than
+/// `degree` points outside the trace subgroup, so that the blinding hides it: the `num_ood_points`
+/// out-of-domain points, plus one point of its LDE for each of the PCS's `num_queries` queries.
+pub(crate) fn check_hiding_bound(degree: usize, num_ood_points: usize, num_queries: usize) {
+    assert!(
+        num_ood_points + num_queries < degree,
+        "zero-knowledge proofs need the trace height ({degree}) to exceed the number of opened \
+         points: {num_ood_points} out-of-domain points plus {num_queries} queries"
+    );
+}
@@ -0,0 +1,197 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_challenger::SerializingChallenger32;
+use p3_circle::CirclePcs;
+use p3_commit::ExtensionMmcs;
+use p3_field::extension::Complex;
+use p3_field::AbstractField;
+use p3_fri::FriConfig;
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_mersenne_31::Mersenne31;
+use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher32};
+use p3_uni_stark::{
+    decode_proof, encode_proof, prove, verify, PublicRow, StarkConfig, VerificationError,
+};
+
+type Val = Mersenne31;
+type Challenge = Complex<Mersenne31>;
+
+type ByteHash = Keccak256Hash;
+type FieldHash = SerializingHasher32<ByteHash>;
+type MyCompress = CompressionFunctionFromHasher<u8, ByteHash, 2, 32>;
+type ValMmcs = FieldMerkleTreeMmcs<Val, FieldHash, MyCompress, 32, u8>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = SerializingChallenger32<Val, ByteHash>;
+type Pcs = CirclePcs<Val, Challenge, ValMmcs, ChallengeMmcs>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+const NUM_FIBONACCI_COLS: usize = 2;
+
+/// Checks that the trace's last row holds the `n`th Fibonacci number, given the first two.
+struct FibonacciAir;
+
+struct FibonacciRow<F> {
+    left: F,
+    right: F,
+}
+
+impl<F> Borrow<FibonacciRow<F>> for [F] {
+    fn borrow(&self) -> &FibonacciRow<F> {
+        debug_assert_eq!(self.len(), NUM_FIBONACCI_COLS);
+        let (prefix, rows, suffix) = unsafe { self.align_to::<FibonacciRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        &rows[0]
+    }
+}
+
+impl<F> BaseAir<F> for FibonacciAir {
+    fn width(&self) -> usize {
+        NUM_FIBONACCI_COLS
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for FibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let public_values = builder.public_values();
+        let pis = public_values.row_slice(0);
+        let (a, b, x) = (pis[0], pis[1], pis[2]);
+
+        let local: &FibonacciRow<AB::Var> = main.row_slice(0).borrow();
+        let next: &FibonacciRow<AB::Var> = main.row_slice(1).borrow();
+
+        let mut when_first_row = builder.when_first_row();
+        when_first_row.assert_eq(local.left, a);
+        when_first_row.assert_eq(local.right, b);
+
+        let mut when_transition = builder.when_transition();
+        when_transition.assert_eq(local.right, next.left);
+        when_transition.assert_eq(local.left + local.right, next.right);
+
+        // A redundant cubic constraint, for a quotient of several times the trace's degree.
+        when_transition.assert_eq(local.right.into().cube(), next.left.into().cube());
+
+        builder.when_last_row().assert_eq(local.right, x);
+    }
+}
+
+/// Like `FibonacciAir`, with constraints of the same degree, but with the sum in the left column,
+/// so `FibonacciAir`'s traces violate its transitions.
+struct SwappedFibonacciAir;
+
+impl<F> BaseAir<F> for SwappedFibonacciAir {
+    fn width(&self) -> usize {
+        NUM_FIBONACCI_COLS
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for SwappedFibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local: &FibonacciRow<AB::Var> = main.row_slice(0).borrow();
+        let next: &FibonacciRow<AB::Var> = main.row_slice(1).borrow();
+        let mut when_transition = builder.when_transition();
+        when_transition.assert_eq(local.left + local.right, next.left);
+        when_transition.assert_eq(local.right.into().cube(), next.left.into().cube());
+    }
+}
+
+fn generate_trace_rows(n: usize) -> RowMajorMatrix<Val> {
+    let mut values = vec![Val::zero(), Val::one()];
+    for i in 1..n {
+        values.push(values[2 * i - 1]);
+        values.push(values[2 * i - 2] + values[2 * i - 1]);
+    }
+    RowMajorMatrix::new(values, NUM_FIBONACCI_COLS)
+}
+
+fn make_config(log_blowup: usize) -> MyConfig {
+    let val_mmcs = ValMmcs::new(FieldHash::new(ByteHash {}), MyCompress::new(ByteHash {}));
+    let fri_config = FriConfig {
+        log_blowup,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 40,
+        proof_of_work_bits: 8,
+        mmcs: ChallengeMmcs::new(val_mmcs.clone()),
+    };
+    MyConfig::new(Pcs::new(fri_config, val_mmcs))
+}
+
+fn public_values(a: u32, b: u32, x: u32) -> PublicRow<Val> {
+    PublicRow(vec![
+        Val::from_canonical_u32(a),
+        Val::from_canonical_u32(b),
+        Val::from_canonical_u32(x),
+    ])
+}
+
+#[test]
+fn test_prove_fibonacci_over_circle() -> Result<(), VerificationError> {
+    // F_16 = 987, F_64 = 10610209857723, which is 1640641543 modulo 2^31 - 1.
+    for (log_n, x) in [(3, 21), (4, 987), (6, 1640641543)] {
+        let config = make_config(1);
+        let pis = public_values(0, 1, x);
+        let trace = generate_trace_rows(1 << log_n);
+        let mut challenger = Challenger::from_hasher(ByteHash {});
+        let proof = prove(&config, &FibonacciAir, &mut challenger, trace, &pis);
+        let mut challenger = Challenger::from_hasher(ByteHash {});
+        verify(&config, &FibonacciAir, &mut challenger, &proof, &pis)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_circle_rejects_wrong_statements() {
+    let config = make_config(1);
+    let trace = generate_trace_rows(1 << 3);
+    let mut challenger = Challenger::from_hasher(ByteHash {});
+    let proof = prove(
+        &config,
+        &FibonacciAir,
+        &mut challenger,
+        trace,
+        &public_values(0, 1, 21),
+    );
+    for pis in [public_values(0, 1, 22), public_values(1, 1, 21)] {
+        let mut challenger = Challenger::from_hasher(ByteHash {});
+        assert!(verify(&config, &FibonacciAir, &mut challenger, &proof, &pis).is_err());
+    }
+
+    // The AIR isn't part of the transcript, so only the out-of-domain check catches this.
+    let pis = public_values(0, 1, 21);
+    let mut challenger = Challenger::from_hasher(ByteHash {});
+    assert!(matches!(
+        verify(&config, &SwappedFibonacciAir, &mut challenger, &proof, &pis),
+        Err(VerificationError::OodEvaluationMismatch)
+    ));
+}
+
+#[test]
+fn test_circle_rejects_tampered_proofs() {
+    let config = make_config(1);
+    let pis = public_values(0, 1, 21);
+    let trace = generate_trace_rows(1 << 3);
+    let mut challenger = Challenger::from_hasher(ByteHash {});
+    let proof = prove(&config, &FibonacciAir, &mut challenger, trace, &pis);
+    let bytes = encode_proof(&config, &proof);
+
+    // Flip a bit of every few bytes, throughout the commitments, openings and opening proof.
+    for offset in (0..bytes.len()).step_by(53) {
+        let mut mutant = bytes.clone();
+        mutant[offset] ^= 1;
+        let Ok(proof) = decode_proof(&config, &mutant) else {
+            continue;
+        };
+        let mut challenger = Challenger::from_hasher(ByteHash {});
+        assert!(
+            verify(&config, &FibonacciAir, &mut challenger, &proof, &pis).is_err(),
+            "a proof with byte {offset} flipped was accepted"
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use p3_air::{
+    Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, PairBuilder, PermutationAirBuilder,
+    WindowMatrixView,
+};
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractExtensionField, AbstractField, Field, PackedField};
+use p3_fri::{TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_keccak_air::KeccakAir;
+use p3_matrix::MatrixRowSlices;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    get_checked_constraint_program, get_constraint_program, ConstraintRegisters, Instruction,
+    PackedChallenge, PackedConstraintProgram, PackedVal, ProverConstraintFolder, Register,
+    StarkConfig, SymbolicAirBuilder, VerifierConstraintFolder,
+};
+use rand::distributions::Standard;
+use rand::{thread_rng, Rng};
+
+mod generated {
+    use p3_air::{AirBuilderWithPublicValues, PairBuilder, PermutationAirBuilder};
+
+    include!("generated/constraint_program.rs");
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+const PUBLIC_WIDTH: usize = 1;
+
+/// An AIR using each kind of input, with constraints over both the base and extension fields.
+struct TestAir;
+
+impl<F> BaseAir<F> for TestAir {
+    fn width(&self) -> usize {
+        3
+    }
+
+    fn window_size(&self) -> usize {
+        2
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        1
+    }
+
+    fn num_permutation_challenges(&self) -> usize {
+        1
+    }
+
+    fn permutation_width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB> Air<AB> for TestAir
+where
+    AB: AirBuilderWithPublicValues + PairBuilder + PermutationAirBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let selector = builder.preprocessed().row_slice(0)[0];
+        let pis = builder.public_values().row_slice(0)[0];
+        let perm = builder.permutation();
+        let (perm_local, perm_next) = (perm.row_slice(0), perm.row_slice(1));
+        let beta: AB::ExprEF = builder.permutation_randomness()[0].into();
+
+        let sum = local[0] + local[1];
+        builder.when_first_row().assert_eq(local[0], pis);
+        builder.when_transition().assert_eq(next[0], sum.clone());
+        builder
+            .when_transition()
+            .assert_eq(next[1], (local[0] + local[1]) * local[2]);
+        builder.when(selector).assert_bool(local[2]);
+        builder.when_last_row().assert_zero(-sum * AB::F::two());
+
+        builder.assert_one_ext(perm_local[0].into() * (beta.clone() - local[0].into()));
+        builder.assert_eq_ext(
+            perm_next[1].into(),
+            AB::ExprEF::from(local[1].into()) - perm_local[1].into() * beta,
+        );
+    }
+}
+
+/// An AIR multiplying by the extension field generator, which `SymbolicAirBuilder` can't represent
+/// since its extension field is the base field.
+struct ExtConstantAir;
+
+impl<F> BaseAir<F> for ExtConstantAir {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn num_permutation_challenges(&self) -> usize {
+        1
+    }
+
+    fn permutation_width(&self) -> usize {
+        1
+    }
+}
+
+impl<AB: PermutationAirBuilder> Air<AB> for ExtConstantAir {
+    fn eval(&self, builder: &mut AB) {
+        let generator = AB::EF::from_base_fn(|i| AB::F::from_bool(i == 1));
+        let perm_local = builder.permutation().row_slice(0)[0];
+        builder.assert_zero_ext(perm_local.into() * AB::ExprEF::from_f(generator));
+    }
+}
+
+fn random_window<T>(width: usize) -> Vec<Vec<T>>
+where
+    Standard: rand::distributions::Distribution<T>,
+{
+    let mut rng = thread_rng();
+    (0..2)
+        .map(|_| (0..width).map(|_| rng.sample(Standard)).collect())
+        .collect()
+}
+
+/// Evaluate the folded constraints at random openings, either with the AIR itself or with `eval`.
+fn fold_at_random_point(eval: impl Fn(&mut VerifierConstraintFolder<MyConfig>)) -> [Challenge; 2] {
+    let mut rng = thread_rng();
+    let preprocessed = random_window(1);
+    let main = random_window(3);
+    let permutation = random_window(2);
+    let public_values = random_window(PUBLIC_WIDTH);
+    let permutation_challenges = [rng.sample(Standard)];
+    let mut folder = VerifierConstraintFolder::<MyConfig> {
+        preprocessed: WindowMatrixView::new(&preprocessed),
+        main: WindowMatrixView::new(&main),
+        permutation: WindowMatrixView::new(&permutation),
+        permutation_challenges: &permutation_challenges,
+        public_values: WindowMatrixView::new(&public_values),
+        is_first_row: rng.sample(Standard),
+        is_last_row: rng.sample(Standard),
+        transition_selectors: vec![Challenge::one(), rng.sample(Standard)],
+        alpha: rng.sample(Standard),
+        accumulator: Challenge::zero(),
+    };
+
+    TestAir.eval(&mut folder);
+    let expected = folder.accumulator;
+    folder.accumulator = Challenge::zero();
+    eval(&mut folder);
+    [expected, folder.accumulator]
+}
+
+#[test]
+fn test_program_matches_air() {
+    let program = get_constraint_program::<Val, _>(&TestAir, PUBLIC_WIDTH);
+    let [expected, actual] = fold_at_random_point(|folder| program.eval(folder));
+    assert_eq!(actual, expected);
+}
+
+/// Evaluate `air`'s constraints on random packed rows, both with `Air::eval` and with its
+/// `PackedConstraintProgram`, starting from a nonzero accumulator.
+fn fold_packed_at_random_point<A>(air: &A, public_width: usize) -> [PackedChallenge<MyConfig>; 2]
+where
+    A: Air<SymbolicAirBuilder<Val>> + for<'a> Air<ProverConstraintFolder<'a, MyConfig>>,
+{
+    let packed = || PackedVal::<MyConfig>::from_fn(|_| thread_rng().sample(Standard));
+    let packed_challenge = || PackedChallenge::<MyConfig>::from_base_fn(|_| packed());
+    let window_size = BaseAir::<Val>::window_size(air);
+    let window = |width: usize| -> Vec<Vec<_>> {
+        (0..window_size)
+            .map(|_| (0..width).map(|_| packed()).collect())
+            .collect()
+    };
+    let preprocessed = window(BaseAir::<Val>::preprocessed_width(air));
+    let main = window(BaseAir::<Val>::width(air));
+    let public_values = window(public_width);
+    let permutation: Vec<Vec<_>> = (0..window_size)
+        .map(|_| {
+            (0..BaseAir::<Val>::permutation_width(air))
+                .map(|_| packed_challenge())
+                .collect()
+        })
+        .collect();
+    let permutation_challenges: Vec<_> = (0..BaseAir::<Val>::num_permutation_challenges(air))
+        .map(|_| packed_challenge())
+        .collect();
+    let accumulator = packed_challenge();
+    let mut folder = ProverConstraintFolder::<MyConfig> {
+        preprocessed: WindowMatrixView::new(&preprocessed),
+        main: WindowMatrixView::new(&main),
+        permutation: WindowMatrixView::new(&permutation),
+        permutation_challenges: &permutation_challenges,
+        public_values: WindowMatrixView::new(&public_values),
+        is_first_row: packed(),
+        is_last_row: packed(),
+        transition_selectors: (0..window_size).map(|_| packed()).collect(),
+        alpha: thread_rng().sample(Standard),
+        accumulator,
+    };
+
+    air.eval(&mut folder);
+    let expected = folder.accumulator;
+    folder.accumulator = accumulator;
+    let program = PackedConstraintProgram::new(&get_constraint_program(air, public_width));
+    let alpha_powers = program.alpha_powers(folder.alpha);
+    program.eval(
+        &mut folder,
+        &alpha_powers,
+        &mut ConstraintRegisters::default(),
+    );
+    [expected, folder.accumulator]
+}
+
+#[test]
+fn test_packed_program_matches_air() {
+    let [expected, actual] = fold_packed_at_random_point(&TestAir, PUBLIC_WIDTH);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_packed_program_matches_wide_air() {
+    let [expected, actual] = fold_packed_at_random_point(&KeccakAir {}, 0);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_checked_program_rejects_extension_constants() {
+    assert!(get_checked_constraint_program::<MyConfig, _>(&TestAir, PUBLIC_WIDTH).is_ok());
+    assert!(get_checked_constraint_program::<MyConfig, _>(&ExtConstantAir, 0).is_err());
+}
+
+#[test]
+fn test_common_subexpressions_computed_once() {
+    let program = get_constraint_program::<Val, _>(&TestAir, PUBLIC_WIDTH);
+    let instructions = program.instructions();
+
+    // `local[0] + local[1]` is built twice, but computed once.
+    let sums = instructions
+        .iter()
+        .filter(|instruction| matches!(instruction, Instruction::Add(..)))
+        .count();
+    assert_eq!(sums, 1);
+    let main_0 = instructions
+        .iter()
+        .filter(|instruction| {
+            **instruction
+                == Instruction::Variable {
+                    entry: p3_uni_stark::Entry::Main { offset: 0 },
+                    index: 0,
+                }
+        })
+        .count();
+    assert_eq!(main_0, 1);
+
+    // The constants 1 and 2, the former used by both `assert_bool` and `assert_one_ext`.
+    assert_eq!(program.constants(), &[Val::one(), Val::two()]);
+
+    assert_eq!(program.constraints().len(), 7);
+    let ext_constraints = program
+        .constraints()
+        .iter()
+        .filter(|c| matches!(c, Register::Ext(_)))
+        .count();
+    assert_eq!(ext_constraints, 2);
+}
+
+#[test]
+fn test_generated_rust_matches_air() {
+    let [expected, actual] = fold_at_random_point(|folder| generated::eval_test_air(folder));
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_generated_rust_is_up_to_date() {
+    let program = get_constraint_program::<Val, _>(&TestAir, PUBLIC_WIDTH);
+    let strip = |s: &str| s.split_whitespace().collect::<String>();
+    assert_eq!(
+        strip(&program.to_rust("eval_test_air")),
+        strip(include_str!("generated/constraint_program.rs")),
+        "regenerate tests/generated/constraint_program.rs with `ConstraintProgram::to_rust`"
+    );
+}
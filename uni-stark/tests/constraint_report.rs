@@ -0,0 +1,92 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_baby_bear::BabyBear;
+use p3_matrix::MatrixRowSlices;
+use p3_uni_stark::{get_constraint_report, get_log_quotient_degree, Column};
+
+/// An AIR whose only constraint of degree 4 doubles the quotient's size, and whose last column is
+/// never read.
+struct HighDegreeAir;
+
+impl<F> BaseAir<F> for HighDegreeAir {
+    fn width(&self) -> usize {
+        4
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for HighDegreeAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        builder.assert_bool(local[0]);
+        builder
+            .when_transition()
+            .assert_eq(next[1], local[1] * local[0]);
+        builder
+            .when_first_row()
+            .assert_zero(local[2] * local[2] * local[2]);
+    }
+}
+
+#[test]
+fn test_report_degrees_and_columns() {
+    let report = get_constraint_report::<BabyBear, _>(&HighDegreeAir, 0, false);
+    let degrees = report
+        .constraints
+        .iter()
+        .map(|c| c.degree)
+        .collect::<Vec<_>>();
+    assert_eq!(degrees, [2, 2, 4]);
+    assert_eq!(report.max_degree, 4);
+    assert_eq!(
+        report.log_quotient_degree,
+        get_log_quotient_degree::<BabyBear, _>(&HighDegreeAir, 0, false)
+    );
+
+    let columns = report.constraints[1]
+        .columns
+        .iter()
+        .copied()
+        .collect::<Vec<_>>();
+    assert_eq!(columns, [Column::Main(0), Column::Main(1)]);
+    assert_eq!(report.unused_columns, [Column::Main(3)]);
+}
+
+#[test]
+fn test_report_flags_costly_constraints() {
+    let report = get_constraint_report::<BabyBear, _>(&HighDegreeAir, 0, false);
+    assert_eq!(report.log_quotient_degree, 2);
+    assert_eq!(report.costly_constraints, [2]);
+    assert!(report
+        .to_string()
+        .contains("constraint 2: degree 4, columns Main(2) (forces log_quotient_degree 2)"));
+
+    // In zero-knowledge mode, degree 2 constraints already need a quotient of degree 3n, so the
+    // degree 4 constraint still doubles its size.
+    let report = get_constraint_report::<BabyBear, _>(&HighDegreeAir, 0, true);
+    assert_eq!(report.log_quotient_degree, 3);
+    assert_eq!(report.costly_constraints, [2]);
+}
+
+/// An AIR whose constraints all have degree 2, which is as cheap as a quotient gets.
+struct LowDegreeAir;
+
+impl<F> BaseAir<F> for LowDegreeAir {
+    fn width(&self) -> usize {
+        1
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for LowDegreeAir {
+    fn eval(&self, builder: &mut AB) {
+        let local = builder.main().row_slice(0)[0];
+        builder.assert_bool(local);
+    }
+}
+
+#[test]
+fn test_report_flags_nothing_for_minimal_quotient() {
+    let report = get_constraint_report::<BabyBear, _>(&LowDegreeAir, 0, false);
+    assert_eq!(report.log_quotient_degree, 0);
+    assert!(report.costly_constraints.is_empty());
+    assert!(report.unused_columns.is_empty());
+}
@@ -0,0 +1,251 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::Field;
+use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    config_fingerprint, decode_proof, encode_proof, prove, verify, CodecError, ProofDecodingError,
+    PublicRow, StarkConfig, PROOF_FORMAT_VERSION,
+};
+use rand::thread_rng;
+
+/// The Fibonacci recurrence, `(a, b) -> (b, a + b)`, starting from `(0, 1)`.
+struct FibonacciAir;
+
+impl<F> BaseAir<F> for FibonacciAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for FibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+
+        builder.when_first_row().assert_zero(local[0]);
+        builder.when_first_row().assert_one(local[1]);
+        builder.when_transition().assert_eq(local[1], next[0]);
+        builder
+            .when_transition()
+            .assert_eq(local[0] + local[1], next[1]);
+    }
+}
+
+fn generate_trace<F: Field>(log_height: usize) -> RowMajorMatrix<F> {
+    let (mut a, mut b) = (F::zero(), F::one());
+    let mut values = vec![];
+    for _ in 0..1 << log_height {
+        values.extend([a, b]);
+        (a, b) = (b, a + b);
+    }
+    RowMajorMatrix::new(values, 2)
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+/// The length of an encoded proof's header.
+const HEADER_LEN: usize = 18;
+
+fn make_config(perm: &Perm, log_blowup: usize) -> MyConfig {
+    make_config_with(perm, 0, |fri_config| fri_config.log_blowup = log_blowup)
+}
+
+/// A config whose MMCSs commit to caps of height `cap_height`, with FRI parameters adjusted by
+/// `adjust_fri_config`.
+fn make_config_with(
+    perm: &Perm,
+    cap_height: usize,
+    adjust_fri_config: impl FnOnce(&mut FriConfig<ChallengeMmcs>),
+) -> MyConfig {
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new_with_cap(hash, compress, cap_height);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let mut fri_config = FriConfig {
+        log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 28,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+    adjust_fri_config(&mut fri_config);
+    MyConfig::new(Pcs::new(fri_config, Dft {}, val_mmcs))
+}
+
+/// Returns an encoded proof of the Fibonacci AIR.
+fn encoded_proof(config: &MyConfig, perm: &Perm) -> Vec<u8> {
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(
+        config,
+        &FibonacciAir,
+        &mut challenger,
+        generate_trace(4),
+        &PublicRow::default(),
+    );
+    encode_proof(config, &proof)
+}
+
+#[test]
+fn test_round_trip() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let config = make_config(&perm, 1);
+    let bytes = encoded_proof(&config, &perm);
+    assert_eq!(bytes[..4], *b"P3US");
+    assert_eq!(bytes[4..6], PROOF_FORMAT_VERSION.to_le_bytes());
+
+    let proof = decode_proof(&config, &bytes).expect("decoding failed");
+    assert_eq!(encode_proof(&config, &proof), bytes);
+
+    let mut challenger = Challenger::new(perm);
+    verify(
+        &config,
+        &FibonacciAir,
+        &mut challenger,
+        &proof,
+        &PublicRow::default(),
+    )
+    .expect("verification failed");
+}
+
+#[test]
+fn test_reject_bad_header() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let config = make_config(&perm, 1);
+    let bytes = encoded_proof(&config, &perm);
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] ^= 1;
+    assert!(matches!(
+        decode_proof(&config, &bad_magic),
+        Err(ProofDecodingError::InvalidMagic)
+    ));
+
+    let mut bad_version = bytes.clone();
+    bad_version[4..6].copy_from_slice(&(PROOF_FORMAT_VERSION + 1).to_le_bytes());
+    assert!(matches!(
+        decode_proof(&config, &bad_version),
+        Err(ProofDecodingError::UnsupportedVersion(v)) if v == PROOF_FORMAT_VERSION + 1
+    ));
+
+    let other_config = make_config(&perm, 2);
+    assert!(matches!(
+        decode_proof(&other_config, &bytes),
+        Err(ProofDecodingError::ConfigMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_fingerprint_covers_proof_shape() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let config = make_config(&perm, 1);
+    let bytes = encoded_proof(&config, &perm);
+
+    let other_configs = [
+        make_config_with(&perm, 0, |fri_config| fri_config.log_folding_arity = 2),
+        make_config_with(&perm, 0, |fri_config| fri_config.log_final_poly_len = 1),
+        make_config_with(&perm, 0, |fri_config| fri_config.num_queries = 27),
+        make_config_with(&perm, 1, |_| {}),
+    ];
+    for other_config in &other_configs {
+        assert_ne!(
+            config_fingerprint(other_config),
+            config_fingerprint(&config)
+        );
+        assert!(matches!(
+            decode_proof(other_config, &bytes),
+            Err(ProofDecodingError::ConfigMismatch { .. })
+        ));
+    }
+}
+
+#[test]
+fn test_reject_truncated_or_trailing_bytes() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let config = make_config(&perm, 1);
+    let bytes = encoded_proof(&config, &perm);
+
+    assert!(matches!(
+        decode_proof(&config, &bytes[..bytes.len() - 1]),
+        Err(ProofDecodingError::InvalidSection {
+            section: "opening_proof",
+            error: CodecError::UnexpectedEnd,
+        })
+    ));
+
+    let mut trailing = bytes;
+    trailing.push(0);
+    assert!(matches!(
+        decode_proof(&config, &trailing),
+        Err(ProofDecodingError::TrailingBytes)
+    ));
+}
+
+#[test]
+fn test_reject_non_canonical_field_element() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let config = make_config(&perm, 1);
+    let mut bytes = encoded_proof(&config, &perm);
+
+    // Skip the commitments section, the opened values' length, the empty preprocessed openings and
+    // the lengths of the trace openings, to reach the first coefficient of the first trace opening.
+    let commitments_len = u32::from_le_bytes(bytes[HEADER_LEN..HEADER_LEN + 4].try_into().unwrap());
+    let offset = HEADER_LEN + 4 + commitments_len as usize + 4 + 4 + 4 + 4;
+    bytes[offset..offset + 4].copy_from_slice(&0x7800_0001u32.to_le_bytes());
+
+    assert!(matches!(
+        decode_proof(&config, &bytes),
+        Err(ProofDecodingError::InvalidSection {
+            section: "opened_values",
+            error: CodecError::Custom(_),
+        })
+    ));
+}
+
+#[test]
+fn test_inspect_proof() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let config = make_config(&perm, 1);
+    let bytes = encoded_proof(&config, &perm);
+    let proof = decode_proof(&config, &bytes).expect("decoding failed");
+
+    assert_eq!(proof.degree_bits(), 4);
+    assert!(proof.commitments().permutation().is_none());
+    let opened_values = proof.opened_values();
+    assert!(opened_values.preprocessed().is_empty());
+    assert_eq!(opened_values.trace().len(), 2);
+    assert!(opened_values.trace().iter().all(|row| row.len() == 2));
+    // The trace and quotient batches, each opened at every query.
+    let batch_openings = proof.opening_proof().batch_openings();
+    assert_eq!(batch_openings.len(), 2);
+    assert!(batch_openings
+        .iter()
+        .all(|batch_opening| batch_opening.opened_values().len() == 28));
+
+    let size = proof.size(&config);
+    assert_eq!(size.total(), bytes.len());
+    assert!(size.merkle_paths > 0);
+    assert!(size.commit_phase_openings > 0);
+    assert!(size.opened_values > 0);
+}
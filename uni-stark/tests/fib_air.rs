@@ -13,7 +13,7 @@ use p3_matrix::MatrixRowSlices;
 use p3_merkle_tree::FieldMerkleTreeMmcs;
 use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
 use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
-use p3_uni_stark::{prove, verify, PublicRow, StarkConfig};
+use p3_uni_stark::{check_constraints, prove, verify, PublicRow, StarkConfig};
 use rand::thread_rng;
 
 /// For testing the public values feature
@@ -125,6 +125,8 @@ fn test_public_value() {
     let trace = generate_trace_rows::<Val>(0, 1, 1 << 3);
     let fri_config = FriConfig {
         log_blowup: 2,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
         num_queries: 28,
         proof_of_work_bits: 8,
         mmcs: challenge_mmcs,
@@ -143,8 +145,9 @@ fn test_public_value() {
     verify(&config, &FibonacciAir {}, &mut challenger, &proof, &pis).expect("verification failed");
 }
 
+#[cfg(debug_assertions)]
 #[test]
-#[should_panic(expected = "assertion `left == right` failed: constraints had nonzero value")]
+#[should_panic(expected = "constraints had nonzero value on row 7, constraint 4")]
 fn test_incorrect_public_value() {
     let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
     let hash = MyHash::new(perm.clone());
@@ -154,6 +157,8 @@ fn test_incorrect_public_value() {
     let dft = Dft {};
     let fri_config = FriConfig {
         log_blowup: 2,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
         num_queries: 28,
         proof_of_work_bits: 8,
         mmcs: challenge_mmcs,
@@ -171,3 +176,28 @@ fn test_incorrect_public_value() {
     let mut challenger = Challenger::new(perm.clone());
     verify(&config, &FibonacciAir {}, &mut challenger, &proof, &pis).expect("verification failed");
 }
+
+#[test]
+fn test_check_constraints_reports_every_failure() {
+    let pis = PublicRow(vec![
+        BabyBear::from_canonical_u64(0),
+        BabyBear::from_canonical_u64(1),
+        BabyBear::from_canonical_u64(21),
+    ]);
+    let trace = generate_trace_rows::<Val>(0, 1, 1 << 3);
+    assert!(check_constraints(&FibonacciAir {}, &trace, &[] as &[Challenge], &pis).is_empty());
+
+    // Breaking one cell breaks the transitions into and out of its row.
+    let mut trace = trace;
+    trace.values[2 * 4 + 1] += BabyBear::one();
+    let failures = check_constraints(&FibonacciAir {}, &trace, &[] as &[Challenge], &pis);
+    let failed = failures
+        .iter()
+        .map(|failure| (failure.row, failure.constraint))
+        .collect::<Vec<_>>();
+    assert_eq!(failed, [(3, 3), (4, 2), (4, 3)]);
+    assert_eq!(
+        failures[0].to_string(),
+        "row 3, constraint 3: main[0][0] = 2, main[0][1] = 3, main[1][1] = 6"
+    );
+}
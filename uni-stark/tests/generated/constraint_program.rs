@@ -0,0 +1,56 @@
+pub fn eval_test_air<AB>(builder: &mut AB)
+where
+    AB: AirBuilderWithPublicValues + PairBuilder + PermutationAirBuilder,
+{
+    use p3_field::AbstractField;
+    use p3_matrix::MatrixRowSlices;
+
+    let preprocessed = builder.preprocessed();
+    let main = builder.main();
+    let permutation = builder.permutation();
+    let public_values = builder.public_values();
+    let challenges = builder.permutation_randomness().to_vec();
+    let b0: AB::Expr = builder.is_first_row();
+    let b1: AB::Expr = main.row_slice(0)[0].into();
+    let b2: AB::Expr = public_values.row_slice(0)[0].into();
+    let b3: AB::Expr = b1.clone() - b2.clone();
+    let b4: AB::Expr = b0.clone() * b3.clone();
+    let b5: AB::Expr = builder.is_transition_window(2);
+    let b6: AB::Expr = main.row_slice(1)[0].into();
+    let b7: AB::Expr = main.row_slice(0)[1].into();
+    let b8: AB::Expr = b1.clone() + b7.clone();
+    let b9: AB::Expr = b6.clone() - b8.clone();
+    let b10: AB::Expr = b5.clone() * b9.clone();
+    let b11: AB::Expr = main.row_slice(1)[1].into();
+    let b12: AB::Expr = main.row_slice(0)[2].into();
+    let b13: AB::Expr = b8.clone() * b12.clone();
+    let b14: AB::Expr = b11.clone() - b13.clone();
+    let b15: AB::Expr = b5.clone() * b14.clone();
+    let b16: AB::Expr = preprocessed.row_slice(0)[0].into();
+    let b17: AB::Expr = AB::Expr::from_canonical_u64(1);
+    let b18: AB::Expr = b12.clone() - b17.clone();
+    let b19: AB::Expr = b12.clone() * b18.clone();
+    let b20: AB::Expr = b16.clone() * b19.clone();
+    let b21: AB::Expr = builder.is_last_row();
+    let b22: AB::Expr = -b8.clone();
+    let b23: AB::Expr = AB::Expr::from_canonical_u64(2);
+    let b24: AB::Expr = b22.clone() * b23.clone();
+    let b25: AB::Expr = b21.clone() * b24.clone();
+    let e0: AB::ExprEF = permutation.row_slice(0)[0].into();
+    let e1: AB::ExprEF = challenges[0].into();
+    let e2: AB::ExprEF = e1.clone() - b1.clone();
+    let e3: AB::ExprEF = e0.clone() * e2.clone();
+    let e4: AB::ExprEF = e3.clone() - b17.clone();
+    let e5: AB::ExprEF = permutation.row_slice(1)[1].into();
+    let e6: AB::ExprEF = permutation.row_slice(0)[1].into();
+    let e7: AB::ExprEF = e1.clone() * e6.clone();
+    let e8: AB::ExprEF = AB::ExprEF::from(b7.clone()) - e7.clone();
+    let e9: AB::ExprEF = e5.clone() - e8.clone();
+    builder.assert_zero(b4.clone());
+    builder.assert_zero(b10.clone());
+    builder.assert_zero(b15.clone());
+    builder.assert_zero(b20.clone());
+    builder.assert_zero(b25.clone());
+    builder.assert_zero_ext(e4.clone());
+    builder.assert_zero_ext(e9.clone());
+}
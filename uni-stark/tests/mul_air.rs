@@ -95,6 +95,8 @@ fn test_prove_baby_bear() -> Result<(), VerificationError> {
 
     let fri_config = FriConfig {
         log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
         num_queries: 40,
         proof_of_work_bits: 8,
         mmcs: challenge_mmcs,
@@ -0,0 +1,224 @@
+//! A fuzz harness for the verifier: mutate an encoded proof in many ways, and check that decoding
+//! and verifying each mutant returns an error rather than panicking.
+
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::Field;
+use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{decode_proof, encode_proof, prove, verify, PublicRow, StarkConfig};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// The Fibonacci recurrence, `(a, b) -> (b, a + b)`, starting from `(0, 1)`.
+struct FibonacciAir;
+
+impl<F> BaseAir<F> for FibonacciAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for FibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+
+        builder.when_first_row().assert_zero(local[0]);
+        builder.when_first_row().assert_one(local[1]);
+        builder.when_transition().assert_eq(local[1], next[0]);
+        builder
+            .when_transition()
+            .assert_eq(local[0] + local[1], next[1]);
+    }
+}
+
+fn generate_trace<F: Field>(log_height: usize) -> RowMajorMatrix<F> {
+    let (mut a, mut b) = (F::zero(), F::one());
+    let mut values = vec![];
+    for _ in 0..1 << log_height {
+        values.extend([a, b]);
+        (a, b) = (b, a + b);
+    }
+    RowMajorMatrix::new(values, 2)
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+/// The length of an encoded proof's header, which ends with the degree bits.
+const HEADER_LEN: usize = 18;
+
+/// The sizes in bytes of the values that a proof's vectors hold: field elements, extension field
+/// elements and digests.
+const ELEMENT_SIZES: [usize; 3] = [4, 16, 32];
+
+const NUM_MUTANTS: usize = 1000;
+
+fn make_config(perm: &Perm) -> MyConfig {
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_folding_arity: 2,
+        log_final_poly_len: 1,
+        num_queries: 10,
+        proof_of_work_bits: 1,
+        mmcs: challenge_mmcs,
+    };
+    MyConfig::new(Pcs::new(fri_config, Dft {}, val_mmcs))
+}
+
+/// The byte ranges of the length-prefixed sections which follow the header.
+fn section_ranges(bytes: &[u8]) -> Vec<(usize, usize)> {
+    let mut ranges = vec![];
+    let mut start = HEADER_LEN;
+    while start + 4 <= bytes.len() {
+        let len = u32::from_le_bytes(bytes[start..start + 4].try_into().unwrap()) as usize;
+        let end = (start + 4 + len).min(bytes.len());
+        ranges.push((start + 4, end));
+        start = end;
+    }
+    ranges
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn write_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Apply a random mutation to `bytes`.
+///
+/// Besides corrupting bytes, which decoding mostly rejects, mutations change the length of a
+/// vector within a section by guessing where a length prefix is, and fix up the section's length
+/// so that the mutant often decodes to a proof of a different shape.
+fn mutate(bytes: &mut Vec<u8>, rng: &mut StdRng) {
+    let sections = section_ranges(bytes);
+    if sections.is_empty() {
+        // An earlier mutation truncated the proof into its header.
+        return;
+    }
+    let (start, end) = sections[rng.gen_range(0..sections.len())];
+    let mut section = bytes[start..end].to_vec();
+
+    match rng.gen_range(0..6) {
+        // Flip a bit anywhere, including in the header.
+        0 => {
+            let offset = rng.gen_range(0..bytes.len());
+            bytes[offset] ^= 1 << rng.gen_range(0..8);
+            return;
+        }
+        // Replace the degree bits.
+        1 => {
+            write_u32(bytes, HEADER_LEN - 4, rng.gen_range(0..40));
+            return;
+        }
+        // Truncate the proof.
+        2 => {
+            bytes.truncate(rng.gen_range(0..bytes.len()));
+            return;
+        }
+        // Overwrite a word with a small value, like a length or an index.
+        3 if section.len() >= 4 => {
+            let offset = rng.gen_range(0..=section.len() - 4);
+            write_u32(&mut section, offset, rng.gen_range(0..64));
+        }
+        // Remove the first element of a vector.
+        4 if section.len() >= 4 => {
+            let offset = rng.gen_range(0..=section.len() - 4);
+            let len = read_u32(&section, offset);
+            let size = ELEMENT_SIZES[rng.gen_range(0..ELEMENT_SIZES.len())];
+            if len == 0 || offset + 4 + size > section.len() {
+                return;
+            }
+            write_u32(&mut section, offset, len - 1);
+            section.drain(offset + 4..offset + 4 + size);
+        }
+        // Duplicate the first element of a vector.
+        5 if section.len() >= 4 => {
+            let offset = rng.gen_range(0..=section.len() - 4);
+            let len = read_u32(&section, offset);
+            let size = ELEMENT_SIZES[rng.gen_range(0..ELEMENT_SIZES.len())];
+            if len == 0 || offset + 4 + size > section.len() {
+                return;
+            }
+            write_u32(&mut section, offset, len + 1);
+            let element = section[offset + 4..offset + 4 + size].to_vec();
+            section.splice(offset + 4..offset + 4, element);
+        }
+        _ => return,
+    }
+
+    let mut mutant = bytes[..start - 4].to_vec();
+    mutant.extend((section.len() as u32).to_le_bytes());
+    mutant.extend(section);
+    mutant.extend(&bytes[end..]);
+    *bytes = mutant;
+}
+
+#[test]
+fn test_mutated_proofs_are_rejected_without_panicking() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut rng);
+    let config = make_config(&perm);
+    let proof = prove(
+        &config,
+        &FibonacciAir,
+        &mut Challenger::new(perm.clone()),
+        generate_trace(5),
+        &PublicRow::default(),
+    );
+    let bytes = encode_proof(&config, &proof);
+
+    let mut num_decoded = 0;
+    for _ in 0..NUM_MUTANTS {
+        let mut mutant = bytes.clone();
+        for _ in 0..rng.gen_range(1..=3) {
+            mutate(&mut mutant, &mut rng);
+        }
+        if mutant == bytes {
+            continue;
+        }
+        let Ok(proof) = decode_proof(&config, &mutant) else {
+            continue;
+        };
+        num_decoded += 1;
+        let result = verify(
+            &config,
+            &FibonacciAir,
+            &mut Challenger::new(perm.clone()),
+            &proof,
+            &PublicRow::default(),
+        );
+        assert!(result.is_err(), "a mutated proof was accepted");
+    }
+    // Enough mutants must decode for the harness to exercise the verifier, not just the decoder.
+    assert!(
+        num_decoded >= NUM_MUTANTS / 10,
+        "only {num_decoded} mutants decoded"
+    );
+}
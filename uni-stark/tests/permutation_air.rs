@@ -0,0 +1,214 @@
+use p3_air::{Air, BaseAir, ExtensionBuilder, PermutationAirBuilder};
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractField, ExtensionField, Field};
+use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{Matrix, MatrixRowSlices};
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    check_constraints, get_checked_constraint_program, prove_with_key, setup_keys, verify, Entry,
+    PublicRow, StarkConfig,
+};
+use rand::{thread_rng, Rng};
+
+/// An AIR which looks up every entry of a `value` column in a `table` column, using a LogUp
+/// argument. The main trace has columns `value`, `table` and `multiplicity`, where `multiplicity`
+/// counts how many times the table entry on that row is looked up.
+///
+/// Given a challenge `beta`, the permutation trace has columns `1 / (beta - value)`,
+/// `1 / (beta - table)`, and a running sum of `multiplicity / (beta - table) - 1 / (beta - value)`,
+/// which must end at zero.
+struct LookupAir;
+
+impl<F: Field> BaseAir<F> for LookupAir {
+    fn width(&self) -> usize {
+        3
+    }
+
+    fn num_permutation_challenges(&self) -> usize {
+        1
+    }
+
+    fn permutation_width(&self) -> usize {
+        3
+    }
+
+    fn permutation_trace<EF>(
+        &self,
+        main: &RowMajorMatrix<F>,
+        challenges: &[EF],
+    ) -> Option<RowMajorMatrix<EF>>
+    where
+        EF: ExtensionField<F>,
+    {
+        let beta = challenges[0];
+        let mut sum = EF::zero();
+        let mut values = Vec::with_capacity(main.height() * 3);
+        for i in 0..main.height() {
+            let row = main.row_slice(i);
+            let (value, table, multiplicity) = (row[0], row[1], row[2]);
+            let inv_value = (beta - value).inverse();
+            let inv_table = (beta - table).inverse();
+            sum += inv_table * multiplicity - inv_value;
+            values.extend([inv_value, inv_table, sum]);
+        }
+        Some(RowMajorMatrix::new(values, 3))
+    }
+}
+
+impl<AB: PermutationAirBuilder> Air<AB> for LookupAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+
+        let perm = builder.permutation();
+        let perm_local = perm.row_slice(0);
+        let perm_next = perm.row_slice(1);
+
+        let beta: AB::ExprEF = builder.permutation_randomness()[0].into();
+
+        let (inv_value, inv_table, sum) = (perm_local[0], perm_local[1], perm_local[2]);
+        builder.assert_one_ext(inv_value.into() * (beta.clone() - local[0].into()));
+        builder.assert_one_ext(inv_table.into() * (beta - local[1].into()));
+
+        let term = |inv_value: AB::VarEF, inv_table: AB::VarEF, multiplicity: AB::Var| {
+            inv_table.into() * multiplicity.into() - inv_value.into()
+        };
+        builder
+            .when_first_row()
+            .assert_eq_ext(sum, term(inv_value, inv_table, local[2]));
+        builder.when_transition().assert_eq_ext(
+            perm_next[2],
+            sum.into() + term(perm_next[0], perm_next[1], next[2]),
+        );
+        builder.when_last_row().assert_zero_ext(sum);
+    }
+}
+
+fn generate_trace<F: Field>(log_height: usize, lookup_outside_table: bool) -> RowMajorMatrix<F> {
+    let height = 1 << log_height;
+    let mut rng = thread_rng();
+    let mut lookups: Vec<usize> = (0..height).map(|_| rng.gen_range(0..height)).collect();
+    if lookup_outside_table {
+        lookups[0] = height;
+    }
+
+    let mut multiplicities = vec![0; height];
+    for &value in lookups.iter().filter(|&&value| value < height) {
+        multiplicities[value] += 1;
+    }
+
+    let values = (0..height)
+        .flat_map(|i| {
+            [
+                F::from_canonical_usize(lookups[i]),
+                F::from_canonical_usize(i),
+                F::from_canonical_usize(multiplicities[i]),
+            ]
+        })
+        .collect();
+    RowMajorMatrix::new(values, 3)
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+fn do_test(log_height: usize, lookup_outside_table: bool, use_constraint_program: bool) {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = FriConfig {
+        log_blowup: 2,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 28,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+    let pcs = Pcs::new(fri_config, Dft {}, val_mmcs);
+    let config = MyConfig::new(pcs);
+
+    let (mut proving_key, _) = setup_keys(&config, &LookupAir);
+    if use_constraint_program {
+        let program = get_checked_constraint_program::<MyConfig, _>(&LookupAir, 0).unwrap();
+        proving_key = proving_key.with_constraint_program(&program);
+    }
+
+    let trace = generate_trace::<Val>(log_height, lookup_outside_table);
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_with_key(
+        &config,
+        &proving_key,
+        &LookupAir,
+        &mut challenger,
+        trace,
+        &PublicRow::default(),
+    );
+
+    let mut challenger = Challenger::new(perm);
+    verify(
+        &config,
+        &LookupAir,
+        &mut challenger,
+        &proof,
+        &PublicRow::default(),
+    )
+    .expect("verification failed");
+}
+
+#[test]
+fn test_lookup() {
+    do_test(5, false, false);
+}
+
+#[test]
+fn test_lookup_small_trace() {
+    do_test(1, false, false);
+}
+
+#[test]
+fn test_lookup_with_constraint_program() {
+    do_test(5, false, true);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "constraints had nonzero value")]
+fn test_lookup_outside_table() {
+    do_test(5, true, false);
+}
+
+#[test]
+fn test_check_constraints_lookup_outside_table() {
+    let trace = generate_trace::<Val>(5, true);
+    let beta: Challenge = thread_rng().gen();
+    let failures = check_constraints(&LookupAir, &trace, &[beta], &PublicRow::default());
+
+    // Only the running sum's final value is wrong.
+    assert_eq!(failures.len(), 1);
+    assert_eq!((failures[0].row, failures[0].constraint), (31, 4));
+    let values = &failures[0].values;
+    assert_eq!(values.len(), 1);
+    assert_eq!(values[0].entry, Entry::Permutation { offset: 0 });
+    assert_eq!(values[0].index, 2);
+    assert_ne!(values[0].value, Challenge::zero());
+}
@@ -0,0 +1,272 @@
+use p3_air::{Air, AirBuilder, BaseAir, PairBuilder};
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::Field;
+use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    prove, prove_with_key, setup_keys, verify, verify_with_key, PublicRow, StarkConfig,
+    VerificationError,
+};
+use rand::thread_rng;
+
+/// An AIR with two preprocessed columns, a selector which is set on even rows and a constant column
+/// holding `offset + i` on row `i`. The main trace has an accumulator of the constant column, and a
+/// column which must match the constant column wherever the selector is set.
+struct PreprocessedAir {
+    offset: u64,
+    log_height: usize,
+}
+
+impl<F: Field> BaseAir<F> for PreprocessedAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        2
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        let values = (0..1 << self.log_height)
+            .flat_map(|i| {
+                [
+                    F::from_bool(i % 2 == 0),
+                    F::from_canonical_u64(self.offset + i),
+                ]
+            })
+            .collect();
+        Some(RowMajorMatrix::new(values, 2))
+    }
+}
+
+impl<AB: PairBuilder> Air<AB> for PreprocessedAir {
+    fn eval(&self, builder: &mut AB) {
+        let preprocessed = builder.preprocessed();
+        let prep_local = preprocessed.row_slice(0);
+        let prep_next = preprocessed.row_slice(1);
+        let (selector, constant) = (prep_local[0], prep_local[1]);
+        let constant_next = prep_next[1];
+
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+        let (acc, x) = (local[0], local[1]);
+        let acc_next = next[0];
+
+        builder.when_first_row().assert_eq(acc, constant);
+        builder
+            .when_transition()
+            .assert_eq(acc + constant_next, acc_next);
+        builder.when(selector).assert_eq(x, constant);
+    }
+}
+
+/// `PreprocessedAir` with an unused zero column appended to its preprocessed trace.
+struct PaddedPreprocessedAir(PreprocessedAir);
+
+impl<F: Field> BaseAir<F> for PaddedPreprocessedAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        3
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        let trace: RowMajorMatrix<F> = self.0.preprocessed_trace()?;
+        let values = trace
+            .rows()
+            .flat_map(|row| row.iter().copied().chain([F::zero()]))
+            .collect();
+        Some(RowMajorMatrix::new(values, 3))
+    }
+}
+
+impl<AB: PairBuilder> Air<AB> for PaddedPreprocessedAir {
+    fn eval(&self, builder: &mut AB) {
+        self.0.eval(builder);
+    }
+}
+
+fn generate_trace<F: Field>(offset: u64, log_height: usize) -> RowMajorMatrix<F> {
+    let mut values = Vec::with_capacity(2 << log_height);
+    let mut acc = F::zero();
+    for i in 0..1 << log_height {
+        let constant = F::from_canonical_u64(offset + i);
+        acc += constant;
+        // Odd rows are unconstrained by the selector, so we can put anything there.
+        let x = if i % 2 == 0 { constant } else { F::two() };
+        values.extend([acc, x]);
+    }
+    RowMajorMatrix::new(values, 2)
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+fn setup() -> (MyConfig, Perm) {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = FriConfig {
+        log_blowup: 2,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 28,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+    let pcs = Pcs::new(fri_config, Dft {}, val_mmcs);
+    (MyConfig::new(pcs), perm)
+}
+
+#[test]
+fn test_preprocessed_with_keys() {
+    let (config, perm) = setup();
+    let air = PreprocessedAir {
+        offset: 3,
+        log_height: 4,
+    };
+    let (proving_key, verifying_key) = setup_keys(&config, &air);
+
+    for _ in 0..2 {
+        let trace = generate_trace::<Val>(3, 4);
+        let mut challenger = Challenger::new(perm.clone());
+        let proof = prove_with_key(
+            &config,
+            &proving_key,
+            &air,
+            &mut challenger,
+            trace,
+            &PublicRow::default(),
+        );
+
+        let mut challenger = Challenger::new(perm.clone());
+        verify_with_key(
+            &config,
+            &verifying_key,
+            &air,
+            &mut challenger,
+            &proof,
+            &PublicRow::default(),
+        )
+        .expect("verification failed");
+    }
+}
+
+#[test]
+fn test_preprocessed_without_keys() {
+    let (config, perm) = setup();
+    let air = PreprocessedAir {
+        offset: 0,
+        log_height: 5,
+    };
+    let trace = generate_trace::<Val>(0, 5);
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut challenger, trace, &PublicRow::default());
+
+    let mut challenger = Challenger::new(perm);
+    verify(
+        &config,
+        &air,
+        &mut challenger,
+        &proof,
+        &PublicRow::default(),
+    )
+    .expect("verification failed");
+}
+
+#[test]
+fn test_preprocessed_wrong_verifying_key() {
+    let (config, perm) = setup();
+    let air = PreprocessedAir {
+        offset: 0,
+        log_height: 4,
+    };
+    let other_air = PreprocessedAir {
+        offset: 1,
+        log_height: 4,
+    };
+    let (proving_key, _) = setup_keys(&config, &air);
+    let (_, other_verifying_key) = setup_keys(&config, &other_air);
+
+    let trace = generate_trace::<Val>(0, 4);
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_with_key(
+        &config,
+        &proving_key,
+        &air,
+        &mut challenger,
+        trace,
+        &PublicRow::default(),
+    );
+
+    let mut challenger = Challenger::new(perm);
+    verify_with_key(
+        &config,
+        &other_verifying_key,
+        &other_air,
+        &mut challenger,
+        &proof,
+        &PublicRow::default(),
+    )
+    .expect_err("verification should fail against another AIR's preprocessed trace");
+}
+
+#[test]
+fn test_preprocessed_verifying_key_for_another_width() {
+    let (config, perm) = setup();
+    let padded_air = PaddedPreprocessedAir(PreprocessedAir {
+        offset: 0,
+        log_height: 4,
+    });
+    let air = PreprocessedAir {
+        offset: 0,
+        log_height: 4,
+    };
+    let (proving_key, verifying_key) = setup_keys(&config, &padded_air);
+
+    let trace = generate_trace::<Val>(0, 4);
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_with_key(
+        &config,
+        &proving_key,
+        &padded_air,
+        &mut challenger,
+        trace,
+        &PublicRow::default(),
+    );
+
+    // The key and the proof agree with each other, but not with the AIR's preprocessed width.
+    let mut challenger = Challenger::new(perm);
+    let result = verify_with_key(
+        &config,
+        &verifying_key,
+        &air,
+        &mut challenger,
+        &proof,
+        &PublicRow::default(),
+    );
+    assert!(matches!(result, Err(VerificationError::InvalidProofShape)));
+}
@@ -0,0 +1,103 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_code::{IdentityCode, SLCodeRegistry, SystematicLinearCode};
+use p3_commit::UniFromMultiPcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_tensor_pcs::{TensorPcs, WrappedMatrix};
+use p3_uni_stark::{prove, verify, PublicRow, StarkConfig};
+use rand::thread_rng;
+
+/// Counts up in the first column, with the square of each count in the second.
+struct SquaresAir;
+
+impl<F> BaseAir<F> for SquaresAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for SquaresAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+
+        builder.when_first_row().assert_zero(local[0]);
+        builder
+            .when_transition()
+            .assert_eq(next[0], local[0] + AB::Expr::one());
+        builder.assert_eq(local[0] * local[0], local[1]);
+    }
+}
+
+fn generate_trace<F: Field>(n: usize) -> RowMajorMatrix<F> {
+    let values = (0..n)
+        .flat_map(|i| {
+            let x = F::from_canonical_usize(i);
+            [x, x * x]
+        })
+        .collect();
+    RowMajorMatrix::new(values, 2)
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type Message = WrappedMatrix<Val, RowMajorMatrix<Val>>;
+type Codes = SLCodeRegistry<Val, Message, Message>;
+type MultiPcs = TensorPcs<Val, Challenge, Codes, ValMmcs>;
+type Pcs = UniFromMultiPcs<Val, Challenge, MultiPcs, Dft, Challenger>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+/// A uni-stark proof whose univariate openings are proven by a tensor PCS. The identity codes make
+/// it cheap to test, though not sound.
+#[test]
+fn test_prove_with_tensor_pcs() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let codes = SLCodeRegistry::new(
+        (0..=10)
+            .map(|log_len| {
+                Box::new(IdentityCode { len: 1 << log_len })
+                    as Box<dyn SystematicLinearCode<Val, Message, Out = Message>>
+            })
+            .collect(),
+    );
+    let multi_pcs = MultiPcs::new(codes, val_mmcs, 10);
+    let pcs = Pcs::new(multi_pcs, Dft {}, 1);
+    let config = MyConfig::new(pcs);
+
+    let trace = generate_trace::<Val>(1 << 5);
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(
+        &config,
+        &SquaresAir,
+        &mut challenger,
+        trace,
+        &PublicRow(vec![]),
+    );
+    let mut challenger = Challenger::new(perm);
+    verify(
+        &config,
+        &SquaresAir,
+        &mut challenger,
+        &proof,
+        &PublicRow(vec![]),
+    )
+    .expect("verification failed");
+}
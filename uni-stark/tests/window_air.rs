@@ -0,0 +1,127 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractField, Field};
+use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{check_constraints, prove, verify, PublicRow, StarkConfig, VerificationError};
+use rand::thread_rng;
+
+/// Computes the Tribonacci sequence in a single column, reading four rows at a time rather than
+/// copying the previous values into extra columns.
+pub struct TribonacciAir;
+
+impl<F> BaseAir<F> for TribonacciAir {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn window_size(&self) -> usize {
+        4
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for TribonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let rows: [AB::Var; 4] = core::array::from_fn(|i| main.row_slice(i)[0]);
+
+        builder.when_first_row().assert_zero(rows[0]);
+        builder.when_first_row().assert_zero(rows[1]);
+        builder.when_first_row().assert_one(rows[2]);
+
+        builder
+            .when_transition_window(4)
+            .assert_eq(rows[3], rows[0] + rows[1] + rows[2]);
+    }
+}
+
+fn tribonacci_trace<F: Field>(rows: usize) -> RowMajorMatrix<F> {
+    let mut values = vec![F::zero(), F::zero(), F::one()];
+    for i in 3..rows {
+        values.push(values[i - 3] + values[i - 2] + values[i - 1]);
+    }
+    RowMajorMatrix::new_col(values)
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+fn prove_and_verify(trace: RowMajorMatrix<Val>) -> Result<(), VerificationError> {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 40,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+    let pcs = Pcs::new(fri_config, Dft {}, val_mmcs);
+    let config = MyConfig::new(pcs);
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(
+        &config,
+        &TribonacciAir,
+        &mut challenger,
+        trace,
+        &PublicRow::default(),
+    );
+
+    let mut challenger = Challenger::new(perm);
+    verify(
+        &config,
+        &TribonacciAir,
+        &mut challenger,
+        &proof,
+        &PublicRow::default(),
+    )
+}
+
+#[test]
+fn test_window_air() -> Result<(), VerificationError> {
+    prove_and_verify(tribonacci_trace(1 << 6))
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "constraints had nonzero value on row 4")]
+fn test_window_air_invalid_trace() {
+    let mut trace = tribonacci_trace(1 << 6);
+    trace.values[7] += Val::one();
+    let _ = prove_and_verify(trace);
+}
+
+#[test]
+fn test_check_constraints_window_air_invalid_trace() {
+    let mut trace = tribonacci_trace::<Val>(1 << 6);
+    trace.values[7] += Val::one();
+    let failures =
+        check_constraints::<_, Challenge, _, _>(&TribonacciAir, &trace, &[], &PublicRow::default());
+
+    // The first window reading the tampered row starts on row 4.
+    assert!(!failures.is_empty());
+    assert_eq!(failures[0].row, 4);
+}
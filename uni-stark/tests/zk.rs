@@ -0,0 +1,216 @@
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, PairBuilder};
+use p3_baby_bear::BabyBear;
+use p3_challenger::DuplexChallenger;
+use p3_commit::{BlindingRng, ExtensionMmcs};
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractField, Field};
+use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_merkle_tree::FieldMerkleTreeHidingMmcs;
+use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::{
+    prove_with_key, setup_keys, verify_with_key, Proof, PublicRow, StarkConfig, VerificationError,
+};
+use rand::thread_rng;
+
+/// A Fibonacci-like AIR, `(a, b) -> (b, a + b + c)`, where `c` is a preprocessed column holding the
+/// row index. The public values are the two initial values and the final value of `b`.
+struct OffsetFibonacciAir {
+    log_height: usize,
+}
+
+impl<F: Field> BaseAir<F> for OffsetFibonacciAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        1
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        let values = (0..1 << self.log_height)
+            .map(F::from_canonical_usize)
+            .collect();
+        Some(RowMajorMatrix::new_col(values))
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues + PairBuilder> Air<AB> for OffsetFibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let next = main.row_slice(1);
+        let preprocessed = builder.preprocessed();
+        let c_next = preprocessed.row_slice(1)[0];
+        let public_values = builder.public_values();
+        let pis = public_values.row_slice(0);
+
+        builder.when_first_row().assert_eq(local[0], pis[0]);
+        builder.when_first_row().assert_eq(local[1], pis[1]);
+        builder.when_transition().assert_eq(local[1], next[0]);
+        builder
+            .when_transition()
+            .assert_eq(local[0] + local[1] + c_next, next[1]);
+        builder.when_last_row().assert_eq(local[1], pis[2]);
+    }
+}
+
+/// Returns the trace and the final value of `b`.
+fn generate_trace<F: Field>(a: u64, b: u64, log_height: usize) -> (RowMajorMatrix<F>, F) {
+    let (mut a, mut b) = (F::from_canonical_u64(a), F::from_canonical_u64(b));
+    let mut values = vec![a, b];
+    for i in 1..1 << log_height {
+        (a, b) = (b, a + b + F::from_canonical_usize(i));
+        values.extend([a, b]);
+    }
+    (RowMajorMatrix::new(values, 2), b)
+}
+
+type Val = BabyBear;
+type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+type ValMmcs = FieldMerkleTreeHidingMmcs<<Val as Field>::Packing, MyHash, MyCompress, 8>;
+type Challenge = BinomialExtensionField<Val, 4>;
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+type Challenger = DuplexChallenger<Val, Perm, 16>;
+type Dft = Radix2DitParallel;
+type MyFriConfig = TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>;
+type Pcs = TwoAdicFriPcs<MyFriConfig>;
+type MyConfig = StarkConfig<Val, Challenge, Pcs, Challenger>;
+
+fn hiding_pcs(perm: &Perm, blinding_rng: &BlindingRng) -> Pcs {
+    let hash = MyHash::new(perm.clone());
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcs::new(hash, compress, 4, blinding_rng.clone());
+    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    let fri_config = FriConfig {
+        log_blowup: 1,
+        log_folding_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 28,
+        proof_of_work_bits: 8,
+        mmcs: challenge_mmcs,
+    };
+    Pcs::new_hiding(fri_config, Dft {}, val_mmcs, blinding_rng.clone())
+}
+
+fn prove_and_verify(
+    prover_config: &MyConfig,
+    verifier_config: &MyConfig,
+    perm: &Perm,
+    claimed_output: Option<Val>,
+) -> Result<Proof<MyConfig>, VerificationError> {
+    let log_height = 5;
+    let air = OffsetFibonacciAir { log_height };
+    let (trace, output) = generate_trace::<Val>(0, 1, log_height);
+    let pis = PublicRow(vec![Val::zero(), Val::one(), output]);
+
+    // A hiding MMCS salts the preprocessed commitment, so the verifier can't recompute it and must
+    // be given the verifying key.
+    let (proving_key, verifying_key) = setup_keys(prover_config, &air);
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_with_key(
+        prover_config,
+        &proving_key,
+        &air,
+        &mut challenger,
+        trace,
+        &pis,
+    );
+
+    let claimed_pis = PublicRow(vec![
+        Val::zero(),
+        Val::one(),
+        claimed_output.unwrap_or(output),
+    ]);
+    let mut challenger = Challenger::new(perm.clone());
+    verify_with_key(
+        verifier_config,
+        &verifying_key,
+        &air,
+        &mut challenger,
+        &proof,
+        &claimed_pis,
+    )?;
+    Ok(proof)
+}
+
+#[test]
+fn test_zk_prove_and_verify() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let blinding_rng = BlindingRng::new(&mut thread_rng());
+    let config = MyConfig::new_zk(hiding_pcs(&perm, &blinding_rng), blinding_rng);
+
+    let proof_1 = prove_and_verify(&config, &config, &perm, None).expect("verification failed");
+    let proof_2 = prove_and_verify(&config, &config, &perm, None).expect("verification failed");
+    // With the same challenger, only the blinding randomness tells the two proofs apart.
+    assert_ne!(
+        postcard::to_allocvec(&proof_1).unwrap(),
+        postcard::to_allocvec(&proof_2).unwrap()
+    );
+}
+
+#[test]
+fn test_zk_quotient_openings_differ() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let blinding_rng = BlindingRng::new(&mut thread_rng());
+    let config = MyConfig::new_zk(hiding_pcs(&perm, &blinding_rng), blinding_rng);
+
+    // The quotient chunks are blinded, so proving the same witness twice opens them to different
+    // values.
+    let proof_1 = prove_and_verify(&config, &config, &perm, None).expect("verification failed");
+    let proof_2 = prove_and_verify(&config, &config, &perm, None).expect("verification failed");
+    assert_ne!(
+        proof_1.opened_values().quotient_chunks(),
+        proof_2.opened_values().quotient_chunks()
+    );
+}
+
+#[test]
+fn test_zk_wrong_public_value() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let blinding_rng = BlindingRng::new(&mut thread_rng());
+    let config = MyConfig::new_zk(hiding_pcs(&perm, &blinding_rng), blinding_rng);
+
+    assert!(
+        prove_and_verify(&config, &config, &perm, Some(Val::two())).is_err(),
+        "verification should fail for the wrong output"
+    );
+}
+
+#[test]
+fn test_zk_proof_rejected_by_non_zk_verifier() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let blinding_rng = BlindingRng::new(&mut thread_rng());
+    let zk_config = MyConfig::new_zk(hiding_pcs(&perm, &blinding_rng), blinding_rng.clone());
+    let config = MyConfig::new(hiding_pcs(&perm, &blinding_rng));
+
+    assert!(
+        prove_and_verify(&zk_config, &config, &perm, None).is_err(),
+        "a zero-knowledge proof shouldn't verify in non-zero-knowledge mode"
+    );
+}
+
+#[test]
+#[should_panic(expected = "zero-knowledge proofs need the trace height")]
+fn test_zk_trace_too_short_to_hide() {
+    let perm = Perm::new_from_rng(8, 22, DiffusionMatrixBabybear, &mut thread_rng());
+    let blinding_rng = BlindingRng::new(&mut thread_rng());
+    let config = MyConfig::new_zk(hiding_pcs(&perm, &blinding_rng), blinding_rng);
+
+    // The 2 out-of-domain points and 28 queries would reveal the blinded trace at more points than
+    // its 16 random coefficients can hide.
+    let log_height = 4;
+    let air = OffsetFibonacciAir { log_height };
+    let (trace, output) = generate_trace::<Val>(0, 1, log_height);
+    let pis = PublicRow(vec![Val::zero(), Val::one(), output]);
+    let (proving_key, _) = setup_keys(&config, &air);
+    let mut challenger = Challenger::new(perm.clone());
+    prove_with_key(&config, &proving_key, &air, &mut challenger, trace, &pis);
+}